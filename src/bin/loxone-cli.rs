@@ -5,6 +5,7 @@
 
 use clap::{Parser, Subcommand};
 use serde_json::{Value, json};
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::time::Duration;
 
@@ -172,6 +173,16 @@ enum Command {
         /// Arguments as key=value pairs
         args: Vec<String>,
     },
+
+    // --- Backups ---
+    /// Decrypt a program backup archive (passphrase from LOXONE_BACKUP_PASSPHRASE)
+    DecryptBackup {
+        /// Encrypted .lxbak archive
+        archive: PathBuf,
+        /// Output file (default: archive name without .lxbak)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 struct McpClient {
@@ -306,6 +317,13 @@ impl McpClient {
             )
             .await?;
 
+        // Prefer the machine-readable payload; the text content is a summary
+        if let Some(data) = result.get("structuredContent")
+            && !data.is_null()
+        {
+            return Ok(data.clone());
+        }
+
         // Extract text content from MCP tool result
         if let Some(content) = result.get("content").and_then(|c| c.as_array())
             && let Some(first) = content.first()
@@ -535,6 +553,10 @@ async fn main() {
 }
 
 async fn run(cli: Cli) -> Result<(), String> {
+    if let Command::DecryptBackup { archive, output } = &cli.command {
+        return decrypt_backup(archive, output.as_ref());
+    }
+
     ensure_server_running(&cli).await?;
 
     let mut client = McpClient::new(&cli.url);
@@ -716,8 +738,36 @@ async fn run(cli: Cli) -> Result<(), String> {
             let arguments = parse_kv_args(args);
             client.call_tool(tool, arguments).await?
         }
+
+        Command::DecryptBackup { .. } => unreachable!("handled without a server"),
     };
 
     format_output(&result, cli.json);
     Ok(())
 }
+
+/// Decrypt a program backup without contacting the server
+fn decrypt_backup(archive: &PathBuf, output: Option<&PathBuf>) -> Result<(), String> {
+    use loxone_mcp_rust::services::program_backup::{ARCHIVE_EXTENSION, decrypt_archive};
+
+    let passphrase = std::env::var("LOXONE_BACKUP_PASSPHRASE")
+        .map_err(|_| "LOXONE_BACKUP_PASSPHRASE is not set".to_string())?;
+    let encrypted =
+        std::fs::read(archive).map_err(|e| format!("Failed to read {}: {e}", archive.display()))?;
+    let program = decrypt_archive(&encrypted, &passphrase).map_err(|e| e.to_string())?;
+
+    let output = match output {
+        Some(output) => output.clone(),
+        None if archive
+            .extension()
+            .is_some_and(|ext| ext == ARCHIVE_EXTENSION) =>
+        {
+            archive.with_extension("")
+        }
+        None => return Err("Archive has no .lxbak extension; pass --output".to_string()),
+    };
+    std::fs::write(&output, program)
+        .map_err(|e| format!("Failed to write {}: {e}", output.display()))?;
+    println!("Decrypted program written to {}", output.display());
+    Ok(())
+}
@@ -0,0 +1,421 @@
+//! Global Miniserver API budget with degradation-aware throttling
+//!
+//! The Miniserver is a small embedded device: when history polling,
+//! discovery and statistics jobs pile up, its responses slow down for
+//! everyone. [`BudgetedClient`] meters every request against a shared
+//! [`ApiBudget`]:
+//!
+//! - response latency and errors are tracked as moving averages; the further
+//!   they drift from healthy, the lower the effective budget (down to 10 %)
+//! - interactive tool calls (requests made inside `run_tool`) are never
+//!   delayed, but they consume budget
+//! - background jobs wait until the budget has room again
+//! - a reduced profile scales the whole budget down, e.g. while the
+//!   Miniserver runs on UPS power
+//!
+//! The budget defaults to 120 requests per minute and is read from
+//! `LOXONE_API_BUDGET`.
+
+use crate::client::{LoxoneClient, LoxoneResponse, LoxoneStructure, StateStream};
+use crate::error::Result;
+use crate::services::command_history::CommandOrigin;
+use crate::utils::clock::{SharedClock, system_clock};
+use async_trait::async_trait;
+use humantime_serde::re::humantime;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default requests per minute
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 120;
+
+/// Smallest fraction of the budget left to background jobs
+const MIN_BUDGET_FACTOR: f64 = 0.1;
+
+/// Weight of a new sample in the moving averages
+const EWMA_ALPHA: f64 = 0.2;
+
+/// API budget settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiBudgetConfig {
+    /// Requests per minute when the Miniserver responds normally
+    pub requests_per_minute: u32,
+
+    /// Latency considered healthy
+    #[serde(with = "humantime_serde")]
+    pub healthy_latency: Duration,
+
+    /// Latency at which the budget is cut to its minimum
+    #[serde(with = "humantime_serde")]
+    pub degraded_latency: Duration,
+}
+
+impl Default for ApiBudgetConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: std::env::var("LOXONE_API_BUDGET")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&rpm| rpm > 0)
+                .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE),
+            healthy_latency: Duration::from_millis(150),
+            degraded_latency: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Scheduling priority of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Interactive,
+    Background,
+}
+
+impl Priority {
+    /// Interactive inside a tool call, background otherwise
+    pub fn current() -> Self {
+        if CommandOrigin::current().tool.is_some() {
+            Self::Interactive
+        } else {
+            Self::Background
+        }
+    }
+}
+
+struct BudgetState {
+    tokens: f64,
+    refilled_at: Instant,
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    interactive: u64,
+    background: u64,
+    throttled: u64,
+    /// Factor of the reduced profile, if active
+    reduced: Option<f64>,
+}
+
+/// Shared request budget for one Miniserver
+pub struct ApiBudget {
+    config: ApiBudgetConfig,
+    state: Mutex<BudgetState>,
+    clock: SharedClock,
+}
+
+impl Default for ApiBudget {
+    fn default() -> Self {
+        Self::new(ApiBudgetConfig::default())
+    }
+}
+
+impl ApiBudget {
+    pub fn new(config: ApiBudgetConfig) -> Self {
+        let capacity = burst_capacity(config.requests_per_minute as f64);
+        Self {
+            config,
+            state: Mutex::new(BudgetState {
+                tokens: capacity,
+                refilled_at: Instant::now(),
+                latency_ms: None,
+                error_rate: 0.0,
+                interactive: 0,
+                background: 0,
+                throttled: 0,
+                reduced: None,
+            }),
+            clock: system_clock(),
+        }
+    }
+
+    /// Refill the budget and wait on `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.state
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .refilled_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    pub fn config(&self) -> &ApiBudgetConfig {
+        &self.config
+    }
+
+    /// How far responses have drifted from healthy, from 0 (healthy) to 1
+    pub fn degradation(&self) -> f64 {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.degradation_of(&state)
+    }
+
+    fn degradation_of(&self, state: &BudgetState) -> f64 {
+        let healthy = self.config.healthy_latency.as_secs_f64() * 1000.0;
+        let degraded = self.config.degraded_latency.as_secs_f64() * 1000.0;
+        let latency = state
+            .latency_ms
+            .map(|ms| ((ms - healthy) / (degraded - healthy).max(1.0)).clamp(0.0, 1.0))
+            .unwrap_or(0.0);
+        let errors = (state.error_rate * 2.0).clamp(0.0, 1.0);
+        latency.max(errors)
+    }
+
+    /// Requests per minute currently granted
+    pub fn effective_rate(&self) -> f64 {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.rate_of(&state)
+    }
+
+    fn rate_of(&self, state: &BudgetState) -> f64 {
+        let factor = (1.0 - self.degradation_of(state)).max(MIN_BUDGET_FACTOR);
+        let profile = state.reduced.unwrap_or(1.0);
+        (self.config.requests_per_minute as f64 * factor * profile).max(1.0)
+    }
+
+    /// Scale the budget by `factor` (clamped to 0.01–1), or restore it
+    pub fn set_reduced_profile(&self, factor: Option<f64>) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).reduced =
+            factor.map(|f| f.clamp(0.01, 1.0));
+    }
+
+    /// Take one request from the budget and return how long to wait first
+    ///
+    /// Interactive requests never wait; they may overdraw the budget by one
+    /// burst, which background requests then have to wait out.
+    pub fn reserve(&self, priority: Priority) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let rate = self.rate_of(&state);
+        let capacity = burst_capacity(rate);
+        let per_second = rate / 60.0;
+
+        let now = self.clock.now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * per_second).min(capacity);
+        state.refilled_at = now;
+        state.tokens = (state.tokens - 1.0).max(-capacity);
+
+        match priority {
+            Priority::Interactive => {
+                state.interactive += 1;
+                Duration::ZERO
+            }
+            Priority::Background => {
+                state.background += 1;
+                if state.tokens >= 0.0 {
+                    Duration::ZERO
+                } else {
+                    state.throttled += 1;
+                    Duration::from_secs_f64(-state.tokens / per_second)
+                }
+            }
+        }
+    }
+
+    /// Wait for budget according to the calling task's priority
+    pub async fn acquire(&self) {
+        let wait = self.reserve(Priority::current());
+        if !wait.is_zero() {
+            self.clock.sleep(wait).await;
+        }
+    }
+
+    /// Record the latency and outcome of a completed request
+    pub fn record(&self, latency: Duration, success: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let ms = latency.as_secs_f64() * 1000.0;
+        state.latency_ms = Some(match state.latency_ms {
+            Some(avg) => avg + EWMA_ALPHA * (ms - avg),
+            None => ms,
+        });
+        let error = if success { 0.0 } else { 1.0 };
+        state.error_rate += EWMA_ALPHA * (error - state.error_rate);
+    }
+
+    /// Budget, observed health and request counts for status reporting
+    pub fn status(&self) -> serde_json::Value {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        json!({
+            "requests_per_minute": self.config.requests_per_minute,
+            "effective_requests_per_minute": self.rate_of(&state).round(),
+            "profile": if state.reduced.is_some() { "reduced" } else { "normal" },
+            "degradation": (self.degradation_of(&state) * 100.0).round() / 100.0,
+            "latency_ms": state.latency_ms.map(|ms| ms.round()),
+            "error_rate": (state.error_rate * 100.0).round() / 100.0,
+            "healthy_latency": humantime::format_duration(self.config.healthy_latency).to_string(),
+            "interactive_requests": state.interactive,
+            "background_requests": state.background,
+            "background_throttled": state.throttled,
+        })
+    }
+}
+
+/// Requests that may be sent back to back: ten seconds worth, at least one
+fn burst_capacity(rate_per_minute: f64) -> f64 {
+    (rate_per_minute / 6.0).max(1.0)
+}
+
+/// Client that meters every Miniserver request against an [`ApiBudget`]
+pub struct BudgetedClient {
+    inner: Arc<dyn LoxoneClient>,
+    budget: Arc<ApiBudget>,
+}
+
+impl BudgetedClient {
+    pub fn new(inner: Arc<dyn LoxoneClient>, budget: Arc<ApiBudget>) -> Self {
+        Self { inner, budget }
+    }
+
+    async fn metered<T>(&self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        self.budget.acquire().await;
+        let started = self.budget.clock.now();
+        let result = request.await;
+        self.budget
+            .record(self.budget.clock.now() - started, result.is_ok());
+        result
+    }
+}
+
+#[async_trait]
+impl LoxoneClient for BudgetedClient {
+    async fn connect(&mut self) -> Result<()> {
+        Err(crate::error::LoxoneError::config(
+            "Budgeted clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn is_connected(&self) -> Result<bool> {
+        self.inner.is_connected().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        Err(crate::error::LoxoneError::config(
+            "Budgeted clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn send_command(&self, uuid: &str, command: &str) -> Result<LoxoneResponse> {
+        self.metered(self.inner.send_command(uuid, command)).await
+    }
+
+    async fn send_parallel_commands(
+        &self,
+        commands: Vec<(String, String)>,
+    ) -> Result<Vec<Result<LoxoneResponse>>> {
+        // One budget unit per command; `metered` takes the last one
+        for _ in 1..commands.len() {
+            self.budget.acquire().await;
+        }
+        self.metered(self.inner.send_parallel_commands(commands))
+            .await
+    }
+
+    async fn get_structure(&self) -> Result<LoxoneStructure> {
+        self.metered(self.inner.get_structure()).await
+    }
+
+    async fn get_device_states(
+        &self,
+        uuids: &[String],
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        self.metered(self.inner.get_device_states(uuids)).await
+    }
+
+    async fn get_state_values(
+        &self,
+        state_uuids: &[String],
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        self.metered(self.inner.get_state_values(state_uuids)).await
+    }
+
+    async fn get_all_device_states_batch(&self) -> Result<HashMap<String, serde_json::Value>> {
+        self.metered(self.inner.get_all_device_states_batch()).await
+    }
+
+    async fn get_system_info(&self) -> Result<serde_json::Value> {
+        self.metered(self.inner.get_system_info()).await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.metered(self.inner.health_check()).await
+    }
+
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        self.metered(self.inner.get_miniserver_time()).await
+    }
+
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        self.metered(self.inner.send_calendar_command(command))
+            .await
+    }
+
+    async fn get_air_devices(&self) -> Result<serde_json::Value> {
+        self.metered(self.inner.get_air_devices()).await
+    }
+
+    async fn get_device_status(&self) -> Result<serde_json::Value> {
+        self.metered(self.inner.get_device_status()).await
+    }
+
+    async fn get_weather_forecast(&self) -> Result<serde_json::Value> {
+        self.metered(self.inner.get_weather_forecast()).await
+    }
+
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        self.metered(self.inner.download_file(path)).await
+    }
+
+    fn subscribe_states(self: Arc<Self>, uuids: Vec<String>) -> StateStream {
+        self.inner.clone().subscribe_states(uuids)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_throttled_when_degraded() {
+        let budget = ApiBudget::new(ApiBudgetConfig {
+            requests_per_minute: 60,
+            ..ApiBudgetConfig::default()
+        });
+
+        // A burst of ten seconds' worth passes, then background jobs wait
+        for _ in 0..10 {
+            assert_eq!(budget.reserve(Priority::Background), Duration::ZERO);
+        }
+        assert!(budget.reserve(Priority::Background) > Duration::ZERO);
+        // Interactive calls are never delayed
+        assert_eq!(budget.reserve(Priority::Interactive), Duration::ZERO);
+
+        assert_eq!(budget.effective_rate(), 60.0);
+        for _ in 0..30 {
+            budget.record(Duration::from_secs(2), true);
+        }
+        assert!(budget.degradation() > 0.99);
+        assert!((budget.effective_rate() - 6.0).abs() < 0.01);
+
+        let status = budget.status();
+        assert_eq!(status["interactive_requests"], 1);
+        assert_eq!(status["background_throttled"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_priority_follows_tool_scope() {
+        assert_eq!(Priority::current(), Priority::Background);
+        let origin = CommandOrigin {
+            tool: Some("control_lights".to_string()),
+            key: None,
+        };
+        assert_eq!(
+            origin.scope(async { Priority::current() }).await,
+            Priority::Interactive
+        );
+    }
+}
@@ -3,7 +3,11 @@
 //! This module provides RSA and AES encryption capabilities for secure
 //! communication with Loxone Miniservers using the token-based authentication.
 
+use crate::client::time_sync;
 use crate::error::{LoxoneError, Result};
+#[cfg(feature = "crypto-openssl")]
+use crate::utils::clock::{SharedClock, system_clock};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
@@ -36,6 +40,16 @@ pub struct AuthToken {
     pub unsecure_pass: bool,
 }
 
+impl AuthToken {
+    /// Whether the token has expired at `host_time`
+    ///
+    /// `validUntil` counts seconds since the Loxone epoch on the Miniserver
+    /// clock, so the comparison uses the drift-compensated Miniserver time.
+    pub fn is_expired_at(&self, host_time: DateTime<Utc>) -> bool {
+        time_sync::loxone_timestamp(time_sync::miniserver_time(host_time)) >= self.valid_until
+    }
+}
+
 // OpenSSL implementation (modern, battle-tested, Send + Sync)
 #[cfg(feature = "crypto-openssl")]
 use base64::{Engine as _, engine::general_purpose};
@@ -73,6 +87,9 @@ pub struct LoxoneAuth {
 
     /// AES session key
     session_key: Option<Vec<u8>>,
+
+    /// Clock token expiry is checked against
+    clock: SharedClock,
 }
 
 #[cfg(feature = "crypto-openssl")]
@@ -83,9 +100,16 @@ impl LoxoneAuth {
             public_key: None,
             token: None,
             session_key: None,
+            clock: system_clock(),
         }
     }
 
+    /// Check token expiry against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Set the RSA public key from server certificate or raw public key
     pub fn set_public_key(&mut self, certificate_pem: &str) -> Result<()> {
         // Try parsing as X.509 certificate first
@@ -177,10 +201,7 @@ impl LoxoneAuth {
     /// Check if current token is expired
     pub fn is_token_expired(&self) -> bool {
         match &self.token {
-            Some(token) => {
-                let now = chrono::Utc::now().timestamp();
-                now >= token.valid_until
-            }
+            Some(token) => token.is_expired_at(self.clock.utc_now()),
             None => true,
         }
     }
@@ -340,6 +361,12 @@ impl TokenAuthClient {
         }
     }
 
+    /// Check token expiry against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.auth.clock = clock;
+        self
+    }
+
     /// Authenticate with username and password using proper Loxone token flow
     pub async fn authenticate(&mut self, username: &str, password: &str) -> Result<()> {
         // Store username for later use
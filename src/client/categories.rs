@@ -0,0 +1,130 @@
+//! User-defined Loxone categories
+//!
+//! Each control in the structure file references a category from the `cats`
+//! section (`"cat": "<uuid>"`). Categories are defined by the installer in
+//! Loxone Config ("Lighting", "Shading", "Pool", ...) and carry an icon and
+//! a color. [`ClientContext`](super::ClientContext) attaches them to each
+//! [`LoxoneDevice`] next to the heuristic category derived from the control
+//! type, so tools can filter by either.
+
+use crate::client::{LoxoneDevice, LoxoneStructure};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// One entry of the structure's `cats` section
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoxoneCategory {
+    /// Category UUID
+    pub uuid: String,
+    /// Name as configured in Loxone Config
+    pub name: String,
+    /// Icon file on the Miniserver (e.g. `00000000-0000-0020-2000000000000000.svg`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Display color (e.g. `#FFA200`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Loxone category type (`lights`, `shading`, `multimedia`, `undefined`, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category_type: Option<String>,
+}
+
+impl LoxoneCategory {
+    /// Parse a `cats` entry; entries without a name are skipped
+    pub fn from_value(uuid: &str, value: &Value) -> Option<Self> {
+        let text = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        Some(Self {
+            uuid: text("uuid").unwrap_or_else(|| uuid.to_string()),
+            name: text("name")?,
+            icon: text("image"),
+            color: text("color"),
+            category_type: text("type"),
+        })
+    }
+}
+
+/// Categories of a structure file by UUID
+pub fn category_index(structure: &LoxoneStructure) -> HashMap<String, LoxoneCategory> {
+    structure
+        .cats
+        .iter()
+        .filter_map(|(uuid, value)| {
+            LoxoneCategory::from_value(uuid, value).map(|category| (uuid.clone(), category))
+        })
+        .collect()
+}
+
+/// Category referenced by a control's `cat` field
+pub fn control_category(
+    categories: &HashMap<String, LoxoneCategory>,
+    control: &Value,
+) -> Option<LoxoneCategory> {
+    control
+        .get("cat")
+        .and_then(Value::as_str)
+        .and_then(|uuid| categories.get(uuid))
+        .cloned()
+}
+
+impl LoxoneDevice {
+    /// Whether `filter` names the device's heuristic or Loxone category
+    ///
+    /// Matches case-insensitively on the heuristic category (`lights`,
+    /// `blinds`, ...) and on the Loxone category name or UUID.
+    pub fn in_category(&self, filter: &str) -> bool {
+        self.category.eq_ignore_ascii_case(filter)
+            || self.loxone_category.as_ref().is_some_and(|category| {
+                category.name.eq_ignore_ascii_case(filter) || category.uuid == filter
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parses_cats_section() {
+        let structure = LoxoneStructure {
+            last_modified: String::new(),
+            controls: HashMap::new(),
+            rooms: HashMap::new(),
+            cats: HashMap::from([
+                (
+                    "cat-pool".to_string(),
+                    json!({
+                        "uuid": "cat-pool",
+                        "name": "Pool",
+                        "image": "00000000-0000-0020-2000000000000000.svg",
+                        "color": "#69C350",
+                        "type": "undefined",
+                        "isFavorite": false
+                    }),
+                ),
+                ("cat-unnamed".to_string(), json!({"image": "x.svg"})),
+            ]),
+            global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
+        };
+
+        let categories = category_index(&structure);
+        assert_eq!(categories.len(), 1);
+        let pool = control_category(&categories, &json!({"cat": "cat-pool"})).unwrap();
+        assert_eq!(pool.name, "Pool");
+        assert_eq!(
+            pool.icon.as_deref(),
+            Some("00000000-0000-0020-2000000000000000.svg")
+        );
+        assert_eq!(pool.color.as_deref(), Some("#69C350"));
+        assert!(control_category(&categories, &json!({"cat": "cat-unnamed"})).is_none());
+        assert!(control_category(&categories, &json!({})).is_none());
+    }
+}
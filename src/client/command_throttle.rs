@@ -0,0 +1,321 @@
+//! Per-category limits for parallel device commands
+//!
+//! Firing a command at every blind in the house at once starts all motors
+//! in the same instant, and the inrush current can trip breakers. Parallel
+//! commands are therefore limited per device category: at most
+//! `max_concurrent` in flight, and consecutive commands of a category are
+//! spaced by `spacing` (200 ms between blinds by default).
+//!
+//! Limits are read from `LOXONE_COMMAND_LIMITS` as comma-separated
+//! `category=max[/spacing]` entries, e.g. `blinds=2/500ms,lighting=16`.
+
+use humantime_serde::re::humantime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Device categories with their own command limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandCategory {
+    Blinds,
+    Lighting,
+    Climate,
+    Audio,
+    Other,
+}
+
+impl CommandCategory {
+    pub const ALL: [Self; 5] = [
+        Self::Blinds,
+        Self::Lighting,
+        Self::Climate,
+        Self::Audio,
+        Self::Other,
+    ];
+
+    /// Category of a Loxone control type
+    pub fn of(device_type: &str) -> Self {
+        match device_type {
+            "Jalousie" | "Blinds" | "Rolladen" | "CentralJalousie" | "Gate" | "Window" => {
+                Self::Blinds
+            }
+            t if t.starts_with("LightController")
+                || t.starts_with("ColorPicker")
+                || matches!(t, "Switch" | "Dimmer" | "EIBDimmer") =>
+            {
+                Self::Lighting
+            }
+            t if t.starts_with("IRoomController") || t.contains("Climate") => Self::Climate,
+            t if t.starts_with("Audio") => Self::Audio,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Blinds => "blinds",
+            Self::Lighting => "lighting",
+            Self::Climate => "climate",
+            Self::Audio => "audio",
+            Self::Other => "other",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == value)
+    }
+}
+
+/// Limit for one category
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CategoryLimit {
+    /// Commands of the category in flight at the same time
+    pub max_concurrent: usize,
+    /// Minimum delay between the starts of two commands of the category
+    #[serde(with = "humantime_serde")]
+    pub spacing: Duration,
+}
+
+impl CategoryLimit {
+    pub const fn new(max_concurrent: usize, spacing: Duration) -> Self {
+        Self {
+            max_concurrent,
+            spacing,
+        }
+    }
+
+    /// Default limit for a category
+    pub fn default_for(category: CommandCategory) -> Self {
+        match category {
+            CommandCategory::Blinds => Self::new(4, Duration::from_millis(200)),
+            CommandCategory::Lighting => Self::new(8, Duration::ZERO),
+            CommandCategory::Climate => Self::new(4, Duration::from_millis(100)),
+            CommandCategory::Audio => Self::new(4, Duration::ZERO),
+            CommandCategory::Other => Self::new(8, Duration::ZERO),
+        }
+    }
+}
+
+/// Limits for all categories
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandLimits {
+    pub limits: HashMap<CommandCategory, CategoryLimit>,
+}
+
+impl Default for CommandLimits {
+    fn default() -> Self {
+        let mut limits: HashMap<_, _> = CommandCategory::ALL
+            .into_iter()
+            .map(|c| (c, CategoryLimit::default_for(c)))
+            .collect();
+        if let Ok(value) = env::var("LOXONE_COMMAND_LIMITS") {
+            limits.extend(parse_limits(&value));
+        }
+        Self { limits }
+    }
+}
+
+impl CommandLimits {
+    /// Limit that applies to a category
+    pub fn limit_for(&self, category: CommandCategory) -> CategoryLimit {
+        self.limits
+            .get(&category)
+            .copied()
+            .unwrap_or_else(|| CategoryLimit::default_for(category))
+    }
+}
+
+/// Parse `category=max[/spacing]` entries, skipping invalid ones
+fn parse_limits(value: &str) -> Vec<(CommandCategory, CategoryLimit)> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(category, limit)| {
+                let category = CommandCategory::parse(category.trim())?;
+                let (max, spacing) = match limit.split_once('/') {
+                    Some((max, spacing)) => (max, humantime::parse_duration(spacing.trim()).ok()?),
+                    None => (limit, CategoryLimit::default_for(category).spacing),
+                };
+                let max = max.trim().parse::<usize>().ok().filter(|m| *m > 0)?;
+                Some((category, CategoryLimit::new(max, spacing)))
+            });
+            if parsed.is_none() {
+                warn!("Ignoring invalid command limit '{entry}'");
+            }
+            parsed
+        })
+        .collect()
+}
+
+struct CategoryGate {
+    semaphore: Semaphore,
+    spacing: Duration,
+    next_start: Mutex<Option<Instant>>,
+}
+
+/// Enforces [`CommandLimits`] on parallel commands
+pub struct CommandThrottle {
+    limits: CommandLimits,
+    gates: HashMap<CommandCategory, CategoryGate>,
+}
+
+impl Default for CommandThrottle {
+    fn default() -> Self {
+        Self::new(CommandLimits::default())
+    }
+}
+
+impl CommandThrottle {
+    pub fn new(limits: CommandLimits) -> Self {
+        let gates = CommandCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let limit = limits.limit_for(category);
+                (
+                    category,
+                    CategoryGate {
+                        semaphore: Semaphore::new(limit.max_concurrent.max(1)),
+                        spacing: limit.spacing,
+                        next_start: Mutex::new(None),
+                    },
+                )
+            })
+            .collect();
+        Self { limits, gates }
+    }
+
+    pub fn limits(&self) -> &CommandLimits {
+        &self.limits
+    }
+
+    /// Wait for a slot in `category`; the slot is held until the permit drops
+    pub async fn acquire(&self, category: CommandCategory) -> SemaphorePermit<'_> {
+        let gate = &self.gates[&category];
+        let permit = gate
+            .semaphore
+            .acquire()
+            .await
+            .expect("command throttle semaphore is never closed");
+
+        if !gate.spacing.is_zero() {
+            let start = {
+                let mut next_start = gate.next_start.lock().await;
+                let now = Instant::now();
+                let start = next_start.map_or(now, |next| next.max(now));
+                *next_start = Some(start + gate.spacing);
+                start
+            };
+            tokio::time::sleep_until(start).await;
+        }
+        permit
+    }
+
+    /// Run `send` for every command within the category limits
+    ///
+    /// Results are returned in the order of `commands`.
+    pub async fn run_all<T, F, Fut>(
+        &self,
+        commands: Vec<(CommandCategory, String, String)>,
+        send: F,
+    ) -> Vec<T>
+    where
+        F: Fn(String, String) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let send = &send;
+        futures::future::join_all(commands.into_iter().map(
+            |(category, uuid, command)| async move {
+                let _permit = self.acquire(category).await;
+                send(uuid, command).await
+            },
+        ))
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_categories_and_env_format() {
+        assert_eq!(CommandCategory::of("Jalousie"), CommandCategory::Blinds);
+        assert_eq!(
+            CommandCategory::of("LightControllerV2"),
+            CommandCategory::Lighting
+        );
+        assert_eq!(
+            CommandCategory::of("IRoomControllerV2"),
+            CommandCategory::Climate
+        );
+        assert_eq!(CommandCategory::of("Pushbutton"), CommandCategory::Other);
+
+        let parsed = parse_limits("blinds=2/500ms, lighting=16, bogus=1, audio=0");
+        assert_eq!(
+            parsed,
+            vec![
+                (
+                    CommandCategory::Blinds,
+                    CategoryLimit::new(2, Duration::from_millis(500))
+                ),
+                (
+                    CommandCategory::Lighting,
+                    CategoryLimit::new(16, Duration::ZERO)
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrency_and_spacing() {
+        let mut limits = CommandLimits::default();
+        limits.limits.insert(
+            CommandCategory::Blinds,
+            CategoryLimit::new(2, Duration::from_millis(200)),
+        );
+        let throttle = CommandThrottle::new(limits);
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let started = Instant::now();
+        let commands = (0..6)
+            .map(|i| {
+                (
+                    CommandCategory::Blinds,
+                    format!("blind-{i}"),
+                    "FullDown".to_string(),
+                )
+            })
+            .collect();
+
+        let starts = throttle
+            .run_all(commands, |uuid, _| {
+                let in_flight = in_flight.clone();
+                let peak = peak.clone();
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    let offset = started.elapsed();
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    (uuid, offset)
+                }
+            })
+            .await;
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+        assert_eq!(starts[0].0, "blind-0");
+        for pair in starts.windows(2) {
+            assert!(pair[1].1 >= pair[0].1 + Duration::from_millis(200));
+        }
+    }
+}
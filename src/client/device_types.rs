@@ -0,0 +1,370 @@
+//! Device type registry
+//!
+//! [`ClientContext`](super::ClientContext) sorts each control into a device
+//! category (`lights`, `blinds`, `climate`, ...) from its Loxone control
+//! type. The categories and what is known about each type live in
+//! declarative [`DeviceTypeDescriptor`]s:
+//!
+//! - `types`: control types matched exactly, e.g. `LightControllerV2`
+//! - `contains`: fragments matched within the lowercase control type, e.g.
+//!   `jalousie` for `Jalousie` and `CentralJalousie`
+//! - `states`: meaning of its Loxone states, e.g. `brightness = "position"`;
+//!   `get_device_info` reports the current values under these names
+//!
+//! An exact match wins over a fragment match; among matches of the same
+//! kind the first descriptor wins. Controls matching nothing are `other`.
+//!
+//! New control types need no code change: descriptors from the TOML file
+//! named by `LOXONE_DEVICE_TYPES_FILE` are tried before the built-in ones.
+//!
+//! ```toml
+//! [[device_type]]
+//! types = ["Wallbox2"]
+//! category = "energy"
+//! states = { power = "power", session_energy = "energySession" }
+//! ```
+
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// Category of controls no descriptor matches
+pub const OTHER_CATEGORY: &str = "other";
+
+/// What is known about a kind of Loxone control
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceTypeDescriptor {
+    /// Control types matched exactly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<String>,
+    /// Fragments matched within the lowercase control type
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contains: Vec<String>,
+    pub category: String,
+    /// Meaning of a state to its Loxone state name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub states: BTreeMap<String, String>,
+}
+
+impl DeviceTypeDescriptor {
+    fn exact(types: &[&str], category: &str, states: &[(&str, &str)]) -> Self {
+        Self {
+            types: types.iter().map(|t| t.to_string()).collect(),
+            category: category.to_string(),
+            states: states
+                .iter()
+                .map(|(meaning, state)| (meaning.to_string(), state.to_string()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    fn fragments(contains: &[&str], category: &str) -> Self {
+        Self {
+            contains: contains.iter().map(|c| c.to_string()).collect(),
+            category: category.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether `control_type` is one of the exact types
+    pub fn matches_exactly(&self, control_type: &str) -> bool {
+        self.types.iter().any(|t| t == control_type)
+    }
+
+    /// Whether the lowercase `control_type` contains one of the fragments
+    pub fn matches_fragment(&self, control_type: &str) -> bool {
+        let lower = control_type.to_lowercase();
+        self.contains
+            .iter()
+            .any(|fragment| lower.contains(&fragment.to_lowercase()))
+    }
+
+    /// State UUIDs of `control` by meaning, for the described states it has
+    pub fn state_uuids(&self, control: &Value) -> Vec<(String, String)> {
+        self.states
+            .iter()
+            .filter_map(|(meaning, state)| {
+                let uuid = control.get("states")?.get(state)?.as_str()?;
+                Some((meaning.clone(), uuid.to_string()))
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.category.trim().is_empty() {
+            return Err(LoxoneError::config("Device type without a category"));
+        }
+        if self.types.is_empty() && self.contains.is_empty() {
+            return Err(LoxoneError::config(format!(
+                "Device type of category '{}' matches no control type; set types or contains",
+                self.category
+            )));
+        }
+        if self
+            .contains
+            .iter()
+            .any(|fragment| fragment.trim().is_empty())
+        {
+            return Err(LoxoneError::config(format!(
+                "Device type of category '{}' has an empty contains fragment",
+                self.category
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Layout of a device types file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeviceTypesFile {
+    #[serde(default)]
+    device_type: Vec<DeviceTypeDescriptor>,
+}
+
+/// Where extra device types are read from
+#[derive(Debug, Clone, Default)]
+pub struct DeviceTypesConfig {
+    /// TOML file of descriptors (built-in types only if unset)
+    pub file: Option<PathBuf>,
+}
+
+impl DeviceTypesConfig {
+    pub fn from_env() -> Self {
+        Self {
+            file: std::env::var("LOXONE_DEVICE_TYPES_FILE")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+}
+
+/// Device type descriptors, configured ones first
+#[derive(Debug, Clone)]
+pub struct DeviceTypeRegistry {
+    descriptors: Vec<DeviceTypeDescriptor>,
+}
+
+impl Default for DeviceTypeRegistry {
+    fn default() -> Self {
+        Self {
+            descriptors: builtin_descriptors(),
+        }
+    }
+}
+
+impl DeviceTypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Built-in types plus those of the configured file; an invalid file is
+    /// ignored with a warning
+    pub fn from_config(config: &DeviceTypesConfig) -> Self {
+        let mut registry = Self::new();
+        if let Some(path) = &config.file {
+            match registry.load_file(path) {
+                Ok(count) => info!("Loaded {count} device type(s) from {}", path.display()),
+                Err(e) => warn!("Ignoring device types file {}: {e}", path.display()),
+            }
+        }
+        registry
+    }
+
+    /// Registry used by the client context, configured by
+    /// `LOXONE_DEVICE_TYPES_FILE`
+    pub fn global() -> &'static DeviceTypeRegistry {
+        static GLOBAL: OnceLock<DeviceTypeRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(|| DeviceTypeRegistry::from_config(&DeviceTypesConfig::from_env()))
+    }
+
+    /// Add a descriptor, tried before those registered earlier
+    pub fn register(&mut self, descriptor: DeviceTypeDescriptor) -> Result<()> {
+        descriptor.validate()?;
+        self.descriptors.insert(0, descriptor);
+        Ok(())
+    }
+
+    /// Add the descriptors of a TOML file; returns how many were added
+    pub fn load_file(&mut self, path: &Path) -> Result<usize> {
+        let contents = std::fs::read_to_string(path)?;
+        let file: DeviceTypesFile = toml::from_str(&contents).map_err(|e| {
+            LoxoneError::config(format!("Invalid device types file {}: {e}", path.display()))
+        })?;
+        for descriptor in &file.device_type {
+            descriptor.validate()?;
+        }
+        let count = file.device_type.len();
+        // Keep the file's order among themselves
+        for descriptor in file.device_type.into_iter().rev() {
+            self.register(descriptor)?;
+        }
+        Ok(count)
+    }
+
+    /// Descriptor of a control type, if any matches
+    pub fn descriptor(&self, control_type: &str) -> Option<&DeviceTypeDescriptor> {
+        self.descriptors
+            .iter()
+            .find(|d| d.matches_exactly(control_type))
+            .or_else(|| {
+                self.descriptors
+                    .iter()
+                    .find(|d| d.matches_fragment(control_type))
+            })
+    }
+
+    /// Device category of a control type
+    pub fn categorize(&self, control_type: &str) -> &str {
+        self.descriptor(control_type)
+            .map(|d| d.category.as_str())
+            .unwrap_or(OTHER_CATEGORY)
+    }
+
+    /// All descriptors in the order they are tried
+    pub fn descriptors(&self) -> &[DeviceTypeDescriptor] {
+        &self.descriptors
+    }
+}
+
+fn builtin_descriptors() -> Vec<DeviceTypeDescriptor> {
+    vec![
+        DeviceTypeDescriptor::exact(
+            &["LightControllerV2"],
+            "lights",
+            &[("moods", "activeMoods"), ("mood_list", "moodList")],
+        ),
+        DeviceTypeDescriptor::exact(
+            &["Dimmer", "EIBDimmer"],
+            "lights",
+            &[("brightness", "position")],
+        ),
+        DeviceTypeDescriptor::exact(
+            &["Jalousie", "CentralJalousie"],
+            "blinds",
+            &[("position", "position"), ("slats", "shadePosition")],
+        ),
+        DeviceTypeDescriptor::exact(
+            &["Gate", "CentralGate"],
+            "access",
+            &[("position", "position"), ("moving", "active")],
+        ),
+        DeviceTypeDescriptor::exact(
+            &["Alarm"],
+            "security",
+            &[("armed", "armed"), ("level", "level")],
+        ),
+        DeviceTypeDescriptor::exact(
+            &["AudioZoneV2"],
+            "audio",
+            &[("volume", "volume"), ("playing", "playState")],
+        ),
+        DeviceTypeDescriptor::fragments(&["light", "dimmer"], "lights"),
+        DeviceTypeDescriptor::fragments(&["jalousie", "blind"], "blinds"),
+        DeviceTypeDescriptor::fragments(
+            &[
+                "climate",
+                "heating",
+                "temperature",
+                "boiler",
+                "hotwater",
+                "waterheater",
+            ],
+            "climate",
+        ),
+        DeviceTypeDescriptor::fragments(&["sensor", "analog"], "sensors"),
+        DeviceTypeDescriptor::fragments(&["weather"], "weather"),
+        DeviceTypeDescriptor::fragments(&["security", "alarm"], "security"),
+        DeviceTypeDescriptor::fragments(&["gate", "garage"], "access"),
+        DeviceTypeDescriptor::fragments(&["intercom", "doorbell"], "intercom"),
+        DeviceTypeDescriptor::fragments(&["irrigation"], "irrigation"),
+        DeviceTypeDescriptor::fragments(&["ventilation", "airhandling"], "ventilation"),
+        DeviceTypeDescriptor::fragments(&["energy", "meter"], "energy"),
+        DeviceTypeDescriptor::fragments(&["audio", "music"], "audio"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_builtin_categories() {
+        let registry = DeviceTypeRegistry::new();
+        assert_eq!(registry.categorize("LightControllerV2"), "lights");
+        assert_eq!(registry.categorize("CentralJalousie"), "blinds");
+        assert_eq!(registry.categorize("IRoomControllerV2"), "other");
+        assert_eq!(registry.categorize("InfoOnlyAnalog"), "sensors");
+        assert_eq!(registry.categorize("WaterHeater"), "climate");
+        assert_eq!(registry.categorize("CentralGate"), "access");
+
+        let dimmer = registry.descriptor("EIBDimmer").unwrap();
+        assert_eq!(dimmer.states["brightness"], "position");
+        assert!(registry.descriptor("Pushbutton").is_none());
+    }
+
+    #[test]
+    fn test_types_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("device_types.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[device_type]]
+types = ["Wallbox2"]
+category = "energy"
+states = { power = "power" }
+
+[[device_type]]
+contains = ["sauna"]
+category = "wellness"
+
+[[device_type]]
+types = ["IRoomControllerV2"]
+category = "climate"
+
+[[device_type]]
+contains = ["dimmer"]
+category = "knx"
+"#,
+        )
+        .unwrap();
+
+        let registry = DeviceTypeRegistry::from_config(&DeviceTypesConfig {
+            file: Some(path.clone()),
+        });
+        assert_eq!(registry.categorize("Wallbox2"), "energy");
+        let wallbox = json!({"type": "Wallbox2", "states": {"power": "w-1", "mode": "w-2"}});
+        assert_eq!(
+            registry
+                .descriptor("Wallbox2")
+                .unwrap()
+                .state_uuids(&wallbox),
+            [("power".to_string(), "w-1".to_string())]
+        );
+        assert_eq!(registry.categorize("SaunaVapor"), "wellness");
+        assert_eq!(registry.categorize("IRoomControllerV2"), "climate");
+        // Exact built-in types still beat configured fragments
+        assert_eq!(registry.categorize("Dimmer"), "lights");
+        assert_eq!(registry.categorize("KnxDimmer"), "knx");
+
+        std::fs::write(&path, "[[device_type]]\ncategory = \"energy\"\n").unwrap();
+        let mut registry = DeviceTypeRegistry::new();
+        assert!(registry.load_file(&path).is_err());
+        std::fs::write(
+            &path,
+            "[[device_type]]\ntypes = [\"X\"]\ncategory = \"a\"\nicon = 1\n",
+        )
+        .unwrap();
+        assert!(registry.load_file(&path).is_err());
+        assert_eq!(registry.descriptors().len(), builtin_descriptors().len());
+    }
+}
@@ -4,8 +4,11 @@
 //! Miniservers using basic authentication and REST API calls.
 
 use crate::client::{
-    ClientContext, LoxoneClient, LoxoneDevice, LoxoneResponse, LoxoneStructure,
+    AIR_DEVICES_PATH, ClientContext, DEVICE_STATUS_PATH, LoxoneClient, LoxoneDevice,
+    LoxoneResponse, LoxoneStructure, WEATHER_FORECAST_PATH, command_error,
+    command_throttle::{CommandCategory, CommandLimits, CommandThrottle},
     connection_pool::{ConnectionPool, PoolBuilder},
+    is_valid_calendar_command, time_sync,
 };
 use crate::config::{LoxoneConfig, credentials::LoxoneCredentials};
 use crate::error::{LoxoneError, Result};
@@ -65,6 +68,9 @@ pub struct LoxoneHttpClient {
 
     /// Connection pool for resource management
     connection_pool: Arc<ConnectionPool>,
+
+    /// Per-category limits for parallel commands
+    throttle: Arc<CommandThrottle>,
 }
 
 impl LoxoneHttpClient {
@@ -119,9 +125,33 @@ impl LoxoneHttpClient {
             context: Arc::new(ClientContext::new()),
             connected: false,
             connection_pool,
+            throttle: Arc::new(CommandThrottle::default()),
         })
     }
 
+    /// Category of each command's target device, for the command throttle
+    async fn categorize_commands(
+        &self,
+        commands: Vec<(String, String)>,
+    ) -> Vec<(CommandCategory, String, String)> {
+        let devices = self.context.devices.read().await;
+        commands
+            .into_iter()
+            .map(|(uuid, command)| {
+                let category = devices.get(&uuid).map_or(CommandCategory::Other, |d| {
+                    CommandCategory::of(&d.device_type)
+                });
+                (category, uuid, command)
+            })
+            .collect()
+    }
+
+    /// Replace the per-category limits for parallel commands
+    pub fn with_command_limits(mut self, limits: CommandLimits) -> Self {
+        self.throttle = Arc::new(CommandThrottle::new(limits));
+        self
+    }
+
     /// Build URL for API endpoint
     fn build_url(&self, path: &str) -> Result<Url> {
         self.base_url
@@ -185,6 +215,28 @@ impl LoxoneHttpClient {
     }
 
     /// Parse Loxone response format
+    /// Fetch a plain `jdev/sys/*` value as string
+    async fn fetch_sys_value(&self, path: &str) -> Result<String> {
+        let url = self.build_url(path)?;
+        let response = self.execute_request(url).await?;
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to read {path}: {e}")))?;
+
+        let loxone_response = Self::parse_loxone_response(&text);
+        if loxone_response.code != 200 {
+            return Err(LoxoneError::connection(format!(
+                "Request {path} failed: {:?}",
+                loxone_response.value
+            )));
+        }
+        Ok(match loxone_response.value {
+            serde_json::Value::String(value) => value,
+            other => other.to_string(),
+        })
+    }
+
     fn parse_loxone_response(text: &str) -> LoxoneResponse {
         // Try parsing as JSON first
         if let Ok(json_response) = serde_json::from_str::<LoxoneResponse>(text) {
@@ -249,6 +301,10 @@ impl LoxoneClient for LoxoneHttpClient {
                     }
                 }
 
+                if let Err(e) = time_sync::measure(&*self).await {
+                    debug!("Could not measure Miniserver clock drift: {e}");
+                }
+
                 info!("✅ Connected to Loxone Miniserver");
                 Ok(())
             }
@@ -297,16 +353,20 @@ impl LoxoneClient for LoxoneHttpClient {
         let loxone_response = Self::parse_loxone_response(&text);
 
         if loxone_response.code != 200 {
-            return Err(LoxoneError::device_control(format!(
-                "Command failed with code {}: {:?}",
-                loxone_response.code, loxone_response.value
-            )));
+            return Err(command_error(uuid, &loxone_response));
         }
 
         debug!("Command successful: {:?}", loxone_response.value);
         Ok(loxone_response)
     }
 
+    async fn send_parallel_commands(
+        &self,
+        commands: Vec<(String, String)>,
+    ) -> Result<Vec<Result<LoxoneResponse>>> {
+        self.control_devices_parallel(commands).await
+    }
+
     async fn get_structure(&self) -> Result<LoxoneStructure> {
         debug!("Fetching structure file");
 
@@ -455,6 +515,68 @@ impl LoxoneClient for LoxoneHttpClient {
         }
     }
 
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        let date = self.fetch_sys_value("jdev/sys/date").await?;
+        let time = self.fetch_sys_value("jdev/sys/time").await?;
+        time_sync::parse_miniserver_time(&date, &time).ok_or_else(|| {
+            LoxoneError::parsing_error(format!("Unexpected Miniserver time: {date} {time}"))
+        })
+    }
+
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        if !is_valid_calendar_command(command) {
+            return Err(LoxoneError::validation(format!(
+                "Invalid calendar command: {command}"
+            )));
+        }
+
+        debug!("Sending calendar command '{command}'");
+        let url = self.build_url(&format!("jdev/sps/{command}"))?;
+        let response = self.execute_request(url).await?;
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to read response: {e}")))?;
+
+        let loxone_response = Self::parse_loxone_response(&text);
+        if loxone_response.code != 200 {
+            return Err(LoxoneError::device_control(format!(
+                "Calendar command failed with code {}: {:?}",
+                loxone_response.code, loxone_response.value
+            )));
+        }
+        Ok(loxone_response)
+    }
+
+    async fn get_air_devices(&self) -> Result<serde_json::Value> {
+        let value = self.fetch_sys_value(AIR_DEVICES_PATH).await?;
+        serde_json::from_str(&value).map_err(|e| {
+            LoxoneError::parsing_error(format!("Unexpected Air device diagnostics: {e}"))
+        })
+    }
+
+    async fn get_device_status(&self) -> Result<serde_json::Value> {
+        let value = self.fetch_sys_value(DEVICE_STATUS_PATH).await?;
+        serde_json::from_str(&value)
+            .map_err(|e| LoxoneError::parsing_error(format!("Unexpected device status: {e}")))
+    }
+
+    async fn get_weather_forecast(&self) -> Result<serde_json::Value> {
+        let value = self.fetch_sys_value(WEATHER_FORECAST_PATH).await?;
+        serde_json::from_str(&value)
+            .map_err(|e| LoxoneError::parsing_error(format!("Unexpected weather forecast: {e}")))
+    }
+
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        let url = self.build_url(path)?;
+        let response = self.execute_request(url).await?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to download {path}: {e}")))?;
+        Ok(bytes.to_vec())
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -586,7 +708,7 @@ impl LoxoneHttpClient {
         )))
     }
 
-    /// Control multiple devices in parallel
+    /// Control multiple devices in parallel, within the per-category limits
     pub async fn control_devices_parallel(
         &self,
         commands: Vec<(String, String)>, // (uuid, command) pairs
@@ -595,13 +717,13 @@ impl LoxoneHttpClient {
             return Err(LoxoneError::connection("Not connected to Miniserver"));
         }
 
-        // Execute commands sequentially (parallel would need Arc<Self>)
-        let mut results = Vec::new();
-        for (uuid, command) in commands {
-            let result = self.send_command(&uuid, &command).await;
-            results.push(result);
-        }
-        Ok(results)
+        let commands = self.categorize_commands(commands).await;
+        Ok(self
+            .throttle
+            .run_all(commands, |uuid, command| async move {
+                self.send_command(&uuid, &command).await
+            })
+            .await)
     }
 
     /// Get structure using streaming parser for large files
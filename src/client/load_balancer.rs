@@ -623,6 +623,7 @@ mod tests {
                     rooms: std::collections::HashMap::new(),
                     cats: std::collections::HashMap::new(),
                     global_states: std::collections::HashMap::new(),
+                    operating_modes: std::collections::HashMap::new(),
                 })
             }
 
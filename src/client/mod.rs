@@ -1,25 +1,38 @@
 //! Loxone client implementations for HTTP and WebSocket communication
 
 pub mod adaptive_pool;
+pub mod api_budget;
 #[cfg(feature = "crypto-openssl")]
 pub mod auth;
+pub mod categories;
 pub mod client_factory;
 pub mod command_queue;
+pub mod command_throttle;
 pub mod connection_pool;
+pub mod device_types;
 pub mod http_client;
 pub mod load_balancer;
 pub mod pool_health_monitor;
+pub mod recording_client;
+pub mod state_stream;
+pub mod statistics;
 pub mod streaming_parser;
+pub mod structure_compat;
+pub mod tenant_client;
+pub mod time_sync;
 #[cfg(feature = "crypto-openssl")]
 pub mod token_http_client;
+pub mod watching_client;
 #[cfg(feature = "websocket")]
 pub mod websocket_client;
 #[cfg(feature = "websocket")]
 pub mod websocket_resilience;
+pub mod ws_keepalive;
 
 pub use adaptive_pool::{
     AdaptiveConnectionGuard, AdaptiveConnectionPool, AdaptivePoolBuilder, PoolStatistics,
 };
+pub use categories::LoxoneCategory;
 pub use client_factory::{
     AdaptiveClientFactory, ClientFactory, EncryptionLevel, ServerCapabilities, StaticClientFactory,
 };
@@ -31,8 +44,12 @@ pub use pool_health_monitor::{
     AlertThresholds, HealthAlert, HealthMetrics, HealthMonitorConfig, HealthStatus,
     PoolHealthMonitor,
 };
+pub use recording_client::RecordingClient;
+pub use state_stream::{LoxoneEventType, StateStream, StateUpdate};
+pub use tenant_client::TenantScopedClient;
 #[cfg(feature = "crypto-openssl")]
 pub use token_http_client::TokenHttpClient;
+pub use watching_client::WatchingClient;
 #[cfg(feature = "websocket")]
 pub use websocket_client::LoxoneWebSocketClient;
 #[cfg(feature = "websocket")]
@@ -64,6 +81,9 @@ pub struct LoxoneDevice {
     pub states: HashMap<String, serde_json::Value>,
     /// Category
     pub category: String,
+    /// User-defined category from the structure's `cats` section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loxone_category: Option<LoxoneCategory>,
     /// Sub-controls (for complex devices)
     pub sub_controls: HashMap<String, serde_json::Value>,
 }
@@ -80,7 +100,12 @@ pub struct LoxoneRoom {
 }
 
 /// Loxone structure file data
+///
+/// Deserialization goes through [`structure_compat`] so structure files from
+/// older firmware versions (other key casings, missing or array-shaped
+/// sections) load as well.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "serde_json::Value")]
 pub struct LoxoneStructure {
     /// Last modified timestamp
     #[serde(rename = "lastModified")]
@@ -89,11 +114,14 @@ pub struct LoxoneStructure {
     pub controls: HashMap<String, serde_json::Value>,
     /// Room definitions
     pub rooms: HashMap<String, serde_json::Value>,
-    /// Categories
+    /// Categories (missing on some older firmware versions)
     pub cats: HashMap<String, serde_json::Value>,
     /// Global states (optional, not present in all Loxone versions)
-    #[serde(default)]
+    #[serde(rename = "globalStates")]
     pub global_states: HashMap<String, serde_json::Value>,
+    /// Operating mode names by mode id (optional)
+    #[serde(rename = "operatingModes")]
+    pub operating_modes: HashMap<String, serde_json::Value>,
 }
 
 /// Command response from Loxone
@@ -117,12 +145,15 @@ pub struct SystemCapabilities {
     pub has_audio: bool,
     pub has_climate: bool,
     pub has_sensors: bool,
+    pub has_access: bool,
+    pub has_intercom: bool,
 
     // Detailed counts
     pub light_count: usize,
     pub blind_count: usize,
     pub sensor_count: usize,
     pub climate_count: usize,
+    pub access_count: usize,
 }
 
 /// Trait for Loxone client implementations
@@ -140,6 +171,23 @@ pub trait LoxoneClient: Send + Sync {
     /// Send a command to a device
     async fn send_command(&self, uuid: &str, command: &str) -> Result<LoxoneResponse>;
 
+    /// Send `(uuid, command)` pairs at once; results are in the order of
+    /// `commands`
+    ///
+    /// The default sends everything concurrently. HTTP clients override it
+    /// to stay within the per-category limits of [`command_throttle`].
+    async fn send_parallel_commands(
+        &self,
+        commands: Vec<(String, String)>,
+    ) -> Result<Vec<Result<LoxoneResponse>>> {
+        Ok(futures::future::join_all(
+            commands
+                .iter()
+                .map(|(uuid, command)| self.send_command(uuid, command)),
+        )
+        .await)
+    }
+
     /// Get the structure file (LoxAPP3.json)
     async fn get_structure(&self) -> Result<LoxoneStructure>;
 
@@ -171,10 +219,148 @@ pub trait LoxoneClient: Send + Sync {
     /// Health check
     async fn health_check(&self) -> Result<bool>;
 
+    /// Current wall-clock time of the Miniserver (local time, no zone)
+    ///
+    /// Used to measure clock drift, see [`time_sync`].
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        Err(crate::error::LoxoneError::connection(
+            "Miniserver time not available for this client",
+        ))
+    }
+
+    /// Send a calendar command (`jdev/sps/calendar...`), e.g.
+    /// `calendargetentries`
+    ///
+    /// Calendar entries switch the house-wide operating modes, see
+    /// [`crate::server::operating_modes`].
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        Err(crate::error::LoxoneError::connection(format!(
+            "Calendar command {command} not available for this client"
+        )))
+    }
+
+    /// Diagnostics of the Loxone Air peripherals paired with the Miniserver's
+    /// Air interfaces, as returned by [`AIR_DEVICES_PATH`]
+    ///
+    /// Parsed by [`crate::server::air_devices`].
+    async fn get_air_devices(&self) -> Result<serde_json::Value> {
+        Err(crate::error::LoxoneError::connection(
+            "Air device diagnostics not available for this client",
+        ))
+    }
+
+    /// Extensions, Tree branches and Air devices with their online status,
+    /// as returned by [`DEVICE_STATUS_PATH`]
+    ///
+    /// Parsed by [`crate::server::topology`].
+    async fn get_device_status(&self) -> Result<serde_json::Value> {
+        Err(crate::error::LoxoneError::connection(
+            "Device status not available for this client",
+        ))
+    }
+
+    /// Hourly forecast of the Loxone Weather Service, as returned by
+    /// [`WEATHER_FORECAST_PATH`]; fails without a weather service license
+    ///
+    /// Parsed by [`crate::server::weather_forecast`].
+    async fn get_weather_forecast(&self) -> Result<serde_json::Value> {
+        Err(crate::error::LoxoneError::connection(
+            "Weather forecast not available for this client",
+        ))
+    }
+
+    /// Download a file from the Miniserver file API (e.g. `dev/fsget/prog/...`)
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        Err(crate::error::LoxoneError::connection(format!(
+            "File download of {path} not available for this client"
+        )))
+    }
+
+    /// Statistics of a control for one month, see [`statistics`]
+    ///
+    /// Reads the XML file and falls back to the binary file, whose records
+    /// hold one value per name in `outputs`.
+    async fn get_statistics(
+        &self,
+        uuid: &str,
+        year: i32,
+        month: u32,
+        outputs: &[String],
+    ) -> Result<statistics::StatisticSeries> {
+        let xml_error = match self
+            .download_file(&statistics::xml_path(uuid, year, month)?)
+            .await
+        {
+            Ok(bytes) => match statistics::parse_xml(&String::from_utf8_lossy(&bytes)) {
+                Ok(series) => return Ok(series),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+        tracing::debug!("No XML statistics for {uuid} ({year}-{month:02}): {xml_error}");
+
+        let bytes = self
+            .download_file(&statistics::binary_path(uuid, year, month)?)
+            .await?;
+        Ok(statistics::StatisticSeries {
+            name: None,
+            outputs: outputs.to_vec(),
+            points: statistics::parse_binary(&bytes, outputs.len())?,
+        })
+    }
+
+    /// Subscribe to state updates for the given device UUIDs (empty: all devices)
+    ///
+    /// The default implementation polls `get_device_states`; push-capable
+    /// transports override it. Call on a cloned `Arc` handle.
+    fn subscribe_states(self: Arc<Self>, uuids: Vec<String>) -> StateStream
+    where
+        Self: 'static,
+    {
+        state_stream::polling_state_stream(self, uuids, state_stream::DEFAULT_POLL_INTERVAL)
+    }
+
     /// Cast to Any for type checking
     fn as_any(&self) -> &dyn std::any::Any;
 }
 
+/// Diagnostic endpoint listing the Air peripherals with battery level and
+/// reachability
+pub const AIR_DEVICES_PATH: &str = "jdev/sps/airdevices";
+
+/// Device status API listing extensions, Tree branches and Air devices
+pub const DEVICE_STATUS_PATH: &str = "jdev/sps/devicestatus";
+
+/// Weather Service forecast, hourly entries for the coming days
+pub const WEATHER_FORECAST_PATH: &str = "jdev/sps/weatherforecast";
+
+/// Response code of a command rejected by a locked control
+pub const LOCKED_RESPONSE_CODE: i32 = 423;
+
+/// Error for a device command whose response code is not `200`
+pub(crate) fn command_error(uuid: &str, response: &LoxoneResponse) -> crate::error::LoxoneError {
+    if response.code == LOCKED_RESPONSE_CODE {
+        crate::error::LoxoneError::device_locked(format!(
+            "Device {uuid} is locked and ignores commands: {:?}",
+            response.value
+        ))
+    } else {
+        crate::error::LoxoneError::device_control(format!(
+            "Command failed with code {}: {:?}",
+            response.code, response.value
+        ))
+    }
+}
+
+/// Whether `command` is a calendar command that is safe to put in a URL path
+pub(crate) fn is_valid_calendar_command(command: &str) -> bool {
+    command.starts_with("calendar")
+        && !command.contains("..")
+        && command
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-'))
+}
+
 /// Shared client context for caching and state management
 #[derive(Debug, Clone)]
 pub struct ClientContext {
@@ -226,6 +412,7 @@ impl ClientContext {
         let mut devices = HashMap::new();
         let mut rooms = HashMap::new();
         let mut capabilities = SystemCapabilities::default();
+        let categories = categories::category_index(&structure);
 
         // Parse rooms first
         for (uuid, room_data) in &structure.rooms {
@@ -293,7 +480,8 @@ impl ClientContext {
                     .unwrap_or_default();
 
                 // Determine category based on type
-                let category = self.categorize_device(&device_type);
+                let category = Self::categorize_device(&device_type);
+                let loxone_category = categories::control_category(&categories, control_data);
 
                 // Update capabilities
                 self.update_capabilities(&mut capabilities, &device_type, &category);
@@ -314,6 +502,7 @@ impl ClientContext {
                         room: room_name,
                         states,
                         category,
+                        loxone_category,
                         sub_controls,
                     },
                 );
@@ -330,21 +519,11 @@ impl ClientContext {
         Ok(())
     }
 
-    /// Categorize device based on type
-    fn categorize_device(&self, device_type: &str) -> String {
-        match device_type.to_lowercase().as_str() {
-            t if t.contains("light") || t.contains("dimmer") => "lights".to_string(),
-            t if t.contains("jalousie") || t.contains("blind") => "blinds".to_string(),
-            t if t.contains("climate") || t.contains("heating") || t.contains("temperature") => {
-                "climate".to_string()
-            }
-            t if t.contains("sensor") || t.contains("analog") => "sensors".to_string(),
-            t if t.contains("weather") => "weather".to_string(),
-            t if t.contains("security") || t.contains("alarm") => "security".to_string(),
-            t if t.contains("energy") || t.contains("meter") => "energy".to_string(),
-            t if t.contains("audio") || t.contains("music") => "audio".to_string(),
-            _ => "other".to_string(),
-        }
+    /// Categorize device based on type (see [`device_types`])
+    pub fn categorize_device(device_type: &str) -> String {
+        device_types::DeviceTypeRegistry::global()
+            .categorize(device_type)
+            .to_string()
     }
 
     /// Update system capabilities based on device
@@ -373,8 +552,13 @@ impl ClientContext {
             }
             "weather" => capabilities.has_weather = true,
             "security" => capabilities.has_security = true,
+            "access" => {
+                capabilities.has_access = true;
+                capabilities.access_count += 1;
+            }
             "energy" => capabilities.has_energy = true,
             "audio" => capabilities.has_audio = true,
+            "intercom" => capabilities.has_intercom = true,
             _ => {}
         }
     }
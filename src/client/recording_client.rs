@@ -0,0 +1,187 @@
+//! Command-recording client wrapper
+//!
+//! Wraps a [`LoxoneClient`] so every `send_command` is appended to the
+//! [`CommandHistory`] together with the tool and API key of the calling
+//! task (see [`CommandOrigin`]) and, if attached, counted in the
+//! [`LifetimeMetrics`]. Reads pass through unrecorded.
+
+use crate::client::{LoxoneClient, LoxoneResponse, LoxoneStructure, StateStream};
+use crate::error::Result;
+use crate::monitoring::lifetime_metrics::LifetimeMetrics;
+use crate::services::command_history::{CommandHistory, CommandOrigin};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Client that records outbound device commands
+pub struct RecordingClient {
+    inner: Arc<dyn LoxoneClient>,
+    history: Arc<CommandHistory>,
+    lifetime: Option<Arc<LifetimeMetrics>>,
+}
+
+impl RecordingClient {
+    /// Record commands sent through `inner` into `history`
+    pub fn new(inner: Arc<dyn LoxoneClient>, history: Arc<CommandHistory>) -> Self {
+        Self {
+            inner,
+            history,
+            lifetime: None,
+        }
+    }
+
+    /// Also count commands in `metrics`
+    pub fn with_lifetime_metrics(mut self, metrics: Arc<LifetimeMetrics>) -> Self {
+        self.lifetime = Some(metrics);
+        self
+    }
+
+    /// History commands are recorded into
+    pub fn history(&self) -> &Arc<CommandHistory> {
+        &self.history
+    }
+}
+
+#[async_trait]
+impl LoxoneClient for RecordingClient {
+    async fn connect(&mut self) -> Result<()> {
+        Err(crate::error::LoxoneError::config(
+            "Recording clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn is_connected(&self) -> Result<bool> {
+        self.inner.is_connected().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        Err(crate::error::LoxoneError::config(
+            "Recording clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn send_command(&self, uuid: &str, command: &str) -> Result<LoxoneResponse> {
+        let started = Instant::now();
+        let result = self.inner.send_command(uuid, command).await;
+        let outcome = match &result {
+            Ok(response) => Ok(response.code),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Some(lifetime) = &self.lifetime {
+            lifetime.record_command(outcome.is_ok());
+        }
+        self.history.record(
+            uuid,
+            command,
+            CommandOrigin::current(),
+            outcome,
+            started.elapsed().as_millis() as u64,
+        );
+        result
+    }
+
+    async fn get_structure(&self) -> Result<LoxoneStructure> {
+        self.inner.get_structure().await
+    }
+
+    async fn get_device_states(
+        &self,
+        uuids: &[String],
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        self.inner.get_device_states(uuids).await
+    }
+
+    async fn get_state_values(
+        &self,
+        state_uuids: &[String],
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        self.inner.get_state_values(state_uuids).await
+    }
+
+    async fn get_all_device_states_batch(&self) -> Result<HashMap<String, serde_json::Value>> {
+        self.inner.get_all_device_states_batch().await
+    }
+
+    async fn get_system_info(&self) -> Result<serde_json::Value> {
+        self.inner.get_system_info().await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        self.inner.get_miniserver_time().await
+    }
+
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        let started = Instant::now();
+        let result = self.inner.send_calendar_command(command).await;
+        let outcome = match &result {
+            Ok(response) => Ok(response.code),
+            Err(e) => Err(e.to_string()),
+        };
+        self.history.record(
+            "calendar",
+            command,
+            CommandOrigin::current(),
+            outcome,
+            started.elapsed().as_millis() as u64,
+        );
+        result
+    }
+
+    async fn get_air_devices(&self) -> Result<serde_json::Value> {
+        self.inner.get_air_devices().await
+    }
+
+    async fn get_device_status(&self) -> Result<serde_json::Value> {
+        self.inner.get_device_status().await
+    }
+
+    async fn get_weather_forecast(&self) -> Result<serde_json::Value> {
+        self.inner.get_weather_forecast().await
+    }
+
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.download_file(path).await
+    }
+
+    fn subscribe_states(self: Arc<Self>, uuids: Vec<String>) -> StateStream {
+        self.inner.clone().subscribe_states(uuids)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockLoxoneClient;
+    use crate::services::command_history::CommandFilter;
+
+    #[tokio::test]
+    async fn test_commands_are_recorded_with_origin() {
+        let history = Arc::new(CommandHistory::in_memory(10));
+        let client = RecordingClient::new(Arc::new(MockLoxoneClient::new()), history.clone());
+
+        let origin = CommandOrigin {
+            tool: Some("control_device".to_string()),
+            key: None,
+        };
+        origin
+            .scope(client.send_command("light-1", "on"))
+            .await
+            .unwrap();
+        client.get_system_info().await.unwrap();
+
+        let records = history.query(&CommandFilter::default());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].device, "light-1");
+        assert_eq!(records[0].origin.tool.as_deref(), Some("control_device"));
+        assert!(records[0].success);
+    }
+}
@@ -0,0 +1,265 @@
+//! Transport-independent state subscriptions
+//!
+//! [`LoxoneClient::subscribe_states`](super::LoxoneClient::subscribe_states)
+//! returns a [`StateStream`] of [`StateUpdate`]s. The WebSocket client forwards
+//! pushed events; every other client falls back to polling
+//! `get_device_states` and emitting an update whenever a value changes, so
+//! services consume the same stream regardless of transport.
+
+use super::LoxoneClient;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Stream of state updates returned by `subscribe_states`
+pub type StateStream = BoxStream<'static, StateUpdate>;
+
+/// Default interval of the polling fallback
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Event types from Loxone state updates
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LoxoneEventType {
+    /// Device state change
+    #[default]
+    State,
+    /// Weather update
+    Weather,
+    /// Text message
+    Text,
+    /// Alarm/Security event
+    Alarm,
+    /// System event
+    System,
+    /// Binary sensor data
+    Sensor,
+    /// Unknown event type
+    Unknown(String),
+}
+
+impl From<String> for LoxoneEventType {
+    fn from(s: String) -> Self {
+        match s.to_lowercase().as_str() {
+            "state" => LoxoneEventType::State,
+            "weather" => LoxoneEventType::Weather,
+            "text" => LoxoneEventType::Text,
+            "alarm" => LoxoneEventType::Alarm,
+            "system" => LoxoneEventType::System,
+            "sensor" => LoxoneEventType::Sensor,
+            _ => LoxoneEventType::Unknown(s),
+        }
+    }
+}
+
+impl std::fmt::Display for LoxoneEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoxoneEventType::State => write!(f, "state"),
+            LoxoneEventType::Weather => write!(f, "weather"),
+            LoxoneEventType::Text => write!(f, "text"),
+            LoxoneEventType::Alarm => write!(f, "alarm"),
+            LoxoneEventType::System => write!(f, "system"),
+            LoxoneEventType::Sensor => write!(f, "sensor"),
+            LoxoneEventType::Unknown(s) => write!(f, "unknown({s})"),
+        }
+    }
+}
+
+/// State update from the Miniserver, delivered over WebSocket or polling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateUpdate {
+    /// Device UUID
+    pub uuid: String,
+
+    /// State name
+    pub state: String,
+
+    /// New value
+    pub value: serde_json::Value,
+
+    /// Previous value (if available)
+    pub previous_value: Option<serde_json::Value>,
+
+    /// Event type
+    pub event_type: LoxoneEventType,
+
+    /// Timestamp
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+
+    /// Room name (if available)
+    pub room: Option<String>,
+
+    /// Device name (if available)
+    pub device_name: Option<String>,
+}
+
+/// Poll `client` for the given device UUIDs and emit changed values
+///
+/// An empty UUID list polls every control via `get_all_device_states_batch`.
+/// The first successful poll emits the current value of every device; later
+/// polls only emit values that differ from the previous poll. Poll errors are
+/// logged and retried on the next tick.
+pub fn polling_state_stream<C>(
+    client: Arc<C>,
+    uuids: Vec<String>,
+    interval: Duration,
+) -> StateStream
+where
+    C: LoxoneClient + ?Sized + 'static,
+{
+    Box::pin(async_stream::stream! {
+        let mut previous: HashMap<String, serde_json::Value> = HashMap::new();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            let result = if uuids.is_empty() {
+                client.get_all_device_states_batch().await
+            } else {
+                client.get_device_states(&uuids).await
+            };
+
+            let current = match result {
+                Ok(states) => states,
+                Err(e) => {
+                    warn!("State poll failed: {e}");
+                    continue;
+                }
+            };
+
+            let updates = diff_states(&mut previous, current);
+            debug!("State poll produced {} updates", updates.len());
+            for update in updates {
+                yield update;
+            }
+        }
+    })
+}
+
+/// Compare a poll result with the previous one and record the new values
+fn diff_states(
+    previous: &mut HashMap<String, serde_json::Value>,
+    current: HashMap<String, serde_json::Value>,
+) -> Vec<StateUpdate> {
+    let now = chrono::Utc::now();
+    let mut updates: Vec<_> = current
+        .into_iter()
+        .filter_map(|(uuid, value)| {
+            let old = previous.insert(uuid.clone(), value.clone());
+            (old.as_ref() != Some(&value)).then(|| StateUpdate {
+                uuid,
+                state: "value".to_string(),
+                value,
+                previous_value: old,
+                event_type: LoxoneEventType::State,
+                timestamp: now,
+                room: None,
+                device_name: None,
+            })
+        })
+        .collect();
+    updates.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{LoxoneResponse, LoxoneStructure};
+    use crate::error::{LoxoneError, Result};
+    use futures::StreamExt;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_states_emits_changes_only() {
+        let mut previous = HashMap::new();
+
+        let first = diff_states(
+            &mut previous,
+            HashMap::from([("a".to_string(), json!(1)), ("b".to_string(), json!(0))]),
+        );
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|u| u.previous_value.is_none()));
+
+        let second = diff_states(
+            &mut previous,
+            HashMap::from([("a".to_string(), json!(1)), ("b".to_string(), json!(1))]),
+        );
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].uuid, "b");
+        assert_eq!(second[0].previous_value, Some(json!(0)));
+        assert_eq!(second[0].value, json!(1));
+    }
+
+    /// Client whose single device value increments on every poll
+    struct CountingClient {
+        polls: std::sync::atomic::AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl LoxoneClient for CountingClient {
+        async fn connect(&mut self) -> Result<()> {
+            Ok(())
+        }
+        async fn is_connected(&self) -> Result<bool> {
+            Ok(true)
+        }
+        async fn disconnect(&mut self) -> Result<()> {
+            Ok(())
+        }
+        async fn send_command(&self, _uuid: &str, _command: &str) -> Result<LoxoneResponse> {
+            Err(LoxoneError::internal("not used"))
+        }
+        async fn get_structure(&self) -> Result<LoxoneStructure> {
+            Err(LoxoneError::internal("not used"))
+        }
+        async fn get_device_states(
+            &self,
+            uuids: &[String],
+        ) -> Result<HashMap<String, serde_json::Value>> {
+            let poll = self.polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(uuids.iter().map(|u| (u.clone(), json!(poll))).collect())
+        }
+        async fn get_state_values(
+            &self,
+            _state_uuids: &[String],
+        ) -> Result<HashMap<String, serde_json::Value>> {
+            Ok(HashMap::new())
+        }
+        async fn get_system_info(&self) -> Result<serde_json::Value> {
+            Ok(json!({}))
+        }
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    #[tokio::test]
+    async fn test_polling_stream_reports_changes() {
+        let client: Arc<dyn LoxoneClient> = Arc::new(CountingClient {
+            polls: Default::default(),
+        });
+        let mut stream = polling_state_stream(
+            client,
+            vec!["light-1".to_string()],
+            Duration::from_millis(10),
+        );
+
+        let first = stream.next().await.unwrap();
+        assert_eq!(first.uuid, "light-1");
+        assert_eq!(first.value, json!(0));
+        assert_eq!(first.previous_value, None);
+
+        let second = stream.next().await.unwrap();
+        assert_eq!(second.value, json!(1));
+        assert_eq!(second.previous_value, Some(json!(0)));
+    }
+}
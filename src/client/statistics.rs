@@ -0,0 +1,258 @@
+//! Miniserver statistics files
+//!
+//! Controls with statistics enabled list their recorded outputs in the
+//! structure file (`statistic.outputs`, each with a `name`). The Miniserver
+//! keeps one file per control and month:
+//!
+//! - `stats/<uuid>.<yyyymm>.xml`: `<Statistics Name=".." NumOutputs="n"
+//!   Outputs="a,b">` with one `<S T="yyyy-mm-dd hh:mm:ss" V="1.0" V2="2.0"/>`
+//!   element per sample
+//! - `binstatisticdata/<uuid>/<yyyymm>`: the same samples as fixed-size
+//!   records: 16 bytes control UUID, `u32` little-endian seconds since
+//!   2009-01-01 00:00 (Miniserver local time), then one little-endian `f64`
+//!   per output
+//!
+//! Newer firmware serves only the binary files, so the XML file is tried
+//! first and the binary one is the fallback.
+
+use crate::error::{LoxoneError, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+
+/// Bytes before the values of a binary record
+const BINARY_HEADER_LEN: usize = 20;
+
+/// One statistics sample
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatisticPoint {
+    pub time: NaiveDateTime,
+    /// One value per output
+    pub values: Vec<f64>,
+}
+
+/// Samples of one statistics file
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct StatisticSeries {
+    pub name: Option<String>,
+    /// Output names, in the order of [`StatisticPoint::values`]
+    pub outputs: Vec<String>,
+    pub points: Vec<StatisticPoint>,
+}
+
+/// Path of the XML statistics file of a control for a month
+pub fn xml_path(uuid: &str, year: i32, month: u32) -> Result<String> {
+    check_uuid(uuid)?;
+    Ok(format!("stats/{uuid}.{year:04}{month:02}.xml"))
+}
+
+/// Path of the binary statistics file of a control for a month
+pub fn binary_path(uuid: &str, year: i32, month: u32) -> Result<String> {
+    check_uuid(uuid)?;
+    Ok(format!("binstatisticdata/{uuid}/{year:04}{month:02}"))
+}
+
+fn check_uuid(uuid: &str) -> Result<()> {
+    if uuid.is_empty() || !uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(LoxoneError::validation(format!(
+            "Invalid Loxone UUID format: {uuid}"
+        )));
+    }
+    Ok(())
+}
+
+/// Parse an XML statistics file
+pub fn parse_xml(text: &str) -> Result<StatisticSeries> {
+    let header = element(text, "Statistics")
+        .ok_or_else(|| LoxoneError::parsing_error("No <Statistics> element in statistics file"))?;
+    let name = attribute(header, "Name").map(str::to_string);
+    let count: usize = attribute(header, "NumOutputs")
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1);
+    let mut outputs: Vec<String> = attribute(header, "Outputs")
+        .map(|o| o.split(',').map(|s| s.trim().to_string()).collect())
+        .unwrap_or_default();
+    while outputs.len() < count {
+        outputs.push(format!("Value{}", outputs.len() + 1));
+    }
+
+    let points = text
+        .split("<S ")
+        .skip(1)
+        .filter_map(|sample| {
+            // Keep the space before the first attribute for `attribute`
+            let sample = format!(" {}", &sample[..sample.find('>')?]);
+            let time = NaiveDateTime::parse_from_str(attribute(&sample, "T")?, "%Y-%m-%d %H:%M:%S")
+                .ok()?;
+            let values = (0..outputs.len())
+                .map(|i| {
+                    let key = if i == 0 {
+                        "V".to_string()
+                    } else {
+                        format!("V{}", i + 1)
+                    };
+                    attribute(&sample, &key).and_then(|v| v.parse().ok())
+                })
+                .collect::<Option<Vec<f64>>>()?;
+            Some(StatisticPoint { time, values })
+        })
+        .collect();
+
+    Ok(StatisticSeries {
+        name,
+        outputs,
+        points,
+    })
+}
+
+/// Parse a binary statistics file with `outputs` values per record
+pub fn parse_binary(bytes: &[u8], outputs: usize) -> Result<Vec<StatisticPoint>> {
+    let outputs = outputs.max(1);
+    let record_len = BINARY_HEADER_LEN + 8 * outputs;
+    if bytes.len() % record_len != 0 {
+        return Err(LoxoneError::parsing_error(format!(
+            "Binary statistics of {} bytes do not hold {outputs}-value records",
+            bytes.len()
+        )));
+    }
+    // Loxone's binary timestamps count from 2009-01-01
+    let epoch = NaiveDate::from_ymd_opt(2009, 1, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .unwrap_or_default();
+    Ok(bytes
+        .chunks_exact(record_len)
+        .map(|record| {
+            let seconds = u32::from_le_bytes([record[16], record[17], record[18], record[19]]);
+            let values = record[BINARY_HEADER_LEN..]
+                .chunks_exact(8)
+                .map(|v| f64::from_le_bytes(v.try_into().unwrap_or_default()))
+                .collect();
+            StatisticPoint {
+                time: epoch + chrono::Duration::seconds(i64::from(seconds)),
+                values,
+            }
+        })
+        .collect())
+}
+
+/// `(year, month)` of every month from `from` to `to`, inclusive
+pub fn months_between(from: NaiveDate, to: NaiveDate) -> Vec<(i32, u32)> {
+    use chrono::Datelike;
+    let mut months = Vec::new();
+    let (mut year, mut month) = (from.year(), from.month());
+    while (year, month) <= (to.year(), to.month()) {
+        months.push((year, month));
+        (year, month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+    }
+    months
+}
+
+/// Reduce `points` to at most `max_points` by averaging consecutive samples
+///
+/// Each averaged point carries the time of its first sample.
+pub fn downsample(points: &[StatisticPoint], max_points: usize) -> Vec<StatisticPoint> {
+    if max_points == 0 || points.len() <= max_points {
+        return points.to_vec();
+    }
+    let chunk = points.len().div_ceil(max_points);
+    points
+        .chunks(chunk)
+        .map(|samples| {
+            let width = samples.iter().map(|s| s.values.len()).min().unwrap_or(0);
+            let values = (0..width)
+                .map(|i| samples.iter().map(|s| s.values[i]).sum::<f64>() / samples.len() as f64)
+                .collect();
+            StatisticPoint {
+                time: samples[0].time,
+                values,
+            }
+        })
+        .collect()
+}
+
+/// Opening tag of the first `name` element
+fn element<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let start = text.find(&format!("<{name}"))?;
+    let end = text[start..].find('>')?;
+    Some(&text[start..start + end])
+}
+
+/// Value of attribute `name` in an opening tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {name}=\"");
+    let start = tag.find(&pattern)? + pattern.len();
+    let end = tag[start..].find('"')?;
+    Some(&tag[start..start + end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_parse_xml() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<Statistics Name="Living Room Temperature" NumOutputs="2" Outputs="Actual,Target">
+<S T="2025-03-01 00:00:00" V="21.5" V2="22"/>
+<S T="2025-03-01 00:05:00" V="21.6" V2="22"/>
+<S T="broken" V="1"/>
+</Statistics>"#;
+        let series = parse_xml(xml).unwrap();
+        assert_eq!(series.name.as_deref(), Some("Living Room Temperature"));
+        assert_eq!(series.outputs, vec!["Actual", "Target"]);
+        assert_eq!(series.points.len(), 2);
+        assert_eq!(series.points[1].time, time("2025-03-01 00:05:00"));
+        assert_eq!(series.points[1].values, vec![21.6, 22.0]);
+
+        assert!(parse_xml("<html>404</html>").is_err());
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        let mut bytes = Vec::new();
+        for (seconds, value) in [(0u32, 1.5f64), (86_400 + 60, 2.5)] {
+            bytes.extend_from_slice(&[0u8; 16]);
+            bytes.extend_from_slice(&seconds.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let points = parse_binary(&bytes, 1).unwrap();
+        assert_eq!(points[0].time, time("2009-01-01 00:00:00"));
+        assert_eq!(points[1].time, time("2009-01-02 00:01:00"));
+        assert_eq!(points[1].values, vec![2.5]);
+        assert!(parse_binary(&bytes[..30], 1).is_err());
+    }
+
+    #[test]
+    fn test_paths_months_and_downsampling() {
+        assert_eq!(
+            xml_path("0f1e2d3c-0123-4567-ffffeeeeddddcccc", 2025, 3).unwrap(),
+            "stats/0f1e2d3c-0123-4567-ffffeeeeddddcccc.202503.xml"
+        );
+        assert!(binary_path("../secret", 2025, 3).is_err());
+
+        let months = months_between(
+            NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+        );
+        assert_eq!(months, vec![(2024, 11), (2024, 12), (2025, 1), (2025, 2)]);
+
+        let points: Vec<StatisticPoint> = (0..10)
+            .map(|i| StatisticPoint {
+                time: time("2025-03-01 00:00:00") + chrono::Duration::minutes(i),
+                values: vec![i as f64],
+            })
+            .collect();
+        let reduced = downsample(&points, 4);
+        assert_eq!(reduced.len(), 4);
+        assert_eq!(reduced[0].values, vec![1.0]);
+        assert_eq!(reduced[3].values, vec![9.0]);
+        assert_eq!(downsample(&points, 20).len(), 10);
+    }
+}
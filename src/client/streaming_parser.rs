@@ -11,6 +11,7 @@
 //! - Error recovery and partial structure loading
 
 use crate::client::LoxoneStructure;
+use crate::client::structure_compat::normalize_structure;
 use crate::error::{LoxoneError, Result};
 use reqwest::Response;
 use serde::{Deserialize, Serialize};
@@ -133,6 +134,7 @@ struct PartialStructure {
     rooms: HashMap<String, Value>,
     cats: HashMap<String, Value>,
     global_states: HashMap<String, Value>,
+    operating_modes: HashMap<String, Value>,
     #[allow(dead_code)]
     total_size: usize,
 }
@@ -261,10 +263,10 @@ impl StreamingStructureParser {
     async fn parse_complete_json(&mut self, value: Value) -> Result<usize> {
         let mut items_parsed = 0;
 
-        if let Value::Object(obj) = value {
+        if let Value::Object(obj) = normalize_structure(value) {
             // Parse lastModified
             if let Some(last_modified) = obj.get("lastModified")
-                && let Some(s) = last_modified.as_str()
+                && let Some(s) = last_modified.as_str().filter(|s| !s.is_empty())
             {
                 self.parsed_structure.last_modified = Some(s.to_string());
             }
@@ -293,6 +295,11 @@ impl StreamingStructureParser {
             {
                 items_parsed += self.parse_global_states_section(gs_obj.clone()).await?;
             }
+
+            // A handful of names, always kept
+            if let Some(Value::Object(modes_obj)) = obj.get("operatingModes") {
+                self.parsed_structure.operating_modes = modes_obj.clone().into_iter().collect();
+            }
         }
 
         // Clear buffer after successful parse
@@ -452,6 +459,7 @@ impl StreamingStructureParser {
             rooms: self.parsed_structure.rooms.clone(),
             cats: self.parsed_structure.cats.clone(),
             global_states: self.parsed_structure.global_states.clone(),
+            operating_modes: self.parsed_structure.operating_modes.clone(),
         })
     }
 
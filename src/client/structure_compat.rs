@@ -0,0 +1,188 @@
+//! Structure file compatibility across firmware versions
+//!
+//! `LoxAPP3.json` has changed shape over the firmware generations. Older
+//! Gen1 Miniservers (firmware 9/10) differ from current ones in ways that
+//! used to break deserialization:
+//!
+//! - top-level keys with other casings (`LastModified`, `Controls`, ...)
+//! - `cats`, `globalStates` and `operatingModes` missing entirely
+//! - empty sections emitted as `[]` or `null` instead of `{}`
+//! - sections emitted as arrays of objects carrying their own `uuid`
+//! - `lastModified` as a number instead of a date string
+//!
+//! [`normalize_structure`] rewrites such variants into the current layout
+//! before the typed [`LoxoneStructure`] is built; unknown fields are kept
+//! out of the typed structure and ignored.
+
+use crate::client::LoxoneStructure;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Canonical top-level keys of the structure file
+const SECTION_KEYS: &[&str] = &[
+    "controls",
+    "rooms",
+    "cats",
+    "globalStates",
+    "operatingModes",
+];
+
+/// Canonical spelling of a top-level key, matched case- and underscore-insensitively
+fn canonical_key(key: &str) -> Option<&'static str> {
+    let folded: String = key
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect();
+    match folded.as_str() {
+        "lastmodified" => Some("lastModified"),
+        "controls" => Some("controls"),
+        "rooms" => Some("rooms"),
+        "cats" | "categories" => Some("cats"),
+        "globalstates" => Some("globalStates"),
+        "operatingmodes" => Some("operatingModes"),
+        _ => None,
+    }
+}
+
+/// Rewrite a raw structure file into the current layout
+pub fn normalize_structure(value: Value) -> Value {
+    let Value::Object(raw) = value else {
+        return value;
+    };
+
+    let mut normalized = Map::new();
+    for (key, section) in raw {
+        match canonical_key(&key) {
+            // Exact spelling wins over variants when both are present
+            Some(canonical) if canonical == key || !normalized.contains_key(canonical) => {
+                if canonical != key {
+                    debug!("Structure key '{key}' mapped to '{canonical}'");
+                }
+                normalized.insert(canonical.to_string(), section);
+            }
+            Some(_) => {}
+            None => {
+                normalized.entry(key).or_insert(section);
+            }
+        }
+    }
+
+    for key in SECTION_KEYS {
+        let section = normalized.remove(*key).unwrap_or(Value::Null);
+        normalized.insert(key.to_string(), section_to_map(section));
+    }
+
+    let last_modified = match normalized.remove("lastModified") {
+        Some(Value::String(s)) => s,
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    };
+    normalized.insert("lastModified".to_string(), Value::String(last_modified));
+
+    Value::Object(normalized)
+}
+
+/// Convert a section to a UUID-keyed object
+fn section_to_map(section: Value) -> Value {
+    match section {
+        Value::Object(map) => Value::Object(map),
+        Value::Array(items) => {
+            let map = items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let uuid = ["uuidAction", "uuid", "UUID"]
+                        .iter()
+                        .find_map(|k| item.get(*k).and_then(Value::as_str))
+                        .map(str::to_string)
+                        .unwrap_or_else(|| index.to_string());
+                    (uuid, item)
+                })
+                .collect();
+            Value::Object(map)
+        }
+        _ => Value::Object(Map::new()),
+    }
+}
+
+fn into_map(value: Option<Value>) -> HashMap<String, Value> {
+    match value {
+        Some(Value::Object(map)) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
+
+impl TryFrom<Value> for LoxoneStructure {
+    type Error = String;
+
+    fn try_from(value: Value) -> std::result::Result<Self, Self::Error> {
+        if !value.is_object() {
+            return Err(format!("structure file must be a JSON object, got {value}"));
+        }
+        let Value::Object(mut map) = normalize_structure(value) else {
+            unreachable!("normalized structure is an object");
+        };
+        Ok(Self {
+            last_modified: map
+                .remove("lastModified")
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            controls: into_map(map.remove("controls")),
+            rooms: into_map(map.remove("rooms")),
+            cats: into_map(map.remove("cats")),
+            global_states: into_map(map.remove("globalStates")),
+            operating_modes: into_map(map.remove("operatingModes")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_variants() {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "LastModified": 1_500_000_000,
+            "Controls": [{"uuidAction": "c-1", "name": "Light", "type": "Switch"}],
+            "rooms": [],
+            "globalstates": null,
+            "msInfo": {"serialNr": "504F94000000"}
+        }))
+        .unwrap();
+
+        assert_eq!(structure.last_modified, "1500000000");
+        assert_eq!(structure.controls["c-1"]["name"], "Light");
+        assert!(structure.rooms.is_empty());
+        assert!(structure.cats.is_empty());
+        assert!(structure.global_states.is_empty());
+        assert!(structure.operating_modes.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_and_precedence() {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2025-01-01 10:00:00",
+            "controls": {"c-1": {"name": "Exact"}},
+            "Controls": {"c-2": {"name": "Variant"}},
+            "rooms": {},
+            "cats": {},
+            "globalStates": {"sunrise": "g-1"},
+            "OperatingModes": {"3": "Vacation"}
+        }))
+        .unwrap();
+        assert_eq!(structure.controls.len(), 1);
+        assert!(structure.controls.contains_key("c-1"));
+        assert_eq!(structure.operating_modes["3"], "Vacation");
+
+        let serialized = serde_json::to_value(&structure).unwrap();
+        assert_eq!(serialized["globalStates"]["sunrise"], "g-1");
+        let reparsed: LoxoneStructure = serde_json::from_value(serialized).unwrap();
+        assert_eq!(reparsed.global_states, structure.global_states);
+
+        assert!(serde_json::from_value::<LoxoneStructure>(json!([])).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Tenant-scoped client wrapper
+//!
+//! Wraps a shared [`LoxoneClient`] so every consumer (tools, resources,
+//! background jobs) only sees the structure of one tenant and can only read
+//! or command that tenant's controls. Connection management stays with the
+//! wrapped client.
+
+use crate::client::{LoxoneClient, LoxoneResponse, LoxoneStructure};
+use crate::error::{LoxoneError, Result};
+use crate::security::tenants::TenantScope;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Client restricted to the rooms and devices of one tenant
+pub struct TenantScopedClient {
+    inner: Arc<dyn LoxoneClient>,
+    scope: Arc<TenantScope>,
+}
+
+impl TenantScopedClient {
+    /// Restrict `inner` to `scope`
+    pub fn new(inner: Arc<dyn LoxoneClient>, scope: TenantScope) -> Self {
+        Self {
+            inner,
+            scope: Arc::new(scope),
+        }
+    }
+
+    /// Scope applied to this client
+    pub fn scope(&self) -> &TenantScope {
+        &self.scope
+    }
+
+    /// Reject UUIDs outside the tenant's controls and states
+    async fn ensure_allowed(&self, uuids: &[String]) -> Result<()> {
+        let structure = self.inner.get_structure().await?;
+        let allowed = self.scope.allowed_uuids(&structure);
+        if let Some(uuid) = uuids.iter().find(|uuid| !allowed.contains(*uuid)) {
+            return Err(LoxoneError::permission_denied(format!(
+                "Device {uuid} is not part of tenant '{}'",
+                self.scope.tenant().id
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoxoneClient for TenantScopedClient {
+    async fn connect(&mut self) -> Result<()> {
+        Err(LoxoneError::config(
+            "Tenant-scoped clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn is_connected(&self) -> Result<bool> {
+        self.inner.is_connected().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        Err(LoxoneError::config(
+            "Tenant-scoped clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn send_command(&self, uuid: &str, command: &str) -> Result<LoxoneResponse> {
+        self.ensure_allowed(&[uuid.to_string()]).await?;
+        self.inner.send_command(uuid, command).await
+    }
+
+    async fn send_parallel_commands(
+        &self,
+        commands: Vec<(String, String)>,
+    ) -> Result<Vec<Result<LoxoneResponse>>> {
+        let uuids: Vec<String> = commands.iter().map(|(uuid, _)| uuid.clone()).collect();
+        self.ensure_allowed(&uuids).await?;
+        self.inner.send_parallel_commands(commands).await
+    }
+
+    async fn get_structure(&self) -> Result<LoxoneStructure> {
+        let structure = self.inner.get_structure().await?;
+        Ok(self.scope.filter_structure(&structure))
+    }
+
+    async fn get_device_states(
+        &self,
+        uuids: &[String],
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        self.ensure_allowed(uuids).await?;
+        self.inner.get_device_states(uuids).await
+    }
+
+    async fn get_state_values(
+        &self,
+        state_uuids: &[String],
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        self.ensure_allowed(state_uuids).await?;
+        self.inner.get_state_values(state_uuids).await
+    }
+
+    async fn get_system_info(&self) -> Result<serde_json::Value> {
+        self.inner.get_system_info().await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        self.inner.get_miniserver_time().await
+    }
+
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        // Operating modes switch the whole installation
+        Err(LoxoneError::permission_denied(format!(
+            "Calendar command {command} is not available to tenant '{}'",
+            self.scope.tenant().id
+        )))
+    }
+
+    async fn get_air_devices(&self) -> Result<serde_json::Value> {
+        self.inner.get_air_devices().await
+    }
+
+    async fn get_device_status(&self) -> Result<serde_json::Value> {
+        self.inner.get_device_status().await
+    }
+
+    async fn get_weather_forecast(&self) -> Result<serde_json::Value> {
+        self.inner.get_weather_forecast().await
+    }
+
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.download_file(path).await
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,167 @@
+//! Clock drift between this host and the Miniserver
+//!
+//! Token lifetimes (`validUntil`) are issued in Miniserver time, counted in
+//! seconds since 2009-01-01. When the host clock and the Miniserver clock
+//! disagree, tokens look expired while still valid (or the other way round)
+//! and authentication starts failing in confusing ways.
+//!
+//! [`measure`] reads the Miniserver clock, records the offset process-wide
+//! and logs a warning beyond [`DEFAULT_DRIFT_WARNING`]. Token expiry checks
+//! use [`miniserver_now`], which applies the last measured offset.
+//!
+//! The Miniserver reports local wall-clock time without a zone. Time zone
+//! offsets are whole quarter hours, so the quarter hour nearest to the raw
+//! difference is attributed to the time zone and the remainder is drift.
+
+use crate::client::LoxoneClient;
+use crate::error::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Unix timestamp of the Loxone epoch (2009-01-01 00:00:00 UTC)
+pub const LOXONE_EPOCH_OFFSET: i64 = 1_230_768_000;
+
+/// Drift beyond which a warning is logged and the health check degrades
+pub const DEFAULT_DRIFT_WARNING: Duration = Duration::from_secs(30);
+
+const QUARTER_HOUR_MS: i64 = 15 * 60 * 1000;
+
+/// Last measured offset (Miniserver clock minus host clock) in milliseconds
+static OFFSET_MS: AtomicI64 = AtomicI64::new(0);
+static MEASURED: AtomicBool = AtomicBool::new(false);
+
+/// One clock comparison against the Miniserver
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftSample {
+    /// Miniserver clock minus host clock in milliseconds (time zone removed)
+    pub offset_ms: i64,
+    /// Time zone offset of the Miniserver's wall clock in minutes
+    pub timezone_offset_minutes: i64,
+    /// Wall-clock time reported by the Miniserver
+    pub miniserver_time: NaiveDateTime,
+    /// Host time at the midpoint of the request
+    pub measured_at: DateTime<Utc>,
+    /// Request round trip, the measurement uncertainty
+    pub round_trip_ms: u64,
+}
+
+impl DriftSample {
+    /// Absolute drift
+    pub fn drift(&self) -> Duration {
+        Duration::from_millis(self.offset_ms.unsigned_abs())
+    }
+
+    /// Whether the drift exceeds `threshold`
+    pub fn exceeds(&self, threshold: Duration) -> bool {
+        self.drift() > threshold
+    }
+}
+
+/// Split a raw wall-clock difference into time zone offset and drift (both ms)
+pub fn split_offset(raw_ms: i64) -> (i64, i64) {
+    let zone_ms = (raw_ms as f64 / QUARTER_HOUR_MS as f64).round() as i64 * QUARTER_HOUR_MS;
+    (zone_ms, raw_ms - zone_ms)
+}
+
+/// Compare the host clock against the Miniserver and record the offset
+pub async fn measure(client: &dyn LoxoneClient) -> Result<DriftSample> {
+    let before = Utc::now();
+    let miniserver_time = client.get_miniserver_time().await?;
+    let after = Utc::now();
+
+    let round_trip = after - before;
+    let measured_at = before + round_trip / 2;
+    let raw_ms = (miniserver_time - measured_at.naive_utc()).num_milliseconds();
+    let (zone_ms, offset_ms) = split_offset(raw_ms);
+
+    let sample = DriftSample {
+        offset_ms,
+        timezone_offset_minutes: zone_ms / 60_000,
+        miniserver_time,
+        measured_at,
+        round_trip_ms: round_trip.num_milliseconds().max(0) as u64,
+    };
+    record_offset(offset_ms);
+
+    if sample.exceeds(DEFAULT_DRIFT_WARNING) {
+        warn!(
+            "Miniserver clock differs from host clock by {:.1}s; token expiry is compensated, \
+             but check NTP on both sides",
+            offset_ms as f64 / 1000.0
+        );
+    } else {
+        debug!("Miniserver clock offset: {offset_ms} ms");
+    }
+    Ok(sample)
+}
+
+/// Record a measured offset (Miniserver clock minus host clock)
+pub fn record_offset(offset_ms: i64) {
+    OFFSET_MS.store(offset_ms, Ordering::Relaxed);
+    MEASURED.store(true, Ordering::Relaxed);
+}
+
+/// Last measured offset in milliseconds, if a measurement was taken
+pub fn offset_ms() -> Option<i64> {
+    MEASURED
+        .load(Ordering::Relaxed)
+        .then(|| OFFSET_MS.load(Ordering::Relaxed))
+}
+
+/// Current time on the Miniserver clock, estimated from the host clock
+pub fn miniserver_now() -> DateTime<Utc> {
+    miniserver_time(Utc::now())
+}
+
+/// Miniserver clock time corresponding to `host_time`
+pub fn miniserver_time(host_time: DateTime<Utc>) -> DateTime<Utc> {
+    host_time + chrono::Duration::milliseconds(offset_ms().unwrap_or(0))
+}
+
+/// Seconds since the Loxone epoch, as used by `validUntil`
+pub fn loxone_timestamp(time: DateTime<Utc>) -> i64 {
+    time.timestamp() - LOXONE_EPOCH_OFFSET
+}
+
+/// Parse the `jdev/sys/date` and `jdev/sys/time` values into a wall-clock time
+pub fn parse_miniserver_time(date: &str, time: &str) -> Option<NaiveDateTime> {
+    let date = chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+    let time = chrono::NaiveTime::parse_from_str(time.trim(), "%H:%M:%S").ok()?;
+    Some(date.and_time(time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_offset() {
+        // UTC+2 with the Miniserver 40 seconds ahead
+        assert_eq!(split_offset(7_240_000), (7_200_000, 40_000));
+        // UTC with the Miniserver 5 seconds behind
+        assert_eq!(split_offset(-5_000), (0, -5_000));
+        // UTC+5:45 (Nepal)
+        assert_eq!(split_offset(20_700_000 + 1_500), (20_700_000, 1_500));
+    }
+
+    #[tokio::test]
+    async fn test_measure_against_mock() {
+        let client = crate::mock::MockLoxoneClient::new();
+        let sample = measure(&client).await.unwrap();
+        assert!(!sample.exceeds(DEFAULT_DRIFT_WARNING));
+        assert!(offset_ms().is_some());
+
+        assert_eq!(
+            parse_miniserver_time("2025-03-01", "12:30:05"),
+            chrono::NaiveDate::from_ymd_opt(2025, 3, 1).and_then(|d| d.and_hms_opt(12, 30, 5))
+        );
+        assert!(parse_miniserver_time("01.03.2025", "12:30").is_none());
+        assert_eq!(
+            loxone_timestamp(DateTime::from_timestamp(LOXONE_EPOCH_OFFSET + 60, 0).unwrap()),
+            60
+        );
+    }
+}
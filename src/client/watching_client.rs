@@ -0,0 +1,128 @@
+//! State-watching client wrapper
+//!
+//! Wraps a [`LoxoneClient`] so that, while the current tool call asked for
+//! read-after-write confirmation (see [`PendingConfirmations`]), every
+//! `send_command` snapshots the control's states first and registers a
+//! [`StateWatch`] once the command was accepted. The confirmation
+//! middleware waits for the watches after the tool returns.
+
+use crate::client::{LoxoneClient, LoxoneResponse, LoxoneStructure, StateStream};
+use crate::error::Result;
+use crate::server::state_confirmation::{PendingConfirmations, StateWatch};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Client that watches the states of commanded controls
+pub struct WatchingClient {
+    inner: Arc<dyn LoxoneClient>,
+}
+
+impl WatchingClient {
+    /// Watch commands sent through `inner`
+    pub fn new(inner: Arc<dyn LoxoneClient>) -> Self {
+        Self { inner }
+    }
+
+    /// Snapshot the control `target` (UUID or name) before `command`
+    async fn prepare(&self, target: &str, command: &str) -> Option<StateWatch> {
+        let structure = self.inner.get_structure().await.ok()?;
+        let control = structure.controls.get(target).or_else(|| {
+            structure.controls.values().find(|control| {
+                control
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .is_some_and(|name| name.eq_ignore_ascii_case(target))
+            })
+        })?;
+        Some(StateWatch::prepare(&self.inner, control, command).await)
+    }
+}
+
+#[async_trait]
+impl LoxoneClient for WatchingClient {
+    async fn connect(&mut self) -> Result<()> {
+        Err(crate::error::LoxoneError::config(
+            "Watching clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn is_connected(&self) -> Result<bool> {
+        self.inner.is_connected().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        Err(crate::error::LoxoneError::config(
+            "Watching clients share the connection of the underlying client",
+        ))
+    }
+
+    async fn send_command(&self, uuid: &str, command: &str) -> Result<LoxoneResponse> {
+        let Some(pending) = PendingConfirmations::current().filter(|p| p.is_requested()) else {
+            return self.inner.send_command(uuid, command).await;
+        };
+        let watch = self.prepare(uuid, command).await;
+        let response = self.inner.send_command(uuid, command).await?;
+        if let Some(watch) = watch {
+            pending.push(uuid, command, watch, self.inner.clone());
+        }
+        Ok(response)
+    }
+
+    async fn get_structure(&self) -> Result<LoxoneStructure> {
+        self.inner.get_structure().await
+    }
+
+    async fn get_device_states(&self, uuids: &[String]) -> Result<HashMap<String, Value>> {
+        self.inner.get_device_states(uuids).await
+    }
+
+    async fn get_state_values(&self, state_uuids: &[String]) -> Result<HashMap<String, Value>> {
+        self.inner.get_state_values(state_uuids).await
+    }
+
+    async fn get_all_device_states_batch(&self) -> Result<HashMap<String, Value>> {
+        self.inner.get_all_device_states_batch().await
+    }
+
+    async fn get_system_info(&self) -> Result<Value> {
+        self.inner.get_system_info().await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        self.inner.get_miniserver_time().await
+    }
+
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        self.inner.send_calendar_command(command).await
+    }
+
+    async fn get_air_devices(&self) -> Result<Value> {
+        self.inner.get_air_devices().await
+    }
+
+    async fn get_device_status(&self) -> Result<Value> {
+        self.inner.get_device_status().await
+    }
+
+    async fn get_weather_forecast(&self) -> Result<Value> {
+        self.inner.get_weather_forecast().await
+    }
+
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.download_file(path).await
+    }
+
+    fn subscribe_states(self: Arc<Self>, uuids: Vec<String>) -> StateStream {
+        self.inner.clone().subscribe_states(uuids)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -7,9 +7,15 @@
 //! - Real-time device state updates
 //! - Event filtering and subscription management
 //! - Automatic reconnection with exponential backoff
+//! - Keepalive round-trip tracking and reconnection of stalled streams
 //! - Integration with HTTP clients for hybrid operation
 //! - Efficient binary message parsing for sensor data
 
+#[cfg(feature = "websocket")]
+use crate::client::ws_keepalive::{
+    KEEPALIVE_COMMAND, KeepaliveAction, KeepaliveConfig, KeepaliveStats, KeepaliveTracker,
+    is_keepalive_answer,
+};
 #[cfg(feature = "websocket")]
 use crate::client::{ClientContext, LoxoneClient, LoxoneResponse, LoxoneStructure};
 #[cfg(feature = "websocket")]
@@ -47,9 +53,15 @@ use tracing::{debug, error, info, warn};
 #[cfg(feature = "websocket")]
 use url::Url;
 
+pub use crate::client::state_stream::{LoxoneEventType, StateUpdate};
+
 #[cfg(feature = "websocket")]
 type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
 
+/// Longest time the message task holds the stream lock while waiting for a message
+#[cfg(feature = "websocket")]
+const READ_SLICE: Duration = Duration::from_millis(500);
+
 #[cfg(feature = "websocket")]
 type SubscriberList = Arc<RwLock<Vec<(mpsc::UnboundedSender<StateUpdate>, FilterType)>>>;
 
@@ -77,85 +89,6 @@ pub struct LoxoneWebSocketMessage {
     pub timestamp: Option<u64>,
 }
 
-/// Event types from Loxone WebSocket
-#[cfg(feature = "websocket")]
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
-#[serde(rename_all = "lowercase")]
-pub enum LoxoneEventType {
-    /// Device state change
-    #[default]
-    State,
-    /// Weather update
-    Weather,
-    /// Text message
-    Text,
-    /// Alarm/Security event
-    Alarm,
-    /// System event
-    System,
-    /// Binary sensor data
-    Sensor,
-    /// Unknown event type
-    Unknown(String),
-}
-
-impl From<String> for LoxoneEventType {
-    fn from(s: String) -> Self {
-        match s.to_lowercase().as_str() {
-            "state" => LoxoneEventType::State,
-            "weather" => LoxoneEventType::Weather,
-            "text" => LoxoneEventType::Text,
-            "alarm" => LoxoneEventType::Alarm,
-            "system" => LoxoneEventType::System,
-            "sensor" => LoxoneEventType::Sensor,
-            _ => LoxoneEventType::Unknown(s),
-        }
-    }
-}
-
-impl std::fmt::Display for LoxoneEventType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            LoxoneEventType::State => write!(f, "state"),
-            LoxoneEventType::Weather => write!(f, "weather"),
-            LoxoneEventType::Text => write!(f, "text"),
-            LoxoneEventType::Alarm => write!(f, "alarm"),
-            LoxoneEventType::System => write!(f, "system"),
-            LoxoneEventType::Sensor => write!(f, "sensor"),
-            LoxoneEventType::Unknown(s) => write!(f, "unknown({s})"),
-        }
-    }
-}
-
-/// State update from WebSocket
-#[cfg(feature = "websocket")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StateUpdate {
-    /// Device UUID
-    pub uuid: String,
-
-    /// State name
-    pub state: String,
-
-    /// New value
-    pub value: serde_json::Value,
-
-    /// Previous value (if available)
-    pub previous_value: Option<serde_json::Value>,
-
-    /// Event type
-    pub event_type: LoxoneEventType,
-
-    /// Timestamp
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-
-    /// Room name (if available)
-    pub room: Option<String>,
-
-    /// Device name (if available)
-    pub device_name: Option<String>,
-}
-
 /// Event subscription filter
 #[cfg(feature = "websocket")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -487,6 +420,10 @@ pub struct WebSocketStats {
 
     /// Events debounced (due to min_interval)
     pub events_debounced: u64,
+
+    /// Keepalive round trips and missed answers
+    #[serde(default)]
+    pub keepalive: KeepaliveStats,
 }
 
 /// WebSocket client for real-time Loxone communication
@@ -544,6 +481,9 @@ pub struct LoxoneWebSocketClient {
 
     /// Weather data storage
     weather_storage: Option<Arc<crate::storage::WeatherStorage>>,
+
+    /// Keepalive tracking for stall detection
+    keepalive: Arc<KeepaliveTracker>,
 }
 
 #[cfg(feature = "websocket")]
@@ -568,6 +508,7 @@ impl LoxoneWebSocketClient {
             encryption_session: Arc::new(RwLock::new(None)),
             resilience_manager: None,
             weather_storage: None,
+            keepalive: Arc::new(KeepaliveTracker::default()),
         })
     }
 
@@ -587,6 +528,16 @@ impl LoxoneWebSocketClient {
         self.reconnection_config = config;
     }
 
+    /// Configure keepalive interval and stall detection
+    pub fn set_keepalive_config(&mut self, config: KeepaliveConfig) {
+        self.keepalive = Arc::new(KeepaliveTracker::new(config));
+    }
+
+    /// Keepalive tracker, e.g. for the health report
+    pub fn keepalive(&self) -> Arc<KeepaliveTracker> {
+        self.keepalive.clone()
+    }
+
     /// Enable resilience features with message acknowledgment
     pub async fn enable_resilience(
         &mut self,
@@ -896,19 +847,38 @@ impl LoxoneWebSocketClient {
         let state_sender_clone = self.state_sender.clone();
         let stats_clone = self.stats.clone();
         let connected_clone = self.connected.clone();
+        let keepalive = self.keepalive.clone();
 
         #[allow(clippy::manual_map)]
-        let message_task = if let Some(ws_stream) = ws_stream {
+        let message_task = if let Some(ws_stream) = ws_stream.clone() {
             Some(tokio::spawn(async move {
                 loop {
+                    // Read in slices so senders (keepalive, commands) and the
+                    // reconnection task can take the stream lock in between
                     let message = {
                         use futures_util::StreamExt;
                         let mut stream = ws_stream.lock().await;
-                        stream.next().await
+                        match tokio::time::timeout(READ_SLICE, stream.next()).await {
+                            Ok(message) => message,
+                            Err(_) => continue,
+                        }
                     };
 
                     match message {
                         Some(Ok(msg)) => {
+                            let is_answer = match &msg {
+                                tokio_tungstenite::tungstenite::Message::Binary(data) => {
+                                    is_keepalive_answer(data)
+                                }
+                                tokio_tungstenite::tungstenite::Message::Pong(_) => true,
+                                _ => false,
+                            };
+                            if is_answer
+                                && let Some(rtt) = keepalive.answered(std::time::Instant::now())
+                            {
+                                debug!("Keepalive answered in {} ms", rtt.as_millis());
+                            }
+
                             // Update message statistics
                             {
                                 let mut stats_guard = stats_clone.write().await;
@@ -950,7 +920,57 @@ impl LoxoneWebSocketClient {
             None
         };
 
-        // Task 3: Reconnection and token refresh manager (if enabled)
+        // Task 3: Keepalive sender and stall detection
+        #[allow(clippy::manual_map)]
+        let keepalive_task = if let Some(ws_stream) = ws_stream {
+            let keepalive = self.keepalive.clone();
+            let connected = self.connected.clone();
+
+            Some(tokio::spawn(async move {
+                let config = keepalive.config().clone();
+                loop {
+                    sleep(config.interval).await;
+                    if !*connected.read().await {
+                        continue;
+                    }
+
+                    match keepalive.tick(std::time::Instant::now()) {
+                        KeepaliveAction::Wait => {}
+                        KeepaliveAction::Stalled => {
+                            warn!(
+                                "WebSocket stalled: {} keepalives unanswered, reconnecting",
+                                config.max_missed
+                            );
+                            keepalive.record_stall();
+                            *connected.write().await = false;
+                        }
+                        KeepaliveAction::Send => {
+                            // A stream that cannot even be written counts as missed
+                            keepalive.sent(std::time::Instant::now());
+                            let sent = tokio::time::timeout(config.timeout, async {
+                                ws_stream
+                                    .lock()
+                                    .await
+                                    .send(tokio_tungstenite::tungstenite::Message::Text(
+                                        KEEPALIVE_COMMAND.to_string(),
+                                    ))
+                                    .await
+                            })
+                            .await;
+                            match sent {
+                                Ok(Ok(())) => debug!("Sent WebSocket keepalive"),
+                                Ok(Err(e)) => warn!("Failed to send WebSocket keepalive: {}", e),
+                                Err(_) => warn!("Timed out sending WebSocket keepalive"),
+                            }
+                        }
+                    }
+                }
+            }))
+        } else {
+            None
+        };
+
+        // Task 4: Reconnection and token refresh manager (if enabled)
         let reconnection_task = if self.reconnection_config.enabled {
             let connected = self.connected.clone();
             let base_url = self.base_url.clone();
@@ -1099,6 +1119,9 @@ impl LoxoneWebSocketClient {
         if let Some(message_task) = message_task {
             handles.push(message_task);
         }
+        if let Some(keepalive_task) = keepalive_task {
+            handles.push(keepalive_task);
+        }
         if let Some(reconnection_task) = reconnection_task {
             handles.push(reconnection_task);
         }
@@ -1327,7 +1350,9 @@ impl LoxoneWebSocketClient {
 
     /// Get connection statistics
     pub async fn get_stats(&self) -> WebSocketStats {
-        self.stats.read().await.clone()
+        let mut stats = self.stats.read().await.clone();
+        stats.keepalive = self.keepalive.stats();
+        stats
     }
 
     /// Clear all subscribers
@@ -2608,6 +2633,78 @@ impl LoxoneClient for LoxoneWebSocketClient {
         Ok(*self.connected.read().await)
     }
 
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        if let Some(http_client) = &self.http_client {
+            http_client.get_miniserver_time().await
+        } else {
+            Err(LoxoneError::connection(
+                "Miniserver time not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        if let Some(http_client) = &self.http_client {
+            http_client.send_calendar_command(command).await
+        } else {
+            Err(LoxoneError::connection(
+                "Calendar commands not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn get_air_devices(&self) -> Result<serde_json::Value> {
+        if let Some(http_client) = &self.http_client {
+            http_client.get_air_devices().await
+        } else {
+            Err(LoxoneError::connection(
+                "Air device diagnostics not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn get_device_status(&self) -> Result<serde_json::Value> {
+        if let Some(http_client) = &self.http_client {
+            http_client.get_device_status().await
+        } else {
+            Err(LoxoneError::connection(
+                "Device status not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn get_weather_forecast(&self) -> Result<serde_json::Value> {
+        if let Some(http_client) = &self.http_client {
+            http_client.get_weather_forecast().await
+        } else {
+            Err(LoxoneError::connection(
+                "Weather forecast not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        if let Some(http_client) = &self.http_client {
+            http_client.download_file(path).await
+        } else {
+            Err(LoxoneError::connection(
+                "File download not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    fn subscribe_states(
+        self: Arc<Self>,
+        uuids: Vec<String>,
+    ) -> crate::client::state_stream::StateStream {
+        Box::pin(async_stream::stream! {
+            let mut receiver = self.subscribe_to_devices(uuids.into_iter().collect()).await;
+            while let Some(update) = receiver.recv().await {
+                yield update;
+            }
+        })
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -0,0 +1,239 @@
+//! Keepalive tracking for the Miniserver WebSocket
+//!
+//! A WebSocket can stall while the TCP connection stays up (e.g. after a
+//! Miniserver reboot behind a NAT, or a half-open connection): no error is
+//! raised and state updates silently stop. The client therefore sends the
+//! Loxone `keepalive` command periodically and measures the round trip to
+//! the answer. After `max_missed` unanswered keepalives in a row the stream
+//! is considered stalled and the client reconnects.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Keepalive command understood by the Miniserver
+pub const KEEPALIVE_COMMAND: &str = "keepalive";
+
+/// Header identifier of the Miniserver's keepalive answer
+const KEEPALIVE_IDENTIFIER: u8 = 6;
+
+/// Keepalive configuration
+#[derive(Debug, Clone)]
+pub struct KeepaliveConfig {
+    /// Interval between keepalives
+    pub interval: Duration,
+    /// Time to wait for the answer before counting a keepalive as missed
+    pub timeout: Duration,
+    /// Consecutive missed keepalives before the stream counts as stalled
+    pub max_missed: u32,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            max_missed: 3,
+        }
+    }
+}
+
+/// Keepalive statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeepaliveStats {
+    /// Keepalives sent
+    pub sent: u64,
+    /// Answers received
+    pub received: u64,
+    /// Keepalives that were not answered in time
+    pub missed_total: u64,
+    /// Unanswered keepalives in a row
+    pub missed_consecutive: u32,
+    /// Round trip of the last answer
+    pub last_rtt_ms: Option<u64>,
+    /// Average round trip over all answers
+    pub avg_rtt_ms: Option<f64>,
+    /// Slowest round trip observed
+    pub max_rtt_ms: Option<u64>,
+    /// When the last answer arrived
+    pub last_answer: Option<chrono::DateTime<chrono::Utc>>,
+    /// Reconnects triggered by a stalled stream
+    pub stall_reconnects: u64,
+}
+
+/// What the keepalive task should do on a tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepaliveAction {
+    /// Send the next keepalive
+    Send,
+    /// A keepalive is still within its timeout
+    Wait,
+    /// Too many keepalives went unanswered; reconnect
+    Stalled,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    stats: KeepaliveStats,
+    outstanding: Option<Instant>,
+    rtt_sum_ms: u64,
+}
+
+/// Tracks keepalives sent on one WebSocket connection
+#[derive(Debug)]
+pub struct KeepaliveTracker {
+    config: KeepaliveConfig,
+    state: Mutex<TrackerState>,
+}
+
+impl Default for KeepaliveTracker {
+    fn default() -> Self {
+        Self::new(KeepaliveConfig::default())
+    }
+}
+
+impl KeepaliveTracker {
+    pub fn new(config: KeepaliveConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(TrackerState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &KeepaliveConfig {
+        &self.config
+    }
+
+    /// Decide what to do at `now`, counting an overdue keepalive as missed
+    pub fn tick(&self, now: Instant) -> KeepaliveAction {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(sent_at) = state.outstanding {
+            if now.duration_since(sent_at) < self.config.timeout {
+                return KeepaliveAction::Wait;
+            }
+            state.outstanding = None;
+            state.stats.missed_total += 1;
+            state.stats.missed_consecutive += 1;
+        }
+        if state.stats.missed_consecutive >= self.config.max_missed {
+            KeepaliveAction::Stalled
+        } else {
+            KeepaliveAction::Send
+        }
+    }
+
+    /// Record that a keepalive was sent at `now`
+    pub fn sent(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.stats.sent += 1;
+        state.outstanding = Some(now);
+    }
+
+    /// Record an answer received at `now`; returns the round trip
+    pub fn answered(&self, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let sent_at = state.outstanding.take()?;
+        let rtt = now.duration_since(sent_at);
+        let rtt_ms = rtt.as_millis() as u64;
+
+        state.rtt_sum_ms += rtt_ms;
+        let rtt_sum_ms = state.rtt_sum_ms;
+        let stats = &mut state.stats;
+        stats.received += 1;
+        stats.missed_consecutive = 0;
+        stats.last_rtt_ms = Some(rtt_ms);
+        stats.avg_rtt_ms = Some(rtt_sum_ms as f64 / stats.received as f64);
+        stats.max_rtt_ms = Some(stats.max_rtt_ms.map_or(rtt_ms, |max| max.max(rtt_ms)));
+        stats.last_answer = Some(chrono::Utc::now());
+        Some(rtt)
+    }
+
+    /// Record a stall; the connection is about to be re-established
+    pub fn record_stall(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.outstanding = None;
+        state.stats.missed_consecutive = 0;
+        state.stats.stall_reconnects += 1;
+    }
+
+    pub fn stats(&self) -> KeepaliveStats {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .stats
+            .clone()
+    }
+
+    /// Whether the stream currently has unanswered keepalives
+    pub fn is_degraded(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .stats
+            .missed_consecutive
+            > 0
+    }
+}
+
+/// Whether a binary message is the Miniserver's keepalive answer
+///
+/// The answer is a bare message header (`0x03`, identifier `6`).
+pub fn is_keepalive_answer(data: &[u8]) -> bool {
+    data.len() >= 8 && data[0] == 0x03 && data[1] == KEEPALIVE_IDENTIFIER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_and_stall() {
+        let tracker = KeepaliveTracker::new(KeepaliveConfig {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            max_missed: 2,
+        });
+        let start = Instant::now();
+
+        assert_eq!(tracker.tick(start), KeepaliveAction::Send);
+        tracker.sent(start);
+        assert_eq!(
+            tracker.tick(start + Duration::from_secs(5)),
+            KeepaliveAction::Wait
+        );
+        assert_eq!(
+            tracker.answered(start + Duration::from_millis(120)),
+            Some(Duration::from_millis(120))
+        );
+        // Answers without an outstanding keepalive are ignored
+        assert_eq!(tracker.answered(start + Duration::from_secs(1)), None);
+
+        let t = start + Duration::from_secs(30);
+        tracker.sent(t);
+        assert_eq!(
+            tracker.tick(t + Duration::from_secs(30)),
+            KeepaliveAction::Send
+        );
+        assert!(tracker.is_degraded());
+        tracker.sent(t + Duration::from_secs(30));
+        assert_eq!(
+            tracker.tick(t + Duration::from_secs(60)),
+            KeepaliveAction::Stalled
+        );
+
+        let stats = tracker.stats();
+        assert_eq!((stats.sent, stats.received, stats.missed_total), (3, 1, 2));
+        assert_eq!(stats.last_rtt_ms, Some(120));
+
+        tracker.record_stall();
+        assert!(!tracker.is_degraded());
+        assert_eq!(tracker.stats().stall_reconnects, 1);
+    }
+
+    #[test]
+    fn test_keepalive_answer_header() {
+        assert!(is_keepalive_answer(&[0x03, 0x06, 0, 0, 0, 0, 0, 0]));
+        assert!(!is_keepalive_answer(&[0x03, 0x02, 0, 0, 8, 0, 0, 0]));
+        assert!(!is_keepalive_answer(&[0x03, 0x06]));
+    }
+}
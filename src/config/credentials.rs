@@ -7,6 +7,7 @@ use crate::config::CredentialStore;
 use crate::error::{LoxoneError, Result};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "infisical")]
 use crate::config::infisical_client::{InfisicalClient, create_authenticated_client};
@@ -28,6 +29,57 @@ pub struct LoxoneCredentials {
     pub public_key: Option<String>,
 }
 
+/// Default mount point of Docker/Kubernetes secrets
+pub const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+
+/// Read a configuration value from the environment or a mounted secret
+///
+/// Lookup order for e.g. `LOXONE_PASS`:
+/// 1. the `LOXONE_PASS` environment variable
+/// 2. the file named by `LOXONE_PASS_FILE`
+/// 3. `loxone_pass` in the secrets directory (`LOXONE_SECRETS_DIR`,
+///    default `/run/secrets`)
+///
+/// Trailing newlines are stripped from file contents. A `*_FILE` variable
+/// pointing at an unreadable file is an error rather than a silent fallback.
+pub fn env_or_secret(name: &str) -> Result<Option<String>> {
+    let secrets_dir = env::var("LOXONE_SECRETS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_SECRETS_DIR));
+    resolve_secret(name, |var| env::var(var).ok(), &secrets_dir)
+}
+
+fn resolve_secret(
+    name: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    secrets_dir: &Path,
+) -> Result<Option<String>> {
+    if let Some(value) = lookup(name) {
+        return Ok(Some(value));
+    }
+
+    let read = |path: &Path| {
+        std::fs::read_to_string(path)
+            .map(|content| content.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    let file_var = format!("{name}_FILE");
+    if let Some(path) = lookup(&file_var) {
+        return read(Path::new(&path)).map(Some).map_err(|e| {
+            LoxoneError::credentials(format!("Failed to read {file_var} ({path}): {e}"))
+        });
+    }
+
+    let mounted = secrets_dir.join(name.to_lowercase());
+    match read(&mounted) {
+        Ok(value) => {
+            tracing::debug!("Loaded {name} from {}", mounted.display());
+            Ok(Some(value))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
 /// Credential manager for secure storage and retrieval
 pub struct CredentialManager {
     store: CredentialStore,
@@ -178,14 +230,14 @@ impl CredentialManager {
     }
 
     async fn get_environment(&self) -> Result<LoxoneCredentials> {
-        let username = env::var(Self::USERNAME_KEY).map_err(|_| {
+        let username = env_or_secret(Self::USERNAME_KEY)?.ok_or_else(|| {
             LoxoneError::credentials(format!(
                 "{} environment variable not set",
                 Self::USERNAME_KEY
             ))
         })?;
 
-        let password = env::var(Self::PASSWORD_KEY).map_err(|_| {
+        let password = env_or_secret(Self::PASSWORD_KEY)?.ok_or_else(|| {
             LoxoneError::credentials(format!(
                 "{} environment variable not set",
                 Self::PASSWORD_KEY
@@ -193,14 +245,14 @@ impl CredentialManager {
         })?;
 
         // Load API key using the standard variable name
-        let api_key = env::var("LOXONE_API_KEY").ok();
+        let api_key = env_or_secret(Self::API_KEY_KEY)?;
 
         Ok(LoxoneCredentials {
             username,
             password,
             api_key,
             #[cfg(feature = "crypto-openssl")]
-            public_key: env::var("LOXONE_PUBLIC_KEY").ok(),
+            public_key: env_or_secret("LOXONE_PUBLIC_KEY")?,
         })
     }
 }
@@ -495,16 +547,16 @@ pub async fn create_best_credential_manager() -> Result<MultiBackendCredentialMa
     let mut stores = Vec::new();
     let mut infisical_configured = false;
     // Check if environment variables for Loxone are configured
-    let env_configured =
-        std::env::var("LOXONE_USER").is_ok() && std::env::var("LOXONE_PASS").is_ok();
+    let env_configured = matches!(env_or_secret("LOXONE_USER"), Ok(Some(_)))
+        && matches!(env_or_secret("LOXONE_PASS"), Ok(Some(_)));
 
     // Try Infisical first if configured (preferred for team environments)
     #[cfg(feature = "infisical")]
     {
-        if let (Ok(project_id), Ok(client_id), Ok(client_secret)) = (
+        if let (Ok(project_id), Ok(client_id), Ok(Some(client_secret))) = (
             std::env::var("INFISICAL_PROJECT_ID"),
             std::env::var("INFISICAL_CLIENT_ID"),
-            std::env::var("INFISICAL_CLIENT_SECRET"),
+            env_or_secret("INFISICAL_CLIENT_SECRET"),
         ) {
             let environment =
                 std::env::var("INFISICAL_ENVIRONMENT").unwrap_or_else(|_| "dev".to_string());
@@ -551,7 +603,52 @@ pub async fn create_best_credential_manager() -> Result<MultiBackendCredentialMa
         tracing::info!("   export LOXONE_USER=\"your-username\"");
         tracing::info!("   export LOXONE_PASS=\"your-password\"");
         tracing::info!("   export LOXONE_HOST=\"192.168.1.100\"");
+        tracing::info!("   or mount secrets via LOXONE_USER_FILE / LOXONE_PASS_FILE");
     }
 
     Ok(manager)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_resolve_secret_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let pass_file = dir.path().join("custom_pass");
+        std::fs::write(&pass_file, "from-file\n").unwrap();
+        std::fs::write(dir.path().join("loxone_user"), "mounted-user\n").unwrap();
+
+        let mut vars = HashMap::from([(
+            "LOXONE_PASS_FILE".to_string(),
+            pass_file.display().to_string(),
+        )]);
+        let lookup = |vars: &HashMap<String, String>, name: &str| vars.get(name).cloned();
+
+        let resolve = |vars: &HashMap<String, String>, name: &str| {
+            resolve_secret(name, |v| lookup(vars, v), dir.path()).unwrap()
+        };
+        assert_eq!(resolve(&vars, "LOXONE_PASS").as_deref(), Some("from-file"));
+        assert_eq!(
+            resolve(&vars, "LOXONE_USER").as_deref(),
+            Some("mounted-user")
+        );
+        assert_eq!(resolve(&vars, "LOXONE_API_KEY"), None);
+
+        vars.insert("LOXONE_PASS".to_string(), "from-env".to_string());
+        assert_eq!(resolve(&vars, "LOXONE_PASS").as_deref(), Some("from-env"));
+    }
+
+    #[test]
+    fn test_missing_secret_file_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = resolve_secret(
+            "LOXONE_PASS",
+            |v| (v == "LOXONE_PASS_FILE").then(|| "/nonexistent/loxone_pass".to_string()),
+            dir.path(),
+        );
+        assert!(result.is_err());
+    }
+}
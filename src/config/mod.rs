@@ -3,6 +3,8 @@
 pub mod credential_registry;
 pub mod credentials;
 pub mod master_key;
+pub mod room_metadata;
+pub mod wasmcloud;
 
 #[cfg(target_os = "macos")]
 pub mod security_keychain;
@@ -11,6 +13,8 @@ pub mod security_keychain;
 pub mod infisical_client;
 
 use crate::error::{LoxoneError, Result};
+use crate::server::tool_middleware::ToolMiddlewareConfig;
+use crate::server::tool_timeouts::ToolTimeoutConfig;
 use serde::{Deserialize, Serialize};
 use std::{env, time::Duration};
 use url::Url;
@@ -50,6 +54,50 @@ pub struct ServerConfig {
 
     /// Feature flags
     pub features: FeatureConfig,
+
+    /// Room metadata (floor, orientation, area, window facades)
+    #[serde(default)]
+    pub room_metadata: room_metadata::RoomMetadataConfig,
+
+    /// Audit log of device commands
+    #[serde(default)]
+    pub command_history: crate::services::command_history::CommandHistoryConfig,
+
+    /// Scheduled, encrypted backups of the Miniserver program
+    #[serde(default)]
+    pub program_backup: crate::services::program_backup::ProgramBackupConfig,
+
+    /// Protective rules for awnings and skylights in wind and rain
+    #[serde(default)]
+    pub weather_protection: crate::services::weather_protection::WeatherProtectionConfig,
+
+    /// Miniserver request budget shared by tools and background jobs
+    #[serde(default)]
+    pub api_budget: crate::client::api_budget::ApiBudgetConfig,
+
+    /// UPS and power-fail monitoring
+    #[serde(default)]
+    pub power_monitor: crate::services::power_monitor::PowerMonitorConfig,
+
+    /// Request, command and error totals kept across restarts
+    #[serde(default)]
+    pub lifetime_metrics: crate::monitoring::lifetime_metrics::LifetimeMetricsConfig,
+
+    /// Tool groups and background jobs switched on or off for this installation
+    #[serde(default)]
+    pub feature_flags: crate::server::feature_flags::FeatureFlagsConfig,
+
+    /// Daily token and cost limits for sampling requests
+    #[serde(default)]
+    pub sampling_budget: crate::sampling::budget::SamplingBudgetConfig,
+
+    /// When tools that disarm, unlock or open the house ask for consent
+    #[serde(default)]
+    pub consent: crate::mcp_consent::ConsentConfig,
+
+    /// File the named room snapshots are kept in
+    #[serde(default)]
+    pub scene_snapshots: crate::services::scene_snapshot::SceneSnapshotConfig,
 }
 
 /// Loxone Miniserver configuration
@@ -150,6 +198,22 @@ pub struct ToolConfig {
 
     /// Maximum devices to return in listings
     pub max_devices_per_query: usize,
+
+    /// Per-tool execution timeouts
+    #[serde(default)]
+    pub timeouts: ToolTimeoutConfig,
+
+    /// Built-in tool middlewares
+    #[serde(default)]
+    pub middleware: ToolMiddlewareConfig,
+
+    /// Language of tool titles and descriptions (defaults to `LOXONE_LOCALE`)
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Unit system of returned values (defaults to `LOXONE_UNITS`, else metric)
+    #[serde(default)]
+    pub units: Option<crate::services::UnitSystem>,
 }
 
 /// Mock server configuration
@@ -321,6 +385,10 @@ impl Default for ToolConfig {
             enable_climate: true,
             enable_weather: true,
             max_devices_per_query: 100,
+            timeouts: ToolTimeoutConfig::default(),
+            middleware: ToolMiddlewareConfig::default(),
+            locale: None,
+            units: None,
         }
     }
 }
@@ -507,13 +575,15 @@ impl ServerConfig {
                 .map_err(|e| LoxoneError::config(format!("Invalid LOXONE_HOST: {e}")))?;
         }
 
-        if let Ok(username) = env::var("LOXONE_USER") {
+        if let Some(username) = credentials::env_or_secret("LOXONE_USER")? {
             config.loxone.username = username;
         }
 
         // Validate that password is available if username is set via environment
         // Note: Password is not stored in config for security, but we validate it exists
-        if !config.loxone.username.is_empty() && env::var("LOXONE_PASS").is_err() {
+        if !config.loxone.username.is_empty()
+            && !matches!(credentials::env_or_secret("LOXONE_PASS"), Ok(Some(_)))
+        {
             tracing::warn!(
                 "LOXONE_USER is set but LOXONE_PASS is missing. Credential manager will be used instead."
             );
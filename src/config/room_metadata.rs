@@ -0,0 +1,322 @@
+//! Room metadata configuration
+//!
+//! The Loxone structure file only knows room names. Floor, orientation,
+//! floor area and which facades have windows are configured in a TOML file
+//! keyed by room name or UUID:
+//!
+//! ```toml
+//! [rooms."Living Room"]
+//! floor = 0
+//! orientation = "south"
+//! area_m2 = 32.5
+//! window_facades = ["south", "west"]
+//! ```
+//!
+//! Entries are validated against a schema before use, so typos and
+//! out-of-range values fail at startup instead of skewing shading or climate
+//! decisions later.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::schema_validation::SchemaConstraint;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Compass direction of a room or facade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Orientation {
+    #[serde(alias = "n")]
+    North,
+    #[serde(alias = "ne")]
+    NorthEast,
+    #[serde(alias = "e")]
+    East,
+    #[serde(alias = "se")]
+    SouthEast,
+    #[serde(alias = "s")]
+    South,
+    #[serde(alias = "sw")]
+    SouthWest,
+    #[serde(alias = "w")]
+    West,
+    #[serde(alias = "nw")]
+    NorthWest,
+}
+
+/// Accepted orientation spellings (canonical names and abbreviations)
+const ORIENTATION_VALUES: &[&str] = &[
+    "north",
+    "north-east",
+    "east",
+    "south-east",
+    "south",
+    "south-west",
+    "west",
+    "north-west",
+    "n",
+    "ne",
+    "e",
+    "se",
+    "s",
+    "sw",
+    "w",
+    "nw",
+];
+
+impl Orientation {
+    /// Parse a canonical name or abbreviation (case-insensitive)
+    pub fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(json!(value.trim().to_lowercase())).ok()
+    }
+
+    /// Compass azimuth in degrees (north = 0, east = 90)
+    pub fn azimuth(&self) -> f64 {
+        match self {
+            Self::North => 0.0,
+            Self::NorthEast => 45.0,
+            Self::East => 90.0,
+            Self::SouthEast => 135.0,
+            Self::South => 180.0,
+            Self::SouthWest => 225.0,
+            Self::West => 270.0,
+            Self::NorthWest => 315.0,
+        }
+    }
+
+    /// Whether a facade with this orientation receives direct sun from
+    /// `sun_azimuth` (within 90° of the facade normal)
+    pub fn faces(&self, sun_azimuth: f64) -> bool {
+        let diff = (self.azimuth() - sun_azimuth).rem_euclid(360.0);
+        diff.min(360.0 - diff) < 90.0
+    }
+}
+
+/// Metadata for one room
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoomMetadata {
+    /// Floor number (0 = ground floor, negative = basement)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub floor: Option<i32>,
+    /// Main orientation of the room
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<Orientation>,
+    /// Floor area in square metres
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub area_m2: Option<f64>,
+    /// Facades with windows, used for shading
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub window_facades: Vec<Orientation>,
+}
+
+impl RoomMetadata {
+    /// Whether the room has windows on a facade facing `orientation`
+    pub fn has_facade(&self, orientation: Orientation) -> bool {
+        self.window_facades.contains(&orientation)
+            || (self.window_facades.is_empty() && self.orientation == Some(orientation))
+    }
+}
+
+/// Metadata for all configured rooms
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoomMetadataConfig {
+    /// Metadata by room name or UUID
+    #[serde(default)]
+    pub rooms: HashMap<String, RoomMetadata>,
+}
+
+impl RoomMetadataConfig {
+    /// Parse and validate room metadata from TOML
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let raw: toml::Value = toml::from_str(content)
+            .map_err(|e| LoxoneError::config(format!("Invalid room metadata file: {e}")))?;
+        let raw = serde_json::to_value(raw).map_err(LoxoneError::Json)?;
+        Self::validate(&raw)?;
+        let config: Self = serde_json::from_value(raw)
+            .map_err(|e| LoxoneError::config(format!("Invalid room metadata: {e}")))?;
+
+        let mut seen = HashMap::new();
+        for key in config.rooms.keys() {
+            if let Some(previous) = seen.insert(key.to_lowercase(), key) {
+                return Err(LoxoneError::config(format!(
+                    "Room '{key}' is configured twice (also as '{previous}')"
+                )));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load and validate room metadata from a TOML file
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            LoxoneError::config(format!(
+                "Failed to read room metadata file {}: {e}",
+                path.display()
+            ))
+        })?;
+        Self::from_toml(&content)
+    }
+
+    /// Validate raw metadata against the room metadata schema
+    fn validate(raw: &Value) -> Result<()> {
+        let Some(rooms) = raw.get("rooms") else {
+            return Ok(());
+        };
+        let rooms = rooms
+            .as_object()
+            .ok_or_else(|| LoxoneError::config("'rooms' must be a table of room entries"))?;
+
+        for (room, entry) in rooms {
+            let constraints = Self::schema(room);
+            let field = |name: &str| entry.get(name).cloned().unwrap_or(Value::Null);
+            let invalid = |e: LoxoneError| LoxoneError::config(e.to_string());
+
+            constraints
+                .floor
+                .validate(&field("floor"))
+                .map_err(invalid)?;
+            constraints
+                .orientation
+                .validate(&field("orientation"))
+                .map_err(invalid)?;
+            constraints
+                .area
+                .validate(&field("area_m2"))
+                .map_err(invalid)?;
+            match field("window_facades") {
+                Value::Null => {}
+                Value::Array(facades) => {
+                    for facade in &facades {
+                        constraints.facade.validate(facade).map_err(invalid)?;
+                    }
+                }
+                other => {
+                    return Err(LoxoneError::config(format!(
+                        "Field 'rooms.{room}.window_facades' must be a list, got: {other}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn schema(room: &str) -> RoomSchema {
+        let number = |field: &str, description: &str, min: f64, max: f64| SchemaConstraint {
+            field: format!("rooms.{room}.{field}"),
+            field_type: "number".to_string(),
+            required: false,
+            pattern: None,
+            pattern_description: Some(description.to_string()),
+            min_length: None,
+            max_length: None,
+            min_value: Some(min),
+            max_value: Some(max),
+            enum_values: None,
+            examples: Vec::new(),
+            default: None,
+        };
+        let orientation = |field: &str| SchemaConstraint {
+            field: format!("rooms.{room}.{field}"),
+            field_type: "string".to_string(),
+            required: false,
+            pattern: None,
+            pattern_description: Some("Compass direction".to_string()),
+            min_length: None,
+            max_length: None,
+            min_value: None,
+            max_value: None,
+            enum_values: Some(ORIENTATION_VALUES.iter().map(|v| v.to_string()).collect()),
+            examples: vec![json!("south"), json!("north-west")],
+            default: None,
+        };
+        RoomSchema {
+            floor: number("floor", "Floor number", -5.0, 200.0),
+            orientation: orientation("orientation"),
+            area: number("area_m2", "Floor area in m²", 0.5, 10_000.0),
+            facade: orientation("window_facades"),
+        }
+    }
+
+    /// Metadata for a room by UUID or name (case-insensitive)
+    pub fn get(&self, uuid: &str, name: Option<&str>) -> Option<&RoomMetadata> {
+        self.rooms.get(uuid).or_else(|| {
+            self.rooms.iter().find_map(|(key, metadata)| {
+                (key.eq_ignore_ascii_case(uuid)
+                    || name.is_some_and(|name| key.eq_ignore_ascii_case(name)))
+                .then_some(metadata)
+            })
+        })
+    }
+
+    /// Metadata of a structure room entry as JSON (`null` when unconfigured)
+    pub fn to_json(&self, uuid: &str, room: Option<&Value>) -> Value {
+        let name = room.and_then(|r| r.get("name")).and_then(Value::as_str);
+        self.get(uuid, name)
+            .and_then(|metadata| serde_json::to_value(metadata).ok())
+            .unwrap_or(Value::Null)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty()
+    }
+}
+
+struct RoomSchema {
+    floor: SchemaConstraint,
+    orientation: SchemaConstraint,
+    area: SchemaConstraint,
+    facade: SchemaConstraint,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOMS: &str = r#"
+        [rooms."Living Room"]
+        floor = 0
+        orientation = "south"
+        area_m2 = 32.5
+        window_facades = ["s", "west"]
+
+        [rooms.room-2]
+        floor = 1
+        orientation = "north"
+    "#;
+
+    #[test]
+    fn test_parse_and_lookup() {
+        let config = RoomMetadataConfig::from_toml(ROOMS).unwrap();
+        let living = config.get("room-1", Some("living room")).unwrap();
+        assert_eq!(living.floor, Some(0));
+        assert!(living.has_facade(Orientation::West));
+        assert!(!living.has_facade(Orientation::North));
+
+        let bedroom = config.get("room-2", None).unwrap();
+        assert!(bedroom.has_facade(Orientation::North));
+        assert_eq!(
+            config.to_json("room-2", None),
+            json!({"floor": 1, "orientation": "north"})
+        );
+        assert_eq!(config.to_json("room-3", None), Value::Null);
+
+        assert!(Orientation::South.faces(200.0));
+        assert!(!Orientation::North.faces(180.0));
+        assert!(Orientation::North.faces(350.0));
+        assert_eq!(Orientation::parse("SW"), Some(Orientation::SouthWest));
+    }
+
+    #[test]
+    fn test_schema_validation() {
+        let error = |toml: &str| RoomMetadataConfig::from_toml(toml).unwrap_err().to_string();
+        assert!(error("[rooms.Kitchen]\nfloor = 500").contains("rooms.Kitchen.floor"));
+        assert!(error("[rooms.Kitchen]\narea_m2 = 0").contains("area_m2"));
+        assert!(error("[rooms.Kitchen]\norientation = \"up\"").contains("must be one of"));
+        assert!(error("[rooms.Kitchen]\nwindow_facades = [\"sud\"]").contains("window_facades"));
+        assert!(error("[rooms.Kitchen]\nbalcony = true").contains("unknown field"));
+        assert!(error("[rooms.Kitchen]\n[rooms.kitchen]").contains("configured twice"));
+        assert!(RoomMetadataConfig::from_toml("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! wasmCloud link-definition handling
+//!
+//! When the WASIP2 component runs under wasmCloud, capabilities are not
+//! configured through environment variables but through link definitions in
+//! the application manifest (see `examples/wasmcloud/wadm.yaml`). Each link
+//! names the WIT interfaces it satisfies and carries a string map of config.
+//!
+//! Supported links:
+//! - `wasi:keyvalue` (`store`): `bucket` selects the bucket holding credentials
+//! - `wasi:http` (`outgoing-handler`): `miniserver_url` and optional
+//!   comma-separated `allowed_hosts` restricting outbound requests
+//! - `wasi:http` (`incoming-handler`): `address` the HTTP server provider binds
+//!
+//! This module is target independent so link handling can be unit-tested
+//! natively; the component glue only has to collect the links and call
+//! [`WasmCloudLinks::from_links`].
+
+use super::ServerConfig;
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+/// Default keyvalue bucket for credentials
+pub const DEFAULT_KEYVALUE_BUCKET: &str = "loxone-mcp";
+
+/// Capability a link definition provides to the component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkCapability {
+    /// `wasi:keyvalue/store`
+    KeyValue,
+    /// `wasi:http/outgoing-handler`
+    HttpClient,
+    /// `wasi:http/incoming-handler`
+    HttpServer,
+}
+
+/// A link definition as delivered by the wasmCloud host
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LinkDefinition {
+    /// Link name (`default` unless the manifest names it)
+    #[serde(default = "default_link_name")]
+    pub name: String,
+    /// WIT namespace, e.g. `wasi`
+    pub wit_namespace: String,
+    /// WIT package, e.g. `keyvalue`
+    pub wit_package: String,
+    /// WIT interfaces covered by the link
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+    /// Link configuration values
+    #[serde(default)]
+    pub config: HashMap<String, String>,
+}
+
+fn default_link_name() -> String {
+    "default".to_string()
+}
+
+impl LinkDefinition {
+    /// Capability provided by this link, if the component uses it
+    pub fn capability(&self) -> Option<LinkCapability> {
+        let has = |interface: &str| self.interfaces.iter().any(|i| i == interface);
+        match (self.wit_namespace.as_str(), self.wit_package.as_str()) {
+            ("wasi", "keyvalue") if has("store") => Some(LinkCapability::KeyValue),
+            ("wasi", "http") if has("outgoing-handler") => Some(LinkCapability::HttpClient),
+            ("wasi", "http") if has("incoming-handler") => Some(LinkCapability::HttpServer),
+            _ => None,
+        }
+    }
+}
+
+/// Capabilities resolved from the component's link definitions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WasmCloudLinks {
+    /// Bucket holding the stored credentials
+    pub keyvalue_bucket: Option<String>,
+    /// Miniserver URL from the outgoing HTTP link
+    pub miniserver_url: Option<Url>,
+    /// Hosts outbound requests may target (empty: only the Miniserver)
+    pub allowed_hosts: Vec<String>,
+    /// Address the HTTP server provider listens on
+    pub http_address: Option<String>,
+}
+
+impl WasmCloudLinks {
+    /// Resolve capabilities from link definitions
+    ///
+    /// Only `default` links are considered; links for unrelated interfaces
+    /// are ignored so the component can share a lattice with other actors.
+    pub fn from_links(links: &[LinkDefinition]) -> Result<Self> {
+        let mut resolved = Self::default();
+
+        for link in links.iter().filter(|l| l.name == "default") {
+            match link.capability() {
+                Some(LinkCapability::KeyValue) => {
+                    let bucket = link
+                        .config
+                        .get("bucket")
+                        .map(String::as_str)
+                        .unwrap_or(DEFAULT_KEYVALUE_BUCKET);
+                    resolved.keyvalue_bucket = Some(bucket.to_string());
+                }
+                Some(LinkCapability::HttpClient) => {
+                    if let Some(url) = link.config.get("miniserver_url") {
+                        resolved.miniserver_url = Some(url.parse().map_err(|e| {
+                            LoxoneError::config(format!(
+                                "Invalid miniserver_url in wasi:http link: {e}"
+                            ))
+                        })?);
+                    }
+                    if let Some(hosts) = link.config.get("allowed_hosts") {
+                        resolved.allowed_hosts = hosts
+                            .split(',')
+                            .map(|h| h.trim().to_lowercase())
+                            .filter(|h| !h.is_empty())
+                            .collect();
+                    }
+                }
+                Some(LinkCapability::HttpServer) => {
+                    resolved.http_address = link.config.get("address").cloned();
+                }
+                None => {}
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Check that the links provide everything the component needs
+    pub fn validate(&self) -> Result<()> {
+        if self.keyvalue_bucket.is_none() {
+            return Err(LoxoneError::config(
+                "Missing wasi:keyvalue link: credentials cannot be loaded",
+            ));
+        }
+        if self.miniserver_url.is_none() {
+            return Err(LoxoneError::config(
+                "Missing miniserver_url on the wasi:http outgoing-handler link",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply the linked Miniserver URL to the server configuration
+    pub fn apply(&self, config: &mut ServerConfig) {
+        if let Some(url) = &self.miniserver_url {
+            config.loxone.url = url.clone();
+        }
+    }
+
+    /// Whether an outbound request to `url` is permitted by the HTTP link
+    pub fn allows_outgoing(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str().map(str::to_lowercase) else {
+            return false;
+        };
+
+        let is_miniserver = self
+            .miniserver_url
+            .as_ref()
+            .and_then(|u| u.host_str())
+            .is_some_and(|h| h.eq_ignore_ascii_case(&host));
+
+        is_miniserver || self.allowed_hosts.contains(&host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(package: &str, interface: &str, config: &[(&str, &str)]) -> LinkDefinition {
+        LinkDefinition {
+            name: "default".to_string(),
+            wit_namespace: "wasi".to_string(),
+            wit_package: package.to_string(),
+            interfaces: vec![interface.to_string()],
+            config: config
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_links() {
+        let links = WasmCloudLinks::from_links(&[
+            link("keyvalue", "store", &[]),
+            link(
+                "http",
+                "outgoing-handler",
+                &[
+                    ("miniserver_url", "http://192.168.1.77"),
+                    ("allowed_hosts", "app.infisical.com, "),
+                ],
+            ),
+            link("http", "incoming-handler", &[("address", "0.0.0.0:8080")]),
+            link("messaging", "consumer", &[]),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            links.keyvalue_bucket.as_deref(),
+            Some(DEFAULT_KEYVALUE_BUCKET)
+        );
+        assert_eq!(links.allowed_hosts, vec!["app.infisical.com"]);
+        assert_eq!(links.http_address.as_deref(), Some("0.0.0.0:8080"));
+        assert!(links.validate().is_ok());
+
+        let mut config = ServerConfig::default();
+        links.apply(&mut config);
+        assert_eq!(config.loxone.url.as_str(), "http://192.168.1.77/");
+    }
+
+    #[test]
+    fn test_missing_links_fail_validation() {
+        let links =
+            WasmCloudLinks::from_links(&[link("keyvalue", "store", &[("bucket", "lx")])]).unwrap();
+        assert_eq!(links.keyvalue_bucket.as_deref(), Some("lx"));
+        assert!(links.validate().is_err());
+
+        assert!(
+            WasmCloudLinks::from_links(&[link(
+                "http",
+                "outgoing-handler",
+                &[("miniserver_url", "not a url")]
+            )])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_outgoing_allowlist() {
+        let links = WasmCloudLinks {
+            miniserver_url: Some("http://miniserver.local".parse().unwrap()),
+            allowed_hosts: vec!["app.infisical.com".to_string()],
+            ..Default::default()
+        };
+
+        assert!(links.allows_outgoing(&"http://MiniServer.local/jdev/cfg/api".parse().unwrap()));
+        assert!(links.allows_outgoing(&"https://app.infisical.com/api".parse().unwrap()));
+        assert!(!links.allows_outgoing(&"https://example.com".parse().unwrap()));
+    }
+}
@@ -49,6 +49,10 @@ pub enum LoxoneError {
     #[error("Device control error: {0}")]
     DeviceControl(String),
 
+    /// Command rejected because the control is locked
+    #[error("Device locked: {0}")]
+    DeviceLocked(String),
+
     /// Sensor discovery errors
     #[error("Sensor discovery error: {0}")]
     SensorDiscovery(String),
@@ -149,6 +153,7 @@ pub enum ErrorCode {
     DeviceOffline,
     DeviceControlFailed,
     DeviceTypeUnsupported,
+    DeviceLocked,
 
     // Data errors (1400-1499)
     ParsingFailed,
@@ -211,6 +216,7 @@ impl ErrorCode {
             ErrorCode::DeviceOffline => 1302,
             ErrorCode::DeviceControlFailed => 1303,
             ErrorCode::DeviceTypeUnsupported => 1304,
+            ErrorCode::DeviceLocked => 1305,
 
             // Data errors (1400-1499)
             ErrorCode::ParsingFailed => 1401,
@@ -436,6 +442,11 @@ impl LoxoneError {
         Self::DeviceControl(msg.into())
     }
 
+    /// Create a device locked error
+    pub fn device_locked<S: Into<String>>(msg: S) -> Self {
+        Self::DeviceLocked(msg.into())
+    }
+
     /// Create a sensor discovery error
     pub fn sensor_discovery<S: Into<String>>(msg: S) -> Self {
         Self::SensorDiscovery(msg.into())
@@ -471,6 +482,11 @@ impl LoxoneError {
         Self::ResourceExhausted(msg.into())
     }
 
+    /// Create a permission denied error
+    pub fn permission_denied<S: Into<String>>(msg: S) -> Self {
+        Self::PermissionDenied(msg.into())
+    }
+
     /// Create a consent denied error
     pub fn consent_denied<S: Into<String>>(msg: S) -> Self {
         Self::ConsentDenied(msg.into())
@@ -524,6 +540,7 @@ impl LoxoneError {
             LoxoneError::Config(_) => ErrorCode::ConfigurationInvalid,
             LoxoneError::Credentials(_) => ErrorCode::InvalidCredentials,
             LoxoneError::DeviceControl(_) => ErrorCode::DeviceControlFailed,
+            LoxoneError::DeviceLocked(_) => ErrorCode::DeviceLocked,
             LoxoneError::SensorDiscovery(_) => ErrorCode::DeviceNotFound,
             LoxoneError::Discovery(_) => ErrorCode::NetworkUnreachable,
             LoxoneError::Timeout(_) => ErrorCode::ConnectionTimeout,
@@ -561,6 +578,7 @@ impl LoxoneError {
             }
             LoxoneError::Timeout(_) | LoxoneError::Network(_) => ErrorSeverity::Warning,
             LoxoneError::DeviceControl(_) | LoxoneError::NotFound(_) => ErrorSeverity::Error,
+            LoxoneError::DeviceLocked(_) => ErrorSeverity::Warning,
             LoxoneError::InvalidInput(_) | LoxoneError::Parsing(_) => ErrorSeverity::Warning,
             LoxoneError::ResourceExhausted(_) | LoxoneError::RateLimit(_) => ErrorSeverity::Error,
             _ => ErrorSeverity::Error,
@@ -727,6 +745,7 @@ impl LoxoneError {
                 LoxoneError::Http(_) => "HTTP request failed".to_string(),
                 LoxoneError::Config(_) => "Configuration error".to_string(),
                 LoxoneError::DeviceControl(_) => "Device control failed".to_string(),
+                LoxoneError::DeviceLocked(_) => "Device is locked".to_string(),
                 LoxoneError::SensorDiscovery(_) => "Sensor discovery failed".to_string(),
                 LoxoneError::Discovery(_) => "Network discovery failed".to_string(),
                 LoxoneError::Mcp(_) => "MCP protocol error".to_string(),
@@ -962,6 +981,7 @@ impl pulseengine_mcp_logging::ErrorClassification for LoxoneError {
             LoxoneError::Credentials(_) => "credentials_error",
             LoxoneError::Crypto(_) => "crypto_error",
             LoxoneError::DeviceControl(_) => "device_control_error",
+            LoxoneError::DeviceLocked(_) => "device_locked_error",
             LoxoneError::SensorDiscovery(_) => "sensor_discovery_error",
             LoxoneError::Discovery(_) => "discovery_error",
             #[cfg(feature = "websocket")]
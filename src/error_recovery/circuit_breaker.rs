@@ -5,6 +5,7 @@
 //! to fail, allowing systems to recover gracefully.
 
 use crate::error::LoxoneError;
+use crate::utils::clock::{SharedClock, system_clock};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
@@ -165,6 +166,7 @@ pub struct CircuitBreaker {
     config: CircuitBreakerConfig,
     state: Arc<RwLock<CircuitBreakerState>>,
     event_listeners: Arc<RwLock<Vec<Arc<dyn CircuitBreakerListener + Send + Sync>>>>,
+    clock: SharedClock,
 }
 
 /// Internal circuit breaker state
@@ -189,12 +191,17 @@ pub trait CircuitBreakerListener: Send + Sync {
 impl CircuitBreaker {
     /// Create new circuit breaker
     pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Create a circuit breaker timing its open state on `clock`
+    pub fn with_clock(config: CircuitBreakerConfig, clock: SharedClock) -> Self {
         let initial_state = CircuitBreakerState {
             current_state: CircuitState::Closed,
             failure_count: 0,
             success_count: 0,
             recent_failures: VecDeque::new(),
-            last_state_change: Utc::now(),
+            last_state_change: clock.utc_now(),
             current_timeout: config.timeout_duration,
             consecutive_timeouts: 0,
             stats: CircuitBreakerStats {
@@ -215,6 +222,7 @@ impl CircuitBreaker {
             config,
             state: Arc::new(RwLock::new(initial_state)),
             event_listeners: Arc::new(RwLock::new(Vec::new())),
+            clock,
         }
     }
 
@@ -238,7 +246,7 @@ impl CircuitBreaker {
                 true
             }
             CircuitState::Open => {
-                let elapsed = Utc::now() - state.last_state_change;
+                let elapsed = self.clock.utc_now() - state.last_state_change;
                 if elapsed >= state.current_timeout {
                     // Transition to half-open
                     self.transition_state(&mut state, CircuitState::HalfOpen)
@@ -285,7 +293,7 @@ impl CircuitBreaker {
     pub async fn record_success(&self) {
         let mut state = self.state.write().await;
         state.stats.successful_requests += 1;
-        state.stats.last_success = Some(Utc::now());
+        state.stats.last_success = Some(self.clock.utc_now());
 
         match state.current_state {
             CircuitState::Closed => {
@@ -329,14 +337,14 @@ impl CircuitBreaker {
         }
 
         state.stats.failed_requests += 1;
-        state.stats.last_failure = Some(Utc::now());
+        state.stats.last_failure = Some(self.clock.utc_now());
 
         match state.current_state {
             CircuitState::Closed => {
-                state.recent_failures.push_back(Utc::now());
+                state.recent_failures.push_back(self.clock.utc_now());
 
                 // Remove old failures outside the window
-                let cutoff = Utc::now() - self.config.failure_window;
+                let cutoff = self.clock.utc_now() - self.config.failure_window;
                 while let Some(failure_time) = state.recent_failures.front() {
                     if *failure_time < cutoff {
                         state.recent_failures.pop_front();
@@ -420,7 +428,7 @@ impl CircuitBreaker {
 
         // Calculate time until transition for open state
         if state.current_state == CircuitState::Open {
-            let elapsed = Utc::now() - state.last_state_change;
+            let elapsed = self.clock.utc_now() - state.last_state_change;
             if elapsed < state.current_timeout {
                 stats.time_until_transition = Some(state.current_timeout - elapsed);
             }
@@ -437,7 +445,7 @@ impl CircuitBreaker {
         state.failure_count = 0;
         state.success_count = 0;
         state.recent_failures.clear();
-        state.last_state_change = Utc::now();
+        state.last_state_change = self.clock.utc_now();
         state.current_timeout = self.config.timeout_duration;
         state.consecutive_timeouts = 0;
 
@@ -462,7 +470,7 @@ impl CircuitBreaker {
     async fn transition_state(&self, state: &mut CircuitBreakerState, new_state: CircuitState) {
         let old_state = state.current_state;
         state.current_state = new_state;
-        state.last_state_change = Utc::now();
+        state.last_state_change = self.clock.utc_now();
         state.success_count = 0;
         state.failure_count = 0;
         state.stats.state = new_state;
@@ -486,7 +494,7 @@ impl CircuitBreaker {
     ) {
         let event = CircuitBreakerEvent {
             event_type,
-            timestamp: Utc::now(),
+            timestamp: self.clock.utc_now(),
             previous_state,
             new_state,
             context,
@@ -516,6 +524,7 @@ impl CircuitBreaker {
 pub struct CircuitBreakerManager {
     breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
     default_config: CircuitBreakerConfig,
+    clock: SharedClock,
 }
 
 impl CircuitBreakerManager {
@@ -524,9 +533,16 @@ impl CircuitBreakerManager {
         Self {
             breakers: Arc::new(RwLock::new(HashMap::new())),
             default_config,
+            clock: system_clock(),
         }
     }
 
+    /// Time the breakers created from now on with `clock`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Get or create circuit breaker for service
     pub async fn get_breaker(&self, service_name: &str) -> Arc<CircuitBreaker> {
         let mut breakers = self.breakers.write().await;
@@ -535,7 +551,10 @@ impl CircuitBreakerManager {
             return breaker.clone();
         }
 
-        let breaker = Arc::new(CircuitBreaker::new(self.default_config.clone()));
+        let breaker = Arc::new(CircuitBreaker::with_clock(
+            self.default_config.clone(),
+            self.clock.clone(),
+        ));
         breakers.insert(service_name.to_string(), breaker.clone());
 
         info!("Created new circuit breaker for service: {}", service_name);
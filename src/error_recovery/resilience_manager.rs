@@ -7,6 +7,7 @@ use crate::error::{LoxoneError, Result};
 use crate::error_recovery::{
     CircuitBreakerConfig, CircuitBreakerManager, RetryExecutor, RetryPolicy,
 };
+use crate::utils::clock::{SharedClock, system_clock};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::future::Future;
@@ -107,6 +108,7 @@ pub struct ResilienceManager {
     circuit_breaker_manager: Arc<CircuitBreakerManager>,
     retry_executors: Arc<RwLock<HashMap<String, Arc<RetryExecutor>>>>,
     fallback_cache: Arc<RwLock<HashMap<String, CachedFallback>>>,
+    clock: SharedClock,
 }
 
 /// Cached fallback value
@@ -127,13 +129,21 @@ impl Default for ResilienceManager {
 impl ResilienceManager {
     /// Create new resilience manager
     pub fn new() -> Self {
+        Self::with_clock(system_clock())
+    }
+
+    /// Create a resilience manager whose retries, breakers, timeouts and
+    /// cached fallbacks run on `clock`
+    pub fn with_clock(clock: SharedClock) -> Self {
         Self {
             configs: Arc::new(RwLock::new(HashMap::new())),
-            circuit_breaker_manager: Arc::new(CircuitBreakerManager::new(
-                CircuitBreakerConfig::default(),
-            )),
+            circuit_breaker_manager: Arc::new(
+                CircuitBreakerManager::new(CircuitBreakerConfig::default())
+                    .with_clock(clock.clone()),
+            ),
             retry_executors: Arc::new(RwLock::new(HashMap::new())),
             fallback_cache: Arc::new(RwLock::new(HashMap::new())),
+            clock,
         }
     }
 
@@ -143,7 +153,8 @@ impl ResilienceManager {
         configs.insert(service_name.to_string(), config.clone());
 
         // Create retry executor
-        let executor = Arc::new(RetryExecutor::new(config.retry_policy));
+        let executor =
+            Arc::new(RetryExecutor::new(config.retry_policy).with_clock(self.clock.clone()));
         let mut executors = self.retry_executors.write().await;
         executors.insert(service_name.to_string(), executor);
 
@@ -244,9 +255,9 @@ impl ResilienceManager {
     {
         let timeout_duration = std::time::Duration::from_millis(timeout.num_milliseconds() as u64);
 
-        match tokio::time::timeout(timeout_duration, executor.execute(operation)).await {
-            Ok(result) => result,
-            Err(_) => Err(LoxoneError::timeout("Operation timed out")),
+        tokio::select! {
+            result = executor.execute(operation) => result,
+            _ = self.clock.sleep(timeout_duration) => Err(LoxoneError::timeout("Operation timed out")),
         }
     }
 
@@ -274,7 +285,7 @@ impl ResilienceManager {
             FallbackStrategy::Cached => {
                 let cache = self.fallback_cache.read().await;
                 if let Some(cached) = cache.get(service_name)
-                    && cached.expires_at > chrono::Utc::now()
+                    && cached.expires_at > self.clock.utc_now()
                 {
                     info!("Using cached fallback for service: {}", service_name);
                     return serde_json::from_value(cached.value.clone()).map_err(|e| {
@@ -332,7 +343,7 @@ impl ResilienceManager {
             }
         };
 
-        let now = chrono::Utc::now();
+        let now = self.clock.utc_now();
         let cached_fallback = CachedFallback {
             value: cached_value,
             cached_at: now,
@@ -400,7 +411,7 @@ impl ResilienceManager {
     /// Clean up expired cache entries
     pub async fn cleanup_cache(&self) {
         let mut cache = self.fallback_cache.write().await;
-        let now = chrono::Utc::now();
+        let now = self.clock.utc_now();
 
         cache.retain(|_, cached| cached.expires_at > now);
 
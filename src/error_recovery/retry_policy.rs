@@ -5,13 +5,13 @@
 //! integration.
 
 use crate::error::{LoxoneError, Result};
+use crate::utils::clock::{SharedClock, system_clock};
 use chrono::{DateTime, Duration, Utc};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
 /// Retry policy configuration
@@ -283,6 +283,7 @@ impl RetryPolicy {
 pub struct RetryExecutor {
     policy: RetryPolicy,
     stats: Arc<RwLock<RetryStats>>,
+    clock: SharedClock,
 }
 
 /// Retry statistics
@@ -310,9 +311,16 @@ impl RetryExecutor {
         Self {
             policy,
             stats: Arc::new(RwLock::new(RetryStats::default())),
+            clock: system_clock(),
         }
     }
 
+    /// Wait between attempts on `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Execute operation with retry policy
     pub async fn execute<F, T, Fut>(&self, operation: F) -> Result<T>
     where
@@ -381,7 +389,7 @@ impl RetryExecutor {
 
                     let mut stats = self.stats.write().await;
                     stats.total_retry_attempts += 1;
-                    stats.last_retry = Some(Utc::now());
+                    stats.last_retry = Some(self.clock.utc_now());
                     drop(stats);
 
                     if self.policy.detailed_logging {
@@ -391,10 +399,11 @@ impl RetryExecutor {
                         );
                     }
 
-                    sleep(std::time::Duration::from_millis(
-                        delay.num_milliseconds() as u64
-                    ))
-                    .await;
+                    self.clock
+                        .sleep(std::time::Duration::from_millis(
+                            delay.num_milliseconds() as u64
+                        ))
+                        .await;
                 }
             }
         }
@@ -432,6 +441,7 @@ fn fibonacci(n: u32) -> u32 {
 /// Retry builder for fluent API
 pub struct RetryBuilder {
     policy: RetryPolicy,
+    clock: Option<SharedClock>,
 }
 
 impl Default for RetryBuilder {
@@ -445,6 +455,7 @@ impl RetryBuilder {
     pub fn new() -> Self {
         Self {
             policy: RetryPolicy::default(),
+            clock: None,
         }
     }
 
@@ -476,9 +487,19 @@ impl RetryBuilder {
         self
     }
 
+    /// Wait between attempts on `clock`
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
     /// Build retry executor
     pub fn build(self) -> RetryExecutor {
-        RetryExecutor::new(self.policy)
+        let executor = RetryExecutor::new(self.policy);
+        match self.clock {
+            Some(clock) => executor.with_clock(clock),
+            None => executor,
+        }
     }
 }
 
@@ -3,7 +3,8 @@
 use super::{
     DependencyCheck, DependencyStatus, DependencyType, HealthCheck, HealthCheckResult, HealthStatus,
 };
-use crate::client::ClientContext;
+use crate::client::ws_keepalive::KeepaliveTracker;
+use crate::client::{ClientContext, LoxoneClient, time_sync};
 use crate::error::Result;
 use std::sync::Arc;
 use std::time::Duration;
@@ -409,6 +410,112 @@ impl HealthCheck for CredentialsHealthCheck {
     }
 }
 
+/// Clock drift between this host and the Miniserver
+///
+/// Token expiry is compared against Miniserver time, so drift beyond the
+/// threshold is reported as a warning even though it is compensated.
+pub struct ClockDriftHealthCheck {
+    client: Arc<dyn LoxoneClient>,
+    warning_threshold: Duration,
+}
+
+impl ClockDriftHealthCheck {
+    pub fn new(client: Arc<dyn LoxoneClient>, warning_threshold: Duration) -> Self {
+        Self {
+            client,
+            warning_threshold,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for ClockDriftHealthCheck {
+    fn name(&self) -> &str {
+        "clock_drift"
+    }
+
+    async fn check(&self) -> Result<HealthCheckResult> {
+        let sample = match time_sync::measure(self.client.as_ref()).await {
+            Ok(sample) => sample,
+            Err(e) => {
+                return Ok(HealthCheckResult::warning(
+                    self.name(),
+                    "Could not read Miniserver time",
+                )
+                .with_metadata("error", e.to_string()));
+            }
+        };
+
+        let drift_secs = sample.offset_ms as f64 / 1000.0;
+        let result = if sample.exceeds(self.warning_threshold) {
+            HealthCheckResult::warning(
+                self.name(),
+                &format!("Miniserver clock drift {drift_secs:+.1}s exceeds threshold"),
+            )
+        } else {
+            HealthCheckResult::healthy(
+                self.name(),
+                &format!("Miniserver clock drift {drift_secs:+.1}s"),
+            )
+        };
+        Ok(result
+            .with_metadata("offset_ms", sample.offset_ms)
+            .with_metadata("round_trip_ms", sample.round_trip_ms)
+            .with_metadata("timezone_offset_minutes", sample.timezone_offset_minutes)
+            .with_metadata(
+                "warning_threshold_secs",
+                self.warning_threshold.as_secs_f64(),
+            ))
+    }
+}
+
+/// Keepalive round trips on the Miniserver WebSocket
+///
+/// Unanswered keepalives mean the stream is stalling even if TCP is up; the
+/// client reconnects once the configured number is missed in a row.
+pub struct WebSocketKeepaliveHealthCheck {
+    tracker: Arc<KeepaliveTracker>,
+}
+
+impl WebSocketKeepaliveHealthCheck {
+    pub fn new(tracker: Arc<KeepaliveTracker>) -> Self {
+        Self { tracker }
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for WebSocketKeepaliveHealthCheck {
+    fn name(&self) -> &str {
+        "websocket_keepalive"
+    }
+
+    async fn check(&self) -> Result<HealthCheckResult> {
+        let stats = self.tracker.stats();
+        let result = if stats.missed_consecutive > 0 {
+            HealthCheckResult::warning(
+                self.name(),
+                &format!(
+                    "{} keepalive(s) unanswered in a row",
+                    stats.missed_consecutive
+                ),
+            )
+        } else if let Some(rtt) = stats.last_rtt_ms {
+            HealthCheckResult::healthy(self.name(), &format!("Keepalive round trip {rtt} ms"))
+        } else {
+            HealthCheckResult::healthy(self.name(), "No keepalive answered yet")
+        };
+
+        let stats = serde_json::to_value(&stats).unwrap_or_default();
+        Ok(stats
+            .as_object()
+            .into_iter()
+            .flatten()
+            .fold(result, |result, (key, value)| {
+                result.with_metadata(key.as_str(), value.clone())
+            }))
+    }
+}
+
 /// Loxone Miniserver dependency check
 pub struct LoxoneMiniserverCheck {
     _client_context: Arc<ClientContext>,
@@ -678,6 +785,36 @@ mod tests {
         // Result depends on environment variables, but should not panic
     }
 
+    #[tokio::test]
+    async fn test_clock_drift_check() {
+        let client: Arc<dyn LoxoneClient> = Arc::new(crate::mock::MockLoxoneClient::new());
+        let check = ClockDriftHealthCheck::new(client, time_sync::DEFAULT_DRIFT_WARNING);
+        let result = check.check().await.unwrap();
+
+        assert_eq!(result.name, "clock_drift");
+        assert_eq!(result.status, HealthStatus::Healthy);
+        assert!(result.metadata.contains_key("offset_ms"));
+    }
+
+    #[tokio::test]
+    async fn test_websocket_keepalive_check() {
+        let tracker = Arc::new(KeepaliveTracker::default());
+        let now = std::time::Instant::now();
+        tracker.sent(now);
+        tracker.answered(now + Duration::from_millis(40));
+
+        let check = WebSocketKeepaliveHealthCheck::new(tracker.clone());
+        let result = check.check().await.unwrap();
+        assert_eq!(result.status, HealthStatus::Healthy);
+        assert_eq!(result.metadata["last_rtt_ms"], 40);
+
+        tracker.sent(now + Duration::from_secs(30));
+        tracker.tick(now + Duration::from_secs(60));
+        let result = check.check().await.unwrap();
+        assert_eq!(result.status, HealthStatus::Warning);
+        assert_eq!(result.metadata["missed_consecutive"], 1);
+    }
+
     #[tokio::test]
     async fn test_filesystem_check() {
         let check = FileSystemCheck::new("/tmp", false);
@@ -5,6 +5,7 @@ use super::{
     HealthStatus, TrendDirection,
 };
 use crate::error::{LoxoneError, Result};
+use crate::notifications::{NotificationDispatcher, NotificationSeverity};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -50,6 +51,14 @@ impl HealthMonitor {
         }
     }
 
+    /// Deliver triggered alerts through `notifier`
+    pub fn with_notifier(mut self, notifier: Arc<NotificationDispatcher>) -> Self {
+        self.alert_manager = Arc::new(Mutex::new(
+            AlertManager::new(self.config.alert_config.clone()).with_notifier(notifier),
+        ));
+        self
+    }
+
     /// Start monitoring service
     pub async fn start(&self) -> Result<()> {
         info!("Starting health monitoring service");
@@ -331,6 +340,8 @@ pub struct AlertManager {
     active_alerts: HashMap<String, Alert>,
     /// Alert history
     alert_history: Vec<Alert>,
+    /// Channels newly triggered alerts are sent to
+    notifier: Option<Arc<NotificationDispatcher>>,
 }
 
 impl AlertManager {
@@ -340,9 +351,20 @@ impl AlertManager {
             config,
             active_alerts: HashMap::new(),
             alert_history: Vec::new(),
+            notifier: None,
         }
     }
 
+    /// Send newly triggered alerts through `notifier`
+    ///
+    /// Alert IDs double as notification events, so an alert such as
+    /// `security.alarm_triggered` uses its own template and others fall back
+    /// to the generic alert template.
+    pub fn with_notifier(mut self, notifier: Arc<NotificationDispatcher>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
     /// Evaluate health-based alerts
     pub async fn evaluate_health_alerts(&mut self, report: &HealthReport) -> Result<()> {
         // Check for critical health status
@@ -467,7 +489,7 @@ impl AlertManager {
     }
 
     /// Trigger an alert
-    async fn trigger_alert(
+    pub async fn trigger_alert(
         &mut self,
         alert_id: String,
         severity: AlertSeverity,
@@ -493,6 +515,25 @@ impl AlertManager {
 
         info!("Alert triggered: {} - {}", alert_id, alert.message);
 
+        // Notify once per activation, not on every re-evaluation
+        if alert.count == 1
+            && let Some(notifier) = &self.notifier
+        {
+            let mut vars = match &alert.data {
+                serde_json::Value::Object(data) => data.clone(),
+                _ => serde_json::Map::new(),
+            };
+            vars.insert("id".to_string(), alert.id.clone().into());
+            vars.insert("message".to_string(), alert.message.clone().into());
+            notifier
+                .notify(
+                    &alert.id,
+                    NotificationSeverity::from(&alert.severity),
+                    &serde_json::Value::Object(vars),
+                )
+                .await;
+        }
+
         self.active_alerts.insert(alert_id, alert.clone());
         self.alert_history.push(alert);
 
@@ -505,7 +546,7 @@ impl AlertManager {
     }
 
     /// Resolve an alert
-    async fn resolve_alert(&mut self, alert_id: &str) -> Result<()> {
+    pub async fn resolve_alert(&mut self, alert_id: &str) -> Result<()> {
         if let Some(mut alert) = self.active_alerts.remove(alert_id) {
             alert.resolved_at = Some(
                 SystemTime::now()
@@ -659,6 +700,16 @@ pub enum AlertSeverity {
     Critical,
 }
 
+impl From<&AlertSeverity> for NotificationSeverity {
+    fn from(severity: &AlertSeverity) -> Self {
+        match severity {
+            AlertSeverity::Info => Self::Info,
+            AlertSeverity::Warning => Self::Warning,
+            AlertSeverity::Critical => Self::Critical,
+        }
+    }
+}
+
 /// Monitoring state
 #[derive(Debug)]
 struct MonitoringState {
@@ -22,8 +22,12 @@ use crate::http_transport::cors_middleware::AxumCorsMiddleware;
 use crate::performance::{
     middleware::PerformanceMiddleware, PerformanceConfig, PerformanceMonitor,
 };
+use crate::security::caller::Caller;
+use crate::security::key_store::{KeyStore, KeyStoreConfig};
+use crate::security::tenants::TenantRegistry;
 use crate::security::{enhanced_cors::EnhancedCorsConfig, SecurityConfig};
 use crate::server::LoxoneMcpServer;
+use crate::server::action_aliases::{ActionAliases, locale_from_accept_language};
 // Legacy support removed - framework is now default
 use rate_limiting::{EnhancedRateLimiter, RateLimitResult};
 
@@ -574,9 +578,16 @@ impl HttpTransportServer {
         // Initialize the global SSE manager for use by the notification dispatcher
         init_global_sse_manager(sse_manager.clone());
 
+        let tenants = std::env::var_os("LOXONE_TENANTS_FILE")
+            .map(|path| TenantRegistry::load_file(std::path::Path::new(&path)))
+            .transpose()?
+            .map(Arc::new);
+
         let shared_state = Arc::new(AppState {
             mcp_server: self.mcp_server.clone(),
             auth_manager: self.auth_manager.clone(),
+            key_store: Arc::new(KeyStore::new(KeyStoreConfig::default()).await?),
+            tenants,
             rate_limiter: self.rate_limiter.clone(),
             #[cfg(feature = "influxdb")]
             metrics_collector: self.metrics_collector.clone(),
@@ -797,6 +808,10 @@ impl HttpTransportServer {
 struct AppState {
     mcp_server: LoxoneMcpServer,
     auth_manager: Arc<AuthenticationManager>,
+    /// Keys requests are resolved against, for per-request scoping
+    key_store: Arc<KeyStore>,
+    /// Tenants of `LOXONE_TENANTS_FILE`, if configured
+    tenants: Option<Arc<TenantRegistry>>,
     rate_limiter: EnhancedRateLimiter,
     #[cfg(feature = "influxdb")]
     metrics_collector: Arc<MetricsCollector>,
@@ -1102,6 +1117,24 @@ async fn create_mcp_sse_stream(
     )
 }
 
+/// Server view for the API key of a request
+///
+/// The tenant of the caller's key applies to this request only. Requests
+/// without a key (dev mode) run on the server as started.
+async fn caller_server(
+    state: &AppState,
+    headers: &HeaderMap,
+    query: &HashMap<String, String>,
+) -> Result<LoxoneMcpServer> {
+    let Some(key) = crate::auth::validation::extract_api_key(headers, None)
+        .or_else(|| query.get("api_key").cloned())
+    else {
+        return Ok(state.mcp_server.clone());
+    };
+    let caller = Caller::resolve(&state.key_store, &key, state.tenants.as_deref()).await?;
+    state.mcp_server.for_caller(&caller).await
+}
+
 /// Handle MCP messages via HTTP POST (Streamable HTTP transport for MCP Inspector)
 async fn handle_mcp_message(
     State(state): State<Arc<AppState>>,
@@ -1763,8 +1796,29 @@ async fn handle_mcp_message(
                     tool_name, arguments
                 );
 
+                // Locale the tool reads action words in: `_meta.locale` of
+                // the call, else the client's preferred language
+                let locale = params
+                    .and_then(|p| p.pointer("/_meta/locale"))
+                    .and_then(|l| l.as_str())
+                    .map(str::to_string)
+                    .or_else(|| {
+                        headers
+                            .get(header::ACCEPT_LANGUAGE)
+                            .and_then(|v| v.to_str().ok())
+                            .and_then(locale_from_accept_language)
+                    });
+                let server = caller_server(&state, &headers, &query)
+                    .await
+                    .map_err(|_| (StatusCode::FORBIDDEN, "API key cannot be resolved"))?;
                 // Call the actual MCP server's call_tool method
-                match state.mcp_server.call_tool(tool_name, arguments).await {
+                let call = server.call_tool(tool_name, arguments);
+                let result = match locale {
+                    Some(locale) => ActionAliases::scope(locale, call).await,
+                    None => call.await,
+                };
+
+                match result {
                     Ok(result) => {
                         let response = serde_json::json!({
                             "jsonrpc": "2.0",
@@ -1818,8 +1872,11 @@ async fn handle_mcp_message(
                     }
                 };
 
+                let server = caller_server(&state, &headers, &query)
+                    .await
+                    .map_err(|_| (StatusCode::FORBIDDEN, "API key cannot be resolved"))?;
                 // Read the resource using the handler
-                match ResourceHandler::read_resource(&state.mcp_server, context).await {
+                match ResourceHandler::read_resource(&server, context).await {
                     Ok(resource_content) => {
                         let response = serde_json::json!({
                             "jsonrpc": "2.0",
@@ -245,6 +245,7 @@ pub async fn get_dashboard_json(State(context): State<Arc<ClientContext>>) -> Js
             "blinds": capabilities.blind_count,
             "climate": capabilities.climate_count,
             "sensors": capabilities.sensor_count,
+            "access": capabilities.access_count,
         },
         "rooms": room_data,
         "devices": {
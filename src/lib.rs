@@ -26,6 +26,7 @@ pub mod health;
 pub mod logging;
 pub mod mcp_consent;
 pub mod monitoring;
+pub mod notifications;
 pub mod performance;
 pub mod sampling;
 pub mod security;
@@ -371,7 +371,8 @@ impl MetricsCollector {
                 metrics.network_errors += 1;
                 metrics.timeout_errors += 1;
             }
-            crate::error::LoxoneError::DeviceControl(_) => {
+            crate::error::LoxoneError::DeviceControl(_)
+            | crate::error::LoxoneError::DeviceLocked(_) => {
                 metrics.business_errors += 1;
                 metrics.device_control_errors += 1;
             }
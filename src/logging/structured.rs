@@ -182,6 +182,10 @@ impl ErrorClass {
                 error_type: "device_control_failed".to_string(),
                 domain: "loxone".to_string(),
             },
+            crate::error::LoxoneError::DeviceLocked(_) => Self::Business {
+                error_type: "device_locked".to_string(),
+                domain: "loxone".to_string(),
+            },
             crate::error::LoxoneError::Config(_) => Self::Server {
                 error_type: "configuration_error".to_string(),
                 retryable: false,
@@ -11,12 +11,34 @@ use pulseengine_mcp_server::McpServerBuilder;
 use loxone_mcp_rust::{
     Result, ServerConfig as LoxoneServerConfig,
     config::{
-        credential_registry::CredentialRegistry, credentials::create_best_credential_manager,
+        credential_registry::CredentialRegistry,
+        credentials::{create_best_credential_manager, env_or_secret},
+        room_metadata::RoomMetadataConfig,
     },
-    server::macro_backend::LoxoneMcpServer,
+    monitoring::{lifetime_metrics::LifetimeMetrics, observability},
+    security::{
+        caller::OPERATIONS,
+        guest_access,
+        key_store::{KeyStore, KeyStoreConfig},
+        tenants::{TenantRegistry, TenantScope},
+    },
+    server::{
+        client_sessions::SessionTransport,
+        connection_limits::{self, ConnectionLimiter, ConnectionLimitsConfig},
+        daemon,
+        http_gateway::{GatewayConfig, HttpGateway},
+        macro_backend::LoxoneMcpServer,
+        session_backend::SessionBackend,
+        standby::{self, StandbyConfig, StandbyMonitor, StandbyReadOnly},
+        status_page::{self, StatusPageConfig, StatusProbe},
+        subscription::SubscriptionCoordinator,
+        systemd,
+    },
+    storage::migrations,
 };
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{info, warn};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
@@ -54,6 +76,38 @@ struct Config {
     /// Disable SSL certificate verification (not recommended for production)
     #[arg(long, global = true)]
     insecure: bool,
+
+    /// Run in the background, detached from the terminal (Unix only)
+    #[arg(long, global = true)]
+    daemon: bool,
+
+    /// PID file used by --daemon, stop and status
+    #[arg(long, global = true, env = "LOXONE_PID_FILE")]
+    pid_file: Option<PathBuf>,
+
+    /// Log file used by --daemon
+    #[arg(long, global = true, env = "LOXONE_LOG_FILE")]
+    log_file: Option<PathBuf>,
+
+    /// Only expose the rooms and devices of this tenant (see --tenants-file)
+    #[arg(long, global = true, env = "LOXONE_TENANT")]
+    tenant: Option<String>,
+
+    /// TOML file tagging rooms and devices per tenant
+    #[arg(long, global = true, env = "LOXONE_TENANTS_FILE")]
+    tenants_file: Option<PathBuf>,
+
+    /// TOML file with room metadata (floor, orientation, area, window facades)
+    #[arg(long, global = true, env = "LOXONE_ROOMS_FILE")]
+    rooms_file: Option<PathBuf>,
+
+    /// Check connection, auth, structure and one read per tool category, then exit
+    #[arg(long, global = true)]
+    self_test: bool,
+
+    /// Show which persisted files would be migrated to the current format, then exit
+    #[arg(long, global = true)]
+    migrate_dry_run: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -85,6 +139,21 @@ enum TransportCommand {
         /// Enable CORS (permissive mode)
         #[arg(long)]
         enable_cors: bool,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        tunnel: TunnelArgs,
+
+        #[command(flatten)]
+        limits: ConnectionLimitArgs,
+
+        #[command(flatten)]
+        status_page: StatusPageArgs,
+
+        #[command(flatten)]
+        replication: ReplicationArgs,
     },
     /// Run with streamable HTTP transport (new MCP Inspector)
     StreamableHttp {
@@ -95,7 +164,318 @@ enum TransportCommand {
         /// Enable CORS
         #[arg(long)]
         enable_cors: bool,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        tunnel: TunnelArgs,
+
+        #[command(flatten)]
+        limits: ConnectionLimitArgs,
+
+        #[command(flatten)]
+        status_page: StatusPageArgs,
+
+        #[command(flatten)]
+        replication: ReplicationArgs,
+    },
+    /// Generate systemd unit files for running the server as a service
+    SystemdUnit {
+        /// Transport the service runs (http or streamable-http)
+        #[arg(long, default_value = "http")]
+        transport: String,
+
+        /// Port the service listens on
+        #[arg(short, long, default_value = "3001")]
+        port: u16,
+
+        /// System user to run the service as
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Path to the server binary (default: the current executable)
+        #[arg(long)]
+        binary: Option<PathBuf>,
+
+        /// Also generate a loxone-mcp.socket unit for socket activation
+        #[arg(long)]
+        socket_activation: bool,
+
+        /// Watchdog interval in seconds (0 disables the watchdog)
+        #[arg(long, default_value = "30")]
+        watchdog_secs: u64,
+
+        /// Write the unit files into this directory instead of printing them
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
     },
+    /// Generate Prometheus alerting rules and a Grafana dashboard for the exported metrics
+    Observability {
+        /// Prometheus job name the server is scraped as
+        #[arg(long, default_value = "loxone-mcp")]
+        job: String,
+
+        /// Write the files into this directory instead of printing them
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// Stop a server started with --daemon
+    Stop {
+        /// Seconds to wait for the server to exit
+        #[arg(long, default_value = "10")]
+        timeout: u64,
+    },
+    /// Show whether a server started with --daemon is running
+    Status,
+}
+
+/// Built-in TLS termination options for the HTTP transports
+#[derive(Args, Debug, Clone)]
+struct TlsArgs {
+    /// Port for the TLS listener; enables TLS termination in front of the HTTP port
+    #[arg(long, env = "LOXONE_TLS_PORT")]
+    tls_port: Option<u16>,
+
+    /// PEM certificate chain for TLS
+    #[arg(long, env = "LOXONE_TLS_CERT", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for TLS
+    #[arg(long, env = "LOXONE_TLS_KEY", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Domain to obtain a Let's Encrypt certificate for (repeatable)
+    #[arg(
+        long = "acme-domain",
+        env = "LOXONE_ACME_DOMAINS",
+        value_delimiter = ',',
+        conflicts_with = "tls_cert"
+    )]
+    acme_domains: Vec<String>,
+
+    /// Contact email for the ACME account
+    #[arg(long, env = "LOXONE_ACME_CONTACT")]
+    acme_contact: Option<String>,
+
+    /// Directory for cached ACME account keys and certificates
+    #[arg(long, env = "LOXONE_ACME_CACHE_DIR")]
+    acme_cache_dir: Option<PathBuf>,
+
+    /// Use the Let's Encrypt production directory (default: staging)
+    #[arg(long)]
+    acme_production: bool,
+}
+
+impl TlsArgs {
+    /// Start TLS termination in front of the plain HTTP port, if requested
+    #[cfg(feature = "tls")]
+    async fn start(&self, http_port: u16) -> Result<()> {
+        use loxone_mcp_rust::server::tls::{CertificateSource, TlsConfig, TlsTerminator};
+
+        let Some(tls_port) = self.tls_port else {
+            return Ok(());
+        };
+
+        let certificate = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert_path), Some(key_path)) => CertificateSource::Files {
+                cert_path: cert_path.clone(),
+                key_path: key_path.clone(),
+            },
+            _ => CertificateSource::Acme {
+                domains: self.acme_domains.clone(),
+                contact: self.acme_contact.clone(),
+                cache_dir: self.acme_cache_dir.clone().unwrap_or_else(|| {
+                    dirs::data_local_dir()
+                        .unwrap_or_else(|| PathBuf::from("."))
+                        .join("loxone-mcp")
+                        .join("acme")
+                }),
+                production: self.acme_production,
+            },
+        };
+
+        TlsTerminator::spawn(TlsConfig {
+            listen_addr: std::net::SocketAddr::from(([0, 0, 0, 0], tls_port)),
+            upstream_port: http_port,
+            certificate,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// TLS termination is unavailable without the `tls` feature
+    #[cfg(not(feature = "tls"))]
+    async fn start(&self, _http_port: u16) -> Result<()> {
+        if self.tls_port.is_some() {
+            return Err(loxone_mcp_rust::LoxoneError::config(
+                "TLS termination requested but the server was built without the `tls` feature",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Outbound tunnel options for the HTTP transports
+#[derive(Args, Debug, Clone)]
+struct TunnelArgs {
+    /// Relay WebSocket URL; opens an outbound tunnel for clients outside the LAN
+    #[arg(long, env = "LOXONE_TUNNEL_RELAY")]
+    tunnel_relay: Option<String>,
+
+    /// Name remote clients reach this server by on the relay
+    #[arg(long, env = "LOXONE_TUNNEL_ID", default_value = "loxone-mcp")]
+    tunnel_id: String,
+}
+
+impl TunnelArgs {
+    /// Open the tunnel to the relay, if requested
+    #[cfg(all(feature = "websocket", feature = "crypto-openssl"))]
+    async fn start(&self, http_port: u16, keys: Arc<KeyStore>) -> Result<()> {
+        use loxone_mcp_rust::server::tunnel::{TunnelClient, TunnelConfig};
+
+        let Some(relay_url) = &self.tunnel_relay else {
+            return Ok(());
+        };
+        TunnelClient::new(
+            TunnelConfig {
+                relay_url: relay_url.clone(),
+                tunnel_id: self.tunnel_id.clone(),
+                upstream_port: http_port,
+            },
+            keys,
+        )
+        .spawn();
+        Ok(())
+    }
+
+    /// The tunnel is unavailable without the `websocket` and `crypto-openssl` features
+    #[cfg(not(all(feature = "websocket", feature = "crypto-openssl")))]
+    async fn start(&self, _http_port: u16, _keys: Arc<KeyStore>) -> Result<()> {
+        if self.tunnel_relay.is_some() {
+            return Err(loxone_mcp_rust::LoxoneError::config(
+                "Tunnel requested but the server was built without the `websocket` and `crypto-openssl` features",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Connection limits for the HTTP transports
+#[derive(Args, Debug, Clone)]
+struct ConnectionLimitArgs {
+    /// Maximum open connections across all clients
+    #[arg(long, env = "LOXONE_MAX_CONNECTIONS")]
+    max_connections: Option<usize>,
+
+    /// Maximum open connections from one IP address
+    #[arg(long, env = "LOXONE_MAX_CONNECTIONS_PER_IP")]
+    max_connections_per_ip: Option<usize>,
+}
+
+impl ConnectionLimitArgs {
+    /// Validated limits
+    fn config(&self) -> Result<ConnectionLimitsConfig> {
+        let config = ConnectionLimitsConfig {
+            max_connections: self.max_connections,
+            max_per_ip: self.max_connections_per_ip,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Public status page for the HTTP transports
+#[derive(Args, Debug, Clone)]
+struct StatusPageArgs {
+    /// Port for an unauthenticated status page (coarse health and uptime only)
+    #[arg(long, env = "LOXONE_STATUS_PAGE_PORT")]
+    status_page_port: Option<u16>,
+
+    /// Address the status page binds to
+    #[arg(long, env = "LOXONE_STATUS_PAGE_BIND", default_value = "0.0.0.0")]
+    status_page_bind: std::net::IpAddr,
+
+    /// Status page requests per minute from one IP address
+    #[arg(long, env = "LOXONE_STATUS_PAGE_RATE_LIMIT", default_value = "30")]
+    status_page_rate_limit: u32,
+}
+
+impl StatusPageArgs {
+    /// Serve the status page, if a port is configured
+    async fn start(&self, probe: StatusProbe) -> Result<()> {
+        let Some(port) = self.status_page_port else {
+            return Ok(());
+        };
+        status_page::spawn(
+            StatusPageConfig {
+                listen_addr: std::net::SocketAddr::new(self.status_page_bind, port),
+                requests_per_minute: self.status_page_rate_limit,
+            },
+            probe,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Warm-standby replication for the HTTP transports
+#[derive(Args, Debug, Clone)]
+struct ReplicationArgs {
+    /// Directory for state snapshots; the primary publishes, a standby applies
+    #[arg(long, env = "LOXONE_REPLICA_DIR")]
+    replica_dir: Option<PathBuf>,
+
+    /// Run as warm standby of the primary with this health URL
+    /// (e.g. http://primary:8090/status.json)
+    #[arg(long, env = "LOXONE_STANDBY_OF", requires = "replica_dir")]
+    standby_of: Option<String>,
+
+    /// Seconds between snapshots on the primary and health checks on a standby
+    #[arg(long, env = "LOXONE_REPLICA_INTERVAL", default_value = "10")]
+    replica_interval_secs: u64,
+
+    /// Failed health checks in a row before a standby takes over
+    #[arg(long, env = "LOXONE_STANDBY_FAILOVER_AFTER", default_value = "3")]
+    standby_failover_after: u32,
+}
+
+impl ReplicationArgs {
+    fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.replica_interval_secs.max(1))
+    }
+
+    /// On a standby, mirror the primary until it goes dark
+    ///
+    /// Returns the gate limiting the standby to read tools; `None` on a
+    /// primary.
+    async fn wait_if_standby(&self) -> Result<Option<StandbyReadOnly>> {
+        let (Some(primary), Some(replica_dir)) = (&self.standby_of, &self.replica_dir) else {
+            return Ok(None);
+        };
+        let config = StandbyConfig {
+            check_interval: self.interval(),
+            failover_after: self.standby_failover_after,
+            ..StandbyConfig::new(primary.clone(), replica_dir.clone())
+        };
+        StandbyMonitor::new(config, migrations::default_stores())?
+            .wait_for_takeover()
+            .await;
+        Ok(Some(StandbyReadOnly::new(primary.clone())))
+    }
+
+    /// On a primary with a replica directory, publish state snapshots
+    fn start_publisher(&self) {
+        if self.standby_of.is_none()
+            && let Some(replica_dir) = &self.replica_dir
+        {
+            standby::spawn_publisher(
+                migrations::default_stores(),
+                replica_dir.clone(),
+                self.interval(),
+            );
+        }
+    }
 }
 
 impl Config {
@@ -107,12 +487,18 @@ impl Config {
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
         };
 
+        // Daemon output goes to a log file, so skip terminal colors there
         tracing_subscriber::registry()
             .with(filter)
-            .with(fmt::layer().compact())
+            .with(fmt::layer().compact().with_ansi(!self.daemon))
             .init();
     }
 
+    /// PID and log file locations for daemon mode
+    fn daemon_options(&self) -> daemon::DaemonOptions {
+        daemon::DaemonOptions::new(self.pid_file.clone(), self.log_file.clone())
+    }
+
     /// Validate configuration
     fn validate(&self) -> Result<()> {
         let has_credential_id = self.credential_id.is_some();
@@ -120,6 +506,23 @@ impl Config {
             && self.loxone_user.is_some()
             && self.loxone_password.is_some();
 
+        if self.daemon && matches!(self.transport, TransportCommand::Stdio { .. }) {
+            return Err(loxone_mcp_rust::LoxoneError::config(
+                "--daemon is only supported with the HTTP transports",
+            ));
+        }
+
+        if self.self_test && self.daemon {
+            return Err(loxone_mcp_rust::LoxoneError::config(
+                "--self-test runs in the foreground and cannot be combined with --daemon",
+            ));
+        }
+
+        // A dry run only reads local files
+        if self.migrate_dry_run {
+            return Ok(());
+        }
+
         match &self.transport {
             TransportCommand::Stdio { offline } => {
                 if !offline && !has_credential_id && !has_direct_credentials {
@@ -135,6 +538,10 @@ impl Config {
                     ));
                 }
             }
+            TransportCommand::SystemdUnit { .. }
+            | TransportCommand::Observability { .. }
+            | TransportCommand::Stop { .. }
+            | TransportCommand::Status => {}
             TransportCommand::StreamableHttp { .. } => {
                 if !has_credential_id && !has_direct_credentials {
                     return Err(loxone_mcp_rust::LoxoneError::config(
@@ -147,6 +554,192 @@ impl Config {
     }
 }
 
+/// Print or write the systemd unit files requested by the `systemd-unit` subcommand
+fn write_systemd_units(config: &Config) -> Result<()> {
+    let TransportCommand::SystemdUnit {
+        transport,
+        port,
+        user,
+        binary,
+        socket_activation,
+        watchdog_secs,
+        output_dir,
+    } = &config.transport
+    else {
+        return Ok(());
+    };
+
+    let binary = match binary {
+        Some(binary) => binary.clone(),
+        None => std::env::current_exe()?,
+    };
+    let options = systemd::UnitOptions {
+        binary,
+        transport: transport.clone(),
+        port: *port,
+        user: user.clone(),
+        credential_id: config.credential_id.clone(),
+        socket_activation: *socket_activation,
+        watchdog: (*watchdog_secs > 0).then(|| std::time::Duration::from_secs(*watchdog_secs)),
+    };
+    options.validate()?;
+
+    let mut units = vec![(
+        "loxone-mcp.service",
+        systemd::generate_service_unit(&options),
+    )];
+    if options.socket_activation {
+        units.push(("loxone-mcp.socket", systemd::generate_socket_unit(&options)));
+    }
+
+    match output_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            for (name, contents) in &units {
+                let path = dir.join(name);
+                std::fs::write(&path, contents)?;
+                println!("Wrote {}", path.display());
+            }
+        }
+        None => {
+            for (name, contents) in &units {
+                println!("# {name}\n{contents}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print or write the files requested by the `observability` subcommand
+fn write_observability_assets(job: &str, output_dir: Option<&std::path::Path>) -> Result<()> {
+    let options = observability::ObservabilityOptions {
+        job: job.to_string(),
+    };
+    let dashboard = serde_json::to_string_pretty(&observability::grafana_dashboard(&options))?;
+    let files = [
+        (
+            observability::ALERT_RULES_FILE,
+            observability::render_alert_rules(&options),
+        ),
+        (observability::DASHBOARD_FILE, dashboard),
+    ];
+
+    match output_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            for (name, contents) in &files {
+                let path = dir.join(name);
+                std::fs::write(&path, contents)?;
+                println!("Wrote {}", path.display());
+            }
+        }
+        None => {
+            for (name, contents) in &files {
+                println!("# {name}\n{contents}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Serve an HTTP transport, honouring systemd socket activation and TLS termination
+///
+/// The framework transport listens on loopback only; `gateway` checks the
+/// API key of every request in front of it, and each session runs as the
+/// key that opened it.
+#[allow(clippy::too_many_arguments)]
+async fn serve_http_transport(
+    server: LoxoneMcpServer,
+    gateway: HttpGateway,
+    port: u16,
+    tls: &TlsArgs,
+    tunnel: &TunnelArgs,
+    limits: &ConnectionLimitArgs,
+    status_page: &StatusPageArgs,
+    label: &str,
+) -> Result<()> {
+    let limits = limits.config()?;
+    let limiter = ConnectionLimiter::global();
+    limiter.configure(limits.clone());
+    limiter.start_publishing();
+
+    // An inherited socket or the connection limiter owns the public port, so
+    // the gateway moves to loopback as well
+    let activated = systemd::take_activated_listener()?;
+    let fronted = activated.is_some() || limits.is_limited();
+    let gateway_port = if fronted {
+        systemd::free_loopback_port()?
+    } else {
+        port
+    };
+    let upstream_port = systemd::free_loopback_port()?;
+
+    let lifetime_metrics = server.lifetime_metrics().clone();
+    let status_probe = server.status_probe();
+    let subscriptions = Arc::new(SubscriptionCoordinator::new().await?);
+    subscriptions.start().await?;
+    let mut mcp_server = SessionBackend::new(server, gateway.sessions())
+        .with_subscriptions(subscriptions.clone())
+        .serve_http(upstream_port)
+        .await?;
+    let gateway = gateway.with_subscriptions(subscriptions);
+    let gateway_ip: std::net::IpAddr = if fronted || gateway.is_dev_mode() {
+        [127, 0, 0, 1].into()
+    } else {
+        [0, 0, 0, 0].into()
+    };
+    let keys = gateway.keys();
+    gateway
+        .spawn(GatewayConfig {
+            listen_addr: std::net::SocketAddr::new(gateway_ip, gateway_port),
+            upstream_port,
+        })
+        .await?;
+
+    if let Some(listener) = activated {
+        systemd::forward_activated_listener(listener, gateway_port)?;
+        info!("✅ Server started ({label}, socket activated, internal port {gateway_port})");
+    } else if limits.is_limited() {
+        connection_limits::spawn_forwarder(
+            std::net::SocketAddr::from(([0, 0, 0, 0], port)),
+            gateway_port,
+            limiter.clone(),
+        )
+        .await?;
+        info!("✅ Server started ({label} port {port}, internal port {gateway_port})");
+    } else {
+        info!("✅ Server started ({label} port {port})");
+    }
+    tls.start(gateway_port).await?;
+    tunnel.start(gateway_port, keys.clone()).await?;
+    status_page.start(status_probe).await?;
+    guest_access::spawn_expiry_cleanup(keys);
+    systemd::notify_ready(&format!("Serving {label}"));
+
+    let run_result: std::result::Result<(), _> = mcp_server.run().await;
+    systemd::notify_stopping();
+    save_lifetime_metrics(&lifetime_metrics);
+    run_result
+        .map_err(|e| loxone_mcp_rust::LoxoneError::connection(format!("Server error: {e}")))?;
+    Ok(())
+}
+
+/// Tenants of the `--tenants-file`, for tenant-scoped API keys
+fn load_tenants(config: &Config) -> Result<Option<Arc<TenantRegistry>>> {
+    config
+        .tenants_file
+        .as_ref()
+        .map(|path| TenantRegistry::load_file(path).map(Arc::new))
+        .transpose()
+}
+
+/// Save the lifetime totals before exiting
+fn save_lifetime_metrics(metrics: &LifetimeMetrics) {
+    if let Err(e) = metrics.save() {
+        warn!("Failed to save lifetime metrics: {e}");
+    }
+}
+
 /// Load credentials from credential ID
 async fn load_credentials_by_id(credential_id: &str) -> Result<(String, String, String)> {
     let registry = CredentialRegistry::load()?;
@@ -176,23 +769,117 @@ async fn try_auto_detect_credentials() -> Result<(String, String, String)> {
     let manager = create_best_credential_manager().await?;
     let credentials = manager.get_credentials().await?;
 
-    let host = std::env::var("LOXONE_HOST").map_err(|_| {
+    let host = env_or_secret("LOXONE_HOST")?.ok_or_else(|| {
         loxone_mcp_rust::LoxoneError::config("LOXONE_HOST environment variable not set".to_string())
     })?;
 
     Ok((host, credentials.username, credentials.password))
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+/// Resolve the tenant the server is scoped to
+///
+/// Guest keys are always limited to their devices. Otherwise `--tenant`
+/// wins, then the tenant of the API key (if the key store tags it with
+/// one). Returns `None` for unscoped servers.
+async fn resolve_tenant_scope(
+    config: &Config,
+    keys: &KeyStore,
+    api_key: Option<&str>,
+) -> Result<Option<TenantScope>> {
+    if let Some(key) = api_key
+        && let Some(scope) = keys
+            .get_key(key)
+            .await
+            .as_ref()
+            .and_then(guest_access::guest_scope)
+    {
+        return Ok(Some(scope));
+    }
+    let tenant = match (&config.tenant, api_key) {
+        (Some(tenant), _) => Some(tenant.clone()),
+        (None, Some(key)) => keys.tenant_for_key(key).await,
+        (None, None) => None,
+    };
+    let Some(tenant) = tenant else {
+        return Ok(None);
+    };
+
+    let path = config.tenants_file.as_ref().ok_or_else(|| {
+        loxone_mcp_rust::LoxoneError::config(format!(
+            "Tenant '{tenant}' requested but no --tenants-file / LOXONE_TENANTS_FILE configured"
+        ))
+    })?;
+    TenantRegistry::load_file(path)?.scope(&tenant).map(Some)
+}
+
+/// Print the state of a daemonized server; exits with 3 when it is not running
+fn print_daemon_status(config: &Config) -> Result<()> {
+    let options = config.daemon_options();
+    let status = daemon::status(&options.pid_file)?;
+    println!("loxone-mcp-server: {status}");
+    if !matches!(status, daemon::DaemonStatus::Running(_)) {
+        std::process::exit(3);
+    }
+    Ok(())
+}
+
+/// Stop a daemonized server
+fn stop_daemon(config: &Config, timeout_secs: u64) -> Result<()> {
+    let options = config.daemon_options();
+    match daemon::stop(
+        &options.pid_file,
+        std::time::Duration::from_secs(timeout_secs),
+    )? {
+        daemon::DaemonStatus::Running(pid) => println!("Stopped loxone-mcp-server (pid {pid})"),
+        status => println!("loxone-mcp-server: {status}"),
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
     let config = Config::parse();
 
+    // Validate configuration
+    config.validate()?;
+
+    match config.transport {
+        TransportCommand::SystemdUnit { .. } => return write_systemd_units(&config),
+        TransportCommand::Observability {
+            ref job,
+            ref output_dir,
+        } => return write_observability_assets(job, output_dir.as_deref()),
+        TransportCommand::Stop { timeout } => return stop_daemon(&config, timeout),
+        TransportCommand::Status => return print_daemon_status(&config),
+        _ => {}
+    }
+
+    if config.migrate_dry_run {
+        let report = migrations::migrate(&migrations::default_stores(), true)?;
+        print!("{report}");
+        return Ok(());
+    }
+
+    // Detach before the runtime starts: forking a multi-threaded process is unsound
+    if config.daemon {
+        daemon::daemonize(&config.daemon_options())?;
+    }
+
     // Initialize logging
     config.initialize_logging();
 
-    // Validate configuration
-    config.validate()?;
+    // Upgrade persisted files before anything reads them
+    let report = migrations::migrate(&migrations::default_stores(), false)?;
+    if report.changed() {
+        info!("{}", report.to_string().trim_end());
+    }
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(config))
+}
 
+async fn run(config: Config) -> Result<()> {
     if config.insecure {
         warn!(
             "SSL certificate verification is DISABLED (--insecure). This is not recommended for production use."
@@ -249,64 +936,116 @@ async fn main() -> Result<()> {
         }
     };
 
+    let room_metadata = match &config.rooms_file {
+        Some(path) => {
+            let metadata = RoomMetadataConfig::load_file(path)?;
+            info!("🏠 Loaded metadata for {} rooms", metadata.rooms.len());
+            metadata
+        }
+        None => RoomMetadataConfig::default(),
+    };
+
+    // One key store for the whole process, so guest keys minted by a tool
+    // are valid at once and the expiry cleanup sees them
+    let keys = Arc::new(KeyStore::new(KeyStoreConfig::default()).await?);
+
     // Build a LoxoneMcpServer with Loxone client for all online modes
-    let build_mcp_server =
-        |loxone_host: &str, loxone_user: &str, loxone_password: &str, insecure: bool| {
-            let host = loxone_host.to_string();
-            let user = loxone_user.to_string();
-            let pass = loxone_password.to_string();
-            async move {
-                use loxone_mcp_rust::client::{ClientContext, LoxoneHttpClient};
-                use loxone_mcp_rust::config::credentials::LoxoneCredentials;
-                use loxone_mcp_rust::services::SensorTypeRegistry;
-
-                let loxone_url: url::Url = format!("http://{host}").parse().map_err(|e| {
-                    loxone_mcp_rust::LoxoneError::config(format!("Invalid URL: {e}"))
+    let build_mcp_server = |loxone_host: &str,
+                            loxone_user: &str,
+                            loxone_password: &str,
+                            insecure: bool,
+                            tenant: Option<TenantScope>,
+                            api_key: Option<&str>| {
+        let host = loxone_host.to_string();
+        let user = loxone_user.to_string();
+        let pass = loxone_password.to_string();
+        let room_metadata = room_metadata.clone();
+        let keys = keys.clone();
+        let api_key = api_key.map(str::to_string);
+        async move {
+            use loxone_mcp_rust::client::{ClientContext, LoxoneHttpClient, TenantScopedClient};
+            use loxone_mcp_rust::config::credentials::LoxoneCredentials;
+            use loxone_mcp_rust::services::SensorTypeRegistry;
+
+            let loxone_url: url::Url = format!("http://{host}")
+                .parse()
+                .map_err(|e| loxone_mcp_rust::LoxoneError::config(format!("Invalid URL: {e}")))?;
+
+            let loxone_cfg = loxone_mcp_rust::config::LoxoneConfig {
+                url: loxone_url,
+                timeout: std::time::Duration::from_secs(30),
+                verify_ssl: !insecure,
+                ..Default::default()
+            };
+
+            let credentials = LoxoneCredentials {
+                username: user,
+                password: pass,
+                api_key: None,
+                #[cfg(feature = "crypto-openssl")]
+                public_key: None,
+            };
+
+            let client = LoxoneHttpClient::new(loxone_cfg, credentials)
+                .await
+                .map_err(|e| {
+                    loxone_mcp_rust::LoxoneError::connection(format!(
+                        "Failed to create client: {e}"
+                    ))
                 })?;
 
-                let loxone_cfg = loxone_mcp_rust::config::LoxoneConfig {
-                    url: loxone_url,
-                    timeout: std::time::Duration::from_secs(30),
-                    verify_ssl: !insecure,
-                    ..Default::default()
-                };
-
-                let credentials = LoxoneCredentials {
-                    username: user,
-                    password: pass,
-                    api_key: None,
-                    #[cfg(feature = "crypto-openssl")]
-                    public_key: None,
-                };
-
-                let client = LoxoneHttpClient::new(loxone_cfg, credentials)
-                    .await
-                    .map_err(|e| {
-                        loxone_mcp_rust::LoxoneError::connection(format!(
-                            "Failed to create client: {e}"
-                        ))
-                    })?;
-
-                let context = Arc::new(ClientContext::new());
-                let client_arc: Arc<dyn loxone_mcp_rust::client::LoxoneClient> = Arc::new(client);
-                let sensor_registry = Arc::new(SensorTypeRegistry::new());
-                let value_resolver =
-                    Arc::new(loxone_mcp_rust::services::UnifiedValueResolver::new(
-                        client_arc.clone(),
-                        sensor_registry,
-                    ));
+            let context = Arc::new(ClientContext::new());
+            let mut client_arc: Arc<dyn loxone_mcp_rust::client::LoxoneClient> = Arc::new(client);
+            if let Some(scope) = tenant {
+                info!("🏢 Scoping server to tenant '{}'", scope.tenant().id);
+                client_arc = Arc::new(TenantScopedClient::new(client_arc, scope));
+            }
+            let sensor_registry = Arc::new(SensorTypeRegistry::new());
+            let value_resolver = Arc::new(loxone_mcp_rust::services::UnifiedValueResolver::new(
+                client_arc.clone(),
+                sensor_registry,
+            ));
 
-                info!("✅ Loxone client connected");
+            info!("✅ Loxone client connected");
 
-                Ok::<LoxoneMcpServer, loxone_mcp_rust::LoxoneError>(LoxoneMcpServer::with_context(
-                    client_arc,
-                    context,
-                    value_resolver,
-                    None,
-                    LoxoneServerConfig::default(),
-                ))
+            let mut server = LoxoneMcpServer::with_context(
+                client_arc,
+                context,
+                value_resolver,
+                None,
+                LoxoneServerConfig {
+                    room_metadata,
+                    ..Default::default()
+                },
+            )
+            .with_key_store(keys);
+            if let Some(key) = api_key {
+                server = server.with_api_key(&key);
             }
+            server.start_background_jobs();
+
+            Ok::<LoxoneMcpServer, loxone_mcp_rust::LoxoneError>(server)
+        }
+    };
+
+    if config.self_test {
+        let api_key = match &config.transport {
+            TransportCommand::Http { api_key, .. } => api_key.as_deref(),
+            _ => None,
         };
+        let server = build_mcp_server(
+            &loxone_host,
+            &loxone_user,
+            &_loxone_password,
+            config.insecure,
+            resolve_tenant_scope(&config, &keys, api_key).await?,
+            api_key,
+        )
+        .await?;
+        let report = loxone_mcp_rust::server::self_test::run(&server).await;
+        println!("{report}");
+        std::process::exit(report.exit_code());
+    }
 
     match config.transport {
         TransportCommand::Stdio { offline } => {
@@ -322,22 +1061,45 @@ async fn main() -> Result<()> {
                     &loxone_user,
                     &_loxone_password,
                     config.insecure,
+                    resolve_tenant_scope(&config, &keys, None).await?,
+                    None,
                 )
                 .await?
             };
+            // The stdio client is the local operator
+            let server = server.with_client_session(
+                SessionTransport::Stdio,
+                OPERATIONS.iter().map(|op| op.to_string()).collect(),
+            );
 
-            let mut mcp_server = server.serve_stdio().await.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Failed to start server: {e}"))
-            })?;
+            let lifetime_metrics = server.lifetime_metrics().clone();
+            let mut mcp_server = SessionBackend::local(server).serve_stdio().await?;
             info!("✅ Server started (stdio)");
-            mcp_server.run().await.map_err(|e| {
+            systemd::notify_ready("Serving stdio");
+            let run_result = mcp_server.run().await;
+            systemd::notify_stopping();
+            save_lifetime_metrics(&lifetime_metrics);
+            run_result.map_err(|e| {
                 loxone_mcp_rust::LoxoneError::connection(format!("Server error: {e}"))
             })?;
         }
 
-        TransportCommand::Http { port, dev_mode, .. } => {
-            let server = if dev_mode {
-                warn!("Development mode enabled — no auth, localhost only");
+        TransportCommand::Http {
+            port,
+            dev_mode,
+            ref tls,
+            ref tunnel,
+            ref limits,
+            ref status_page,
+            ref replication,
+            ref api_key,
+            ..
+        } => {
+            let standby = replication.wait_if_standby().await?;
+            let mut server = if dev_mode {
+                warn!(
+                    "Development mode enabled — requests without a key act as the local operator, localhost only"
+                );
                 LoxoneMcpServer::with_defaults()
             } else {
                 info!(
@@ -349,49 +1111,81 @@ async fn main() -> Result<()> {
                     &loxone_user,
                     &_loxone_password,
                     config.insecure,
+                    resolve_tenant_scope(&config, &keys, api_key.as_deref()).await?,
+                    api_key.as_deref(),
                 )
                 .await?
             };
+            if let Some(gate) = standby {
+                server = server.with_tool_middleware(Arc::new(gate));
+            }
+            replication.start_publisher();
+            let gateway = HttpGateway::new(server.clone(), keys.clone())
+                .with_tenants(load_tenants(&config)?)
+                .with_dev_mode(dev_mode);
 
-            let serve_result: std::result::Result<
-                pulseengine_mcp_server::McpServer<LoxoneMcpServer>,
-                _,
-            > = server.serve_http(port).await;
-            let mut mcp_server = serve_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Failed to start server: {e}"))
-            })?;
-            info!("✅ Server started (HTTP port {})", port);
-            let run_result: std::result::Result<(), _> = mcp_server.run().await;
-            run_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Server error: {e}"))
-            })?;
+            serve_http_transport(
+                server,
+                gateway,
+                port,
+                tls,
+                tunnel,
+                limits,
+                status_page,
+                "HTTP",
+            )
+            .await?;
         }
 
-        TransportCommand::StreamableHttp { port, .. } => {
+        TransportCommand::StreamableHttp {
+            port,
+            ref tls,
+            ref tunnel,
+            ref limits,
+            ref status_page,
+            ref replication,
+            ..
+        } => {
+            let standby = replication.wait_if_standby().await?;
             info!(
                 "🚀 Starting MCP server with Loxone connection (Streamable HTTP port {})",
                 port
             );
-            let server = build_mcp_server(
+            let mut server = build_mcp_server(
                 &loxone_host,
                 &loxone_user,
                 &_loxone_password,
                 config.insecure,
+                resolve_tenant_scope(&config, &keys, None).await?,
+                None,
             )
             .await?;
+            if let Some(gate) = standby {
+                server = server.with_tool_middleware(Arc::new(gate));
+            }
+            replication.start_publisher();
+            let gateway = HttpGateway::new(server.clone(), keys.clone())
+                .with_tenants(load_tenants(&config)?)
+                .with_session_transport(SessionTransport::StreamableHttp);
 
-            let serve_result: std::result::Result<
-                pulseengine_mcp_server::McpServer<LoxoneMcpServer>,
-                _,
-            > = server.serve_http(port).await;
-            let mut mcp_server = serve_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Failed to start server: {e}"))
-            })?;
-            info!("✅ Server started (Streamable HTTP port {})", port);
-            let run_result: std::result::Result<(), _> = mcp_server.run().await;
-            run_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Server error: {e}"))
-            })?;
+            serve_http_transport(
+                server,
+                gateway,
+                port,
+                tls,
+                tunnel,
+                limits,
+                status_page,
+                "Streamable HTTP",
+            )
+            .await?;
+        }
+
+        TransportCommand::SystemdUnit { .. }
+        | TransportCommand::Observability { .. }
+        | TransportCommand::Stop { .. }
+        | TransportCommand::Status => {
+            unreachable!("management commands return before the runtime starts")
         }
     }
 
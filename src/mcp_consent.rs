@@ -19,6 +19,9 @@ use tokio::sync::{RwLock, mpsc};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// How often a waiting consent request checks for an answer
+const RESPONSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Sensitivity levels for different operations
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -369,15 +372,10 @@ impl ConsentManager {
             warn!("Failed to send consent request to UI: {}", e);
         }
 
-        // Wait for response or timeout
+        // Wait for response or timeout; approvals the user wants to reuse
+        // are cached by `process_response`
         let decision = self.wait_for_consent_response(request_id).await?;
 
-        // Cache the decision if appropriate
-        if matches!(decision, ConsentDecision::Approved) {
-            self.cache_consent_decision(&request.operation, &decision)
-                .await;
-        }
-
         // Record the decision
         self.record_consent_decision(request, decision.clone())
             .await;
@@ -612,29 +610,56 @@ impl ConsentManager {
         }
     }
 
-    /// Wait for consent response with timeout
+    /// Wait until the request is answered or the timeout passes
+    ///
+    /// Answers arrive through [`Self::process_response`] or the response
+    /// channel of [`Self::setup_channels`].
     async fn wait_for_consent_response(&self, request_id: Uuid) -> Result<ConsentDecision> {
-        // This is a simplified implementation
-        // In practice, you'd want to use proper async waiting with timeout
-        tokio::time::sleep(Duration::from_millis(100)).await;
-
-        // Check if response was processed
-        let pending = self.pending_requests.read().await;
-        if pending.contains_key(&request_id) {
-            // Still pending, return timeout
-            Ok(ConsentDecision::TimedOut)
-        } else {
-            // Response was processed, find it in history
-            let history = self.decision_history.read().await;
-            for record in history.iter().rev() {
-                if record.request.id == request_id {
-                    return Ok(record.decision.clone());
-                }
+        let deadline = tokio::time::Instant::now() + self.config.default_timeout;
+        loop {
+            self.drain_responses().await?;
+            if !self.pending_requests.read().await.contains_key(&request_id) {
+                let history = self.decision_history.read().await;
+                return Ok(history
+                    .iter()
+                    .rev()
+                    .find(|record| record.request.id == request_id)
+                    .map(|record| record.decision.clone())
+                    .unwrap_or(ConsentDecision::TimedOut));
+            }
+            if tokio::time::Instant::now() >= deadline {
+                self.pending_requests.write().await.remove(&request_id);
+                return Ok(ConsentDecision::TimedOut);
             }
-            Ok(ConsentDecision::TimedOut)
+            tokio::time::sleep(RESPONSE_POLL_INTERVAL).await;
         }
     }
 
+    /// Process responses waiting in the response channel
+    async fn drain_responses(&self) -> Result<()> {
+        let responses: Vec<ConsentResponse> = match self.response_receiver.write().await.as_mut() {
+            Some(receiver) => std::iter::from_fn(|| receiver.try_recv().ok()).collect(),
+            None => Vec::new(),
+        };
+        for response in responses {
+            self.process_response(response).await?;
+        }
+        Ok(())
+    }
+
+    /// Requests waiting for the user's answer, oldest first
+    pub async fn pending_requests(&self) -> Vec<ConsentRequest> {
+        let mut pending: Vec<ConsentRequest> = self
+            .pending_requests
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect();
+        pending.sort_by_key(|request| request.created_at);
+        pending
+    }
+
     /// Record consent decision for audit trail
     async fn record_consent_decision(&self, request: ConsentRequest, decision: ConsentDecision) {
         if !self.config.audit_all_decisions {
@@ -0,0 +1,55 @@
+//! Assertion helpers for tool results and sent commands
+//!
+//! Tools return `Result<Value, String>`; structured errors (unknown room,
+//! disabled feature, unavailable capability) are JSON encoded in the error
+//! string. These helpers unwrap both with readable panic messages.
+
+use super::ScriptedClient;
+use serde_json::Value;
+
+/// Unwrap a successful tool result
+#[track_caller]
+pub fn assert_tool_ok(result: Result<Value, String>) -> Value {
+    match result {
+        Ok(value) => value,
+        Err(error) => panic!("expected tool to succeed, got error: {error}"),
+    }
+}
+
+/// Unwrap a failed tool result, checking that the error mentions `needle`
+///
+/// Returns the error parsed as JSON for structured errors and as a JSON
+/// string otherwise.
+#[track_caller]
+pub fn assert_tool_error(result: Result<Value, String>, needle: &str) -> Value {
+    let error = match result {
+        Ok(value) => panic!("expected tool to fail, got: {value}"),
+        Err(error) => error,
+    };
+    assert!(
+        error.contains(needle),
+        "expected tool error to contain '{needle}', got: {error}"
+    );
+    serde_json::from_str(&error).unwrap_or(Value::String(error))
+}
+
+/// Assert that `command` was sent to `uuid`
+#[track_caller]
+pub fn assert_command_sent(client: &ScriptedClient, uuid: &str, command: &str) {
+    let sent = client.commands_for(uuid);
+    assert!(
+        sent.iter().any(|c| c == command),
+        "expected '{command}' to be sent to {uuid}, sent: {sent:?} (all: {:?})",
+        client.commands()
+    );
+}
+
+/// Assert that nothing was sent to `uuid`
+#[track_caller]
+pub fn assert_no_command_sent(client: &ScriptedClient, uuid: &str) {
+    let sent = client.commands_for(uuid);
+    assert!(
+        sent.is_empty(),
+        "expected no commands to {uuid}, sent: {sent:?}"
+    );
+}
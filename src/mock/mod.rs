@@ -1,6 +1,28 @@
 //! Mock implementations for testing
 //!
 //! This module provides mock clients and components for testing purposes.
+//! It is public with the `test-utils` feature, so applications embedding
+//! [`LoxoneMcpServer`](crate::server::macro_backend::LoxoneMcpServer) can reuse it:
+//!
+//! - [`StructureBuilder`] and the [`devices`] factories build structure files,
+//! - [`ScriptedClient`] records commands and answers from scripted rules,
+//! - [`TestServer`] runs the tools against a scripted client,
+//! - [`assert_tool_ok`], [`assert_tool_error`] and [`assert_command_sent`]
+//!   check the outcome.
+
+pub mod assertions;
+pub mod scripted;
+pub mod server;
+pub mod structure;
+
+pub use assertions::{
+    assert_command_sent, assert_no_command_sent, assert_tool_error, assert_tool_ok,
+};
+pub use scripted::{ScriptedClient, SentCommand};
+pub use server::{TestServer, isolated_config};
+pub use structure::{
+    DeviceSpec, StructureBuilder, device_uuid, devices, room_uuid, sample_house, state_uuid,
+};
 
 use crate::client::{LoxoneClient, LoxoneResponse, LoxoneStructure};
 use crate::error::Result;
@@ -86,6 +108,90 @@ impl LoxoneClient for MockLoxoneClient {
         Ok(self.connected)
     }
 
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        Ok(chrono::Local::now().naive_local())
+    }
+
+    async fn send_calendar_command(&self, command: &str) -> Result<LoxoneResponse> {
+        let value = if command.starts_with("calendargetentries") {
+            Value::String("[]".to_string())
+        } else {
+            Value::String("OK".to_string())
+        };
+        Ok(LoxoneResponse { code: 200, value })
+    }
+
+    async fn get_air_devices(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "devices": [
+                {"serial": "50:4F:94:10:2A:01", "name": "Window Kitchen",
+                 "type": "Door & Window Contact Air", "battery": 85, "online": true,
+                 "rssi": -71},
+                {"serial": "50:4F:94:10:2A:02", "name": "Smoke Detector Hall",
+                 "type": "Smoke Detector Air", "battery": 14, "online": true,
+                 "rssi": -80},
+                {"serial": "50:4F:94:10:2A:03", "name": "Smart Socket",
+                 "type": "Smart Socket Air", "online": false}
+            ]
+        }))
+    }
+
+    async fn get_device_status(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "extensions": [
+                {"serial": "13:00:00:01", "name": "Tree", "type": "TreeInterface",
+                 "online": true, "branches": [
+                    {"name": "Left", "devices": [
+                        {"serial": "B0:4F:94:00:00:01", "name": "Touch Kitchen",
+                         "type": "Touch Tree", "online": true}]}]},
+                {"serial": "12:00:00:01", "name": "Air", "type": "AirInterface",
+                 "online": true, "devices": [
+                    {"serial": "50:4F:94:10:2A:01", "name": "Window Kitchen",
+                     "type": "Door & Window Contact Air", "online": true},
+                    {"serial": "50:4F:94:10:2A:03", "name": "Smart Socket",
+                     "type": "Smart Socket Air", "online": false}]}
+            ]
+        }))
+    }
+
+    async fn get_weather_forecast(&self) -> Result<Value> {
+        // Two days of hourly entries starting with the current hour
+        let start = chrono::Utc::now().timestamp() / 3600 * 3600;
+        let hourly: Vec<Value> = (0..48)
+            .map(|hour| {
+                let timestamp = start + hour * 3600;
+                let daytime = (timestamp / 3600 % 24) as f64;
+                serde_json::json!({
+                    "timestamp": timestamp,
+                    "temperature": 12.0 + 6.0 * ((daytime - 9.0) / 24.0 * std::f64::consts::TAU).sin(),
+                    "perceivedTemperature": 11.0,
+                    "precipitation": if (30..36).contains(&hour) { 1.2 } else { 0.0 },
+                    "precipitationProbability": if (30..36).contains(&hour) { 80 } else { 10 },
+                    "windSpeed": 14.0,
+                    "windDirection": 250,
+                    "relativeHumidity": 65,
+                    "cloudCover": 40,
+                    "weatherType": 3
+                })
+            })
+            .collect();
+        Ok(serde_json::json!({"hourly": hourly}))
+    }
+
+    async fn download_file(&self, path: &str) -> Result<Vec<u8>> {
+        match path.trim_start_matches('/') {
+            "dev/fslist/prog" => Ok(
+                b"-      48213 Tue Mar 04 09:12:40 2025 sps_150_20250304091240.zip\n\
+                -      48957 Sat Mar 08 18:30:02 2025 sps_151_20250308183002.zip\n"
+                    .to_vec(),
+            ),
+            p if p.starts_with("dev/fsget/prog/sps_") => Ok(b"PK\x03\x04mock program".to_vec()),
+            _ => Err(crate::error::LoxoneError::not_found(format!(
+                "Mock file {path} not found"
+            ))),
+        }
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -0,0 +1,274 @@
+//! Scripted mock client
+//!
+//! [`ScriptedClient`] serves a fixed structure, records every command sent
+//! and answers with scripted responses, so a test can drive a tool and then
+//! check what reached the "Miniserver":
+//!
+//! ```ignore
+//! # tokio_test::block_on(async {
+//! use loxone_mcp_rust::client::LoxoneClient;
+//! use loxone_mcp_rust::mock::{ScriptedClient, sample_house};
+//!
+//! let client = ScriptedClient::new(sample_house());
+//! client.fail("dead-beef", None, "Device offline");
+//! assert!(client.send_command("dead-beef", "on").await.is_err());
+//! assert_eq!(client.commands().len(), 1);
+//! # });
+//! ```
+
+use crate::client::{LoxoneClient, LoxoneResponse, LoxoneStructure};
+use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A command received by a [`ScriptedClient`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentCommand {
+    pub uuid: String,
+    pub command: String,
+}
+
+/// What a scripted command answers
+#[derive(Debug, Clone)]
+enum Reply {
+    Respond(LoxoneResponse),
+    Fail(String),
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    uuid: String,
+    /// `None` matches any command
+    command: Option<String>,
+    reply: Reply,
+    /// States changed when the rule matches
+    state_updates: Vec<(String, Value)>,
+}
+
+impl Rule {
+    fn matches(&self, uuid: &str, command: &str) -> bool {
+        self.uuid == uuid && self.command.as_deref().is_none_or(|c| c == command)
+    }
+}
+
+/// Mock client answering from scripted rules
+///
+/// Commands without a matching rule succeed with code 200 and value `"1"`.
+/// Rules added later take precedence. States default to nothing; set them
+/// with [`Self::set_state`] by device or state UUID.
+pub struct ScriptedClient {
+    structure: Mutex<LoxoneStructure>,
+    rules: Mutex<Vec<Rule>>,
+    states: Mutex<HashMap<String, Value>>,
+    commands: Mutex<Vec<SentCommand>>,
+    connected: Mutex<bool>,
+}
+
+impl ScriptedClient {
+    /// Connected client serving `structure`
+    pub fn new(structure: LoxoneStructure) -> Self {
+        Self {
+            structure: Mutex::new(structure),
+            rules: Mutex::new(Vec::new()),
+            states: Mutex::new(HashMap::new()),
+            commands: Mutex::new(Vec::new()),
+            connected: Mutex::new(true),
+        }
+    }
+
+    fn push_rule(&self, rule: Rule) {
+        self.rules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(rule);
+    }
+
+    /// Answer `command` (any command if `None`) to `uuid` with `value`
+    pub fn respond(&self, uuid: &str, command: Option<&str>, value: Value) {
+        self.respond_with_code(uuid, command, 200, value);
+    }
+
+    /// Answer with a non-200 Loxone response code
+    pub fn respond_with_code(&self, uuid: &str, command: Option<&str>, code: i32, value: Value) {
+        self.push_rule(Rule {
+            uuid: uuid.to_string(),
+            command: command.map(str::to_string),
+            reply: Reply::Respond(LoxoneResponse { code, value }),
+            state_updates: Vec::new(),
+        });
+    }
+
+    /// Fail `command` (any command if `None`) to `uuid` with a connection error
+    pub fn fail(&self, uuid: &str, command: Option<&str>, message: &str) {
+        self.push_rule(Rule {
+            uuid: uuid.to_string(),
+            command: command.map(str::to_string),
+            reply: Reply::Fail(message.to_string()),
+            state_updates: Vec::new(),
+        });
+    }
+
+    /// Change a state when `command` is sent to `uuid`, like a real device
+    pub fn on_command_set_state(&self, uuid: &str, command: &str, state: &str, value: Value) {
+        self.push_rule(Rule {
+            uuid: uuid.to_string(),
+            command: Some(command.to_string()),
+            reply: Reply::Respond(default_response()),
+            state_updates: vec![(state.to_string(), value)],
+        });
+    }
+
+    /// Set the value of a device or state UUID
+    pub fn set_state(&self, uuid: &str, value: Value) {
+        self.states
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(uuid.to_string(), value);
+    }
+
+    /// Replace the served structure
+    pub fn set_structure(&self, structure: LoxoneStructure) {
+        *self.structure.lock().unwrap_or_else(|e| e.into_inner()) = structure;
+    }
+
+    /// Simulate a lost connection (or its return)
+    pub fn set_connected(&self, connected: bool) {
+        *self.connected.lock().unwrap_or_else(|e| e.into_inner()) = connected;
+    }
+
+    /// Commands received so far, oldest first
+    pub fn commands(&self) -> Vec<SentCommand> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Commands received for one UUID
+    pub fn commands_for(&self, uuid: &str) -> Vec<String> {
+        self.commands()
+            .into_iter()
+            .filter(|c| c.uuid == uuid)
+            .map(|c| c.command)
+            .collect()
+    }
+
+    /// Forget the recorded commands
+    pub fn clear_commands(&self) {
+        self.commands
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    fn ensure_connected(&self) -> Result<()> {
+        if *self.connected.lock().unwrap_or_else(|e| e.into_inner()) {
+            Ok(())
+        } else {
+            Err(LoxoneError::connection("Scripted client disconnected"))
+        }
+    }
+
+    fn lookup(&self, uuids: &[String]) -> HashMap<String, Value> {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        uuids
+            .iter()
+            .filter_map(|uuid| states.get(uuid).map(|v| (uuid.clone(), v.clone())))
+            .collect()
+    }
+}
+
+fn default_response() -> LoxoneResponse {
+    LoxoneResponse {
+        code: 200,
+        value: json!("1"),
+    }
+}
+
+#[async_trait]
+impl LoxoneClient for ScriptedClient {
+    async fn connect(&mut self) -> Result<()> {
+        self.set_connected(true);
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> Result<bool> {
+        Ok(self.ensure_connected().is_ok())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.set_connected(false);
+        Ok(())
+    }
+
+    async fn send_command(&self, uuid: &str, command: &str) -> Result<LoxoneResponse> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(SentCommand {
+                uuid: uuid.to_string(),
+                command: command.to_string(),
+            });
+        self.ensure_connected()?;
+        let rule = self
+            .rules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(uuid, command))
+            .cloned();
+        let Some(rule) = rule else {
+            return Ok(default_response());
+        };
+        for (state, value) in rule.state_updates {
+            self.set_state(&state, value);
+        }
+        match rule.reply {
+            Reply::Respond(response) => Ok(response),
+            Reply::Fail(message) => Err(LoxoneError::connection(message)),
+        }
+    }
+
+    async fn get_structure(&self) -> Result<LoxoneStructure> {
+        self.ensure_connected()?;
+        Ok(self
+            .structure
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    }
+
+    async fn get_device_states(&self, uuids: &[String]) -> Result<HashMap<String, Value>> {
+        self.ensure_connected()?;
+        Ok(self.lookup(uuids))
+    }
+
+    async fn get_state_values(&self, state_uuids: &[String]) -> Result<HashMap<String, Value>> {
+        self.ensure_connected()?;
+        Ok(self.lookup(state_uuids))
+    }
+
+    async fn get_system_info(&self) -> Result<Value> {
+        self.ensure_connected()?;
+        Ok(json!({
+            "version": "scripted",
+            "name": "Scripted Miniserver"
+        }))
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(self.ensure_connected().is_ok())
+    }
+
+    async fn get_miniserver_time(&self) -> Result<chrono::NaiveDateTime> {
+        self.ensure_connected()?;
+        Ok(chrono::Local::now().naive_local())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
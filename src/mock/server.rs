@@ -0,0 +1,155 @@
+//! Ready-to-use server fixtures
+//!
+//! [`TestServer`] wires a [`ScriptedClient`] into a [`LoxoneMcpServer`] the
+//! same way the binary does, but with a configuration that writes no files
+//! (no persisted metrics, feature flags file, command log or scene
+//! snapshots), so tests can call tools directly.
+
+use super::ScriptedClient;
+use crate::client::{ClientContext, LoxoneClient, LoxoneStructure};
+use crate::config::ServerConfig;
+use crate::mcp_consent::ConsentResponse;
+use crate::server::feature_flags::FeatureFlagsConfig;
+use crate::server::macro_backend::LoxoneMcpServer;
+use crate::services::{SensorTypeRegistry, UnifiedValueResolver};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Server configuration that keeps everything in memory
+pub fn isolated_config() -> ServerConfig {
+    let mut config = ServerConfig::default();
+    config.lifetime_metrics.persist = false;
+    config.feature_flags = FeatureFlagsConfig {
+        flags: Default::default(),
+        file: None,
+    };
+    config.command_history.log_file = None;
+    config.program_backup.directory = None;
+    config.scene_snapshots.file = None;
+    config
+}
+
+/// A server backed by a scripted client
+pub struct TestServer {
+    pub server: LoxoneMcpServer,
+    pub client: Arc<ScriptedClient>,
+    pub context: Arc<ClientContext>,
+}
+
+impl TestServer {
+    /// Server over `structure` with [`isolated_config`]
+    pub async fn new(structure: LoxoneStructure) -> Self {
+        Self::with_config(structure, isolated_config()).await
+    }
+
+    /// Server over `structure` with a custom configuration
+    pub async fn with_config(structure: LoxoneStructure, config: ServerConfig) -> Self {
+        let client = Arc::new(ScriptedClient::new(structure.clone()));
+        let context = Arc::new(ClientContext::new());
+        context
+            .update_structure(structure)
+            .await
+            .expect("fixture structure must load");
+        let dyn_client: Arc<dyn LoxoneClient> = client.clone();
+        let resolver = Arc::new(UnifiedValueResolver::new(
+            dyn_client.clone(),
+            Arc::new(SensorTypeRegistry::new()),
+        ));
+        let server =
+            LoxoneMcpServer::with_context(dyn_client, context.clone(), resolver, None, config);
+        Self {
+            server,
+            client,
+            context,
+        }
+    }
+
+    /// Answer the next consent request as the user would
+    ///
+    /// Spawn this before calling a consent-gated tool; the returned task
+    /// finishes once it answered.
+    pub fn answer_consent(&self, approved: bool) -> tokio::task::JoinHandle<()> {
+        let consent = self.server.consent_manager().clone();
+        tokio::spawn(async move {
+            loop {
+                if let Some(request) = consent.pending_requests().await.into_iter().next() {
+                    let response = ConsentResponse {
+                        request_id: request.id,
+                        approved,
+                        reason: (!approved).then(|| "Denied in test".to_string()),
+                        responded_at: SystemTime::now(),
+                        validity_duration: None,
+                        apply_to_similar: false,
+                        user_id: None,
+                    };
+                    consent
+                        .process_response(response)
+                        .await
+                        .expect("consent response must be processed");
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+    }
+}
+
+impl std::ops::Deref for TestServer {
+    type Target = LoxoneMcpServer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.server
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{
+        assert_command_sent, assert_no_command_sent, assert_tool_error, assert_tool_ok,
+        device_uuid, sample_house,
+    };
+
+    #[tokio::test]
+    async fn test_tools_against_scripted_client() {
+        let fixture = TestServer::new(sample_house()).await;
+        let ceiling = device_uuid("Kitchen", "Ceiling");
+
+        let result = fixture
+            .control_lights(
+                "room".into(),
+                Some("Kitchen".into()),
+                "on".into(),
+                None,
+                None,
+            )
+            .await;
+        assert_eq!(assert_tool_ok(result)["devices_affected"], 1);
+        assert_command_sent(&fixture.client, &ceiling, "on");
+        assert_no_command_sent(&fixture.client, &device_uuid("Bedroom", "Bedside"));
+
+        fixture.client.fail(&ceiling, None, "Device offline");
+        let result = fixture
+            .control_lights(
+                "device".into(),
+                Some(ceiling.clone()),
+                "off".into(),
+                None,
+                None,
+            )
+            .await;
+        assert_tool_error(result, "Device offline");
+
+        let result = fixture
+            .control_lights(
+                "room".into(),
+                Some("Kichen".into()),
+                "on".into(),
+                None,
+                None,
+            )
+            .await;
+        let error = assert_tool_error(result, "room_not_found");
+        assert_eq!(error["error"]["suggestions"][0], "Kitchen");
+    }
+}
@@ -0,0 +1,302 @@
+//! Builders for Loxone structure files
+//!
+//! Tools read devices from the structure file (`LoxAPP3.json`). The builder
+//! produces a [`LoxoneStructure`] with the shape the Miniserver sends, so
+//! tests need no hand-written JSON:
+//!
+//! ```ignore
+//! use loxone_mcp_rust::mock::{StructureBuilder, devices};
+//!
+//! let structure = StructureBuilder::new()
+//!     .room("Kitchen")
+//!     .device("Kitchen", devices::light("Ceiling"))
+//!     .device("Kitchen", devices::blind("Window"))
+//!     .build();
+//! assert_eq!(structure.controls.len(), 2);
+//! ```
+//!
+//! UUIDs are derived from the names, so they are stable across runs; state
+//! UUIDs are `<device uuid>-<state name>`.
+
+use crate::client::LoxoneStructure;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+/// Deterministic Loxone-style UUID for a name within a kind
+pub fn uuid_for(kind: &str, name: &str) -> String {
+    // FNV-1a, good enough to keep fixture UUIDs apart
+    let hash = format!("{kind}/{name}")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+            (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:016x}",
+        hash >> 32,
+        (hash >> 16) & 0xffff,
+        hash & 0xffff,
+        hash.rotate_left(17)
+    )
+}
+
+/// UUID of a device state as created by [`StructureBuilder`]
+pub fn state_uuid(device_uuid: &str, state: &str) -> String {
+    format!("{device_uuid}-{state}")
+}
+
+/// A device to add with [`StructureBuilder::device`]
+#[derive(Debug, Clone)]
+pub struct DeviceSpec {
+    pub name: String,
+    pub control_type: String,
+    pub category: Option<String>,
+    pub states: Vec<String>,
+    pub details: Value,
+    pub uuid: Option<String>,
+}
+
+impl DeviceSpec {
+    /// Device of any control type without states
+    pub fn new(name: impl Into<String>, control_type: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            control_type: control_type.into(),
+            category: None,
+            states: Vec::new(),
+            details: Value::Null,
+            uuid: None,
+        }
+    }
+
+    /// Add states, each getting a state UUID
+    pub fn states(mut self, states: &[&str]) -> Self {
+        self.states.extend(states.iter().map(|s| s.to_string()));
+        self
+    }
+
+    /// Put the device into a category (created if needed)
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Set the `details` object of the control
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Use a fixed UUID instead of one derived from the name
+    pub fn uuid(mut self, uuid: impl Into<String>) -> Self {
+        self.uuid = Some(uuid.into());
+        self
+    }
+}
+
+/// Factories for common devices, with the control types the tools expect
+pub mod devices {
+    use super::DeviceSpec;
+    use serde_json::json;
+
+    /// Lighting controller, as switched by `control_lights`
+    pub fn light(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "LightController")
+            .category("Lighting")
+            .states(&["activeScene", "sceneList"])
+    }
+
+    /// Dimmable light
+    pub fn dimmer(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "Dimmer")
+            .category("Lighting")
+            .states(&["position", "min", "max", "step"])
+    }
+
+    /// On/off switch
+    pub fn switch(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "Switch")
+            .category("Lighting")
+            .states(&["active"])
+    }
+
+    /// Blind or shading, as moved by `control_blinds`
+    pub fn blind(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "Jalousie")
+            .category("Shading")
+            .states(&["up", "down", "position", "shadePosition", "safetyActive"])
+    }
+
+    /// Intelligent room controller
+    pub fn room_controller(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "IRoomController")
+            .category("Climate")
+            .states(&[
+                "tempActual",
+                "tempTarget",
+                "comfortTemperature",
+                "activeMode",
+                "operatingMode",
+            ])
+    }
+
+    /// Analog temperature sensor
+    pub fn temperature_sensor(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "InfoOnlyAnalog")
+            .category("Climate")
+            .states(&["value"])
+            .details(json!({"format": "%.1f°C"}))
+    }
+
+    /// Window or door contact
+    pub fn window_contact(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "InfoOnlyDigital")
+            .category("Security")
+            .states(&["active"])
+            .details(json!({"text": {"on": "Open", "off": "Closed"}}))
+    }
+
+    /// Audio zone
+    pub fn audio_zone(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "AudioZoneV2")
+            .category("Audio")
+            .states(&["playState", "volume", "power"])
+    }
+
+    /// Burglar alarm
+    pub fn alarm(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "Alarm")
+            .category("Security")
+            .states(&["armed", "level", "armedDelay"])
+    }
+
+    /// Energy meter
+    pub fn meter(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "Meter")
+            .category("Energy")
+            .states(&["actual", "total"])
+            .details(json!({"actualFormat": "%.3fkW", "totalFormat": "%.1fkWh"}))
+    }
+
+    /// Gate or garage door, as moved by `control_gate`
+    pub fn gate(name: &str) -> DeviceSpec {
+        DeviceSpec::new(name, "Gate").category("Access").states(&[
+            "position",
+            "active",
+            "preventOpen",
+            "preventClose",
+        ])
+    }
+}
+
+/// Builds a [`LoxoneStructure`] room by room
+#[derive(Debug, Clone)]
+pub struct StructureBuilder {
+    structure: LoxoneStructure,
+}
+
+impl StructureBuilder {
+    pub fn new() -> Self {
+        Self {
+            structure: LoxoneStructure {
+                last_modified: "2025-01-01 00:00:00".to_string(),
+                controls: HashMap::new(),
+                rooms: HashMap::new(),
+                cats: HashMap::new(),
+                global_states: HashMap::new(),
+                operating_modes: HashMap::new(),
+            },
+        }
+    }
+
+    /// Add a room (no-op if it exists)
+    pub fn room(mut self, name: &str) -> Self {
+        self.structure
+            .rooms
+            .entry(uuid_for("room", name))
+            .or_insert_with(|| json!({"name": name, "uuid": uuid_for("room", name)}));
+        self
+    }
+
+    /// Add a category (no-op if it exists)
+    pub fn category(mut self, name: &str) -> Self {
+        self.structure
+            .cats
+            .entry(uuid_for("cat", name))
+            .or_insert_with(|| json!({"name": name, "uuid": uuid_for("cat", name)}));
+        self
+    }
+
+    /// Add a device to a room, creating the room and category as needed
+    pub fn device(mut self, room: &str, device: DeviceSpec) -> Self {
+        self = self.room(room);
+        let uuid = device
+            .uuid
+            .clone()
+            .unwrap_or_else(|| uuid_for("control", &format!("{room}/{}", device.name)));
+        let states: Map<String, Value> = device
+            .states
+            .iter()
+            .map(|state| (state.clone(), Value::String(state_uuid(&uuid, state))))
+            .collect();
+        let mut control = json!({
+            "name": device.name,
+            "type": device.control_type,
+            "uuidAction": uuid,
+            "room": uuid_for("room", room),
+            "states": states,
+        });
+        if let Some(category) = &device.category {
+            self = self.category(category);
+            control["cat"] = json!(uuid_for("cat", category));
+        }
+        if !device.details.is_null() {
+            control["details"] = device.details;
+        }
+        self.structure.controls.insert(uuid, control);
+        self
+    }
+
+    /// Add a global state such as `operatingMode`
+    pub fn global_state(mut self, name: &str, uuid: &str) -> Self {
+        self.structure
+            .global_states
+            .insert(name.to_string(), json!(uuid));
+        self
+    }
+
+    pub fn build(self) -> LoxoneStructure {
+        self.structure
+    }
+}
+
+impl Default for StructureBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// UUID of a device added with [`StructureBuilder::device`]
+pub fn device_uuid(room: &str, name: &str) -> String {
+    uuid_for("control", &format!("{room}/{name}"))
+}
+
+/// UUID of a room added with [`StructureBuilder::room`]
+pub fn room_uuid(name: &str) -> String {
+    uuid_for("room", name)
+}
+
+/// A small house: kitchen, living room and bedroom with lights, blinds and climate
+pub fn sample_house() -> LoxoneStructure {
+    StructureBuilder::new()
+        .device("Kitchen", devices::light("Ceiling"))
+        .device("Kitchen", devices::blind("Window"))
+        .device("Kitchen", devices::temperature_sensor("Temperature"))
+        .device("Living Room", devices::dimmer("Floor Lamp"))
+        .device("Living Room", devices::blind("Terrace"))
+        .device("Living Room", devices::room_controller("Climate"))
+        .device("Living Room", devices::audio_zone("Speakers"))
+        .device("Bedroom", devices::switch("Bedside"))
+        .device("Bedroom", devices::window_contact("Window"))
+        .device("Garage", devices::gate("Garage Door"))
+        .build()
+}
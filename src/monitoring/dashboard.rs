@@ -271,6 +271,10 @@ struct DashboardData {
     rate_limit_rejections: u64,
     total_requests: f64,
     error_requests: f64,
+    // Totals kept across restarts
+    lifetime_requests: f64,
+    lifetime_commands: f64,
+    lifetime_errors: f64,
     // Loxone-specific metrics
     loxone_active_devices: f64,
     loxone_device_power_cycles: f64,
@@ -303,6 +307,9 @@ fn parse_metrics_for_dashboard(prometheus_text: &str) -> DashboardData {
         rate_limit_rejections: 0,
         total_requests: 0.0,
         error_requests: 0.0,
+        lifetime_requests: 0.0,
+        lifetime_commands: 0.0,
+        lifetime_errors: 0.0,
         // Initialize Loxone metrics
         loxone_active_devices: 0.0,
         loxone_device_power_cycles: 0.0,
@@ -364,6 +371,9 @@ fn parse_metrics_for_dashboard(prometheus_text: &str) -> DashboardData {
                 "system_memory_usage_mb" => data.memory_usage = value,
                 "process_uptime_seconds" => data.uptime_seconds = value as u64,
                 "rate_limit_rejections_total" => data.rate_limit_rejections = value as u64,
+                "mcp_lifetime_requests_total" => data.lifetime_requests = value,
+                "mcp_lifetime_commands_total" => data.lifetime_commands = value,
+                "mcp_lifetime_errors_total" => data.lifetime_errors = value,
                 // Loxone-specific metrics
                 "loxone_active_devices" => data.loxone_active_devices = value,
                 "loxone_device_power_cycles_total" => data.loxone_device_power_cycles = value,
@@ -0,0 +1,276 @@
+//! Counters that survive restarts
+//!
+//! The in-process collectors start from zero on every deploy. These totals
+//! (tool requests, device commands, errors) are saved to a JSON file on
+//! shutdown and every [`SAVE_INTERVAL`], and picked up again on start, so
+//! long-term statistics keep counting across restarts. The metrics
+//! collector exports them as the `mcp_lifetime_*` counters the dashboards
+//! read.
+//!
+//! The file defaults to `<data dir>/loxone-mcp/metrics.json`;
+//! `LOXONE_METRICS_FILE` moves it and `LOXONE_PERSIST_METRICS=false` keeps
+//! the totals in memory only.
+
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Interval between saves while running
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Where lifetime totals are stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeMetricsConfig {
+    /// Save totals to `file`; otherwise they reset with the process
+    pub persist: bool,
+    pub file: PathBuf,
+}
+
+impl Default for LifetimeMetricsConfig {
+    fn default() -> Self {
+        Self {
+            persist: std::env::var("LOXONE_PERSIST_METRICS")
+                .map(|v| !matches!(v.to_lowercase().as_str(), "0" | "false" | "no"))
+                .unwrap_or(true),
+            file: std::env::var("LOXONE_METRICS_FILE")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| {
+                    dirs::data_local_dir()
+                        .unwrap_or_else(std::env::temp_dir)
+                        .join("loxone-mcp")
+                        .join("metrics.json")
+                }),
+        }
+    }
+}
+
+/// Totals since the first start
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeTotals {
+    /// When counting began
+    pub since: Option<DateTime<Utc>>,
+    /// Server starts, including the current one
+    pub starts: u64,
+    /// Tool calls
+    pub requests: u64,
+    /// Device commands sent to the Miniserver
+    pub commands: u64,
+    /// Failed tool calls and device commands
+    pub errors: u64,
+    /// Errors by tool category (`read`, `control`, `discovery`) or `command`
+    pub errors_by_category: BTreeMap<String, u64>,
+    pub saved_at: Option<DateTime<Utc>>,
+}
+
+/// Lifetime totals of this server, optionally backed by a file
+#[derive(Debug, Default)]
+pub struct LifetimeMetrics {
+    file: Option<PathBuf>,
+    totals: Mutex<LifetimeTotals>,
+}
+
+impl LifetimeMetrics {
+    /// Totals that are never saved
+    pub fn in_memory() -> Self {
+        Self::starting(None, LifetimeTotals::default())
+    }
+
+    /// Continue the totals saved in `config.file`
+    ///
+    /// A missing or unreadable file starts from zero; the latter is logged
+    /// and overwritten on the next save.
+    pub fn from_config(config: &LifetimeMetricsConfig) -> Self {
+        if !config.persist {
+            return Self::in_memory();
+        }
+        let totals = match Self::load(&config.file) {
+            Ok(Some(totals)) => {
+                info!(
+                    "Loaded lifetime metrics from {} ({} requests, {} commands)",
+                    config.file.display(),
+                    totals.requests,
+                    totals.commands
+                );
+                totals
+            }
+            Ok(None) => LifetimeTotals::default(),
+            Err(e) => {
+                warn!("{e}; starting lifetime metrics from zero");
+                LifetimeTotals::default()
+            }
+        };
+        Self::starting(Some(config.file.clone()), totals)
+    }
+
+    fn starting(file: Option<PathBuf>, mut totals: LifetimeTotals) -> Self {
+        totals.since.get_or_insert_with(Utc::now);
+        totals.starts += 1;
+        Self {
+            file,
+            totals: Mutex::new(totals),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Option<LifetimeTotals>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map(Some).map_err(|e| {
+            LoxoneError::parsing_error(format!(
+                "Invalid lifetime metrics file {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    fn totals_mut(&self) -> std::sync::MutexGuard<'_, LifetimeTotals> {
+        self.totals.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Count a tool call of the given timeout category
+    pub fn record_request(&self, category: &str, ok: bool) {
+        let mut totals = self.totals_mut();
+        totals.requests += 1;
+        if !ok {
+            totals.errors += 1;
+            *totals
+                .errors_by_category
+                .entry(category.to_string())
+                .or_default() += 1;
+        }
+    }
+
+    /// Count a device command
+    pub fn record_command(&self, ok: bool) {
+        let mut totals = self.totals_mut();
+        totals.commands += 1;
+        if !ok {
+            totals.errors += 1;
+            *totals
+                .errors_by_category
+                .entry("command".to_string())
+                .or_default() += 1;
+        }
+    }
+
+    pub fn totals(&self) -> LifetimeTotals {
+        self.totals_mut().clone()
+    }
+
+    /// Write the totals to the file, if persisted
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        let totals = {
+            let mut totals = self.totals_mut();
+            totals.saved_at = Some(Utc::now());
+            totals.clone()
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Write next to the file and rename, so a crash never leaves half a file
+        let tmp = path.with_extension("json.tmp");
+        let content = serde_json::to_vec_pretty(&totals)?;
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, path)?;
+        debug!("Saved lifetime metrics to {}", path.display());
+        Ok(())
+    }
+
+    /// Save every [`SAVE_INTERVAL`] so a crash loses little
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn start_autosave(self: &Arc<Self>) {
+        if self.file.is_none() {
+            return;
+        }
+        let metrics = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SAVE_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = metrics.save() {
+                    warn!("Failed to save lifetime metrics: {e}");
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = LifetimeMetricsConfig {
+            persist: true,
+            file: dir.path().join("state").join("metrics.json"),
+        };
+
+        let first = LifetimeMetrics::from_config(&config);
+        first.record_request("control", true);
+        first.record_request("read", false);
+        first.record_command(false);
+        first.save().unwrap();
+        let since = first.totals().since;
+
+        let second = LifetimeMetrics::from_config(&config);
+        second.record_request("read", true);
+        let totals = second.totals();
+        assert_eq!(totals.since, since);
+        assert_eq!(
+            (
+                totals.starts,
+                totals.requests,
+                totals.commands,
+                totals.errors
+            ),
+            (2, 3, 1, 2)
+        );
+        assert_eq!(totals.errors_by_category["command"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_counters_continue_after_restart() {
+        use crate::monitoring::metrics::MetricsCollector;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = LifetimeMetricsConfig {
+            persist: true,
+            file: dir.path().join("metrics.json"),
+        };
+        let first = LifetimeMetrics::from_config(&config);
+        first.record_request("control", true);
+        first.record_command(true);
+        first.save().unwrap();
+
+        let second = Arc::new(LifetimeMetrics::from_config(&config));
+        second.record_request("read", false);
+        let collector = MetricsCollector::new();
+        collector.init_default_metrics().await;
+        collector.attach_lifetime_metrics(second);
+
+        let export = collector.export_prometheus().await;
+        assert!(export.contains("mcp_lifetime_requests_total 2\n"));
+        assert!(export.contains("mcp_lifetime_commands_total 1\n"));
+        assert!(export.contains("mcp_lifetime_errors_total 1\n"));
+        assert!(export.contains("mcp_server_starts_total 2\n"));
+        // The per-process counter starts over
+        assert!(export.contains("mcp_requests_total 0\n"));
+    }
+}
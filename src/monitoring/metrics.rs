@@ -15,6 +15,7 @@ use tracing::{debug, info};
 
 #[cfg(feature = "influxdb")]
 use super::influxdb::{InfluxManager, McpMetrics};
+use super::lifetime_metrics::LifetimeMetrics;
 
 /// Metric type for Prometheus export
 #[derive(Debug, Clone, PartialEq)]
@@ -25,6 +26,167 @@ pub enum MetricType {
     Summary,
 }
 
+/// Name, help and type of a metric the collector exports
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricDefinition {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub metric_type: MetricType,
+}
+
+impl MetricDefinition {
+    const fn new(name: &'static str, help: &'static str, metric_type: MetricType) -> Self {
+        Self {
+            name,
+            help,
+            metric_type,
+        }
+    }
+}
+
+/// Metrics exported by [`MetricsCollector`]
+///
+/// Alerting rules and dashboards generated by
+/// [`observability`](super::observability) only reference names from this
+/// table, so renaming a metric here keeps them in sync.
+pub const DEFAULT_METRICS: &[MetricDefinition] = &[
+    MetricDefinition::new(
+        "process_uptime_seconds",
+        "Time since process start",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "mcp_requests_total",
+        "Total number of MCP requests",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_requests_by_status_2xx",
+        "Requests with 2xx status",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_requests_by_status_3xx",
+        "Requests with 3xx status",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_requests_by_status_4xx",
+        "Requests with 4xx status",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_requests_by_status_5xx",
+        "Requests with 5xx status",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_request_duration_ms",
+        "Request duration percentiles in milliseconds",
+        MetricType::Histogram,
+    ),
+    MetricDefinition::new(
+        "rate_limit_rejections_total",
+        "Total number of rate limited requests",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "rate_limit_allowed_total",
+        "Total number of requests allowed by rate limiter",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "system_cpu_usage_percent",
+        "CPU usage percentage",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "system_memory_usage_mb",
+        "Memory usage in MB",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "system_memory_total_mb",
+        "Total memory in MB",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "process_memory_usage_mb",
+        "Process memory usage in MB",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "system_disk_usage_percent",
+        "Disk usage percentage",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "system_network_rx_bytes",
+        "Network received bytes",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "system_network_tx_bytes",
+        "Network transmitted bytes",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "http_connections_active",
+        "Open HTTP connections",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "http_connections_accepted_total",
+        "HTTP connections admitted by the connection limits",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "http_connections_rejected_total",
+        "HTTP connections refused by the connection limits",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "http_connections_accept_pending",
+        "Accepted HTTP connections waiting for the transport",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "http_connections_accept_wait_ms",
+        "Mean time from accept to transport connect in milliseconds",
+        MetricType::Gauge,
+    ),
+    MetricDefinition::new(
+        "http_accept_errors_total",
+        "Failed accepts on the HTTP listeners",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_lifetime_requests_total",
+        "Tool calls since the first start, kept across restarts",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_lifetime_commands_total",
+        "Device commands since the first start, kept across restarts",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_lifetime_errors_total",
+        "Failed tool calls and commands since the first start, kept across restarts",
+        MetricType::Counter,
+    ),
+    MetricDefinition::new(
+        "mcp_server_starts_total",
+        "Server starts, including the current one",
+        MetricType::Counter,
+    ),
+];
+
+/// Look up a metric of [`DEFAULT_METRICS`] by name
+pub fn metric_definition(name: &str) -> Option<&'static MetricDefinition> {
+    DEFAULT_METRICS.iter().find(|d| d.name == name)
+}
+
 /// Metric value
 #[derive(Debug, Clone)]
 pub enum MetricValue {
@@ -86,6 +248,8 @@ pub struct MetricsCollector {
     system: Arc<RwLock<System>>,
     /// Last system update time
     last_system_update: Arc<RwLock<Instant>>,
+    /// Totals exported as the `mcp_lifetime_*` counters
+    lifetime: std::sync::RwLock<Option<Arc<LifetimeMetrics>>>,
 }
 
 impl MetricsCollector {
@@ -102,6 +266,7 @@ impl MetricsCollector {
             start_time: Instant::now(),
             system: Arc::new(RwLock::new(system)),
             last_system_update: Arc::new(RwLock::new(Instant::now())),
+            lifetime: std::sync::RwLock::new(None),
         }
     }
 
@@ -118,6 +283,42 @@ impl MetricsCollector {
             start_time: Instant::now(),
             system: Arc::new(RwLock::new(system)),
             last_system_update: Arc::new(RwLock::new(Instant::now())),
+            lifetime: std::sync::RwLock::new(None),
+        }
+    }
+
+    /// Export `metrics` as the `mcp_lifetime_*` counters
+    ///
+    /// The dashboard reads these instead of counters that reset with the
+    /// process.
+    pub fn attach_lifetime_metrics(&self, metrics: Arc<LifetimeMetrics>) {
+        *self.lifetime.write().unwrap_or_else(|e| e.into_inner()) = Some(metrics);
+    }
+
+    /// Copy the attached lifetime totals into their counters
+    async fn refresh_lifetime_counters(&self) {
+        let Some(lifetime) = self
+            .lifetime
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+        else {
+            return;
+        };
+        let totals = lifetime.totals();
+        let mut metrics = self.metrics.write().await;
+        for (name, value) in [
+            ("mcp_lifetime_requests_total", totals.requests),
+            ("mcp_lifetime_commands_total", totals.commands),
+            ("mcp_lifetime_errors_total", totals.errors),
+            ("mcp_server_starts_total", totals.starts),
+        ] {
+            if let Some(metric) = metrics.get_mut(name)
+                && let MetricValue::Counter(ref mut count) = metric.value
+            {
+                *count = value;
+                metric.timestamp = Instant::now();
+            }
         }
     }
 
@@ -225,7 +426,9 @@ impl MetricsCollector {
         if !metrics.contains_key(name) {
             let metadata = MetricMetadata {
                 name: name.to_string(),
-                help: format!("Histogram for {name}"),
+                help: metric_definition(name)
+                    .map(|d| d.help.to_string())
+                    .unwrap_or_else(|| format!("Histogram for {name}")),
                 metric_type: MetricType::Histogram,
                 labels: HashMap::new(),
             };
@@ -325,6 +528,7 @@ impl MetricsCollector {
 
     /// Export metrics in Prometheus format
     pub async fn export_prometheus(&self) -> String {
+        self.refresh_lifetime_counters().await;
         let metrics = self.metrics.read().await;
         let mut output = String::new();
 
@@ -513,89 +717,21 @@ impl MetricsCollector {
 
     /// Initialize default metrics
     pub async fn init_default_metrics(&self) {
-        // Request metrics
-        self.register_counter(
-            "mcp_requests_total",
-            "Total number of MCP requests",
-            HashMap::new(),
-        )
-        .await;
-
-        // Status code metrics
-        for status in ["2xx", "3xx", "4xx", "5xx"] {
-            self.register_counter(
-                &format!("mcp_requests_by_status_{status}"),
-                &format!("Requests with {status} status"),
-                HashMap::new(),
-            )
-            .await;
+        for definition in DEFAULT_METRICS {
+            match definition.metric_type {
+                MetricType::Counter => {
+                    self.register_counter(definition.name, definition.help, HashMap::new())
+                        .await
+                }
+                MetricType::Gauge => {
+                    self.register_gauge(definition.name, definition.help, HashMap::new())
+                        .await
+                }
+                // Histograms are created on first observation, uptime is written on export
+                MetricType::Histogram | MetricType::Summary => {}
+            }
         }
 
-        // Rate limiting metrics
-        self.register_counter(
-            "rate_limit_rejections_total",
-            "Total number of rate limited requests",
-            HashMap::new(),
-        )
-        .await;
-
-        self.register_counter(
-            "rate_limit_allowed_total",
-            "Total number of requests allowed by rate limiter",
-            HashMap::new(),
-        )
-        .await;
-
-        // System metrics
-        self.register_gauge(
-            "system_cpu_usage_percent",
-            "CPU usage percentage",
-            HashMap::new(),
-        )
-        .await;
-
-        self.register_gauge(
-            "system_memory_usage_mb",
-            "Memory usage in MB",
-            HashMap::new(),
-        )
-        .await;
-
-        self.register_gauge(
-            "system_memory_total_mb",
-            "Total memory in MB",
-            HashMap::new(),
-        )
-        .await;
-
-        self.register_gauge(
-            "process_memory_usage_mb",
-            "Process memory usage in MB",
-            HashMap::new(),
-        )
-        .await;
-
-        self.register_gauge(
-            "system_disk_usage_percent",
-            "Disk usage percentage",
-            HashMap::new(),
-        )
-        .await;
-
-        self.register_gauge(
-            "system_network_rx_bytes",
-            "Network received bytes",
-            HashMap::new(),
-        )
-        .await;
-
-        self.register_gauge(
-            "system_network_tx_bytes",
-            "Network transmitted bytes",
-            HashMap::new(),
-        )
-        .await;
-
         debug!("Initialized default metrics");
     }
 }
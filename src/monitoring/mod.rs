@@ -12,7 +12,9 @@ pub mod influxdb;
 
 pub mod clean_dashboard;
 pub mod dashboard;
+pub mod lifetime_metrics;
 pub mod loxone_stats;
 pub mod metrics;
+pub mod observability;
 pub mod server_metrics;
 pub mod unified_collector;
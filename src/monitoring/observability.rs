@@ -0,0 +1,494 @@
+//! Prometheus alerting rules and Grafana dashboard for the exported metrics
+//!
+//! The `observability` subcommand writes both files so a monitoring stack can
+//! be set up without hand-writing queries:
+//!
+//! ```bash
+//! loxone-mcp-server observability --output-dir /etc/prometheus/loxone
+//! ```
+//!
+//! Every query refers to metrics by the names in [`DEFAULT_METRICS`], the
+//! table the collector registers from, so renaming a metric there without
+//! updating the generated rules fails the tests instead of leaving empty
+//! panels and alerts that never fire.
+
+use super::metrics::{DEFAULT_METRICS, metric_definition};
+use serde_json::{Value, json};
+
+/// File name of the alerting rules
+pub const ALERT_RULES_FILE: &str = "loxone-mcp-alerts.yml";
+
+/// File name of the dashboard
+pub const DASHBOARD_FILE: &str = "loxone-mcp-dashboard.json";
+
+/// Share of failed requests that raises an alert
+pub const ERROR_RATIO_THRESHOLD: f64 = 0.05;
+
+/// p99 request latency that raises an alert
+pub const SLOW_REQUEST_MS: u64 = 2000;
+
+/// Process memory that raises an alert
+pub const MEMORY_THRESHOLD_MB: u64 = 512;
+
+/// Options for the generated files
+#[derive(Debug, Clone)]
+pub struct ObservabilityOptions {
+    /// Prometheus job scraping the server
+    pub job: String,
+}
+
+impl Default for ObservabilityOptions {
+    fn default() -> Self {
+        Self {
+            job: "loxone-mcp".to_string(),
+        }
+    }
+}
+
+/// One Prometheus alerting rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub name: &'static str,
+    pub expr: String,
+    pub duration: &'static str,
+    pub severity: &'static str,
+    pub summary: &'static str,
+    pub description: &'static str,
+}
+
+/// Name of an exported metric
+///
+/// Only names of [`DEFAULT_METRICS`] may be used in generated queries.
+fn metric(name: &'static str) -> &'static str {
+    debug_assert!(
+        metric_definition(name).is_some(),
+        "{name} is not exported by the metrics collector"
+    );
+    name
+}
+
+/// Alerting rules for a server scraped by `options.job`
+pub fn alert_rules(options: &ObservabilityOptions) -> Vec<AlertRule> {
+    let job = format!("job=\"{}\"", options.job);
+    let rate = |name: &'static str| format!("rate({}{{{job}}}[5m])", metric(name));
+
+    vec![
+        AlertRule {
+            name: "LoxoneMcpDown",
+            expr: format!("up{{{job}}} == 0"),
+            duration: "2m",
+            severity: "critical",
+            summary: "Loxone MCP server is down",
+            description: "Prometheus cannot scrape {{ $labels.instance }}.",
+        },
+        AlertRule {
+            name: "LoxoneMcpHighErrorRate",
+            expr: format!(
+                "{} / {} > {ERROR_RATIO_THRESHOLD}",
+                rate("mcp_requests_by_status_5xx"),
+                rate("mcp_requests_total")
+            ),
+            duration: "10m",
+            severity: "warning",
+            summary: "Loxone MCP server fails requests",
+            description: "{{ $value | humanizePercentage }} of requests on {{ $labels.instance }} end with a server error.",
+        },
+        AlertRule {
+            name: "LoxoneMcpSlowRequests",
+            expr: format!(
+                "{}_bucket{{{job},le=\"99\"}} > {SLOW_REQUEST_MS}",
+                metric("mcp_request_duration_ms")
+            ),
+            duration: "10m",
+            severity: "warning",
+            summary: "Loxone MCP requests are slow",
+            description: "p99 request latency on {{ $labels.instance }} is {{ $value }} ms.",
+        },
+        AlertRule {
+            name: "LoxoneMcpRateLimited",
+            expr: format!(
+                "{rejected} / ({rejected} + {allowed}) > 0.1",
+                rejected = rate("rate_limit_rejections_total"),
+                allowed = rate("rate_limit_allowed_total")
+            ),
+            duration: "10m",
+            severity: "warning",
+            summary: "Loxone MCP server rejects requests",
+            description: "{{ $value | humanizePercentage }} of requests on {{ $labels.instance }} hit the rate limit.",
+        },
+        AlertRule {
+            name: "LoxoneMcpConnectionsRefused",
+            expr: format!("{} > 0", rate("http_connections_rejected_total")),
+            duration: "10m",
+            severity: "warning",
+            summary: "Loxone MCP server refuses connections",
+            description: "{{ $labels.instance }} refuses {{ $value }} connections per second at its connection limits.",
+        },
+        AlertRule {
+            name: "LoxoneMcpAcceptErrors",
+            expr: format!("{} > 0", rate("http_accept_errors_total")),
+            duration: "5m",
+            severity: "warning",
+            summary: "Loxone MCP server fails to accept connections",
+            description: "Accepting connections fails on {{ $labels.instance }}, often because file descriptors ran out.",
+        },
+        AlertRule {
+            name: "LoxoneMcpRestarting",
+            expr: format!(
+                "resets({}{{{job}}}[30m]) > 2",
+                metric("process_uptime_seconds")
+            ),
+            duration: "0m",
+            severity: "warning",
+            summary: "Loxone MCP server restarts repeatedly",
+            description: "{{ $labels.instance }} restarted {{ $value }} times in 30 minutes.",
+        },
+        AlertRule {
+            name: "LoxoneMcpHighMemory",
+            expr: format!(
+                "{}{{{job}}} > {MEMORY_THRESHOLD_MB}",
+                metric("process_memory_usage_mb")
+            ),
+            duration: "15m",
+            severity: "warning",
+            summary: "Loxone MCP server uses a lot of memory",
+            description: "{{ $labels.instance }} uses {{ $value }} MB.",
+        },
+        AlertRule {
+            name: "LoxoneMcpDiskFull",
+            expr: format!("{}{{{job}}} > 90", metric("system_disk_usage_percent")),
+            duration: "15m",
+            severity: "warning",
+            summary: "Disk of the Loxone MCP host is almost full",
+            description: "Disk usage on {{ $labels.instance }} is {{ $value }}%.",
+        },
+    ]
+}
+
+/// Prometheus rule file with [`alert_rules`]
+pub fn render_alert_rules(options: &ObservabilityOptions) -> String {
+    // Single-quoted YAML scalars: only the quote itself needs escaping
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+
+    let mut yaml = String::from("# Generated by loxone-mcp-server observability\ngroups:\n");
+    yaml.push_str(&format!("  - name: {}\n    rules:\n", quote(&options.job)));
+    for rule in alert_rules(options) {
+        yaml.push_str(&format!("      - alert: {}\n", rule.name));
+        yaml.push_str(&format!("        expr: {}\n", quote(&rule.expr)));
+        yaml.push_str(&format!("        for: {}\n", rule.duration));
+        yaml.push_str(&format!(
+            "        labels:\n          severity: {}\n",
+            rule.severity
+        ));
+        yaml.push_str(&format!(
+            "        annotations:\n          summary: {}\n          description: {}\n",
+            quote(rule.summary),
+            quote(rule.description)
+        ));
+    }
+    yaml
+}
+
+/// Grafana dashboard for a server scraped by `options.job`
+///
+/// Uses a `datasource` variable, so it can be imported into any Grafana
+/// with a Prometheus data source.
+pub fn grafana_dashboard(options: &ObservabilityOptions) -> Value {
+    let job = "job=\"$job\"";
+    let series = |name: &'static str| format!("{}{{{job}}}", metric(name));
+    let rate = |name: &'static str| format!("rate({}{{{job}}}[$__rate_interval])", metric(name));
+    let latency = |le: &str| {
+        format!(
+            "{}_bucket{{{job},le=\"{le}\"}}",
+            metric("mcp_request_duration_ms")
+        )
+    };
+
+    let status: Vec<(String, String)> = [
+        ("mcp_requests_by_status_2xx", "2xx"),
+        ("mcp_requests_by_status_3xx", "3xx"),
+        ("mcp_requests_by_status_4xx", "4xx"),
+        ("mcp_requests_by_status_5xx", "5xx"),
+    ]
+    .into_iter()
+    .map(|(name, legend)| (rate(name), legend.to_string()))
+    .collect();
+
+    let panels = vec![
+        panel(
+            "stat",
+            "Uptime",
+            "s",
+            vec![(series("process_uptime_seconds"), "uptime".to_string())],
+            (0, 0, 6, 4),
+        ),
+        panel(
+            "stat",
+            "Requests / s",
+            "reqps",
+            vec![(rate("mcp_requests_total"), "requests".to_string())],
+            (6, 0, 6, 4),
+        ),
+        panel(
+            "stat",
+            "Error ratio",
+            "percentunit",
+            vec![(
+                format!(
+                    "{} / {}",
+                    rate("mcp_requests_by_status_5xx"),
+                    rate("mcp_requests_total")
+                ),
+                "5xx".to_string(),
+            )],
+            (12, 0, 6, 4),
+        ),
+        panel(
+            "stat",
+            "Process memory",
+            "decmbytes",
+            vec![(series("process_memory_usage_mb"), "memory".to_string())],
+            (18, 0, 6, 4),
+        ),
+        panel(
+            "timeseries",
+            "Responses by status",
+            "reqps",
+            status,
+            (0, 4, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Request latency",
+            "ms",
+            vec![
+                (latency("50"), "p50".to_string()),
+                (latency("90"), "p90".to_string()),
+                (latency("99"), "p99".to_string()),
+            ],
+            (12, 4, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Rate limiter",
+            "reqps",
+            vec![
+                (rate("rate_limit_allowed_total"), "allowed".to_string()),
+                (rate("rate_limit_rejections_total"), "rejected".to_string()),
+            ],
+            (0, 12, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Memory",
+            "decmbytes",
+            vec![
+                (series("process_memory_usage_mb"), "process".to_string()),
+                (series("system_memory_usage_mb"), "system used".to_string()),
+                (series("system_memory_total_mb"), "system total".to_string()),
+            ],
+            (12, 12, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Host usage",
+            "percent",
+            vec![
+                (series("system_cpu_usage_percent"), "cpu".to_string()),
+                (series("system_disk_usage_percent"), "disk".to_string()),
+            ],
+            (0, 20, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Network",
+            "decbytes",
+            vec![
+                (series("system_network_rx_bytes"), "received".to_string()),
+                (series("system_network_tx_bytes"), "transmitted".to_string()),
+            ],
+            (12, 20, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Connections",
+            "short",
+            vec![
+                (series("http_connections_active"), "open".to_string()),
+                (
+                    series("http_connections_accept_pending"),
+                    "accept queue".to_string(),
+                ),
+            ],
+            (0, 28, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Connection admission",
+            "cps",
+            vec![
+                (
+                    rate("http_connections_accepted_total"),
+                    "accepted".to_string(),
+                ),
+                (
+                    rate("http_connections_rejected_total"),
+                    "refused".to_string(),
+                ),
+                (
+                    rate("http_accept_errors_total"),
+                    "accept errors".to_string(),
+                ),
+            ],
+            (12, 28, 12, 8),
+        ),
+        panel(
+            "timeseries",
+            "Accept wait",
+            "ms",
+            vec![(
+                series("http_connections_accept_wait_ms"),
+                "mean".to_string(),
+            )],
+            (0, 36, 12, 8),
+        ),
+        panel(
+            "stat",
+            "Lifetime totals",
+            "short",
+            vec![
+                (
+                    series("mcp_lifetime_requests_total"),
+                    "requests".to_string(),
+                ),
+                (
+                    series("mcp_lifetime_commands_total"),
+                    "commands".to_string(),
+                ),
+                (series("mcp_lifetime_errors_total"), "errors".to_string()),
+                (series("mcp_server_starts_total"), "starts".to_string()),
+            ],
+            (12, 36, 12, 8),
+        ),
+    ];
+    let panels: Vec<Value> = panels
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut panel)| {
+            panel["id"] = json!(i + 1);
+            panel
+        })
+        .collect();
+
+    json!({
+        "title": "Loxone MCP Server",
+        "uid": "loxone-mcp",
+        "tags": ["loxone", "mcp"],
+        "timezone": "browser",
+        "schemaVersion": 39,
+        "refresh": "30s",
+        "time": { "from": "now-6h", "to": "now" },
+        "templating": {
+            "list": [
+                {
+                    "name": "datasource",
+                    "label": "Data source",
+                    "type": "datasource",
+                    "query": "prometheus",
+                },
+                {
+                    "name": "job",
+                    "label": "Job",
+                    "type": "custom",
+                    "query": options.job,
+                    "current": { "text": options.job, "value": options.job },
+                },
+            ]
+        },
+        "panels": panels,
+    })
+}
+
+/// Dashboard panel; `grid` is `(x, y, width, height)`
+fn panel(
+    kind: &str,
+    title: &str,
+    unit: &str,
+    targets: Vec<(String, String)>,
+    grid: (u32, u32, u32, u32),
+) -> Value {
+    let datasource = json!({ "type": "prometheus", "uid": "${datasource}" });
+    let targets: Vec<Value> = targets
+        .into_iter()
+        .zip('A'..)
+        .map(|((expr, legend), ref_id)| {
+            json!({
+                "datasource": datasource,
+                "expr": expr,
+                "legendFormat": legend,
+                "refId": ref_id.to_string(),
+            })
+        })
+        .collect();
+    let (x, y, w, h) = grid;
+    json!({
+        "type": kind,
+        "title": title,
+        "datasource": datasource,
+        "gridPos": { "x": x, "y": y, "w": w, "h": h },
+        "fieldConfig": { "defaults": { "unit": unit }, "overrides": [] },
+        "targets": targets,
+    })
+}
+
+/// Metrics of [`DEFAULT_METRICS`] that no generated query uses
+pub fn unused_metrics(options: &ObservabilityOptions) -> Vec<&'static str> {
+    let rules = render_alert_rules(options);
+    let dashboard = grafana_dashboard(options).to_string();
+    DEFAULT_METRICS
+        .iter()
+        .map(|d| d.name)
+        .filter(|name| !rules.contains(name) && !dashboard.contains(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::metrics::{MetricsCollector, RequestTiming};
+
+    #[tokio::test]
+    async fn test_generated_queries_match_exported_metrics() {
+        let collector = MetricsCollector::new();
+        collector.init_default_metrics().await;
+        collector
+            .record_request_timing(RequestTiming {
+                endpoint: "/mcp".to_string(),
+                method: "POST".to_string(),
+                duration_ms: 12.0,
+                status_code: 200,
+            })
+            .await;
+        let export = collector.export_prometheus().await;
+        for definition in DEFAULT_METRICS {
+            assert!(
+                export.contains(&format!("# HELP {} ", definition.name)),
+                "{} is not exported",
+                definition.name
+            );
+        }
+
+        let options = ObservabilityOptions {
+            job: "home".to_string(),
+        };
+        assert!(unused_metrics(&options).is_empty());
+
+        let rules = render_alert_rules(&options);
+        assert!(rules.contains(
+            "expr: 'rate(mcp_requests_by_status_5xx{job=\"home\"}[5m]) / rate(mcp_requests_total{job=\"home\"}[5m]) > 0.05'"
+        ));
+        let dashboard = grafana_dashboard(&options);
+        assert_eq!(dashboard["templating"]["list"][1]["query"], "home");
+        assert_eq!(dashboard["panels"][5]["targets"][2]["refId"], "C");
+    }
+}
@@ -0,0 +1,287 @@
+//! Notification channels and dispatch
+//!
+//! A [`NotificationChannel`] delivers a rendered notification somewhere
+//! outside the server (log, e-mail, ...). The [`NotificationDispatcher`]
+//! renders an event once per channel kind with [`NotificationTemplates`] and
+//! hands it to every channel that accepts the severity. Delivery failures
+//! are logged and reported, never propagated to the caller raising the alert.
+
+use super::templates::{ChannelKind, NotificationTemplates};
+use crate::error::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+/// Template used when an event has no template of its own
+pub const GENERIC_EVENT: &str = "alert.triggered";
+
+/// Severity of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl NotificationSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// A notification rendered for one channel kind
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// Event that caused the notification, e.g. `security.alarm_triggered`
+    pub event: String,
+    pub severity: NotificationSeverity,
+    pub subject: String,
+    pub body: String,
+    /// Variables the templates were rendered with
+    pub data: Value,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Destination for notifications
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Name used in logs and delivery reports
+    fn name(&self) -> &str;
+
+    /// Channel kind, selects the template variant
+    fn kind(&self) -> ChannelKind;
+
+    /// Whether notifications of `severity` are delivered on this channel
+    fn accepts(&self, _severity: NotificationSeverity) -> bool {
+        true
+    }
+
+    /// Deliver one notification
+    async fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Writes notifications to the application log
+pub struct LogChannel;
+
+#[async_trait]
+impl NotificationChannel for LogChannel {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Log
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        match notification.severity {
+            NotificationSeverity::Critical => error!("{}", notification.body),
+            NotificationSeverity::Warning => warn!("{}", notification.body),
+            NotificationSeverity::Info => info!("{}", notification.body),
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of delivering a notification to one channel
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryResult {
+    pub channel: String,
+    pub delivered: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Renders events and fans them out to the registered channels
+pub struct NotificationDispatcher {
+    templates: NotificationTemplates,
+    channels: Vec<Arc<dyn NotificationChannel>>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(templates: NotificationTemplates) -> Self {
+        Self {
+            templates,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Add a channel
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// Log channel, plus e-mail when `LOXONE_SMTP_SERVER` is set
+    pub fn from_env() -> Result<Self> {
+        let dispatcher = Self::default();
+        #[cfg(feature = "email")]
+        if let Some(config) = super::email::SmtpConfig::from_env() {
+            let email = super::email::EmailChannel::new(&config)?;
+            return Ok(dispatcher.with_channel(Arc::new(email)));
+        }
+        Ok(dispatcher)
+    }
+
+    pub fn channels(&self) -> &[Arc<dyn NotificationChannel>] {
+        &self.channels
+    }
+
+    /// Render `event` and deliver it to every channel accepting `severity`
+    pub async fn notify(
+        &self,
+        event: &str,
+        severity: NotificationSeverity,
+        vars: &Value,
+    ) -> Vec<DeliveryResult> {
+        let mut results = Vec::new();
+        for channel in self
+            .channels
+            .iter()
+            .filter(|channel| channel.accepts(severity))
+        {
+            let notification = self.render(event, severity, channel.kind(), vars);
+            let result = channel.send(&notification).await;
+            match &result {
+                Ok(()) => debug!("Notification '{event}' delivered via {}", channel.name()),
+                Err(e) => warn!(
+                    "Failed to deliver notification '{event}' via {}: {e}",
+                    channel.name()
+                ),
+            }
+            results.push(DeliveryResult {
+                channel: channel.name().to_string(),
+                delivered: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+        results
+    }
+
+    fn render(
+        &self,
+        event: &str,
+        severity: NotificationSeverity,
+        kind: ChannelKind,
+        vars: &Value,
+    ) -> Notification {
+        let mut data = vars.clone();
+        if let Some(map) = data.as_object_mut() {
+            map.entry("severity")
+                .or_insert_with(|| severity.as_str().into());
+            map.entry("event").or_insert_with(|| event.into());
+        }
+
+        let rendered = self
+            .templates
+            .render(event, kind, &data)
+            .or_else(|_| self.templates.render(GENERIC_EVENT, kind, &data));
+        let (subject, body) = match rendered {
+            Ok(rendered) => (
+                rendered.subject.unwrap_or_else(|| event.to_string()),
+                rendered.body,
+            ),
+            Err(e) => {
+                warn!("Failed to render notification '{event}': {e}");
+                (event.to_string(), data.to_string())
+            }
+        };
+
+        Notification {
+            event: event.to_string(),
+            severity,
+            subject,
+            body,
+            data,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+impl Default for NotificationDispatcher {
+    fn default() -> Self {
+        Self::new(NotificationTemplates::default()).with_channel(Arc::new(LogChannel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::sync::Mutex;
+
+    struct Recorder {
+        sent: Mutex<Vec<Notification>>,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn kind(&self) -> ChannelKind {
+            ChannelKind::Email
+        }
+
+        fn accepts(&self, severity: NotificationSeverity) -> bool {
+            severity >= NotificationSeverity::Critical
+        }
+
+        async fn send(&self, notification: &Notification) -> Result<()> {
+            self.sent.lock().await.push(notification.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_renders_and_filters_by_severity() {
+        let recorder = Arc::new(Recorder {
+            sent: Mutex::new(Vec::new()),
+        });
+        let dispatcher = NotificationDispatcher::new(NotificationTemplates::builtin("en"))
+            .with_channel(recorder.clone());
+
+        let vars = json!({"name": "House", "room": "Hallway", "cause": "motion"});
+        let results = dispatcher
+            .notify(
+                "security.alarm_triggered",
+                NotificationSeverity::Critical,
+                &vars,
+            )
+            .await;
+        assert!(results[0].delivered);
+
+        // Below the channel's severity: not delivered
+        let results = dispatcher
+            .notify("heating.failure", NotificationSeverity::Warning, &vars)
+            .await;
+        assert!(results.is_empty());
+
+        // Unknown events fall back to the generic alert template
+        dispatcher
+            .notify(
+                "custom.event",
+                NotificationSeverity::Critical,
+                &json!({"id": "x", "message": "Something broke"}),
+            )
+            .await;
+
+        let sent = recorder.sent.lock().await;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].subject, "Alarm triggered in Hallway");
+        assert_eq!(
+            sent[0].body,
+            "The alarm House was triggered in Hallway: motion."
+        );
+        assert_eq!(sent[1].subject, "[critical] Something broke");
+    }
+}
@@ -0,0 +1,229 @@
+//! E-mail notifications over SMTP
+//!
+//! With `use_tls`, port 465 uses implicit TLS and any other port upgrades
+//! the connection with STARTTLS; certificates are verified against the
+//! bundled web PKI roots. Without `use_tls` the connection is plain text and
+//! should only be used for a relay on the local network.
+//!
+//! The channel is configured from the environment:
+//!
+//! | Variable | Meaning |
+//! |----------|---------|
+//! | `LOXONE_SMTP_SERVER` | SMTP host (enables e-mail notifications) |
+//! | `LOXONE_SMTP_PORT` | Port, default 587 |
+//! | `LOXONE_SMTP_USERNAME` / `LOXONE_SMTP_PASSWORD` | Credentials |
+//! | `LOXONE_SMTP_FROM` | Sender address |
+//! | `LOXONE_SMTP_TO` | Comma-separated recipients |
+//! | `LOXONE_SMTP_TLS` | `false` to disable TLS |
+
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// SMTP configuration for email alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub server: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub from_email: String,
+    pub to_emails: Vec<String>,
+    pub use_tls: bool,
+}
+
+impl SmtpConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.server.is_empty() {
+            return Err(LoxoneError::invalid_input("SMTP server cannot be empty"));
+        }
+        if self.from_email.is_empty() {
+            return Err(LoxoneError::invalid_input("From email cannot be empty"));
+        }
+        if self.to_emails.is_empty() {
+            return Err(LoxoneError::invalid_input(
+                "At least one recipient email must be configured",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read the configuration from `LOXONE_SMTP_*`, if a server is set
+    pub fn from_env() -> Option<Self> {
+        let server = env::var("LOXONE_SMTP_SERVER")
+            .ok()
+            .filter(|s| !s.is_empty())?;
+        Some(Self {
+            server,
+            port: env::var("LOXONE_SMTP_PORT")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(587),
+            username: env::var("LOXONE_SMTP_USERNAME").unwrap_or_default(),
+            password: env::var("LOXONE_SMTP_PASSWORD").unwrap_or_default(),
+            from_email: env::var("LOXONE_SMTP_FROM").unwrap_or_default(),
+            to_emails: env::var("LOXONE_SMTP_TO")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|to| !to.is_empty())
+                .map(str::to_string)
+                .collect(),
+            use_tls: env::var("LOXONE_SMTP_TLS").map_or(true, |v| v != "false" && v != "0"),
+        })
+    }
+}
+
+#[cfg(feature = "email")]
+pub use channel::EmailChannel;
+
+#[cfg(feature = "email")]
+mod channel {
+    use super::SmtpConfig;
+    use crate::error::{LoxoneError, Result};
+    use crate::notifications::channel::{Notification, NotificationChannel, NotificationSeverity};
+    use crate::notifications::templates::ChannelKind;
+    use async_trait::async_trait;
+    use lettre::message::{Mailbox, header::ContentType};
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+    /// Port that expects TLS from the first byte instead of STARTTLS
+    const IMPLICIT_TLS_PORT: u16 = 465;
+
+    /// Sends notifications as plain-text e-mail
+    pub struct EmailChannel {
+        from: Mailbox,
+        to: Vec<Mailbox>,
+        transport: AsyncSmtpTransport<Tokio1Executor>,
+        min_severity: NotificationSeverity,
+    }
+
+    impl EmailChannel {
+        /// Build the channel; only critical notifications are sent by default
+        pub fn new(config: &SmtpConfig) -> Result<Self> {
+            config.validate()?;
+
+            let parse = |address: &str| {
+                address.parse::<Mailbox>().map_err(|e| {
+                    LoxoneError::config(format!("Invalid e-mail address '{address}': {e}"))
+                })
+            };
+            let from = parse(&config.from_email)?;
+            let to = config
+                .to_emails
+                .iter()
+                .map(|address| parse(address))
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut builder = if !config.use_tls {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.server)
+            } else if config.port == IMPLICIT_TLS_PORT {
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&config.server)
+                    .map_err(|e| LoxoneError::config(format!("Invalid SMTP server: {e}")))?
+            } else {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.server)
+                    .map_err(|e| LoxoneError::config(format!("Invalid SMTP server: {e}")))?
+            }
+            .port(config.port);
+            if !config.username.is_empty() {
+                builder = builder.credentials(Credentials::new(
+                    config.username.clone(),
+                    config.password.clone(),
+                ));
+            }
+
+            Ok(Self {
+                from,
+                to,
+                transport: builder.build(),
+                min_severity: NotificationSeverity::Critical,
+            })
+        }
+
+        /// Also send notifications of lower severity
+        pub fn with_min_severity(mut self, severity: NotificationSeverity) -> Self {
+            self.min_severity = severity;
+            self
+        }
+
+        fn message(&self, notification: &Notification) -> Result<Message> {
+            let mut builder = Message::builder()
+                .from(self.from.clone())
+                .subject(notification.subject.clone())
+                .header(ContentType::TEXT_PLAIN);
+            for to in &self.to {
+                builder = builder.to(to.clone());
+            }
+            builder
+                .body(notification.body.clone())
+                .map_err(|e| LoxoneError::internal(format!("Failed to build e-mail: {e}")))
+        }
+    }
+
+    #[async_trait]
+    impl NotificationChannel for EmailChannel {
+        fn name(&self) -> &str {
+            "email"
+        }
+
+        fn kind(&self) -> ChannelKind {
+            ChannelKind::Email
+        }
+
+        fn accepts(&self, severity: NotificationSeverity) -> bool {
+            severity >= self.min_severity
+        }
+
+        async fn send(&self, notification: &Notification) -> Result<()> {
+            let message = self.message(notification)?;
+            self.transport
+                .send(message)
+                .await
+                .map_err(|e| LoxoneError::connection(format!("SMTP delivery failed: {e}")))?;
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::Utc;
+        use serde_json::json;
+
+        #[test]
+        fn test_message_from_notification() {
+            let config = SmtpConfig {
+                server: "smtp.example.com".to_string(),
+                port: 587,
+                username: String::new(),
+                password: String::new(),
+                from_email: "Loxone <loxone@example.com>".to_string(),
+                to_emails: vec!["owner@example.com".to_string()],
+                use_tls: true,
+            };
+            let channel = EmailChannel::new(&config).unwrap();
+            assert!(channel.accepts(NotificationSeverity::Critical));
+            assert!(!channel.accepts(NotificationSeverity::Warning));
+
+            let notification = Notification {
+                event: "heating.failure".to_string(),
+                severity: NotificationSeverity::Critical,
+                subject: "Heating failure in Bathroom".to_string(),
+                body: "Heating problem in Bathroom: no flow".to_string(),
+                data: json!({}),
+                timestamp: Utc::now(),
+            };
+            let formatted =
+                String::from_utf8(channel.message(&notification).unwrap().formatted()).unwrap();
+            assert!(formatted.contains("Subject: Heating failure in Bathroom"));
+            assert!(formatted.contains("To: owner@example.com"));
+
+            let invalid = SmtpConfig {
+                to_emails: vec!["not an address".to_string()],
+                ..config
+            };
+            assert!(EmailChannel::new(&invalid).is_err());
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! Outbound notifications
+//!
+//! This module renders the texts sent to users and external systems (alerts,
+//! webhook payloads, e-mails) from localized, per-channel templates so
+//! notifications match the household's language, and delivers them through
+//! pluggable channels (log, e-mail).
+
+pub mod channel;
+pub mod email;
+pub mod templates;
+
+pub use channel::{
+    DeliveryResult, LogChannel, Notification, NotificationChannel, NotificationDispatcher,
+    NotificationSeverity,
+};
+#[cfg(feature = "email")]
+pub use email::EmailChannel;
+pub use email::SmtpConfig;
+pub use templates::{
+    ChannelKind, NotificationTemplates, RenderedNotification, TemplateDefinition, resolve_locale,
+};
@@ -0,0 +1,525 @@
+//! Localized notification templates
+//!
+//! Templates use a small handlebars-style syntax:
+//!
+//! - `{{name}}` or `{{alert.value}}` inserts a variable (dotted paths walk
+//!   into objects; missing variables render as an empty string)
+//! - `{{#if name}}…{{else}}…{{/if}}` renders a branch depending on whether the
+//!   variable is truthy (present, not `false`, `0`, `""` or `null`)
+//!
+//! Every template is registered for an event (e.g. `performance.cpu_usage`)
+//! and a locale, optionally specialised for one channel. Lookup prefers the
+//! channel-specific template in the selected locale, then the generic one,
+//! then the same pair in the fallback locale (`en`).
+//!
+//! Custom templates are loaded from a TOML file:
+//!
+//! ```toml
+//! [[template]]
+//! event = "performance.cpu_usage"
+//! locale = "de"
+//! channel = "email"          # optional
+//! subject = "CPU-Last hoch"  # optional
+//! body = "CPU-Auslastung {{value}} % (Grenze {{threshold}} %)"
+//! ```
+
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Locale used when no template exists for the selected one
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// Channel a notification is rendered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    /// Application log / console output
+    Log,
+    /// Generic JSON webhook
+    Webhook,
+    /// E-mail
+    Email,
+    /// Slack incoming webhook
+    Slack,
+}
+
+/// A template as stored in a template file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateDefinition {
+    /// Event the template renders
+    pub event: String,
+    /// Locale such as `en` or `de`
+    pub locale: String,
+    /// Restrict the template to one channel
+    #[serde(default)]
+    pub channel: Option<ChannelKind>,
+    /// Subject line (e-mail subject, webhook title)
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// Message body
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TemplateFile {
+    #[serde(default)]
+    template: Vec<TemplateDefinition>,
+}
+
+/// Rendered notification text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenderedNotification {
+    pub subject: Option<String>,
+    pub body: String,
+    /// Locale the template was actually taken from
+    pub locale: String,
+}
+
+type TemplateKey = (String, Option<ChannelKind>, String);
+
+/// Template registry with locale selection
+#[derive(Debug, Clone)]
+pub struct NotificationTemplates {
+    locale: String,
+    templates: HashMap<TemplateKey, TemplateDefinition>,
+}
+
+impl NotificationTemplates {
+    /// Built-in templates in English and German, rendered in `locale`
+    pub fn builtin(locale: &str) -> Self {
+        let mut templates = Self {
+            locale: normalize_locale(locale),
+            templates: HashMap::new(),
+        };
+        for (event, locale, subject, body) in BUILTIN_TEMPLATES {
+            templates.register(TemplateDefinition {
+                event: event.to_string(),
+                locale: locale.to_string(),
+                channel: None,
+                subject: Some(subject.to_string()),
+                body: body.to_string(),
+            });
+        }
+        templates
+    }
+
+    /// Selected locale
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Change the selected locale
+    pub fn set_locale(&mut self, locale: &str) {
+        self.locale = normalize_locale(locale);
+    }
+
+    /// Add or replace a template
+    pub fn register(&mut self, mut definition: TemplateDefinition) {
+        definition.locale = normalize_locale(&definition.locale);
+        let key = (
+            definition.event.clone(),
+            definition.channel,
+            definition.locale.clone(),
+        );
+        self.templates.insert(key, definition);
+    }
+
+    /// Load custom templates from a TOML file, overriding built-in ones
+    pub fn load_file(&mut self, path: &Path) -> Result<usize> {
+        let contents = std::fs::read_to_string(path)?;
+        let file: TemplateFile = toml::from_str(&contents).map_err(|e| {
+            LoxoneError::config(format!(
+                "Invalid notification template file {}: {e}",
+                path.display()
+            ))
+        })?;
+        for definition in &file.template {
+            validate_template(&definition.body)?;
+            if let Some(subject) = &definition.subject {
+                validate_template(subject)?;
+            }
+        }
+        let count = file.template.len();
+        for definition in file.template {
+            self.register(definition);
+        }
+        Ok(count)
+    }
+
+    /// Render `event` for `channel` with the given variables
+    pub fn render(
+        &self,
+        event: &str,
+        channel: ChannelKind,
+        vars: &Value,
+    ) -> Result<RenderedNotification> {
+        let definition = self.lookup(event, channel).ok_or_else(|| {
+            LoxoneError::not_found(format!("No notification template for event '{event}'"))
+        })?;
+
+        Ok(RenderedNotification {
+            subject: definition
+                .subject
+                .as_deref()
+                .map(|subject| render_template(subject, vars))
+                .transpose()?,
+            body: render_template(&definition.body, vars)?,
+            locale: definition.locale.clone(),
+        })
+    }
+
+    fn lookup(&self, event: &str, channel: ChannelKind) -> Option<&TemplateDefinition> {
+        let mut locales = vec![self.locale.as_str()];
+        if self.locale != FALLBACK_LOCALE {
+            locales.push(FALLBACK_LOCALE);
+        }
+        locales.into_iter().find_map(|locale| {
+            [Some(channel), None].into_iter().find_map(|channel| {
+                self.templates
+                    .get(&(event.to_string(), channel, locale.to_string()))
+            })
+        })
+    }
+}
+
+impl Default for NotificationTemplates {
+    fn default() -> Self {
+        Self::builtin(&resolve_locale(None))
+    }
+}
+
+/// Pick the notification locale
+///
+/// Uses `explicit` if given, else `LOXONE_LOCALE`, else the POSIX locale
+/// (`LC_ALL`, `LANG`), else English.
+pub fn resolve_locale(explicit: Option<&str>) -> String {
+    explicit
+        .map(str::to_string)
+        .or_else(|| std::env::var("LOXONE_LOCALE").ok())
+        .or_else(|| std::env::var("LC_ALL").ok())
+        .or_else(|| std::env::var("LANG").ok())
+        .map(|locale| normalize_locale(&locale))
+        .filter(|locale| !locale.is_empty() && locale != "c" && locale != "posix")
+        .unwrap_or_else(|| FALLBACK_LOCALE.to_string())
+}
+
+/// Reduce `de_DE.UTF-8` / `de-AT` to the language code `de`
+fn normalize_locale(locale: &str) -> String {
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// Check that a template's blocks are balanced
+pub fn validate_template(template: &str) -> Result<()> {
+    render_template(template, &Value::Null).map(|_| ())
+}
+
+/// Render a template against `vars`
+pub fn render_template(template: &str, vars: &Value) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| LoxoneError::invalid_input("Unclosed '{{' in template"))?;
+        let tag = after[..end].trim();
+        rest = &after[end + 2..];
+
+        if let Some(condition) = tag.strip_prefix("#if ") {
+            let (then_branch, else_branch, remainder) = split_if_block(rest)?;
+            let branch = if is_truthy(lookup_var(vars, condition.trim())) {
+                then_branch
+            } else {
+                else_branch
+            };
+            output.push_str(&render_template(branch, vars)?);
+            rest = remainder;
+        } else if tag == "else" || tag == "/if" {
+            return Err(LoxoneError::invalid_input(format!(
+                "Unexpected '{{{{{tag}}}}}' in template"
+            )));
+        } else {
+            output.push_str(&format_var(lookup_var(vars, tag)));
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Split the text after `{{#if …}}` into then/else branches and the remainder
+fn split_if_block(text: &str) -> Result<(&str, &str, &str)> {
+    let mut depth = 0;
+    let mut else_at = None;
+    let mut offset = 0;
+
+    while let Some(start) = text[offset..].find("{{") {
+        let tag_start = offset + start;
+        let end = text[tag_start..]
+            .find("}}")
+            .ok_or_else(|| LoxoneError::invalid_input("Unclosed '{{' in template"))?;
+        let tag_end = tag_start + end + 2;
+        let tag = text[tag_start + 2..tag_end - 2].trim();
+
+        if tag.starts_with("#if ") {
+            depth += 1;
+        } else if tag == "else" && depth == 0 {
+            else_at = Some((tag_start, tag_end));
+        } else if tag == "/if" {
+            if depth == 0 {
+                let remainder = &text[tag_end..];
+                return Ok(match else_at {
+                    Some((else_start, else_end)) => {
+                        (&text[..else_start], &text[else_end..tag_start], remainder)
+                    }
+                    None => (&text[..tag_start], "", remainder),
+                });
+            }
+            depth -= 1;
+        }
+        offset = tag_end;
+    }
+
+    Err(LoxoneError::invalid_input("Missing '{{/if}}' in template"))
+}
+
+fn lookup_var<'a>(vars: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(vars, |value, key| value.get(key))
+        .filter(|value| !value.is_null())
+}
+
+fn format_var(value: Option<&Value>) -> String {
+    match value {
+        None => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64().is_some_and(|v| v != 0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::Object(_)) => true,
+    }
+}
+
+/// Built-in templates: (event, locale, subject, body)
+const BUILTIN_TEMPLATES: &[(&str, &str, &str, &str)] = &[
+    (
+        "performance.response_time",
+        "en",
+        "Slow responses",
+        "High response time: {{value}} ms (threshold {{threshold}} ms)",
+    ),
+    (
+        "performance.response_time",
+        "de",
+        "Langsame Antworten",
+        "Hohe Antwortzeit: {{value}} ms (Grenzwert {{threshold}} ms)",
+    ),
+    (
+        "performance.cpu_usage",
+        "en",
+        "High CPU usage",
+        "High CPU usage: {{value}}% (threshold {{threshold}}%)",
+    ),
+    (
+        "performance.cpu_usage",
+        "de",
+        "Hohe CPU-Auslastung",
+        "Hohe CPU-Auslastung: {{value}} % (Grenzwert {{threshold}} %)",
+    ),
+    (
+        "performance.memory_usage",
+        "en",
+        "High memory usage",
+        "High memory usage: {{value}} MB (threshold {{threshold}} MB)",
+    ),
+    (
+        "performance.memory_usage",
+        "de",
+        "Hoher Speicherverbrauch",
+        "Hoher Speicherverbrauch: {{value}} MB (Grenzwert {{threshold}} MB)",
+    ),
+    (
+        "performance.error_rate",
+        "en",
+        "High error rate",
+        "High error rate: {{value}}% (threshold {{threshold}}%)",
+    ),
+    (
+        "performance.error_rate",
+        "de",
+        "Hohe Fehlerrate",
+        "Hohe Fehlerrate: {{value}} % (Grenzwert {{threshold}} %)",
+    ),
+    (
+        "performance.throughput",
+        "en",
+        "Low throughput",
+        "Low throughput: {{value}} requests/s (threshold {{threshold}})",
+    ),
+    (
+        "performance.throughput",
+        "de",
+        "Niedriger Durchsatz",
+        "Niedriger Durchsatz: {{value}} Anfragen/s (Grenzwert {{threshold}})",
+    ),
+    (
+        "alert.triggered",
+        "en",
+        "[{{severity}}] {{message}}",
+        "Alert {{id}} ({{severity}}): {{message}}",
+    ),
+    (
+        "alert.triggered",
+        "de",
+        "[{{severity}}] {{message}}",
+        "Alarm {{id}} ({{severity}}): {{message}}",
+    ),
+    (
+        "security.alarm_triggered",
+        "en",
+        "Alarm triggered{{#if room}} in {{room}}{{/if}}",
+        "The alarm {{name}} was triggered{{#if room}} in {{room}}{{/if}}{{#if cause}}: {{cause}}{{/if}}.",
+    ),
+    (
+        "security.alarm_triggered",
+        "de",
+        "Alarm ausgelöst{{#if room}} in {{room}}{{/if}}",
+        "Die Alarmanlage {{name}} wurde ausgelöst{{#if room}} in {{room}}{{/if}}{{#if cause}}: {{cause}}{{/if}}.",
+    ),
+    (
+        "heating.failure",
+        "en",
+        "Heating failure{{#if room}} in {{room}}{{/if}}",
+        "Heating problem{{#if room}} in {{room}}{{/if}}: {{message}}",
+    ),
+    (
+        "heating.failure",
+        "de",
+        "Heizungsstörung{{#if room}} in {{room}}{{/if}}",
+        "Heizungsproblem{{#if room}} in {{room}}{{/if}}: {{message}}",
+    ),
+    (
+        "power.on_battery",
+        "en",
+        "Power failure: running on UPS",
+        "Mains power failed ({{source}}){{#if battery_percent}}, UPS battery at {{battery_percent}}%{{/if}}. Background polling is reduced until power returns.",
+    ),
+    (
+        "power.on_battery",
+        "de",
+        "Stromausfall: Betrieb über USV",
+        "Die Netzversorgung ist ausgefallen ({{source}}){{#if battery_percent}}, USV-Akku bei {{battery_percent}} %{{/if}}. Hintergrundabfragen sind bis zur Rückkehr des Stroms reduziert.",
+    ),
+    (
+        "power.restored",
+        "en",
+        "Mains power restored",
+        "Mains power is back after {{duration}}. Normal polling has resumed.",
+    ),
+    (
+        "power.restored",
+        "de",
+        "Netzversorgung wiederhergestellt",
+        "Der Strom ist nach {{duration}} zurück. Die Abfragen laufen wieder normal.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_render_variables_and_conditionals() {
+        let vars = json!({"room": {"name": "Kitchen"}, "count": 2, "quiet": false});
+        let rendered = render_template(
+            "{{room.name}}: {{#if count}}{{count}} open{{#if quiet}} (quiet){{/if}}{{else}}all closed{{/if}}{{missing}}",
+            &vars,
+        )
+        .unwrap();
+        assert_eq!(rendered, "Kitchen: 2 open");
+
+        assert!(render_template("{{#if x}}unterminated", &vars).is_err());
+        assert!(render_template("{{/if}}", &vars).is_err());
+        assert!(render_template("{{open", &vars).is_err());
+    }
+
+    #[test]
+    fn test_locale_selection_and_fallback() {
+        assert_eq!(resolve_locale(Some("de_DE.UTF-8")), "de");
+        assert_eq!(resolve_locale(Some("de-AT")), "de");
+
+        let vars = json!({"value": 95.5, "threshold": 80});
+        let german = NotificationTemplates::builtin("de");
+        let rendered = german
+            .render("performance.cpu_usage", ChannelKind::Log, &vars)
+            .unwrap();
+        assert_eq!(
+            rendered.body,
+            "Hohe CPU-Auslastung: 95.5 % (Grenzwert 80 %)"
+        );
+
+        let french = NotificationTemplates::builtin("fr");
+        let rendered = french
+            .render("performance.cpu_usage", ChannelKind::Log, &vars)
+            .unwrap();
+        assert_eq!(rendered.locale, "en");
+        assert!(
+            french
+                .render("unknown.event", ChannelKind::Log, &vars)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_channel_templates_from_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            br#"
+[[template]]
+event = "performance.cpu_usage"
+locale = "de"
+channel = "email"
+subject = "Server: CPU {{value}} %"
+body = "Die CPU-Auslastung liegt bei {{value}} %."
+"#,
+        )
+        .unwrap();
+
+        let mut templates = NotificationTemplates::builtin("de");
+        assert_eq!(templates.load_file(file.path()).unwrap(), 1);
+
+        let vars = json!({"value": 91, "threshold": 80});
+        let email = templates
+            .render("performance.cpu_usage", ChannelKind::Email, &vars)
+            .unwrap();
+        assert_eq!(email.subject.as_deref(), Some("Server: CPU 91 %"));
+        assert_eq!(email.body, "Die CPU-Auslastung liegt bei 91 %.");
+
+        // Other channels keep the generic template
+        let webhook = templates
+            .render("performance.cpu_usage", ChannelKind::Webhook, &vars)
+            .unwrap();
+        assert!(webhook.body.starts_with("Hohe CPU-Auslastung"));
+    }
+}
@@ -1,9 +1,14 @@
 //! Performance reporting and alerting system
 
 use crate::error::{LoxoneError, Result};
+pub use crate::notifications::SmtpConfig;
+use crate::notifications::{
+    ChannelKind, NotificationTemplates, RenderedNotification, resolve_locale,
+};
 use crate::performance::{PerformanceMeasurement, PerformanceStatistics};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
@@ -21,6 +26,12 @@ pub struct ReporterConfig {
     pub alerting: AlertingConfig,
     /// Export configuration
     pub export: ExportConfig,
+    /// Notification locale (defaults to `LOXONE_LOCALE` or the system locale)
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// TOML file with custom notification templates
+    #[serde(default)]
+    pub templates_file: Option<PathBuf>,
 }
 
 impl Default for ReporterConfig {
@@ -31,6 +42,8 @@ impl Default for ReporterConfig {
             report_generation: ReportGenerationConfig::default(),
             alerting: AlertingConfig::default(),
             export: ExportConfig::default(),
+            locale: None,
+            templates_file: None,
         }
     }
 }
@@ -52,6 +65,8 @@ impl ReporterConfig {
             report_generation: ReportGenerationConfig::production(),
             alerting: AlertingConfig::production(),
             export: ExportConfig::production(),
+            locale: None,
+            templates_file: None,
         }
     }
 
@@ -63,6 +78,8 @@ impl ReporterConfig {
             report_generation: ReportGenerationConfig::development(),
             alerting: AlertingConfig::development(),
             export: ExportConfig::development(),
+            locale: None,
+            templates_file: None,
         }
     }
 
@@ -74,6 +91,8 @@ impl ReporterConfig {
             report_generation: ReportGenerationConfig::minimal(),
             alerting: AlertingConfig::disabled(),
             export: ExportConfig::disabled(),
+            locale: None,
+            templates_file: None,
         }
     }
 
@@ -156,35 +175,6 @@ impl ReportDestination {
     }
 }
 
-/// SMTP configuration for email alerts
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SmtpConfig {
-    pub server: String,
-    pub port: u16,
-    pub username: String,
-    pub password: String,
-    pub from_email: String,
-    pub to_emails: Vec<String>,
-    pub use_tls: bool,
-}
-
-impl SmtpConfig {
-    fn validate(&self) -> Result<()> {
-        if self.server.is_empty() {
-            return Err(LoxoneError::invalid_input("SMTP server cannot be empty"));
-        }
-        if self.from_email.is_empty() {
-            return Err(LoxoneError::invalid_input("From email cannot be empty"));
-        }
-        if self.to_emails.is_empty() {
-            return Err(LoxoneError::invalid_input(
-                "At least one recipient email must be configured",
-            ));
-        }
-        Ok(())
-    }
-}
-
 /// Report generation configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReportGenerationConfig {
@@ -691,6 +681,19 @@ pub enum AlertType {
     Throughput,
 }
 
+impl AlertType {
+    /// Notification template event for this alert type
+    pub fn template_event(&self) -> &'static str {
+        match self {
+            AlertType::ResponseTime => "performance.response_time",
+            AlertType::CpuUsage => "performance.cpu_usage",
+            AlertType::MemoryUsage => "performance.memory_usage",
+            AlertType::ErrorRate => "performance.error_rate",
+            AlertType::Throughput => "performance.throughput",
+        }
+    }
+}
+
 /// Alert severity
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AlertSeverity {
@@ -702,6 +705,7 @@ pub enum AlertSeverity {
 /// Performance reporter
 pub struct PerformanceReporter {
     config: ReporterConfig,
+    templates: NotificationTemplates,
     alert_history: RwLock<Vec<PerformanceAlert>>,
     report_history: RwLock<Vec<PerformanceReport>>,
     last_report_time: RwLock<Option<Instant>>,
@@ -712,8 +716,19 @@ impl PerformanceReporter {
     pub fn new(config: ReporterConfig) -> Result<Self> {
         config.validate()?;
 
+        let mut templates =
+            NotificationTemplates::builtin(&resolve_locale(config.locale.as_deref()));
+        if let Some(path) = &config.templates_file {
+            let count = templates.load_file(path)?;
+            debug!(
+                "Loaded {count} notification templates from {}",
+                path.display()
+            );
+        }
+
         Ok(Self {
             config,
+            templates,
             alert_history: RwLock::new(Vec::new()),
             report_history: RwLock::new(Vec::new()),
             last_report_time: RwLock::new(None),
@@ -899,7 +914,11 @@ impl PerformanceReporter {
                 id: uuid::Uuid::new_v4().to_string(),
                 alert_type: AlertType::ResponseTime,
                 severity: AlertSeverity::Warning,
-                message: format!("High response time: {}ms", duration.as_millis()),
+                message: self.alert_message(
+                    &AlertType::ResponseTime,
+                    duration.as_millis() as f64,
+                    self.config.alerting.thresholds.response_time_ms as f64,
+                ),
                 metric_value: duration.as_millis() as f64,
                 threshold: self.config.alerting.thresholds.response_time_ms as f64,
                 triggered_at: SystemTime::now()
@@ -917,7 +936,11 @@ impl PerformanceReporter {
                 id: uuid::Uuid::new_v4().to_string(),
                 alert_type: AlertType::CpuUsage,
                 severity: AlertSeverity::Critical,
-                message: format!("High CPU usage: {cpu:.1}%"),
+                message: self.alert_message(
+                    &AlertType::CpuUsage,
+                    (cpu * 10.0).round() / 10.0,
+                    self.config.alerting.thresholds.cpu_usage_percent,
+                ),
                 metric_value: cpu,
                 threshold: self.config.alerting.thresholds.cpu_usage_percent,
                 triggered_at: SystemTime::now()
@@ -958,6 +981,18 @@ impl PerformanceReporter {
                 ReportDestination::Console => {
                     eprintln!("ALERT: {} - {}", alert.message, alert.metric_value);
                 }
+                ReportDestination::Webhook { url, .. } => {
+                    let rendered = self.render_alert(alert, ChannelKind::Webhook);
+                    debug!("Would send alert to webhook {}: {:?}", url, rendered);
+                }
+                ReportDestination::Email { .. } => {
+                    let rendered = self.render_alert(alert, ChannelKind::Email);
+                    debug!("Would send alert e-mail: {:?}", rendered);
+                }
+                ReportDestination::Slack { webhook_url } => {
+                    let rendered = self.render_alert(alert, ChannelKind::Slack);
+                    debug!("Would send Slack alert to {}: {:?}", webhook_url, rendered);
+                }
                 _ => {
                     debug!("Would send alert to destination: {:?}", destination);
                 }
@@ -967,6 +1002,38 @@ impl PerformanceReporter {
         Ok(())
     }
 
+    /// Render the log message for an alert in the configured locale
+    fn alert_message(&self, alert_type: &AlertType, value: f64, threshold: f64) -> String {
+        let vars = serde_json::json!({"value": value, "threshold": threshold});
+        match self
+            .templates
+            .render(alert_type.template_event(), ChannelKind::Log, &vars)
+        {
+            Ok(rendered) => rendered.body,
+            Err(e) => {
+                warn!("Failed to render alert template: {e}");
+                format!("{alert_type:?} alert: {value} (threshold {threshold})")
+            }
+        }
+    }
+
+    /// Render an alert for an outbound channel
+    fn render_alert(&self, alert: &PerformanceAlert, channel: ChannelKind) -> RenderedNotification {
+        let vars = serde_json::json!({
+            "value": alert.metric_value,
+            "threshold": alert.threshold,
+            "severity": format!("{:?}", alert.severity),
+            "id": alert.id,
+        });
+        self.templates
+            .render(alert.alert_type.template_event(), channel, &vars)
+            .unwrap_or_else(|_| RenderedNotification {
+                subject: None,
+                body: alert.message.clone(),
+                locale: self.templates.locale().to_string(),
+            })
+    }
+
     async fn log_measurement(&self, measurement: &PerformanceMeasurement) -> Result<()> {
         let duration = measurement
             .timing
@@ -1064,6 +1131,20 @@ mod tests {
         assert!(reporter.is_ok());
     }
 
+    #[test]
+    fn test_alert_message_uses_locale() {
+        let config = ReporterConfig {
+            locale: Some("de_DE.UTF-8".to_string()),
+            ..ReporterConfig::development()
+        };
+        let reporter = PerformanceReporter::new(config).unwrap();
+
+        assert_eq!(
+            reporter.alert_message(&AlertType::CpuUsage, 92.5, 80.0),
+            "Hohe CPU-Auslastung: 92.5 % (Grenzwert 80.0 %)"
+        );
+    }
+
     #[tokio::test]
     async fn test_report_generation() {
         let config = ReporterConfig::development();
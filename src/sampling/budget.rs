@@ -0,0 +1,348 @@
+//! Token and cost budgets for sampling requests
+//!
+//! Every sampling request is charged to the client it runs for (the API key
+//! label of the session, else the session id, else `local`) and to the
+//! current day. Token counts come from the provider when it reports them
+//! ([`SamplingResponse::usage`]) and are estimated from the text length
+//! otherwise; costs come from the provider or from the configured price per
+//! 1000 tokens.
+//!
+//! Before a request is sent, its worst case (estimated prompt plus
+//! `max_tokens`) is checked against the daily limits, so a request that could
+//! overrun the budget is refused instead of being paid for. Usage resets at
+//! local midnight and is served as the `loxone://system/sampling-usage`
+//! resource.
+
+use crate::error::{LoxoneError, Result};
+use crate::sampling::{SamplingRequest, SamplingResponse};
+use crate::server::client_sessions::ClientSessionRegistry;
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// URI of the usage resource
+pub const SAMPLING_USAGE_URI: &str = "loxone://system/sampling-usage";
+
+/// Characters per token used for estimates
+pub const CHARS_PER_TOKEN: u64 = 4;
+
+/// Client name for requests outside any session
+pub const LOCAL_CLIENT: &str = "local";
+
+/// Daily sampling limits (`None`: unlimited)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplingBudgetConfig {
+    /// Tokens per day across all clients
+    pub daily_tokens: Option<u64>,
+    /// Tokens per day for one client
+    pub client_daily_tokens: Option<u64>,
+    /// Cost in USD per day across all clients
+    pub daily_cost_usd: Option<f64>,
+    /// Price per 1000 tokens for responses that report no cost
+    pub cost_per_1k_tokens_usd: f64,
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|v| v.trim().parse().ok())
+}
+
+impl Default for SamplingBudgetConfig {
+    fn default() -> Self {
+        Self {
+            daily_tokens: env_parse("LOXONE_SAMPLING_DAILY_TOKENS"),
+            client_daily_tokens: env_parse("LOXONE_SAMPLING_CLIENT_DAILY_TOKENS"),
+            daily_cost_usd: env_parse("LOXONE_SAMPLING_DAILY_COST_USD"),
+            cost_per_1k_tokens_usd: env_parse("LOXONE_SAMPLING_COST_PER_1K_TOKENS").unwrap_or(0.0),
+        }
+    }
+}
+
+/// Token counts of one request as reported by the provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SamplingUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Cost in USD, if the provider reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+/// Usage summed over requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    /// Requests whose token counts were estimated
+    pub estimated_requests: u64,
+    /// Requests refused by the budget
+    pub refused_requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    pub fn tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+#[derive(Debug)]
+struct DailyUsage {
+    date: NaiveDate,
+    total: UsageTotals,
+    clients: BTreeMap<String, UsageTotals>,
+}
+
+impl DailyUsage {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            total: UsageTotals::default(),
+            clients: BTreeMap::new(),
+        }
+    }
+}
+
+/// Estimated tokens of a text
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Estimated prompt tokens of a request
+pub fn estimate_request_tokens(request: &SamplingRequest) -> u64 {
+    let system = request.system_prompt.as_deref().map_or(0, estimate_tokens);
+    request
+        .messages
+        .iter()
+        .filter_map(|m| m.content.text.as_deref())
+        .map(estimate_tokens)
+        .sum::<u64>()
+        + system
+}
+
+/// Client the current task's requests are charged to
+pub fn current_client() -> String {
+    let Some(id) = ClientSessionRegistry::current() else {
+        return LOCAL_CLIENT.to_string();
+    };
+    ClientSessionRegistry::global()
+        .sessions()
+        .into_iter()
+        .find(|s| s.id == id)
+        .and_then(|s| s.name)
+        .unwrap_or(id)
+}
+
+/// Daily sampling usage and limits
+#[derive(Debug)]
+pub struct SamplingBudget {
+    config: SamplingBudgetConfig,
+    usage: Mutex<DailyUsage>,
+}
+
+impl SamplingBudget {
+    pub fn new(config: SamplingBudgetConfig) -> Self {
+        Self {
+            config,
+            usage: Mutex::new(DailyUsage::new(Local::now().date_naive())),
+        }
+    }
+
+    /// Usage of today, starting a new day if the date changed
+    fn today(&self, date: NaiveDate) -> MutexGuard<'_, DailyUsage> {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        if usage.date != date {
+            *usage = DailyUsage::new(date);
+        }
+        usage
+    }
+
+    fn cost(&self, tokens: u64) -> f64 {
+        tokens as f64 / 1000.0 * self.config.cost_per_1k_tokens_usd
+    }
+
+    /// Refuse `request` for `client` if its worst case exceeds a daily limit
+    pub fn check(&self, client: &str, request: &SamplingRequest) -> Result<()> {
+        self.check_on(Local::now().date_naive(), client, request)
+    }
+
+    fn check_on(&self, date: NaiveDate, client: &str, request: &SamplingRequest) -> Result<()> {
+        let worst_case = estimate_request_tokens(request)
+            + u64::from(request.sampling_params.max_tokens.unwrap_or(0));
+        let mut usage = self.today(date);
+        let used_by_client = usage.clients.get(client).map_or(0, UsageTotals::tokens);
+        let exceeded = if self
+            .config
+            .daily_tokens
+            .is_some_and(|limit| usage.total.tokens() + worst_case > limit)
+        {
+            Some("daily token budget")
+        } else if self
+            .config
+            .client_daily_tokens
+            .is_some_and(|limit| used_by_client + worst_case > limit)
+        {
+            Some("daily token budget of this client")
+        } else if self
+            .config
+            .daily_cost_usd
+            .is_some_and(|limit| usage.total.cost_usd + self.cost(worst_case) > limit)
+        {
+            Some("daily cost budget")
+        } else {
+            None
+        };
+        let Some(exceeded) = exceeded else {
+            return Ok(());
+        };
+        usage.total.refused_requests += 1;
+        usage
+            .clients
+            .entry(client.to_string())
+            .or_default()
+            .refused_requests += 1;
+        Err(LoxoneError::resource_exhausted(format!(
+            "Sampling request of up to {worst_case} tokens would exceed the {exceeded}; usage resets at midnight"
+        )))
+    }
+
+    /// Charge a completed request to `client`
+    pub fn record(
+        &self,
+        client: &str,
+        request: &SamplingRequest,
+        response: &SamplingResponse,
+    ) -> SamplingUsage {
+        self.record_on(Local::now().date_naive(), client, request, response)
+    }
+
+    fn record_on(
+        &self,
+        date: NaiveDate,
+        client: &str,
+        request: &SamplingRequest,
+        response: &SamplingResponse,
+    ) -> SamplingUsage {
+        let estimated = response.usage.is_none();
+        let mut charged = response.usage.unwrap_or_else(|| SamplingUsage {
+            input_tokens: estimate_request_tokens(request),
+            output_tokens: response.content.text.as_deref().map_or(0, estimate_tokens),
+            cost_usd: None,
+        });
+        let cost = charged
+            .cost_usd
+            .unwrap_or_else(|| self.cost(charged.input_tokens + charged.output_tokens));
+        charged.cost_usd = Some(cost);
+
+        let mut usage = self.today(date);
+        let DailyUsage { total, clients, .. } = &mut *usage;
+        for totals in [total, clients.entry(client.to_string()).or_default()] {
+            totals.requests += 1;
+            totals.estimated_requests += u64::from(estimated);
+            totals.input_tokens += charged.input_tokens;
+            totals.output_tokens += charged.output_tokens;
+            totals.cost_usd += cost;
+        }
+        charged
+    }
+
+    /// Content of [`SAMPLING_USAGE_URI`]
+    pub fn usage_resource(&self) -> Value {
+        let usage = self.today(Local::now().date_naive());
+        let remaining = |limit: Option<u64>, used: u64| limit.map(|l| l.saturating_sub(used));
+        let clients: BTreeMap<&String, Value> = usage
+            .clients
+            .iter()
+            .map(|(name, totals)| {
+                (
+                    name,
+                    json!({
+                        "usage": totals,
+                        "remaining_tokens": remaining(self.config.client_daily_tokens, totals.tokens()),
+                    }),
+                )
+            })
+            .collect();
+        json!({
+            "uri": SAMPLING_USAGE_URI,
+            "date": usage.date.to_string(),
+            "limits": self.config,
+            "total": usage.total,
+            "remaining_tokens": remaining(self.config.daily_tokens, usage.total.tokens()),
+            "remaining_cost_usd": self
+                .config
+                .daily_cost_usd
+                .map(|limit| (limit - usage.total.cost_usd).max(0.0)),
+            "clients": clients,
+        })
+    }
+}
+
+impl Default for SamplingBudget {
+    fn default() -> Self {
+        Self::new(SamplingBudgetConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::{SamplingMessage, SamplingMessageContent};
+
+    fn response(text: &str, usage: Option<SamplingUsage>) -> SamplingResponse {
+        SamplingResponse {
+            model: "test".to_string(),
+            stop_reason: "endTurn".to_string(),
+            role: "assistant".to_string(),
+            content: SamplingMessageContent::text(text),
+            usage,
+        }
+    }
+
+    #[test]
+    fn test_budget_charges_clients_and_refuses_overruns() {
+        let budget = SamplingBudget::new(SamplingBudgetConfig {
+            daily_tokens: Some(1000),
+            client_daily_tokens: Some(400),
+            daily_cost_usd: None,
+            cost_per_1k_tokens_usd: 0.5,
+        });
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let request =
+            SamplingRequest::new(vec![SamplingMessage::user("x".repeat(80))]).with_max_tokens(100);
+
+        budget.check_on(day, "kitchen-tablet", &request).unwrap();
+        let reported = SamplingUsage {
+            input_tokens: 150,
+            output_tokens: 150,
+            cost_usd: None,
+        };
+        let charged = budget.record_on(
+            day,
+            "kitchen-tablet",
+            &request,
+            &response("ok", Some(reported)),
+        );
+        assert_eq!(charged.cost_usd, Some(0.15));
+        // 300 used + 20 prompt + 100 max_tokens exceeds the client's 400
+        assert!(budget.check_on(day, "kitchen-tablet", &request).is_err());
+        budget.check_on(day, "desktop", &request).unwrap();
+
+        let charged = budget.record_on(day, "desktop", &request, &response(&"y".repeat(40), None));
+        assert_eq!((charged.input_tokens, charged.output_tokens), (20, 10));
+        {
+            let usage = budget.today(day);
+            assert_eq!(usage.total.tokens(), 330);
+            assert_eq!(usage.total.estimated_requests, 1);
+            assert_eq!(usage.clients["kitchen-tablet"].refused_requests, 1);
+        }
+
+        // A new day starts from zero
+        let next = day.succ_opt().unwrap();
+        budget.check_on(next, "kitchen-tablet", &request).unwrap();
+        assert_eq!(budget.today(next).total.tokens(), 0);
+    }
+}
@@ -4,6 +4,7 @@
 //! to MCP clients following the proper MCP sampling protocol.
 
 use crate::error::{LoxoneError, Result};
+use crate::sampling::budget::{SamplingBudget, current_client};
 use crate::sampling::{SamplingRequest, SamplingResponse};
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -195,6 +196,7 @@ impl SamplingClient for MockSamplingClient {
             stop_reason: "endTurn".to_string(),
             role: "assistant".to_string(),
             content: crate::sampling::SamplingMessageContent::text(response_text),
+            usage: None,
         })
     }
 
@@ -276,6 +278,8 @@ pub struct SamplingClientManager {
     config: Arc<crate::sampling::config::ProviderFactoryConfig>,
     /// Health status of providers
     provider_health: Arc<RwLock<std::collections::HashMap<String, bool>>>,
+    /// Daily token and cost budget, if enforced
+    budget: Option<Arc<SamplingBudget>>,
 }
 
 impl SamplingClientManager {
@@ -345,16 +349,37 @@ impl SamplingClientManager {
             capabilities,
             config: config_arc,
             provider_health,
+            budget: None,
         }
     }
 
+    /// Charge requests to `budget` and refuse those that would exceed it
+    pub fn with_budget(mut self, budget: Arc<SamplingBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
     /// Check if sampling is available
     pub async fn is_available(&self) -> bool {
         self.primary_client.is_sampling_supported()
     }
 
     /// Send a sampling request with intelligent fallback
+    ///
+    /// With a budget, the request is charged to the current client and
+    /// refused before sending if it could exceed a daily limit.
     pub async fn request_sampling(&self, request: SamplingRequest) -> Result<SamplingResponse> {
+        let Some(budget) = &self.budget else {
+            return self.send_with_fallback(&request).await;
+        };
+        let client = current_client();
+        budget.check(&client, &request)?;
+        let response = self.send_with_fallback(&request).await?;
+        budget.record(&client, &request, &response);
+        Ok(response)
+    }
+
+    async fn send_with_fallback(&self, request: &SamplingRequest) -> Result<SamplingResponse> {
         if !self.primary_client.is_sampling_supported() {
             warn!("Sampling request attempted but not supported by client");
             return Err(LoxoneError::ServiceUnavailable(
@@ -368,7 +393,7 @@ impl SamplingClientManager {
         );
 
         // Try primary provider first
-        match self.try_primary_client(&request).await {
+        match self.try_primary_client(request).await {
             Ok(response) => {
                 debug!(
                     "✅ Primary provider response received from model: {}",
@@ -395,7 +420,7 @@ impl SamplingClientManager {
                         self.fallback_clients.len()
                     );
 
-                    match self.try_fallback_client(fallback_client, &request).await {
+                    match self.try_fallback_client(fallback_client, request).await {
                         Ok(response) => {
                             info!(
                                 "✅ Fallback provider {} succeeded: {}",
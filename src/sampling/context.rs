@@ -0,0 +1,324 @@
+//! `includeContext` support for sampling requests
+//!
+//! The MCP spec lets a sampling request ask for server context to be included
+//! in the prompt (`none`, `thisServer`, `allServers`). This module renders the
+//! relevant server resources — rooms, device states and recent sensor history —
+//! into a single context message, keeping the total size within a character
+//! budget so large installations do not blow the model's context window.
+//!
+//! Only this server's resources are available here, so `allServers` is treated
+//! like `thisServer`.
+
+use super::{SamplingMessage, SamplingRequest};
+use crate::client::ClientContext;
+use serde_json::{Value, json};
+use tracing::{debug, warn};
+
+/// Context inclusion requested by a sampling request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludeContext {
+    /// Do not include any server context
+    None,
+    /// Include context from this server
+    ThisServer,
+    /// Include context from all connected servers
+    AllServers,
+}
+
+impl IncludeContext {
+    /// Parse the `include_context` field; unknown values fall back to `None`
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("none") | Some("") => Self::None,
+            Some("thisserver") | Some("this_server") => Self::ThisServer,
+            Some("allservers") | Some("all_servers") => Self::AllServers,
+            Some(other) => {
+                warn!("Unknown include_context value '{other}', ignoring");
+                Self::None
+            }
+        }
+    }
+
+    /// Whether any context should be embedded
+    pub fn is_requested(self) -> bool {
+        self != Self::None
+    }
+}
+
+/// Size limits for embedded context
+#[derive(Debug, Clone, Copy)]
+pub struct ContextBudget {
+    /// Maximum characters of rendered context
+    pub max_chars: usize,
+    /// Maximum recent history entries considered
+    pub max_history_entries: usize,
+}
+
+impl Default for ContextBudget {
+    fn default() -> Self {
+        Self {
+            max_chars: 8000,
+            max_history_entries: 20,
+        }
+    }
+}
+
+/// Renders server resources into a budgeted context block
+pub struct SamplingContextBuilder<'a> {
+    context: &'a ClientContext,
+    budget: ContextBudget,
+}
+
+impl<'a> SamplingContextBuilder<'a> {
+    /// Create a builder over the cached client context
+    pub fn new(context: &'a ClientContext, budget: ContextBudget) -> Self {
+        Self { context, budget }
+    }
+
+    /// Render the context block, or `None` when nothing fits or nothing is cached
+    ///
+    /// Sections are filled in priority order (rooms, device states, history)
+    /// and each section stops adding items once the budget is used up.
+    pub async fn build(&self) -> Option<String> {
+        let sections = [
+            ("Rooms", "loxone://rooms", self.room_items().await),
+            (
+                "Device states",
+                "loxone://devices/all",
+                self.device_items().await,
+            ),
+            (
+                "Recent history",
+                "loxone://history/recent",
+                self.history_items().await,
+            ),
+        ];
+
+        let mut remaining = self.budget.max_chars;
+        let mut rendered = Vec::new();
+        for (title, uri, items) in sections {
+            if items.is_empty() {
+                continue;
+            }
+            match render_section(title, uri, &items, remaining) {
+                Some(section) => {
+                    remaining = remaining.saturating_sub(section.len() + 2);
+                    rendered.push(section);
+                }
+                None => break,
+            }
+        }
+
+        (!rendered.is_empty()).then(|| rendered.join("\n\n"))
+    }
+
+    async fn room_items(&self) -> Vec<Value> {
+        let rooms = self.context.rooms.read().await;
+        let mut items: Vec<_> = rooms
+            .values()
+            .map(|room| json!({"name": room.name, "devices": room.device_count}))
+            .collect();
+        items.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        items
+    }
+
+    async fn device_items(&self) -> Vec<Value> {
+        let devices = self.context.devices.read().await;
+        let mut items: Vec<_> = devices
+            .values()
+            .map(|device| {
+                json!({
+                    "name": device.name,
+                    "type": device.device_type,
+                    "room": device.room,
+                    "uuid": device.uuid,
+                    "states": device.states,
+                })
+            })
+            .collect();
+        items.sort_by(|a, b| {
+            (a["room"].as_str(), a["name"].as_str()).cmp(&(b["room"].as_str(), b["name"].as_str()))
+        });
+        items
+    }
+
+    async fn history_items(&self) -> Vec<Value> {
+        let Some(logger) = self.context.sensor_logger.read().await.clone() else {
+            return Vec::new();
+        };
+
+        let mut entries: Vec<_> = logger
+            .get_all_history()
+            .await
+            .into_values()
+            .flatten()
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        entries
+            .into_iter()
+            .take(self.budget.max_history_entries)
+            .map(|entry| {
+                json!({
+                    "time": entry.timestamp.to_rfc3339(),
+                    "sensor": entry.sensor_name,
+                    "room": entry.room,
+                    "from": entry.old_value,
+                    "to": entry.new_value,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Render one resource section as JSON lines within `budget` characters
+fn render_section(title: &str, uri: &str, items: &[Value], budget: usize) -> Option<String> {
+    let mut section = format!("## {title} ({uri})");
+    if section.len() >= budget {
+        return None;
+    }
+
+    let mut included = 0;
+    for item in items {
+        let line = format!("\n{item}");
+        // Leave room for the truncation marker
+        if section.len() + line.len() + 40 > budget {
+            break;
+        }
+        section.push_str(&line);
+        included += 1;
+    }
+
+    if included == 0 {
+        return None;
+    }
+    if included < items.len() {
+        section.push_str(&format!("\n… {} more omitted", items.len() - included));
+    }
+    Some(section)
+}
+
+/// Embed server context into `request` if it asks for it
+///
+/// The context is added as the first message and `include_context` is reset
+/// to `none` so a client that also honors the field does not add it twice.
+/// Returns whether context was embedded.
+pub async fn embed_requested_context(
+    request: &mut SamplingRequest,
+    context: &ClientContext,
+    budget: ContextBudget,
+) -> bool {
+    if !IncludeContext::parse(request.include_context.as_deref()).is_requested() {
+        return false;
+    }
+
+    let Some(text) = SamplingContextBuilder::new(context, budget).build().await else {
+        debug!("include_context requested but no server context is cached");
+        return false;
+    };
+
+    debug!("Embedding {} characters of server context", text.len());
+    request.messages.insert(
+        0,
+        SamplingMessage::user(format!(
+            "Context from the Loxone MCP server resources:\n\n{text}"
+        )),
+    );
+    request.include_context = Some("none".to_string());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{LoxoneDevice, LoxoneRoom};
+    use std::collections::HashMap;
+
+    async fn populated_context(devices: usize) -> ClientContext {
+        let context = ClientContext::new();
+        context.rooms.write().await.insert(
+            "room-1".to_string(),
+            LoxoneRoom {
+                uuid: "room-1".to_string(),
+                name: "Kitchen".to_string(),
+                device_count: devices,
+            },
+        );
+        let mut map = context.devices.write().await;
+        for i in 0..devices {
+            map.insert(
+                format!("dev-{i}"),
+                LoxoneDevice {
+                    uuid: format!("dev-{i}"),
+                    name: format!("Light {i}"),
+                    device_type: "LightControllerV2".to_string(),
+                    room: Some("Kitchen".to_string()),
+                    states: HashMap::from([("active".to_string(), json!(1))]),
+                    category: "lighting".to_string(),
+                    loxone_category: None,
+                    sub_controls: HashMap::new(),
+                },
+            );
+        }
+        drop(map);
+        context
+    }
+
+    #[test]
+    fn test_parse_include_context() {
+        assert_eq!(IncludeContext::parse(None), IncludeContext::None);
+        assert_eq!(IncludeContext::parse(Some("none")), IncludeContext::None);
+        assert_eq!(
+            IncludeContext::parse(Some("thisServer")),
+            IncludeContext::ThisServer
+        );
+        assert_eq!(
+            IncludeContext::parse(Some("allServers")),
+            IncludeContext::AllServers
+        );
+        assert_eq!(IncludeContext::parse(Some("bogus")), IncludeContext::None);
+    }
+
+    #[tokio::test]
+    async fn test_embed_context_when_requested() {
+        let context = populated_context(2).await;
+        let mut request = SamplingRequest::new(vec![SamplingMessage::user("Dim the kitchen")]);
+
+        assert!(!embed_requested_context(&mut request, &context, ContextBudget::default()).await);
+        assert_eq!(request.messages.len(), 1);
+
+        request.include_context = Some("thisServer".to_string());
+        assert!(embed_requested_context(&mut request, &context, ContextBudget::default()).await);
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.include_context.as_deref(), Some("none"));
+
+        let text = request.messages[0].content.text.as_deref().unwrap();
+        assert!(text.contains("loxone://rooms"));
+        assert!(text.contains("Light 1"));
+    }
+
+    #[tokio::test]
+    async fn test_context_respects_budget() {
+        let context = populated_context(200).await;
+        let budget = ContextBudget {
+            max_chars: 1500,
+            ..Default::default()
+        };
+
+        let text = SamplingContextBuilder::new(&context, budget)
+            .build()
+            .await
+            .unwrap();
+        assert!(text.len() <= 1500);
+        assert!(text.contains("more omitted"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_context_embeds_nothing() {
+        let context = ClientContext::new();
+        let mut request = SamplingRequest::new(vec![SamplingMessage::user("Hello")]);
+        request.include_context = Some("allServers".to_string());
+
+        assert!(!embed_requested_context(&mut request, &context, ContextBudget::default()).await);
+        assert_eq!(request.messages.len(), 1);
+    }
+}
@@ -627,6 +627,7 @@ mod tests {
                     room: Some("Living Room".to_string()),
                     states: HashMap::new(),
                     category: "Lighting".to_string(),
+                    loxone_category: None,
                     sub_controls: HashMap::new(),
                 },
             );
@@ -4,8 +4,10 @@
 //! LLM completions from clients. This is the proper way to integrate LLMs with MCP
 //! instead of making direct API calls.
 
+pub mod budget;
 pub mod client;
 pub mod config;
+pub mod context;
 pub mod executor;
 pub mod ollama_http;
 pub mod protocol;
@@ -203,6 +205,12 @@ impl SamplingRequest {
         self
     }
 
+    /// Request server context (`none`, `thisServer` or `allServers`)
+    pub fn with_include_context<S: Into<String>>(mut self, include_context: S) -> Self {
+        self.include_context = Some(include_context.into());
+        self
+    }
+
     /// Add metadata
     pub fn with_metadata(mut self, key: String, value: serde_json::Value) -> Self {
         if self.metadata.is_none() {
@@ -220,6 +228,9 @@ pub struct SamplingResponse {
     pub stop_reason: String,
     pub role: String,
     pub content: SamplingMessageContent,
+    /// Token counts reported by the provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<budget::SamplingUsage>,
 }
 
 /// Sampling request builder for home automation scenarios
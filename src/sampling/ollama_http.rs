@@ -6,6 +6,7 @@
 use crate::error::{LoxoneError, Result};
 #[cfg(test)]
 use crate::sampling::SamplingMessage;
+use crate::sampling::budget::SamplingUsage;
 use crate::sampling::{SamplingRequest, SamplingResponse};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -49,7 +50,6 @@ struct OllamaGenerateResponse {
     #[allow(dead_code)]
     load_duration: u64,
     #[serde(default)]
-    #[allow(dead_code)]
     prompt_eval_count: u32,
     #[serde(default)]
     eval_count: u32,
@@ -217,6 +217,11 @@ impl OllamaHttpClient {
             },
             role: "assistant".to_string(),
             content: crate::sampling::SamplingMessageContent::text(ollama_response.response),
+            usage: Some(SamplingUsage {
+                input_tokens: u64::from(ollama_response.prompt_eval_count),
+                output_tokens: u64::from(ollama_response.eval_count),
+                cost_usd: None,
+            }),
         })
     }
 
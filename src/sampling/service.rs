@@ -4,6 +4,7 @@
 //! to provide a complete LLM-powered home automation solution.
 
 use super::client::{SamplingCapabilities, SamplingClient};
+use super::context::{ContextBudget, embed_requested_context};
 use super::executor::{BatchExecutionResult, CommandExecutor, ExecutionContext};
 use super::response_parser::{CommandExtractor, SamplingResponse as ParsedResponse};
 use super::{AutomationSamplingBuilder, SamplingMessage, SamplingRequest};
@@ -67,6 +68,13 @@ pub struct SamplingServiceConfig {
     pub enable_response_caching: bool,
     /// Timeout for LLM sampling requests (seconds)
     pub sampling_timeout_seconds: u32,
+    /// Character budget for context embedded via `include_context`
+    #[serde(default = "default_context_budget_chars")]
+    pub context_budget_chars: usize,
+}
+
+fn default_context_budget_chars() -> usize {
+    ContextBudget::default().max_chars
 }
 
 impl Default for SamplingServiceConfig {
@@ -78,6 +86,7 @@ impl Default for SamplingServiceConfig {
             require_human_approval: true,
             enable_response_caching: true,
             sampling_timeout_seconds: 30,
+            context_budget_chars: default_context_budget_chars(),
         }
     }
 }
@@ -274,7 +283,13 @@ impl SamplingService {
     }
 
     /// Execute LLM sampling
-    async fn execute_sampling(&self, request: SamplingRequest) -> Result<String> {
+    async fn execute_sampling(&self, mut request: SamplingRequest) -> Result<String> {
+        let budget = ContextBudget {
+            max_chars: self.config.context_budget_chars,
+            ..Default::default()
+        };
+        embed_requested_context(&mut request, &self.client_context, budget).await;
+
         debug!(
             "Executing LLM sampling with {} messages",
             request.messages.len()
@@ -0,0 +1,248 @@
+//! Identity of the API key behind a request
+//!
+//! One HTTP server serves every client, each with its own API key. The HTTP
+//! auth path resolves the bearer key of each request into a [`Caller`] and
+//! runs the request on
+//! [`LoxoneMcpServer::for_caller`](crate::server::macro_backend::LoxoneMcpServer::for_caller),
+//! so the role, tenant and guest grant of the key apply to that request
+//! only, whatever key the server itself was started with.
+
+use crate::error::{LoxoneError, Result};
+use crate::security::guest_access;
+use crate::security::key_store::KeyStore;
+use crate::security::tenants::{TenantRegistry, TenantScope};
+use crate::services::command_history::key_label;
+
+/// Operations checked for every caller
+pub const OPERATIONS: [&str; 4] = ["admin", "control", "monitor", "read"];
+
+/// The API key a request was made with
+#[derive(Debug, Clone)]
+pub struct Caller {
+    /// Key label recorded with commands, e.g. `lmcp_operator_001`
+    pub label: String,
+    /// [`OPERATIONS`] the key grants
+    pub capabilities: Vec<String>,
+    /// Devices the key is limited to
+    pub scope: Option<TenantScope>,
+}
+
+impl Caller {
+    /// Resolve `key` against the key store
+    ///
+    /// Guest keys are limited to their granted devices. Fails for unknown,
+    /// inactive or expired keys, and for tenant keys whose tenant is not in
+    /// `tenants`.
+    pub async fn resolve(
+        store: &KeyStore,
+        key: &str,
+        tenants: Option<&TenantRegistry>,
+    ) -> Result<Self> {
+        let api_key = store.validate_key(key, None).await?;
+        let scope = match (guest_access::guest_scope(&api_key), &api_key.tenant) {
+            (Some(scope), _) => Some(scope),
+            (None, Some(tenant)) => Some(
+                tenants
+                    .ok_or_else(|| {
+                        LoxoneError::config(format!(
+                            "Key is scoped to tenant '{tenant}' but no tenants file is configured"
+                        ))
+                    })?
+                    .scope(tenant)?,
+            ),
+            (None, None) => None,
+        };
+        let mut capabilities = Vec::new();
+        for op in OPERATIONS {
+            if store.check_permission(key, op).await? {
+                capabilities.push(op.to_string());
+            }
+        }
+        Ok(Self {
+            label: key_label(key),
+            capabilities,
+            scope,
+        })
+    }
+
+    /// Whether the key may use admin-only tools and resources
+    pub fn is_admin(&self) -> bool {
+        self.capabilities.iter().any(|c| c == "admin")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{
+        TestServer, assert_command_sent, assert_no_command_sent, assert_tool_ok, device_uuid,
+        sample_house,
+    };
+    use crate::security::guest_access::GuestGrant;
+    use crate::security::key_store::{ApiKey, ApiKeyRole, KeyStoreBackend, KeyStoreConfig};
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    async fn memory_store() -> KeyStore {
+        KeyStore::new(KeyStoreConfig {
+            backend: KeyStoreBackend::Memory,
+            file_path: None,
+            auto_save: false,
+            encrypt_at_rest: false,
+        })
+        .await
+        .unwrap()
+    }
+
+    fn key(id: &str, role: ApiKeyRole, tenant: Option<&str>) -> ApiKey {
+        ApiKey {
+            id: id.to_string(),
+            name: id.to_string(),
+            role,
+            created_by: "admin".to_string(),
+            created_at: Utc::now(),
+            expires_at: None,
+            ip_whitelist: Vec::new(),
+            active: true,
+            last_used: None,
+            usage_count: 0,
+            metadata: HashMap::new(),
+            tenant: tenant.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenant_key_is_scoped_per_call() {
+        let store = memory_store().await;
+        store
+            .add_key(key(
+                "lmcp_operator_001_secret",
+                ApiKeyRole::Operator,
+                Some("bedroom"),
+            ))
+            .await
+            .unwrap();
+        let tenants =
+            TenantRegistry::from_toml("[[tenant]]\nid = \"bedroom\"\nrooms = [\"Bedroom\"]\n")
+                .unwrap();
+
+        assert!(
+            Caller::resolve(&store, "lmcp_operator_001_secret", None)
+                .await
+                .is_err()
+        );
+        assert!(
+            Caller::resolve(&store, "unknown", Some(&tenants))
+                .await
+                .is_err()
+        );
+        let caller = Caller::resolve(&store, "lmcp_operator_001_secret", Some(&tenants))
+            .await
+            .unwrap();
+        assert_eq!(caller.label, "lmcp_operator_001");
+
+        let fixture = TestServer::new(sample_house()).await;
+        let tenant = fixture.for_caller(&caller).await.unwrap();
+        let ceiling = device_uuid("Kitchen", "Ceiling");
+        let result = tenant
+            .control_lights(
+                "device".into(),
+                Some(ceiling.clone()),
+                "on".into(),
+                None,
+                None,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_no_command_sent(&fixture.client, &ceiling);
+
+        // The server itself stays unscoped for other callers
+        let result = fixture
+            .control_lights(
+                "device".into(),
+                Some(ceiling.clone()),
+                "on".into(),
+                None,
+                None,
+            )
+            .await;
+        assert_tool_ok(result);
+        assert_command_sent(&fixture.client, &ceiling, "on");
+    }
+
+    #[tokio::test]
+    async fn test_guest_key_cannot_command_outside_grant() {
+        let store = memory_store().await;
+        let grant = GuestGrant {
+            name: "Kitchen lights".to_string(),
+            rooms: vec!["Kitchen".to_string()],
+            category: Some("lights".to_string()),
+            allow_control: true,
+            valid_for: Duration::from_secs(3600),
+        };
+        let key = guest_access::mint_guest_key(&store, &grant, &sample_house(), "admin")
+            .await
+            .unwrap();
+        let caller = Caller::resolve(&store, &key.id, None).await.unwrap();
+
+        let fixture = TestServer::new(sample_house()).await;
+        let guest = fixture.for_caller(&caller).await.unwrap();
+        let window = device_uuid("Kitchen", "Window");
+        let result = guest
+            .control_blinds(window.clone(), Some("down".into()), None, None)
+            .await;
+        assert!(result.is_err());
+        assert_no_command_sent(&fixture.client, &window);
+
+        let ceiling = device_uuid("Kitchen", "Ceiling");
+        let result = guest
+            .control_lights(
+                "device".into(),
+                Some(ceiling.clone()),
+                "on".into(),
+                None,
+                None,
+            )
+            .await;
+        assert_tool_ok(result);
+        assert_command_sent(&fixture.client, &ceiling, "on");
+    }
+
+    #[tokio::test]
+    async fn test_admin_tools_follow_the_callers_role() {
+        let store = memory_store().await;
+        for (id, role) in [
+            ("lmcp_admin_001_secret", ApiKeyRole::Admin),
+            ("lmcp_operator_001_secret", ApiKeyRole::Operator),
+        ] {
+            store.add_key(key(id, role, None)).await.unwrap();
+        }
+        let fixture = TestServer::new(sample_house()).await;
+
+        let admin = Caller::resolve(&store, "lmcp_admin_001_secret", None)
+            .await
+            .unwrap();
+        assert!(admin.is_admin());
+        let view = fixture.for_caller(&admin).await.unwrap();
+        assert_tool_ok(view.get_connected_clients().await);
+
+        let operator = Caller::resolve(&store, "lmcp_operator_001_secret", None)
+            .await
+            .unwrap();
+        assert_eq!(operator.capabilities, ["control", "monitor", "read"]);
+        let view = fixture.for_caller(&operator).await.unwrap();
+        assert!(view.get_connected_clients().await.is_err());
+        let result = view
+            .create_guest_access(
+                "Visitor".into(),
+                vec!["Kitchen".into()],
+                None,
+                None,
+                None,
+                None,
+            )
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! Time-limited guest API keys
+//!
+//! Admins mint guest keys for visitors, e.g. "guest can control the Guest
+//! Room lights for 48 h". A guest key is an ordinary key store entry with:
+//!
+//! - an expiry, after which validation fails and cleanup deletes it
+//! - the operator role (control) or the monitor role (read only)
+//! - the control UUIDs it may see, stored in its metadata; every request
+//!   made with the key is scoped to them the same way as tenant keys (see
+//!   [`crate::security::caller`])
+//!
+//! Devices are resolved when the key is minted, so controls added to the
+//! room later are not included.
+
+use crate::client::LoxoneStructure;
+use crate::error::{LoxoneError, Result};
+use crate::security::key_store::{ApiKey, ApiKeyRole, KeyStore};
+use crate::security::tenants::{Tenant, TenantScope};
+use chrono::Utc;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Metadata marking a key as a guest key
+const GUEST_FLAG: &str = "guest";
+
+/// Metadata with the comma-separated control UUIDs of a guest key
+const GUEST_DEVICES: &str = "guest_devices";
+
+/// Metadata with the human-readable scope of a guest key
+const GUEST_SCOPE: &str = "guest_scope";
+
+/// Longest validity of a guest key
+pub const MAX_VALIDITY: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// Interval between expiry cleanups
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Device categories a guest key can be limited to, with control type fragments
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "lights",
+        &["light", "dimmer", "switch", "colorpicker", "moodswitch"],
+    ),
+    ("blinds", &["jalousie", "blind"]),
+    ("climate", &["iroomcontroller", "climate"]),
+    ("audio", &["audio", "music"]),
+];
+
+/// What a guest key grants
+#[derive(Debug, Clone)]
+pub struct GuestGrant {
+    /// Name of the key, e.g. "Guest Room lights"
+    pub name: String,
+    /// Room names or UUIDs the guest may use
+    pub rooms: Vec<String>,
+    /// Restrict to one category (`lights`, `blinds`, `climate`, `audio`)
+    pub category: Option<String>,
+    /// Allow device control; otherwise read only
+    pub allow_control: bool,
+    pub valid_for: Duration,
+}
+
+impl GuestGrant {
+    /// Human-readable scope, e.g. "control lights in Guest Room"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {} in {}",
+            if self.allow_control {
+                "control"
+            } else {
+                "view"
+            },
+            self.category.as_deref().unwrap_or("all devices"),
+            self.rooms.join(", ")
+        )
+    }
+}
+
+/// Control UUIDs covered by a grant
+pub fn resolve_devices(grant: &GuestGrant, structure: &LoxoneStructure) -> Result<Vec<String>> {
+    if grant.rooms.is_empty() {
+        return Err(LoxoneError::invalid_input(
+            "A guest key needs at least one room",
+        ));
+    }
+    let patterns = match grant.category.as_deref() {
+        None => None,
+        Some(category) => Some(
+            CATEGORIES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(category))
+                .map(|(_, patterns)| *patterns)
+                .ok_or_else(|| {
+                    LoxoneError::invalid_input(format!(
+                        "Unknown category '{category}'; use one of {}",
+                        CATEGORIES
+                            .iter()
+                            .map(|(name, _)| *name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                })?,
+        ),
+    };
+
+    let mut room_uuids = Vec::new();
+    for wanted in &grant.rooms {
+        let lower = wanted.to_lowercase();
+        let uuid = structure
+            .rooms
+            .iter()
+            .find(|(uuid, room)| {
+                uuid.to_lowercase() == lower
+                    || room
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .is_some_and(|name| name.to_lowercase() == lower)
+            })
+            .map(|(uuid, _)| uuid.clone())
+            .ok_or_else(|| LoxoneError::not_found(format!("Room '{wanted}' not found")))?;
+        room_uuids.push(uuid);
+    }
+
+    let mut devices: Vec<String> = structure
+        .controls
+        .iter()
+        .filter(|(_, control)| {
+            control
+                .get("room")
+                .and_then(Value::as_str)
+                .is_some_and(|room| room_uuids.iter().any(|r| r == room))
+        })
+        .filter(|(_, control)| {
+            let control_type = control
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_lowercase();
+            patterns.is_none_or(|patterns| patterns.iter().any(|p| control_type.contains(p)))
+        })
+        .map(|(uuid, _)| uuid.clone())
+        .collect();
+    if devices.is_empty() {
+        return Err(LoxoneError::not_found(format!(
+            "No devices match '{}'",
+            grant.describe()
+        )));
+    }
+    devices.sort();
+    Ok(devices)
+}
+
+/// Mint and store a guest key for `grant`
+///
+/// Expired guest keys are removed first.
+pub async fn mint_guest_key(
+    store: &KeyStore,
+    grant: &GuestGrant,
+    structure: &LoxoneStructure,
+    created_by: &str,
+) -> Result<ApiKey> {
+    if grant.valid_for.is_zero() || grant.valid_for > MAX_VALIDITY {
+        return Err(LoxoneError::invalid_input(
+            "Guest keys must be valid for between 1 second and 30 days",
+        ));
+    }
+    let devices = resolve_devices(grant, structure)?;
+    remove_expired(store).await?;
+
+    let seq = store
+        .list_keys()
+        .await
+        .iter()
+        .filter_map(|key| {
+            key.id
+                .strip_prefix("lmcp_guest_")?
+                .split('_')
+                .next()?
+                .parse()
+                .ok()
+        })
+        .max()
+        .unwrap_or(0u32)
+        + 1;
+    let secret = uuid::Uuid::new_v4().simple().to_string();
+    let now = Utc::now();
+    let key = ApiKey {
+        id: format!("lmcp_guest_{seq:03}_{secret}"),
+        name: grant.name.clone(),
+        role: if grant.allow_control {
+            ApiKeyRole::Operator
+        } else {
+            ApiKeyRole::Monitor
+        },
+        created_by: created_by.to_string(),
+        created_at: now,
+        expires_at: Some(
+            now + chrono::Duration::from_std(grant.valid_for)
+                .map_err(|e| LoxoneError::invalid_input(format!("Invalid validity: {e}")))?,
+        ),
+        ip_whitelist: Vec::new(),
+        active: true,
+        last_used: None,
+        usage_count: 0,
+        metadata: HashMap::from([
+            (GUEST_FLAG.to_string(), "true".to_string()),
+            (GUEST_DEVICES.to_string(), devices.join(",")),
+            (GUEST_SCOPE.to_string(), grant.describe()),
+        ]),
+        tenant: None,
+    };
+    store.add_key(key.clone()).await?;
+    info!(
+        "Minted guest key {} ({}) until {}",
+        crate::services::command_history::key_label(&key.id),
+        grant.describe(),
+        key.expires_at.unwrap_or(now)
+    );
+    Ok(key)
+}
+
+/// Whether a key was minted by [`mint_guest_key`]
+pub fn is_guest_key(key: &ApiKey) -> bool {
+    key.metadata.get(GUEST_FLAG).is_some_and(|v| v == "true")
+}
+
+/// Scope limiting requests made with a guest key to its devices
+pub fn guest_scope(key: &ApiKey) -> Option<TenantScope> {
+    if !is_guest_key(key) {
+        return None;
+    }
+    let devices = key
+        .metadata
+        .get(GUEST_DEVICES)
+        .map(|devices| devices.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+    Some(TenantScope::new(Tenant {
+        id: crate::services::command_history::key_label(&key.id),
+        name: Some(key.name.clone()),
+        rooms: Vec::new(),
+        devices,
+    }))
+}
+
+/// Delete guest keys past their expiry; returns the deleted key labels
+pub async fn remove_expired(store: &KeyStore) -> Result<Vec<String>> {
+    let now = Utc::now();
+    let mut removed = Vec::new();
+    for key in store.list_keys().await {
+        if is_guest_key(&key) && key.expires_at.is_some_and(|expires| expires <= now) {
+            store.remove_key(&key.id).await?;
+            removed.push(crate::services::command_history::key_label(&key.id));
+        }
+    }
+    if !removed.is_empty() {
+        info!("Removed {} expired guest key(s)", removed.len());
+    }
+    Ok(removed)
+}
+
+/// Remove expired guest keys every [`CLEANUP_INTERVAL`]
+///
+/// Must be called from within a Tokio runtime.
+pub fn spawn_expiry_cleanup(store: Arc<KeyStore>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = remove_expired(&store).await {
+                warn!("Guest key cleanup failed: {e}");
+            }
+        }
+    })
+}
+
+/// Ready-to-use client configuration for a guest key
+pub fn connection_snippet(key: &ApiKey, server_url: &str) -> Value {
+    let base = server_url.trim_end_matches('/');
+    json!({
+        "claude_desktop_config": {
+            "mcpServers": {
+                "loxone-guest": {
+                    "command": "npx",
+                    "args": [
+                        "mcp-remote",
+                        format!("{base}/sse"),
+                        "--header",
+                        format!("Authorization: Bearer {}", key.id),
+                    ],
+                }
+            }
+        },
+        "curl": format!(
+            "curl -X POST {base}/message -H 'Authorization: Bearer {}' \
+             -H 'Content-Type: application/json' \
+             -d '{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/list\"}}'",
+            key.id
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::key_store::{KeyStoreBackend, KeyStoreConfig};
+
+    fn structure() -> LoxoneStructure {
+        serde_json::from_value(json!({
+            "lastModified": "",
+            "rooms": {"r-guest": {"name": "Guest Room"}, "r-living": {"name": "Living"}},
+            "controls": {
+                "ceiling": {"name": "Ceiling", "type": "LightControllerV2", "room": "r-guest"},
+                "blind": {"name": "Blind", "type": "Jalousie", "room": "r-guest"},
+                "living": {"name": "Living Light", "type": "Dimmer", "room": "r-living"},
+            },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_guest_key_scope_and_expiry() {
+        let store = KeyStore::new(KeyStoreConfig {
+            backend: KeyStoreBackend::Memory,
+            file_path: None,
+            auto_save: false,
+            encrypt_at_rest: false,
+        })
+        .await
+        .unwrap();
+        let grant = GuestGrant {
+            name: "Guest Room lights".to_string(),
+            rooms: vec!["guest room".to_string()],
+            category: Some("lights".to_string()),
+            allow_control: true,
+            valid_for: Duration::from_secs(48 * 3600),
+        };
+
+        let key = mint_guest_key(&store, &grant, &structure(), "admin")
+            .await
+            .unwrap();
+        assert!(key.id.starts_with("lmcp_guest_001_"));
+        assert_eq!(key.role, ApiKeyRole::Operator);
+        let scope = guest_scope(&key).unwrap();
+        assert_eq!(scope.tenant().devices, ["ceiling"]);
+        assert!(
+            connection_snippet(&key, "https://home.example/")
+                .to_string()
+                .contains("https://home.example/sse")
+        );
+
+        let mut expired = key.clone();
+        expired.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        store.update_key(expired).await.unwrap();
+        assert_eq!(remove_expired(&store).await.unwrap(), ["lmcp_guest_001"]);
+        assert!(store.list_keys().await.is_empty());
+    }
+}
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
@@ -66,6 +66,10 @@ pub struct ApiKey {
     /// Optional metadata
     #[serde(default)]
     pub metadata: HashMap<String, String>,
+
+    /// Tenant the key is scoped to (see [`crate::security::tenants`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
 }
 
 /// Key store configuration
@@ -125,6 +129,9 @@ pub struct KeyStore {
 
     /// File path for persistence
     file_path: Option<PathBuf>,
+
+    /// Stamp of the key file when it was last read or written
+    file_stamp: Mutex<Option<FileStamp>>,
 }
 
 impl KeyStore {
@@ -136,6 +143,7 @@ impl KeyStore {
             keys,
             config: config.clone(),
             file_path: config.file_path.clone(),
+            file_stamp: Mutex::new(None),
         };
 
         // Load existing keys
@@ -154,8 +162,36 @@ impl KeyStore {
         }
     }
 
-    /// Load keys from file
-    async fn load_from_file(&mut self) -> Result<()> {
+    /// Reload the key file if it changed since it was last read or written
+    ///
+    /// Other processes - the key CLI, a second server - share the file, so
+    /// keys they add or revoke take effect without a restart, and saving
+    /// does not overwrite their changes. A file that fails to load keeps the
+    /// current keys.
+    async fn refresh(&self) {
+        if !matches!(self.config.backend, KeyStoreBackend::File) {
+            return;
+        }
+        let Some(path) = &self.file_path else {
+            return;
+        };
+        let stamp = FileStamp::of(path).await;
+        if stamp == *self.file_stamp.lock().unwrap_or_else(|e| e.into_inner()) {
+            return;
+        }
+        if let Err(e) = self.load_from_file().await {
+            warn!("Failed to reload key store from {}: {e}", path.display());
+        }
+    }
+
+    /// Remember the stamp of the key file as read or written
+    async fn mark_file_read(&self, path: &std::path::Path) {
+        let stamp = FileStamp::of(path).await;
+        *self.file_stamp.lock().unwrap_or_else(|e| e.into_inner()) = stamp;
+    }
+
+    /// Load keys from file, replacing the keys in memory
+    async fn load_from_file(&self) -> Result<()> {
         let path = self
             .file_path
             .as_ref()
@@ -163,10 +199,13 @@ impl KeyStore {
 
         if !path.exists() {
             info!("Key store file not found, starting with empty store");
+            self.keys.write().await.clear();
+            self.mark_file_read(path).await;
             return Ok(());
         }
 
         let content = tokio::fs::read_to_string(&path).await?;
+        self.mark_file_read(path).await;
 
         let keys: Vec<ApiKey> = if path.extension().and_then(|s| s.to_str()) == Some("toml") {
             // Try TOML first, fall back to JSON if it fails
@@ -182,9 +221,7 @@ impl KeyStore {
         };
 
         let mut store = self.keys.write().await;
-        for key in keys {
-            store.insert(key.id.clone(), key);
-        }
+        *store = keys.into_iter().map(|key| (key.id.clone(), key)).collect();
 
         info!("Loaded {} API keys from {}", store.len(), path.display());
         Ok(())
@@ -261,6 +298,7 @@ impl KeyStore {
         let temp_path = path.with_extension("tmp");
         tokio::fs::write(&temp_path, content).await?;
         tokio::fs::rename(&temp_path, path).await?;
+        self.mark_file_read(path).await;
 
         debug!("Saved {} keys to {}", keys_vec.len(), path.display());
         Ok(())
@@ -274,6 +312,7 @@ impl KeyStore {
 
     /// Add a new API key
     pub async fn add_key(&self, key: ApiKey) -> Result<()> {
+        self.refresh().await;
         let mut keys = self.keys.write().await;
 
         // Check if key ID already exists
@@ -305,6 +344,7 @@ impl KeyStore {
 
     /// Remove an API key
     pub async fn remove_key(&self, key_id: &str) -> Result<()> {
+        self.refresh().await;
         let mut keys = self.keys.write().await;
 
         if keys.remove(key_id).is_none() {
@@ -320,6 +360,7 @@ impl KeyStore {
 
     /// Update an existing key
     pub async fn update_key(&self, key: ApiKey) -> Result<()> {
+        self.refresh().await;
         let mut keys = self.keys.write().await;
 
         if !keys.contains_key(&key.id) {
@@ -336,16 +377,19 @@ impl KeyStore {
 
     /// Get a key by ID
     pub async fn get_key(&self, key_id: &str) -> Option<ApiKey> {
+        self.refresh().await;
         self.keys.read().await.get(key_id).cloned()
     }
 
     /// List all keys
     pub async fn list_keys(&self) -> Vec<ApiKey> {
+        self.refresh().await;
         self.keys.read().await.values().cloned().collect()
     }
 
     /// Validate a key and check permissions
     pub async fn validate_key(&self, key_id: &str, client_ip: Option<IpAddr>) -> Result<ApiKey> {
+        self.refresh().await;
         let keys = self.keys.read().await;
 
         let key = keys
@@ -406,6 +450,7 @@ impl KeyStore {
 
     /// Record key usage
     pub async fn record_usage(&self, key_id: &str) -> Result<()> {
+        self.refresh().await;
         let mut keys = self.keys.write().await;
 
         if let Some(key) = keys.get_mut(key_id) {
@@ -422,6 +467,15 @@ impl KeyStore {
         Ok(())
     }
 
+    /// Tenant a key is scoped to, if any
+    pub async fn tenant_for_key(&self, key_id: &str) -> Option<String> {
+        self.keys
+            .read()
+            .await
+            .get(key_id)
+            .and_then(|key| key.tenant.clone())
+    }
+
     /// Check if a key has permission for an operation
     pub async fn check_permission(&self, key_id: &str, operation: &str) -> Result<bool> {
         let keys = self.keys.read().await;
@@ -430,6 +484,11 @@ impl KeyStore {
             .get(key_id)
             .ok_or_else(|| LoxoneError::authentication("Invalid API key"))?;
 
+        // Tenant keys only ever act within their units, whatever their role
+        if key.tenant.is_some() && matches!(operation, "admin" | "keys" | "system") {
+            return Ok(false);
+        }
+
         Ok(match &key.role {
             ApiKeyRole::Admin => true, // Admin can do everything
             ApiKeyRole::Operator => {
@@ -450,6 +509,32 @@ impl KeyStore {
     }
 }
 
+/// Identity of a key file version
+///
+/// Saves replace the file atomically, so on Unix the inode tells versions
+/// apart even when two writes share a modification time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<std::time::SystemTime>,
+    len: u64,
+    inode: u64,
+}
+
+impl FileStamp {
+    async fn of(path: &std::path::Path) -> Option<Self> {
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            inode,
+        })
+    }
+}
+
 /// Default key store path
 pub fn default_key_store_path() -> PathBuf {
     // Use current directory as fallback instead of dirs crate
@@ -504,3 +589,60 @@ fn check_ipv6_in_cidr(ip: std::net::Ipv6Addr, network: std::net::Ipv6Addr, prefi
 
     (ip_bits & mask) == (network_bits & mask)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: &str) -> ApiKey {
+        ApiKey {
+            id: id.to_string(),
+            name: id.to_string(),
+            role: ApiKeyRole::Operator,
+            created_by: "admin".to_string(),
+            created_at: Utc::now(),
+            expires_at: None,
+            ip_whitelist: Vec::new(),
+            active: true,
+            last_used: None,
+            usage_count: 0,
+            metadata: HashMap::new(),
+            tenant: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keys_changed_by_another_process_take_effect() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = KeyStoreConfig {
+            file_path: Some(dir.path().join("keys.json")),
+            ..KeyStoreConfig::default()
+        };
+        let server = KeyStore::new(config.clone()).await.unwrap();
+        server.add_key(key("lmcp_operator_001_a")).await.unwrap();
+
+        // The key CLI opens the file on its own
+        let cli = KeyStore::new(config).await.unwrap();
+        cli.add_key(key("lmcp_operator_002_b")).await.unwrap();
+        cli.remove_key("lmcp_operator_001_a").await.unwrap();
+
+        assert!(
+            server
+                .validate_key("lmcp_operator_002_b", None)
+                .await
+                .is_ok()
+        );
+        assert!(
+            server
+                .validate_key("lmcp_operator_001_a", None)
+                .await
+                .is_err()
+        );
+
+        // Saving does not bring the revoked key back
+        server.add_key(key("lmcp_operator_003_c")).await.unwrap();
+        let mut ids: Vec<_> = cli.list_keys().await.into_iter().map(|k| k.id).collect();
+        ids.sort();
+        assert_eq!(ids, ["lmcp_operator_002_b", "lmcp_operator_003_c"]);
+    }
+}
@@ -1,14 +1,17 @@
 //! Security hardening and production security measures
 
+pub mod caller;
 pub mod cors;
 pub mod encryption;
 pub mod enhanced_cors;
 pub mod enhanced_validation;
+pub mod guest_access;
 pub mod headers;
 pub mod input_sanitization;
 pub mod key_store;
 pub mod policy;
 pub mod rate_limiting;
+pub mod tenants;
 
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
@@ -0,0 +1,297 @@
+//! Tenant scoping for multi-unit installations
+//!
+//! Property managers often run one Miniserver (and one MCP server) for
+//! several flats. Tenants are defined in a TOML file and tag the rooms and
+//! devices belonging to each unit:
+//!
+//! ```toml
+//! [[tenant]]
+//! id = "flat-1"
+//! name = "Flat 1 (ground floor)"
+//! rooms = ["Flat 1 Kitchen", "Flat 1 Living"]
+//! devices = ["0f1e2d3c-..."]
+//! ```
+//!
+//! API keys carry the tenant they belong to; a [`TenantScope`] then filters
+//! the structure file and authorizes commands so a tenant's MCP client only
+//! sees and controls its own units.
+
+use crate::client::LoxoneStructure;
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// A tenant and the rooms/devices tagged as theirs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tenant {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Room UUIDs or names (case-insensitive)
+    #[serde(default)]
+    pub rooms: Vec<String>,
+    /// Additional control UUIDs outside the tenant's rooms
+    #[serde(default)]
+    pub devices: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TenantFile {
+    #[serde(default)]
+    tenant: Vec<Tenant>,
+}
+
+/// All configured tenants by ID
+#[derive(Debug, Clone, Default)]
+pub struct TenantRegistry {
+    tenants: HashMap<String, Tenant>,
+}
+
+impl TenantRegistry {
+    /// Parse tenants from TOML
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let file: TenantFile = toml::from_str(content)
+            .map_err(|e| LoxoneError::config(format!("Invalid tenants file: {e}")))?;
+        let mut tenants = HashMap::new();
+        for tenant in file.tenant {
+            if tenant.rooms.is_empty() && tenant.devices.is_empty() {
+                return Err(LoxoneError::config(format!(
+                    "Tenant '{}' has no rooms or devices",
+                    tenant.id
+                )));
+            }
+            if let Some(previous) = tenants.insert(tenant.id.clone(), tenant) {
+                return Err(LoxoneError::config(format!(
+                    "Tenant '{}' is defined twice",
+                    previous.id
+                )));
+            }
+        }
+        Ok(Self { tenants })
+    }
+
+    /// Load tenants from a TOML file
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            LoxoneError::config(format!(
+                "Failed to read tenants file {}: {e}",
+                path.display()
+            ))
+        })?;
+        Self::from_toml(&content)
+    }
+
+    /// Look up a tenant by ID
+    pub fn get(&self, id: &str) -> Option<&Tenant> {
+        self.tenants.get(id)
+    }
+
+    /// Scope for a tenant ID
+    pub fn scope(&self, id: &str) -> Result<TenantScope> {
+        self.get(id)
+            .cloned()
+            .map(TenantScope::new)
+            .ok_or_else(|| LoxoneError::not_found(format!("Tenant '{id}' not found")))
+    }
+
+    /// Number of configured tenants
+    pub fn len(&self) -> usize {
+        self.tenants.len()
+    }
+
+    /// Whether no tenants are configured
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+}
+
+/// Visibility and control limits of one tenant
+#[derive(Debug, Clone)]
+pub struct TenantScope {
+    tenant: Tenant,
+    rooms: HashSet<String>,
+}
+
+impl TenantScope {
+    /// Build the scope of a tenant
+    pub fn new(tenant: Tenant) -> Self {
+        let rooms = tenant.rooms.iter().map(|r| r.to_lowercase()).collect();
+        Self { tenant, rooms }
+    }
+
+    /// The tenant this scope belongs to
+    pub fn tenant(&self) -> &Tenant {
+        &self.tenant
+    }
+
+    /// Whether a room (by UUID and structure entry) belongs to the tenant
+    fn allows_room(&self, uuid: &str, room: Option<&Value>) -> bool {
+        self.rooms.contains(&uuid.to_lowercase())
+            || room
+                .and_then(|r| r.get("name"))
+                .and_then(Value::as_str)
+                .is_some_and(|name| self.rooms.contains(&name.to_lowercase()))
+    }
+
+    /// Reduce the structure to the tenant's rooms and controls
+    ///
+    /// Rooms of explicitly tagged devices stay visible so those devices keep
+    /// their room reference; other controls in such rooms are still hidden.
+    pub fn filter_structure(&self, structure: &LoxoneStructure) -> LoxoneStructure {
+        let room_allowed: HashSet<&String> = structure
+            .rooms
+            .iter()
+            .filter(|(uuid, room)| self.allows_room(uuid, Some(room)))
+            .map(|(uuid, _)| uuid)
+            .collect();
+
+        let controls: HashMap<String, Value> = structure
+            .controls
+            .iter()
+            .filter(|(uuid, control)| {
+                self.tenant.devices.contains(uuid)
+                    || control
+                        .get("room")
+                        .and_then(Value::as_str)
+                        .is_some_and(|room| room_allowed.iter().any(|r| r.as_str() == room))
+            })
+            .map(|(uuid, control)| (uuid.clone(), control.clone()))
+            .collect();
+
+        let visible_rooms: HashSet<&str> = controls
+            .values()
+            .filter_map(|c| c.get("room").and_then(Value::as_str))
+            .chain(room_allowed.iter().map(|r| r.as_str()))
+            .collect();
+        let rooms = structure
+            .rooms
+            .iter()
+            .filter(|(uuid, _)| visible_rooms.contains(uuid.as_str()))
+            .map(|(uuid, room)| (uuid.clone(), room.clone()))
+            .collect();
+
+        LoxoneStructure {
+            last_modified: structure.last_modified.clone(),
+            controls,
+            rooms,
+            cats: structure.cats.clone(),
+            // Global states describe the whole installation
+            global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
+        }
+    }
+
+    /// Control, sub-control and state UUIDs the tenant may address
+    pub fn allowed_uuids(&self, structure: &LoxoneStructure) -> HashSet<String> {
+        fn collect(uuid: &str, control: &Value, out: &mut HashSet<String>) {
+            out.insert(uuid.to_string());
+            if let Some(states) = control.get("states").and_then(Value::as_object) {
+                for state in states.values() {
+                    match state {
+                        Value::String(s) => {
+                            out.insert(s.clone());
+                        }
+                        Value::Array(list) => {
+                            out.extend(list.iter().filter_map(Value::as_str).map(str::to_string));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            if let Some(subs) = control.get("subControls").and_then(Value::as_object) {
+                for (sub_uuid, sub) in subs {
+                    collect(sub_uuid, sub, out);
+                }
+            }
+        }
+
+        let mut allowed = HashSet::new();
+        for (uuid, control) in &self.filter_structure(structure).controls {
+            collect(uuid, control, &mut allowed);
+        }
+        allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn structure() -> LoxoneStructure {
+        LoxoneStructure {
+            last_modified: "2025-01-01".to_string(),
+            controls: HashMap::from([
+                (
+                    "light-1".to_string(),
+                    json!({"name": "Light", "room": "room-1", "states": {"active": "light-1-s"}}),
+                ),
+                (
+                    "light-2".to_string(),
+                    json!({"name": "Light", "room": "room-2", "states": {"active": "light-2-s"}}),
+                ),
+                (
+                    "meter-2".to_string(),
+                    json!({
+                        "name": "Meter Flat 1",
+                        "room": "room-2",
+                        "subControls": {"meter-2/sub": {"states": {"total": "meter-2-t"}}}
+                    }),
+                ),
+            ]),
+            rooms: HashMap::from([
+                ("room-1".to_string(), json!({"name": "Flat 1 Kitchen"})),
+                ("room-2".to_string(), json!({"name": "Basement"})),
+            ]),
+            cats: HashMap::new(),
+            global_states: HashMap::from([("sunrise".to_string(), json!("g-1"))]),
+            operating_modes: HashMap::new(),
+        }
+    }
+
+    const TENANTS: &str = r#"
+        [[tenant]]
+        id = "flat-1"
+        rooms = ["flat 1 kitchen"]
+        devices = ["meter-2"]
+    "#;
+
+    #[test]
+    fn test_filter_structure() {
+        let registry = TenantRegistry::from_toml(TENANTS).unwrap();
+        let scope = registry.scope("flat-1").unwrap();
+        let filtered = scope.filter_structure(&structure());
+
+        let mut controls: Vec<_> = filtered.controls.keys().cloned().collect();
+        controls.sort();
+        assert_eq!(controls, ["light-1", "meter-2"]);
+        assert_eq!(filtered.rooms.len(), 2);
+        assert!(filtered.global_states.is_empty());
+
+        let allowed = scope.allowed_uuids(&structure());
+        assert!(allowed.contains("light-1-s"));
+        assert!(allowed.contains("meter-2/sub"));
+        assert!(allowed.contains("meter-2-t"));
+        assert!(!allowed.contains("light-2"));
+        assert!(!allowed.contains("light-2-s"));
+    }
+
+    #[test]
+    fn test_registry_validation() {
+        assert!(registry_error("[[tenant]]\nid = \"empty\""));
+        assert!(registry_error(&format!("{TENANTS}\n{TENANTS}")));
+        assert!(TenantRegistry::from_toml("").unwrap().is_empty());
+        assert!(
+            TenantRegistry::from_toml(TENANTS)
+                .unwrap()
+                .scope("flat-2")
+                .is_err()
+        );
+    }
+
+    fn registry_error(content: &str) -> bool {
+        TenantRegistry::from_toml(content).is_err()
+    }
+}
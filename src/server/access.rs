@@ -0,0 +1,216 @@
+//! Gate and garage door handling
+//!
+//! Loxone exposes gates and garage doors as `Gate` controls (and `CentralGate`
+//! for grouped doors). Their `states` map names to state UUIDs:
+//!
+//! - `position`: 0.0 (closed) to 1.0 (fully open)
+//! - `active`: -1 closing, 0 not moving, 1 opening
+//! - `preventOpen` / `preventClose`: set while the respective input is locked
+//!   out; `preventClose` is driven by the safety light barrier, so it doubles
+//!   as obstruction detection
+//!
+//! Opening a gate remotely is a physical security concern, so `control_gate`
+//! asks the consent manager before sending an action that opens it.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::action_aliases::ActionAliases;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as gates or garage doors
+pub const GATE_CONTROL_TYPES: &[&str] = &["Gate", "CentralGate"];
+
+/// State names read for a gate status
+pub const GATE_STATE_NAMES: &[&str] = &["position", "active", "preventOpen", "preventClose"];
+
+/// Position below which a gate counts as closed (and above 1 - this as open)
+const POSITION_TOLERANCE: f64 = 0.01;
+
+/// Action requested for a gate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateAction {
+    Open,
+    Close,
+    Stop,
+    /// Open to the partial position configured in the Miniserver
+    PartialOpen,
+}
+
+impl GateAction {
+    /// Parse an action name in any language known to [`ActionAliases`]
+    pub fn parse(action: &str) -> Result<Self> {
+        match ActionAliases::global()
+            .normalize_action(action, &["open", "close", "stop", "partial_open"])
+        {
+            Some("open") => Ok(Self::Open),
+            Some("close") => Ok(Self::Close),
+            Some("stop") => Ok(Self::Stop),
+            Some(_) => Ok(Self::PartialOpen),
+            None => Err(LoxoneError::invalid_input(format!(
+                "Invalid gate action '{}'. Use: open, close, stop, partial_open",
+                action.trim()
+            ))),
+        }
+    }
+
+    /// Miniserver command for the action
+    pub fn command(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Close => "close",
+            Self::Stop => "stop",
+            Self::PartialOpen => "partiallyOpen",
+        }
+    }
+
+    /// Name used in tool output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Close => "close",
+            Self::Stop => "stop",
+            Self::PartialOpen => "partial_open",
+        }
+    }
+
+    /// Whether the action opens the gate and therefore needs consent
+    pub fn requires_consent(self) -> bool {
+        matches!(self, Self::Open | Self::PartialOpen)
+    }
+}
+
+/// Current movement of a gate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GateMovement {
+    Opening,
+    Closing,
+    Stopped,
+}
+
+/// Snapshot of a gate's state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GateStatus {
+    /// Opening in percent (0 = closed, 100 = fully open)
+    pub position_percent: Option<f64>,
+    /// `open`, `closed`, `partially_open` or `unknown`
+    pub state: &'static str,
+    pub movement: GateMovement,
+    /// Safety light barrier or obstacle is blocking the gate from closing
+    pub obstructed: bool,
+    pub open_locked: bool,
+    pub close_locked: bool,
+}
+
+impl GateStatus {
+    /// Build the status from state values keyed by state name
+    pub fn from_state_values(values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+        let flag = |name: &str| number(name).is_some_and(|v| v != 0.0);
+
+        let position = number("position").map(|p| p.clamp(0.0, 1.0));
+        let state = match position {
+            Some(p) if p <= POSITION_TOLERANCE => "closed",
+            Some(p) if p >= 1.0 - POSITION_TOLERANCE => "open",
+            Some(_) => "partially_open",
+            None => "unknown",
+        };
+        let movement = match number("active") {
+            Some(a) if a > 0.0 => GateMovement::Opening,
+            Some(a) if a < 0.0 => GateMovement::Closing,
+            _ => GateMovement::Stopped,
+        };
+        let close_locked = flag("preventClose");
+
+        Self {
+            position_percent: position.map(|p| (p * 1000.0).round() / 10.0),
+            state,
+            movement,
+            // A close lockout on a gate that is not fully closed means something
+            // is in the way of the door
+            obstructed: close_locked && state != "closed",
+            open_locked: flag("preventOpen"),
+            close_locked,
+        }
+    }
+}
+
+/// State UUIDs of a gate control, keyed by state name
+pub fn gate_state_uuids(control: &Value) -> HashMap<String, String> {
+    GATE_STATE_NAMES
+        .iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(*name))
+                .and_then(Value::as_str)
+                .map(|uuid| ((*name).to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_gate_actions() {
+        assert_eq!(GateAction::parse("Open").unwrap(), GateAction::Open);
+        assert_eq!(GateAction::parse("zu").unwrap(), GateAction::Close);
+        assert_eq!(
+            GateAction::parse("partial_open").unwrap().command(),
+            "partiallyOpen"
+        );
+        assert_eq!(GateAction::parse("chiudi").unwrap(), GateAction::Close);
+        assert!(GateAction::parse("unlock").is_err());
+
+        assert!(GateAction::Open.requires_consent());
+        assert!(GateAction::PartialOpen.requires_consent());
+        assert!(!GateAction::Close.requires_consent());
+        assert!(!GateAction::Stop.requires_consent());
+    }
+
+    #[test]
+    fn test_gate_status_and_obstruction() {
+        let closed = GateStatus::from_state_values(&values(&[
+            ("position", json!(0.0)),
+            ("active", json!(0)),
+            ("preventClose", json!(1)),
+        ]));
+        assert_eq!(closed.state, "closed");
+        assert!(!closed.obstructed);
+
+        let blocked = GateStatus::from_state_values(&values(&[
+            ("position", json!("0.42")),
+            ("active", json!(-1)),
+            ("preventClose", json!(1)),
+        ]));
+        assert_eq!(blocked.state, "partially_open");
+        assert_eq!(blocked.position_percent, Some(42.0));
+        assert_eq!(blocked.movement, GateMovement::Closing);
+        assert!(blocked.obstructed);
+
+        let unknown = GateStatus::from_state_values(&HashMap::new());
+        assert_eq!(unknown.state, "unknown");
+        assert_eq!(unknown.movement, GateMovement::Stopped);
+    }
+}
@@ -0,0 +1,528 @@
+//! Action aliases
+//!
+//! Device tools accept actions in the user's language: "ein", "allumer",
+//! "enciende" and "accendi" all switch a light on. Each locale maps its words
+//! to canonical actions ([`CANONICAL_ACTIONS`]). One word may stand for
+//! several actions, e.g. German "ab" is `off` for lights and `down` for
+//! blinds, so callers pass the actions they accept and the first match wins.
+//!
+//! Built-in tables cover English, German, French, Spanish and Italian; more
+//! locales can be added with [`ActionAliases::register`]. The aliases of the
+//! request's locale are tried first, then those of all other locales in
+//! registration order. The request locale is set per tool call by the HTTP
+//! transport (`_meta.locale` of the call or the `Accept-Language` header,
+//! see [`ActionAliases::scope`]) and defaults to `LOXONE_LOCALE`.
+//!
+//! Words are compared case-insensitively and without accents, so "eteindre"
+//! matches "éteindre" and "giu" matches "giù".
+
+use crate::error::{LoxoneError, Result};
+use std::sync::{OnceLock, RwLock};
+
+/// Actions the aliases resolve to
+pub const CANONICAL_ACTIONS: &[&str] = &[
+    "on",
+    "off",
+    "dim",
+    "bright",
+    "up",
+    "down",
+    "stop",
+    "shade",
+    "open",
+    "close",
+    "partial_open",
+    "lock",
+    "unlock",
+    "arm_away",
+    "arm_home",
+    "disarm",
+];
+
+tokio::task_local! {
+    static REQUEST_LOCALE: String;
+}
+
+const ENGLISH: &[(&str, &str)] = &[
+    ("turn_on", "on"),
+    ("switch_on", "on"),
+    ("turn_off", "off"),
+    ("switch_off", "off"),
+    ("dim", "dim"),
+    ("brighten", "bright"),
+    ("raise", "up"),
+    ("open", "up"),
+    ("lower", "down"),
+    ("close", "down"),
+    ("halt", "stop"),
+    ("partial", "partial_open"),
+    ("partiallyopen", "partial_open"),
+    ("arm", "arm_away"),
+    ("away", "arm_away"),
+    ("arm_stay", "arm_home"),
+];
+
+const GERMAN: &[(&str, &str)] = &[
+    ("ein", "on"),
+    ("an", "on"),
+    ("einschalten", "on"),
+    ("aus", "off"),
+    ("ab", "off"),
+    ("ausschalten", "off"),
+    ("dimmen", "dim"),
+    ("hell", "bright"),
+    ("auf", "up"),
+    ("hoch", "up"),
+    ("hochfahren", "up"),
+    ("ab", "down"),
+    ("zu", "down"),
+    ("runter", "down"),
+    ("herunterfahren", "down"),
+    ("halt", "stop"),
+    ("stopp", "stop"),
+    ("schatten", "shade"),
+    ("beschatten", "shade"),
+    ("auf", "open"),
+    ("öffnen", "open"),
+    ("oeffnen", "open"),
+    ("zu", "close"),
+    ("schließen", "close"),
+    ("schliessen", "close"),
+    ("teilweise", "partial_open"),
+    ("teilöffnen", "partial_open"),
+    ("abschließen", "lock"),
+    ("abschliessen", "lock"),
+    ("verriegeln", "lock"),
+    ("zu", "lock"),
+    ("aufschließen", "unlock"),
+    ("aufschliessen", "unlock"),
+    ("entriegeln", "unlock"),
+    ("auf", "unlock"),
+    ("scharf", "arm_away"),
+    ("abwesend", "arm_away"),
+    ("zuhause", "arm_home"),
+    ("unscharf", "disarm"),
+    ("aus", "disarm"),
+];
+
+const FRENCH: &[(&str, &str)] = &[
+    ("allumer", "on"),
+    ("allume", "on"),
+    ("éteindre", "off"),
+    ("éteins", "off"),
+    ("éteint", "off"),
+    ("tamiser", "dim"),
+    ("éclaircir", "bright"),
+    ("monter", "up"),
+    ("monte", "up"),
+    ("lever", "up"),
+    ("ouvrir", "up"),
+    ("ouvre", "up"),
+    ("descendre", "down"),
+    ("descends", "down"),
+    ("baisser", "down"),
+    ("baisse", "down"),
+    ("fermer", "down"),
+    ("ferme", "down"),
+    ("arrêter", "stop"),
+    ("arrête", "stop"),
+    ("arrêt", "stop"),
+    ("ombrager", "shade"),
+    ("ombre", "shade"),
+    ("ouvrir", "open"),
+    ("ouvre", "open"),
+    ("fermer", "close"),
+    ("ferme", "close"),
+    ("entrouvrir", "partial_open"),
+    ("partiel", "partial_open"),
+    ("verrouiller", "lock"),
+    ("verrouille", "lock"),
+    ("déverrouiller", "unlock"),
+    ("déverrouille", "unlock"),
+    ("armer", "arm_away"),
+    ("absent", "arm_away"),
+    ("présent", "arm_home"),
+    ("désarmer", "disarm"),
+    ("désactiver", "disarm"),
+];
+
+const SPANISH: &[(&str, &str)] = &[
+    ("encender", "on"),
+    ("enciende", "on"),
+    ("prender", "on"),
+    ("prende", "on"),
+    ("apagar", "off"),
+    ("apaga", "off"),
+    ("atenuar", "dim"),
+    ("iluminar", "bright"),
+    ("subir", "up"),
+    ("sube", "up"),
+    ("abrir", "up"),
+    ("abre", "up"),
+    ("bajar", "down"),
+    ("baja", "down"),
+    ("cerrar", "down"),
+    ("cierra", "down"),
+    ("parar", "stop"),
+    ("para", "stop"),
+    ("detener", "stop"),
+    ("detén", "stop"),
+    ("sombrear", "shade"),
+    ("sombra", "shade"),
+    ("abrir", "open"),
+    ("abre", "open"),
+    ("cerrar", "close"),
+    ("cierra", "close"),
+    ("entreabrir", "partial_open"),
+    ("parcial", "partial_open"),
+    ("bloquear", "lock"),
+    ("bloquea", "lock"),
+    ("desbloquear", "unlock"),
+    ("desbloquea", "unlock"),
+    ("armar", "arm_away"),
+    ("ausente", "arm_away"),
+    ("en casa", "arm_home"),
+    ("desarmar", "disarm"),
+    ("desactivar", "disarm"),
+];
+
+const ITALIAN: &[(&str, &str)] = &[
+    ("accendere", "on"),
+    ("accendi", "on"),
+    ("spegnere", "off"),
+    ("spegni", "off"),
+    ("attenuare", "dim"),
+    ("schiarire", "bright"),
+    ("alzare", "up"),
+    ("alza", "up"),
+    ("su", "up"),
+    ("aprire", "up"),
+    ("apri", "up"),
+    ("abbassare", "down"),
+    ("abbassa", "down"),
+    ("giù", "down"),
+    ("chiudere", "down"),
+    ("chiudi", "down"),
+    ("fermare", "stop"),
+    ("ferma", "stop"),
+    ("ombreggiare", "shade"),
+    ("ombra", "shade"),
+    ("aprire", "open"),
+    ("apri", "open"),
+    ("chiudere", "close"),
+    ("chiudi", "close"),
+    ("socchiudere", "partial_open"),
+    ("parziale", "partial_open"),
+    ("bloccare", "lock"),
+    ("blocca", "lock"),
+    ("sbloccare", "unlock"),
+    ("sblocca", "unlock"),
+    ("inserire", "arm_away"),
+    ("inserisci", "arm_away"),
+    ("assente", "arm_away"),
+    ("in casa", "arm_home"),
+    ("disinserire", "disarm"),
+    ("disinserisci", "disarm"),
+    ("disattivare", "disarm"),
+];
+
+/// Action words of one locale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleAliases {
+    /// Language code, e.g. `fr`
+    pub locale: String,
+    /// Folded word and the canonical action it stands for
+    aliases: Vec<(String, &'static str)>,
+}
+
+impl LocaleAliases {
+    pub fn new(locale: &str) -> Self {
+        Self {
+            locale: language(locale),
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Add `word` as an alias of the canonical `action`
+    pub fn alias(mut self, word: &str, action: &str) -> Result<Self> {
+        let action = CANONICAL_ACTIONS
+            .iter()
+            .copied()
+            .find(|canonical| *canonical == action)
+            .ok_or_else(|| {
+                LoxoneError::invalid_input(format!(
+                    "Unknown action '{action}'. Use one of: {}",
+                    CANONICAL_ACTIONS.join(", ")
+                ))
+            })?;
+        let word = fold(word);
+        if word.is_empty() {
+            return Err(LoxoneError::invalid_input("The alias is empty"));
+        }
+        self.aliases.push((word, action));
+        Ok(self)
+    }
+
+    fn from_table(locale: &str, table: &[(&str, &str)]) -> Self {
+        table
+            .iter()
+            .try_fold(Self::new(locale), |aliases, (word, action)| {
+                aliases.alias(word, action)
+            })
+            .expect("built-in aliases use canonical actions")
+    }
+
+    /// Canonical actions `word` stands for, in table order
+    fn actions<'a>(&'a self, word: &'a str) -> impl Iterator<Item = &'static str> + 'a {
+        self.aliases
+            .iter()
+            .filter(move |(alias, _)| alias == word)
+            .map(|(_, action)| *action)
+    }
+}
+
+/// Registry of the action words of all locales
+pub struct ActionAliases {
+    locales: RwLock<Vec<LocaleAliases>>,
+}
+
+impl Default for ActionAliases {
+    fn default() -> Self {
+        Self {
+            locales: RwLock::new(vec![
+                LocaleAliases::from_table("en", ENGLISH),
+                LocaleAliases::from_table("de", GERMAN),
+                LocaleAliases::from_table("fr", FRENCH),
+                LocaleAliases::from_table("es", SPANISH),
+                LocaleAliases::from_table("it", ITALIAN),
+            ]),
+        }
+    }
+}
+
+impl ActionAliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aliases shared by the tools of this process
+    pub fn global() -> &'static ActionAliases {
+        static GLOBAL: OnceLock<ActionAliases> = OnceLock::new();
+        GLOBAL.get_or_init(ActionAliases::new)
+    }
+
+    /// Add the aliases of a locale; aliases of a locale already registered
+    /// are added in front of its existing ones
+    pub fn register(&self, aliases: LocaleAliases) {
+        let mut locales = self.locales.write().unwrap_or_else(|e| e.into_inner());
+        match locales.iter_mut().find(|l| l.locale == aliases.locale) {
+            Some(existing) => {
+                let mut merged = aliases.aliases;
+                merged.append(&mut existing.aliases);
+                existing.aliases = merged;
+            }
+            None => locales.push(aliases),
+        }
+    }
+
+    /// Registered language codes
+    pub fn locales(&self) -> Vec<String> {
+        self.locales
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|l| l.locale.clone())
+            .collect()
+    }
+
+    /// Canonical action of `action` among `accepted`, preferring the locale
+    /// of the current request
+    pub fn normalize_action(&self, action: &str, accepted: &[&str]) -> Option<&'static str> {
+        let locale = Self::current_locale().or_else(|| {
+            std::env::var("LOXONE_LOCALE")
+                .ok()
+                .filter(|l| !l.is_empty())
+        });
+        self.normalize_action_in(action, accepted, locale.as_deref())
+    }
+
+    /// Canonical action of `action` among `accepted`, trying the aliases of
+    /// `locale` first
+    pub fn normalize_action_in(
+        &self,
+        action: &str,
+        accepted: &[&str],
+        locale: Option<&str>,
+    ) -> Option<&'static str> {
+        let word = fold(action);
+        if let Some(canonical) = CANONICAL_ACTIONS
+            .iter()
+            .copied()
+            .find(|canonical| *canonical == word && accepted.contains(canonical))
+        {
+            return Some(canonical);
+        }
+
+        let locales = self.locales.read().unwrap_or_else(|e| e.into_inner());
+        let preferred = locale.map(language);
+        let ordered = locales
+            .iter()
+            .filter(|l| Some(&l.locale) == preferred.as_ref())
+            .chain(
+                locales
+                    .iter()
+                    .filter(|l| Some(&l.locale) != preferred.as_ref()),
+            );
+        for aliases in ordered {
+            if let Some(action) = aliases
+                .actions(&word)
+                .find(|action| accepted.contains(action))
+            {
+                return Some(action);
+            }
+        }
+        None
+    }
+
+    /// Run `future` with `locale` as the locale of the request
+    pub async fn scope<F: std::future::Future>(locale: String, future: F) -> F::Output {
+        REQUEST_LOCALE.scope(language(&locale), future).await
+    }
+
+    /// Locale of the current request, if set
+    pub fn current_locale() -> Option<String> {
+        REQUEST_LOCALE.try_with(Clone::clone).ok()
+    }
+}
+
+/// Preferred language of an `Accept-Language` header
+pub fn locale_from_accept_language(header: &str) -> Option<String> {
+    header
+        .split(',')
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let mut parts = entry.split(';');
+            let tag = language(parts.next()?);
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && tag != "*" && quality > 0.0).then_some((index, quality, tag))
+        })
+        .min_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)))
+        .map(|(_, _, tag)| tag)
+}
+
+/// Reduce `fr_CH.UTF-8` / `es-MX` to the language code
+fn language(locale: &str) -> String {
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// Lowercase `word` and strip its accents
+fn fold(word: &str) -> String {
+    word.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' => 'a',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ò' | 'ó' | 'ô' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWITCH: &[&str] = &["on", "off"];
+    const BLINDS: &[&str] = &["up", "down", "stop", "shade"];
+
+    #[test]
+    fn test_normalize_across_locales() {
+        let aliases = ActionAliases::new();
+        for word in ["on", "Ein", "allumer", "enciende", "ACCENDI"] {
+            assert_eq!(aliases.normalize_action_in(word, SWITCH, None), Some("on"));
+        }
+        assert_eq!(
+            aliases.normalize_action_in("eteindre", SWITCH, None),
+            Some("off")
+        );
+        assert_eq!(
+            aliases.normalize_action_in("giu", BLINDS, None),
+            Some("down")
+        );
+        assert_eq!(aliases.normalize_action_in("ab", SWITCH, None), Some("off"));
+        assert_eq!(
+            aliases.normalize_action_in("ab", BLINDS, None),
+            Some("down")
+        );
+        assert_eq!(
+            aliases.normalize_action_in("chiudi", &["open", "close"], None),
+            Some("close")
+        );
+        assert_eq!(
+            aliases.normalize_action_in("desbloquear", &["lock", "unlock"], None),
+            Some("unlock")
+        );
+        assert_eq!(
+            aliases.normalize_action_in("désarmer", &["arm_away", "arm_home", "disarm"], None),
+            Some("disarm")
+        );
+        assert_eq!(aliases.normalize_action_in("up", SWITCH, None), None);
+        assert_eq!(aliases.normalize_action_in("bonjour", SWITCH, None), None);
+    }
+
+    #[test]
+    fn test_registered_locale_and_hint() {
+        let aliases = ActionAliases::new();
+        // "para" is Spanish for stop; a Portuguese table reads it differently
+        aliases.register(
+            LocaleAliases::new("pt_BR")
+                .alias("ligar", "on")
+                .unwrap()
+                .alias("para", "down")
+                .unwrap(),
+        );
+        assert!(LocaleAliases::new("pt").alias("x", "explode").is_err());
+        assert!(aliases.locales().contains(&"pt".to_string()));
+        assert_eq!(
+            aliases.normalize_action_in("ligar", SWITCH, None),
+            Some("on")
+        );
+        assert_eq!(
+            aliases.normalize_action_in("para", BLINDS, None),
+            Some("stop")
+        );
+        assert_eq!(
+            aliases.normalize_action_in("para", BLINDS, Some("pt-PT")),
+            Some("down")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_locale() {
+        assert_eq!(ActionAliases::current_locale(), None);
+        let locale = ActionAliases::scope("it-IT".to_string(), async {
+            ActionAliases::current_locale()
+        })
+        .await;
+        assert_eq!(locale.as_deref(), Some("it"));
+
+        assert_eq!(
+            locale_from_accept_language("en;q=0.5, fr-CH, de;q=0.9").as_deref(),
+            Some("fr")
+        );
+        assert_eq!(locale_from_accept_language("*"), None);
+    }
+}
@@ -0,0 +1,357 @@
+//! Split air conditioners and fan coil units
+//!
+//! Two control types drive room air conditioning, with different commands
+//! and capabilities:
+//!
+//! - `AcControl` (AC Unit Controller, split units): modes auto, heat, cool,
+//!   dry and fan; commands `setMode/<id>`, `setFan/<speed>` and
+//!   `setTarget/<°C>`, switched with `on` and `off`
+//! - `FanCoilControl` (fan coil units): modes auto, heat, cool and fan;
+//!   commands `setOperatingMode/<id>`, `setFanSpeed/<speed>` and
+//!   `setComfortTemperature/<°C>`, switched off with operating mode 0
+//!
+//! Both report `mode`, `fanSpeed`, `targetTemperature` and `temperature`
+//! states. A unit restricts its modes with `details.modes` (list of mode
+//! IDs), its fan speeds with `details.fanSpeeds` and its setpoint with
+//! `details.minTemperature`/`details.maxTemperature`; the defaults below
+//! apply when the details are missing. Fan speed 0 is automatic.
+
+use crate::error::{LoxoneError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as AC or fan coil units
+pub const AC_CONTROL_TYPES: &[&str] = &["AcControl", "FanCoilControl"];
+
+const STATE_NAMES: &[&str] = &["mode", "fanSpeed", "targetTemperature", "temperature"];
+
+/// Operating mode of a unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AcMode {
+    Off,
+    Auto,
+    Heat,
+    Cool,
+    Dry,
+    Fan,
+}
+
+impl AcMode {
+    /// Parse a mode name
+    pub fn parse(mode: &str) -> Result<Self> {
+        match mode.trim().to_lowercase().as_str() {
+            "off" | "aus" => Ok(Self::Off),
+            "auto" | "automatic" => Ok(Self::Auto),
+            "heat" | "heating" => Ok(Self::Heat),
+            "cool" | "cooling" => Ok(Self::Cool),
+            "dry" | "dehumidify" => Ok(Self::Dry),
+            "fan" | "fan_only" => Ok(Self::Fan),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid mode '{other}'. Use: off, auto, heat, cool, dry, fan"
+            ))),
+        }
+    }
+
+    /// Name used in tool output
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Auto => "auto",
+            Self::Heat => "heat",
+            Self::Cool => "cool",
+            Self::Dry => "dry",
+            Self::Fan => "fan",
+        }
+    }
+
+    /// Mode ID used in commands and states
+    fn id(self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::Auto => 1,
+            Self::Heat => 2,
+            Self::Cool => 3,
+            Self::Dry => 4,
+            Self::Fan => 5,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        [
+            Self::Off,
+            Self::Auto,
+            Self::Heat,
+            Self::Cool,
+            Self::Dry,
+            Self::Fan,
+        ]
+        .into_iter()
+        .find(|mode| mode.id() == id)
+    }
+}
+
+/// Kind of unit, deciding the command set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitKind {
+    SplitAc,
+    FanCoil,
+}
+
+impl UnitKind {
+    pub fn from_control_type(control_type: &str) -> Option<Self> {
+        match control_type {
+            "AcControl" => Some(Self::SplitAc),
+            "FanCoilControl" => Some(Self::FanCoil),
+            _ => None,
+        }
+    }
+
+    fn default_modes(self) -> &'static [AcMode] {
+        match self {
+            Self::SplitAc => &[
+                AcMode::Auto,
+                AcMode::Heat,
+                AcMode::Cool,
+                AcMode::Dry,
+                AcMode::Fan,
+            ],
+            Self::FanCoil => &[AcMode::Auto, AcMode::Heat, AcMode::Cool, AcMode::Fan],
+        }
+    }
+
+    fn default_temperature_range(self) -> (f64, f64) {
+        match self {
+            Self::SplitAc => (16.0, 30.0),
+            Self::FanCoil => (10.0, 30.0),
+        }
+    }
+}
+
+/// What a unit supports
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Capabilities {
+    pub kind: UnitKind,
+    /// Modes besides off
+    pub modes: Vec<AcMode>,
+    /// Highest fan speed; 0 is automatic
+    pub fan_speeds: u8,
+    pub min_temperature: f64,
+    pub max_temperature: f64,
+}
+
+impl Capabilities {
+    /// Capabilities of a control, `None` for other control types
+    pub fn of(control: &Value) -> Option<Self> {
+        let kind = UnitKind::from_control_type(control.get("type")?.as_str()?)?;
+        let details = control.get("details");
+        let detail = |name: &str| details.and_then(|d| d.get(name)).and_then(Value::as_f64);
+
+        let modes: Vec<AcMode> = details
+            .and_then(|d| d.get("modes"))
+            .and_then(Value::as_array)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(Value::as_u64)
+                    .filter_map(|id| AcMode::from_id(u8::try_from(id).ok()?))
+                    .filter(|mode| *mode != AcMode::Off)
+                    .collect()
+            })
+            .filter(|modes: &Vec<AcMode>| !modes.is_empty())
+            .unwrap_or_else(|| kind.default_modes().to_vec());
+        let (min, max) = kind.default_temperature_range();
+        Some(Self {
+            kind,
+            modes,
+            fan_speeds: detail("fanSpeeds").map_or(3, |n| n.clamp(1.0, 10.0) as u8),
+            min_temperature: detail("minTemperature").unwrap_or(min),
+            max_temperature: detail("maxTemperature").unwrap_or(max),
+        })
+    }
+}
+
+/// Requested changes to a unit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AcSettings {
+    pub mode: Option<AcMode>,
+    pub fan_speed: Option<u8>,
+    pub temperature: Option<f64>,
+}
+
+impl AcSettings {
+    /// Validate the settings against the unit and build its commands
+    pub fn commands(&self, capabilities: &Capabilities) -> Result<Vec<String>> {
+        if self.mode.is_none() && self.fan_speed.is_none() && self.temperature.is_none() {
+            return Err(LoxoneError::invalid_input(
+                "Give a mode, fan speed or temperature",
+            ));
+        }
+        if let Some(mode) = self.mode
+            && mode != AcMode::Off
+            && !capabilities.modes.contains(&mode)
+        {
+            let allowed: Vec<&str> = capabilities.modes.iter().map(|m| m.name()).collect();
+            return Err(LoxoneError::invalid_input(format!(
+                "Mode '{}' is not supported by this unit. Use: off, {}",
+                mode.name(),
+                allowed.join(", ")
+            )));
+        }
+        if let Some(speed) = self.fan_speed
+            && speed > capabilities.fan_speeds
+        {
+            return Err(LoxoneError::invalid_input(format!(
+                "Fan speed must be between 0 (auto) and {}",
+                capabilities.fan_speeds
+            )));
+        }
+        if let Some(temperature) = self.temperature
+            && !(capabilities.min_temperature..=capabilities.max_temperature).contains(&temperature)
+        {
+            return Err(LoxoneError::invalid_input(format!(
+                "Temperature must be between {}°C and {}°C",
+                capabilities.min_temperature, capabilities.max_temperature
+            )));
+        }
+
+        let mut commands = Vec::new();
+        match (capabilities.kind, self.mode) {
+            (UnitKind::SplitAc, Some(AcMode::Off)) => commands.push("off".to_string()),
+            (UnitKind::SplitAc, Some(mode)) => {
+                commands.push("on".to_string());
+                commands.push(format!("setMode/{}", mode.id()));
+            }
+            (UnitKind::FanCoil, Some(mode)) => {
+                commands.push(format!("setOperatingMode/{}", mode.id()))
+            }
+            (_, None) => {}
+        }
+        if let Some(speed) = self.fan_speed {
+            commands.push(match capabilities.kind {
+                UnitKind::SplitAc => format!("setFan/{speed}"),
+                UnitKind::FanCoil => format!("setFanSpeed/{speed}"),
+            });
+        }
+        if let Some(temperature) = self.temperature {
+            commands.push(match capabilities.kind {
+                UnitKind::SplitAc => format!("setTarget/{temperature}"),
+                UnitKind::FanCoil => format!("setComfortTemperature/{temperature}"),
+            });
+        }
+        Ok(commands)
+    }
+}
+
+/// Snapshot of a unit's state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AcStatus {
+    pub mode: Option<AcMode>,
+    /// 0 is automatic
+    pub fan_speed: Option<u8>,
+    pub target_temperature: Option<f64>,
+    pub room_temperature: Option<f64>,
+}
+
+impl AcStatus {
+    /// Build the status from values keyed by state name
+    pub fn from_state_values(values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| {
+            values
+                .get(name)
+                .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+        };
+        Self {
+            mode: number("mode").and_then(|id| AcMode::from_id(id as u8)),
+            fan_speed: number("fanSpeed").map(|speed| speed.max(0.0) as u8),
+            target_temperature: number("targetTemperature"),
+            room_temperature: number("temperature"),
+        }
+    }
+}
+
+/// State UUIDs of a unit, keyed by state name
+pub fn state_uuids(control: &Value) -> HashMap<String, String> {
+    STATE_NAMES
+        .iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(*name))
+                .and_then(Value::as_str)
+                .map(|uuid| ((*name).to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn settings(mode: Option<&str>, fan_speed: Option<u8>, temperature: Option<f64>) -> AcSettings {
+        AcSettings {
+            mode: mode.map(|m| AcMode::parse(m).unwrap()),
+            fan_speed,
+            temperature,
+        }
+    }
+
+    #[test]
+    fn test_commands_per_unit_kind() {
+        let split = Capabilities::of(&json!({"type": "AcControl"})).unwrap();
+        assert_eq!(
+            settings(Some("cool"), Some(2), Some(22.5))
+                .commands(&split)
+                .unwrap(),
+            vec!["on", "setMode/3", "setFan/2", "setTarget/22.5"]
+        );
+        assert_eq!(
+            settings(Some("off"), None, None).commands(&split).unwrap(),
+            vec!["off"]
+        );
+
+        let fan_coil = Capabilities::of(&json!({"type": "FanCoilControl"})).unwrap();
+        assert_eq!(
+            settings(Some("heat"), Some(0), None)
+                .commands(&fan_coil)
+                .unwrap(),
+            vec!["setOperatingMode/2", "setFanSpeed/0"]
+        );
+        // Fan coils cannot dehumidify
+        assert!(
+            settings(Some("dry"), None, None)
+                .commands(&fan_coil)
+                .is_err()
+        );
+        assert!(settings(None, None, None).commands(&fan_coil).is_err());
+        assert!(Capabilities::of(&json!({"type": "IRoomControllerV2"})).is_none());
+    }
+
+    #[test]
+    fn test_capabilities_from_details() {
+        let unit = Capabilities::of(&json!({
+            "type": "AcControl",
+            "details": {"modes": [0, 3, 5], "fanSpeeds": 5, "minTemperature": 18.0}
+        }))
+        .unwrap();
+        assert_eq!(unit.modes, vec![AcMode::Cool, AcMode::Fan]);
+        assert_eq!(unit.max_temperature, 30.0);
+        assert!(settings(Some("heat"), None, None).commands(&unit).is_err());
+        assert!(settings(None, Some(5), None).commands(&unit).is_ok());
+        assert!(settings(None, Some(6), None).commands(&unit).is_err());
+        assert!(settings(None, None, Some(17.0)).commands(&unit).is_err());
+
+        let control = json!({"states": {"mode": "s-mode", "fanSpeed": "s-fan"}});
+        let uuids = state_uuids(&control);
+        assert_eq!(uuids.len(), 2);
+        let values = HashMap::from([
+            ("mode".to_string(), json!(3)),
+            ("fanSpeed".to_string(), json!("2")),
+        ]);
+        let status = AcStatus::from_state_values(&values);
+        assert_eq!(status.mode, Some(AcMode::Cool));
+        assert_eq!(status.fan_speed, Some(2));
+    }
+}
@@ -0,0 +1,133 @@
+//! Battery and reachability of Loxone Air peripherals
+//!
+//! The Air diagnostics ([`crate::client::AIR_DEVICES_PATH`]) list every
+//! paired peripheral. Firmware versions differ in the field names, so both
+//! spellings are accepted:
+//!
+//! ```json
+//! {"devices": [{"serial": "50:4F:94:10:2A:01", "name": "Window Kitchen",
+//!   "room": "<room uuid>", "type": "Door & Window Contact Air",
+//!   "battery": 85, "online": true, "lastSeen": "2025-03-04 18:12:40",
+//!   "rssi": -71}]}
+//! ```
+//!
+//! Mains-powered devices report no battery level.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Default battery level in % below which a device is flagged
+pub const DEFAULT_LOW_BATTERY_PERCENT: f64 = 20.0;
+
+/// One Air peripheral
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AirDevice {
+    pub serial: String,
+    pub name: String,
+    /// Room UUID or name as reported by the Miniserver
+    pub room: Option<String>,
+    pub device_type: Option<String>,
+    /// `None` for mains-powered devices
+    pub battery_percent: Option<f64>,
+    pub online: bool,
+    pub last_seen: Option<String>,
+    /// Signal strength in dBm
+    pub signal_dbm: Option<f64>,
+}
+
+impl AirDevice {
+    /// Whether the battery is below `threshold` percent
+    pub fn is_low(&self, threshold: f64) -> bool {
+        self.battery_percent.is_some_and(|level| level < threshold)
+    }
+}
+
+/// Parse the diagnostics, a device list or an object with a `devices` list
+pub fn parse_devices(value: &Value) -> Vec<AirDevice> {
+    let devices = match value {
+        Value::Array(devices) => devices,
+        Value::Object(_) => match value.get("devices").and_then(Value::as_array) {
+            Some(devices) => devices,
+            None => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    devices.iter().filter_map(parse_device).collect()
+}
+
+fn parse_device(device: &Value) -> Option<AirDevice> {
+    let field = |names: &[&str]| names.iter().find_map(|name| device.get(*name));
+    let text = |names: &[&str]| {
+        field(names)
+            .and_then(Value::as_str)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let number = |names: &[&str]| {
+        field(names).and_then(|v| match v {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().trim_end_matches('%').parse().ok(),
+            _ => None,
+        })
+    };
+
+    let serial = text(&["serial", "serialNr", "snr"])?;
+    let online = is_online(device);
+    Some(AirDevice {
+        name: text(&["name"]).unwrap_or_else(|| serial.clone()),
+        serial,
+        room: text(&["room", "roomName"]),
+        device_type: text(&["type", "deviceType"]),
+        // Mains-powered devices report a negative level on some firmware
+        battery_percent: number(&["battery", "batteryLevel", "bat"])
+            .filter(|level| (0.0..=100.0).contains(level)),
+        online,
+        last_seen: text(&["lastSeen", "lastContact"]),
+        signal_dbm: number(&["rssi", "signal"]),
+    })
+}
+
+/// Reachability flag of a device entry, `false` if missing
+pub fn is_online(device: &Value) -> bool {
+    match ["online", "reachable", "isOnline"]
+        .iter()
+        .find_map(|name| device.get(*name))
+    {
+        Some(Value::Bool(online)) => *online,
+        Some(Value::Number(n)) => n.as_f64().is_some_and(|n| n != 0.0),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_devices() {
+        let devices = parse_devices(&json!({"devices": [
+            {"serial": "50:4F:94:10:2A:01", "name": "Window Kitchen", "battery": 85,
+             "online": true, "rssi": -71},
+            {"serialNr": "50:4F:94:10:2A:02", "batteryLevel": "12%", "reachable": 0},
+            {"serial": "50:4F:94:10:2A:03", "name": "Smart Socket", "battery": -1,
+             "online": true},
+            {"name": "no serial"}
+        ]}));
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[0].battery_percent, Some(85.0));
+        assert_eq!(devices[0].signal_dbm, Some(-71.0));
+        assert!(devices[0].online);
+        assert_eq!(devices[1].name, "50:4F:94:10:2A:02");
+        assert_eq!(devices[1].battery_percent, Some(12.0));
+        assert!(!devices[1].online);
+        assert_eq!(devices[2].battery_percent, None);
+
+        assert!(!devices[0].is_low(DEFAULT_LOW_BATTERY_PERCENT));
+        assert!(devices[1].is_low(DEFAULT_LOW_BATTERY_PERCENT));
+        assert!(!devices[2].is_low(DEFAULT_LOW_BATTERY_PERCENT));
+
+        assert_eq!(parse_devices(&json!([{"serial": "a"}])).len(), 1);
+        assert!(parse_devices(&json!("error")).is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+//! Burglar alarm handling
+//!
+//! Loxone exposes the burglar alarm as an `Alarm` control. Its `states` map
+//! names to state UUIDs:
+//!
+//! - `armed`: 1 while armed
+//! - `armedDelay` / `armedDelayTotal`: seconds left and total of the arming delay
+//! - `level`: current alarm level (0 = no alarm, 1 silent, up to 6)
+//! - `nextLevel` / `nextLevelDelay`: next level and seconds until it is reached
+//! - `sensors`: text listing the sensors that triggered, separated by `|`
+//! - `disabledMove`: 1 while motion sensors are excluded from the alarm
+//!
+//! Disarming lets anyone into the house, so `control_alarm` asks the user
+//! through the consent manager before the command is sent (see
+//! [`crate::server::tool_middleware::ConsentMiddleware`]).
+
+use crate::error::{LoxoneError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as burglar alarms
+pub const ALARM_CONTROL_TYPES: &[&str] = &["Alarm"];
+
+/// State names read for an alarm status
+pub const ALARM_STATE_NAMES: &[&str] = &[
+    "armed",
+    "armedDelay",
+    "armedDelayTotal",
+    "level",
+    "nextLevel",
+    "nextLevelDelay",
+    "sensors",
+    "disabledMove",
+];
+
+/// Action requested for an alarm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlarmAction {
+    /// Arm including motion sensors
+    Arm,
+    /// Arm without motion sensors, for when someone stays home
+    ArmHome,
+    /// Arm after the configured delay, so the house can be left
+    DelayedArm,
+    Disarm,
+    /// Acknowledge an alarm that went off
+    Acknowledge,
+}
+
+impl AlarmAction {
+    /// Parse an action name (English or German)
+    pub fn parse(action: &str) -> Result<Self> {
+        match action.trim().to_lowercase().as_str() {
+            "arm" | "arm_away" | "scharf" | "abwesend" => Ok(Self::Arm),
+            "arm_home" | "arm_stay" | "zuhause" => Ok(Self::ArmHome),
+            "delayed_arm" | "arm_delayed" | "verzögert" | "verzoegert" => Ok(Self::DelayedArm),
+            "disarm" | "unscharf" => Ok(Self::Disarm),
+            "acknowledge" | "ack" | "quit" | "quittieren" => Ok(Self::Acknowledge),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid alarm action '{other}'. Use: arm, arm_home, delayed_arm, disarm, acknowledge"
+            ))),
+        }
+    }
+
+    /// Miniserver command for the action
+    pub fn command(self) -> &'static str {
+        match self {
+            Self::Arm => "on/1",
+            Self::ArmHome => "on/0",
+            Self::DelayedArm => "delayedon/1",
+            Self::Disarm => "off",
+            Self::Acknowledge => "quit",
+        }
+    }
+
+    /// Name used in tool output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Arm => "arm",
+            Self::ArmHome => "arm_home",
+            Self::DelayedArm => "delayed_arm",
+            Self::Disarm => "disarm",
+            Self::Acknowledge => "acknowledge",
+        }
+    }
+
+    /// Whether the action disarms the alarm and therefore needs consent
+    pub fn requires_consent(self) -> bool {
+        self == Self::Disarm
+    }
+}
+
+/// Snapshot of an alarm's state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlarmStatus {
+    /// `disarmed`, `arming`, `armed` or `alarm`
+    pub state: &'static str,
+    pub armed: bool,
+    /// Seconds until arming completes
+    pub arming_delay_secs: Option<f64>,
+    pub alarm_level: u8,
+    /// Seconds until the alarm escalates to the next level
+    pub next_level_delay_secs: Option<f64>,
+    /// Sensors that triggered the alarm
+    pub active_sensors: Vec<String>,
+    pub motion_disabled: bool,
+}
+
+impl AlarmStatus {
+    /// Build the status from state values keyed by state name
+    pub fn from_state_values(values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+        let flag = |name: &str| number(name).is_some_and(|v| v != 0.0);
+
+        let armed = flag("armed");
+        let arming_delay = number("armedDelay").filter(|d| *d > 0.0);
+        let alarm_level = number("level").map_or(0, |l| l.clamp(0.0, 6.0) as u8);
+        let state = if alarm_level > 0 {
+            "alarm"
+        } else if armed {
+            "armed"
+        } else if arming_delay.is_some() {
+            "arming"
+        } else {
+            "disarmed"
+        };
+
+        Self {
+            state,
+            armed,
+            arming_delay_secs: arming_delay,
+            alarm_level,
+            next_level_delay_secs: number("nextLevelDelay").filter(|d| *d > 0.0),
+            active_sensors: values
+                .get("sensors")
+                .and_then(Value::as_str)
+                .map(parse_sensors)
+                .unwrap_or_default(),
+            motion_disabled: flag("disabledMove"),
+        }
+    }
+}
+
+/// Sensor names from the `sensors` text state
+pub fn parse_sensors(text: &str) -> Vec<String> {
+    text.split('|')
+        .map(str::trim)
+        .filter(|sensor| !sensor.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// State UUIDs of an alarm control, keyed by state name
+pub fn alarm_state_uuids(control: &Value) -> HashMap<String, String> {
+    ALARM_STATE_NAMES
+        .iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(*name))
+                .and_then(Value::as_str)
+                .map(|uuid| ((*name).to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{
+        StructureBuilder, TestServer, assert_command_sent, assert_tool_error, assert_tool_ok,
+        device_uuid, devices,
+    };
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(AlarmAction::parse("Scharf").unwrap(), AlarmAction::Arm);
+        assert_eq!(AlarmAction::parse("arm_home").unwrap().command(), "on/0");
+        assert_eq!(AlarmAction::parse("quit").unwrap().command(), "quit");
+        assert!(AlarmAction::parse("open").is_err());
+
+        assert!(AlarmAction::Disarm.requires_consent());
+        assert!(!AlarmAction::Arm.requires_consent());
+        assert!(!AlarmAction::Acknowledge.requires_consent());
+    }
+
+    #[test]
+    fn test_alarm_status() {
+        let disarmed = AlarmStatus::from_state_values(&values(&[
+            ("armed", json!(0)),
+            ("level", json!(0)),
+            ("sensors", json!("")),
+        ]));
+        assert_eq!(disarmed.state, "disarmed");
+        assert!(disarmed.active_sensors.is_empty());
+
+        let arming = AlarmStatus::from_state_values(&values(&[
+            ("armed", json!(0)),
+            ("armedDelay", json!("25")),
+        ]));
+        assert_eq!(arming.state, "arming");
+        assert_eq!(arming.arming_delay_secs, Some(25.0));
+
+        let alarm = AlarmStatus::from_state_values(&values(&[
+            ("armed", json!(1)),
+            ("level", json!(2)),
+            ("nextLevelDelay", json!(30)),
+            ("sensors", json!("Hallway Motion | Terrace Door|")),
+        ]));
+        assert_eq!(alarm.state, "alarm");
+        assert_eq!(alarm.alarm_level, 2);
+        assert_eq!(alarm.active_sensors, vec!["Hallway Motion", "Terrace Door"]);
+    }
+
+    #[tokio::test]
+    async fn test_disarm_waits_for_the_users_consent() {
+        let structure = StructureBuilder::new()
+            .device("Hall", devices::alarm("Burglar Alarm"))
+            .build();
+        let fixture = TestServer::new(structure).await;
+        let alarm = device_uuid("Hall", "Burglar Alarm");
+
+        let answer = fixture.answer_consent(false);
+        let result = fixture.control_alarm("disarm".into(), None).await;
+        answer.await.unwrap();
+        assert_tool_error(result, "Consent denied");
+        assert!(fixture.client.commands().is_empty());
+
+        let answer = fixture.answer_consent(true);
+        let result = fixture.control_alarm("disarm".into(), None).await;
+        answer.await.unwrap();
+        assert_eq!(assert_tool_ok(result)["consent_confirmed"], true);
+        assert_command_sent(&fixture.client, &alarm, "off");
+
+        // Arming needs no consent
+        let result = fixture.control_alarm("arm".into(), None).await;
+        assert_tool_ok(result);
+        assert!(
+            fixture
+                .consent_manager()
+                .pending_requests()
+                .await
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Audio zones of the Loxone Audioserver and Music Server
+//!
+//! Each zone is a control in the structure file: `AudioZoneV2` for the
+//! Audioserver, `AudioZone` for the older Music Server. Their `details`
+//! carry the player ID the server knows the zone by, and their states the
+//! volume, play state and selected source.
+//!
+//! Commands sent to a zone:
+//!
+//! | Action                 | Command                    |
+//! |------------------------|----------------------------|
+//! | set volume             | `volume/<0-100>`           |
+//! | select source          | `source/<n>`               |
+//! | play favorite          | `playZoneFav/<n>`          |
+//! | group with a leader    | `sync/<leader player ID>`  |
+//! | leave its group        | `unsync`                   |
+//! | announce text (TTS)    | `tts/<text>[/<0-100>]`     |
+//!
+//! Announcements are spoken by the Audioserver's text-to-speech with the
+//! zone's announcement volume unless one is given; the text is URL-encoded.
+
+use crate::client::LoxoneStructure;
+use crate::error::{LoxoneError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Longest announcement text in characters
+pub const MAX_ANNOUNCEMENT_CHARS: usize = 500;
+
+/// Characters spoken per second, for estimating announcement durations
+const SPOKEN_CHARS_PER_SEC: f64 = 14.0;
+
+/// Chime and pause around each announcement
+const ANNOUNCEMENT_OVERHEAD_SECS: f64 = 3.0;
+
+/// Control types of audio zones
+pub const AUDIO_ZONE_TYPES: &[&str] = &["AudioZoneV2", "AudioZone", "MediaController"];
+
+/// States reported for each zone
+pub const ZONE_STATES: &[&str] = &["volume", "playState", "source", "power", "serverState"];
+
+/// An audio zone from the structure file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioZone {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    pub control_type: String,
+    /// Player ID on the Audioserver, used for grouping
+    pub player_id: Option<u64>,
+    /// State name to state UUID for [`ZONE_STATES`]
+    #[serde(skip)]
+    pub states: Vec<(String, String)>,
+}
+
+impl AudioZone {
+    fn from_control(structure: &LoxoneStructure, uuid: &str, control: &Value) -> Option<Self> {
+        let control_type = control.get("type").and_then(Value::as_str)?;
+        if !AUDIO_ZONE_TYPES.contains(&control_type) {
+            return None;
+        }
+        let details = control.get("details");
+        let player_id = details
+            .and_then(|d| d.get("playerid").or_else(|| d.get("playerId")))
+            .and_then(|id| id.as_u64().or_else(|| id.as_str()?.parse().ok()));
+        let states = ZONE_STATES
+            .iter()
+            .filter_map(|state| {
+                control
+                    .get("states")
+                    .and_then(|states| states.get(*state))
+                    .and_then(Value::as_str)
+                    .map(|uuid| (state.to_string(), uuid.to_string()))
+            })
+            .collect();
+        Some(Self {
+            uuid: uuid.to_string(),
+            name: control
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("Unknown")
+                .to_string(),
+            room: control
+                .get("room")
+                .and_then(Value::as_str)
+                .and_then(|room| structure.rooms.get(room))
+                .and_then(|room| room.get("name"))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            control_type: control_type.to_string(),
+            player_id,
+            states,
+        })
+    }
+}
+
+/// All audio zones, sorted by room and name
+pub fn audio_zones(structure: &LoxoneStructure) -> Vec<AudioZone> {
+    let mut zones: Vec<AudioZone> = structure
+        .controls
+        .iter()
+        .filter_map(|(uuid, control)| AudioZone::from_control(structure, uuid, control))
+        .collect();
+    zones.sort_by(|a, b| (&a.room, &a.name).cmp(&(&b.room, &b.name)));
+    zones
+}
+
+/// Find a zone by UUID, name or room name (exact before partial matches)
+pub fn find_zone(zones: &[AudioZone], identifier: &str) -> Result<AudioZone> {
+    let wanted = identifier.trim().to_lowercase();
+    let by_name = |zone: &&AudioZone| zone.name.to_lowercase() == wanted;
+    let by_room = |zone: &&AudioZone| {
+        zone.room
+            .as_deref()
+            .is_some_and(|room| room.to_lowercase() == wanted)
+    };
+    let partial = |zone: &&AudioZone| {
+        zone.name.to_lowercase().contains(&wanted)
+            || zone
+                .room
+                .as_deref()
+                .is_some_and(|room| room.to_lowercase().contains(&wanted))
+    };
+    zones
+        .iter()
+        .find(|zone| zone.uuid == identifier)
+        .or_else(|| zones.iter().find(by_name))
+        .or_else(|| zones.iter().find(by_room))
+        .or_else(|| zones.iter().find(partial))
+        .cloned()
+        .ok_or_else(|| {
+            LoxoneError::not_found(format!(
+                "Audio zone '{identifier}' not found; available: {}",
+                zones
+                    .iter()
+                    .map(|zone| zone.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+}
+
+/// Command setting the volume
+pub fn volume_command(volume: u8) -> Result<String> {
+    if volume > 100 {
+        return Err(LoxoneError::invalid_input("Volume must be between 0-100"));
+    }
+    Ok(format!("volume/{volume}"))
+}
+
+/// Command selecting a source or playing a favorite (both numbered from 1)
+pub fn source_command(source: Option<u32>, favorite: Option<u32>) -> Result<String> {
+    match (source, favorite) {
+        (Some(_), Some(_)) => Err(LoxoneError::invalid_input(
+            "Select either a source or a favorite, not both",
+        )),
+        (Some(0), None) | (None, Some(0)) => Err(LoxoneError::invalid_input(
+            "Sources and favorites are numbered from 1",
+        )),
+        (Some(source), None) => Ok(format!("source/{source}")),
+        (None, Some(favorite)) => Ok(format!("playZoneFav/{favorite}")),
+        (None, None) => Err(LoxoneError::invalid_input(
+            "A source or favorite number is required",
+        )),
+    }
+}
+
+/// Command joining a zone to the group led by `leader`
+pub fn group_command(leader: &AudioZone) -> Result<String> {
+    let player_id = leader.player_id.ok_or_else(|| {
+        LoxoneError::invalid_input(format!(
+            "Audio zone '{}' has no player ID and cannot lead a group",
+            leader.name
+        ))
+    })?;
+    Ok(format!("sync/{player_id}"))
+}
+
+/// Command taking a zone out of its group
+pub const UNGROUP_COMMAND: &str = "unsync";
+
+/// Command announcing `text`, at `volume` instead of the zone's
+/// announcement volume when given
+pub fn tts_command(text: &str, volume: Option<u8>) -> Result<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(LoxoneError::invalid_input("The announcement text is empty"));
+    }
+    if text.chars().count() > MAX_ANNOUNCEMENT_CHARS {
+        return Err(LoxoneError::invalid_input(format!(
+            "Announcements are limited to {MAX_ANNOUNCEMENT_CHARS} characters"
+        )));
+    }
+    let text = urlencoding::encode(text);
+    match volume {
+        Some(volume) => {
+            volume_command(volume)?;
+            Ok(format!("tts/{text}/{volume}"))
+        }
+        None => Ok(format!("tts/{text}")),
+    }
+}
+
+/// Estimated time an announcement of `text` takes to play
+pub fn announcement_duration(text: &str) -> Duration {
+    let chars = text.trim().chars().count() as f64;
+    Duration::from_secs_f64(ANNOUNCEMENT_OVERHEAD_SECS + chars / SPOKEN_CHARS_PER_SEC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn structure() -> LoxoneStructure {
+        LoxoneStructure {
+            last_modified: String::new(),
+            controls: HashMap::from([
+                (
+                    "z-kitchen".to_string(),
+                    json!({
+                        "name": "Kitchen Speakers",
+                        "type": "AudioZoneV2",
+                        "room": "r-kitchen",
+                        "details": {"playerid": 3},
+                        "states": {"volume": "s-vol", "playState": "s-play"}
+                    }),
+                ),
+                (
+                    "z-living".to_string(),
+                    json!({
+                        "name": "Music",
+                        "type": "AudioZone",
+                        "room": "r-living",
+                        "details": {"playerid": "7"}
+                    }),
+                ),
+                (
+                    "light".to_string(),
+                    json!({"name": "Ceiling", "type": "LightControllerV2"}),
+                ),
+            ]),
+            rooms: HashMap::from([
+                ("r-kitchen".to_string(), json!({"name": "Kitchen"})),
+                ("r-living".to_string(), json!({"name": "Living Room"})),
+            ]),
+            cats: HashMap::new(),
+            global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_zones_from_structure() {
+        let zones = audio_zones(&structure());
+        assert_eq!(zones.len(), 2);
+        assert_eq!(zones[0].name, "Kitchen Speakers");
+        assert_eq!(zones[0].player_id, Some(3));
+        assert_eq!(zones[0].states.len(), 2);
+        assert_eq!(zones[1].player_id, Some(7));
+
+        assert_eq!(find_zone(&zones, "living room").unwrap().uuid, "z-living");
+        assert_eq!(find_zone(&zones, "kitchen").unwrap().uuid, "z-kitchen");
+        assert_eq!(find_zone(&zones, "z-living").unwrap().name, "Music");
+        assert!(find_zone(&zones, "Garage").is_err());
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(volume_command(35).unwrap(), "volume/35");
+        assert!(volume_command(101).is_err());
+        assert_eq!(source_command(Some(2), None).unwrap(), "source/2");
+        assert_eq!(source_command(None, Some(4)).unwrap(), "playZoneFav/4");
+        assert!(source_command(Some(1), Some(1)).is_err());
+        assert!(source_command(None, None).is_err());
+
+        let zones = audio_zones(&structure());
+        assert_eq!(group_command(&zones[1]).unwrap(), "sync/7");
+
+        assert_eq!(
+            tts_command(" Dinner is ready ", None).unwrap(),
+            "tts/Dinner%20is%20ready"
+        );
+        assert_eq!(
+            tts_command("Hi/there", Some(40)).unwrap(),
+            "tts/Hi%2Fthere/40"
+        );
+        assert!(tts_command("  ", None).is_err());
+        assert!(tts_command("Hi", Some(120)).is_err());
+        assert!(tts_command(&"a".repeat(MAX_ANNOUNCEMENT_CHARS + 1), None).is_err());
+        assert_eq!(announcement_duration("x".repeat(28).as_str()).as_secs(), 5);
+    }
+}
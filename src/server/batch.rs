@@ -0,0 +1,1010 @@
+//! Batched tool calls
+//!
+//! `batch_execute` runs a list of named sub-operations in one round-trip, so
+//! an LLM can apply a whole scene plan at once:
+//!
+//! ```json
+//! [
+//!   {"id": "close", "tool": "control_blinds", "args": {"target": "Living", "action": "down"}},
+//!   {"id": "dim", "tool": "control_lights", "args": {"scope": "room", "target": "Living", "action": "dim", "brightness": 30}},
+//!   {"id": "music", "tool": "control_audio_zone", "args": {"zone": "Living", "action": "play"}, "depends_on": ["dim"]}
+//! ]
+//! ```
+//!
+//! Operations are grouped into stages by their `depends_on` ids; each stage
+//! runs concurrently once the previous one finished. An operation whose
+//! dependency failed is skipped, and with `stop_on_error` a failure skips
+//! every later stage. Each sub-call goes through the normal tool path
+//! (middlewares, timeouts, dry run, command history).
+
+use crate::error::{LoxoneError, Result};
+use crate::server::macro_backend::LoxoneMcpServer;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// Most operations accepted in one batch
+pub const MAX_OPERATIONS: usize = 50;
+
+/// Name of the batch tool, which cannot be nested
+pub const BATCH_TOOL: &str = "batch_execute";
+
+/// Tools callable from a batch with their argument names
+pub const TOOL_PARAMS: &[(&str, &[&str])] = &[
+    (
+        "control_lights",
+        &["scope", "target", "action", "brightness", "confirm"],
+    ),
+    ("get_lights_status", &[]),
+    (
+        "set_light_color",
+        &["light", "rgb", "hue", "saturation", "kelvin", "brightness"],
+    ),
+    ("get_timers", &["room"]),
+    ("start_timer", &["device", "duration_seconds"]),
+    ("cancel_timer", &["device"]),
+    ("set_temperature", &["room", "temperature", "mode"]),
+    ("get_climate_status", &[]),
+    ("get_valve_diagnostics", &["room"]),
+    ("get_heating_zones", &["room"]),
+    ("get_climate_schedule", &["room"]),
+    ("set_climate_schedule", &["room", "entries", "mode_id"]),
+    ("get_hot_water_status", &[]),
+    ("boost_hot_water", &["unit", "minutes"]),
+    ("set_hot_water_temperature", &["unit", "temperature"]),
+    ("set_hot_water_schedule", &["unit", "entries", "mode_id"]),
+    ("get_ac_status", &["unit"]),
+    ("set_ac", &["unit", "mode", "fan_speed", "temperature"]),
+    ("get_irrigation_status", &["controller"]),
+    ("control_irrigation", &["action", "zone", "controller"]),
+    (
+        "set_irrigation_duration",
+        &["zone", "minutes", "controller"],
+    ),
+    ("get_pool_status", &["pool"]),
+    ("control_pool", &["action", "pool"]),
+    ("set_pool_temperature", &["temperature", "pool"]),
+    ("get_ventilation_status", &["unit"]),
+    (
+        "set_ventilation_mode",
+        &["action", "stage", "minutes", "unit"],
+    ),
+    (
+        "control_blinds",
+        &["target", "action", "position", "confirm"],
+    ),
+    (
+        "set_blind_position",
+        &["target", "position", "slat_angle", "confirm"],
+    ),
+    ("get_blinds_status", &[]),
+    ("get_storm_protection", &["room"]),
+    ("list_rooms", &[]),
+    ("list_devices", &["room", "category"]),
+    ("get_favorites", &["room"]),
+    ("set_favorite", &["device", "favorite", "rating"]),
+    ("get_device_info", &["device_id"]),
+    ("lookup_device", &["query"]),
+    ("search_devices", &["query", "room", "category", "limit"]),
+    ("diagnose_devices", &["room"]),
+    ("lock_device", &["device", "reason"]),
+    ("unlock_device", &["device"]),
+    ("execute_macro", &["steps", "rollback"]),
+    (
+        "get_all_states",
+        &["categories", "rooms", "offset", "limit", "units"],
+    ),
+    ("get_server_status", &[]),
+    ("get_system_topology", &[]),
+    ("get_available_tools", &[]),
+    ("get_tool_descriptions", &["locale"]),
+    ("set_operating_mode", &["mode", "action", "from", "until"]),
+    ("control_audio_zone", &["zone", "action"]),
+    ("set_audio_volume", &["zone", "volume"]),
+    ("get_audio_status", &[]),
+    (
+        "announce",
+        &["text", "zones", "volume", "zone_volumes", "queue"],
+    ),
+    ("list_audio_zones", &[]),
+    ("select_audio_source", &["zone", "source", "favorite"]),
+    ("group_audio_zones", &["action", "zones", "leader"]),
+    ("get_sensor_readings", &[]),
+    ("get_door_window_status", &[]),
+    ("get_motion_status", &[]),
+    (
+        "get_device_statistics",
+        &["device", "from", "to", "max_points"],
+    ),
+    ("get_device_batteries", &["threshold"]),
+    ("get_room_comfort", &["room"]),
+    ("get_text_states", &["room"]),
+    ("set_text_input", &["input", "text"]),
+    (
+        "get_tracker_entries",
+        &["tracker", "query", "since", "until", "offset", "limit"],
+    ),
+    ("list_virtual_inputs", &["kind", "room"]),
+    ("set_virtual_input", &["input", "value"]),
+    ("trigger_virtual_input", &["input", "value"]),
+    ("list_virtual_outputs", &["room"]),
+    ("set_virtual_output", &["output", "value"]),
+    ("get_presence_report", &["room"]),
+    ("get_device_usage", &["room", "flagged_only"]),
+    (
+        "diff_states",
+        &["since", "snapshot", "room", "include_reverted"],
+    ),
+    ("get_connected_clients", &[]),
+    ("get_sampling_usage", &[]),
+    (
+        "create_guest_access",
+        &[
+            "name",
+            "rooms",
+            "category",
+            "hours",
+            "read_only",
+            "server_url",
+        ],
+    ),
+    (
+        "get_command_history",
+        &["device", "tool", "key", "since", "success", "limit"],
+    ),
+    ("get_program_backups", &["run_now"]),
+    ("diff_structure", &["against"]),
+    (
+        "get_miniserver_log",
+        &["severity", "since_minutes", "limit", "file"],
+    ),
+    ("get_weather", &[]),
+    ("get_weather_forecast", &["hours", "days"]),
+    ("get_weather_protection", &[]),
+    (
+        "override_weather_protection",
+        &["device", "minutes", "clear"],
+    ),
+    ("get_energy_status", &[]),
+    ("get_energy_flow", &[]),
+    ("get_wallbox_status", &["charger"]),
+    (
+        "plan_heavy_load",
+        &[
+            "tariff",
+            "duration_minutes",
+            "power_kw",
+            "window_hours",
+            "virtual_input",
+            "value",
+        ],
+    ),
+    ("get_load_manager_status", &["manager"]),
+    ("control_load_shedding", &["action", "consumer", "manager"]),
+    ("control_ev_charging", &["charger", "action", "limit_kwh"]),
+    ("set_charging_current", &["amps", "charger"]),
+    (
+        "schedule_workflow",
+        &["name", "steps", "min_pv_surplus_w", "max_delay_minutes"],
+    ),
+    ("get_scheduled_workflows", &["cancel"]),
+    ("get_security_status", &[]),
+    ("set_security_mode", &["mode", "code"]),
+    ("get_alarm_status", &["alarm"]),
+    ("control_alarm", &["action", "alarm"]),
+    ("get_safety_status", &["device"]),
+    ("test_safety_alarm", &["device"]),
+    ("control_door_lock", &["lock", "action"]),
+    ("get_gate_status", &["gate", "room"]),
+    ("control_gate", &["gate", "action", "confirm"]),
+    ("get_camera_status", &[]),
+    ("control_intercom", &["intercom", "action"]),
+    ("get_intercom_history", &["intercom", "limit"]),
+    ("open_intercom_door", &["intercom", "output"]),
+    ("list_access_codes", &["device"]),
+    (
+        "add_access_code",
+        &[
+            "device",
+            "name",
+            "kind",
+            "secret",
+            "outputs",
+            "valid_from",
+            "valid_until",
+        ],
+    ),
+    ("revoke_access_code", &["device", "code_id"]),
+    ("activate_scene", &["scene", "room"]),
+    ("room_all_off", &["room", "blinds_down"]),
+    ("house_good_night", &[]),
+    ("list_scenes", &[]),
+    (
+        "control_light_moods",
+        &["action", "mood", "room", "controller"],
+    ),
+    ("capture_scene_snapshot", &["room", "name"]),
+    ("restore_scene_snapshot", &["name"]),
+    ("list_scene_snapshots", &["room"]),
+    ("delete_scene_snapshot", &["name"]),
+];
+
+/// One sub-operation of a batch
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchOperation {
+    /// Name other operations refer to (defaults to the 1-based position)
+    #[serde(default)]
+    pub id: Option<String>,
+    pub tool: String,
+    /// Tool arguments by name
+    #[serde(default)]
+    pub args: serde_json::Map<String, Value>,
+    /// Ids of operations that must succeed first
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// Outcome of one sub-operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    Ok,
+    Error,
+    Skipped,
+}
+
+/// Result of one sub-operation
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationResult {
+    pub id: String,
+    pub tool: String,
+    pub status: OperationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Validated batch with its execution stages
+#[derive(Debug, Clone)]
+pub struct BatchPlan {
+    pub operations: Vec<BatchOperation>,
+    /// Operation indexes per stage, in execution order
+    pub stages: Vec<Vec<usize>>,
+}
+
+impl BatchPlan {
+    /// Parse and order raw operations
+    ///
+    /// Rejects unknown tools and arguments, duplicate or unknown ids,
+    /// dependency cycles and nested batches.
+    pub fn new(raw: Vec<Value>) -> Result<Self> {
+        if raw.is_empty() {
+            return Err(LoxoneError::invalid_input(
+                "The batch contains no operations",
+            ));
+        }
+        if raw.len() > MAX_OPERATIONS {
+            return Err(LoxoneError::invalid_input(format!(
+                "A batch may contain at most {MAX_OPERATIONS} operations, got {}",
+                raw.len()
+            )));
+        }
+
+        let mut operations = Vec::with_capacity(raw.len());
+        for (index, value) in raw.into_iter().enumerate() {
+            let mut operation: BatchOperation = serde_json::from_value(value)
+                .map_err(|e| LoxoneError::invalid_input(format!("Operation {}: {e}", index + 1)))?;
+            let id = operation
+                .id
+                .get_or_insert_with(|| (index + 1).to_string())
+                .clone();
+            let params = tool_params(&operation.tool).ok_or_else(|| {
+                LoxoneError::invalid_input(format!(
+                    "Operation '{id}': unknown tool '{}'",
+                    operation.tool
+                ))
+            })?;
+            if let Some(unknown) = operation
+                .args
+                .keys()
+                .find(|key| !params.contains(&key.as_str()))
+            {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Operation '{id}': {} has no argument '{unknown}' (expected: {})",
+                    operation.tool,
+                    params.join(", ")
+                )));
+            }
+            operations.push(operation);
+        }
+
+        let mut positions = HashMap::new();
+        for (index, operation) in operations.iter().enumerate() {
+            if positions
+                .insert(operation.id().to_string(), index)
+                .is_some()
+            {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Duplicate operation id '{}'",
+                    operation.id()
+                )));
+            }
+        }
+        for operation in &operations {
+            if let Some(missing) = operation
+                .depends_on
+                .iter()
+                .find(|dep| !positions.contains_key(*dep))
+            {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Operation '{}' depends on unknown operation '{missing}'",
+                    operation.id()
+                )));
+            }
+        }
+
+        // Kahn's algorithm, keeping the given order within a stage
+        let mut done = HashSet::new();
+        let mut stages = Vec::new();
+        while done.len() < operations.len() {
+            let stage: Vec<usize> = (0..operations.len())
+                .filter(|index| !done.contains(index))
+                .filter(|index| {
+                    operations[*index]
+                        .depends_on
+                        .iter()
+                        .all(|dep| done.contains(&positions[dep]))
+                })
+                .collect();
+            if stage.is_empty() {
+                let cycle: Vec<&str> = (0..operations.len())
+                    .filter(|index| !done.contains(index))
+                    .map(|index| operations[index].id())
+                    .collect();
+                return Err(LoxoneError::invalid_input(format!(
+                    "Dependency cycle between operations {}",
+                    cycle.join(", ")
+                )));
+            }
+            done.extend(stage.iter().copied());
+            stages.push(stage);
+        }
+
+        Ok(Self { operations, stages })
+    }
+}
+
+impl BatchOperation {
+    pub fn id(&self) -> &str {
+        self.id.as_deref().unwrap_or_default()
+    }
+}
+
+/// Argument names of a batchable tool
+pub fn tool_params(tool: &str) -> Option<&'static [&'static str]> {
+    TOOL_PARAMS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, params)| *params)
+}
+
+/// Deserialize argument `key`; a missing key reads as `null`
+fn arg<T: DeserializeOwned>(
+    args: &serde_json::Map<String, Value>,
+    key: &str,
+) -> std::result::Result<T, String> {
+    serde_json::from_value(args.get(key).cloned().unwrap_or(Value::Null))
+        .map_err(|e| format!("Invalid argument '{key}': {e}"))
+}
+
+impl LoxoneMcpServer {
+    /// Run a planned batch and report every operation in the given order
+    pub async fn execute_batch(&self, plan: BatchPlan, stop_on_error: bool) -> Value {
+        let mut results: Vec<Option<OperationResult>> = vec![None; plan.operations.len()];
+        let mut failed: HashSet<&str> = HashSet::new();
+        let mut aborted = false;
+
+        for stage in &plan.stages {
+            let mut runnable = Vec::new();
+            for &index in stage {
+                let operation = &plan.operations[index];
+                let blocker = if aborted {
+                    Some("an earlier operation failed (stop_on_error)".to_string())
+                } else {
+                    operation
+                        .depends_on
+                        .iter()
+                        .find(|dep| failed.contains(dep.as_str()))
+                        .map(|dep| format!("dependency '{dep}' did not succeed"))
+                };
+                match blocker {
+                    Some(reason) => {
+                        failed.insert(operation.id());
+                        results[index] = Some(OperationResult {
+                            id: operation.id().to_string(),
+                            tool: operation.tool.clone(),
+                            status: OperationStatus::Skipped,
+                            result: None,
+                            error: Some(reason),
+                            duration_ms: 0,
+                        });
+                    }
+                    None => runnable.push(index),
+                }
+            }
+
+            let operations = &plan.operations;
+            let outcomes =
+                futures_util::future::join_all(runnable.iter().map(|&index| async move {
+                    let operation = &operations[index];
+                    let started = Instant::now();
+                    let outcome = self
+                        .call_tool_by_name(&operation.tool, &operation.args)
+                        .await;
+                    (index, outcome, started.elapsed().as_millis() as u64)
+                }))
+                .await;
+
+            for (index, outcome, duration_ms) in outcomes {
+                let operation = &plan.operations[index];
+                let (status, result, error) = match outcome {
+                    Ok(value) => (OperationStatus::Ok, Some(value), None),
+                    Err(e) => {
+                        failed.insert(operation.id());
+                        aborted |= stop_on_error;
+                        (OperationStatus::Error, None, Some(e))
+                    }
+                };
+                results[index] = Some(OperationResult {
+                    id: operation.id().to_string(),
+                    tool: operation.tool.clone(),
+                    status,
+                    result,
+                    error,
+                    duration_ms,
+                });
+            }
+        }
+
+        let results: Vec<OperationResult> = results.into_iter().flatten().collect();
+        let count = |status| results.iter().filter(|r| r.status == status).count();
+        json!({
+            "succeeded": count(OperationStatus::Ok),
+            "failed": count(OperationStatus::Error),
+            "skipped": count(OperationStatus::Skipped),
+            "stages": plan
+                .stages
+                .iter()
+                .map(|stage| stage.iter().map(|&i| plan.operations[i].id()).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            "results": results,
+        })
+    }
+
+    /// Invoke a tool by name with JSON arguments
+    async fn call_tool_by_name(
+        &self,
+        tool: &str,
+        args: &serde_json::Map<String, Value>,
+    ) -> std::result::Result<Value, String> {
+        match tool {
+            "control_lights" => {
+                self.control_lights(
+                    arg(args, "scope")?,
+                    arg(args, "target")?,
+                    arg(args, "action")?,
+                    arg(args, "brightness")?,
+                    arg(args, "confirm")?,
+                )
+                .await
+            }
+            "get_lights_status" => self.get_lights_status().await,
+            "set_light_color" => {
+                self.set_light_color(
+                    arg(args, "light")?,
+                    arg(args, "rgb")?,
+                    arg(args, "hue")?,
+                    arg(args, "saturation")?,
+                    arg(args, "kelvin")?,
+                    arg(args, "brightness")?,
+                )
+                .await
+            }
+            "get_timers" => self.get_timers(arg(args, "room")?).await,
+            "start_timer" => {
+                self.start_timer(arg(args, "device")?, arg(args, "duration_seconds")?)
+                    .await
+            }
+            "cancel_timer" => self.cancel_timer(arg(args, "device")?).await,
+            "set_temperature" => {
+                self.set_temperature(
+                    arg(args, "room")?,
+                    arg(args, "temperature")?,
+                    arg(args, "mode")?,
+                )
+                .await
+            }
+            "get_climate_status" => self.get_climate_status().await,
+            "get_valve_diagnostics" => self.get_valve_diagnostics(arg(args, "room")?).await,
+            "get_heating_zones" => self.get_heating_zones(arg(args, "room")?).await,
+            "get_climate_schedule" => self.get_climate_schedule(arg(args, "room")?).await,
+            "set_climate_schedule" => {
+                self.set_climate_schedule(
+                    arg(args, "room")?,
+                    arg(args, "entries")?,
+                    arg(args, "mode_id")?,
+                )
+                .await
+            }
+            "get_hot_water_status" => self.get_hot_water_status().await,
+            "boost_hot_water" => {
+                self.boost_hot_water(arg(args, "unit")?, arg(args, "minutes")?)
+                    .await
+            }
+            "set_hot_water_temperature" => {
+                self.set_hot_water_temperature(arg(args, "unit")?, arg(args, "temperature")?)
+                    .await
+            }
+            "set_hot_water_schedule" => {
+                self.set_hot_water_schedule(
+                    arg(args, "unit")?,
+                    arg(args, "entries")?,
+                    arg(args, "mode_id")?,
+                )
+                .await
+            }
+            "get_ac_status" => self.get_ac_status(arg(args, "unit")?).await,
+            "set_ac" => {
+                self.set_ac(
+                    arg(args, "unit")?,
+                    arg(args, "mode")?,
+                    arg(args, "fan_speed")?,
+                    arg(args, "temperature")?,
+                )
+                .await
+            }
+            "get_irrigation_status" => self.get_irrigation_status(arg(args, "controller")?).await,
+            "control_irrigation" => {
+                self.control_irrigation(
+                    arg(args, "action")?,
+                    arg(args, "zone")?,
+                    arg(args, "controller")?,
+                )
+                .await
+            }
+            "set_irrigation_duration" => {
+                self.set_irrigation_duration(
+                    arg(args, "zone")?,
+                    arg(args, "minutes")?,
+                    arg(args, "controller")?,
+                )
+                .await
+            }
+            "get_pool_status" => self.get_pool_status(arg(args, "pool")?).await,
+            "control_pool" => {
+                self.control_pool(arg(args, "action")?, arg(args, "pool")?)
+                    .await
+            }
+            "set_pool_temperature" => {
+                self.set_pool_temperature(arg(args, "temperature")?, arg(args, "pool")?)
+                    .await
+            }
+            "get_ventilation_status" => self.get_ventilation_status(arg(args, "unit")?).await,
+            "set_ventilation_mode" => {
+                self.set_ventilation_mode(
+                    arg(args, "action")?,
+                    arg(args, "stage")?,
+                    arg(args, "minutes")?,
+                    arg(args, "unit")?,
+                )
+                .await
+            }
+            "control_blinds" => {
+                self.control_blinds(
+                    arg(args, "target")?,
+                    arg(args, "action")?,
+                    arg(args, "position")?,
+                    arg(args, "confirm")?,
+                )
+                .await
+            }
+            "set_blind_position" => {
+                self.set_blind_position(
+                    arg(args, "target")?,
+                    arg(args, "position")?,
+                    arg(args, "slat_angle")?,
+                    arg(args, "confirm")?,
+                )
+                .await
+            }
+            "get_blinds_status" => self.get_blinds_status().await,
+            "get_storm_protection" => self.get_storm_protection(arg(args, "room")?).await,
+            "list_rooms" => self.list_rooms().await,
+            "list_devices" => {
+                self.list_devices(arg(args, "room")?, arg(args, "category")?)
+                    .await
+            }
+            "get_favorites" => self.get_favorites(arg(args, "room")?).await,
+            "set_favorite" => {
+                self.set_favorite(
+                    arg(args, "device")?,
+                    arg(args, "favorite")?,
+                    arg(args, "rating")?,
+                )
+                .await
+            }
+            "get_device_info" => self.get_device_info(arg(args, "device_id")?).await,
+            "lookup_device" => self.lookup_device(arg(args, "query")?).await,
+            "search_devices" => {
+                self.search_devices(
+                    arg(args, "query")?,
+                    arg(args, "room")?,
+                    arg(args, "category")?,
+                    arg(args, "limit")?,
+                )
+                .await
+            }
+            "diagnose_devices" => self.diagnose_devices(arg(args, "room")?).await,
+            "lock_device" => {
+                self.lock_device(arg(args, "device")?, arg(args, "reason")?)
+                    .await
+            }
+            "unlock_device" => self.unlock_device(arg(args, "device")?).await,
+            "execute_macro" => {
+                self.execute_macro(arg(args, "steps")?, arg(args, "rollback")?)
+                    .await
+            }
+            "get_all_states" => {
+                self.get_all_states(
+                    arg(args, "categories")?,
+                    arg(args, "rooms")?,
+                    arg(args, "offset")?,
+                    arg(args, "limit")?,
+                    arg(args, "units")?,
+                )
+                .await
+            }
+            "get_server_status" => self.get_server_status().await,
+            "get_system_topology" => self.get_system_topology().await,
+            "get_available_tools" => self.get_available_tools().await,
+            "get_tool_descriptions" => self.get_tool_descriptions(arg(args, "locale")?).await,
+            "set_operating_mode" => {
+                self.set_operating_mode(
+                    arg(args, "mode")?,
+                    arg(args, "action")?,
+                    arg(args, "from")?,
+                    arg(args, "until")?,
+                )
+                .await
+            }
+            "control_audio_zone" => {
+                self.control_audio_zone(arg(args, "zone")?, arg(args, "action")?)
+                    .await
+            }
+            "set_audio_volume" => {
+                self.set_audio_volume(arg(args, "zone")?, arg(args, "volume")?)
+                    .await
+            }
+            "get_audio_status" => self.get_audio_status().await,
+            "announce" => {
+                self.announce(
+                    arg(args, "text")?,
+                    arg(args, "zones")?,
+                    arg(args, "volume")?,
+                    arg(args, "zone_volumes")?,
+                    arg(args, "queue")?,
+                )
+                .await
+            }
+            "list_audio_zones" => self.list_audio_zones().await,
+            "select_audio_source" => {
+                self.select_audio_source(
+                    arg(args, "zone")?,
+                    arg(args, "source")?,
+                    arg(args, "favorite")?,
+                )
+                .await
+            }
+            "group_audio_zones" => {
+                self.group_audio_zones(
+                    arg(args, "action")?,
+                    arg(args, "zones")?,
+                    arg(args, "leader")?,
+                )
+                .await
+            }
+            "get_sensor_readings" => self.get_sensor_readings().await,
+            "get_door_window_status" => self.get_door_window_status().await,
+            "get_motion_status" => self.get_motion_status().await,
+            "get_device_statistics" => {
+                self.get_device_statistics(
+                    arg(args, "device")?,
+                    arg(args, "from")?,
+                    arg(args, "to")?,
+                    arg(args, "max_points")?,
+                )
+                .await
+            }
+            "get_device_batteries" => self.get_device_batteries(arg(args, "threshold")?).await,
+            "get_room_comfort" => self.get_room_comfort(arg(args, "room")?).await,
+            "get_text_states" => self.get_text_states(arg(args, "room")?).await,
+            "set_text_input" => {
+                self.set_text_input(arg(args, "input")?, arg(args, "text")?)
+                    .await
+            }
+            "get_tracker_entries" => {
+                self.get_tracker_entries(
+                    arg(args, "tracker")?,
+                    arg(args, "query")?,
+                    arg(args, "since")?,
+                    arg(args, "until")?,
+                    arg(args, "offset")?,
+                    arg(args, "limit")?,
+                )
+                .await
+            }
+            "list_virtual_inputs" => {
+                self.list_virtual_inputs(arg(args, "kind")?, arg(args, "room")?)
+                    .await
+            }
+            "set_virtual_input" => {
+                self.set_virtual_input(arg(args, "input")?, arg(args, "value")?)
+                    .await
+            }
+            "trigger_virtual_input" => {
+                self.trigger_virtual_input(arg(args, "input")?, arg(args, "value")?)
+                    .await
+            }
+            "list_virtual_outputs" => self.list_virtual_outputs(arg(args, "room")?).await,
+            "set_virtual_output" => {
+                self.set_virtual_output(arg(args, "output")?, arg(args, "value")?)
+                    .await
+            }
+            "get_presence_report" => self.get_presence_report(arg(args, "room")?).await,
+            "get_device_usage" => {
+                self.get_device_usage(arg(args, "room")?, arg(args, "flagged_only")?)
+                    .await
+            }
+            "diff_states" => {
+                self.diff_states(
+                    arg(args, "since")?,
+                    arg(args, "snapshot")?,
+                    arg(args, "room")?,
+                    arg(args, "include_reverted")?,
+                )
+                .await
+            }
+            "get_connected_clients" => self.get_connected_clients().await,
+            "get_sampling_usage" => self.get_sampling_usage().await,
+            "create_guest_access" => {
+                self.create_guest_access(
+                    arg(args, "name")?,
+                    arg(args, "rooms")?,
+                    arg(args, "category")?,
+                    arg(args, "hours")?,
+                    arg(args, "read_only")?,
+                    arg(args, "server_url")?,
+                )
+                .await
+            }
+            "get_command_history" => {
+                self.get_command_history(
+                    arg(args, "device")?,
+                    arg(args, "tool")?,
+                    arg(args, "key")?,
+                    arg(args, "since")?,
+                    arg(args, "success")?,
+                    arg(args, "limit")?,
+                )
+                .await
+            }
+            "get_program_backups" => self.get_program_backups(arg(args, "run_now")?).await,
+            "diff_structure" => self.diff_structure(arg(args, "against")?).await,
+            "get_miniserver_log" => {
+                self.get_miniserver_log(
+                    arg(args, "severity")?,
+                    arg(args, "since_minutes")?,
+                    arg(args, "limit")?,
+                    arg(args, "file")?,
+                )
+                .await
+            }
+            "get_weather" => self.get_weather().await,
+            "get_weather_forecast" => {
+                self.get_weather_forecast(arg(args, "hours")?, arg(args, "days")?)
+                    .await
+            }
+            "get_weather_protection" => self.get_weather_protection().await,
+            "override_weather_protection" => {
+                self.override_weather_protection(
+                    arg(args, "device")?,
+                    arg(args, "minutes")?,
+                    arg(args, "clear")?,
+                )
+                .await
+            }
+            "get_energy_status" => self.get_energy_status().await,
+            "get_energy_flow" => self.get_energy_flow().await,
+            "get_wallbox_status" => self.get_wallbox_status(arg(args, "charger")?).await,
+            "plan_heavy_load" => {
+                self.plan_heavy_load(
+                    arg(args, "tariff")?,
+                    arg(args, "duration_minutes")?,
+                    arg(args, "power_kw")?,
+                    arg(args, "window_hours")?,
+                    arg(args, "virtual_input")?,
+                    arg(args, "value")?,
+                )
+                .await
+            }
+            "get_load_manager_status" => self.get_load_manager_status(arg(args, "manager")?).await,
+            "control_load_shedding" => {
+                self.control_load_shedding(
+                    arg(args, "action")?,
+                    arg(args, "consumer")?,
+                    arg(args, "manager")?,
+                )
+                .await
+            }
+            "set_charging_current" => {
+                self.set_charging_current(arg(args, "amps")?, arg(args, "charger")?)
+                    .await
+            }
+            "control_ev_charging" => {
+                self.control_ev_charging(
+                    arg(args, "charger")?,
+                    arg(args, "action")?,
+                    arg(args, "limit_kwh")?,
+                )
+                .await
+            }
+            "schedule_workflow" => {
+                self.schedule_workflow(
+                    arg(args, "name")?,
+                    arg(args, "steps")?,
+                    arg(args, "min_pv_surplus_w")?,
+                    arg(args, "max_delay_minutes")?,
+                )
+                .await
+            }
+            "get_scheduled_workflows" => self.get_scheduled_workflows(arg(args, "cancel")?).await,
+            "get_security_status" => self.get_security_status().await,
+            "set_security_mode" => {
+                self.set_security_mode(arg(args, "mode")?, arg(args, "code")?)
+                    .await
+            }
+            "get_alarm_status" => self.get_alarm_status(arg(args, "alarm")?).await,
+            "control_alarm" => {
+                self.control_alarm(arg(args, "action")?, arg(args, "alarm")?)
+                    .await
+            }
+            "get_safety_status" => self.get_safety_status(arg(args, "device")?).await,
+            "test_safety_alarm" => self.test_safety_alarm(arg(args, "device")?).await,
+            "control_door_lock" => {
+                self.control_door_lock(arg(args, "lock")?, arg(args, "action")?)
+                    .await
+            }
+            "get_gate_status" => {
+                self.get_gate_status(arg(args, "gate")?, arg(args, "room")?)
+                    .await
+            }
+            "control_gate" => {
+                self.control_gate(
+                    arg(args, "gate")?,
+                    arg(args, "action")?,
+                    arg(args, "confirm")?,
+                )
+                .await
+            }
+            "get_camera_status" => self.get_camera_status().await,
+            "control_intercom" => {
+                self.control_intercom(arg(args, "intercom")?, arg(args, "action")?)
+                    .await
+            }
+            "get_intercom_history" => {
+                self.get_intercom_history(arg(args, "intercom")?, arg(args, "limit")?)
+                    .await
+            }
+            "open_intercom_door" => {
+                self.open_intercom_door(arg(args, "intercom")?, arg(args, "output")?)
+                    .await
+            }
+            "list_access_codes" => self.list_access_codes(arg(args, "device")?).await,
+            "add_access_code" => {
+                self.add_access_code(
+                    arg(args, "device")?,
+                    arg(args, "name")?,
+                    arg(args, "kind")?,
+                    arg(args, "secret")?,
+                    arg(args, "outputs")?,
+                    arg(args, "valid_from")?,
+                    arg(args, "valid_until")?,
+                )
+                .await
+            }
+            "revoke_access_code" => {
+                self.revoke_access_code(arg(args, "device")?, arg(args, "code_id")?)
+                    .await
+            }
+            "activate_scene" => {
+                self.activate_scene(arg(args, "scene")?, arg(args, "room")?)
+                    .await
+            }
+            "room_all_off" => {
+                self.room_all_off(arg(args, "room")?, arg(args, "blinds_down")?)
+                    .await
+            }
+            "house_good_night" => self.house_good_night().await,
+            "list_scenes" => self.list_scenes().await,
+            "control_light_moods" => {
+                self.control_light_moods(
+                    arg(args, "action")?,
+                    arg(args, "mood")?,
+                    arg(args, "room")?,
+                    arg(args, "controller")?,
+                )
+                .await
+            }
+            "capture_scene_snapshot" => {
+                self.capture_scene_snapshot(arg(args, "room")?, arg(args, "name")?)
+                    .await
+            }
+            "restore_scene_snapshot" => self.restore_scene_snapshot(arg(args, "name")?).await,
+            "list_scene_snapshots" => self.list_scene_snapshots(arg(args, "room")?).await,
+            "delete_scene_snapshot" => self.delete_scene_snapshot(arg(args, "name")?).await,
+            other => Err(format!("Tool '{other}' cannot be called from a batch")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_tool_is_batchable() {
+        let source = include_str!("macro_backend.rs");
+        let tools: Vec<&str> = source
+            .split("self.run_tool")
+            .skip(1)
+            .filter_map(|rest| {
+                let rest = rest.trim_start_matches("_with").strip_prefix("(\"")?;
+                rest.split('"').next()
+            })
+            .filter(|tool| *tool != BATCH_TOOL)
+            .collect();
+        for tool in &tools {
+            assert!(tool_params(tool).is_some(), "{tool} missing in TOOL_PARAMS");
+        }
+        assert_eq!(TOOL_PARAMS.len(), tools.len());
+    }
+
+    #[test]
+    fn test_plan_stages() {
+        let plan = BatchPlan::new(vec![
+            json!({"id": "music", "tool": "control_audio_zone", "args": {"zone": "Living", "action": "play"}, "depends_on": ["dim"]}),
+            json!({"id": "dim", "tool": "control_lights", "args": {"scope": "room", "action": "dim"}}),
+            json!({"tool": "get_blinds_status"}),
+        ])
+        .unwrap();
+        assert_eq!(plan.stages, vec![vec![1, 2], vec![0]]);
+        assert_eq!(plan.operations[2].id(), "3");
+
+        let cycle = BatchPlan::new(vec![
+            json!({"id": "a", "tool": "list_rooms", "depends_on": ["b"]}),
+            json!({"id": "b", "tool": "list_rooms", "depends_on": ["a"]}),
+        ]);
+        assert!(cycle.unwrap_err().to_string().contains("cycle"));
+
+        let typo = BatchPlan::new(vec![json!({"tool": "list_devices", "args": {"rom": "x"}})]);
+        assert!(typo.unwrap_err().to_string().contains("no argument 'rom'"));
+
+        let nested = BatchPlan::new(vec![json!({"tool": "batch_execute"})]);
+        assert!(nested.is_err());
+    }
+}
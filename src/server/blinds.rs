@@ -0,0 +1,80 @@
+//! Jalousie position and slat handling
+//!
+//! Loxone's `Jalousie` block drives blinds, shutters, curtains and awnings;
+//! `details.animation` tells them apart (0 blinds with slats, 1 shutters,
+//! 2-5 curtains, 6 awning). Its `states` report `position` (0.0 up to 1.0
+//! down) and, for blinds, `shadePosition` (0.0 slats open to 1.0 closed).
+//!
+//! A blind is moved to a position with `ManualPosition/<percent>` and its
+//! slats are tilted with `ManualLamelle/<percent>`, both 0 (up/open) to
+//! 100 (down/closed).
+
+use crate::error::{LoxoneError, Result};
+use serde_json::Value;
+
+/// Control types handled as blinds
+pub const BLIND_CONTROL_TYPES: &[&str] = &["Jalousie", "Blinds", "Rolladen"];
+
+/// `details.animation` of blinds with tiltable slats
+const ANIMATION_BLINDS: i64 = 0;
+
+/// Whether a blind has slats that can be tilted
+///
+/// Controls without an `animation` detail are assumed to be blinds, which
+/// is the Jalousie block's default.
+pub fn has_slats(control: &Value) -> bool {
+    control
+        .pointer("/details/animation")
+        .and_then(Value::as_i64)
+        .is_none_or(|animation| animation == ANIMATION_BLINDS)
+}
+
+/// Command moving a blind to `percent` (0 up, 100 down)
+pub fn position_command(percent: u8) -> Result<String> {
+    check_percent("Position", percent)?;
+    Ok(format!("ManualPosition/{percent}"))
+}
+
+/// Command tilting the slats of `control` to `percent` (0 open, 100 closed)
+pub fn slat_command(control: &Value, percent: u8) -> Result<String> {
+    check_percent("Slat angle", percent)?;
+    if !has_slats(control) {
+        let name = control
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("This blind");
+        return Err(LoxoneError::invalid_input(format!(
+            "{name} has no tiltable slats (shutter, curtain or awning)"
+        )));
+    }
+    Ok(format!("ManualLamelle/{percent}"))
+}
+
+fn check_percent(name: &str, percent: u8) -> Result<()> {
+    if percent > 100 {
+        return Err(LoxoneError::invalid_input(format!(
+            "{name} must be between 0-100"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_commands() {
+        let blind = json!({"name": "Office", "type": "Jalousie", "details": {"animation": 0}});
+        let shutter = json!({"name": "Garage", "type": "Jalousie", "details": {"animation": 1}});
+
+        assert_eq!(position_command(40).unwrap(), "ManualPosition/40");
+        assert!(position_command(101).is_err());
+
+        assert_eq!(slat_command(&blind, 75).unwrap(), "ManualLamelle/75");
+        assert!(slat_command(&blind, 120).is_err());
+        assert!(slat_command(&shutter, 50).is_err());
+        assert!(has_slats(&json!({"type": "Jalousie"})));
+    }
+}
@@ -0,0 +1,338 @@
+//! Bulk state reads for dashboards
+//!
+//! `get_all_states` returns the current values of many devices in one call.
+//! The payload is columnar: one array per field, with rooms and categories
+//! stored once and referenced by index, which keeps a few hundred devices
+//! well below the size of the per-device JSON the other tools return.
+//!
+//! Devices are ordered by room, name and UUID so pages are stable between
+//! calls. A page ends at `limit` devices or once the payload reaches
+//! `max_bytes`, whichever comes first; `next_offset` continues it.
+
+use crate::client::LoxoneDevice;
+use crate::error::{LoxoneError, Result};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Devices per page unless the caller asks for fewer
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// Upper bound for `limit`
+pub const MAX_PAGE_SIZE: usize = 2000;
+
+/// Approximate payload size at which a page is cut
+pub const DEFAULT_MAX_BYTES: usize = 128 * 1024;
+
+/// Device categories of the client context
+pub const CATEGORIES: &[&str] = &[
+    "lights",
+    "blinds",
+    "climate",
+    "sensors",
+    "weather",
+    "security",
+    "access",
+    "intercom",
+    "irrigation",
+    "ventilation",
+    "energy",
+    "audio",
+    "other",
+];
+
+/// Current value of one device
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateValue {
+    /// Number if the value is numeric, else its text
+    pub value: Value,
+    pub unit: Option<String>,
+}
+
+/// Filter and page of a bulk state read
+#[derive(Debug, Clone)]
+pub struct StateQuery {
+    pub categories: Vec<String>,
+    pub rooms: Vec<String>,
+    pub offset: usize,
+    pub limit: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for StateQuery {
+    fn default() -> Self {
+        Self {
+            categories: Vec::new(),
+            rooms: Vec::new(),
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+}
+
+impl StateQuery {
+    /// Devices matching the filters, in page order
+    ///
+    /// Fails for unknown categories and for rooms without devices.
+    pub fn select<'a>(
+        &self,
+        devices: impl IntoIterator<Item = &'a LoxoneDevice>,
+    ) -> Result<Vec<&'a LoxoneDevice>> {
+        let devices: Vec<&LoxoneDevice> = devices.into_iter().collect();
+        if let Some(unknown) = self.categories.iter().find(|c| {
+            !CATEGORIES.iter().any(|known| known.eq_ignore_ascii_case(c))
+                && !devices.iter().any(|d| d.in_category(c))
+        }) {
+            let mut loxone_categories: Vec<&str> = devices
+                .iter()
+                .filter_map(|d| d.loxone_category.as_ref())
+                .map(|category| category.name.as_str())
+                .collect();
+            loxone_categories.sort_unstable();
+            loxone_categories.dedup();
+            let mut message = format!(
+                "Unknown category '{unknown}'; use one of {}",
+                CATEGORIES.join(", ")
+            );
+            if !loxone_categories.is_empty() {
+                message.push_str(&format!(
+                    " or a Loxone category ({})",
+                    loxone_categories.join(", ")
+                ));
+            }
+            return Err(LoxoneError::invalid_input(message));
+        }
+
+        let in_room = |device: &LoxoneDevice, wanted: &str| {
+            device
+                .room
+                .as_deref()
+                .is_some_and(|room| room.eq_ignore_ascii_case(wanted))
+        };
+        if let Some(missing) = self
+            .rooms
+            .iter()
+            .find(|room| !devices.iter().any(|d| in_room(d, room)))
+        {
+            return Err(LoxoneError::not_found(format!(
+                "Room '{missing}' not found"
+            )));
+        }
+
+        let mut selected: Vec<&LoxoneDevice> = devices
+            .into_iter()
+            .filter(|d| {
+                self.categories.is_empty() || self.categories.iter().any(|c| d.in_category(c))
+            })
+            .filter(|d| self.rooms.is_empty() || self.rooms.iter().any(|r| in_room(d, r)))
+            .collect();
+        selected.sort_by(|a, b| (&a.room, &a.name, &a.uuid).cmp(&(&b.room, &b.name, &b.uuid)));
+        Ok(selected)
+    }
+
+    /// Devices of the requested page, before size capping
+    pub fn page<'a>(&self, selected: &[&'a LoxoneDevice]) -> Vec<&'a LoxoneDevice> {
+        selected
+            .iter()
+            .skip(self.offset)
+            .take(self.limit.clamp(1, MAX_PAGE_SIZE))
+            .copied()
+            .collect()
+    }
+}
+
+/// Value and unit from a raw state when the resolver has none
+pub fn raw_state_value(raw: &Value) -> StateValue {
+    let value = match raw {
+        Value::Object(map) => map
+            .get("value")
+            .or_else(|| map.get("LL").and_then(|ll| ll.get("value")))
+            .cloned()
+            .unwrap_or(Value::Null),
+        other => other.clone(),
+    };
+    let value = match &value {
+        Value::String(text) => text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(|n| serde_json::Number::from_f64(n).map(Value::Number))
+            .unwrap_or(value),
+        _ => value,
+    };
+    StateValue { value, unit: None }
+}
+
+/// Columnar payload of `page`, cut at the query's byte limit
+///
+/// `total` is the number of matching devices across all pages.
+pub fn build_page(
+    query: &StateQuery,
+    page: &[&LoxoneDevice],
+    values: &HashMap<String, StateValue>,
+    total: usize,
+) -> Value {
+    let mut rooms: Vec<&str> = Vec::new();
+    let mut categories: Vec<&str> = Vec::new();
+    let mut uuid = Vec::new();
+    let mut name = Vec::new();
+    let mut room = Vec::new();
+    let mut category = Vec::new();
+    let mut value = Vec::new();
+    let mut unit = Vec::new();
+
+    let mut bytes = 0;
+    for device in page {
+        let state = values.get(&device.uuid).cloned().unwrap_or_default();
+        let row_bytes = device.uuid.len()
+            + device.name.len()
+            + state.value.to_string().len()
+            + state.unit.as_deref().map_or(4, str::len)
+            + 24;
+        if !uuid.is_empty() && bytes + row_bytes > query.max_bytes {
+            break;
+        }
+        bytes += row_bytes;
+
+        let room_name = device.room.as_deref().unwrap_or("");
+        room.push(intern(&mut rooms, room_name));
+        category.push(intern(&mut categories, &device.category));
+        uuid.push(device.uuid.as_str());
+        name.push(device.name.as_str());
+        value.push(state.value);
+        unit.push(state.unit);
+    }
+
+    let count = uuid.len();
+    let next_offset = (query.offset + count < total).then_some(query.offset + count);
+    json!({
+        "total": total,
+        "offset": query.offset,
+        "count": count,
+        "next_offset": next_offset,
+        "truncated": count < page.len(),
+        "rooms": rooms,
+        "categories": categories,
+        "columns": {
+            "uuid": uuid,
+            "name": name,
+            "room": room,
+            "category": category,
+            "value": value,
+            "unit": unit,
+        },
+    })
+}
+
+/// Index of `entry` in `table`, appending it if new
+fn intern<'a>(table: &mut Vec<&'a str>, entry: &'a str) -> usize {
+    table.iter().position(|e| *e == entry).unwrap_or_else(|| {
+        table.push(entry);
+        table.len() - 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(uuid: &str, name: &str, room: &str, category: &str) -> LoxoneDevice {
+        LoxoneDevice {
+            uuid: uuid.to_string(),
+            name: name.to_string(),
+            device_type: String::new(),
+            room: Some(room.to_string()),
+            states: HashMap::new(),
+            category: category.to_string(),
+            loxone_category: None,
+            sub_controls: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_columnar_pages() {
+        let devices = [
+            device("3", "Spots", "Kitchen", "lights"),
+            device("1", "Ceiling", "Kitchen", "lights"),
+            device("2", "Blind", "Kitchen", "blinds"),
+            device("4", "Floor", "Living", "lights"),
+        ];
+        let values = HashMap::from([
+            (
+                "1".to_string(),
+                StateValue {
+                    value: json!(1.0),
+                    unit: None,
+                },
+            ),
+            ("3".to_string(), raw_state_value(&json!({"value": "0.5"}))),
+        ]);
+
+        let query = StateQuery {
+            categories: vec!["LIGHTS".to_string()],
+            limit: 2,
+            ..StateQuery::default()
+        };
+        let selected = query.select(&devices).unwrap();
+        let payload = build_page(&query, &query.page(&selected), &values, selected.len());
+        assert_eq!(payload["columns"]["name"], json!(["Ceiling", "Spots"]));
+        assert_eq!(payload["columns"]["value"], json!([1.0, 0.5]));
+        assert_eq!(payload["rooms"], json!(["Kitchen"]));
+        assert_eq!(payload["next_offset"], json!(2));
+
+        let capped = StateQuery {
+            max_bytes: 1,
+            ..query.clone()
+        };
+        let payload = build_page(&capped, &capped.page(&selected), &values, selected.len());
+        assert_eq!(payload["count"], json!(1));
+        assert_eq!(payload["truncated"], json!(true));
+        assert_eq!(payload["next_offset"], json!(1));
+
+        let last = StateQuery {
+            offset: 2,
+            ..query.clone()
+        };
+        let payload = build_page(&last, &last.page(&selected), &values, selected.len());
+        assert_eq!(payload["columns"]["unit"], json!([null]));
+        assert_eq!(payload["next_offset"], Value::Null);
+
+        assert!(
+            StateQuery {
+                rooms: vec!["Attic".to_string()],
+                ..StateQuery::default()
+            }
+            .select(&devices)
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_filters_by_loxone_category() {
+        let mut pump = device("5", "Pump", "Garden", "other");
+        pump.loxone_category = Some(crate::client::LoxoneCategory {
+            uuid: "cat-pool".to_string(),
+            name: "Pool".to_string(),
+            icon: None,
+            color: None,
+            category_type: None,
+        });
+        let devices = [device("1", "Ceiling", "Kitchen", "lights"), pump];
+
+        let query = StateQuery {
+            categories: vec!["pool".to_string()],
+            ..StateQuery::default()
+        };
+        let selected = query.select(&devices).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "Pump");
+
+        let error = StateQuery {
+            categories: vec!["Sauna".to_string()],
+            ..StateQuery::default()
+        }
+        .select(&devices)
+        .unwrap_err();
+        assert!(error.to_string().contains("Loxone category (Pool)"));
+    }
+}
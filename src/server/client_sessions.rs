@@ -0,0 +1,246 @@
+//! Tracking of connected MCP clients
+//!
+//! The transports register every client session here: the stdio transport
+//! has exactly one client for the lifetime of the process, the HTTP gateway
+//! one per MCP session, and the TLS terminator, the socket-activation
+//! forwarder and the tunnel one per accepted connection. Tool calls update the activity of the session they
+//! run in. The registry backs the admin-only `loxone://system/clients`
+//! resource.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// URI of the connected clients resource
+pub const CLIENTS_RESOURCE_URI: &str = "loxone://system/clients";
+
+tokio::task_local! {
+    static CURRENT_SESSION: String;
+}
+
+/// Transport a client is connected through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionTransport {
+    Stdio,
+    Http,
+    StreamableHttp,
+    /// HTTPS connection accepted by the TLS terminator
+    Tls,
+    /// Connection accepted on a systemd-activated socket
+    SocketActivated,
+    /// Stream relayed through the outbound tunnel
+    Tunnel,
+}
+
+/// A connected client
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientSession {
+    pub id: String,
+    /// Client name, e.g. the API key label for HTTP clients
+    pub name: Option<String>,
+    pub transport: SessionTransport,
+    /// Remote address for network transports
+    pub peer: Option<String>,
+    /// Operations the client is allowed to perform
+    pub capabilities: Vec<String>,
+    /// Resource subscriptions held by the client
+    pub subscription_count: usize,
+    pub connected_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    /// Tool calls handled in this session
+    pub requests: u64,
+}
+
+/// Registry of connected client sessions
+#[derive(Default)]
+pub struct ClientSessionRegistry {
+    sessions: Mutex<HashMap<String, ClientSession>>,
+    next_id: AtomicU64,
+}
+
+impl ClientSessionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry shared by the transports of this process
+    pub fn global() -> &'static Arc<ClientSessionRegistry> {
+        static GLOBAL: OnceLock<Arc<ClientSessionRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(ClientSessionRegistry::new()))
+    }
+
+    /// Register a new session and return its id
+    pub fn open(&self, transport: SessionTransport, peer: Option<SocketAddr>) -> String {
+        let seq = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let id = format!("{}-{seq}", transport_prefix(transport));
+        let now = Utc::now();
+        let session = ClientSession {
+            id: id.clone(),
+            name: None,
+            transport,
+            peer: peer.map(|p| p.to_string()),
+            capabilities: Vec::new(),
+            subscription_count: 0,
+            connected_at: now,
+            last_activity: now,
+            requests: 0,
+        };
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.clone(), session);
+        id
+    }
+
+    /// Register a session that is closed when the guard drops
+    pub fn open_guarded(
+        self: &Arc<Self>,
+        transport: SessionTransport,
+        peer: Option<SocketAddr>,
+    ) -> SessionGuard {
+        SessionGuard {
+            id: self.open(transport, peer),
+            registry: self.clone(),
+        }
+    }
+
+    /// Set the name and capabilities of a session
+    pub fn identify(&self, id: &str, name: Option<String>, capabilities: Vec<String>) {
+        if let Some(session) = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(id)
+        {
+            session.name = name;
+            session.capabilities = capabilities;
+        }
+    }
+
+    /// Record a request handled in a session
+    pub fn touch(&self, id: &str) {
+        if let Some(session) = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(id)
+        {
+            session.requests += 1;
+            session.last_activity = Utc::now();
+        }
+    }
+
+    pub fn set_subscription_count(&self, id: &str, count: usize) {
+        if let Some(session) = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(id)
+        {
+            session.subscription_count = count;
+        }
+    }
+
+    pub fn close(&self, id: &str) {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id);
+    }
+
+    /// Connected sessions, most recently active first
+    pub fn sessions(&self) -> Vec<ClientSession> {
+        let mut sessions: Vec<_> = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_activity));
+        sessions
+    }
+
+    /// Run `future` as part of the session `id`
+    pub async fn scope<F: std::future::Future>(id: String, future: F) -> F::Output {
+        CURRENT_SESSION.scope(id, future).await
+    }
+
+    /// Session of the current task, if any
+    pub fn current() -> Option<String> {
+        CURRENT_SESSION.try_with(Clone::clone).ok()
+    }
+}
+
+fn transport_prefix(transport: SessionTransport) -> &'static str {
+    match transport {
+        SessionTransport::Stdio => "stdio",
+        SessionTransport::Http => "http",
+        SessionTransport::StreamableHttp => "streamable-http",
+        SessionTransport::Tls => "tls",
+        SessionTransport::SocketActivated => "activated",
+        SessionTransport::Tunnel => "tunnel",
+    }
+}
+
+/// Closes its session when dropped, e.g. when a connection ends
+pub struct SessionGuard {
+    id: String,
+    registry: Arc<ClientSessionRegistry>,
+}
+
+impl SessionGuard {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.registry.close(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sessions_lifecycle() {
+        let registry = Arc::new(ClientSessionRegistry::new());
+        let stdio = registry.open(SessionTransport::Stdio, None);
+        registry.identify(
+            &stdio,
+            Some("desktop".to_string()),
+            vec!["control".to_string()],
+        );
+
+        let peer: SocketAddr = "192.168.1.20:51234".parse().unwrap();
+        let guard = registry.open_guarded(SessionTransport::Tls, Some(peer));
+        registry.set_subscription_count(guard.id(), 2);
+        registry.touch(guard.id());
+        assert_eq!(
+            ClientSessionRegistry::scope(stdio.clone(), async { ClientSessionRegistry::current() })
+                .await,
+            Some(stdio.clone())
+        );
+
+        let sessions = registry.sessions();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, guard.id());
+        assert_eq!(sessions[0].peer.as_deref(), Some("192.168.1.20:51234"));
+        assert_eq!(
+            (sessions[0].requests, sessions[0].subscription_count),
+            (1, 2)
+        );
+        assert_eq!(sessions[1].name.as_deref(), Some("desktop"));
+
+        drop(guard);
+        assert_eq!(registry.sessions().len(), 1);
+        assert_eq!(ClientSessionRegistry::current(), None);
+    }
+}
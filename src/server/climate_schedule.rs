@@ -0,0 +1,265 @@
+//! Heating schedules of Intelligent Room Controllers
+//!
+//! An `IRoomControllerV2` keeps its schedule in an `IRCV2Daytimer`
+//! sub-control. The daytimer's `entriesAndDefaultValue` state holds the
+//! schedule as JSON:
+//!
+//! ```json
+//! {"defValue": 0, "entries": [{"mode": 3, "from": 360, "to": 480, "needActivate": 0, "value": 1}]}
+//! ```
+//!
+//! - `mode`: operating mode (day type) the entry applies to
+//! - `from` / `to`: minutes since midnight
+//! - `value`: temperature level, 0 eco, 1 comfort heating, 2 comfort cooling
+//!
+//! Outside the entries the controller holds eco. The daytimer's `set`
+//! command replaces the whole schedule, so updating one mode re-sends the
+//! entries of all other modes unchanged.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::hot_water;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Control types with a heating schedule
+pub const ROOM_CONTROLLER_TYPES: &[&str] = &[
+    "IRoomControllerV2",
+    "IRoomController",
+    "Intelligent Room Controller",
+];
+
+/// State of the daytimer holding the schedule
+const ENTRIES_STATE: &str = "entriesAndDefaultValue";
+
+/// Temperature level of a schedule period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComfortLevel {
+    Eco,
+    Comfort,
+    Cooling,
+}
+
+impl ComfortLevel {
+    /// Parse a level name (English or German)
+    pub fn parse(level: &str) -> Result<Self> {
+        match level.trim().to_lowercase().as_str() {
+            "eco" | "economy" | "absenk" | "absenkung" => Ok(Self::Eco),
+            "comfort" | "komfort" | "heating" | "heizen" => Ok(Self::Comfort),
+            "cooling" | "kühlen" | "kuehlen" => Ok(Self::Cooling),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid level '{other}'. Use: comfort, eco, cooling"
+            ))),
+        }
+    }
+
+    /// Daytimer value of the level
+    pub fn value(self) -> u32 {
+        match self {
+            Self::Eco => 0,
+            Self::Comfort => 1,
+            Self::Cooling => 2,
+        }
+    }
+
+    fn from_value(value: f64) -> Option<Self> {
+        match value as i64 {
+            0 => Some(Self::Eco),
+            1 => Some(Self::Comfort),
+            2 => Some(Self::Cooling),
+            _ => None,
+        }
+    }
+}
+
+/// One period of a heating schedule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedulePeriod {
+    /// Operating mode the period applies to
+    pub mode: u32,
+    /// Start in minutes since midnight
+    pub from: u32,
+    /// End in minutes since midnight
+    pub to: u32,
+    pub level: ComfortLevel,
+}
+
+impl SchedulePeriod {
+    /// Parse `HH:MM-HH:MM`, optionally followed by `@comfort`, `@eco` or
+    /// `@cooling` (comfort by default)
+    pub fn parse(mode: u32, entry: &str) -> Result<Self> {
+        let (period, level) = match entry.trim().split_once('@') {
+            Some((period, level)) => (period, ComfortLevel::parse(level)?),
+            None => (entry.trim(), ComfortLevel::Comfort),
+        };
+        let (from, to) = hot_water::parse_period(period).ok_or_else(|| {
+            LoxoneError::invalid_input(format!(
+                "Invalid schedule entry '{entry}'. Use HH:MM-HH:MM or HH:MM-HH:MM@eco"
+            ))
+        })?;
+        if from >= to {
+            return Err(LoxoneError::invalid_input(format!(
+                "Schedule entry '{entry}' must end after it starts"
+            )));
+        }
+        Ok(Self {
+            mode,
+            from,
+            to,
+            level,
+        })
+    }
+
+    /// Period as shown to users, e.g. `06:00-08:00`
+    pub fn time_range(&self) -> String {
+        format!("{}-{}", format_time(self.from), format_time(self.to))
+    }
+
+    /// JSON description for tool output
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "mode_id": self.mode,
+            "period": self.time_range(),
+            "level": self.level,
+        })
+    }
+}
+
+/// `HH:MM` of minutes since midnight
+fn format_time(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Periods from an `entriesAndDefaultValue` state value, sorted by mode and
+/// start; entries that cannot be read are skipped
+pub fn parse_schedule(value: &Value) -> Vec<SchedulePeriod> {
+    let schedule = match value {
+        Value::String(text) => serde_json::from_str(text).unwrap_or(Value::Null),
+        other => other.clone(),
+    };
+    let number = |entry: &Value, key: &str| entry.get(key).and_then(Value::as_f64);
+    let mut periods: Vec<SchedulePeriod> = schedule
+        .get("entries")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            Some(SchedulePeriod {
+                mode: number(entry, "mode")? as u32,
+                from: number(entry, "from")? as u32,
+                to: number(entry, "to")? as u32,
+                level: ComfortLevel::from_value(number(entry, "value")?)?,
+            })
+        })
+        .collect();
+    periods.sort_by_key(|p| (p.mode, p.from));
+    periods
+}
+
+/// Schedule with the periods of `mode` replaced by `periods`
+///
+/// Fails if two periods of `mode` overlap.
+pub fn replace_mode(
+    current: &[SchedulePeriod],
+    mode: u32,
+    periods: &[SchedulePeriod],
+) -> Result<Vec<SchedulePeriod>> {
+    let mut updated: Vec<SchedulePeriod> = periods.to_vec();
+    updated.sort_by_key(|p| p.from);
+    if let Some(pair) = updated.windows(2).find(|w| w[1].from < w[0].to) {
+        return Err(LoxoneError::invalid_input(format!(
+            "Schedule entries {} and {} overlap",
+            pair[0].time_range(),
+            pair[1].time_range()
+        )));
+    }
+    updated.extend(current.iter().filter(|p| p.mode != mode));
+    updated.sort_by_key(|p| (p.mode, p.from));
+    Ok(updated)
+}
+
+/// Daytimer command setting the whole schedule
+pub fn schedule_command(periods: &[SchedulePeriod]) -> String {
+    let entries: Vec<String> = periods
+        .iter()
+        .map(|p| format!("{};{};{};0;{}", p.mode, p.from, p.to, p.level.value()))
+        .collect();
+    format!("set/{}/{}", entries.len(), entries.join("/"))
+}
+
+/// Daytimer sub-control of a room controller and its schedule state UUID
+pub fn daytimer(control: &Value) -> Option<(&str, Option<&str>)> {
+    let uuid = hot_water::find_daytimer(control)?;
+    let state = control
+        .get("subControls")
+        .and_then(|subs| subs.get(uuid))
+        .and_then(|sub| sub.get("states"))
+        .and_then(|states| states.get(ENTRIES_STATE))
+        .and_then(Value::as_str);
+    Some((uuid, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_schedule_and_periods() {
+        let state = json!(
+            r#"{"defValue":0,"entries":[
+                {"mode":3,"from":1020,"to":1320,"needActivate":0,"value":1},
+                {"mode":3,"from":360,"to":480,"needActivate":0,"value":1},
+                {"mode":9,"from":480,"to":1380,"needActivate":0,"value":2},
+                {"mode":4,"from":0,"to":60,"value":7}
+            ]}"#
+        );
+        let periods = parse_schedule(&state);
+        assert_eq!(periods.len(), 3);
+        assert_eq!(periods[0].time_range(), "06:00-08:00");
+        assert_eq!(periods[2].level, ComfortLevel::Cooling);
+
+        let period = SchedulePeriod::parse(3, "21:30-24:00@eco").unwrap();
+        assert_eq!((period.from, period.to), (1290, 1440));
+        assert_eq!(period.level, ComfortLevel::Eco);
+        assert!(SchedulePeriod::parse(3, "08:00-06:00").is_err());
+        assert!(SchedulePeriod::parse(3, "06:00-08:00@party").is_err());
+    }
+
+    #[test]
+    fn test_replace_mode() {
+        let current = vec![
+            SchedulePeriod::parse(3, "06:00-08:00").unwrap(),
+            SchedulePeriod::parse(9, "08:00-23:00").unwrap(),
+        ];
+        let new = vec![
+            SchedulePeriod::parse(3, "17:00-22:00").unwrap(),
+            SchedulePeriod::parse(3, "05:30-07:00").unwrap(),
+        ];
+        let updated = replace_mode(&current, 3, &new).unwrap();
+        assert_eq!(
+            schedule_command(&updated),
+            "set/3/3;330;420;0;1/3;1020;1320;0;1/9;480;1380;0;1"
+        );
+
+        let overlapping = vec![
+            SchedulePeriod::parse(3, "06:00-08:00").unwrap(),
+            SchedulePeriod::parse(3, "07:30-09:00").unwrap(),
+        ];
+        assert!(replace_mode(&current, 3, &overlapping).is_err());
+        // Clearing a mode keeps the others
+        assert_eq!(replace_mode(&current, 3, &[]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_daytimer() {
+        let controller = json!({
+            "type": "IRoomControllerV2",
+            "subControls": {
+                "d-1": {"type": "IRCV2Daytimer", "states": {"entriesAndDefaultValue": "s-entries"}}
+            }
+        });
+        assert_eq!(daytimer(&controller), Some(("d-1", Some("s-entries"))));
+        assert_eq!(daytimer(&json!({"type": "IRoomControllerV2"})), None);
+    }
+}
@@ -0,0 +1,303 @@
+//! Access codes and NFC tags of Code Touch devices
+//!
+//! An `NfcCodeTouch` control stores the codes and tags that open its access
+//! outputs. It is managed with commands on the control:
+//!
+//! - `codes/get`: JSON list of entries, `{"uuid", "name", "type", "isActive",
+//!   "outputs", "timeFrom", "timeTo"}`; `type` is 0 for a keypad code and 1
+//!   for an NFC tag, `outputs` a bit mask of the access outputs and the
+//!   validity times are Unix seconds (0 = unlimited)
+//! - `codes/add/{type}/{name}/{outputs}/{secret}/{timeFrom}/{timeTo}`
+//! - `codes/delete/{uuid}`
+//!
+//! The secret (code digits or tag ID) is never returned by `codes/get` and
+//! is masked in the command history, see
+//! [`crate::services::command_history::redact`].
+
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Control types managed as Code Touch devices
+pub const CODE_TOUCH_TYPES: &[&str] = &["NfcCodeTouch"];
+
+/// Command listing the codes and tags of a device
+pub const LIST_COMMAND: &str = "codes/get";
+
+/// Number of access outputs of a Code Touch
+pub const ACCESS_OUTPUTS: u8 = 8;
+
+/// Length range of keypad codes
+const CODE_DIGITS: std::ops::RangeInclusive<usize> = 2..=8;
+
+/// Length range of NFC tag IDs in hex digits
+const TAG_HEX_DIGITS: std::ops::RangeInclusive<usize> = 8..=20;
+
+/// Longest entry name
+const MAX_NAME_LEN: usize = 40;
+
+/// What grants access
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessKind {
+    Code,
+    NfcTag,
+}
+
+impl AccessKind {
+    /// Parse a kind name
+    pub fn parse(kind: &str) -> Result<Self> {
+        match kind.trim().to_lowercase().as_str() {
+            "code" | "pin" | "keypad" => Ok(Self::Code),
+            "nfc" | "tag" | "nfc_tag" => Ok(Self::NfcTag),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid access kind '{other}'. Use: code, nfc_tag"
+            ))),
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Self::Code => 0,
+            Self::NfcTag => 1,
+        }
+    }
+}
+
+/// A code or tag stored on a device, without its secret
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccessEntry {
+    pub id: String,
+    pub name: String,
+    pub kind: AccessKind,
+    pub active: bool,
+    /// Access outputs the entry opens, 1-based
+    pub outputs: Vec<u8>,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+/// Parse the `codes/get` response, a JSON list or a text holding one
+pub fn parse_entries(value: &Value) -> Result<Vec<AccessEntry>> {
+    let parsed;
+    let list = match value {
+        Value::String(text) => {
+            parsed = serde_json::from_str::<Value>(text)
+                .map_err(|e| LoxoneError::parsing_error(format!("Unexpected code list: {e}")))?;
+            &parsed
+        }
+        other => other,
+    };
+    let entries = list
+        .as_array()
+        .ok_or_else(|| LoxoneError::parsing_error("Unexpected code list: not a list"))?;
+
+    let time = |entry: &Value, name: &str| {
+        entry
+            .get(name)
+            .and_then(Value::as_i64)
+            .filter(|seconds| *seconds > 0)
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+    };
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            let id = entry.get("uuid").and_then(Value::as_str)?.to_string();
+            let mask = entry.get("outputs").and_then(Value::as_u64).unwrap_or(0);
+            Some(AccessEntry {
+                name: entry
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or(&id)
+                    .to_string(),
+                kind: match entry.get("type").and_then(Value::as_u64) {
+                    Some(1) => AccessKind::NfcTag,
+                    _ => AccessKind::Code,
+                },
+                active: entry
+                    .get("isActive")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true),
+                outputs: (1..=ACCESS_OUTPUTS)
+                    .filter(|output| mask & (1 << (output - 1)) != 0)
+                    .collect(),
+                valid_from: time(entry, "timeFrom"),
+                valid_until: time(entry, "timeTo"),
+                id,
+            })
+        })
+        .collect())
+}
+
+/// A new code or tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAccess {
+    pub name: String,
+    pub kind: AccessKind,
+    /// Code digits or tag ID
+    pub secret: String,
+    /// Access outputs to open, 1-based; empty for the first output
+    pub outputs: Vec<u8>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+}
+
+impl NewAccess {
+    /// Validate the entry and build its `codes/add` command
+    ///
+    /// Validity times are taken as UTC.
+    pub fn add_command(&self) -> Result<String> {
+        let name: String = self
+            .name
+            .chars()
+            .filter(|c| !matches!(c, '/' | '?' | '#' | '%' | '\\') && !c.is_control())
+            .collect();
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(LoxoneError::invalid_input(format!(
+                "Name must be 1 to {MAX_NAME_LEN} characters"
+            )));
+        }
+
+        let secret = match self.kind {
+            AccessKind::Code => {
+                let code = self.secret.trim();
+                if !CODE_DIGITS.contains(&code.len()) || !code.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(LoxoneError::invalid_input(format!(
+                        "Code must be {} to {} digits",
+                        CODE_DIGITS.start(),
+                        CODE_DIGITS.end()
+                    )));
+                }
+                code.to_string()
+            }
+            AccessKind::NfcTag => {
+                let tag: String = self
+                    .secret
+                    .chars()
+                    .filter(|c| !matches!(c, ':' | ' ' | '-'))
+                    .collect::<String>()
+                    .to_uppercase();
+                if !TAG_HEX_DIGITS.contains(&tag.len())
+                    || !tag.chars().all(|c| c.is_ascii_hexdigit())
+                {
+                    return Err(LoxoneError::invalid_input(format!(
+                        "NFC tag ID must be {} to {} hex digits",
+                        TAG_HEX_DIGITS.start(),
+                        TAG_HEX_DIGITS.end()
+                    )));
+                }
+                tag
+            }
+        };
+
+        let mut mask: u32 = 0;
+        for output in &self.outputs {
+            if !(1..=ACCESS_OUTPUTS).contains(output) {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Access output must be between 1 and {ACCESS_OUTPUTS}"
+                )));
+            }
+            mask |= 1 << (output - 1);
+        }
+        if mask == 0 {
+            mask = 1;
+        }
+
+        let seconds = |time: Option<NaiveDateTime>| time.map_or(0, |t| t.and_utc().timestamp());
+        if let (Some(from), Some(until)) = (self.valid_from, self.valid_until)
+            && from >= until
+        {
+            return Err(LoxoneError::invalid_input(
+                "Validity must end after it starts",
+            ));
+        }
+
+        Ok(format!(
+            "codes/add/{}/{}/{mask}/{secret}/{}/{}",
+            self.kind.id(),
+            urlencoding::encode(name),
+            seconds(self.valid_from),
+            seconds(self.valid_until)
+        ))
+    }
+}
+
+/// Command removing the entry `id`
+pub fn delete_command(id: &str) -> Result<String> {
+    let id = id.trim();
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(LoxoneError::invalid_input(format!(
+            "Invalid code ID '{id}'"
+        )));
+    }
+    Ok(format!("codes/delete/{id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn new_access(kind: AccessKind, secret: &str) -> NewAccess {
+        NewAccess {
+            name: "Cleaner/Anna".to_string(),
+            kind,
+            secret: secret.to_string(),
+            outputs: vec![1, 3],
+            valid_from: None,
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let entries = parse_entries(&json!(
+            r#"[{"uuid": "1a2b-01", "name": "Anna", "type": 0, "isActive": true,
+                 "outputs": 5, "timeFrom": 0, "timeTo": 1767225600},
+                {"uuid": "1a2b-02", "type": 1, "isActive": false, "outputs": 1},
+                {"name": "no id"}]"#
+        ))
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].outputs, vec![1, 3]);
+        assert_eq!(entries[0].valid_from, None);
+        assert_eq!(
+            entries[0].valid_until.unwrap().to_rfc3339(),
+            "2026-01-01T00:00:00+00:00"
+        );
+        assert_eq!(entries[1].kind, AccessKind::NfcTag);
+        assert_eq!(entries[1].name, "1a2b-02");
+        assert!(!entries[1].active);
+        assert!(parse_entries(&json!({"error": 1})).is_err());
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(
+            new_access(AccessKind::Code, "4711").add_command().unwrap(),
+            "codes/add/0/CleanerAnna/5/4711/0/0"
+        );
+        assert_eq!(
+            new_access(AccessKind::NfcTag, "04:a2:1b:3c:5d:80")
+                .add_command()
+                .unwrap(),
+            "codes/add/1/CleanerAnna/5/04A21B3C5D80/0/0"
+        );
+        assert!(new_access(AccessKind::Code, "12ab").add_command().is_err());
+        assert!(
+            new_access(AccessKind::NfcTag, "04:a2")
+                .add_command()
+                .is_err()
+        );
+
+        let mut bad_output = new_access(AccessKind::Code, "4711");
+        bad_output.outputs = vec![9];
+        assert!(bad_output.add_command().is_err());
+
+        assert_eq!(delete_command("1a2b-01").unwrap(), "codes/delete/1a2b-01");
+        assert!(delete_command("../x").is_err());
+        assert_eq!(AccessKind::parse("NFC").unwrap(), AccessKind::NfcTag);
+    }
+}
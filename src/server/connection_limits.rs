@@ -0,0 +1,453 @@
+//! Connection limits and socket-level metrics for the HTTP transports
+//!
+//! A misbehaving client that opens hundreds of connections can exhaust the
+//! file descriptors and memory of a small host. With a limit configured, the
+//! public port is served by a forwarder that admits connections before
+//! handing them to the framework transport on loopback (the same way socket
+//! activation does), and the TLS terminator and socket-activation forwarder
+//! check the same limits.
+//!
+//! Connections over the limit get a minimal `503` (total limit) or `429`
+//! (per-IP limit) response and are closed. The limiter also counts accepted
+//! and rejected connections, accept errors and connections waiting for the
+//! upstream transport (the accept queue); the counts are published to the
+//! metrics collector every [`PUBLISH_INTERVAL`].
+
+use crate::error::{LoxoneError, Result};
+use crate::monitoring::metrics::{MetricsCollector, get_metrics};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Interval between metric updates
+pub const PUBLISH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Connection limits of the HTTP transports (`None`: unlimited)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionLimitsConfig {
+    /// Open connections across all clients
+    pub max_connections: Option<usize>,
+    /// Open connections from one IP address
+    pub max_per_ip: Option<usize>,
+}
+
+impl ConnectionLimitsConfig {
+    /// Whether any limit is set
+    pub fn is_limited(&self) -> bool {
+        self.max_connections.is_some() || self.max_per_ip.is_some()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.max_connections == Some(0) || self.max_per_ip == Some(0) {
+            return Err(LoxoneError::config("Connection limits must be at least 1"));
+        }
+        if let (Some(total), Some(per_ip)) = (self.max_connections, self.max_per_ip)
+            && per_ip > total
+        {
+            return Err(LoxoneError::config(format!(
+                "Per-IP connection limit ({per_ip}) exceeds the total limit ({total})"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Why a connection was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// `max_connections` reached
+    ServerFull,
+    /// `max_per_ip` reached for the peer
+    PeerLimit,
+}
+
+impl Rejection {
+    /// Response written to a refused plain HTTP connection
+    pub fn http_response(self) -> &'static [u8] {
+        match self {
+            Self::ServerFull => {
+                b"HTTP/1.1 503 Service Unavailable\r\nRetry-After: 5\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            }
+            Self::PeerLimit => {
+                b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 5\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct OpenConnections {
+    total: usize,
+    peak: usize,
+    pending: usize,
+    per_ip: HashMap<IpAddr, usize>,
+}
+
+/// Connection counts and totals since start
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConnectionStats {
+    pub active: usize,
+    pub peak: usize,
+    /// Accepted connections not yet connected to the transport
+    pub pending: usize,
+    pub peers: usize,
+    pub accepted_total: u64,
+    pub rejected_total: u64,
+    pub rejected_per_ip_total: u64,
+    pub accept_errors_total: u64,
+    /// Mean time from accept to upstream connect
+    pub accept_wait_ms: f64,
+    pub limits: ConnectionLimitsConfig,
+}
+
+/// Admits connections within the configured limits
+#[derive(Debug, Default)]
+pub struct ConnectionLimiter {
+    limits: Mutex<ConnectionLimitsConfig>,
+    open: Mutex<OpenConnections>,
+    accepted: AtomicU64,
+    rejected_total: AtomicU64,
+    rejected_per_ip: AtomicU64,
+    accept_errors: AtomicU64,
+    waited: AtomicU64,
+    wait_micros: AtomicU64,
+    /// Totals already added to the collector's counters
+    published: Mutex<(u64, u64, u64)>,
+}
+
+impl ConnectionLimiter {
+    pub fn new(limits: ConnectionLimitsConfig) -> Self {
+        Self {
+            limits: Mutex::new(limits),
+            ..Default::default()
+        }
+    }
+
+    /// Limiter shared by all listeners of the process
+    pub fn global() -> &'static Arc<ConnectionLimiter> {
+        static GLOBAL: OnceLock<Arc<ConnectionLimiter>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(ConnectionLimiter::default()))
+    }
+
+    /// Replace the limits; open connections are kept
+    pub fn configure(&self, limits: ConnectionLimitsConfig) {
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+    }
+
+    pub fn limits(&self) -> ConnectionLimitsConfig {
+        self.limits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Admit a connection from `peer`; the permit releases it when dropped
+    pub fn admit(
+        self: &Arc<Self>,
+        peer: SocketAddr,
+    ) -> std::result::Result<ConnectionPermit, Rejection> {
+        let limits = self.limits();
+        let ip = peer.ip();
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        let from_peer = open.per_ip.get(&ip).copied().unwrap_or(0);
+        let rejection = if limits.max_per_ip.is_some_and(|max| from_peer >= max) {
+            Some(Rejection::PeerLimit)
+        } else if limits.max_connections.is_some_and(|max| open.total >= max) {
+            Some(Rejection::ServerFull)
+        } else {
+            None
+        };
+        if let Some(rejection) = rejection {
+            self.rejected_total.fetch_add(1, Ordering::Relaxed);
+            if rejection == Rejection::PeerLimit {
+                self.rejected_per_ip.fetch_add(1, Ordering::Relaxed);
+            }
+            return Err(rejection);
+        }
+        open.total += 1;
+        open.peak = open.peak.max(open.total);
+        open.pending += 1;
+        *open.per_ip.entry(ip).or_default() += 1;
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+        Ok(ConnectionPermit {
+            limiter: self.clone(),
+            ip,
+            accepted_at: Instant::now(),
+            pending: true,
+        })
+    }
+
+    /// Count a failed `accept()`
+    pub fn record_accept_error(&self) {
+        self.accept_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> ConnectionStats {
+        let open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        let waited = self.waited.load(Ordering::Relaxed);
+        ConnectionStats {
+            active: open.total,
+            peak: open.peak,
+            pending: open.pending,
+            peers: open.per_ip.len(),
+            accepted_total: self.accepted.load(Ordering::Relaxed),
+            rejected_total: self.rejected_total.load(Ordering::Relaxed),
+            rejected_per_ip_total: self.rejected_per_ip.load(Ordering::Relaxed),
+            accept_errors_total: self.accept_errors.load(Ordering::Relaxed),
+            accept_wait_ms: if waited == 0 {
+                0.0
+            } else {
+                self.wait_micros.load(Ordering::Relaxed) as f64 / waited as f64 / 1000.0
+            },
+            limits: self.limits(),
+        }
+    }
+
+    /// Write the current counts to the metrics collector
+    pub async fn publish(&self, metrics: &MetricsCollector) {
+        let stats = self.stats();
+        let totals = (
+            stats.accepted_total,
+            stats.rejected_total,
+            stats.accept_errors_total,
+        );
+        let previous = std::mem::replace(
+            &mut *self.published.lock().unwrap_or_else(|e| e.into_inner()),
+            totals,
+        );
+        metrics
+            .increment_counter("http_connections_accepted_total", totals.0 - previous.0)
+            .await;
+        metrics
+            .increment_counter("http_connections_rejected_total", totals.1 - previous.1)
+            .await;
+        metrics
+            .increment_counter("http_accept_errors_total", totals.2 - previous.2)
+            .await;
+        metrics
+            .set_gauge("http_connections_active", stats.active as f64)
+            .await;
+        metrics
+            .set_gauge("http_connections_accept_pending", stats.pending as f64)
+            .await;
+        metrics
+            .set_gauge("http_connections_accept_wait_ms", stats.accept_wait_ms)
+            .await;
+    }
+
+    /// Publish the counts to the global collector every [`PUBLISH_INTERVAL`]
+    pub fn start_publishing(self: &Arc<Self>) -> JoinHandle<()> {
+        let limiter = self.clone();
+        tokio::spawn(async move {
+            let metrics = get_metrics();
+            let mut ticker = tokio::time::interval(PUBLISH_INTERVAL);
+            loop {
+                ticker.tick().await;
+                limiter.publish(&metrics).await;
+            }
+        })
+    }
+
+    fn release(&self, ip: IpAddr, pending: bool) {
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        open.total = open.total.saturating_sub(1);
+        if pending {
+            open.pending = open.pending.saturating_sub(1);
+        }
+        if let Some(count) = open.per_ip.get_mut(&ip) {
+            *count -= 1;
+            if *count == 0 {
+                open.per_ip.remove(&ip);
+            }
+        }
+    }
+}
+
+/// An admitted connection
+#[derive(Debug)]
+pub struct ConnectionPermit {
+    limiter: Arc<ConnectionLimiter>,
+    ip: IpAddr,
+    accepted_at: Instant,
+    pending: bool,
+}
+
+impl ConnectionPermit {
+    /// Mark the connection as handed to the transport, leaving the accept queue
+    pub fn upstream_connected(&mut self) {
+        if !std::mem::take(&mut self.pending) {
+            return;
+        }
+        let limiter = &self.limiter;
+        limiter.waited.fetch_add(1, Ordering::Relaxed);
+        limiter.wait_micros.fetch_add(
+            self.accepted_at.elapsed().as_micros() as u64,
+            Ordering::Relaxed,
+        );
+        let mut open = limiter.open.lock().unwrap_or_else(|e| e.into_inner());
+        open.pending = open.pending.saturating_sub(1);
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.limiter.release(self.ip, self.pending);
+    }
+}
+
+/// Serve `listen_addr`, forwarding admitted connections to the transport on
+/// loopback `upstream_port`
+pub async fn spawn_forwarder(
+    listen_addr: SocketAddr,
+    upstream_port: u16,
+    limiter: Arc<ConnectionLimiter>,
+) -> Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(listen_addr).await.map_err(|e| {
+        LoxoneError::config(format!(
+            "Failed to bind HTTP listener on {listen_addr}: {e}"
+        ))
+    })?;
+    let upstream = SocketAddr::from(([127, 0, 0, 1], upstream_port));
+    let limits = limiter.limits();
+    info!(
+        "🚦 Connection limits on {listen_addr}: {} total, {} per IP",
+        limits
+            .max_connections
+            .map_or("unlimited".to_string(), |n| n.to_string()),
+        limits
+            .max_per_ip
+            .map_or("unlimited".to_string(), |n| n.to_string())
+    );
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let (inbound, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    limiter.record_accept_error();
+                    warn!("HTTP listener accept failed: {e}");
+                    continue;
+                }
+            };
+            match limiter.admit(peer) {
+                Ok(permit) => {
+                    tokio::spawn(forward(inbound, upstream, permit));
+                }
+                Err(rejection) => {
+                    debug!("Refusing connection from {peer}: {rejection:?}");
+                    tokio::spawn(refuse(inbound, rejection));
+                }
+            }
+        }
+    }))
+}
+
+/// Pipe an admitted connection to the upstream transport
+pub async fn forward(mut inbound: TcpStream, upstream: SocketAddr, mut permit: ConnectionPermit) {
+    let result = async {
+        let mut outbound = TcpStream::connect(upstream).await?;
+        permit.upstream_connected();
+        outbound.set_nodelay(true)?;
+        tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await
+    }
+    .await;
+    if let Err(e) = result {
+        debug!("Connection from {} closed: {e}", permit.ip);
+    }
+}
+
+/// Answer a refused plain HTTP connection and close it
+pub async fn refuse(mut inbound: TcpStream, rejection: Rejection) {
+    let _ = inbound.write_all(rejection.http_response()).await;
+    let _ = inbound.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn test_admit_enforces_total_and_per_ip_limits() {
+        let limiter = Arc::new(ConnectionLimiter::new(ConnectionLimitsConfig {
+            max_connections: Some(3),
+            max_per_ip: Some(2),
+        }));
+        let a: SocketAddr = "192.168.1.10:5000".parse().unwrap();
+        let b: SocketAddr = "192.168.1.11:5000".parse().unwrap();
+        let c: SocketAddr = "192.168.1.12:5000".parse().unwrap();
+
+        let mut first = limiter.admit(a).unwrap();
+        let _second = limiter.admit(a).unwrap();
+        assert_eq!(limiter.admit(a).unwrap_err(), Rejection::PeerLimit);
+        let _third = limiter.admit(b).unwrap();
+        assert_eq!(limiter.admit(c).unwrap_err(), Rejection::ServerFull);
+
+        first.upstream_connected();
+        let stats = limiter.stats();
+        assert_eq!((stats.active, stats.pending, stats.peers), (3, 2, 2));
+        assert_eq!((stats.rejected_total, stats.rejected_per_ip_total), (2, 1));
+
+        drop(first);
+        assert!(limiter.admit(c).is_ok());
+        assert_eq!(limiter.stats().peak, 3);
+        assert!(
+            ConnectionLimitsConfig {
+                max_connections: Some(1),
+                max_per_ip: Some(2),
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forwarder_refuses_connections_over_the_limit() {
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_port = upstream.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = upstream.accept().await {
+                tokio::spawn(async move {
+                    let _ = stream.write_all(b"upstream").await;
+                    let mut buf = [0; 16];
+                    let _ = stream.read(&mut buf).await;
+                });
+            }
+        });
+
+        let port = crate::server::systemd::free_loopback_port().unwrap();
+        let limiter = Arc::new(ConnectionLimiter::new(ConnectionLimitsConfig {
+            max_connections: None,
+            max_per_ip: Some(1),
+        }));
+        spawn_forwarder(
+            ([127, 0, 0, 1], port).into(),
+            upstream_port,
+            limiter.clone(),
+        )
+        .await
+        .unwrap();
+
+        let mut admitted = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let mut buf = [0; 8];
+        admitted.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"upstream");
+
+        let mut refused = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let mut response = String::new();
+        refused.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 429"));
+
+        let stats = limiter.stats();
+        assert_eq!((stats.active, stats.rejected_per_ip_total), (1, 1));
+        assert!(stats.accept_wait_ms > 0.0);
+    }
+}
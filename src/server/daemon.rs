@@ -0,0 +1,242 @@
+//! Background (daemon) mode for hosts without a service manager
+//!
+//! `--daemon` detaches the server from the terminal, writes a PID file and
+//! redirects stdout/stderr (and therefore all log output) to a log file. The
+//! `stop` and `status` subcommands use the PID file to find the running
+//! instance. Detaching forks the process, so it must happen before the Tokio
+//! runtime or any other thread is started.
+//!
+//! Daemon mode is only available on Unix with the `daemon` feature. Hosts with
+//! systemd should prefer the generated unit files instead.
+
+use crate::error::{LoxoneError, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File locations used by daemon mode
+#[derive(Debug, Clone)]
+pub struct DaemonOptions {
+    /// PID file of the running instance
+    pub pid_file: PathBuf,
+    /// File receiving stdout/stderr of the detached process
+    pub log_file: PathBuf,
+}
+
+impl DaemonOptions {
+    /// Build options, falling back to the per-user data directory for unset paths
+    pub fn new(pid_file: Option<PathBuf>, log_file: Option<PathBuf>) -> Self {
+        let base = dirs::data_local_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("loxone-mcp");
+        Self {
+            pid_file: pid_file.unwrap_or_else(|| base.join("loxone-mcp.pid")),
+            log_file: log_file.unwrap_or_else(|| base.join("loxone-mcp.log")),
+        }
+    }
+}
+
+/// State of the instance referenced by a PID file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaemonStatus {
+    /// No PID file exists
+    NotRunning,
+    /// The PID file points at a live process
+    Running(u32),
+    /// The PID file points at a process that no longer exists
+    Stale(u32),
+}
+
+impl std::fmt::Display for DaemonStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotRunning => write!(f, "not running"),
+            Self::Running(pid) => write!(f, "running (pid {pid})"),
+            Self::Stale(pid) => write!(f, "not running (stale PID file for pid {pid})"),
+        }
+    }
+}
+
+/// Read the PID stored in a PID file
+pub fn read_pid(path: &Path) -> Result<Option<u32>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    contents.trim().parse::<u32>().map(Some).map_err(|_| {
+        LoxoneError::config(format!(
+            "PID file {} does not contain a valid PID",
+            path.display()
+        ))
+    })
+}
+
+/// Detach from the terminal, write the PID file and redirect output to the log file
+#[cfg(all(feature = "daemon", unix))]
+pub fn daemonize(options: &DaemonOptions) -> Result<()> {
+    if let DaemonStatus::Running(pid) = status(&options.pid_file)? {
+        return Err(LoxoneError::config(format!(
+            "Server is already running (pid {pid}, PID file {})",
+            options.pid_file.display()
+        )));
+    }
+
+    for path in [&options.pid_file, &options.log_file] {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&options.log_file)?;
+    let log_err = log.try_clone()?;
+
+    println!(
+        "Starting in background, logging to {} (PID file {})",
+        options.log_file.display(),
+        options.pid_file.display()
+    );
+
+    daemonize::Daemonize::new()
+        .pid_file(&options.pid_file)
+        .working_directory(std::env::current_dir()?)
+        .stdout(log)
+        .stderr(log_err)
+        .start()
+        .map_err(|e| LoxoneError::config(format!("Failed to start daemon: {e}")))
+}
+
+/// Daemon mode is unavailable without the `daemon` feature
+#[cfg(not(all(feature = "daemon", unix)))]
+pub fn daemonize(_options: &DaemonOptions) -> Result<()> {
+    Err(LoxoneError::config(
+        "Daemon mode requires a Unix build with the `daemon` feature",
+    ))
+}
+
+/// Check whether the instance in the PID file is alive
+#[cfg(all(feature = "daemon", unix))]
+pub fn status(pid_file: &Path) -> Result<DaemonStatus> {
+    Ok(match read_pid(pid_file)? {
+        None => DaemonStatus::NotRunning,
+        Some(pid) if process_alive(pid) => DaemonStatus::Running(pid),
+        Some(pid) => DaemonStatus::Stale(pid),
+    })
+}
+
+/// Process status checks are unavailable without the `daemon` feature
+#[cfg(not(all(feature = "daemon", unix)))]
+pub fn status(_pid_file: &Path) -> Result<DaemonStatus> {
+    Err(LoxoneError::config(
+        "Daemon status requires a Unix build with the `daemon` feature",
+    ))
+}
+
+/// Send SIGTERM to the running instance and wait for it to exit
+///
+/// Returns the status observed before stopping. The PID file is removed once
+/// the process is gone, including stale PID files.
+#[cfg(all(feature = "daemon", unix))]
+pub fn stop(pid_file: &Path, timeout: Duration) -> Result<DaemonStatus> {
+    let current = status(pid_file)?;
+    if let DaemonStatus::Running(pid) = current {
+        let pid_t = libc::pid_t::try_from(pid)
+            .map_err(|_| LoxoneError::config(format!("PID {pid} is out of range")))?;
+        // SAFETY: kill(2) has no memory-safety preconditions
+        if unsafe { libc::kill(pid_t, libc::SIGTERM) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        let deadline = std::time::Instant::now() + timeout;
+        while process_alive(pid) {
+            if std::time::Instant::now() >= deadline {
+                return Err(LoxoneError::timeout(format!(
+                    "Server (pid {pid}) did not exit within {timeout:?}"
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    if current != DaemonStatus::NotRunning {
+        match std::fs::remove_file(pid_file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(current)
+}
+
+/// Stopping a daemon is unavailable without the `daemon` feature
+#[cfg(not(all(feature = "daemon", unix)))]
+pub fn stop(_pid_file: &Path, _timeout: Duration) -> Result<DaemonStatus> {
+    Err(LoxoneError::config(
+        "Stopping the daemon requires a Unix build with the `daemon` feature",
+    ))
+}
+
+/// Probe a process with signal 0
+#[cfg(all(feature = "daemon", unix))]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only performs the permission and existence checks
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    // EPERM means the process exists but belongs to another user
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_read_pid() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("server.pid");
+        assert_eq!(read_pid(&path).unwrap(), None);
+
+        std::fs::write(&path, "1234\n").unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(1234));
+
+        std::fs::write(&path, "not a pid").unwrap();
+        assert!(read_pid(&path).is_err());
+    }
+
+    #[test]
+    fn test_default_paths() {
+        let options = DaemonOptions::new(None, Some(PathBuf::from("/var/log/loxone.log")));
+        assert!(options.pid_file.ends_with("loxone-mcp/loxone-mcp.pid"));
+        assert_eq!(options.log_file, PathBuf::from("/var/log/loxone.log"));
+    }
+
+    #[cfg(all(feature = "daemon", unix))]
+    #[test]
+    fn test_status_and_stop_stale_pid_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("server.pid");
+        assert_eq!(status(&path).unwrap(), DaemonStatus::NotRunning);
+
+        std::fs::write(&path, std::process::id().to_string()).unwrap();
+        assert_eq!(
+            status(&path).unwrap(),
+            DaemonStatus::Running(std::process::id())
+        );
+
+        // PIDs above the kernel's pid_max never exist
+        std::fs::write(&path, "2147483646").unwrap();
+        assert_eq!(status(&path).unwrap(), DaemonStatus::Stale(2147483646));
+        assert_eq!(
+            stop(&path, Duration::from_secs(1)).unwrap(),
+            DaemonStatus::Stale(2147483646)
+        );
+        assert!(!path.exists());
+    }
+}
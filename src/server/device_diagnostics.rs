@@ -0,0 +1,278 @@
+//! Diagnostics for configured but unreachable devices
+//!
+//! Backs the `diagnose_devices` tool. A control from the structure file is
+//! reported when the Miniserver has no live state for it, or when the Tree
+//! or Air peripheral behind it is offline according to the device status
+//! API ([`crate::server::topology`]). Controls are matched to peripherals by
+//! the serial number in `details.serialNr` where the firmware provides one,
+//! otherwise by name.
+//!
+//! Each finding carries suggested causes, most likely first, derived from
+//! what is known about the peripheral: an offline extension, a weak Air
+//! signal or an empty battery.
+
+use crate::server::air_devices::AirDevice;
+use crate::server::device_search::SearchCandidate;
+use crate::server::topology::{Connection, Extension, Peripheral};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Air signal strength in dBm below which the link counts as weak
+pub const WEAK_SIGNAL_DBM: f64 = -85.0;
+
+/// Battery level in % below which an empty battery is suggested
+pub const EMPTY_BATTERY_PERCENT: f64 = 5.0;
+
+/// Why a device was reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Problem {
+    /// The peripheral is reported offline
+    PeripheralOffline,
+    /// The extension the peripheral is attached to is offline
+    ExtensionOffline,
+    /// The Miniserver reports no state for the control
+    NoLiveState,
+}
+
+/// Where a peripheral is attached
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HardwareLink {
+    pub serial: String,
+    pub name: String,
+    pub connection: Connection,
+    pub extension: String,
+    pub extension_online: bool,
+    /// Tree branch, for Tree devices
+    pub branch: Option<String>,
+    pub online: bool,
+}
+
+/// Flatten the topology to one entry per peripheral
+pub fn hardware_links(extensions: &[Extension]) -> Vec<HardwareLink> {
+    let mut links = Vec::new();
+    for extension in extensions {
+        let link = |device: &Peripheral, branch: Option<&str>| HardwareLink {
+            serial: device.serial.clone(),
+            name: device.name.clone(),
+            connection: if branch.is_some() {
+                Connection::Tree
+            } else {
+                extension.connection
+            },
+            extension: extension.name.clone(),
+            extension_online: extension.online,
+            branch: branch.map(str::to_string),
+            online: device.online,
+        };
+        for branch in &extension.branches {
+            links.extend(branch.devices.iter().map(|d| link(d, Some(&branch.name))));
+        }
+        links.extend(extension.devices.iter().map(|d| link(d, None)));
+    }
+    links
+}
+
+/// A configured device that looks unreachable
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    pub device_type: String,
+    pub problem: Problem,
+    /// Peripheral the control was matched to
+    pub hardware: Option<HardwareLink>,
+    /// Suggested causes, most likely first
+    pub causes: Vec<String>,
+}
+
+/// Serial number a control declares, if any
+pub fn control_serial(control: &Value) -> Option<&str> {
+    control
+        .get("details")
+        .and_then(|details| details.get("serialNr"))
+        .and_then(Value::as_str)
+        .filter(|serial| !serial.is_empty())
+}
+
+/// Cross-reference controls with live states and peripheral status
+///
+/// `serials` maps control UUIDs to their declared serial numbers;
+/// `live_states` holds the state of every control the Miniserver answered
+/// for. Findings are sorted by room, then name.
+pub fn diagnose(
+    controls: &[SearchCandidate],
+    serials: &HashMap<String, String>,
+    live_states: &HashMap<String, Value>,
+    links: &[HardwareLink],
+    air: &[AirDevice],
+) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = controls
+        .iter()
+        .filter_map(|control| {
+            let hardware = match serials.get(&control.uuid) {
+                Some(serial) => links.iter().find(|link| link.serial == *serial),
+                None => links
+                    .iter()
+                    .find(|link| link.name.eq_ignore_ascii_case(&control.name)),
+            };
+            let has_state = live_states
+                .get(&control.uuid)
+                .is_some_and(|state| !state.is_null());
+
+            let problem = match hardware {
+                Some(link) if !link.extension_online => Problem::ExtensionOffline,
+                Some(link) if !link.online => Problem::PeripheralOffline,
+                _ if !has_state => Problem::NoLiveState,
+                _ => return None,
+            };
+            let air_device = hardware.and_then(|link| air.iter().find(|d| d.serial == link.serial));
+            Some(Finding {
+                uuid: control.uuid.clone(),
+                name: control.name.clone(),
+                room: control.room.clone(),
+                device_type: control.device_type.clone(),
+                problem,
+                causes: causes(problem, hardware, air_device),
+                hardware: hardware.cloned(),
+            })
+        })
+        .collect();
+    findings.sort_by(|a, b| (&a.room, &a.name).cmp(&(&b.room, &b.name)));
+    findings
+}
+
+/// Suggested causes for a finding
+fn causes(problem: Problem, link: Option<&HardwareLink>, air: Option<&AirDevice>) -> Vec<String> {
+    let mut causes = Vec::new();
+    match (problem, link) {
+        (Problem::ExtensionOffline, Some(link)) => {
+            causes.push(format!(
+                "Extension '{}' is offline: check its power supply and Link bus wiring",
+                link.extension
+            ));
+        }
+        (Problem::PeripheralOffline, Some(link)) => match link.connection {
+            Connection::Tree => causes.push(format!(
+                "Tree device not answering on branch '{}' of '{}': check the Tree wiring and \
+                 its power supply",
+                link.branch.as_deref().unwrap_or("unknown"),
+                link.extension
+            )),
+            Connection::Air => {
+                if let Some(level) = air.and_then(|d| d.battery_percent)
+                    && level < EMPTY_BATTERY_PERCENT
+                {
+                    causes.push(format!("Battery is empty ({level:.0}%): replace it"));
+                }
+                if let Some(signal) = air.and_then(|d| d.signal_dbm)
+                    && signal < WEAK_SIGNAL_DBM
+                {
+                    causes.push(format!(
+                        "Weak Air signal ({signal:.0} dBm): move the device closer or add a \
+                         mains-powered Air device as repeater"
+                    ));
+                }
+                causes.push(
+                    "Air device out of range, without power or unpaired: check it in Loxone Config"
+                        .to_string(),
+                );
+            }
+            Connection::Link => causes.push(format!(
+                "Device on '{}' not answering: check its wiring",
+                link.extension
+            )),
+        },
+        _ => {
+            causes.push(
+                "The Miniserver reports no state: the control may be unused in the program or \
+                 its input not connected"
+                    .to_string(),
+            );
+            causes.push(
+                "The structure file may be outdated: reload it after saving the program"
+                    .to_string(),
+            );
+        }
+    }
+    causes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::topology;
+    use serde_json::json;
+
+    fn control(uuid: &str, name: &str) -> SearchCandidate {
+        SearchCandidate {
+            uuid: uuid.to_string(),
+            name: name.to_string(),
+            room: Some("Kitchen".to_string()),
+            category: None,
+            device_type: "InfoOnlyDigital".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let extensions = topology::parse_extensions(&json!({"extensions": [
+            {"serial": "13:01", "name": "Tree", "type": "TreeInterface", "online": true,
+             "branches": [{"name": "Left", "devices": [
+                {"serial": "B0:01", "name": "Touch Kitchen", "online": false}]}]},
+            {"serial": "12:01", "name": "Air", "type": "AirInterface", "online": true,
+             "devices": [{"serial": "50:01", "name": "Window Kitchen", "online": false}]},
+            {"serial": "14:01", "name": "Relay Extension", "type": "RelayExtension",
+             "online": false, "devices": [{"serial": "R:01", "name": "Relay 1", "online": true}]}
+        ]}));
+        let links = hardware_links(&extensions);
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].branch.as_deref(), Some("Left"));
+
+        let air = vec![AirDevice {
+            serial: "50:01".to_string(),
+            name: "Window Kitchen".to_string(),
+            room: None,
+            device_type: None,
+            battery_percent: Some(2.0),
+            online: false,
+            last_seen: None,
+            signal_dbm: Some(-92.0),
+        }];
+        let controls = vec![
+            control("c-touch", "Touch Kitchen"),
+            control("c-window", "Kitchen Window"),
+            control("c-relay", "Relay 1"),
+            control("c-unused", "Spare Input"),
+            control("c-ok", "Light"),
+        ];
+        let serials = HashMap::from([("c-window".to_string(), "50:01".to_string())]);
+        let live_states = HashMap::from([
+            ("c-touch".to_string(), json!(1)),
+            ("c-window".to_string(), json!(0)),
+            ("c-relay".to_string(), json!(1)),
+            ("c-unused".to_string(), Value::Null),
+            ("c-ok".to_string(), json!(1)),
+        ]);
+
+        let findings = diagnose(&controls, &serials, &live_states, &links, &air);
+        let problems: Vec<(&str, Problem)> = findings
+            .iter()
+            .map(|f| (f.uuid.as_str(), f.problem))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                ("c-window", Problem::PeripheralOffline),
+                ("c-relay", Problem::ExtensionOffline),
+                ("c-unused", Problem::NoLiveState),
+                ("c-touch", Problem::PeripheralOffline),
+            ]
+        );
+        assert!(findings[0].causes[0].contains("Battery is empty"));
+        assert!(findings[0].causes[1].contains("Weak Air signal"));
+        assert!(findings[3].causes[0].contains("branch 'Left'"));
+    }
+}
@@ -0,0 +1,213 @@
+//! Compact UUID ↔ name ↔ room ↔ type index
+//!
+//! Backs the `loxone://system/device-index` resource and the `lookup_device`
+//! tool. The index is deliberately tabular (one row per control) so clients
+//! can keep it in context and ground references like "the kitchen light"
+//! without fetching the full device list on every turn.
+
+use crate::client::LoxoneStructure;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// URI of the device index resource
+pub const DEVICE_INDEX_URI: &str = "loxone://system/device-index";
+
+/// Maximum number of matches returned by a lookup
+pub const MAX_LOOKUP_RESULTS: usize = 20;
+
+/// One control in the index
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceIndexEntry {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    pub device_type: String,
+}
+
+/// How a lookup query matched an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// Query is the control UUID
+    Uuid,
+    /// Query equals the control name
+    ExactName,
+    /// Query is part of the control name
+    PartialName,
+    /// Query names the control's room
+    Room,
+}
+
+/// Index over all controls of a structure file
+#[derive(Debug, Clone, Default)]
+pub struct DeviceIndex {
+    /// Structure `lastModified`, usable as a cache version
+    pub version: String,
+    /// Entries sorted by room, then name
+    pub entries: Vec<DeviceIndexEntry>,
+}
+
+impl DeviceIndex {
+    /// Build the index from the structure file
+    pub fn from_structure(structure: &LoxoneStructure) -> Self {
+        let room_name = |uuid: &str| {
+            structure
+                .rooms
+                .get(uuid)
+                .and_then(|room| room.get("name"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        let mut entries: Vec<_> = structure
+            .controls
+            .iter()
+            .map(|(uuid, control)| DeviceIndexEntry {
+                uuid: uuid.clone(),
+                name: control
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Unknown")
+                    .to_string(),
+                room: control
+                    .get("room")
+                    .and_then(Value::as_str)
+                    .and_then(room_name),
+                device_type: control
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Unknown")
+                    .to_string(),
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.room, &a.name, &a.uuid).cmp(&(&b.room, &b.name, &b.uuid)));
+
+        Self {
+            version: structure.last_modified.clone(),
+            entries,
+        }
+    }
+
+    /// Render the resource body: column names plus one row per control
+    pub fn to_resource_json(&self) -> Value {
+        let rows: Vec<Value> = self
+            .entries
+            .iter()
+            .map(|e| json!([e.uuid, e.name, e.room, e.device_type]))
+            .collect();
+        json!({
+            "uri": DEVICE_INDEX_URI,
+            "version": self.version,
+            "columns": ["uuid", "name", "room", "type"],
+            "count": rows.len(),
+            "devices": rows,
+        })
+    }
+
+    /// Resolve a UUID, name or room reference to index entries
+    ///
+    /// A UUID match wins outright. Otherwise exact name matches rank before
+    /// partial name matches, which rank before controls in a matching room.
+    pub fn lookup(&self, query: &str) -> Vec<(MatchKind, &DeviceIndexEntry)> {
+        let query = query.trim();
+        if let Some(entry) = self.entries.iter().find(|e| e.uuid == query) {
+            return vec![(MatchKind::Uuid, entry)];
+        }
+
+        let lower = query.to_lowercase();
+        if lower.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let name = entry.name.to_lowercase();
+                let kind = if name == lower {
+                    MatchKind::ExactName
+                } else if name.contains(&lower) {
+                    MatchKind::PartialName
+                } else if entry
+                    .room
+                    .as_ref()
+                    .is_some_and(|room| room.to_lowercase() == lower)
+                {
+                    MatchKind::Room
+                } else {
+                    return None;
+                };
+                Some((kind, entry))
+            })
+            .collect();
+        matches.sort_by_key(|(kind, _)| *kind);
+        matches.truncate(MAX_LOOKUP_RESULTS);
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn structure() -> LoxoneStructure {
+        LoxoneStructure {
+            last_modified: "2025-01-01 10:00:00".to_string(),
+            controls: HashMap::from([
+                (
+                    "uuid-1".to_string(),
+                    json!({"name": "Kitchen Light", "type": "LightControllerV2", "room": "room-k"}),
+                ),
+                (
+                    "uuid-2".to_string(),
+                    json!({"name": "Light", "type": "Switch", "room": "room-l"}),
+                ),
+                (
+                    "uuid-3".to_string(),
+                    json!({"name": "Blind", "type": "Jalousie", "room": "room-k"}),
+                ),
+            ]),
+            rooms: HashMap::from([
+                ("room-k".to_string(), json!({"name": "Kitchen"})),
+                ("room-l".to_string(), json!({"name": "Living Room"})),
+            ]),
+            cats: HashMap::new(),
+            global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_resource_json_is_tabular() {
+        let index = DeviceIndex::from_structure(&structure());
+        let body = index.to_resource_json();
+
+        assert_eq!(body["count"], 3);
+        assert_eq!(body["version"], "2025-01-01 10:00:00");
+        assert_eq!(
+            body["devices"][0],
+            json!(["uuid-3", "Blind", "Kitchen", "Jalousie"])
+        );
+    }
+
+    #[test]
+    fn test_lookup_ranking() {
+        let index = DeviceIndex::from_structure(&structure());
+
+        let by_uuid = index.lookup("uuid-2");
+        assert_eq!(by_uuid.len(), 1);
+        assert_eq!(by_uuid[0].0, MatchKind::Uuid);
+
+        let by_name = index.lookup("light");
+        assert_eq!(by_name[0].0, MatchKind::ExactName);
+        assert_eq!(by_name[0].1.uuid, "uuid-2");
+        assert_eq!(by_name[1].0, MatchKind::PartialName);
+
+        let by_room = index.lookup("kitchen");
+        assert_eq!(by_room[0].1.uuid, "uuid-1");
+        assert_eq!(by_room[1], (MatchKind::Room, &index.entries[0]));
+
+        assert!(index.lookup("garage").is_empty());
+        assert!(index.lookup("  ").is_empty());
+    }
+}
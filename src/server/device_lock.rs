@@ -0,0 +1,125 @@
+//! Control locking
+//!
+//! Loxone can lock a control so it ignores commands from apps and the API,
+//! e.g. while a blind is being serviced. Locked controls expose a `jLocked`
+//! state holding JSON:
+//!
+//! ```json
+//! {"locked": true, "reason": "Maintenance"}
+//! ```
+//!
+//! - `lockcontrol/1/<reason>` locks the control
+//! - `lockcontrol/0` unlocks it
+//!
+//! Commands sent to a locked control are answered with code 423, which the
+//! clients surface as [`LoxoneError::DeviceLocked`].
+
+use crate::error::{LoxoneError, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// State holding the lock status
+pub const LOCK_STATE: &str = "jLocked";
+
+/// Command unlocking a control
+pub const UNLOCK_COMMAND: &str = "lockcontrol/0";
+
+/// Longest lock reason sent to the Miniserver
+const MAX_REASON_LEN: usize = 100;
+
+/// Lock status of a control
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockStatus {
+    pub locked: bool,
+    pub reason: Option<String>,
+}
+
+/// Command locking a control, with an optional reason shown in the apps
+///
+/// Characters that would break the command path are removed from the
+/// reason.
+pub fn lock_command(reason: Option<&str>) -> Result<String> {
+    let reason: String = reason
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !matches!(c, '/' | '?' | '#' | '%' | '\\') && !c.is_control())
+        .collect();
+    let reason = reason.trim();
+    if reason.chars().count() > MAX_REASON_LEN {
+        return Err(LoxoneError::invalid_input(format!(
+            "Lock reason must be at most {MAX_REASON_LEN} characters"
+        )));
+    }
+    if reason.is_empty() {
+        Ok("lockcontrol/1".to_string())
+    } else {
+        Ok(format!("lockcontrol/1/{}", urlencoding::encode(reason)))
+    }
+}
+
+/// UUID of the lock state of a control, if it can be locked
+pub fn lock_state(control: &Value) -> Option<&str> {
+    control
+        .get("states")
+        .and_then(|states| states.get(LOCK_STATE))
+        .and_then(Value::as_str)
+}
+
+/// Parse a `jLocked` state value (JSON, possibly encoded as a string)
+pub fn parse_lock_state(value: &Value) -> Option<LockStatus> {
+    let status = match value {
+        Value::String(text) if text.trim().is_empty() => return None,
+        Value::String(text) => serde_json::from_str(text).ok()?,
+        other => other.clone(),
+    };
+    let locked = match status.get("locked")? {
+        Value::Bool(locked) => *locked,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        _ => return None,
+    };
+    let reason = status
+        .get("reason")
+        .and_then(Value::as_str)
+        .filter(|r| !r.is_empty())
+        .map(str::to_string);
+    Some(LockStatus { locked, reason })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lock_command() {
+        assert_eq!(lock_command(None).unwrap(), "lockcontrol/1");
+        assert_eq!(
+            lock_command(Some("Window cleaning / ladder")).unwrap(),
+            "lockcontrol/1/Window%20cleaning%20%20ladder"
+        );
+        assert_eq!(lock_command(Some(" ?# ")).unwrap(), "lockcontrol/1");
+        assert!(lock_command(Some(&"x".repeat(101))).is_err());
+    }
+
+    #[test]
+    fn test_parse_lock_state() {
+        let locked = parse_lock_state(&json!(r#"{"locked":true,"reason":"Maintenance"}"#)).unwrap();
+        assert!(locked.locked);
+        assert_eq!(locked.reason.as_deref(), Some("Maintenance"));
+
+        let unlocked = parse_lock_state(&json!({"locked": 0, "reason": ""})).unwrap();
+        assert_eq!(
+            unlocked,
+            LockStatus {
+                locked: false,
+                reason: None
+            }
+        );
+        assert_eq!(parse_lock_state(&json!("")), None);
+        assert_eq!(parse_lock_state(&json!(1.0)), None);
+
+        let control = json!({"states": {"jLocked": "s-lock", "position": "s-pos"}});
+        assert_eq!(lock_state(&control), Some("s-lock"));
+        assert_eq!(lock_state(&json!({"states": {}})), None);
+    }
+}
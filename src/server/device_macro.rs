@@ -0,0 +1,325 @@
+//! Device macros with rollback
+//!
+//! Backs the `execute_macro` tool. A macro is an ordered list of
+//! `{"device": "Kitchen Light", "command": "on"}` steps sent one after the
+//! other. When a step fails, the remaining steps are skipped and, with
+//! rollback, the steps already executed are undone in reverse order.
+//!
+//! Only lights and blinds can be undone; their previous state is read
+//! before the macro runs and restored with:
+//!
+//! - `Switch`: `active` restored with `on` / `off`
+//! - `Dimmer`, `EIBDimmer`: `position` restored by sending the value
+//! - `LightControllerV2`: `activeMoods` restored with `changeTo/<id>`,
+//!   further mixed moods with `addMood/<id>`
+//! - blinds: `position` (0.0-1.0) restored with `ManualPosition/<percent>`
+//!
+//! Other steps are reported as not reversible and left as they are.
+//!
+//! Macros drive lights, switches and blinds only, and every command is
+//! checked against the control type before anything is sent (see
+//! [`check_step`]). Alarms, gates, intercoms, pools and safety devices are
+//! refused: their own tools ask for the user's consent first.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::access;
+use crate::server::alarm;
+use crate::server::blinds;
+use crate::server::intercom;
+use crate::server::light_moods;
+use crate::server::pool;
+use crate::server::safety;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Most steps one macro may contain
+pub const MAX_STEPS: usize = 50;
+
+/// One step of a macro
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MacroStep {
+    /// Device UUID or name
+    pub device: String,
+    pub command: String,
+}
+
+/// Outcome of one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Executed,
+    Failed,
+    /// Not run after an earlier step failed
+    Skipped,
+    /// Executed and undone
+    RolledBack,
+    /// Executed, undoing it failed
+    RollbackFailed,
+    /// Executed, but the device cannot be undone
+    NotReversible,
+}
+
+/// Parse and check the raw steps
+pub fn parse_steps(raw: Vec<Value>) -> Result<Vec<MacroStep>> {
+    if raw.is_empty() {
+        return Err(LoxoneError::invalid_input("The macro contains no steps"));
+    }
+    if raw.len() > MAX_STEPS {
+        return Err(LoxoneError::invalid_input(format!(
+            "A macro may contain at most {MAX_STEPS} steps, got {}",
+            raw.len()
+        )));
+    }
+    raw.into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let step: MacroStep = serde_json::from_value(value)
+                .map_err(|e| LoxoneError::invalid_input(format!("Step {}: {e}", index + 1)))?;
+            if step.command.trim().is_empty() {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Step {}: the command is empty",
+                    index + 1
+                )));
+            }
+            Ok(step)
+        })
+        .collect()
+}
+
+/// Control types whose tools ask for the user's consent, with the tool
+const CONSENT_TOOLS: &[(&[&str], &str)] = &[
+    (alarm::ALARM_CONTROL_TYPES, "control_alarm"),
+    (access::GATE_CONTROL_TYPES, "control_gate"),
+    (intercom::INTERCOM_CONTROL_TYPES, "open_intercom_door"),
+    (pool::POOL_CONTROL_TYPES, "control_pool"),
+    (safety::SMOKE_ALARM_TYPES, "test_safety_alarm"),
+    (safety::LEAK_DETECTOR_TYPES, "test_safety_alarm"),
+];
+
+/// Lighting controllers switched by scene or mood
+const SCENE_CONTROLLER_TYPES: &[&str] = &["LightController", "LightControllerV2"];
+
+/// Check that `command` may be sent to `control` by a macro
+///
+/// Controls needing consent and control types other than lights, switches
+/// and blinds are refused, as are commands the control type does not know.
+pub fn check_step(control: &Value, command: &str) -> Result<()> {
+    let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+    let name = control
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("Unknown");
+    if let Some((_, tool)) = CONSENT_TOOLS
+        .iter()
+        .find(|(types, _)| types.contains(&control_type))
+    {
+        return Err(LoxoneError::permission_denied(format!(
+            "'{name}' ({control_type}) needs the user's consent; use {tool} instead of a macro"
+        )));
+    }
+
+    let command = command.trim();
+    let number_in = |max: f64| {
+        command
+            .parse::<f64>()
+            .is_ok_and(|value| (0.0..=max).contains(&value))
+    };
+    let id_after = |prefix: &str| {
+        command
+            .strip_prefix(prefix)
+            .is_some_and(|id| id.parse::<u32>().is_ok())
+    };
+    let valid = match control_type {
+        "Switch" | "Pushbutton" => matches!(command, "on" | "off" | "pulse"),
+        "Dimmer" | "EIBDimmer" => matches!(command, "on" | "off") || number_in(100.0),
+        t if SCENE_CONTROLLER_TYPES.contains(&t) => {
+            matches!(command, "on" | "off" | "plus" | "minus")
+                || id_after("changeTo/")
+                || id_after("addMood/")
+                || id_after("removeMood/")
+        }
+        t if blinds::BLIND_CONTROL_TYPES.contains(&t) => {
+            matches!(
+                command,
+                "up" | "down" | "FullUp" | "FullDown" | "Stop" | "stop" | "Shade" | "shade"
+            ) || command
+                .strip_prefix("ManualPosition/")
+                .is_some_and(|percent| percent.parse::<u8>().is_ok_and(|p| p <= 100))
+        }
+        _ => {
+            return Err(LoxoneError::invalid_input(format!(
+                "'{name}' ({control_type}) cannot be used in a macro. \
+                 Macros drive lights, switches and blinds"
+            )));
+        }
+    };
+    if !valid {
+        return Err(LoxoneError::invalid_input(format!(
+            "'{command}' is not a valid command for '{name}' ({control_type})"
+        )));
+    }
+    Ok(())
+}
+
+/// Name of the state holding what undoing a control restores
+pub fn restore_state(control: &Value) -> Option<&'static str> {
+    let control_type = control.get("type").and_then(Value::as_str)?;
+    match control_type {
+        "Switch" => Some("active"),
+        "Dimmer" | "EIBDimmer" => Some("position"),
+        t if light_moods::MOOD_CONTROLLER_TYPES.contains(&t) => Some("activeMoods"),
+        t if blinds::BLIND_CONTROL_TYPES.contains(&t) => Some("position"),
+        _ => None,
+    }
+}
+
+/// UUID of the state to read before running a step on `control`
+pub fn undo_state_uuid(control: &Value) -> Option<String> {
+    let state = restore_state(control)?;
+    control
+        .get("states")
+        .and_then(|states| states.get(state))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Commands restoring `control` to the state read before the macro, or
+/// `None` when it cannot be undone
+pub fn undo_commands(control: &Value, values: &HashMap<String, Value>) -> Option<Vec<String>> {
+    let value = values.get(&undo_state_uuid(control)?)?;
+    restore_commands(control.get("type").and_then(Value::as_str)?, value)
+}
+
+/// Commands bringing a control of `control_type` back to `value` of its
+/// [`restore_state`]
+pub fn restore_commands(control_type: &str, value: &Value) -> Option<Vec<String>> {
+    let number = || {
+        value
+            .as_f64()
+            .or_else(|| value.as_str()?.trim().parse().ok())
+    };
+    match control_type {
+        "Switch" => {
+            let on = match value {
+                Value::Bool(b) => *b,
+                _ => number()? != 0.0,
+            };
+            Some(vec![if on { "on" } else { "off" }.to_string()])
+        }
+        "Dimmer" | "EIBDimmer" => Some(vec![format!("{}", number()?)]),
+        t if light_moods::MOOD_CONTROLLER_TYPES.contains(&t) => {
+            let moods = light_moods::parse_active_moods(value);
+            let (first, mixed) = moods.split_first()?;
+            Some(
+                std::iter::once(format!("changeTo/{first}"))
+                    .chain(mixed.iter().map(|mood| format!("addMood/{mood}")))
+                    .collect(),
+            )
+        }
+        t if blinds::BLIND_CONTROL_TYPES.contains(&t) => {
+            let percent = (number()?.clamp(0.0, 1.0) * 100.0).round() as u8;
+            blinds::position_command(percent).ok().map(|c| vec![c])
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{StructureBuilder, TestServer, assert_tool_error, devices};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_steps() {
+        let steps = parse_steps(vec![json!({"device": "Kitchen", "command": "on"})]).unwrap();
+        assert_eq!(steps[0].command, "on");
+        assert!(parse_steps(vec![]).is_err());
+        assert!(parse_steps(vec![json!({"device": "Kitchen"})]).is_err());
+        assert!(parse_steps(vec![json!({"device": "Kitchen", "command": " "})]).is_err());
+        assert!(parse_steps(vec![json!({"device": "K", "command": "on", "delay": 1})]).is_err());
+    }
+
+    #[test]
+    fn test_check_step() {
+        let dimmer = json!({"name": "Floor Lamp", "type": "Dimmer"});
+        assert!(check_step(&dimmer, "45").is_ok());
+        assert!(check_step(&dimmer, "150").is_err());
+        assert!(check_step(&dimmer, "FullUp").is_err());
+
+        let blind = json!({"name": "Terrace", "type": "Jalousie"});
+        assert!(check_step(&blind, "ManualPosition/40").is_ok());
+        assert!(check_step(&blind, "ManualPosition/140").is_err());
+
+        let lights = json!({"name": "Ceiling", "type": "LightControllerV2"});
+        assert!(check_step(&lights, "changeTo/3").is_ok());
+        assert!(check_step(&lights, "changeTo/all").is_err());
+
+        let gate = json!({"name": "Garage Door", "type": "Gate"});
+        let error = check_step(&gate, "open").unwrap_err().to_string();
+        assert!(error.contains("control_gate"), "{error}");
+        let zone = json!({"name": "Speakers", "type": "AudioZoneV2"});
+        assert!(check_step(&zone, "play").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_disarm_step_is_rejected() {
+        let structure = StructureBuilder::new()
+            .device("Kitchen", devices::light("Ceiling"))
+            .device("Hall", devices::alarm("Burglar Alarm"))
+            .build();
+        let fixture = TestServer::new(structure).await;
+
+        let result = fixture
+            .execute_macro(
+                vec![
+                    json!({"device": "Ceiling", "command": "off"}),
+                    json!({"device": "Burglar Alarm", "command": "off"}),
+                ],
+                None,
+            )
+            .await;
+        assert_tool_error(result, "control_alarm");
+        assert!(fixture.client.commands().is_empty());
+    }
+
+    #[test]
+    fn test_undo_commands() {
+        let values = HashMap::from([
+            ("s-active".to_string(), json!(0)),
+            ("s-dim".to_string(), json!("35")),
+            ("s-moods".to_string(), json!("[2,5]")),
+            ("s-pos".to_string(), json!(0.4)),
+        ]);
+        let switch = json!({"type": "Switch", "states": {"active": "s-active"}});
+        assert_eq!(
+            undo_commands(&switch, &values),
+            Some(vec!["off".to_string()])
+        );
+
+        let dimmer = json!({"type": "Dimmer", "states": {"position": "s-dim"}});
+        assert_eq!(
+            undo_commands(&dimmer, &values),
+            Some(vec!["35".to_string()])
+        );
+
+        let lights = json!({"type": "LightControllerV2", "states": {"activeMoods": "s-moods"}});
+        assert_eq!(
+            undo_commands(&lights, &values),
+            Some(vec!["changeTo/2".to_string(), "addMood/5".to_string()])
+        );
+
+        let blind = json!({"type": "Jalousie", "states": {"position": "s-pos"}});
+        assert_eq!(
+            undo_commands(&blind, &values),
+            Some(vec!["ManualPosition/40".to_string()])
+        );
+
+        let gate = json!({"type": "Gate", "states": {"position": "s-pos"}});
+        assert_eq!(undo_state_uuid(&gate), None);
+        assert_eq!(undo_commands(&gate, &values), None);
+        assert_eq!(undo_commands(&blind, &HashMap::new()), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Fuzzy device search
+//!
+//! Backs the `search_devices` tool. Unlike [`crate::server::device_index`]
+//! lookups, which need an exact or contained name, the search tolerates
+//! typos and word order: "kitchen celing light" finds the control "Ceiling
+//! Light" in the room "Kitchen".
+//!
+//! Each query word is scored against the words of the control name by the
+//! better of Levenshtein and trigram similarity; words naming the room or
+//! category count slightly less than name words. The score of a control is
+//! the average over the query words, plus a boost when it is in the
+//! requested room or category.
+
+use crate::client::LoxoneStructure;
+use crate::server::room_suggestions::levenshtein;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Default number of results
+pub const DEFAULT_SEARCH_RESULTS: usize = 10;
+
+/// Largest number of results
+pub const MAX_SEARCH_RESULTS: usize = 50;
+
+/// Lowest score of a result
+pub const MIN_SCORE: f64 = 0.5;
+
+/// Word similarity below which a word does not match at all
+const MIN_WORD_SIMILARITY: f64 = 0.6;
+
+/// Weight of a query word matching the room or category instead of the name
+const CONTEXT_WEIGHT: f64 = 0.9;
+
+/// Score added for a control in the requested room
+const ROOM_BOOST: f64 = 0.15;
+
+/// Score added for a control in the requested category
+const CATEGORY_BOOST: f64 = 0.1;
+
+/// A control that can be found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchCandidate {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    pub category: Option<String>,
+    pub device_type: String,
+}
+
+/// A ranked search result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub candidate: SearchCandidate,
+    /// 0 to about 1.25, higher is better
+    pub score: f64,
+}
+
+/// All controls of a structure with room and category names
+pub fn candidates(structure: &LoxoneStructure) -> Vec<SearchCandidate> {
+    let name_of = |table: &HashMap<String, Value>, uuid: &str| {
+        table
+            .get(uuid)
+            .and_then(|entry| entry.get("name"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    structure
+        .controls
+        .iter()
+        .map(|(uuid, control)| {
+            let field = |name: &str| control.get(name).and_then(Value::as_str);
+            SearchCandidate {
+                uuid: uuid.clone(),
+                name: field("name").unwrap_or("Unknown").to_string(),
+                room: field("room").and_then(|room| name_of(&structure.rooms, room)),
+                category: field("cat").and_then(|cat| name_of(&structure.cats, cat)),
+                device_type: field("type").unwrap_or("Unknown").to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Lower-case words of a text
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Trigrams of a word padded with spaces
+fn trigrams(word: &str) -> HashSet<[char; 3]> {
+    let chars: Vec<char> = format!("  {word} ").chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// Similarity of two lower-case words from 0 to 1
+pub fn word_similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 0.0;
+    }
+    // "kit" for "kitchen"
+    if a.chars().count() >= 3 && b.starts_with(a) {
+        return 0.9;
+    }
+    let edit = 1.0 - levenshtein(a, b) as f64 / longest as f64;
+    let (ta, tb) = (trigrams(a), trigrams(b));
+    let trigram = ta.intersection(&tb).count() as f64 / ta.union(&tb).count() as f64;
+    edit.max(trigram)
+}
+
+/// Best similarity of `word` to any of `words`, 0 below the match threshold
+fn best_match(word: &str, words: &[String]) -> f64 {
+    let best = words
+        .iter()
+        .map(|candidate| word_similarity(word, candidate))
+        .fold(0.0, f64::max);
+    if best >= MIN_WORD_SIMILARITY {
+        best
+    } else {
+        0.0
+    }
+}
+
+/// Whether `wanted` names `actual` closely enough for a boost
+fn names_match(actual: Option<&str>, wanted: Option<&str>) -> bool {
+    match (actual, wanted) {
+        (Some(actual), Some(wanted)) => {
+            let (actual, wanted) = (actual.to_lowercase(), wanted.trim().to_lowercase());
+            actual == wanted || word_similarity(&wanted, &actual) >= 0.8
+        }
+        _ => false,
+    }
+}
+
+/// Rank candidates for `query`, best first; a UUID query matches exactly
+pub fn search(
+    candidates: &[SearchCandidate],
+    query: &str,
+    room: Option<&str>,
+    category: Option<&str>,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let query = query.trim();
+    if let Some(candidate) = candidates.iter().find(|c| c.uuid == query) {
+        return vec![SearchResult {
+            candidate: candidate.clone(),
+            score: 1.0,
+        }];
+    }
+    let query_words = words(query);
+    if query_words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<SearchResult> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let name = words(&candidate.name);
+            let mut context = words(candidate.room.as_deref().unwrap_or(""));
+            context.extend(words(candidate.category.as_deref().unwrap_or("")));
+
+            let total: f64 = query_words
+                .iter()
+                .map(|word| {
+                    best_match(word, &name).max(CONTEXT_WEIGHT * best_match(word, &context))
+                })
+                .sum();
+            let mut score = total / query_words.len() as f64;
+            if names_match(candidate.room.as_deref(), room) {
+                score += ROOM_BOOST;
+            }
+            if names_match(candidate.category.as_deref(), category) {
+                score += CATEGORY_BOOST;
+            }
+            (score >= MIN_SCORE).then(|| SearchResult {
+                candidate: candidate.clone(),
+                score: (score * 1000.0).round() / 1000.0,
+            })
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.candidate.name.cmp(&b.candidate.name))
+    });
+    results.truncate(limit.clamp(1, MAX_SEARCH_RESULTS));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(uuid: &str, name: &str, room: &str, category: &str) -> SearchCandidate {
+        SearchCandidate {
+            uuid: uuid.to_string(),
+            name: name.to_string(),
+            room: Some(room.to_string()),
+            category: Some(category.to_string()),
+            device_type: "LightControllerV2".to_string(),
+        }
+    }
+
+    fn candidates() -> Vec<SearchCandidate> {
+        vec![
+            candidate("uuid-1", "Ceiling Light", "Kitchen", "Lighting"),
+            candidate("uuid-2", "Kitchen Light", "Kitchen", "Lighting"),
+            candidate("uuid-3", "Ceiling Light", "Living Room", "Lighting"),
+            candidate("uuid-4", "Blind", "Kitchen", "Shading"),
+        ]
+    }
+
+    #[test]
+    fn test_word_similarity() {
+        assert_eq!(word_similarity("light", "light"), 1.0);
+        assert_eq!(word_similarity("kit", "kitchen"), 0.9);
+        assert!(word_similarity("celing", "ceiling") > 0.8);
+        assert!(word_similarity("blind", "ceiling") < MIN_WORD_SIMILARITY);
+    }
+
+    #[test]
+    fn test_search_tolerates_typos() {
+        let results = search(&candidates(), "kitchen celing light", None, None, 10);
+        assert_eq!(results[0].candidate.uuid, "uuid-1");
+        assert!(results[0].score > results[1].score);
+        assert!(results.iter().all(|r| r.candidate.uuid != "uuid-4"));
+
+        // Room boost decides between equal names
+        let boosted = search(
+            &candidates(),
+            "ceiling light",
+            Some("living room"),
+            None,
+            10,
+        );
+        assert_eq!(boosted[0].candidate.uuid, "uuid-3");
+
+        let by_uuid = search(&candidates(), "uuid-4", None, None, 10);
+        assert_eq!(by_uuid.len(), 1);
+        assert_eq!(by_uuid[0].score, 1.0);
+
+        assert!(search(&candidates(), "sauna heater", None, None, 10).is_empty());
+        assert_eq!(search(&candidates(), "light", None, None, 1).len(), 1);
+    }
+}
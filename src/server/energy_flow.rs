@@ -0,0 +1,251 @@
+//! Energy flow between grid, solar production, battery and the house
+//!
+//! The `EnergyFlowMonitor` and `EnergyManager2` blocks report the flows as
+//! states, all powers in kW:
+//!
+//! - `Gpwr`: grid power, positive while importing, negative while exporting
+//! - `Ppwr`: production (PV) power
+//! - `Spwr`: storage power, positive while charging the battery
+//! - `Ssoc`: battery state of charge in %
+//! - `Cpwr`: consumption of the house, when the block computes it
+//!
+//! Installations without such a block usually have `Meter` controls named
+//! after what they measure ("Grid", "PV", "Battery", "Consumption"); their
+//! `actual` state is used instead, and a battery's `soc` state if present.
+//! Consumption that is not measured is derived from the other flows.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Subscribable resource with the current energy flow
+pub const ENERGY_FLOW_URI: &str = "loxone://energy/flow";
+
+/// Blocks reporting the whole flow
+pub const ENERGY_FLOW_CONTROL_TYPES: &[&str] =
+    &["EnergyFlowMonitor", "EnergyManager2", "EnergyManager"];
+
+/// Meter blocks classified by name
+pub const METER_CONTROL_TYPES: &[&str] = &["Meter", "PowerMeter"];
+
+/// State names of the flow blocks
+const FLOW_STATES: &[(&str, FlowQuantity)] = &[
+    ("Gpwr", FlowQuantity::Grid),
+    ("Ppwr", FlowQuantity::Production),
+    ("Spwr", FlowQuantity::Storage),
+    ("Ssoc", FlowQuantity::StateOfCharge),
+    ("Cpwr", FlowQuantity::Consumption),
+];
+
+/// Name fragments of meters, checked in order
+const METER_NAME_PATTERNS: &[(&[&str], FlowQuantity)] = &[
+    (
+        &["battery", "batterie", "speicher", "storage"],
+        FlowQuantity::Storage,
+    ),
+    (
+        &["solar", "pv", "photovolt", "production", "erzeugung"],
+        FlowQuantity::Production,
+    ),
+    (&["grid", "netz", "utility", "bezug"], FlowQuantity::Grid),
+    (
+        &["consumption", "verbrauch", "house", "haus", "total load"],
+        FlowQuantity::Consumption,
+    ),
+];
+
+/// Quantity a state contributes to the energy flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowQuantity {
+    Grid,
+    Production,
+    Storage,
+    StateOfCharge,
+    Consumption,
+}
+
+/// State UUIDs of a control that feed the energy flow
+pub fn flow_states(control: &Value) -> Vec<(FlowQuantity, String)> {
+    let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+    let state = |name: &str| {
+        control
+            .get("states")
+            .and_then(|states| states.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    if ENERGY_FLOW_CONTROL_TYPES.contains(&control_type) {
+        return FLOW_STATES
+            .iter()
+            .filter_map(|(name, quantity)| Some((*quantity, state(name)?)))
+            .collect();
+    }
+    if !METER_CONTROL_TYPES.contains(&control_type) {
+        return Vec::new();
+    }
+
+    let name = control
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_lowercase();
+    let Some(quantity) = METER_NAME_PATTERNS
+        .iter()
+        .find(|(patterns, _)| patterns.iter().any(|p| name.contains(p)))
+        .map(|(_, quantity)| *quantity)
+    else {
+        return Vec::new();
+    };
+    let mut states: Vec<(FlowQuantity, String)> = state("actual")
+        .map(|uuid| (quantity, uuid))
+        .into_iter()
+        .collect();
+    if quantity == FlowQuantity::Storage
+        && let Some(soc) = state("soc")
+    {
+        states.push((FlowQuantity::StateOfCharge, soc));
+    }
+    states
+}
+
+/// Current energy flow, powers in kW
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EnergyFlow {
+    pub production_kw: Option<f64>,
+    pub consumption_kw: Option<f64>,
+    /// Whether consumption was derived from the other flows
+    pub consumption_derived: bool,
+    pub grid_import_kw: Option<f64>,
+    pub grid_export_kw: Option<f64>,
+    /// Positive while charging, negative while discharging
+    pub battery_power_kw: Option<f64>,
+    pub battery_soc_percent: Option<f64>,
+    /// Share of consumption not covered by the grid, in %
+    pub self_sufficiency_percent: Option<f64>,
+}
+
+impl EnergyFlow {
+    /// Combine readings; powers of the same quantity are summed and the
+    /// state of charge is averaged
+    pub fn from_readings(readings: &[(FlowQuantity, f64)]) -> Self {
+        let sum = |quantity: FlowQuantity| {
+            readings
+                .iter()
+                .filter(|(q, _)| *q == quantity)
+                .map(|(_, v)| *v)
+                .reduce(|a, b| a + b)
+        };
+        let grid = sum(FlowQuantity::Grid);
+        let production = sum(FlowQuantity::Production);
+        let storage = sum(FlowQuantity::Storage);
+        let soc: Vec<f64> = readings
+            .iter()
+            .filter(|(q, _)| *q == FlowQuantity::StateOfCharge)
+            .map(|(_, v)| *v)
+            .collect();
+
+        let measured = sum(FlowQuantity::Consumption);
+        let derived = (measured.is_none() && (grid.is_some() || production.is_some()))
+            .then(|| grid.unwrap_or(0.0) + production.unwrap_or(0.0) - storage.unwrap_or(0.0))
+            .map(|c| c.max(0.0));
+        let consumption = measured.or(derived);
+
+        let grid_import = grid.map(|g| g.max(0.0));
+        let self_sufficiency = match (consumption, grid_import) {
+            (Some(c), Some(import)) if c > 0.0 => {
+                Some(((c - import.min(c)) / c * 100.0 * 10.0).round() / 10.0)
+            }
+            _ => None,
+        };
+
+        Self {
+            production_kw: production,
+            consumption_kw: consumption,
+            consumption_derived: derived.is_some(),
+            grid_import_kw: grid_import,
+            grid_export_kw: grid.map(|g| (-g).max(0.0)),
+            battery_power_kw: storage,
+            battery_soc_percent: (!soc.is_empty())
+                .then(|| soc.iter().sum::<f64>() / soc.len() as f64),
+            self_sufficiency_percent: self_sufficiency,
+        }
+    }
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+pub fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_flow_states() {
+        let manager = json!({
+            "type": "EnergyManager2",
+            "states": {"Gpwr": "s-g", "Ppwr": "s-p", "Ssoc": "s-soc", "MinSoc": "s-min"}
+        });
+        assert_eq!(
+            flow_states(&manager),
+            vec![
+                (FlowQuantity::Grid, "s-g".to_string()),
+                (FlowQuantity::Production, "s-p".to_string()),
+                (FlowQuantity::StateOfCharge, "s-soc".to_string()),
+            ]
+        );
+
+        let battery = json!({
+            "type": "Meter",
+            "name": "PV Batteriespeicher",
+            "states": {"actual": "s-a", "soc": "s-soc"}
+        });
+        assert_eq!(
+            flow_states(&battery)[0],
+            (FlowQuantity::Storage, "s-a".to_string())
+        );
+        assert_eq!(flow_states(&battery).len(), 2);
+
+        let heat_pump = json!({"type": "Meter", "name": "Heat Pump", "states": {"actual": "s"}});
+        assert!(flow_states(&heat_pump).is_empty());
+        assert!(flow_states(&json!({"type": "Switch", "name": "Grid"})).is_empty());
+    }
+
+    #[test]
+    fn test_energy_flow() {
+        // Exporting with a charging battery
+        let flow = EnergyFlow::from_readings(&[
+            (FlowQuantity::Grid, -1.5),
+            (FlowQuantity::Production, 6.0),
+            (FlowQuantity::Storage, 2.0),
+            (FlowQuantity::StateOfCharge, 60.0),
+            (FlowQuantity::StateOfCharge, 80.0),
+        ]);
+        assert_eq!(flow.consumption_kw, Some(2.5));
+        assert!(flow.consumption_derived);
+        assert_eq!(flow.grid_import_kw, Some(0.0));
+        assert_eq!(flow.grid_export_kw, Some(1.5));
+        assert_eq!(flow.battery_soc_percent, Some(70.0));
+        assert_eq!(flow.self_sufficiency_percent, Some(100.0));
+
+        // Importing at night with measured consumption
+        let flow = EnergyFlow::from_readings(&[
+            (FlowQuantity::Grid, 0.8),
+            (FlowQuantity::Storage, -1.2),
+            (FlowQuantity::Consumption, 2.0),
+        ]);
+        assert!(!flow.consumption_derived);
+        assert_eq!(flow.grid_import_kw, Some(0.8));
+        assert_eq!(flow.self_sufficiency_percent, Some(60.0));
+        assert_eq!(flow.production_kw, None);
+
+        assert_eq!(EnergyFlow::from_readings(&[]), EnergyFlow::default());
+    }
+}
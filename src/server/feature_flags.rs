@@ -0,0 +1,372 @@
+//! Per-installation feature flags
+//!
+//! Operators can switch off tool groups and background jobs without
+//! rebuilding. A flag's value comes from, in order of precedence:
+//!
+//! 1. the flags file (`LOXONE_FEATURE_FLAGS_FILE`, default
+//!    `<data dir>/loxone-mcp/feature_flags.json`), re-read when it changes;
+//! 2. the configuration (`LOXONE_FEATURE_FLAGS=batch=false,sampling=true`);
+//! 3. the built-in default of [`FLAGS`].
+//!
+//! Tools are checked on every call by [`FeatureFlagGate`], so editing the
+//! file takes effect immediately; background jobs and notification channels
+//! are checked when the server starts.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::tool_middleware::{PreHook, ToolCall, ToolMiddleware};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::warn;
+
+/// A switchable feature
+#[derive(Debug, Clone, Copy)]
+pub struct FlagDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: bool,
+    /// Tools that are refused while the flag is off
+    pub tools: &'static [&'static str],
+}
+
+const fn flag(
+    name: &'static str,
+    description: &'static str,
+    default: bool,
+    tools: &'static [&'static str],
+) -> FlagDefinition {
+    FlagDefinition {
+        name,
+        description,
+        default,
+        tools,
+    }
+}
+
+/// Flags known to this release
+pub const FLAGS: &[FlagDefinition] = &[
+    flag(
+        "batch",
+        "Running several tools in one batch_execute call",
+        true,
+        &["batch_execute"],
+    ),
+    flag(
+        "guest_access",
+        "Creating temporary guest API keys",
+        true,
+        &["create_guest_access"],
+    ),
+    flag(
+        "program_backup",
+        "Scheduled Miniserver program backups and structure diffs",
+        true,
+        &["get_program_backups", "diff_structure"],
+    ),
+    flag(
+        "weather_protection",
+        "Retracting blinds on wind and rain",
+        true,
+        &["get_weather_protection", "override_weather_protection"],
+    ),
+    flag(
+        "energy_scheduler",
+        "Workflows scheduled for cheap or green energy",
+        true,
+        &["schedule_workflow", "get_scheduled_workflows"],
+    ),
+    flag(
+        "heating_diagnostics",
+        "Sampling valve positions for heating diagnostics",
+        true,
+        &["get_valve_diagnostics"],
+    ),
+    flag(
+        "presence_reports",
+        "Nightly presence reports",
+        true,
+        &["get_presence_report"],
+    ),
+    flag(
+        "device_usage",
+        "Device usage statistics",
+        true,
+        &["get_device_usage"],
+    ),
+    flag(
+        "miniserver_log",
+        "Reading the Miniserver system log",
+        true,
+        &["get_miniserver_log"],
+    ),
+    flag("power_monitor", "UPS and power-fail monitoring", true, &[]),
+    flag(
+        "load_shedding_log",
+        "Sampling load managers for the shed load history",
+        true,
+        &[],
+    ),
+    flag(
+        "notifications",
+        "E-mail and webhook notifications (the log channel stays on)",
+        true,
+        &[],
+    ),
+    flag("sampling", "Features that ask the client's LLM", false, &[]),
+];
+
+/// Look up a flag of [`FLAGS`] by name
+pub fn definition(name: &str) -> Option<&'static FlagDefinition> {
+    FLAGS.iter().find(|f| f.name == name)
+}
+
+/// Where flag values come from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureFlagsConfig {
+    /// Values overriding the built-in defaults
+    pub flags: BTreeMap<String, bool>,
+    /// JSON file with values overriding `flags`
+    pub file: Option<PathBuf>,
+}
+
+impl Default for FeatureFlagsConfig {
+    fn default() -> Self {
+        let flags = std::env::var("LOXONE_FEATURE_FLAGS")
+            .map(|list| parse_flag_list(&list))
+            .unwrap_or_default();
+        Self {
+            flags,
+            file: Some(
+                std::env::var("LOXONE_FEATURE_FLAGS_FILE")
+                    .ok()
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(default_flags_file),
+            ),
+        }
+    }
+}
+
+/// Default location of the flags file
+pub fn default_flags_file() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("loxone-mcp")
+        .join("feature_flags.json")
+}
+
+/// Parse `name=bool` pairs separated by commas; invalid pairs are logged
+fn parse_flag_list(list: &str) -> BTreeMap<String, bool> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .filter_map(|pair| {
+            let parsed = pair.split_once('=').and_then(|(name, value)| {
+                let enabled = match value.trim().to_lowercase().as_str() {
+                    "1" | "true" | "on" | "yes" => true,
+                    "0" | "false" | "off" | "no" => false,
+                    _ => return None,
+                };
+                Some((name.trim().to_string(), enabled))
+            });
+            if parsed.is_none() {
+                warn!("Ignoring invalid feature flag '{pair}', expected name=true|false");
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Current value of a flag and where it came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlagState {
+    pub name: &'static str,
+    pub enabled: bool,
+    /// `default`, `config` or `file`
+    pub source: &'static str,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Default)]
+struct StoredFlags {
+    modified: Option<SystemTime>,
+    flags: BTreeMap<String, bool>,
+}
+
+/// Flag values of this installation
+#[derive(Debug, Default)]
+pub struct FeatureFlags {
+    configured: BTreeMap<String, bool>,
+    file: Option<PathBuf>,
+    stored: Mutex<StoredFlags>,
+}
+
+impl FeatureFlags {
+    pub fn from_config(config: &FeatureFlagsConfig) -> Self {
+        for name in config.flags.keys() {
+            if definition(name).is_none() {
+                warn!("Ignoring unknown feature flag '{name}'");
+            }
+        }
+        Self {
+            configured: config.flags.clone(),
+            file: config.file.clone(),
+            stored: Mutex::new(StoredFlags::default()),
+        }
+    }
+
+    /// Values from the file, re-read when its modification time changes
+    fn stored(&self) -> BTreeMap<String, bool> {
+        let mut stored = self.stored.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(path) = &self.file else {
+            return stored.flags.clone();
+        };
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified != stored.modified {
+            stored.flags = match modified {
+                None => BTreeMap::new(),
+                Some(_) => std::fs::read_to_string(path)
+                    .map_err(LoxoneError::from)
+                    .and_then(|content| Ok(serde_json::from_str(&content)?))
+                    .unwrap_or_else(|e| {
+                        warn!("Ignoring feature flags file {}: {e}", path.display());
+                        BTreeMap::new()
+                    }),
+            };
+            stored.modified = modified;
+        }
+        stored.flags.clone()
+    }
+
+    fn state(&self, flag: &'static FlagDefinition, stored: &BTreeMap<String, bool>) -> FlagState {
+        let (enabled, source) = match (stored.get(flag.name), self.configured.get(flag.name)) {
+            (Some(&enabled), _) => (enabled, "file"),
+            (None, Some(&enabled)) => (enabled, "config"),
+            (None, None) => (flag.default, "default"),
+        };
+        FlagState {
+            name: flag.name,
+            enabled,
+            source,
+            description: flag.description,
+        }
+    }
+
+    /// Whether a flag is on; unknown flags are off
+    pub fn is_enabled(&self, name: &str) -> bool {
+        definition(name).is_some_and(|flag| self.state(flag, &self.stored()).enabled)
+    }
+
+    /// All flags with their current values
+    pub fn snapshot(&self) -> Vec<FlagState> {
+        let stored = self.stored();
+        FLAGS.iter().map(|flag| self.state(flag, &stored)).collect()
+    }
+
+    /// Disabled flag that refuses `tool`, if any
+    pub fn blocking(&self, tool: &str) -> Option<&'static FlagDefinition> {
+        let stored = self.stored();
+        FLAGS
+            .iter()
+            .filter(|flag| flag.tools.contains(&tool))
+            .find(|flag| !self.state(flag, &stored).enabled)
+    }
+
+    /// Store a value in the flags file
+    pub fn set(&self, name: &str, enabled: bool) -> Result<()> {
+        if definition(name).is_none() {
+            return Err(LoxoneError::invalid_input(format!(
+                "Unknown feature flag '{name}'"
+            )));
+        }
+        let path = self
+            .file
+            .as_ref()
+            .ok_or_else(|| LoxoneError::config("No feature flags file configured"))?;
+        let mut flags = self.stored();
+        flags.insert(name.to_string(), enabled);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&flags)?)?;
+        std::fs::rename(&tmp, path)?;
+        let mut stored = self.stored.lock().unwrap_or_else(|e| e.into_inner());
+        stored.modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        stored.flags = flags;
+        Ok(())
+    }
+}
+
+/// Middleware refusing tools whose feature is switched off
+pub struct FeatureFlagGate {
+    flags: std::sync::Arc<FeatureFlags>,
+}
+
+impl FeatureFlagGate {
+    pub fn new(flags: std::sync::Arc<FeatureFlags>) -> Self {
+        Self { flags }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for FeatureFlagGate {
+    fn name(&self) -> &'static str {
+        "feature_flags"
+    }
+
+    async fn before(&self, call: &ToolCall) -> PreHook {
+        let Some(flag) = self.flags.blocking(&call.tool) else {
+            return PreHook::Continue;
+        };
+        PreHook::Respond(Err(json!({
+            "error": "disabled",
+            "tool": call.tool,
+            "flag": flag.name,
+            "message": format!(
+                "'{}' is disabled on this installation (feature flag '{}')",
+                call.tool, flag.name
+            ),
+        })
+        .to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_overrides_config_and_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let flags = FeatureFlags::from_config(&FeatureFlagsConfig {
+            flags: parse_flag_list("batch=false, sampling=on, bogus"),
+            file: Some(dir.path().join("flags.json")),
+        });
+        assert!(!flags.is_enabled("batch"));
+        assert!(flags.is_enabled("sampling"));
+        assert!(flags.is_enabled("program_backup"));
+        assert_eq!(flags.blocking("batch_execute").unwrap().name, "batch");
+        assert!(flags.blocking("control_lights").is_none());
+
+        flags.set("guest_access", false).unwrap();
+        flags.set("batch", true).unwrap();
+        assert_eq!(
+            flags.blocking("create_guest_access").unwrap().name,
+            "guest_access"
+        );
+        assert!(flags.blocking("batch_execute").is_none());
+        let batch = flags
+            .snapshot()
+            .into_iter()
+            .find(|f| f.name == "batch")
+            .unwrap();
+        assert_eq!((batch.enabled, batch.source), (true, "file"));
+        assert!(flags.set("bogus", true).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Heating demand per zone
+//!
+//! Backs the `get_heating_zones` tool. Every `IRoomControllerV2` is a
+//! heating zone; its outputs tell whether the room is calling for heat:
+//!
+//! - `actuatingValueHeating` / `actuatingValueCooling`: valve opening the
+//!   controller demands, in percent (0.0-1.0 on some firmware)
+//! - `tempActual` and `tempTarget`: room and target temperature
+//!
+//! Controllers configured without actuating outputs do not report them; the
+//! opening of a valve actuator in the same room
+//! ([`crate::services::heating_diagnostics`]) is used instead, and failing
+//! that the demand is derived from the room being below its target.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Room controller types treated as heating zones
+pub const ZONE_CONTROLLER_TYPES: &[&str] = &["IRoomControllerV2"];
+
+/// Valve opening in percent from which a zone counts as calling
+pub const DEMAND_THRESHOLD_PERCENT: f64 = 1.0;
+
+/// Degrees below target from which a zone without valve data counts as
+/// calling for heat
+const TEMPERATURE_HYSTERESIS: f64 = 0.3;
+
+const HEATING_STATE: &str = "actuatingValueHeating";
+const COOLING_STATE: &str = "actuatingValueCooling";
+const ACTUAL_STATE: &str = "tempActual";
+const TARGET_STATE: &str = "tempTarget";
+
+/// What a zone asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Demand {
+    Heating,
+    Cooling,
+    Idle,
+    Unknown,
+}
+
+/// Where the valve opening came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValveOrigin {
+    /// Actuating output of the room controller
+    Controller,
+    /// Valve actuator in the same room
+    Actuator,
+    /// No valve data, demand derived from the temperatures
+    Temperature,
+}
+
+/// Demand of one heating zone
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ZoneStatus {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    /// Heating valve opening in percent
+    pub valve_percent: Option<f64>,
+    /// Cooling valve opening in percent
+    pub cooling_percent: Option<f64>,
+    pub room_temperature: Option<f64>,
+    pub target_temperature: Option<f64>,
+    pub demand: Demand,
+    pub valve_origin: ValveOrigin,
+}
+
+/// State UUIDs of a zone controller, keyed by state name
+pub fn state_uuids(control: &Value) -> HashMap<&'static str, String> {
+    [HEATING_STATE, COOLING_STATE, ACTUAL_STATE, TARGET_STATE]
+        .into_iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(name))
+                .and_then(Value::as_str)
+                .map(|uuid| (name, uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Percent from a 0-100 or 0.0-1.0 actuating value
+fn as_percent(value: f64) -> f64 {
+    let percent = if value > 0.0 && value <= 1.0 {
+        value * 100.0
+    } else {
+        value
+    };
+    (percent.clamp(0.0, 100.0) * 10.0).round() / 10.0
+}
+
+/// Evaluate a zone from its state values (keyed as in [`state_uuids`]) and
+/// the opening of a valve actuator in its room, if known
+pub fn evaluate(
+    uuid: &str,
+    name: &str,
+    room: Option<&str>,
+    values: &HashMap<&str, Value>,
+    actuator_percent: Option<f64>,
+) -> ZoneStatus {
+    let number = |state: &str| {
+        values
+            .get(state)
+            .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+    };
+    let (actual, target) = (number(ACTUAL_STATE), number(TARGET_STATE));
+    let cooling = number(COOLING_STATE).map(as_percent);
+    let (valve, valve_origin) = match number(HEATING_STATE) {
+        Some(value) => (Some(as_percent(value)), ValveOrigin::Controller),
+        None => match actuator_percent {
+            Some(percent) => (Some(percent), ValveOrigin::Actuator),
+            None => (None, ValveOrigin::Temperature),
+        },
+    };
+
+    let demand = if valve.is_some_and(|v| v >= DEMAND_THRESHOLD_PERCENT) {
+        Demand::Heating
+    } else if cooling.is_some_and(|v| v >= DEMAND_THRESHOLD_PERCENT) {
+        Demand::Cooling
+    } else if valve.is_some() {
+        Demand::Idle
+    } else {
+        match (actual, target) {
+            (Some(actual), Some(target)) if actual < target - TEMPERATURE_HYSTERESIS => {
+                Demand::Heating
+            }
+            (Some(_), Some(_)) => Demand::Idle,
+            _ => Demand::Unknown,
+        }
+    };
+
+    ZoneStatus {
+        uuid: uuid.to_string(),
+        name: name.to_string(),
+        room: room.map(str::to_string),
+        valve_percent: valve,
+        cooling_percent: cooling,
+        room_temperature: actual,
+        target_temperature: target,
+        demand,
+        valve_origin,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_zone_demand() {
+        let control = json!({"states": {"actuatingValueHeating": "s-h", "tempActual": "s-t"}});
+        assert_eq!(state_uuids(&control).len(), 2);
+
+        let values = HashMap::from([
+            (HEATING_STATE, json!(0.45)),
+            (ACTUAL_STATE, json!(20.1)),
+            (TARGET_STATE, json!("21.5")),
+        ]);
+        let zone = evaluate("z1", "Bath", Some("Bath"), &values, Some(10.0));
+        assert_eq!(zone.valve_percent, Some(45.0));
+        assert_eq!(zone.valve_origin, ValveOrigin::Controller);
+        assert_eq!(zone.demand, Demand::Heating);
+
+        // Actuator in the room when the controller has no output
+        let values = HashMap::from([(ACTUAL_STATE, json!(22.0)), (TARGET_STATE, json!(21.0))]);
+        let zone = evaluate("z2", "Office", None, &values, Some(0.0));
+        assert_eq!(zone.valve_origin, ValveOrigin::Actuator);
+        assert_eq!(zone.demand, Demand::Idle);
+
+        // Temperatures only
+        let values = HashMap::from([(ACTUAL_STATE, json!(19.0)), (TARGET_STATE, json!(21.0))]);
+        let zone = evaluate("z3", "Kids", None, &values, None);
+        assert_eq!(zone.valve_origin, ValveOrigin::Temperature);
+        assert_eq!(zone.demand, Demand::Heating);
+
+        let values = HashMap::from([(COOLING_STATE, json!(60)), (HEATING_STATE, json!(0))]);
+        assert_eq!(
+            evaluate("z4", "Attic", None, &values, None).demand,
+            Demand::Cooling
+        );
+        assert_eq!(
+            evaluate("z5", "Hall", None, &HashMap::new(), None).demand,
+            Demand::Unknown
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Hot water and boiler handling
+//!
+//! Loxone has no single hot water block. Installations typically combine:
+//!
+//! - a controller: a `Boiler`/`HotWater` control or an Intelligent Room
+//!   Controller named after the tank, with `tempActual`/`tempTarget` states
+//!   and a `Daytimer` sub-control holding the heating schedule
+//! - an analog sensor reporting the tank temperature
+//! - a switch or push button wired to the boost input
+//!
+//! All of them are recognised by type or by name ("hot water", "Warmwasser",
+//! "boiler", "DHW") so they can be reported and controlled together.
+//!
+//! The target temperature is set with `settemp/<°C>` on boiler blocks and
+//! with `setComfortTemperature/<°C>` on room controllers heating a tank.
+
+use crate::error::{LoxoneError, Result};
+use serde_json::Value;
+
+/// Control types that are always hot water controllers
+pub const HOT_WATER_CONTROL_TYPES: &[&str] = &["HotWater", "Boiler", "WaterHeater"];
+
+/// Name fragments identifying hot water controls of generic types
+const HOT_WATER_NAME_PATTERNS: &[&str] = &[
+    "hot water",
+    "hotwater",
+    "warmwasser",
+    "boiler",
+    "dhw",
+    "water heater",
+];
+
+/// Room controller types that may be set up to heat a tank
+const CONTROLLER_TYPES: &[&str] = &[
+    "IRoomControllerV2",
+    "IRoomController",
+    "Intelligent Room Controller",
+];
+
+/// States carrying the tank temperature, in order of preference
+const TANK_TEMPERATURE_STATES: &[&str] = &["tempActual", "temperature", "value"];
+
+/// Default boost duration in minutes
+pub const DEFAULT_BOOST_MINUTES: u32 = 60;
+
+/// Longest boost accepted in minutes
+pub const MAX_BOOST_MINUTES: u32 = 240;
+
+/// Tank temperature limits accepted for schedules and targets (°C)
+pub const MIN_TANK_TEMPERATURE: f64 = 30.0;
+pub const MAX_TANK_TEMPERATURE: f64 = 75.0;
+
+/// Role of a control in the hot water setup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotWaterRole {
+    /// Controller with target temperature and schedule
+    Controller,
+    /// Tank temperature sensor
+    Sensor,
+    /// Switch or push button triggering a boost
+    BoostInput,
+}
+
+impl HotWaterRole {
+    /// Classify a control, returning `None` for non hot water controls
+    pub fn of(control: &Value) -> Option<Self> {
+        let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+        if HOT_WATER_CONTROL_TYPES.contains(&control_type) {
+            return Some(Self::Controller);
+        }
+
+        let name = control
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_lowercase();
+        if !HOT_WATER_NAME_PATTERNS.iter().any(|p| name.contains(p)) {
+            return None;
+        }
+        match control_type {
+            t if CONTROLLER_TYPES.contains(&t) => Some(Self::Controller),
+            "Switch" | "Pushbutton" => Some(Self::BoostInput),
+            _ if tank_temperature_state(control).is_some() => Some(Self::Sensor),
+            _ => None,
+        }
+    }
+
+    /// Name used in tool output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Controller => "controller",
+            Self::Sensor => "sensor",
+            Self::BoostInput => "boost_input",
+        }
+    }
+}
+
+/// State UUID of the tank temperature
+pub fn tank_temperature_state(control: &Value) -> Option<String> {
+    let states = control.get("states")?;
+    TANK_TEMPERATURE_STATES
+        .iter()
+        .find_map(|name| states.get(*name).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+/// State UUID of the target temperature
+pub fn target_temperature_state(control: &Value) -> Option<String> {
+    control
+        .get("states")
+        .and_then(|s| s.get("tempTarget"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Build the command that boosts heating for `minutes`
+///
+/// Controllers get a timed override to comfort mode (mode id 1); boost
+/// inputs are pulsed (push buttons) or switched on (switches).
+pub fn boost_command(control: &Value, minutes: u32) -> Result<String> {
+    if minutes == 0 || minutes > MAX_BOOST_MINUTES {
+        return Err(LoxoneError::invalid_input(format!(
+            "Boost duration must be between 1 and {MAX_BOOST_MINUTES} minutes"
+        )));
+    }
+    let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+    match HotWaterRole::of(control) {
+        Some(HotWaterRole::Controller) => Ok(format!("override/1/{}", minutes * 60)),
+        Some(HotWaterRole::BoostInput) if control_type == "Pushbutton" => Ok("pulse".to_string()),
+        Some(HotWaterRole::BoostInput) => Ok("on".to_string()),
+        Some(HotWaterRole::Sensor) | None => Err(LoxoneError::invalid_input(
+            "Control cannot boost hot water heating",
+        )),
+    }
+}
+
+/// Build the command setting the tank target temperature
+pub fn target_temperature_command(control: &Value, temperature: f64) -> Result<String> {
+    if !(MIN_TANK_TEMPERATURE..=MAX_TANK_TEMPERATURE).contains(&temperature) {
+        return Err(LoxoneError::invalid_input(format!(
+            "Tank temperature must be between {MIN_TANK_TEMPERATURE}°C and {MAX_TANK_TEMPERATURE}°C"
+        )));
+    }
+    let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+    match HotWaterRole::of(control) {
+        Some(HotWaterRole::Controller) if CONTROLLER_TYPES.contains(&control_type) => {
+            Ok(format!("setComfortTemperature/{temperature}"))
+        }
+        Some(HotWaterRole::Controller) => Ok(format!("settemp/{temperature}")),
+        _ => Err(LoxoneError::invalid_input(
+            "Control has no hot water target temperature",
+        )),
+    }
+}
+
+/// One heating period of a hot water schedule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduleEntry {
+    /// Start in minutes since midnight
+    pub from: u32,
+    /// End in minutes since midnight
+    pub to: u32,
+    /// Tank temperature to hold, if not the controller default
+    pub temperature: Option<f64>,
+}
+
+impl ScheduleEntry {
+    /// Parse `HH:MM-HH:MM`, optionally followed by `@temperature`
+    pub fn parse(entry: &str) -> Result<Self> {
+        let invalid = || {
+            LoxoneError::invalid_input(format!(
+                "Invalid schedule entry '{entry}'. Use HH:MM-HH:MM or HH:MM-HH:MM@55"
+            ))
+        };
+        let (period, temperature) = match entry.trim().split_once('@') {
+            Some((period, temp)) => (
+                period,
+                Some(temp.trim().parse::<f64>().map_err(|_| invalid())?),
+            ),
+            None => (entry.trim(), None),
+        };
+        let (from, to) = parse_period(period).ok_or_else(invalid)?;
+        if from >= to {
+            return Err(LoxoneError::invalid_input(format!(
+                "Schedule entry '{entry}' must end after it starts"
+            )));
+        }
+        if let Some(temp) = temperature
+            && !(MIN_TANK_TEMPERATURE..=MAX_TANK_TEMPERATURE).contains(&temp)
+        {
+            return Err(LoxoneError::invalid_input(format!(
+                "Tank temperature must be between {MIN_TANK_TEMPERATURE}°C and {MAX_TANK_TEMPERATURE}°C"
+            )));
+        }
+        Ok(Self {
+            from,
+            to,
+            temperature,
+        })
+    }
+}
+
+/// Start and end of an `HH:MM-HH:MM` period in minutes since midnight
+pub fn parse_period(period: &str) -> Option<(u32, u32)> {
+    let (from, to) = period.split_once('-')?;
+    let minutes = |time: &str| -> Option<u32> {
+        let (h, m) = time.trim().split_once(':')?;
+        let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+        // 24:00 is allowed as the end of the day
+        (h < 24 && m < 60 || h == 24 && m == 0).then_some(h * 60 + m)
+    };
+    Some((minutes(from)?, minutes(to)?))
+}
+
+/// Build the `Daytimer` command replacing all entries for `mode_id`
+pub fn schedule_command(mode_id: u32, entries: &[ScheduleEntry]) -> Result<String> {
+    let mut sorted = entries.to_vec();
+    sorted.sort_by_key(|e| e.from);
+    if sorted.windows(2).any(|w| w[1].from < w[0].to) {
+        return Err(LoxoneError::invalid_input(
+            "Schedule entries must not overlap",
+        ));
+    }
+    let entries: Vec<String> = sorted
+        .iter()
+        .map(|e| {
+            let value = e
+                .temperature
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "1".to_string());
+            format!("{mode_id};{};{};0;{value}", e.from, e.to)
+        })
+        .collect();
+    Ok(format!("set/{}/{}", entries.len(), entries.join("/")))
+}
+
+/// Find the schedule (`Daytimer`) sub-control of a controller
+pub fn find_daytimer(control: &Value) -> Option<&str> {
+    control
+        .get("subControls")
+        .and_then(Value::as_object)?
+        .iter()
+        .find(|(_, sub)| {
+            sub.get("type")
+                .and_then(Value::as_str)
+                .is_some_and(|t| t.contains("Daytimer"))
+        })
+        .map(|(uuid, _)| uuid.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_roles_and_boost() {
+        let boiler = json!({"type": "Boiler", "name": "Tank"});
+        let irc = json!({"type": "IRoomControllerV2", "name": "Warmwasser"});
+        let button = json!({"type": "Pushbutton", "name": "Hot Water Boost"});
+        let sensor = json!({
+            "type": "InfoOnlyAnalog",
+            "name": "DHW Tank Top",
+            "states": {"value": "s-1"}
+        });
+        let living = json!({"type": "IRoomControllerV2", "name": "Living Room"});
+
+        assert_eq!(HotWaterRole::of(&boiler), Some(HotWaterRole::Controller));
+        assert_eq!(HotWaterRole::of(&irc), Some(HotWaterRole::Controller));
+        assert_eq!(HotWaterRole::of(&button), Some(HotWaterRole::BoostInput));
+        assert_eq!(HotWaterRole::of(&sensor), Some(HotWaterRole::Sensor));
+        assert_eq!(HotWaterRole::of(&living), None);
+
+        assert_eq!(boost_command(&irc, 30).unwrap(), "override/1/1800");
+        assert_eq!(boost_command(&button, 30).unwrap(), "pulse");
+        assert!(boost_command(&sensor, 30).is_err());
+        assert!(boost_command(&boiler, MAX_BOOST_MINUTES + 1).is_err());
+
+        assert_eq!(
+            target_temperature_command(&boiler, 55.0).unwrap(),
+            "settemp/55"
+        );
+        assert_eq!(
+            target_temperature_command(&irc, 52.5).unwrap(),
+            "setComfortTemperature/52.5"
+        );
+        assert!(target_temperature_command(&boiler, 80.0).is_err());
+        assert!(target_temperature_command(&button, 55.0).is_err());
+    }
+
+    #[test]
+    fn test_schedule_entries() {
+        let morning = ScheduleEntry::parse("06:00-07:30").unwrap();
+        let evening = ScheduleEntry::parse("18:00-24:00@60").unwrap();
+        assert_eq!(morning.from, 360);
+        assert_eq!(evening.to, 1440);
+        assert_eq!(
+            schedule_command(0, &[evening, morning]).unwrap(),
+            "set/2/0;360;450;0;1/0;1080;1440;0;60"
+        );
+
+        assert!(ScheduleEntry::parse("07:30-06:00").is_err());
+        assert!(ScheduleEntry::parse("06:00-07:00@90").is_err());
+        assert!(ScheduleEntry::parse("6am-7am").is_err());
+        let overlap = ScheduleEntry::parse("07:00-08:00").unwrap();
+        assert!(schedule_command(0, &[morning, overlap]).is_err());
+    }
+}
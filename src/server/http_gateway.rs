@@ -0,0 +1,1140 @@
+//! Authenticating front of the HTTP transports
+//!
+//! The framework's Streamable HTTP transport knows nothing about API keys,
+//! so it listens on loopback only and this gateway owns the public port.
+//! Every request must carry a key from the key store (`Authorization:
+//! Bearer`, `X-API-Key` or the `api_key` query parameter); requests with a
+//! valid key are passed through to the framework, others get `401`.
+//!
+//! Each MCP session is bound to the key that opened it (see
+//! [`HttpSessions`]); requests without an `Mcp-Session-Id` get a fresh one,
+//! a session used with another key gets `403`, and `DELETE /mcp` ends it.
+//!
+//! The gateway also serves the endpoints that are not MCP messages:
+//!
+//! - `GET /admin/api/consent` - consent requests waiting for the user
+//! - `POST /admin/api/consent/:id` - approve or deny one, e.g. `{"approved": true}`
+//! - `GET /events/poll?client_id=<session>` - resource change notifications
+//!   of a session, for clients that cannot hold an SSE stream (see
+//!   [`poll`](crate::server::subscription::poll))
+//!
+//! The consent endpoints need an admin key; a session's events are only
+//! served to the key that opened the session. In development mode requests
+//! without a key act as the local operator.
+
+use crate::error::{LoxoneError, Result};
+use crate::mcp_consent::ConsentResponse;
+use crate::security::caller::Caller;
+use crate::security::key_store::KeyStore;
+use crate::security::tenants::TenantRegistry;
+use crate::server::action_aliases::locale_from_accept_language;
+use crate::server::client_sessions::SessionTransport;
+use crate::server::macro_backend::LoxoneMcpServer;
+use crate::server::session_backend::HttpSessions;
+use crate::server::subscription::SubscriptionCoordinator;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Path, Request, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// Largest request body passed to the framework
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Paths of the framework transport that belong to an MCP session
+const SESSION_PATHS: [&str; 3] = ["/mcp", "/messages", "/sse"];
+
+/// Session header of the Streamable HTTP transport
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Where the gateway listens and forwards to
+#[derive(Debug, Clone, PartialEq)]
+pub struct GatewayConfig {
+    /// Public address of the HTTP transport
+    pub listen_addr: SocketAddr,
+    /// Loopback port of the framework transport
+    pub upstream_port: u16,
+}
+
+/// API key check in front of the framework transport
+pub struct HttpGateway {
+    server: LoxoneMcpServer,
+    keys: Arc<KeyStore>,
+    tenants: Option<Arc<TenantRegistry>>,
+    sessions: Arc<HttpSessions>,
+    subscriptions: Option<Arc<SubscriptionCoordinator>>,
+    dev_mode: bool,
+}
+
+impl HttpGateway {
+    /// Gateway for `server`, checking keys against `keys`
+    pub fn new(server: LoxoneMcpServer, keys: Arc<KeyStore>) -> Self {
+        Self {
+            server,
+            keys,
+            tenants: None,
+            sessions: Arc::new(HttpSessions::new(SessionTransport::Http)),
+            subscriptions: None,
+            dev_mode: false,
+        }
+    }
+
+    /// Tenants that tenant-scoped keys refer to
+    pub fn with_tenants(mut self, tenants: Option<Arc<TenantRegistry>>) -> Self {
+        self.tenants = tenants;
+        self
+    }
+
+    /// Transport the sessions are listed under (HTTP by default)
+    pub fn with_session_transport(mut self, transport: SessionTransport) -> Self {
+        self.sessions = Arc::new(HttpSessions::new(transport));
+        self
+    }
+
+    /// Serve the long-poll endpoint of `coordinator`'s notifications
+    pub fn with_subscriptions(mut self, coordinator: Arc<SubscriptionCoordinator>) -> Self {
+        self.subscriptions = Some(coordinator);
+        self
+    }
+
+    /// Let requests without a key act as the local operator
+    pub fn with_dev_mode(mut self, dev_mode: bool) -> Self {
+        self.dev_mode = dev_mode;
+        self
+    }
+
+    /// Sessions and their callers; serve them with a
+    /// [`SessionBackend`](crate::server::session_backend::SessionBackend)
+    pub fn sessions(&self) -> Arc<HttpSessions> {
+        self.sessions.clone()
+    }
+
+    /// Key store requests are checked against
+    pub fn keys(&self) -> Arc<KeyStore> {
+        self.keys.clone()
+    }
+
+    /// Whether requests without a key are accepted
+    pub fn is_dev_mode(&self) -> bool {
+        self.dev_mode
+    }
+
+    /// Serve the gateway; returns the bound address
+    pub async fn spawn(self, config: GatewayConfig) -> Result<SocketAddr> {
+        let listener = TcpListener::bind(config.listen_addr).await.map_err(|e| {
+            LoxoneError::config(format!(
+                "Failed to bind HTTP transport on {}: {e}",
+                config.listen_addr
+            ))
+        })?;
+        let local_addr = listener.local_addr()?;
+        let state = Arc::new(GatewayState {
+            gateway: self,
+            upstream: format!("http://127.0.0.1:{}", config.upstream_port),
+            http: reqwest::Client::new(),
+        });
+        info!(
+            "🔐 HTTP transport on http://{local_addr}/mcp (internal port {})",
+            config.upstream_port
+        );
+        tokio::spawn(async move {
+            let app = router(state).into_make_service_with_connect_info::<SocketAddr>();
+            if let Err(e) = axum::serve(listener, app).await {
+                warn!("HTTP gateway stopped: {e}");
+            }
+        });
+        Ok(local_addr)
+    }
+}
+
+struct GatewayState {
+    gateway: HttpGateway,
+    upstream: String,
+    http: reqwest::Client,
+}
+
+impl GatewayState {
+    /// Caller behind a request; `None` is the local operator in development mode
+    async fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        uri: &Uri,
+    ) -> std::result::Result<Option<Caller>, Response> {
+        let Some(key) = request_key(headers, uri) else {
+            if self.gateway.dev_mode {
+                return Ok(None);
+            }
+            return Err(unauthorized("API key required"));
+        };
+        Caller::resolve(&self.gateway.keys, &key, self.gateway.tenants.as_deref())
+            .await
+            .map(Some)
+            .map_err(|e| unauthorized(&e.to_string()))
+    }
+
+    /// Like [`Self::authenticate`], but only admin keys pass
+    async fn authenticate_admin(
+        &self,
+        headers: &HeaderMap,
+        uri: &Uri,
+    ) -> std::result::Result<(), Response> {
+        match self.authenticate(headers, uri).await? {
+            Some(caller) if !caller.is_admin() => {
+                Err((StatusCode::FORBIDDEN, "Admin key required").into_response())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// API key of a request, from the headers or the `api_key` query parameter
+fn request_key(headers: &HeaderMap, uri: &Uri) -> Option<String> {
+    let header_value = |name: HeaderName| headers.get(name).and_then(|v| v.to_str().ok());
+    header_value(header::AUTHORIZATION)
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| header_value(HeaderName::from_static("x-api-key")))
+        .map(|key| key.trim().to_string())
+        .or_else(|| {
+            url::form_urlencoded::parse(uri.query()?.as_bytes())
+                .find(|(name, _)| name == "api_key")
+                .map(|(_, key)| key.into_owned())
+        })
+        .filter(|key| !key.is_empty())
+}
+
+/// Headers that describe one hop, not the message
+fn is_hop_header(name: &HeaderName) -> bool {
+    [
+        header::HOST,
+        header::CONNECTION,
+        header::CONTENT_LENGTH,
+        header::TRANSFER_ENCODING,
+    ]
+    .contains(name)
+}
+
+fn unauthorized(reason: &str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        reason.to_string(),
+    )
+        .into_response()
+}
+
+fn router(state: Arc<GatewayState>) -> Router {
+    let poll = state.gateway.subscriptions.as_ref().map(|subscriptions| {
+        subscriptions
+            .poll_router()
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                authorize_poll,
+            ))
+    });
+    let app = Router::new()
+        .route("/admin/api/consent", get(list_consent_requests))
+        .route("/admin/api/consent/:id", post(answer_consent_request))
+        .fallback(forward)
+        .with_state(state);
+    match poll {
+        Some(poll) => app.merge(poll),
+        None => app,
+    }
+}
+
+/// Let a poll through only for a session of the caller's key
+async fn authorize_poll(
+    State(state): State<Arc<GatewayState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let caller = match state.authenticate(request.headers(), request.uri()).await {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let client_id = url::form_urlencoded::parse(request.uri().query().unwrap_or("").as_bytes())
+        .find(|(name, _)| name == "client_id")
+        .map(|(_, id)| id.into_owned());
+    match client_id {
+        Some(id) if !state.gateway.sessions.is_held_by(&id, &caller) => {
+            (StatusCode::FORBIDDEN, "Session belongs to another key").into_response()
+        }
+        _ => next.run(request).await,
+    }
+}
+
+/// Pass an authenticated request to the framework transport
+async fn forward(
+    State(state): State<Arc<GatewayState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let caller = match state.authenticate(&parts.headers, &parts.uri).await {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let body = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => return (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response(),
+    };
+    let mut path = parts
+        .uri
+        .path_and_query()
+        .map_or("/", |p| p.as_str())
+        .to_string();
+    if SESSION_PATHS.contains(&parts.uri.path()) {
+        let mut sessions = request_sessions(&parts.headers, &parts.uri);
+        let ending = parts.method == Method::DELETE;
+        if ending && sessions.is_empty() {
+            return (StatusCode::BAD_REQUEST, "Mcp-Session-Id required").into_response();
+        }
+        if sessions.is_empty() {
+            let id = uuid::Uuid::new_v4().to_string();
+            parts.headers.insert(
+                SESSION_HEADER,
+                HeaderValue::from_str(&id).expect("UUIDs are valid header values"),
+            );
+            // SSE streams name their session in the query
+            if parts.method == Method::GET {
+                let separator = if parts.uri.query().is_some() {
+                    '&'
+                } else {
+                    '?'
+                };
+                path = format!("{path}{separator}sessionId={id}");
+            }
+            sessions.push(id);
+        }
+        let locale = request_locale(&parts.headers, &body);
+        for id in &sessions {
+            if !state
+                .gateway
+                .sessions
+                .bind(id, caller.clone(), locale.clone(), Some(peer))
+            {
+                return (StatusCode::FORBIDDEN, "Session belongs to another key").into_response();
+            }
+        }
+        if ending {
+            for id in &sessions {
+                state.gateway.sessions.close(id);
+            }
+            return StatusCode::NO_CONTENT.into_response();
+        }
+    }
+    let mut upstream = state
+        .http
+        .request(parts.method, format!("{}{path}", state.upstream))
+        .body(body);
+    for (name, value) in &parts.headers {
+        if !is_hop_header(name) {
+            upstream = upstream.header(name, value);
+        }
+    }
+    match upstream.send().await {
+        Ok(response) => relay(response),
+        Err(e) => {
+            warn!("HTTP gateway could not reach the transport: {e}");
+            (StatusCode::BAD_GATEWAY, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Locale a request reads action words in: `_meta.locale` of the call,
+/// else the client's preferred language
+fn request_locale(headers: &HeaderMap, body: &[u8]) -> Option<String> {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|message| {
+            message
+                .pointer("/params/_meta/locale")
+                .and_then(|locale| locale.as_str())
+                .map(str::to_string)
+        })
+        .or_else(|| {
+            headers
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(locale_from_accept_language)
+        })
+}
+
+/// Sessions a request refers to: the session header, the `sessionId` of an
+/// SSE stream and the session of a resumed stream's `Last-Event-ID`
+fn request_sessions(headers: &HeaderMap, uri: &Uri) -> Vec<String> {
+    let header_value = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let query = uri.query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "sessionId")
+            .map(|(_, id)| id.into_owned())
+    });
+    let resumed = header_value("last-event-id")
+        .filter(|event| event.matches(':').count() == 2)
+        .and_then(|event| event.split(':').next())
+        .map(str::to_string);
+    header_value(SESSION_HEADER)
+        .map(str::to_string)
+        .into_iter()
+        .chain(query)
+        .chain(resumed)
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// Stream a framework response back to the client (SSE streams stay open)
+fn relay(upstream: reqwest::Response) -> Response {
+    let status = upstream.status();
+    let headers = upstream.headers().clone();
+    let chunks = futures::stream::unfold(Some(upstream), |upstream| async move {
+        let mut upstream = upstream?;
+        match upstream.chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(upstream))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    let mut response = Response::new(Body::from_stream(chunks));
+    *response.status_mut() = status;
+    for (name, value) in &headers {
+        if !is_hop_header(name) {
+            response.headers_mut().append(name, value.clone());
+        }
+    }
+    response
+}
+
+/// Consent requests waiting for the user
+async fn list_consent_requests(
+    State(state): State<Arc<GatewayState>>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Response {
+    if let Err(response) = state.authenticate_admin(&headers, &uri).await {
+        return response;
+    }
+    let pending = state
+        .gateway
+        .server
+        .consent_manager()
+        .pending_requests()
+        .await;
+    Json(serde_json::json!({ "pending": pending })).into_response()
+}
+
+/// Answer to a consent request
+#[derive(Debug, Deserialize)]
+struct ConsentAnswer {
+    approved: bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Approve or deny a waiting consent request
+async fn answer_consent_request(
+    State(state): State<Arc<GatewayState>>,
+    Path(request_id): Path<uuid::Uuid>,
+    headers: HeaderMap,
+    uri: Uri,
+    Json(answer): Json<ConsentAnswer>,
+) -> Response {
+    if let Err(response) = state.authenticate_admin(&headers, &uri).await {
+        return response;
+    }
+    let consent = state.gateway.server.consent_manager();
+    if !consent
+        .pending_requests()
+        .await
+        .iter()
+        .any(|request| request.id == request_id)
+    {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let response = ConsentResponse {
+        request_id,
+        approved: answer.approved,
+        reason: answer.reason,
+        responded_at: SystemTime::now(),
+        validity_duration: None,
+        apply_to_similar: false,
+        user_id: None,
+    };
+    match consent.process_response(response).await {
+        Ok(()) => Json(serde_json::json!({
+            "request_id": request_id,
+            "approved": answer.approved
+        }))
+        .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{
+        DeviceSpec, StructureBuilder, TestServer, assert_command_sent, assert_no_command_sent,
+        device_uuid, devices, sample_house,
+    };
+    use crate::security::guest_access;
+    use crate::security::key_store::{ApiKey, ApiKeyRole, KeyStoreBackend, KeyStoreConfig};
+    use crate::server::action_aliases::{ActionAliases, LocaleAliases};
+    use crate::server::session_backend::SessionBackend;
+    use crate::server::subscription::poll::POLL_PATH;
+    use crate::server::subscription::types::{
+        ResourceChange, ResourceChangeNotification, ResourceChangeType,
+    };
+    use crate::server::systemd::free_loopback_port;
+    use crate::services::command_history::key_label;
+    use chrono::Utc;
+    use pulseengine_mcp_server::McpServer;
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    const ADMIN_KEY: &str = "lmcp_admin_001_secret";
+    const OPERATOR_KEY: &str = "lmcp_operator_001_secret";
+    /// Operator key of the tenant renting the bedroom
+    const TENANT_KEY: &str = "lmcp_operator_002_secret";
+    const MONITOR_KEY: &str = "lmcp_monitor_001_secret";
+
+    fn key(id: &str, role: ApiKeyRole) -> ApiKey {
+        ApiKey {
+            id: id.to_string(),
+            name: id.to_string(),
+            role,
+            created_by: "admin".to_string(),
+            created_at: Utc::now(),
+            expires_at: None,
+            ip_whitelist: Vec::new(),
+            active: true,
+            last_used: None,
+            usage_count: 0,
+            metadata: HashMap::new(),
+            tenant: None,
+        }
+    }
+
+    /// Framework transport and gateway in front of a test server
+    struct TestGateway {
+        base: String,
+        keys: Arc<KeyStore>,
+        subscriptions: Arc<SubscriptionCoordinator>,
+        _framework: McpServer<SessionBackend>,
+    }
+
+    impl TestGateway {
+        async fn start(fixture: &TestServer) -> Self {
+            let keys = KeyStore::new(KeyStoreConfig {
+                backend: KeyStoreBackend::Memory,
+                file_path: None,
+                auto_save: false,
+                encrypt_at_rest: false,
+            })
+            .await
+            .unwrap();
+            keys.add_key(key(ADMIN_KEY, ApiKeyRole::Admin))
+                .await
+                .unwrap();
+            keys.add_key(key(OPERATOR_KEY, ApiKeyRole::Operator))
+                .await
+                .unwrap();
+            keys.add_key(key(MONITOR_KEY, ApiKeyRole::Monitor))
+                .await
+                .unwrap();
+            keys.add_key(ApiKey {
+                tenant: Some("bedroom".to_string()),
+                ..key(TENANT_KEY, ApiKeyRole::Operator)
+            })
+            .await
+            .unwrap();
+            let tenants =
+                TenantRegistry::from_toml("[[tenant]]\nid = \"bedroom\"\nrooms = [\"Bedroom\"]\n")
+                    .unwrap();
+            let keys = Arc::new(keys);
+            let server = fixture.server.clone().with_key_store(keys.clone());
+            let subscriptions = Arc::new(SubscriptionCoordinator::new().await.unwrap());
+            let gateway = HttpGateway::new(server.clone(), keys.clone())
+                .with_tenants(Some(Arc::new(tenants)))
+                .with_subscriptions(subscriptions.clone());
+
+            let upstream_port = free_loopback_port().unwrap();
+            let mut framework = SessionBackend::new(server, gateway.sessions())
+                .serve_http(upstream_port)
+                .await
+                .unwrap();
+            framework.start().await.unwrap();
+            let addr = gateway
+                .spawn(GatewayConfig {
+                    listen_addr: "127.0.0.1:0".parse().unwrap(),
+                    upstream_port,
+                })
+                .await
+                .unwrap();
+            Self {
+                base: format!("http://{addr}"),
+                keys,
+                subscriptions,
+                _framework: framework,
+            }
+        }
+
+        /// JSON-RPC request as a client would send it
+        fn rpc(&self, key: &str, method: &str, params: Value) -> reqwest::RequestBuilder {
+            reqwest::Client::new()
+                .post(format!("{}/mcp", self.base))
+                .bearer_auth(key)
+                .header(header::ACCEPT, "application/json")
+                .json(&json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": method,
+                    "params": params
+                }))
+        }
+
+        /// Call a tool in the background, as a client would
+        fn call_tool(
+            &self,
+            key: &str,
+            tool: &str,
+            arguments: Value,
+        ) -> tokio::task::JoinHandle<Value> {
+            let request = self.rpc(
+                key,
+                "tools/call",
+                json!({ "name": tool, "arguments": arguments }),
+            );
+            tokio::spawn(async move { rpc_message(request.send().await.unwrap()).await })
+        }
+
+        /// First consent request the user is asked for
+        async fn wait_for_consent(&self) -> String {
+            for _ in 0..500 {
+                let pending: Value = reqwest::Client::new()
+                    .get(format!("{}/admin/api/consent", self.base))
+                    .bearer_auth(ADMIN_KEY)
+                    .send()
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+                if let Some(id) = pending["pending"][0]["id"].as_str() {
+                    return id.to_string();
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            panic!("no consent request was raised");
+        }
+
+        async fn answer(&self, key: &str, request_id: &str, approved: bool) -> StatusCode {
+            reqwest::Client::new()
+                .post(format!("{}/admin/api/consent/{request_id}", self.base))
+                .bearer_auth(key)
+                .json(&json!({ "approved": approved }))
+                .send()
+                .await
+                .unwrap()
+                .status()
+        }
+    }
+
+    /// JSON-RPC message of a response
+    async fn rpc_message(response: reqwest::Response) -> Value {
+        let body = response.text().await.unwrap();
+        // Responses with progress notifications arrive as an SSE stream
+        let message = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .last()
+            .unwrap_or(&body);
+        serde_json::from_str(message.trim()).unwrap()
+    }
+
+    fn assert_rpc_ok(message: &Value) {
+        assert!(message["error"].is_null(), "{message}");
+        assert_ne!(message["result"]["isError"], json!(true), "{message}");
+    }
+
+    /// Call a consent-gated tool with an operator key and approve it with an admin key
+    async fn approve_over_http(fixture: &TestServer, tool: &str, arguments: Value) {
+        let gateway = TestGateway::start(fixture).await;
+        let call = gateway.call_tool(OPERATOR_KEY, tool, arguments);
+        let request_id = gateway.wait_for_consent().await;
+        assert_eq!(
+            gateway.answer(ADMIN_KEY, &request_id, true).await,
+            StatusCode::OK
+        );
+        assert_rpc_ok(&call.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_admin_approves_disarm_over_http() {
+        let structure = StructureBuilder::new()
+            .device("Hall", devices::alarm("Burglar Alarm"))
+            .build();
+        let fixture = TestServer::new(structure).await;
+        let gateway = TestGateway::start(&fixture).await;
+
+        let call = gateway.call_tool(OPERATOR_KEY, "control_alarm", json!({ "action": "disarm" }));
+        let request_id = gateway.wait_for_consent().await;
+        assert_eq!(
+            gateway.answer(OPERATOR_KEY, &request_id, true).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            gateway.answer(ADMIN_KEY, &request_id, true).await,
+            StatusCode::OK
+        );
+
+        assert_rpc_ok(&call.await.unwrap());
+        let alarm = device_uuid("Hall", "Burglar Alarm");
+        assert_command_sent(&fixture.client, &alarm, "off");
+    }
+
+    #[tokio::test]
+    async fn test_admin_approves_intercom_door_over_http() {
+        let mut structure = StructureBuilder::new()
+            .device("Entrance", DeviceSpec::new("Front Door", "Intercom"))
+            .build();
+        let intercom = device_uuid("Entrance", "Front Door");
+        structure.controls.get_mut(&intercom).unwrap()["subControls"] = json!({
+            "front-door-strike": {"name": "Türöffner", "type": "Pushbutton"}
+        });
+        let fixture = TestServer::new(structure).await;
+
+        approve_over_http(&fixture, "open_intercom_door", json!({})).await;
+        assert_command_sent(&fixture.client, "front-door-strike", "pulse");
+    }
+
+    #[tokio::test]
+    async fn test_admin_approves_opening_a_gate_over_http() {
+        let fixture = TestServer::new(sample_house()).await;
+
+        approve_over_http(
+            &fixture,
+            "control_gate",
+            json!({ "gate": "Garage Door", "action": "open" }),
+        )
+        .await;
+        let gate = device_uuid("Garage", "Garage Door");
+        assert_command_sent(&fixture.client, &gate, "open");
+    }
+
+    #[tokio::test]
+    async fn test_admin_approves_closing_the_pool_cover_over_http() {
+        let structure = StructureBuilder::new()
+            .device("Garden", DeviceSpec::new("Pool", "PoolController"))
+            .build();
+        let fixture = TestServer::new(structure).await;
+
+        approve_over_http(&fixture, "control_pool", json!({ "action": "cover_close" })).await;
+        let pool = device_uuid("Garden", "Pool");
+        assert_command_sent(&fixture.client, &pool, "coverClose");
+    }
+
+    #[tokio::test]
+    async fn test_admin_approves_a_smoke_alarm_test_over_http() {
+        let structure = StructureBuilder::new()
+            .device("Hall", DeviceSpec::new("Smoke Alarm", "SmokeAlarm"))
+            .build();
+        let fixture = TestServer::new(structure).await;
+
+        approve_over_http(&fixture, "test_safety_alarm", json!({})).await;
+        let smoke_alarm = device_uuid("Hall", "Smoke Alarm");
+        assert_command_sent(&fixture.client, &smoke_alarm, "testalarm");
+    }
+
+    #[tokio::test]
+    async fn test_requests_without_a_valid_key_are_rejected() {
+        let fixture = TestServer::new(StructureBuilder::new().build()).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let http = reqwest::Client::new();
+
+        let response = http
+            .get(format!("{}/admin/api/consent", gateway.base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = http
+            .post(format!("{}/mcp", gateway.base))
+            .bearer_auth("lmcp_admin_999_unknown")
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_key_only_commands_its_rooms_over_http() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let lights = |target: &str| json!({ "scope": "device", "target": target, "action": "on" });
+
+        let ceiling = device_uuid("Kitchen", "Ceiling");
+        let message = gateway
+            .call_tool(TENANT_KEY, "control_lights", lights(&ceiling))
+            .await
+            .unwrap();
+        assert!(
+            message["error"].is_object() || message["result"]["isError"] == json!(true),
+            "{message}"
+        );
+        assert_no_command_sent(&fixture.client, &ceiling);
+
+        let bedside = device_uuid("Bedroom", "Bedside");
+        let message = gateway
+            .call_tool(TENANT_KEY, "control_lights", lights(&bedside))
+            .await
+            .unwrap();
+        assert_rpc_ok(&message);
+        assert_command_sent(&fixture.client, &bedside, "on");
+
+        // The installation stays unscoped for other keys
+        let message = gateway
+            .call_tool(OPERATOR_KEY, "control_lights", lights(&ceiling))
+            .await
+            .unwrap();
+        assert_rpc_ok(&message);
+        assert_command_sent(&fixture.client, &ceiling, "on");
+    }
+
+    #[tokio::test]
+    async fn test_guest_key_minted_over_http_works_until_it_expires() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let message = gateway
+            .call_tool(
+                ADMIN_KEY,
+                "create_guest_access",
+                json!({ "name": "Visitor", "rooms": ["Kitchen"], "category": "lights" }),
+            )
+            .await
+            .unwrap();
+        assert_rpc_ok(&message);
+        let guest = message["result"]["structuredContent"]["api_key"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        // Valid at once, and limited to its grant
+        let ceiling = device_uuid("Kitchen", "Ceiling");
+        let message = gateway
+            .call_tool(
+                &guest,
+                "control_lights",
+                json!({ "scope": "device", "target": ceiling, "action": "on" }),
+            )
+            .await
+            .unwrap();
+        assert_rpc_ok(&message);
+        assert_command_sent(&fixture.client, &ceiling, "on");
+        let window = device_uuid("Kitchen", "Window");
+        let message = gateway
+            .call_tool(
+                &guest,
+                "control_blinds",
+                json!({ "target": window, "action": "down" }),
+            )
+            .await
+            .unwrap();
+        assert!(
+            message["error"].is_object() || message["result"]["isError"] == json!(true),
+            "{message}"
+        );
+        assert_no_command_sent(&fixture.client, &window);
+
+        let mut key = gateway.keys.get_key(&guest).await.unwrap();
+        key.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        gateway.keys.update_key(key).await.unwrap();
+        let response = gateway
+            .rpc(&guest, "tools/list", json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            guest_access::remove_expired(&gateway.keys).await.unwrap(),
+            [key_label(&guest)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_list_follows_the_devices_of_each_key() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let tools = |key: &'static str| {
+            let request = gateway.rpc(key, "tools/list", json!({}));
+            async move {
+                let message = rpc_message(request.send().await.unwrap()).await;
+                assert_rpc_ok(&message);
+                message["result"]["tools"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|tool| tool["name"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let all = tools(OPERATOR_KEY).await;
+        assert!(all.iter().any(|name| name == "control_gate"));
+        assert!(!all.iter().any(|name| name == "control_ev_charging"));
+        // The bedroom tenant has no gate
+        let bedroom = tools(TENANT_KEY).await;
+        assert!(!bedroom.iter().any(|name| name == "control_gate"));
+        assert!(bedroom.iter().any(|name| name == "list_rooms"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_list_is_described_in_the_request_locale() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let response = gateway
+            .rpc(OPERATOR_KEY, "tools/list", json!({}))
+            .header(header::ACCEPT_LANGUAGE, "de-DE,de;q=0.9")
+            .send()
+            .await
+            .unwrap();
+        let message = rpc_message(response).await;
+        let lights = message["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|tool| tool["name"] == "control_lights")
+            .unwrap();
+        assert_eq!(lights["title"], "Licht steuern");
+        assert!(
+            lights["description"]
+                .as_str()
+                .unwrap()
+                .starts_with("Einzelne Leuchten"),
+            "{lights}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sessions_cannot_be_taken_over_by_another_key() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gateway = TestGateway::start(&fixture).await;
+
+        let response = gateway
+            .rpc(OPERATOR_KEY, "tools/list", json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = response.headers()[SESSION_HEADER].clone();
+
+        let response = gateway
+            .rpc(TENANT_KEY, "tools/list", json!({}))
+            .header(SESSION_HEADER, session.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = reqwest::Client::new()
+            .get(format!("{}/mcp", gateway.base))
+            .bearer_auth(TENANT_KEY)
+            .query(&[("sessionId", session.to_str().unwrap())])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = gateway
+            .rpc(OPERATOR_KEY, "tools/list", json!({}))
+            .header(SESSION_HEADER, session)
+            .send()
+            .await
+            .unwrap();
+        assert_rpc_ok(&rpc_message(response).await);
+    }
+
+    #[tokio::test]
+    async fn test_each_session_is_a_client_with_the_capabilities_of_its_key() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let monitor_clients = || {
+            let call = gateway.call_tool(ADMIN_KEY, "get_connected_clients", json!({}));
+            async move {
+                let message = call.await.unwrap();
+                assert_rpc_ok(&message);
+                message["result"]["structuredContent"]["clients"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .filter(|client| client["name"] == "lmcp_monitor_001")
+                    .cloned()
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let mut sessions = Vec::new();
+        for _ in 0..2 {
+            let response = gateway
+                .rpc(MONITOR_KEY, "tools/list", json!({}))
+                .send()
+                .await
+                .unwrap();
+            sessions.push(response.headers()[SESSION_HEADER].clone());
+        }
+        let clients = monitor_clients().await;
+        assert_eq!(clients.len(), 2);
+        for client in &clients {
+            assert_eq!(client["transport"], "http", "{client}");
+            assert!(
+                !client["capabilities"]
+                    .as_array()
+                    .unwrap()
+                    .contains(&json!("admin")),
+                "{client}"
+            );
+        }
+
+        let response = reqwest::Client::new()
+            .delete(format!("{}/mcp", gateway.base))
+            .bearer_auth(MONITOR_KEY)
+            .header(SESSION_HEADER, sessions[0].clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(monitor_clients().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_polls_only_serve_sessions_of_the_callers_key() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let response = gateway
+            .rpc(OPERATOR_KEY, "tools/list", json!({}))
+            .send()
+            .await
+            .unwrap();
+        let session = response.headers()[SESSION_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let notification = ResourceChangeNotification::new(ResourceChange {
+            resource_uri: "loxone://devices/all".to_string(),
+            change_type: ResourceChangeType::DeviceState,
+            timestamp: SystemTime::now(),
+            previous_value: None,
+            new_value: json!({ "state": "on" }),
+            loxone_uuid: None,
+            metadata: HashMap::new(),
+        });
+        gateway
+            .subscriptions
+            .event_buffer()
+            .push(&session, notification)
+            .await;
+        let poll = |key: Option<&str>, client_id: &str| {
+            let request = reqwest::Client::new()
+                .get(format!("{}{POLL_PATH}", gateway.base))
+                .query(&[("client_id", client_id), ("timeout", "0")]);
+            match key {
+                Some(key) => request.bearer_auth(key),
+                None => request,
+            }
+            .send()
+        };
+
+        let response = poll(None, &session).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = poll(Some(TENANT_KEY), &session).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = poll(Some(OPERATOR_KEY), "not-a-session").await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = poll(Some(OPERATOR_KEY), &session).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let batch: Value = response.json().await.unwrap();
+        assert_eq!(batch["events"].as_array().unwrap().len(), 1, "{batch}");
+    }
+
+    #[tokio::test]
+    async fn test_access_codes_follow_the_role_of_each_request_key() {
+        let structure = StructureBuilder::new()
+            .device("Entrance", DeviceSpec::new("Keypad", "NfcCodeTouch"))
+            .build();
+        let fixture = TestServer::new(structure).await;
+        let keypad = device_uuid("Entrance", "Keypad");
+        fixture
+            .client
+            .respond(&keypad, Some("codes/get"), json!([]));
+        let gateway = TestGateway::start(&fixture).await;
+
+        let message = gateway
+            .call_tool(OPERATOR_KEY, "list_access_codes", json!({}))
+            .await
+            .unwrap();
+        assert!(
+            message.to_string().contains("requires an admin API key"),
+            "{message}"
+        );
+        let message = gateway
+            .call_tool(ADMIN_KEY, "list_access_codes", json!({}))
+            .await
+            .unwrap();
+        assert_rpc_ok(&message);
+    }
+
+    #[tokio::test]
+    async fn test_action_words_follow_the_locale_of_each_request() {
+        // "para" is Spanish for stop; in this Portuguese table it lowers blinds
+        ActionAliases::global().register(LocaleAliases::new("pt").alias("para", "down").unwrap());
+        let structure = StructureBuilder::new()
+            .device("Living", devices::blind("Left"))
+            .device("Living", devices::blind("Right"))
+            .device("Living", devices::blind("Door"))
+            .build();
+        let fixture = TestServer::new(structure).await;
+        let gateway = TestGateway::start(&fixture).await;
+        let blinds = |name: &str| {
+            json!({ "name": "control_blinds", "arguments": {
+                "target": device_uuid("Living", name),
+                "action": "para"
+            }})
+        };
+
+        let mut call = blinds("Left");
+        call["_meta"] = json!({ "locale": "pt-BR" });
+        let response = gateway
+            .rpc(OPERATOR_KEY, "tools/call", call)
+            .send()
+            .await
+            .unwrap();
+        assert_rpc_ok(&rpc_message(response).await);
+        assert_command_sent(&fixture.client, &device_uuid("Living", "Left"), "FullDown");
+
+        let response = gateway
+            .rpc(OPERATOR_KEY, "tools/call", blinds("Right"))
+            .header(header::ACCEPT_LANGUAGE, "pt-PT, en;q=0.5")
+            .send()
+            .await
+            .unwrap();
+        assert_rpc_ok(&rpc_message(response).await);
+        assert_command_sent(&fixture.client, &device_uuid("Living", "Right"), "FullDown");
+
+        let response = gateway
+            .rpc(OPERATOR_KEY, "tools/call", blinds("Door"))
+            .send()
+            .await
+            .unwrap();
+        assert_rpc_ok(&rpc_message(response).await);
+        assert_command_sent(&fixture.client, &device_uuid("Living", "Door"), "Stop");
+    }
+}
@@ -0,0 +1,188 @@
+//! Intercom and doorbell handling
+//!
+//! Loxone exposes door stations as `Intercom` controls (`IntercomV2` for the
+//! second generation). Their `states` map names to state UUIDs:
+//!
+//! - `bell`: 1 while someone is ringing
+//! - `lastBellEvents` (`lastBellTimestamps` on V2): the latest rings as
+//!   `YYYYMMDDhhmmss` timestamps in Miniserver local time, separated by `|`
+//!
+//! The door strike, light and other outputs of the door station are
+//! `subControls`, usually `Pushbutton`s that are pulsed. Releasing the door
+//! strike lets someone in, so `open_intercom_door` asks the user through the
+//! consent manager before the command is sent.
+
+use crate::error::{LoxoneError, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Control types handled as intercoms
+pub const INTERCOM_CONTROL_TYPES: &[&str] = &["Intercom", "IntercomV2"];
+
+/// Resource listing the latest bell events of all intercoms
+pub const INTERCOM_EVENTS_URI: &str = "loxone://intercom/events";
+
+/// States holding the latest bell events, by intercom generation
+const BELL_EVENT_STATES: &[&str] = &["lastBellEvents", "lastBellTimestamps"];
+
+/// Subcontrol types that can drive a door strike
+const OUTPUT_TYPES: &[&str] = &["Pushbutton", "Switch"];
+
+/// Output names that identify the door strike (English and German)
+const DOOR_KEYWORDS: &[&str] = &[
+    "door", "strike", "opener", "tür", "tuer", "öffner", "oeffner",
+];
+
+/// Command releasing the door strike
+pub const DOOR_COMMAND: &str = "pulse";
+
+/// An output of a door station
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IntercomOutput {
+    pub uuid: String,
+    pub name: String,
+    pub output_type: String,
+}
+
+impl IntercomOutput {
+    /// Whether the output's name marks it as the door strike
+    pub fn is_door(&self) -> bool {
+        let name = self.name.to_lowercase();
+        DOOR_KEYWORDS.iter().any(|keyword| name.contains(keyword))
+    }
+}
+
+/// Whether a control is an intercom
+pub fn is_intercom(control: &Value) -> bool {
+    control
+        .get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|t| INTERCOM_CONTROL_TYPES.contains(&t))
+}
+
+/// UUIDs of the `bell` state and the bell event state, if present
+pub fn bell_state_uuids(control: &Value) -> (Option<String>, Option<String>) {
+    let state = |name: &str| {
+        control
+            .get("states")
+            .and_then(|states| states.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    (
+        state("bell"),
+        BELL_EVENT_STATES.iter().find_map(|name| state(name)),
+    )
+}
+
+/// Bell events from the event state text, newest first
+///
+/// Unparsable entries are skipped.
+pub fn parse_bell_events(text: &str) -> Vec<NaiveDateTime> {
+    let mut events: Vec<NaiveDateTime> = text
+        .split(['|', ','])
+        .map(str::trim)
+        .filter_map(|entry| NaiveDateTime::parse_from_str(entry, "%Y%m%d%H%M%S").ok())
+        .collect();
+    events.sort_unstable_by(|a, b| b.cmp(a));
+    events
+}
+
+/// Outputs of a door station that can be pulsed, door strikes first
+pub fn outputs(control: &Value) -> Vec<IntercomOutput> {
+    let mut outputs: Vec<IntercomOutput> = control
+        .get("subControls")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(uuid, sub)| {
+            let output_type = sub.get("type").and_then(Value::as_str)?;
+            OUTPUT_TYPES.contains(&output_type).then(|| IntercomOutput {
+                uuid: uuid.clone(),
+                name: sub
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Unknown")
+                    .to_string(),
+                output_type: output_type.to_string(),
+            })
+        })
+        .collect();
+    outputs.sort_by(|a, b| b.is_door().cmp(&a.is_door()).then(a.name.cmp(&b.name)));
+    outputs
+}
+
+/// The output to pulse: the named one, or the door strike
+pub fn door_output(outputs: &[IntercomOutput], name: Option<&str>) -> Result<IntercomOutput> {
+    let found = match name {
+        Some(name) => {
+            let wanted = name.trim().to_lowercase();
+            outputs
+                .iter()
+                .find(|output| output.uuid == name || output.name.to_lowercase() == wanted)
+                .or_else(|| {
+                    outputs
+                        .iter()
+                        .find(|output| output.name.to_lowercase().contains(&wanted))
+                })
+        }
+        None => outputs.iter().find(|output| output.is_door()),
+    };
+    found.cloned().ok_or_else(|| {
+        LoxoneError::not_found(format!(
+            "No {} output on this intercom; available: {}",
+            name.unwrap_or("door strike"),
+            outputs
+                .iter()
+                .map(|output| output.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_bell_events() {
+        let events = parse_bell_events("20240115143000|20240116081502||garbage|20231224180000");
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].to_string(), "2024-01-16 08:15:02");
+        assert_eq!(events[2].to_string(), "2023-12-24 18:00:00");
+        assert!(parse_bell_events("").is_empty());
+    }
+
+    #[test]
+    fn test_door_output() {
+        let intercom = json!({
+            "name": "Front Door",
+            "type": "Intercom",
+            "states": {"bell": "s-bell", "lastBellEvents": "s-events"},
+            "subControls": {
+                "o-light": {"name": "Light", "type": "Pushbutton"},
+                "o-door": {"name": "Türöffner", "type": "Pushbutton"},
+                "o-info": {"name": "Status", "type": "InfoOnlyDigital"}
+            }
+        });
+        assert!(is_intercom(&intercom));
+        assert_eq!(
+            bell_state_uuids(&intercom),
+            (Some("s-bell".to_string()), Some("s-events".to_string()))
+        );
+
+        let outputs = outputs(&intercom);
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].uuid, "o-door");
+        assert_eq!(door_output(&outputs, None).unwrap().uuid, "o-door");
+        assert_eq!(
+            door_output(&outputs, Some("light")).unwrap().uuid,
+            "o-light"
+        );
+        assert!(door_output(&outputs, Some("Garage")).is_err());
+        assert!(door_output(&outputs[1..], None).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Irrigation controller handling
+//!
+//! Loxone's `Irrigation` block waters its zones one after the other. Its
+//! `states` map names to state UUIDs:
+//!
+//! - `zones`: JSON list of `{"id": 0, "name": "Lawn", "duration": 600}`,
+//!   durations in seconds
+//! - `currentZone`: ID of the zone being watered, -1 while idle
+//! - `time`: seconds left for the current zone
+//! - `rainActive`: 1 while the rain sensor suppresses watering
+//!
+//! Older configurations expose the zones as `subControls` instead, which is
+//! what [`LoxoneDevice::irrigation_zones`] reads. The block is controlled
+//! with `start` (all zones in order), `select/<id>` (a single zone), `stop`,
+//! and `setDuration/<id>=<seconds>` to change how long a zone is watered on
+//! every scheduled run.
+
+use crate::client::LoxoneDevice;
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as irrigation controllers
+pub const IRRIGATION_CONTROL_TYPES: &[&str] = &["Irrigation"];
+
+/// State names read for an irrigation status
+pub const IRRIGATION_STATE_NAMES: &[&str] = &["zones", "currentZone", "time", "rainActive"];
+
+/// Longest zone duration accepted (4 hours)
+pub const MAX_DURATION_SECS: u32 = 4 * 3600;
+
+/// One zone of an irrigation controller
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IrrigationZone {
+    pub id: i64,
+    pub name: String,
+    /// Watering time per run in seconds
+    #[serde(default)]
+    pub duration: Option<u32>,
+    /// Sub-control of the zone, if the zones are sub-controls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+}
+
+impl LoxoneDevice {
+    /// Irrigation zones defined as sub-controls, ordered by name
+    ///
+    /// Zone IDs are the position in that order, which is how the
+    /// Miniserver numbers them.
+    pub fn irrigation_zones(&self) -> Vec<IrrigationZone> {
+        zones_from_sub_controls(&self.sub_controls)
+    }
+}
+
+/// Irrigation zones from a control's sub-controls
+pub fn zones_from_sub_controls(sub_controls: &HashMap<String, Value>) -> Vec<IrrigationZone> {
+    let mut subs: Vec<(&String, &str, &Value)> = sub_controls
+        .iter()
+        .filter_map(|(uuid, sub)| Some((uuid, sub.get("name")?.as_str()?, sub)))
+        .collect();
+    subs.sort_by(|a, b| a.1.cmp(b.1));
+    subs.into_iter()
+        .enumerate()
+        .map(|(id, (uuid, name, sub))| IrrigationZone {
+            id: id as i64,
+            name: name.to_string(),
+            duration: sub
+                .pointer("/details/duration")
+                .and_then(Value::as_u64)
+                .map(|d| d as u32),
+            uuid: Some(uuid.clone()),
+        })
+        .collect()
+}
+
+/// Irrigation zones from a `zones` state value; unparsable entries are skipped
+pub fn parse_zones(value: &Value) -> Vec<IrrigationZone> {
+    let list = match value {
+        Value::String(text) => serde_json::from_str(text).unwrap_or(Value::Null),
+        other => other.clone(),
+    };
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|zone| serde_json::from_value(zone.clone()).ok())
+        .collect()
+}
+
+/// Snapshot of an irrigation controller's state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IrrigationStatus {
+    pub watering: bool,
+    /// Zone being watered
+    pub current_zone: Option<IrrigationZone>,
+    /// Seconds left for the current zone
+    pub remaining_secs: Option<u32>,
+    pub rain_active: bool,
+    pub zones: Vec<IrrigationZone>,
+}
+
+impl IrrigationStatus {
+    /// Build the status from state values keyed by state name
+    ///
+    /// `fallback_zones` are used when the controller has no `zones` state.
+    pub fn from_state_values(
+        values: &HashMap<String, Value>,
+        fallback_zones: Vec<IrrigationZone>,
+    ) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+
+        let zones = values
+            .get("zones")
+            .map(parse_zones)
+            .filter(|zones| !zones.is_empty())
+            .unwrap_or(fallback_zones);
+        let current_zone = number("currentZone")
+            .filter(|id| *id >= 0.0)
+            .and_then(|id| zones.iter().find(|zone| zone.id == id as i64))
+            .cloned();
+        let watering = current_zone.is_some();
+
+        Self {
+            watering,
+            remaining_secs: number("time")
+                .filter(|_| watering)
+                .map(|t| t.max(0.0).round() as u32),
+            current_zone,
+            rain_active: number("rainActive").is_some_and(|v| v != 0.0),
+            zones,
+        }
+    }
+}
+
+/// Find a zone by ID or name: exact name first, then a partial match
+pub fn find_zone<'a>(zones: &'a [IrrigationZone], query: &str) -> Result<&'a IrrigationZone> {
+    let found = if let Ok(id) = query.trim().parse::<i64>() {
+        zones.iter().find(|zone| zone.id == id)
+    } else {
+        let query = query.trim().to_lowercase();
+        zones
+            .iter()
+            .find(|zone| zone.name.to_lowercase() == query)
+            .or_else(|| {
+                zones
+                    .iter()
+                    .find(|zone| zone.name.to_lowercase().contains(&query))
+            })
+    };
+    found.ok_or_else(|| {
+        LoxoneError::not_found(format!(
+            "Irrigation zone '{query}' not found; available: {}",
+            zones
+                .iter()
+                .map(|zone| zone.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    })
+}
+
+/// Command watering only `zone`
+pub fn select_command(zone: &IrrigationZone) -> String {
+    format!("select/{}", zone.id)
+}
+
+/// Command changing how long `zone` is watered per run
+pub fn duration_command(zone: &IrrigationZone, minutes: u32) -> Result<String> {
+    let secs = minutes.saturating_mul(60);
+    if secs == 0 || secs > MAX_DURATION_SECS {
+        return Err(LoxoneError::invalid_input(format!(
+            "Duration must be between 1 and {} minutes",
+            MAX_DURATION_SECS / 60
+        )));
+    }
+    Ok(format!("setDuration/{}={secs}", zone.id))
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_status_from_zones_state() {
+        let zones = json!(
+            r#"[{"id":0,"name":"Lawn","duration":900},{"id":1,"name":"Hedge","duration":300}]"#
+        );
+        let idle = IrrigationStatus::from_state_values(
+            &values(&[("zones", zones.clone()), ("currentZone", json!(-1))]),
+            Vec::new(),
+        );
+        assert!(!idle.watering);
+        assert_eq!(idle.zones.len(), 2);
+        assert_eq!(idle.remaining_secs, None);
+
+        let watering = IrrigationStatus::from_state_values(
+            &values(&[
+                ("zones", zones),
+                ("currentZone", json!("1")),
+                ("time", json!(125.4)),
+                ("rainActive", json!(0)),
+            ]),
+            Vec::new(),
+        );
+        assert!(watering.watering);
+        assert_eq!(watering.current_zone.unwrap().name, "Hedge");
+        assert_eq!(watering.remaining_secs, Some(125));
+    }
+
+    #[test]
+    fn test_zones_from_sub_controls() {
+        let subs = HashMap::from([
+            (
+                "z-b".to_string(),
+                json!({"name": "Vegetables", "details": {"duration": 600}}),
+            ),
+            ("z-a".to_string(), json!({"name": "Front Lawn"})),
+        ]);
+        let zones = zones_from_sub_controls(&subs);
+        assert_eq!(zones[0].name, "Front Lawn");
+        assert_eq!(zones[1].id, 1);
+        assert_eq!(zones[1].duration, Some(600));
+
+        let status = IrrigationStatus::from_state_values(
+            &values(&[("currentZone", json!(0)), ("time", json!(30))]),
+            zones.clone(),
+        );
+        assert_eq!(status.current_zone.unwrap().uuid.as_deref(), Some("z-a"));
+
+        assert_eq!(find_zone(&zones, "veg").unwrap().id, 1);
+        assert_eq!(find_zone(&zones, "0").unwrap().name, "Front Lawn");
+        assert!(find_zone(&zones, "Pool").is_err());
+    }
+
+    #[test]
+    fn test_commands() {
+        let lawn = IrrigationZone {
+            id: 2,
+            name: "Lawn".to_string(),
+            duration: None,
+            uuid: None,
+        };
+        assert_eq!(select_command(&lawn), "select/2");
+        assert_eq!(duration_command(&lawn, 15).unwrap(), "setDuration/2=900");
+        assert!(duration_command(&lawn, 0).is_err());
+        assert!(duration_command(&lawn, 500).is_err());
+    }
+}
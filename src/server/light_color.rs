@@ -0,0 +1,265 @@
+//! Color and tunable-white light handling
+//!
+//! Loxone exposes RGB and tunable-white lights as `ColorPickerV2` controls,
+//! usually as sub-controls of a `LightControllerV2`. Their `color` state
+//! holds the current color as text, in the same format as the commands:
+//!
+//! - `hsv(<hue>,<saturation>,<value>)`: hue 0-360, saturation and value
+//!   (brightness) 0-100
+//! - `temp(<brightness>,<kelvin>)`: brightness 0-100 and color temperature
+//!   in Kelvin
+//!
+//! RGB values are converted to HSV before they are sent.
+
+use crate::error::{LoxoneError, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Control types handled as color pickers
+pub const COLOR_PICKER_TYPES: &[&str] = &["ColorPickerV2", "ColorPicker"];
+
+/// Color temperature range of Loxone tunable-white lights in Kelvin
+pub const KELVIN_RANGE: (u32, u32) = (2700, 6500);
+
+/// Color to set on a color picker
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum LightColor {
+    /// Hue 0-360, saturation and value 0-100
+    Hsv {
+        hue: f64,
+        saturation: f64,
+        value: f64,
+    },
+    /// Brightness 0-100 at a color temperature
+    Temperature { brightness: f64, kelvin: u32 },
+}
+
+impl LightColor {
+    /// HSV color, checking each component's range
+    pub fn hsv(hue: f64, saturation: f64, value: f64) -> Result<Self> {
+        check_range("Hue", hue, 0.0, 360.0)?;
+        check_range("Saturation", saturation, 0.0, 100.0)?;
+        check_range("Brightness", value, 0.0, 100.0)?;
+        Ok(Self::Hsv {
+            hue,
+            saturation,
+            value,
+        })
+    }
+
+    /// Tunable-white color, checking brightness and temperature
+    pub fn temperature(brightness: f64, kelvin: u32) -> Result<Self> {
+        check_range("Brightness", brightness, 0.0, 100.0)?;
+        let (min, max) = KELVIN_RANGE;
+        if !(min..=max).contains(&kelvin) {
+            return Err(LoxoneError::invalid_input(format!(
+                "Color temperature must be between {min}K and {max}K"
+            )));
+        }
+        Ok(Self::Temperature { brightness, kelvin })
+    }
+
+    /// HSV color of an RGB value
+    pub fn from_rgb(red: u8, green: u8, blue: u8) -> Self {
+        let (r, g, b) = (
+            f64::from(red) / 255.0,
+            f64::from(green) / 255.0,
+            f64::from(blue) / 255.0,
+        );
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+        Self::Hsv {
+            hue: hue.round(),
+            saturation: (saturation * 100.0).round(),
+            value: (max * 100.0).round(),
+        }
+    }
+
+    /// Miniserver command for the color
+    pub fn command(&self) -> String {
+        match self {
+            Self::Hsv {
+                hue,
+                saturation,
+                value,
+            } => format!("hsv({hue},{saturation},{value})"),
+            Self::Temperature { brightness, kelvin } => format!("temp({brightness},{kelvin})"),
+        }
+    }
+}
+
+/// RGB components from `#rrggbb`, `rrggbb` or `r,g,b`
+pub fn parse_rgb(text: &str) -> Result<(u8, u8, u8)> {
+    let text = text.trim();
+    let invalid = || {
+        LoxoneError::invalid_input(format!(
+            "Invalid RGB color '{text}'. Use #rrggbb or r,g,b with values 0-255"
+        ))
+    };
+
+    if text.contains(',') {
+        let parts: Vec<u8> = text
+            .split(',')
+            .map(|part| part.trim().parse::<u8>())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| invalid())?;
+        let [r, g, b] = parts[..] else {
+            return Err(invalid());
+        };
+        return Ok((r, g, b));
+    }
+
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Color from a `color` state text; `None` if it is not recognized
+pub fn parse_color_state(text: &str) -> Option<LightColor> {
+    let text = text.trim();
+    let (mode, args) = text.strip_suffix(')')?.split_once('(')?;
+    let numbers: Vec<f64> = args
+        .split(',')
+        .map(|n| n.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match (mode, numbers.as_slice()) {
+        ("hsv", &[hue, saturation, value]) => Some(LightColor::Hsv {
+            hue,
+            saturation,
+            value,
+        }),
+        ("temp", &[brightness, kelvin]) => Some(LightColor::Temperature {
+            brightness,
+            kelvin: kelvin.max(0.0).round() as u32,
+        }),
+        _ => None,
+    }
+}
+
+/// Color pickers of a control: the control itself, or its color picker
+/// sub-controls, as `(uuid, name, color state uuid)`
+pub fn color_pickers(uuid: &str, control: &Value) -> Vec<(String, String, Option<String>)> {
+    let picker = |uuid: &str, control: &Value| {
+        let control_type = control.get("type").and_then(Value::as_str)?;
+        COLOR_PICKER_TYPES.contains(&control_type).then(|| {
+            (
+                uuid.to_string(),
+                control
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Unknown")
+                    .to_string(),
+                control
+                    .pointer("/states/color")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            )
+        })
+    };
+
+    if let Some(found) = picker(uuid, control) {
+        return vec![found];
+    }
+    control
+        .get("subControls")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(uuid, sub)| picker(uuid, sub))
+        .collect()
+}
+
+fn check_range(name: &str, value: f64, min: f64, max: f64) -> Result<()> {
+    if !(min..=max).contains(&value) {
+        return Err(LoxoneError::invalid_input(format!(
+            "{name} must be between {min} and {max}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rgb_to_hsv() {
+        assert_eq!(LightColor::from_rgb(255, 0, 0).command(), "hsv(0,100,100)");
+        assert_eq!(
+            LightColor::from_rgb(0, 0, 255).command(),
+            "hsv(240,100,100)"
+        );
+        assert_eq!(
+            LightColor::from_rgb(255, 128, 0).command(),
+            "hsv(30,100,100)"
+        );
+        assert_eq!(LightColor::from_rgb(0, 0, 0).command(), "hsv(0,0,0)");
+
+        assert_eq!(parse_rgb("#FF8000").unwrap(), (255, 128, 0));
+        assert_eq!(parse_rgb("10, 20,30").unwrap(), (10, 20, 30));
+        assert!(parse_rgb("#FF80").is_err());
+        assert!(parse_rgb("256,0,0").is_err());
+        assert!(parse_rgb("1,2").is_err());
+    }
+
+    #[test]
+    fn test_validation_and_state() {
+        assert!(LightColor::hsv(361.0, 50.0, 50.0).is_err());
+        assert!(LightColor::hsv(120.0, 50.0, 101.0).is_err());
+        assert_eq!(
+            LightColor::temperature(80.0, 3000).unwrap().command(),
+            "temp(80,3000)"
+        );
+        assert!(LightColor::temperature(80.0, 2000).is_err());
+
+        assert_eq!(
+            parse_color_state("temp(60,4000)"),
+            Some(LightColor::Temperature {
+                brightness: 60.0,
+                kelvin: 4000
+            })
+        );
+        assert_eq!(
+            parse_color_state("hsv(120,50.5,100)"),
+            LightColor::hsv(120.0, 50.5, 100.0).ok()
+        );
+        assert_eq!(parse_color_state("rgb(1,2,3)"), None);
+    }
+
+    #[test]
+    fn test_color_pickers() {
+        let controller = json!({
+            "name": "Living Room",
+            "type": "LightControllerV2",
+            "subControls": {
+                "c-1": {"name": "LED Strip", "type": "ColorPickerV2", "states": {"color": "s-color"}},
+                "c-2": {"name": "Ceiling", "type": "Dimmer"}
+            }
+        });
+        let pickers = color_pickers("lc", &controller);
+        assert_eq!(pickers.len(), 1);
+        assert_eq!(pickers[0].0, "c-1");
+        assert_eq!(pickers[0].2.as_deref(), Some("s-color"));
+
+        let picker = json!({"name": "Lamp", "type": "ColorPickerV2"});
+        assert_eq!(color_pickers("p", &picker)[0].1, "Lamp");
+        assert!(color_pickers("d", &json!({"type": "Dimmer"})).is_empty());
+    }
+}
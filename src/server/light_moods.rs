@@ -0,0 +1,187 @@
+//! Moods of LightControllerV2 blocks
+//!
+//! A `LightControllerV2` groups the lights of a room and switches between
+//! moods ("Bright", "Movie", "Off", ...) configured by the installer or the
+//! user in the app. The structure file only references its states; the moods
+//! themselves are read at runtime:
+//!
+//! - `moodList`: JSON list of `{"name": "Movie", "id": 3, "static": false}`
+//! - `activeMoods`: JSON list of the active mood IDs (several when mixed)
+//!
+//! A mood is activated with `changeTo/<id>`, which replaces the active
+//! moods, or mixed in and out with `addMood/<id>` and `removeMood/<id>`.
+
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Control types with a mood list
+pub const MOOD_CONTROLLER_TYPES: &[&str] = &["LightControllerV2"];
+
+/// One mood of a light controller
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mood {
+    pub id: i64,
+    pub name: String,
+    /// Built-in moods ("Off", "Bright") cannot be edited in the app
+    #[serde(default, rename = "static")]
+    pub is_static: bool,
+}
+
+/// What to do with a mood
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoodAction {
+    /// Report the moods and which are active
+    List,
+    /// Make the mood the only active one
+    Activate,
+    /// Mix the mood into the active ones
+    Add,
+    /// Take the mood out of the mix
+    Remove,
+}
+
+impl MoodAction {
+    pub fn parse(action: &str) -> Result<Self> {
+        match action.to_lowercase().as_str() {
+            "list" => Ok(Self::List),
+            "activate" | "change" | "set" => Ok(Self::Activate),
+            "add" | "mix" => Ok(Self::Add),
+            "remove" | "unmix" => Ok(Self::Remove),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Unknown mood action '{other}'; use list, activate, add or remove"
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Activate => "activate",
+            Self::Add => "add",
+            Self::Remove => "remove",
+        }
+    }
+
+    /// Miniserver command applying the action to `mood`
+    pub fn command(self, mood: &Mood) -> Option<String> {
+        match self {
+            Self::List => None,
+            Self::Activate => Some(format!("changeTo/{}", mood.id)),
+            Self::Add => Some(format!("addMood/{}", mood.id)),
+            Self::Remove => Some(format!("removeMood/{}", mood.id)),
+        }
+    }
+}
+
+/// Whether a control has a mood list
+pub fn is_mood_controller(control: &Value) -> bool {
+    control
+        .get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|t| MOOD_CONTROLLER_TYPES.contains(&t))
+}
+
+/// UUID of a named state of the control
+pub fn state_uuid<'a>(control: &'a Value, state: &str) -> Option<&'a str> {
+    control
+        .get("states")
+        .and_then(|states| states.get(state))
+        .and_then(Value::as_str)
+}
+
+/// A state value holding JSON, either already parsed or as text
+fn json_state(value: &Value) -> Option<Value> {
+    let value = value
+        .get("value")
+        .or_else(|| value.get("LL").and_then(|ll| ll.get("value")))
+        .unwrap_or(value);
+    match value {
+        Value::String(text) => serde_json::from_str(text).ok(),
+        other => Some(other.clone()),
+    }
+}
+
+/// Moods from a `moodList` state value; unparsable entries are skipped
+pub fn parse_mood_list(value: &Value) -> Vec<Mood> {
+    json_state(value)
+        .and_then(|list| list.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|mood| serde_json::from_value(mood).ok())
+        .collect()
+}
+
+/// Mood IDs from an `activeMoods` state value
+pub fn parse_active_moods(value: &Value) -> Vec<i64> {
+    json_state(value)
+        .and_then(|list| list.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(Value::as_i64)
+        .collect()
+}
+
+/// Find a mood by ID or name: exact name first, then a partial match
+pub fn find_mood<'a>(moods: &'a [Mood], query: &str) -> Option<&'a Mood> {
+    if let Ok(id) = query.trim().parse::<i64>() {
+        return moods.iter().find(|mood| mood.id == id);
+    }
+    let query = query.trim().to_lowercase();
+    moods
+        .iter()
+        .find(|mood| mood.name.to_lowercase() == query)
+        .or_else(|| {
+            moods
+                .iter()
+                .find(|mood| mood.name.to_lowercase().contains(&query))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_find_moods() {
+        let list = json!({
+            "value": r#"[{"name":"Movie","id":3,"static":false,"used":1},
+                         {"name":"Bright","id":777,"static":true},
+                         {"name":"Off","id":778,"static":true},
+                         {"broken":true}]"#
+        });
+        let moods = parse_mood_list(&list);
+        assert_eq!(moods.len(), 3);
+        assert_eq!(find_mood(&moods, "movie").unwrap().id, 3);
+        assert_eq!(find_mood(&moods, "brig").unwrap().id, 777);
+        assert_eq!(find_mood(&moods, "778").unwrap().name, "Off");
+        assert!(find_mood(&moods, "Party").is_none());
+
+        assert_eq!(parse_active_moods(&json!("[3,777]")), vec![3, 777]);
+        assert!(parse_active_moods(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_actions() {
+        let movie = Mood {
+            id: 3,
+            name: "Movie".to_string(),
+            is_static: false,
+        };
+        assert_eq!(
+            MoodAction::parse("Activate").unwrap().command(&movie),
+            Some("changeTo/3".to_string())
+        );
+        assert_eq!(
+            MoodAction::parse("mix").unwrap().command(&movie),
+            Some("addMood/3".to_string())
+        );
+        assert_eq!(
+            MoodAction::parse("remove").unwrap().command(&movie),
+            Some("removeMood/3".to_string())
+        );
+        assert_eq!(MoodAction::parse("list").unwrap().command(&movie), None);
+        assert!(MoodAction::parse("dance").is_err());
+    }
+}
@@ -444,6 +444,7 @@ mod tests {
                     rooms,
                     cats: HashMap::new(),
                     global_states: HashMap::new(),
+                    operating_modes: HashMap::new(),
                 },
             }
         }
@@ -7,13 +7,100 @@
 //! - Parameter validation
 //! - Error handling
 
-use crate::client::{ClientContext, LoxoneClient, LoxoneStructure};
+use crate::client::api_budget::{ApiBudget, BudgetedClient};
+use crate::client::device_types::DeviceTypeRegistry;
+use crate::client::{
+    ClientContext, LoxoneClient, LoxoneStructure, RecordingClient, TenantScopedClient,
+    WatchingClient, categories, statistics,
+};
 use crate::config::ServerConfig;
-use crate::services::{StateManager, UnifiedValueResolver};
+use crate::config::room_metadata::Orientation;
+use crate::mcp_consent::ConsentManager;
+use crate::monitoring::lifetime_metrics::LifetimeMetrics;
+use crate::notifications::NotificationDispatcher;
+use crate::performance::metrics::{MetricsCollector, MetricsConfig};
+use crate::sampling::budget::{SamplingBudget, current_client};
+use crate::security::caller::Caller;
+use crate::security::guest_access::{self, GuestGrant};
+use crate::security::key_store::KeyStore;
+use crate::server::access::{self, GateAction, GateStatus};
+use crate::server::action_aliases::ActionAliases;
+use crate::server::air_conditioning::{self, AcMode, AcSettings, AcStatus, Capabilities};
+use crate::server::air_devices;
+use crate::server::alarm::{self, AlarmAction, AlarmStatus};
+use crate::server::audio::{self, AudioZone};
+use crate::server::batch::BatchPlan;
+use crate::server::blinds;
+use crate::server::bulk_states::{self, StateQuery, StateValue};
+use crate::server::client_sessions::{
+    CLIENTS_RESOURCE_URI, ClientSessionRegistry, SessionTransport,
+};
+use crate::server::climate_schedule;
+use crate::server::code_touch;
+use crate::server::connection_limits::ConnectionLimiter;
+use crate::server::device_diagnostics;
+use crate::server::device_index::DeviceIndex;
+use crate::server::device_lock;
+use crate::server::device_macro::{self, StepStatus};
+use crate::server::device_search;
+use crate::server::energy_flow::{self, ENERGY_FLOW_URI, EnergyFlow, FlowQuantity};
+use crate::server::feature_flags::{FeatureFlagGate, FeatureFlags};
+use crate::server::heating_zones::{self, Demand};
+use crate::server::hot_water::{self, HotWaterRole};
+use crate::server::intercom::{self, INTERCOM_EVENTS_URI};
+use crate::server::irrigation::{self, IrrigationStatus};
+use crate::server::light_color::{self, LightColor};
+use crate::server::light_moods::{self, MoodAction};
+use crate::server::operating_modes;
+use crate::server::pool::{self, PoolAction, PoolStatus};
+use crate::server::room_comfort::{self, Reading};
+use crate::server::room_suggestions;
+use crate::server::safety::{self, SAFETY_STATUS_URI, SafetyKind, SafetyStatus};
+use crate::server::shortcuts;
+use crate::server::state_confirmation::{DEFAULT_CONFIRM_TIMEOUT, PendingConfirmations};
+use crate::server::state_diff::{self, DeviceChange};
+use crate::server::status_page::StatusProbe;
+use crate::server::storm_protection;
+use crate::server::timers::{self, TimerState};
+use crate::server::tool_descriptions::ToolDescriptions;
+use crate::server::tool_middleware::{
+    ConfirmationMiddleware, ConsentMiddleware, LifetimeMetricsMiddleware, ToolCall, ToolMiddleware,
+    ToolMiddlewareChain,
+};
+use crate::server::tool_registry::{CapabilityGate, ToolAvailability};
+use crate::server::tool_timeouts::ToolTimeouts;
+use crate::server::topology;
+use crate::server::tracker;
+use crate::server::ventilation::{self, VentilationMode, VentilationStatus};
+use crate::server::virtual_inputs::{self, VirtualInputKind};
+use crate::server::virtual_outputs::{self, VirtualOutput};
+use crate::server::wallbox::{self, ChargingAction, WALLBOX_URI, WallboxStatus};
+use crate::server::weather_forecast;
+use crate::services::announcements::{self, Announcer};
+use crate::services::command_history::{
+    COMMAND_HISTORY_URI, CommandFilter, CommandHistory, CommandOrigin, key_label,
+};
+use crate::services::countdowns::{self, Countdowns};
+use crate::services::device_usage::DeviceUsageStore;
+use crate::services::energy_scheduler::{self, EnergyPreference, EnergyScheduler, WorkflowStep};
+use crate::services::favorites::{self, Favorites, Rating};
+use crate::services::heating_diagnostics::{HeatingDiagnostics, ValveSource};
+use crate::services::load_manager::{self, LoadManagerStatus, LoadSheddingLog};
+use crate::services::miniserver_log::{self, LogSeverity};
+use crate::services::power_monitor::PowerMonitor;
+use crate::services::presence_report::PresenceReportStore;
+use crate::services::program_backup::{ProgramBackup, RESTORE_RESOURCE_URI};
+use crate::services::scene_snapshot::{self, SceneSnapshot, SceneSnapshots, SnapshotDevice};
+use crate::services::tariff::{self, SolarProfile, TariffProfile};
+use crate::services::weather_protection::{self, WeatherProtection};
+use crate::services::{StateManager, UnifiedValueResolver, UnitSystem};
+use humantime_serde::re::humantime;
 use pulseengine_mcp_macros::{mcp_server, mcp_tools};
+use pulseengine_mcp_protocol::Tool;
+use pulseengine_mcp_server::McpToolsProvider;
 use serde_json::{Value, json};
 use std::sync::Arc;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 /// Loxone MCP Server with macro-based tool definitions
 ///
@@ -36,6 +123,54 @@ pub struct LoxoneMcpServer {
     state_manager: Option<Arc<StateManager>>,
     /// Server configuration (for future use)
     config: Option<ServerConfig>,
+    /// Latest presence heatmap, regenerated nightly
+    presence_reports: Arc<PresenceReportStore>,
+    /// Rolling valve samples for heating diagnostics
+    heating_diagnostics: Arc<HeatingDiagnostics>,
+    /// Per-tool execution budgets
+    tool_timeouts: Arc<ToolTimeouts>,
+    /// Hooks run around every tool call
+    tool_middleware: Arc<ToolMiddlewareChain>,
+    /// Audit log of device commands sent by tools
+    command_history: Arc<CommandHistory>,
+    /// Label of the API key the server runs under, recorded with commands
+    api_key_label: Option<String>,
+    /// Scheduled backups of the Miniserver program
+    program_backup: Arc<ProgramBackup>,
+    /// Workflows waiting for PV surplus
+    energy_scheduler: Arc<EnergyScheduler>,
+    /// Wind and rain protection for awnings and skylights
+    weather_protection: Arc<WeatherProtection>,
+    /// Running switch counts and on-hours per device
+    device_usage: Arc<DeviceUsageStore>,
+    /// Miniserver request budget; background jobs yield to tool calls
+    api_budget: Arc<ApiBudget>,
+    power_monitor: Arc<PowerMonitor>,
+    /// Loads shed by the load managers during the last day
+    load_shedding: Arc<LoadSheddingLog>,
+    /// Timer runs with a custom duration
+    countdowns: Arc<Countdowns>,
+    /// Announcements waiting to be spoken
+    announcer: Arc<Announcer>,
+    /// Named snapshots of room lighting, blinds and climate
+    scene_snapshots: Arc<SceneSnapshots>,
+    /// Favorite and rating overrides of controls
+    favorites: Arc<Favorites>,
+    /// Request, command and error totals kept across restarts
+    lifetime_metrics: Arc<LifetimeMetrics>,
+    /// Tool groups and background jobs switched off for this installation
+    feature_flags: Arc<FeatureFlags>,
+    /// Sampling token and cost usage per client and day
+    sampling_budget: Arc<SamplingBudget>,
+    /// Consent requests of tools that disarm, unlock or open the house
+    consent: Arc<ConsentManager>,
+    /// Key store of the transport, for guest keys
+    key_store: Option<Arc<KeyStore>>,
+    /// Client session this server instance serves
+    client_session: Option<String>,
+    /// Whether the client may use admin-only tools and resources; follows
+    /// the key of each request on HTTP (see [`Self::for_caller`])
+    admin_session: bool,
 }
 
 impl LoxoneMcpServer {
@@ -48,13 +183,337 @@ impl LoxoneMcpServer {
         config: ServerConfig,
     ) -> Self {
         info!("Initializing Loxone MCP Server with macro-based tools");
+        let performance_metrics = MetricsCollector::new(MetricsConfig::default())
+            .map(Arc::new)
+            .map_err(|e| warn!("Tool performance metrics disabled: {e}"))
+            .ok();
+        let mut tool_timeouts = ToolTimeouts::new(config.mcp.tools.timeouts.clone());
+        if let Some(metrics) = &performance_metrics {
+            tool_timeouts = tool_timeouts.with_metrics(metrics.clone());
+        }
+        let tool_timeouts = Arc::new(tool_timeouts);
+        let mut tool_middleware =
+            ToolMiddlewareChain::from_config(&config.mcp.tools.middleware, performance_metrics);
+        let lifetime_metrics = Arc::new(LifetimeMetrics::from_config(&config.lifetime_metrics));
+        tool_middleware.push(Arc::new(LifetimeMetricsMiddleware::new(
+            lifetime_metrics.clone(),
+        )));
+        tool_middleware.push(Arc::new(CapabilityGate::new(context.clone())));
+        let feature_flags = Arc::new(FeatureFlags::from_config(&config.feature_flags));
+        tool_middleware.push(Arc::new(FeatureFlagGate::new(feature_flags.clone())));
+        let consent = Arc::new(ConsentManager::with_config(config.consent.clone()));
+        tool_middleware.push(Arc::new(ConsentMiddleware::new(consent.clone())));
+        tool_middleware.push(Arc::new(ConfirmationMiddleware::new(
+            DEFAULT_CONFIRM_TIMEOUT,
+        )));
+        let command_history = Arc::new(CommandHistory::from_config(&config.command_history));
+        let program_backup = Arc::new(ProgramBackup::new(config.program_backup.clone()));
+        let weather_protection =
+            Arc::new(WeatherProtection::new(config.weather_protection.clone()));
+        let api_budget = Arc::new(ApiBudget::new(config.api_budget.clone()));
+        let sampling_budget = Arc::new(SamplingBudget::new(config.sampling_budget.clone()));
+        let notifier = if feature_flags.is_enabled("notifications") {
+            NotificationDispatcher::from_env().unwrap_or_else(|e| {
+                warn!("Notification channels unavailable, using the log only: {e}");
+                NotificationDispatcher::default()
+            })
+        } else {
+            info!("Notifications disabled by feature flag, using the log only");
+            NotificationDispatcher::default()
+        };
+        let power_monitor = Arc::new(PowerMonitor::new(
+            config.power_monitor.clone(),
+            api_budget.clone(),
+            Arc::new(notifier),
+        ));
+        let client: Arc<dyn LoxoneClient> =
+            Arc::new(BudgetedClient::new(client, api_budget.clone()));
+        let client: Arc<dyn LoxoneClient> = Arc::new(
+            RecordingClient::new(client, command_history.clone())
+                .with_lifetime_metrics(lifetime_metrics.clone()),
+        );
+        let client: Arc<dyn LoxoneClient> = Arc::new(WatchingClient::new(client));
+        let scene_snapshots = Arc::new(SceneSnapshots::new(&config.scene_snapshots));
         Self {
             client: Some(client),
             context: Some(context),
             value_resolver: Some(value_resolver),
             state_manager,
             config: Some(config),
+            presence_reports: Arc::new(PresenceReportStore::default()),
+            heating_diagnostics: Arc::new(HeatingDiagnostics::new()),
+            tool_timeouts,
+            tool_middleware: Arc::new(tool_middleware),
+            command_history,
+            api_key_label: None,
+            program_backup,
+            energy_scheduler: Arc::new(EnergyScheduler::new()),
+            weather_protection,
+            device_usage: Arc::new(DeviceUsageStore::new()),
+            api_budget,
+            power_monitor,
+            load_shedding: Arc::new(LoadSheddingLog::new()),
+            countdowns: Arc::new(Countdowns::new()),
+            announcer: Arc::new(Announcer::new()),
+            scene_snapshots,
+            favorites: Arc::new(Favorites::new()),
+            lifetime_metrics,
+            feature_flags,
+            sampling_budget,
+            consent,
+            key_store: None,
+            client_session: None,
+            admin_session: false,
+        }
+    }
+
+    /// Record commands as issued under this API key
+    pub fn with_api_key(mut self, key_id: &str) -> Self {
+        self.api_key_label = Some(key_label(key_id));
+        self
+    }
+
+    /// Mint guest keys into `keys`, the store the transport checks keys against
+    pub fn with_key_store(mut self, keys: Arc<KeyStore>) -> Self {
+        self.key_store = Some(keys);
+        self
+    }
+
+    /// Add a middleware after the configured ones
+    pub fn with_tool_middleware(mut self, middleware: Arc<dyn ToolMiddleware>) -> Self {
+        Arc::make_mut(&mut self.tool_middleware).push(middleware);
+        self
+    }
+
+    /// Register the client session served over `transport`
+    ///
+    /// `capabilities` are the operations the client's API key grants; an
+    /// `admin` capability unlocks the connected clients listing. Call after
+    /// [`Self::with_api_key`] so the session is named after the key.
+    pub fn with_client_session(
+        mut self,
+        transport: SessionTransport,
+        capabilities: Vec<String>,
+    ) -> Self {
+        let registry = ClientSessionRegistry::global();
+        let id = registry.open(transport, None);
+        registry.identify(&id, self.api_key_label.clone(), capabilities.clone());
+        self.in_client_session(id, &capabilities)
+    }
+
+    /// Run tool calls as part of the registered client session `id`
+    ///
+    /// For transports that register their sessions themselves, such as the
+    /// HTTP gateway; `capabilities` are those the session was identified
+    /// with.
+    pub fn in_client_session(mut self, id: String, capabilities: &[String]) -> Self {
+        self.admin_session = capabilities.iter().any(|c| c == "admin");
+        self.client_session = Some(id);
+        self
+    }
+
+    /// Start background jobs that need a connected client context
+    ///
+    /// Must be called from within a Tokio runtime.
+    ///
+    /// Jobs whose feature flag is off are not started.
+    pub fn start_background_jobs(&self) {
+        let enabled = |flag: &str| {
+            let enabled = self.feature_flags.is_enabled(flag);
+            if !enabled {
+                info!("Background job '{flag}' disabled by feature flag");
+            }
+            enabled
+        };
+        if let Some(context) = &self.context {
+            if enabled("presence_reports") {
+                self.presence_reports.start_nightly(context.clone());
+            }
+            if enabled("device_usage") {
+                self.device_usage.start_updates(context.clone());
+            }
+        }
+        if let Some(client) = &self.client {
+            if enabled("heating_diagnostics") {
+                self.heating_diagnostics.start_sampling(client.clone());
+            }
+            if enabled("program_backup") {
+                self.program_backup.start_schedule(client.clone());
+            }
+            if enabled("energy_scheduler") {
+                self.energy_scheduler.start(client.clone());
+            }
+            if enabled("weather_protection") {
+                self.weather_protection.start(client.clone());
+            }
+            if enabled("power_monitor") {
+                self.power_monitor.start(client.clone());
+            }
+            if enabled("load_shedding_log") {
+                self.load_shedding.start(client.clone());
+            }
+        }
+        self.lifetime_metrics.start_autosave();
+        crate::monitoring::metrics::get_metrics()
+            .attach_lifetime_metrics(self.lifetime_metrics.clone());
+    }
+
+    /// Feature flags of this installation
+    pub fn feature_flags(&self) -> &Arc<FeatureFlags> {
+        &self.feature_flags
+    }
+
+    /// Coarse health source for the public status page
+    pub fn status_probe(&self) -> StatusProbe {
+        StatusProbe::new(self.client.clone(), self.api_budget.clone())
+    }
+
+    /// Sampling budget; pass it to the sampling client manager
+    pub fn sampling_budget(&self) -> &Arc<SamplingBudget> {
+        &self.sampling_budget
+    }
+
+    /// Lifetime totals of this server; save them on shutdown
+    pub fn lifetime_metrics(&self) -> &Arc<LifetimeMetrics> {
+        &self.lifetime_metrics
+    }
+
+    /// Consent requests waiting for the user; answer them here
+    pub fn consent_manager(&self) -> &Arc<ConsentManager> {
+        &self.consent
+    }
+
+    /// Run a tool body through the middleware chain within the tool's
+    /// configured time budget
+    async fn run_tool<F>(&self, tool: &str, body: F) -> std::result::Result<Value, String>
+    where
+        F: std::future::Future<Output = std::result::Result<Value, String>>,
+    {
+        self.run_tool_with(tool, Value::Null, body).await
+    }
+
+    /// [`Self::run_tool`] for tools whose arguments middlewares act on,
+    /// such as consent-gated tools
+    async fn run_tool_with<F>(
+        &self,
+        tool: &str,
+        arguments: Value,
+        body: F,
+    ) -> std::result::Result<Value, String>
+    where
+        F: std::future::Future<Output = std::result::Result<Value, String>>,
+    {
+        let origin = CommandOrigin {
+            tool: Some(tool.to_string()),
+            key: self.api_key_label.clone(),
+        };
+        let call = ToolCall::new(tool).with_arguments(arguments);
+        let run = origin.scope(PendingConfirmations::scope(
+            self.tool_middleware
+                .run(&call, self.tool_timeouts.run(tool, body)),
+        ));
+        match &self.client_session {
+            Some(id) => {
+                ClientSessionRegistry::global().touch(id);
+                ClientSessionRegistry::scope(id.clone(), run).await
+            }
+            None => run.await,
+        }
+    }
+
+    /// Loxone client the tools run against, if any
+    pub fn client(&self) -> Option<&Arc<dyn LoxoneClient>> {
+        self.client.as_ref()
+    }
+
+    /// View of this server for one request's caller
+    ///
+    /// Commands are recorded under the caller's key, admin-only tools follow
+    /// the caller's role, and a tenant or guest key only sees and commands
+    /// its own devices. The view shares the background jobs and stores of
+    /// this server.
+    pub async fn for_caller(&self, caller: &Caller) -> crate::error::Result<Self> {
+        let mut server = self.clone();
+        server.api_key_label = Some(caller.label.clone());
+        server.admin_session = caller.is_admin();
+        if let (Some(scope), Some(client)) = (&caller.scope, &self.client) {
+            let client: Arc<dyn LoxoneClient> =
+                Arc::new(TenantScopedClient::new(client.clone(), scope.clone()));
+            let context = Arc::new(ClientContext::new());
+            context
+                .update_structure(client.get_structure().await?)
+                .await?;
+            server.value_resolver = self
+                .value_resolver
+                .as_ref()
+                .map(|resolver| Arc::new(resolver.for_client(client.clone())));
+            server.state_manager = None;
+            server.client = Some(client);
+            server.context = Some(context);
+        }
+        Ok(server)
+    }
+
+    /// Tools offered in `tools/list`
+    ///
+    /// The generated tool set without the tools this installation (or the
+    /// caller's part of it) has no devices for, with titles and descriptions
+    /// in `locale` (see [`Self::tool_descriptions`]).
+    pub async fn listed_tools(&self, locale: Option<&str>) -> Vec<Tool> {
+        let mut tools = <Self as McpToolsProvider>::get_available_tools(self);
+        if let Some(context) = &self.context {
+            let availability = ToolAvailability::from_context(context).await;
+            tools.retain(|tool| availability.is_available(&tool.name));
+        }
+        let descriptions = self.tool_descriptions(locale);
+        for tool in &mut tools {
+            if let Some(text) = descriptions.describe(&tool.name) {
+                tool.title = Some(text.title);
+                tool.description = text.description;
+            }
         }
+        tools
+    }
+
+    /// Tool texts in `locale`, else `mcp.tools.locale`, else
+    /// `LOXONE_LOCALE`/`LANG`
+    fn tool_descriptions(&self, locale: Option<&str>) -> ToolDescriptions {
+        let configured = self
+            .config
+            .as_ref()
+            .and_then(|config| config.mcp.tools.locale.as_deref());
+        ToolDescriptions::load(locale.or(configured))
+    }
+
+    /// Configured metadata of a room as JSON (`null` when unconfigured)
+    fn room_metadata(&self, structure: &LoxoneStructure, room_uuid: &str) -> Value {
+        self.config
+            .as_ref()
+            .map(|config| {
+                config
+                    .room_metadata
+                    .to_json(room_uuid, structure.rooms.get(room_uuid))
+            })
+            .unwrap_or(Value::Null)
+    }
+
+    /// Room UUIDs with windows on the given facade
+    fn rooms_with_facade(&self, structure: &LoxoneStructure, facade: Orientation) -> Vec<String> {
+        let Some(config) = &self.config else {
+            return Vec::new();
+        };
+        structure
+            .rooms
+            .iter()
+            .filter(|(uuid, room)| {
+                let name = room.get("name").and_then(|v| v.as_str());
+                config
+                    .room_metadata
+                    .get(uuid, name)
+                    .is_some_and(|metadata| metadata.has_facade(facade))
+            })
+            .map(|(uuid, _)| uuid.clone())
+            .collect()
     }
 
     /// Check if connected to Loxone
@@ -86,6 +545,14 @@ impl LoxoneMcpServer {
         None
     }
 
+    /// Structured `room_not_found` error with the closest room names
+    fn room_not_found(structure: &LoxoneStructure, room_name: &str) -> String {
+        room_suggestions::room_not_found(
+            room_suggestions::structure_room_names(structure),
+            room_name,
+        )
+    }
+
     /// Find controls matching the given types within a specific room (by room UUID).
     fn find_controls_by_type_in_room<'a>(
         structure: &'a LoxoneStructure,
@@ -142,6 +609,16 @@ impl LoxoneMcpServer {
         })
     }
 
+    /// Resolve an audio zone by UUID, name or room name.
+    async fn resolve_audio_zone(&self, zone: &str) -> std::result::Result<AudioZone, String> {
+        let structure = self
+            .get_client()?
+            .get_structure()
+            .await
+            .map_err(|e| format!("Failed to get structure: {e}"))?;
+        audio::find_zone(&audio::audio_zones(&structure), zone).map_err(|e| e.to_string())
+    }
+
     /// Search for climate controllers in a room by room name.
     fn find_climate_in_room<'a>(
         structure: &'a LoxoneStructure,
@@ -189,215 +666,60 @@ impl LoxoneMcpServer {
             }
         }
     }
-}
-
-/// All MCP tools defined in a single impl block
-#[mcp_tools]
-impl LoxoneMcpServer {
-    // ========================================================================
-    // LIGHTING TOOLS
-    // ========================================================================
 
-    /// Control lights in a room, by device, or system-wide
-    ///
-    /// Unified lighting control with scope-based targeting. Supports:
-    /// - scope: "device" (single light), "room" (all lights in room), "system" (all lights)
-    /// - action: "on", "off", "dim", "bright"
-    /// - brightness: 0-100 for dimming (optional)
-    pub async fn control_lights(
+    /// Collect hot water controls with their tank and target temperatures
+    async fn fetch_hot_water_units(
         &self,
-        scope: String,
-        target: Option<String>,
-        action: String,
-        brightness: Option<u8>,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        client: &Arc<dyn LoxoneClient>,
+        structure: &LoxoneStructure,
+    ) -> Vec<Value> {
+        let mut units: Vec<(&String, &Value, HotWaterRole)> = structure
+            .controls
+            .iter()
+            .filter_map(|(uuid, control)| {
+                HotWaterRole::of(control).map(|role| (uuid, control, role))
+            })
+            .collect();
+        units.sort_by_key(|(uuid, _, _)| *uuid);
 
-        // Normalize action (multi-language support)
-        let normalized_action = match action.to_lowercase().as_str() {
-            "on" | "ein" | "an" | "einschalten" => "on",
-            "off" | "aus" | "ab" | "ausschalten" => "off",
-            "dim" | "dimmen" => "dim",
-            "bright" | "hell" => "bright",
-            _ => {
-                return Err(format!(
-                    "Invalid action '{action}'. Supported: on, off, dim, bright"
-                ));
-            }
+        let state_uuids: Vec<String> = units
+            .iter()
+            .flat_map(|(_, control, _)| {
+                hot_water::tank_temperature_state(control)
+                    .into_iter()
+                    .chain(hot_water::target_temperature_state(control))
+            })
+            .collect();
+        let values = if state_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&state_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch hot water temperatures: {e}");
+                    std::collections::HashMap::new()
+                })
         };
-
-        // Validate brightness
-        if let Some(level) = brightness
-            && level > 100
-        {
-            return Err("Brightness must be between 0-100".to_string());
-        }
-
-        // Build the Loxone command string from the normalized action + brightness
-        let command = match (normalized_action, brightness) {
-            (_, Some(level)) => format!("{level}"),
-            ("on", None) => "on".to_string(),
-            ("off", None) => "off".to_string(),
-            ("dim", None) => "25".to_string(), // default dim level
-            ("bright", None) => "100".to_string(), // full brightness
-            _ => "on".to_string(),
+        let temperature = |state: Option<String>| {
+            state
+                .and_then(|uuid| values.get(&uuid).cloned())
+                .unwrap_or(Value::Null)
         };
 
-        let client = self.get_client()?;
-        let light_types = &["Switch", "Dimmer", "LightController", "ColorPicker"];
-
-        match scope.to_lowercase().as_str() {
-            "device" => {
-                let target_id = target
-                    .as_deref()
-                    .ok_or_else(|| "target is required when scope is 'device'".to_string())?;
-                let response = client
-                    .send_command(target_id, &command)
-                    .await
-                    .map_err(|e| format!("Failed to send command to device {target_id}: {e}"))?;
-                Ok(json!({
-                    "scope": "device",
-                    "target": target_id,
-                    "action": normalized_action,
-                    "brightness": brightness,
-                    "command_sent": command,
-                    "status": "executed",
-                    "miniserver_response": response.value
-                }))
-            }
-            "room" => {
-                let room_name = target.as_deref().ok_or_else(|| {
-                    "target (room name) is required when scope is 'room'".to_string()
-                })?;
-                let structure = client
-                    .get_structure()
-                    .await
-                    .map_err(|e| format!("Failed to get structure: {e}"))?;
-                let room_uuid = Self::resolve_room_uuid(&structure, room_name)
-                    .ok_or_else(|| format!("Room '{room_name}' not found"))?;
-                let controls =
-                    Self::find_controls_by_type_in_room(&structure, &room_uuid, light_types);
-                if controls.is_empty() {
-                    return Err(format!("No lights found in room '{room_name}'"));
-                }
-                let mut results = Vec::new();
-                for (uuid, control) in &controls {
-                    let name = control
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    match client.send_command(uuid, &command).await {
-                        Ok(response) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "executed",
-                                "miniserver_response": response.value
-                            }));
-                        }
-                        Err(e) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "error",
-                                "error": format!("{e}")
-                            }));
-                        }
-                    }
-                }
-                Ok(json!({
-                    "scope": "room",
-                    "target": room_name,
-                    "action": normalized_action,
-                    "brightness": brightness,
-                    "command_sent": command,
-                    "devices_affected": results.len(),
-                    "results": results
-                }))
-            }
-            "system" => {
-                let structure = client
-                    .get_structure()
-                    .await
-                    .map_err(|e| format!("Failed to get structure: {e}"))?;
-                let controls = Self::find_controls_by_type(&structure, light_types);
-                if controls.is_empty() {
-                    return Err("No lights found in the system".to_string());
-                }
-                let mut results = Vec::new();
-                for (uuid, control) in &controls {
-                    let name = control
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    match client.send_command(uuid, &command).await {
-                        Ok(response) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "executed",
-                                "miniserver_response": response.value
-                            }));
-                        }
-                        Err(e) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "error",
-                                "error": format!("{e}")
-                            }));
-                        }
-                    }
-                }
-                Ok(json!({
-                    "scope": "system",
-                    "action": normalized_action,
-                    "brightness": brightness,
-                    "command_sent": command,
-                    "devices_affected": results.len(),
-                    "results": results
-                }))
-            }
-            _ => Err(format!(
-                "Invalid scope '{scope}'. Use: device, room, system"
-            )),
-        }
-    }
-
-    /// Get the current state of all lights
-    ///
-    /// Returns a list of all lighting devices with their current state,
-    /// brightness level, and room location.
-    pub async fn get_lights_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut light_uuids = Vec::new();
-        let mut light_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-            if matches!(
-                control_type,
-                "Switch" | "Dimmer" | "LightController" | "ColorPicker"
-            ) {
-                light_uuids.push(uuid.clone());
-                light_info.push((uuid.clone(), control.clone()));
+        // The resolver parses tank readings with their unit; raw states are the fallback
+        let mut resolved = std::collections::HashMap::new();
+        if let Some(resolver) = &self.value_resolver {
+            let uuids: Vec<String> = units.iter().map(|(uuid, _, _)| (*uuid).clone()).collect();
+            match resolver.resolve_batch_values(&uuids).await {
+                Ok(values) => resolved = values,
+                Err(e) => warn!("Value resolver unavailable for hot water: {e}"),
             }
         }
 
-        // Fetch live states for all lights
-        let live_states = Self::fetch_live_states(client, &light_uuids).await;
-
-        let lights: Vec<Value> = light_info
-            .iter()
-            .map(|(uuid, control)| {
+        units
+            .into_iter()
+            .map(|(uuid, control, role)| {
                 let name = control
                     .get("name")
                     .and_then(|v| v.as_str())
@@ -406,1411 +728,8241 @@ impl LoxoneMcpServer {
                     .get("room")
                     .and_then(|v| v.as_str())
                     .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
-
+                let (tank_temperature, unit) = match resolved.get(uuid) {
+                    Some(value)
+                        if value.numeric_value.is_some()
+                            && value.unit.as_deref().is_some_and(|u| u.contains('°')) =>
+                    {
+                        (json!(value.numeric_value), value.unit.clone())
+                    }
+                    _ => (
+                        temperature(hot_water::tank_temperature_state(control)),
+                        Some("°C".to_string()),
+                    ),
+                };
                 json!({
                     "uuid": uuid,
                     "name": name,
-                    "type": control_type,
                     "room": room,
-                    "state": state
+                    "role": role.as_str(),
+                    "tank_temperature": tank_temperature,
+                    "target_temperature": temperature(hot_water::target_temperature_state(control)),
+                    "temperature_unit": unit,
+                    "has_schedule": hot_water::find_daytimer(control).is_some()
                 })
             })
+            .collect()
+    }
+
+    /// Find the hot water control to act on, by name/UUID or the only one of a role
+    fn find_hot_water_control<'a>(
+        structure: &'a LoxoneStructure,
+        unit: Option<&str>,
+        roles: &[HotWaterRole],
+    ) -> std::result::Result<(&'a String, &'a Value), String> {
+        let candidates: Vec<(&String, &Value)> = structure
+            .controls
+            .iter()
+            .filter(|(_, control)| HotWaterRole::of(control).is_some_and(|r| roles.contains(&r)))
             .collect();
+        match unit {
+            Some(identifier) => {
+                let lower = identifier.to_lowercase();
+                candidates
+                    .into_iter()
+                    .find(|(uuid, control)| {
+                        *uuid == identifier
+                            || control
+                                .get("name")
+                                .and_then(|v| v.as_str())
+                                .is_some_and(|n| n.to_lowercase().contains(&lower))
+                    })
+                    .ok_or_else(|| format!("Hot water control '{identifier}' not found"))
+            }
+            None => match candidates.as_slice() {
+                [single] => Ok(*single),
+                [] => Err("No hot water controls found in the system".to_string()),
+                _ => Err(format!(
+                    "Found {} hot water controls; specify which one to use",
+                    candidates.len()
+                )),
+            },
+        }
+    }
 
-        Ok(json!({
-            "lights": lights,
-            "count": lights.len()
-        }))
+    /// Alarm controls matching `alarm` (UUID or name), or all of them
+    fn find_alarms<'a>(
+        structure: &'a LoxoneStructure,
+        alarm: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        let is_alarm = |control: &Value| {
+            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            alarm::ALARM_CONTROL_TYPES.contains(&control_type)
+        };
+        match alarm {
+            Some(alarm) => Self::find_control_by_id_or_name(structure, alarm)
+                .filter(|(_, control)| is_alarm(control))
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("Alarm '{alarm}' not found")),
+            None => {
+                let alarms: Vec<_> = structure
+                    .controls
+                    .iter()
+                    .filter(|(_, control)| is_alarm(control))
+                    .collect();
+                if alarms.is_empty() {
+                    return Err("No burglar alarm found in the system".to_string());
+                }
+                Ok(alarms)
+            }
+        }
     }
 
-    // ========================================================================
-    // CLIMATE TOOLS
-    // ========================================================================
+    /// Intercom controls matching `intercom` (UUID or name), or all of them
+    fn find_intercoms<'a>(
+        structure: &'a LoxoneStructure,
+        intercom: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        match intercom {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| intercom::is_intercom(control))
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("Intercom '{name}' not found")),
+            None => {
+                let intercoms: Vec<_> = structure
+                    .controls
+                    .iter()
+                    .filter(|(_, control)| intercom::is_intercom(control))
+                    .collect();
+                if intercoms.is_empty() {
+                    return Err("No intercom found in the system".to_string());
+                }
+                Ok(intercoms)
+            }
+        }
+    }
 
-    /// Control room temperature settings
-    ///
-    /// Set target temperature for a room or zone. Supports heating and cooling modes.
-    pub async fn set_temperature(
+    /// State of each timer, with server-side countdowns taking precedence
+    async fn read_timers(
         &self,
-        room: String,
-        temperature: f64,
-        mode: Option<String>,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        client: &Arc<dyn LoxoneClient>,
+        timers: &[(&String, &Value)],
+    ) -> std::result::Result<Vec<(TimerState, Option<u64>)>, String> {
+        let state_uuids: Vec<String> = timers
+            .iter()
+            .flat_map(|(_, control)| {
+                let (remaining, total) = timers::state_uuids(control);
+                remaining.into_iter().chain(total)
+            })
+            .collect();
+        let values = if state_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&state_uuids)
+                .await
+                .map_err(|e| format!("Failed to read timer states: {e}"))?
+        };
+        Ok(timers
+            .iter()
+            .map(|(uuid, control)| {
+                let (state, total) = timers::read_timer(control, &values);
+                match self.countdowns.status(uuid) {
+                    Some(countdown) => (
+                        TimerState::Running {
+                            remaining_secs: countdown.remaining_secs,
+                        },
+                        total,
+                    ),
+                    None => (state, total),
+                }
+            })
+            .collect())
+    }
 
-        if !(5.0..=35.0).contains(&temperature) {
-            return Err("Temperature must be between 5°C and 35°C".to_string());
+    /// The Code Touch named `device` (UUID or name), or the only one
+    fn find_code_touch<'a>(
+        structure: &'a LoxoneStructure,
+        device: Option<&str>,
+    ) -> std::result::Result<(&'a String, &'a Value), String> {
+        if let Some(name) = device {
+            return Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    code_touch::CODE_TOUCH_TYPES.contains(&control_type)
+                })
+                .ok_or_else(|| format!("Code Touch '{name}' not found"));
         }
-
-        let mode = mode.unwrap_or_else(|| "auto".to_string());
-        if !["heat", "cool", "auto", "off"].contains(&mode.as_str()) {
-            return Err(format!("Invalid mode '{mode}'. Use: heat, cool, auto, off"));
+        match Self::find_controls_by_type(structure, code_touch::CODE_TOUCH_TYPES)[..] {
+            [] => Err("No Code Touch found in the system".to_string()),
+            [found] => Ok(found),
+            ref several => Err(format!(
+                "Several Code Touch devices found; name one of: {}",
+                several
+                    .iter()
+                    .filter_map(|(_, c)| c.get("name").and_then(|v| v.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
         }
+    }
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
+    /// Codes and tags stored on a Code Touch
+    async fn read_access_entries(
+        client: &Arc<dyn LoxoneClient>,
+        uuid: &str,
+        name: &str,
+    ) -> std::result::Result<Vec<code_touch::AccessEntry>, String> {
+        let response = client
+            .send_command(uuid, code_touch::LIST_COMMAND)
             .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            .map_err(|e| format!("Failed to read codes of {name}: {e}"))?;
+        code_touch::parse_entries(&response.value).map_err(|e| format!("{name}: {e}"))
+    }
 
-        let climate_types = &["IRoomController", "Intelligent Room Controller"];
+    /// Irrigation controllers matching `controller` (UUID or name), or all of them
+    fn find_irrigation_controllers<'a>(
+        structure: &'a LoxoneStructure,
+        controller: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        match controller {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    irrigation::IRRIGATION_CONTROL_TYPES.contains(&control_type)
+                })
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("Irrigation controller '{name}' not found")),
+            None => {
+                let controllers =
+                    Self::find_controls_by_type(structure, irrigation::IRRIGATION_CONTROL_TYPES);
+                if controllers.is_empty() {
+                    return Err("No irrigation controller found in the system".to_string());
+                }
+                Ok(controllers)
+            }
+        }
+    }
 
-        // Try to find the thermostat: first by direct UUID/name, then by room
-        let thermostat = Self::find_control_by_id_or_name(&structure, &room);
-        let targets: Vec<(&String, &Value)> = if let Some(target) = thermostat {
-            // Check it's actually a climate controller
-            let control_type = target.1.get("type").and_then(|v| v.as_str()).unwrap_or("");
-            if climate_types.contains(&control_type) {
-                vec![target]
-            } else {
-                // Not a climate controller, search by room
-                Self::find_climate_in_room(&structure, &room, climate_types)?
+    /// Read the irrigation states of the given controllers, keyed by control UUID
+    async fn fetch_irrigation_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        controllers: &[(&String, &Value)],
+    ) -> std::result::Result<std::collections::HashMap<String, IrrigationStatus>, String> {
+        let state_uuid = |control: &Value, name: &str| {
+            control
+                .get("states")
+                .and_then(|states| states.get(name))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let all_uuids: Vec<String> = controllers
+            .iter()
+            .flat_map(|(_, control)| {
+                irrigation::IRRIGATION_STATE_NAMES
+                    .iter()
+                    .filter_map(|name| state_uuid(control, name))
+            })
+            .collect();
+        let values = client
+            .get_state_values(&all_uuids)
+            .await
+            .map_err(|e| format!("Failed to read irrigation states: {e}"))?;
+
+        Ok(controllers
+            .iter()
+            .map(|(uuid, control)| {
+                let named = irrigation::IRRIGATION_STATE_NAMES
+                    .iter()
+                    .filter_map(|name| {
+                        let value = values.get(&state_uuid(control, name)?)?;
+                        Some((name.to_string(), value.clone()))
+                    })
+                    .collect();
+                let sub_controls = control
+                    .get("subControls")
+                    .and_then(|v| v.as_object())
+                    .map(|subs| subs.clone().into_iter().collect())
+                    .unwrap_or_default();
+                let status = IrrigationStatus::from_state_values(
+                    &named,
+                    irrigation::zones_from_sub_controls(&sub_controls),
+                );
+                ((*uuid).clone(), status)
+            })
+            .collect())
+    }
+
+    /// Pool controllers matching `pool` (UUID or name), or all of them
+    fn find_pools<'a>(
+        structure: &'a LoxoneStructure,
+        pool: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        match pool {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    pool::POOL_CONTROL_TYPES.contains(&control_type)
+                })
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("Pool '{name}' not found")),
+            None => {
+                let pools = Self::find_controls_by_type(structure, pool::POOL_CONTROL_TYPES);
+                if pools.is_empty() {
+                    return Err("No pool controller found in the system".to_string());
+                }
+                Ok(pools)
             }
+        }
+    }
+
+    /// Read the pool states of the given controls, keyed by control UUID
+    async fn fetch_pool_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        pools: &[(&String, &Value)],
+    ) -> std::collections::HashMap<String, PoolStatus> {
+        let state_uuids: Vec<(String, std::collections::HashMap<String, String>)> = pools
+            .iter()
+            .map(|(uuid, control)| ((*uuid).clone(), pool::pool_state_uuids(control)))
+            .collect();
+        let all_uuids: Vec<String> = state_uuids
+            .iter()
+            .flat_map(|(_, states)| states.values().cloned())
+            .collect();
+
+        let values = if all_uuids.is_empty() {
+            std::collections::HashMap::new()
         } else {
-            Self::find_climate_in_room(&structure, &room, climate_types)?
+            client
+                .get_state_values(&all_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch pool states: {e}");
+                    std::collections::HashMap::new()
+                })
         };
 
-        if targets.is_empty() {
-            return Err(format!("No climate controller found for room '{room}'"));
-        }
+        state_uuids
+            .into_iter()
+            .map(|(uuid, states)| {
+                let named = states
+                    .into_iter()
+                    .filter_map(|(name, state_uuid)| {
+                        values.get(&state_uuid).map(|v| (name, v.clone()))
+                    })
+                    .collect();
+                (uuid, PoolStatus::from_state_values(&named))
+            })
+            .collect()
+    }
 
-        let command = format!("settemp/{temperature}");
-        let mut results = Vec::new();
-        for (uuid, control) in &targets {
-            let name = control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown");
-            match client.send_command(uuid, &command).await {
-                Ok(response) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "status": "executed",
-                        "miniserver_response": response.value
-                    }));
-                }
-                Err(e) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "status": "error",
-                        "error": format!("{e}")
-                    }));
+    /// Wallboxes matching `charger` (UUID or name), or all of them
+    fn find_wallboxes<'a>(
+        structure: &'a LoxoneStructure,
+        charger: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        match charger {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    wallbox::WALLBOX_CONTROL_TYPES.contains(&control_type)
+                })
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("EV charger '{name}' not found")),
+            None => {
+                let chargers =
+                    Self::find_controls_by_type(structure, wallbox::WALLBOX_CONTROL_TYPES);
+                if chargers.is_empty() {
+                    return Err("No EV charger found in the system".to_string());
                 }
+                Ok(chargers)
             }
         }
+    }
 
-        // Also send mode command if not "auto" (the default)
-        if mode != "auto" {
-            let mode_command = match mode.as_str() {
-                "heat" => "setmode/1",
-                "cool" => "setmode/2",
-                "off" => "setmode/0",
-                _ => "setmode/3", // auto
-            };
-            for (uuid, _) in &targets {
-                if let Err(e) = client.send_command(uuid, mode_command).await {
-                    warn!("Failed to set mode on {uuid}: {e}");
+    /// Load managers matching `manager` (UUID or name), or all of them
+    fn find_load_managers<'a>(
+        structure: &'a LoxoneStructure,
+        manager: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        match manager {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    load_manager::LOAD_MANAGER_TYPES.contains(&control_type)
+                })
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("Load manager '{name}' not found")),
+            None => {
+                let managers =
+                    Self::find_controls_by_type(structure, load_manager::LOAD_MANAGER_TYPES);
+                if managers.is_empty() {
+                    return Err("No load manager found in the system".to_string());
                 }
+                Ok(managers)
             }
         }
-
-        Ok(json!({
-            "room": room,
-            "target_temperature": temperature,
-            "mode": mode,
-            "command_sent": command,
-            "controllers_affected": results.len(),
-            "results": results
-        }))
     }
 
-    /// Get current climate status for all rooms
-    pub async fn get_climate_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+    /// Read the wallbox states of the given controls, keyed by control UUID
+    async fn fetch_wallbox_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        chargers: &[(&String, &Value)],
+    ) -> std::collections::HashMap<String, WallboxStatus> {
+        let state_uuids: Vec<(String, std::collections::HashMap<String, String>)> = chargers
+            .iter()
+            .map(|(uuid, control)| ((*uuid).clone(), wallbox::wallbox_state_uuids(control)))
+            .collect();
+        let all_uuids: Vec<String> = state_uuids
+            .iter()
+            .flat_map(|(_, states)| states.values().cloned())
+            .collect();
 
-        let mut climate_uuids = Vec::new();
-        let mut climate_info = Vec::new();
+        let values = if all_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&all_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch wallbox states: {e}");
+                    std::collections::HashMap::new()
+                })
+        };
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        state_uuids
+            .into_iter()
+            .map(|(uuid, states)| {
+                let named = states
+                    .into_iter()
+                    .filter_map(|(name, state_uuid)| {
+                        values.get(&state_uuid).map(|v| (name, v.clone()))
+                    })
+                    .collect();
+                (uuid, WallboxStatus::from_state_values(&named))
+            })
+            .collect()
+    }
 
-            if matches!(
-                control_type,
-                "IRoomController" | "Intelligent Room Controller"
-            ) {
-                climate_uuids.push(uuid.clone());
-                climate_info.push((uuid.clone(), control.clone()));
+    /// AC and fan coil units matching `unit` (UUID or name), or all of them
+    fn find_ac_units<'a>(
+        structure: &'a LoxoneStructure,
+        unit: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        match unit {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| Capabilities::of(control).is_some())
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("AC or fan coil unit '{name}' not found")),
+            None => {
+                let units =
+                    Self::find_controls_by_type(structure, air_conditioning::AC_CONTROL_TYPES);
+                if units.is_empty() {
+                    return Err("No AC or fan coil unit found in the system".to_string());
+                }
+                Ok(units)
             }
         }
+    }
 
-        let live_states = Self::fetch_live_states(client, &climate_uuids).await;
-
-        let climate_data: Vec<Value> = climate_info
+    /// Read the AC states of the given controls, keyed by control UUID
+    async fn fetch_ac_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        units: &[(&String, &Value)],
+    ) -> std::collections::HashMap<String, AcStatus> {
+        let state_uuids: Vec<(String, std::collections::HashMap<String, String>)> = units
             .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+            .map(|(uuid, control)| ((*uuid).clone(), air_conditioning::state_uuids(control)))
+            .collect();
+        let all_uuids: Vec<String> = state_uuids
+            .iter()
+            .flat_map(|(_, states)| states.values().cloned())
+            .collect();
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "room": room,
-                    "state": state
+        let values = if all_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&all_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch AC states: {e}");
+                    std::collections::HashMap::new()
                 })
+        };
+
+        state_uuids
+            .into_iter()
+            .map(|(uuid, states)| {
+                let named = states
+                    .into_iter()
+                    .filter_map(|(name, state_uuid)| {
+                        values.get(&state_uuid).map(|v| (name, v.clone()))
+                    })
+                    .collect();
+                (uuid, AcStatus::from_state_values(&named))
             })
-            .collect();
+            .collect()
+    }
 
-        Ok(json!({
-            "climate_controllers": climate_data,
-            "count": climate_data.len()
-        }))
+    /// Ventilation units matching `unit` (UUID or name), or all of them
+    fn find_ventilation_units<'a>(
+        structure: &'a LoxoneStructure,
+        unit: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        match unit {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    ventilation::is_ventilation_control_type(control_type)
+                })
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("Ventilation unit '{name}' not found")),
+            None => {
+                let units =
+                    Self::find_controls_by_type(structure, ventilation::VENTILATION_CONTROL_TYPES);
+                if units.is_empty() {
+                    return Err("No ventilation unit found in the system".to_string());
+                }
+                Ok(units)
+            }
+        }
     }
 
-    // ========================================================================
-    // BLINDS/ROLLADEN TOOLS
-    // ========================================================================
+    /// Read the ventilation states of the given controls, keyed by control UUID
+    async fn fetch_ventilation_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        units: &[(&String, &Value)],
+    ) -> std::collections::HashMap<String, VentilationStatus> {
+        let state_uuids: Vec<(String, std::collections::HashMap<String, String>)> = units
+            .iter()
+            .map(|(uuid, control)| ((*uuid).clone(), ventilation::state_uuids(control)))
+            .collect();
+        let all_uuids: Vec<String> = state_uuids
+            .iter()
+            .flat_map(|(_, states)| states.values().cloned())
+            .collect();
 
-    /// Control blinds/rolladen position
-    ///
-    /// Set blind position (0=fully open, 100=fully closed) or use actions like up/down/stop.
-    pub async fn control_blinds(
-        &self,
-        target: String,
-        action: Option<String>,
-        position: Option<u8>,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        let values = if all_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&all_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch ventilation states: {e}");
+                    std::collections::HashMap::new()
+                })
+        };
 
-        // Determine command based on action or position
-        let command = if let Some(pos) = position {
-            if pos > 100 {
-                return Err("Position must be between 0-100".to_string());
-            }
-            format!("ManualPosition/{pos}")
-        } else if let Some(ref act) = action {
-            match act.to_lowercase().as_str() {
-                "up" | "open" | "auf" => "FullUp".to_string(),
-                "down" | "close" | "ab" | "zu" => "FullDown".to_string(),
-                "stop" | "halt" => "Stop".to_string(),
-                "shade" | "schatten" => "Shade".to_string(),
-                _ => {
-                    return Err(format!(
-                        "Invalid action '{act}'. Use: up, down, stop, shade"
-                    ));
+        state_uuids
+            .into_iter()
+            .map(|(uuid, states)| {
+                let named = states
+                    .into_iter()
+                    .filter_map(|(name, state_uuid)| {
+                        values.get(&state_uuid).map(|v| (name, v.clone()))
+                    })
+                    .collect();
+                (uuid, VentilationStatus::from_state_values(&named))
+            })
+            .collect()
+    }
+
+    /// Smoke alarms and leak detectors matching `device` (UUID or name), or
+    /// all of them
+    fn find_safety_devices<'a>(
+        structure: &'a LoxoneStructure,
+        device: Option<&str>,
+    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
+        let is_safety = |control: &Value| {
+            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            SafetyKind::from_control_type(control_type).is_some()
+        };
+        match device {
+            Some(name) => Self::find_control_by_id_or_name(structure, name)
+                .filter(|(_, control)| is_safety(control))
+                .map(|found| vec![found])
+                .ok_or_else(|| format!("Smoke alarm or leak detector '{name}' not found")),
+            None => {
+                let devices: Vec<_> = structure
+                    .controls
+                    .iter()
+                    .filter(|(_, control)| is_safety(control))
+                    .collect();
+                if devices.is_empty() {
+                    return Err("No smoke alarm or leak detector found in the system".to_string());
                 }
+                Ok(devices)
             }
+        }
+    }
+
+    /// Read the states of smoke alarms and leak detectors, keyed by control UUID
+    async fn fetch_safety_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        devices: &[(&String, &Value)],
+    ) -> std::collections::HashMap<String, SafetyStatus> {
+        let state_uuids: Vec<(
+            String,
+            SafetyKind,
+            std::collections::HashMap<String, String>,
+        )> = devices
+            .iter()
+            .filter_map(|(uuid, control)| {
+                let control_type = control.get("type").and_then(|v| v.as_str())?;
+                let kind = SafetyKind::from_control_type(control_type)?;
+                Some(((*uuid).clone(), kind, safety::safety_state_uuids(control)))
+            })
+            .collect();
+        let all_uuids: Vec<String> = state_uuids
+            .iter()
+            .flat_map(|(_, _, states)| states.values().cloned())
+            .collect();
+
+        let values = if all_uuids.is_empty() {
+            std::collections::HashMap::new()
         } else {
-            return Err("Either action or position must be provided".to_string());
+            client
+                .get_state_values(&all_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch safety device states: {e}");
+                    std::collections::HashMap::new()
+                })
         };
 
-        let client = self.get_client()?;
+        state_uuids
+            .into_iter()
+            .map(|(uuid, kind, states)| {
+                let named = states
+                    .into_iter()
+                    .filter_map(|(name, state_uuid)| {
+                        values.get(&state_uuid).map(|v| (name, v.clone()))
+                    })
+                    .collect();
+                (uuid, SafetyStatus::from_state_values(kind, &named))
+            })
+            .collect()
+    }
+
+    /// Virtual input by UUID or name: exact name first, then a partial match
+    ///
+    /// Only virtual inputs are matched by name, so a light named like the
+    /// input is never picked.
+    fn find_virtual_input<'a>(
+        structure: &'a LoxoneStructure,
+        input: &str,
+    ) -> std::result::Result<(&'a String, &'a Value), String> {
+        if let Some(found) = structure.controls.get_key_value(input) {
+            return Ok(found);
+        }
+        let lower = input.to_lowercase();
+        let inputs: Vec<(&String, &Value, String)> = structure
+            .controls
+            .iter()
+            .filter(|(_, control)| {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                VirtualInputKind::from_control_type(control_type).is_some()
+            })
+            .filter_map(|(uuid, control)| {
+                let name = control.get("name").and_then(|v| v.as_str())?;
+                Some((uuid, control, name.to_lowercase()))
+            })
+            .collect();
+        inputs
+            .iter()
+            .find(|(_, _, name)| *name == lower)
+            .or_else(|| inputs.iter().find(|(_, _, name)| name.contains(&lower)))
+            .map(|(uuid, control, _)| (*uuid, *control))
+            .ok_or_else(|| format!("Virtual input '{input}' not found"))
+    }
+
+    /// Send `value` to a virtual input and describe the result
+    async fn send_virtual_input(
+        client: &Arc<dyn LoxoneClient>,
+        uuid: &str,
+        control: &Value,
+        value: &str,
+    ) -> std::result::Result<Value, String> {
+        let command = virtual_inputs::build_command(control, value).map_err(|e| e.to_string())?;
+        let name = control
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown");
+        let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
 
-        // Target can be a UUID or a device name; send command directly
         let response = client
-            .send_command(&target, &command)
+            .send_command(uuid, &command)
             .await
-            .map_err(|e| format!("Failed to send blinds command to {target}: {e}"))?;
+            .map_err(|e| format!("Failed to set virtual input {name}: {e}"))?;
 
         Ok(json!({
-            "target": target,
-            "action": action,
-            "position": position,
+            "uuid": uuid,
+            "name": name,
+            "type": control_type,
+            "kind": VirtualInputKind::from_control_type(control_type).map(VirtualInputKind::as_str),
+            "value": value,
             "command_sent": command,
-            "status": "executed",
+            "status": "updated",
             "miniserver_response": response.value
         }))
     }
 
-    /// Get status of all blinds/rolladen
-    pub async fn get_blinds_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
+    /// Send the commands of a shortcut in parallel and report per device
+    async fn run_shortcut(
+        client: &Arc<dyn LoxoneClient>,
+        structure: &LoxoneStructure,
+        plan: Vec<shortcuts::PlannedCommand>,
+    ) -> std::result::Result<Value, String> {
+        let commands = plan
+            .iter()
+            .map(|planned| (planned.uuid.clone(), planned.command.to_string()))
+            .collect();
+        let results = client
+            .send_parallel_commands(commands)
             .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut blind_uuids = Vec::new();
-        let mut blind_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            .map_err(|e| format!("Failed to send commands: {e}"))?;
 
-            if matches!(control_type, "Jalousie" | "Blinds" | "Rolladen") {
-                blind_uuids.push(uuid.clone());
-                blind_info.push((uuid.clone(), control.clone()));
-            }
-        }
-
-        let live_states = Self::fetch_live_states(client, &blind_uuids).await;
-
-        let blinds: Vec<Value> = blind_info
+        let devices: Vec<Value> = plan
             .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
-
+            .zip(results)
+            .map(|(planned, result)| {
+                let room = planned
+                    .room
+                    .as_ref()
+                    .and_then(|room| structure.rooms.get(room))
+                    .and_then(|room| room.get("name"))
+                    .and_then(|v| v.as_str());
                 json!({
-                    "uuid": uuid,
-                    "name": name,
+                    "uuid": planned.uuid,
+                    "name": planned.name,
                     "room": room,
-                    "state": state
+                    "category": planned.category,
+                    "command_sent": planned.command,
+                    "success": result.is_ok(),
+                    "error": result.err().map(|e| e.to_string())
                 })
             })
             .collect();
+        let failed = devices
+            .iter()
+            .filter(|d| d["success"] == json!(false))
+            .count();
 
         Ok(json!({
-            "blinds": blinds,
-            "count": blinds.len()
+            "devices": devices,
+            "count": devices.len(),
+            "failed": failed,
+            "status": if failed == 0 { "executed" } else { "partial" }
         }))
     }
 
-    // ========================================================================
-    // DISCOVERY TOOLS
-    // ========================================================================
-
-    /// List all rooms in the Loxone system
-    pub async fn list_rooms(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// Find the room controller of a room, or by controller UUID/name
+    fn find_room_controller<'a>(
+        structure: &'a LoxoneStructure,
+        room: &str,
+    ) -> std::result::Result<(&'a String, &'a Value), String> {
+        let types = climate_schedule::ROOM_CONTROLLER_TYPES;
+        let is_controller = |control: &Value| {
+            control
+                .get("type")
+                .and_then(Value::as_str)
+                .is_some_and(|t| types.contains(&t))
+        };
+        if let Some(target) = Self::find_control_by_id_or_name(structure, room)
+            && is_controller(target.1)
+        {
+            return Ok(target);
+        }
+        match Self::find_climate_in_room(structure, room, types)?[..] {
+            [] => Err(format!("No room controller found for '{room}'")),
+            [target] => Ok(target),
+            _ => Err(format!(
+                "Several room controllers match '{room}', use the controller name or UUID"
+            )),
+        }
+    }
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
+    /// Read the current schedule of a room controller
+    async fn read_climate_schedule(
+        client: &Arc<dyn LoxoneClient>,
+        control: &Value,
+    ) -> std::result::Result<Vec<climate_schedule::SchedulePeriod>, String> {
+        let name = control
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown");
+        let (_, state) = climate_schedule::daytimer(control)
+            .ok_or_else(|| format!("Room controller '{name}' has no schedule"))?;
+        let Some(state) = state else {
+            return Ok(Vec::new());
+        };
+        let values = client
+            .get_device_states(&[state.to_string()])
             .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            .map_err(|e| format!("Failed to read schedule of {name}: {e}"))?;
+        Ok(values
+            .get(state)
+            .map(climate_schedule::parse_schedule)
+            .unwrap_or_default())
+    }
 
-        let rooms: Vec<_> = structure
-            .rooms
+    /// Read the alarm states of the given controls, keyed by control UUID
+    async fn fetch_alarm_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        alarms: &[(&String, &Value)],
+    ) -> std::collections::HashMap<String, AlarmStatus> {
+        let state_uuids: Vec<(String, std::collections::HashMap<String, String>)> = alarms
             .iter()
-            .map(|(uuid, room)| {
-                json!({
-                    "uuid": uuid,
-                    "name": room.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "type": room.get("type").and_then(|v| v.as_str()).unwrap_or("Room")
+            .map(|(uuid, control)| ((*uuid).clone(), alarm::alarm_state_uuids(control)))
+            .collect();
+        let all_uuids: Vec<String> = state_uuids
+            .iter()
+            .flat_map(|(_, states)| states.values().cloned())
+            .collect();
+
+        let values = if all_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&all_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch alarm states: {e}");
+                    std::collections::HashMap::new()
                 })
+        };
+
+        state_uuids
+            .into_iter()
+            .map(|(uuid, states)| {
+                let named = states
+                    .into_iter()
+                    .filter_map(|(name, state_uuid)| {
+                        values.get(&state_uuid).map(|v| (name, v.clone()))
+                    })
+                    .collect();
+                (uuid, AlarmStatus::from_state_values(&named))
             })
+            .collect()
+    }
+
+    /// Read the position, movement and lockout states of gate controls
+    async fn fetch_gate_statuses(
+        client: &Arc<dyn LoxoneClient>,
+        gates: &[(&String, &Value)],
+    ) -> std::collections::HashMap<String, GateStatus> {
+        let state_uuids: Vec<(String, std::collections::HashMap<String, String>)> = gates
+            .iter()
+            .map(|(uuid, control)| ((*uuid).clone(), access::gate_state_uuids(control)))
+            .collect();
+        let all_uuids: Vec<String> = state_uuids
+            .iter()
+            .flat_map(|(_, states)| states.values().cloned())
             .collect();
 
-        Ok(json!({
-            "rooms": rooms,
-            "count": rooms.len()
-        }))
+        let values = if all_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&all_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to fetch gate states: {e}");
+                    std::collections::HashMap::new()
+                })
+        };
+
+        state_uuids
+            .into_iter()
+            .map(|(uuid, states)| {
+                let named = states
+                    .into_iter()
+                    .filter_map(|(name, state_uuid)| {
+                        values.get(&state_uuid).map(|v| (name, v.clone()))
+                    })
+                    .collect();
+                (uuid, GateStatus::from_state_values(&named))
+            })
+            .collect()
     }
+}
 
-    /// List all devices in a specific room or system-wide
-    pub async fn list_devices(
+/// All MCP tools defined in a single impl block
+#[mcp_tools]
+impl LoxoneMcpServer {
+    // ========================================================================
+    // LIGHTING TOOLS
+    // ========================================================================
+
+    /// Control lights in a room, by device, or system-wide
+    ///
+    /// Unified lighting control with scope-based targeting. Supports:
+    /// - scope: "device" (single light), "room" (all lights in room), "system" (all lights)
+    /// - action: "on", "off", "dim", "bright"
+    /// - brightness: 0-100 for dimming (optional)
+    /// - confirm: wait for the light state to change and report it (optional)
+    pub async fn control_lights(
         &self,
-        room: Option<String>,
+        scope: String,
+        target: Option<String>,
+        action: String,
+        brightness: Option<u8>,
+        confirm: Option<bool>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        let arguments = json!({
+            "scope": scope,
+            "target": target,
+            "action": action,
+            "brightness": brightness,
+            "confirm": confirm
+        });
+        self.run_tool_with("control_lights", arguments, async move {
+            self.ensure_connected()?;
+
+            // Normalize action (multi-language support)
+            let normalized_action = ActionAliases::global()
+                .normalize_action(&action, &["on", "off", "dim", "bright"])
+                .ok_or_else(|| {
+                    format!("Invalid action '{action}'. Supported: on, off, dim, bright")
+                })?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            // Validate brightness
+            if let Some(level) = brightness
+                && level > 100
+            {
+                return Err("Brightness must be between 0-100".to_string());
+            }
 
-        let devices: Vec<_> = structure
-            .controls
-            .iter()
-            .filter(|(_, control)| {
-                if let Some(ref room_filter) = room {
-                    control
-                        .get("room")
-                        .and_then(|v| v.as_str())
-                        .map(|r| r.to_lowercase().contains(&room_filter.to_lowercase()))
-                        .unwrap_or(false)
-                } else {
-                    true
-                }
-            })
-            .map(|(uuid, control)| {
-                json!({
-                    "uuid": uuid,
-                    "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "type": control.get("type").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "room": control.get("room").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "category": control.get("cat").and_then(|v| v.as_str()).unwrap_or("Unknown")
-                })
-            })
-            .collect();
+            // Build the Loxone command string from the normalized action + brightness
+            let command = match (normalized_action, brightness) {
+                (_, Some(level)) => format!("{level}"),
+                ("on", None) => "on".to_string(),
+                ("off", None) => "off".to_string(),
+                ("dim", None) => "25".to_string(), // default dim level
+                ("bright", None) => "100".to_string(), // full brightness
+                _ => "on".to_string(),
+            };
 
-        Ok(json!({
-            "devices": devices,
-            "count": devices.len(),
-            "filter": room
-        }))
+            let client = self.get_client()?;
+            let light_types = &["Switch", "Dimmer", "LightController", "ColorPicker"];
+
+            match scope.to_lowercase().as_str() {
+                "device" => {
+                    let target_id = target
+                        .as_deref()
+                        .ok_or_else(|| "target is required when scope is 'device'".to_string())?;
+                    let response = client
+                        .send_command(target_id, &command)
+                        .await
+                        .map_err(|e| {
+                            format!("Failed to send command to device {target_id}: {e}")
+                        })?;
+                    Ok(json!({
+                        "scope": "device",
+                        "target": target_id,
+                        "action": normalized_action,
+                        "brightness": brightness,
+                        "command_sent": command,
+                        "status": "executed",
+                        "miniserver_response": response.value
+                    }))
+                }
+                "room" => {
+                    let room_name = target.as_deref().ok_or_else(|| {
+                        "target (room name) is required when scope is 'room'".to_string()
+                    })?;
+                    let structure = client
+                        .get_structure()
+                        .await
+                        .map_err(|e| format!("Failed to get structure: {e}"))?;
+                    let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                    let controls =
+                        Self::find_controls_by_type_in_room(&structure, &room_uuid, light_types);
+                    if controls.is_empty() {
+                        return Err(format!("No lights found in room '{room_name}'"));
+                    }
+                    let mut results = Vec::new();
+                    for (uuid, control) in &controls {
+                        let name = control
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Unknown");
+                        match client.send_command(uuid, &command).await {
+                            Ok(response) => {
+                                results.push(json!({
+                                    "uuid": uuid,
+                                    "name": name,
+                                    "status": "executed",
+                                    "miniserver_response": response.value
+                                }));
+                            }
+                            Err(e) => {
+                                results.push(json!({
+                                    "uuid": uuid,
+                                    "name": name,
+                                    "status": "error",
+                                    "error": format!("{e}")
+                                }));
+                            }
+                        }
+                    }
+                    Ok(json!({
+                        "scope": "room",
+                        "target": room_name,
+                        "action": normalized_action,
+                        "brightness": brightness,
+                        "command_sent": command,
+                        "devices_affected": results.len(),
+                        "results": results
+                    }))
+                }
+                "system" => {
+                    let structure = client
+                        .get_structure()
+                        .await
+                        .map_err(|e| format!("Failed to get structure: {e}"))?;
+                    let controls = Self::find_controls_by_type(&structure, light_types);
+                    if controls.is_empty() {
+                        return Err("No lights found in the system".to_string());
+                    }
+                    let mut results = Vec::new();
+                    for (uuid, control) in &controls {
+                        let name = control
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Unknown");
+                        match client.send_command(uuid, &command).await {
+                            Ok(response) => {
+                                results.push(json!({
+                                    "uuid": uuid,
+                                    "name": name,
+                                    "status": "executed",
+                                    "miniserver_response": response.value
+                                }));
+                            }
+                            Err(e) => {
+                                results.push(json!({
+                                    "uuid": uuid,
+                                    "name": name,
+                                    "status": "error",
+                                    "error": format!("{e}")
+                                }));
+                            }
+                        }
+                    }
+                    Ok(json!({
+                        "scope": "system",
+                        "action": normalized_action,
+                        "brightness": brightness,
+                        "command_sent": command,
+                        "devices_affected": results.len(),
+                        "results": results
+                    }))
+                }
+                _ => Err(format!(
+                    "Invalid scope '{scope}'. Use: device, room, system"
+                )),
+            }
+        })
+        .await
     }
 
-    /// Get detailed information about a specific device
-    pub async fn get_device_info(
-        &self,
-        device_id: String,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// Get the current state of all lights
+    ///
+    /// Returns a list of all lighting devices with their current state,
+    /// brightness level, and room location.
+    pub async fn get_lights_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_lights_status", async move {
+            self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-        // Find device by UUID or name
-        let device = structure.controls.iter().find(|(uuid, control)| {
-            uuid.to_string() == device_id
-                || control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .map(|n| n.to_lowercase().contains(&device_id.to_lowercase()))
-                    .unwrap_or(false)
-        });
+            let mut light_uuids = Vec::new();
+            let mut light_info = Vec::new();
 
-        match device {
-            Some((uuid, control)) => Ok(json!({
-                "uuid": uuid,
-                "control": control
-            })),
-            None => Err(format!("Device '{device_id}' not found")),
-        }
-    }
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
 
-    // ========================================================================
-    // SYSTEM TOOLS
-    // ========================================================================
+                if matches!(
+                    control_type,
+                    "Switch" | "Dimmer" | "LightController" | "ColorPicker"
+                ) {
+                    light_uuids.push(uuid.clone());
+                    light_info.push((uuid.clone(), control.clone()));
+                }
+            }
 
-    /// Get server status and health information
-    pub async fn get_server_status(&self) -> std::result::Result<serde_json::Value, String> {
-        let connected = self.context.is_some() && self.client.is_some();
+            // Fetch live states for all lights
+            let live_states = Self::fetch_live_states(client, &light_uuids).await;
 
-        Ok(json!({
-            "connected": connected,
-            "version": env!("CARGO_PKG_VERSION"),
-            "name": "Loxone MCP Server"
-        }))
-    }
+            let lights: Vec<Value> = light_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
 
-    // ========================================================================
-    // AUDIO TOOLS
-    // ========================================================================
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "state": state
+                    })
+                })
+                .collect();
 
-    /// Control an audio zone (play, pause, stop, next, previous)
+            Ok(json!({
+                "lights": lights,
+                "count": lights.len()
+            }))
+        })
+        .await
+    }
+
+    /// Set the color of an RGB or tunable-white light
     ///
-    /// Manages playback in audio zones. Actions: play, pause, stop, next, previous, mute, unmute
-    pub async fn control_audio_zone(
+    /// Give exactly one of: `rgb` (#rrggbb or r,g,b), `hue` (0-360 with
+    /// optional `saturation` 0-100) or `kelvin` (2700-6500). `brightness`
+    /// (0-100, default 100) applies to hue and kelvin. A light controller
+    /// sets all of its color pickers
+    pub async fn set_light_color(
         &self,
-        zone: String,
-        action: String,
+        light: String,
+        rgb: Option<String>,
+        hue: Option<f64>,
+        saturation: Option<f64>,
+        kelvin: Option<u32>,
+        brightness: Option<f64>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        self.run_tool("set_light_color", async move {
+            self.ensure_connected()?;
+
+            let brightness = brightness.unwrap_or(100.0);
+            let color = match (rgb, hue, kelvin) {
+                (Some(rgb), None, None) => {
+                    let (r, g, b) = light_color::parse_rgb(&rgb).map_err(|e| e.to_string())?;
+                    LightColor::from_rgb(r, g, b)
+                }
+                (None, Some(hue), None) => {
+                    LightColor::hsv(hue, saturation.unwrap_or(100.0), brightness)
+                        .map_err(|e| e.to_string())?
+                }
+                (None, None, Some(kelvin)) => {
+                    LightColor::temperature(brightness, kelvin).map_err(|e| e.to_string())?
+                }
+                _ => {
+                    return Err("Give exactly one of rgb, hue or kelvin".to_string());
+                }
+            };
 
-        let normalized_action = match action.to_lowercase().as_str() {
-            "play" | "abspielen" | "start" => "play",
-            "pause" | "pausieren" => "pause",
-            "stop" | "stopp" | "anhalten" => "stop",
-            "next" | "weiter" | "nächster" => "next",
-            "previous" | "zurück" | "vorheriger" => "previous",
-            "mute" | "stumm" => "mute",
-            "unmute" | "laut" => "unmute",
-            _ => {
-                return Err(format!(
-                    "Invalid action '{action}'. Use: play, pause, stop, next, previous, mute, unmute"
-                ));
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &light)
+                .ok_or_else(|| format!("Light '{light}' not found"))?;
+            let pickers = light_color::color_pickers(uuid, control);
+            if pickers.is_empty() {
+                return Err(format!("Light '{light}' has no color control"));
             }
-        };
 
-        let client = self.get_client()?;
-
-        // Map normalized actions to Loxone audio commands
-        let command = match normalized_action {
-            "play" => "play",
-            "pause" => "pause",
-            "stop" => "stop",
-            "next" => "queueplus",
-            "previous" => "queueminus",
-            "mute" => "mute",
-            "unmute" => "unmute",
-            _ => normalized_action,
-        };
+            let color_states: Vec<String> = pickers
+                .iter()
+                .filter_map(|(_, _, state)| state.clone())
+                .collect();
+            let previous = if color_states.is_empty() {
+                std::collections::HashMap::new()
+            } else {
+                client
+                    .get_state_values(&color_states)
+                    .await
+                    .unwrap_or_default()
+            };
 
-        let response = client
-            .send_command(&zone, command)
-            .await
-            .map_err(|e| format!("Failed to control audio zone {zone}: {e}"))?;
+            let command = color.command();
+            let mut results = Vec::new();
+            for (picker_uuid, name, state) in &pickers {
+                let previous_color = state
+                    .as_ref()
+                    .and_then(|state| previous.get(state))
+                    .and_then(|v| v.as_str())
+                    .and_then(light_color::parse_color_state);
+                let result = client.send_command(picker_uuid, &command).await;
+                results.push(json!({
+                    "uuid": picker_uuid,
+                    "name": name,
+                    "previous_color": previous_color,
+                    "success": result.is_ok(),
+                    "error": result.err().map(|e| e.to_string())
+                }));
+            }
 
-        Ok(json!({
-            "zone": zone,
-            "action": normalized_action,
-            "command_sent": command,
-            "status": "executed",
-            "miniserver_response": response.value
-        }))
+            Ok(json!({
+                "light": light,
+                "color": color,
+                "command_sent": command,
+                "color_pickers": results,
+                "status": "executed"
+            }))
+        })
+        .await
     }
 
-    /// Set volume for an audio zone
+    /// Get the state of stairwell light switches and other timers
     ///
-    /// Set volume level (0-100) for a specific audio zone
-    pub async fn set_audio_volume(
+    /// Returns each timer with its state (off, running, on), the remaining
+    /// seconds while running and the configured duration. Optionally
+    /// filtered by room.
+    pub async fn get_timers(
         &self,
-        zone: String,
-        volume: u8,
+        room: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        self.run_tool("get_timers", async move {
+            self.ensure_connected()?;
 
-        if volume > 100 {
-            return Err("Volume must be between 0-100".to_string());
-        }
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let timers = match &room {
+                Some(room_name) => {
+                    let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                    Self::find_controls_by_type_in_room(&structure, &room_uuid, timers::TIMER_TYPES)
+                }
+                None => Self::find_controls_by_type(&structure, timers::TIMER_TYPES),
+            };
+            let states = self.read_timers(client, &timers).await?;
 
-        let client = self.get_client()?;
-        let command = format!("volume/{volume}");
-        let response = client
-            .send_command(&zone, &command)
-            .await
-            .map_err(|e| format!("Failed to set volume on zone {zone}: {e}"))?;
+            let timer_list: Vec<Value> = timers
+                .iter()
+                .zip(states)
+                .map(|((uuid, control), (state, total))| {
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .and_then(|room| structure.rooms.get(room))
+                        .and_then(|room| room.get("name"))
+                        .and_then(|v| v.as_str());
+                    let mut entry = json!({
+                        "uuid": uuid,
+                        "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "room": room,
+                        "configured_duration_secs": total,
+                        "custom_duration": self.countdowns.status(uuid).is_some()
+                    });
+                    if let (Some(entry), Value::Object(state)) =
+                        (entry.as_object_mut(), json!(state))
+                    {
+                        entry.extend(state);
+                    }
+                    entry
+                })
+                .collect();
 
-        Ok(json!({
-            "zone": zone,
-            "volume": volume,
-            "command_sent": command,
-            "status": "executed",
-            "miniserver_response": response.value
-        }))
+            Ok(json!({
+                "timers": timer_list,
+                "count": timer_list.len()
+            }))
+        })
+        .await
     }
 
-    /// Get status of all audio zones
-    pub async fn get_audio_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut audio_uuids = Vec::new();
-        let mut audio_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-            if control_type.contains("Audio") || control_type == "MediaController" {
-                audio_uuids.push(uuid.clone());
-                audio_info.push((uuid.clone(), control.clone()));
+    /// Start a stairwell light switch or timer
+    ///
+    /// Without `duration_seconds` the timer runs for the duration configured
+    /// in Loxone Config. A custom duration (1 s to 24 h) switches it on and
+    /// off again after that time; the countdown runs in this server and is
+    /// lost on restart.
+    pub async fn start_timer(
+        &self,
+        device: String,
+        duration_seconds: Option<u64>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("start_timer", async move {
+            self.ensure_connected()?;
+            if let Some(seconds) = duration_seconds
+                && !(1..=countdowns::MAX_DURATION_SECS).contains(&seconds)
+            {
+                return Err(format!(
+                    "Duration must be between 1 and {} seconds",
+                    countdowns::MAX_DURATION_SECS
+                ));
             }
-        }
 
-        let live_states = Self::fetch_live_states(client, &audio_uuids).await;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &device)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    timers::TIMER_TYPES.contains(&control_type)
+                })
+                .ok_or_else(|| format!("Timer '{device}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
 
-        let audio_zones: Vec<Value> = audio_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+            let command = match duration_seconds {
+                Some(_) => timers::ON_COMMAND,
+                None => timers::PULSE_COMMAND,
+            };
+            // A pulse must not be cut short by an earlier custom run
+            self.countdowns.cancel(uuid);
+            client
+                .send_command(uuid, command)
+                .await
+                .map_err(|e| format!("Failed to start {name}: {e}"))?;
+            let countdown = duration_seconds.map(|seconds| {
+                self.countdowns.start(
+                    client.clone(),
+                    uuid,
+                    std::time::Duration::from_secs(seconds),
+                )
+            });
+
+            Ok(json!({
+                "device": name,
+                "uuid": uuid,
+                "command_sent": command,
+                "duration_secs": duration_seconds,
+                "ends_at": countdown.map(|c| c.ends_at),
+                "status": "started"
+            }))
+        })
+        .await
+    }
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
+    /// Cancel a running stairwell light switch or timer
+    ///
+    /// Switches the output off and drops a custom-duration countdown.
+    pub async fn cancel_timer(
+        &self,
+        device: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("cancel_timer", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &device)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    timers::TIMER_TYPES.contains(&control_type)
                 })
-            })
-            .collect();
+                .ok_or_else(|| format!("Timer '{device}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
 
-        Ok(json!({
-            "audio_zones": audio_zones,
-            "count": audio_zones.len()
-        }))
+            let had_countdown = self.countdowns.cancel(uuid);
+            client
+                .send_command(uuid, timers::OFF_COMMAND)
+                .await
+                .map_err(|e| format!("Failed to cancel {name}: {e}"))?;
+
+            Ok(json!({
+                "device": name,
+                "uuid": uuid,
+                "command_sent": timers::OFF_COMMAND,
+                "custom_countdown_cancelled": had_countdown,
+                "status": "cancelled"
+            }))
+        })
+        .await
     }
 
     // ========================================================================
-    // SENSOR TOOLS
+    // CLIMATE TOOLS
     // ========================================================================
 
-    /// Get all sensor readings
+    /// Control room temperature settings
     ///
-    /// Returns current values from all sensors (temperature, humidity, motion, etc.)
-    pub async fn get_sensor_readings(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// Set target temperature for a room or zone. Supports heating and cooling modes.
+    pub async fn set_temperature(
+        &self,
+        room: String,
+        temperature: f64,
+        mode: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_temperature", async move {
+            self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            if !(5.0..=35.0).contains(&temperature) {
+                return Err("Temperature must be between 5°C and 35°C".to_string());
+            }
 
-        let mut sensor_uuids = Vec::new();
-        let mut sensor_info = Vec::new();
+            let mode = mode.unwrap_or_else(|| "auto".to_string());
+            if !["heat", "cool", "auto", "off"].contains(&mode.as_str()) {
+                return Err(format!("Invalid mode '{mode}'. Use: heat, cool, auto, off"));
+            }
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-            // Match sensor types
-            if matches!(
-                control_type,
-                "InfoOnlyAnalog"
-                    | "InfoOnlyDigital"
-                    | "PresenceDetector"
-                    | "MotionSensor"
-                    | "SmokeAlarm"
-                    | "Meter"
-                    | "Sensor"
-            ) {
-                sensor_uuids.push(uuid.clone());
-                sensor_info.push((uuid.clone(), control.clone()));
-            }
-        }
+            let climate_types = &["IRoomController", "Intelligent Room Controller"];
 
-        let live_states = Self::fetch_live_states(client, &sensor_uuids).await;
+            // Try to find the thermostat: first by direct UUID/name, then by room
+            let thermostat = Self::find_control_by_id_or_name(&structure, &room);
+            let targets: Vec<(&String, &Value)> = if let Some(target) = thermostat {
+                // Check it's actually a climate controller
+                let control_type = target.1.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                if climate_types.contains(&control_type) {
+                    vec![target]
+                } else {
+                    // Not a climate controller, search by room
+                    Self::find_climate_in_room(&structure, &room, climate_types)?
+                }
+            } else {
+                Self::find_climate_in_room(&structure, &room, climate_types)?
+            };
 
-        let sensors: Vec<Value> = sensor_info
-            .iter()
-            .map(|(uuid, control)| {
+            if targets.is_empty() {
+                return Err(format!("No climate controller found for room '{room}'"));
+            }
+
+            let command = format!("settemp/{temperature}");
+            let mut results = Vec::new();
+            for (uuid, control) in &targets {
                 let name = control
                     .get("name")
                     .and_then(|v| v.as_str())
                     .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+                match client.send_command(uuid, &command).await {
+                    Ok(response) => {
+                        results.push(json!({
+                            "uuid": uuid,
+                            "name": name,
+                            "status": "executed",
+                            "miniserver_response": response.value
+                        }));
+                    }
+                    Err(e) => {
+                        results.push(json!({
+                            "uuid": uuid,
+                            "name": name,
+                            "status": "error",
+                            "error": format!("{e}")
+                        }));
+                    }
+                }
+            }
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "value": state
-                })
-            })
-            .collect();
+            // Also send mode command if not "auto" (the default)
+            if mode != "auto" {
+                let mode_command = match mode.as_str() {
+                    "heat" => "setmode/1",
+                    "cool" => "setmode/2",
+                    "off" => "setmode/0",
+                    _ => "setmode/3", // auto
+                };
+                for (uuid, _) in &targets {
+                    if let Err(e) = client.send_command(uuid, mode_command).await {
+                        warn!("Failed to set mode on {uuid}: {e}");
+                    }
+                }
+            }
 
-        Ok(json!({
-            "sensors": sensors,
-            "count": sensors.len()
-        }))
+            Ok(json!({
+                "room": room,
+                "target_temperature": temperature,
+                "mode": mode,
+                "command_sent": command,
+                "controllers_affected": results.len(),
+                "results": results
+            }))
+        })
+        .await
     }
 
-    /// Get door and window sensor status
-    ///
-    /// Returns open/closed state of all door and window sensors
-    pub async fn get_door_window_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// Get current climate status for all rooms
+    pub async fn get_climate_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_climate_status", async move {
+            self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-        let mut dw_uuids = Vec::new();
-        let mut dw_info = Vec::new();
+            let mut climate_uuids = Vec::new();
+            let mut climate_info = Vec::new();
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-            let name = control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_lowercase();
-
-            // Match door/window sensors
-            if control_type == "InfoOnlyDigital"
-                && (name.contains("door")
-                    || name.contains("window")
-                    || name.contains("tür")
-                    || name.contains("fenster"))
-            {
-                dw_uuids.push(uuid.clone());
-                dw_info.push((uuid.clone(), control.clone()));
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                if matches!(
+                    control_type,
+                    "IRoomController" | "Intelligent Room Controller"
+                ) {
+                    climate_uuids.push(uuid.clone());
+                    climate_info.push((uuid.clone(), control.clone()));
+                }
             }
-        }
 
-        let live_states = Self::fetch_live_states(client, &dw_uuids).await;
+            let live_states = Self::fetch_live_states(client, &climate_uuids).await;
 
-        let door_windows: Vec<Value> = dw_info
-            .iter()
-            .map(|(uuid, control)| {
-                let display_name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+            let climate_data: Vec<Value> = climate_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
 
-                json!({
-                    "uuid": uuid,
-                    "name": display_name,
-                    "room": room,
-                    "state": state
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "room": room,
+                        "room_metadata": self.room_metadata(&structure, room),
+                        "state": state
+                    })
                 })
-            })
-            .collect();
+                .collect();
 
-        Ok(json!({
-            "door_window_sensors": door_windows,
-            "count": door_windows.len()
-        }))
+            Ok(json!({
+                "climate_controllers": climate_data,
+                "count": climate_data.len()
+            }))
+        })
+        .await
     }
 
-    /// Get motion detector status
-    pub async fn get_motion_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+    /// Get heating valve diagnostics
+    ///
+    /// Reports per-room valve positions and actuator duty cycles over the last
+    /// two hours and flags stuck valves (fully open, room not warming up)
+    pub async fn get_valve_diagnostics(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_valve_diagnostics", async move {
+            self.ensure_connected()?;
 
-        let mut motion_uuids = Vec::new();
-        let mut motion_info = Vec::new();
+            let client = self.get_client()?;
+            if !self.heating_diagnostics.has_samples().await {
+                self.heating_diagnostics
+                    .sample(client.as_ref())
+                    .await
+                    .map_err(|e| format!("Failed to read valve states: {e}"))?;
+            }
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            let mut diagnoses = self.heating_diagnostics.diagnose(chrono::Utc::now()).await;
+            if let Some(ref room_name) = room {
+                let lower = room_name.to_lowercase();
+                diagnoses.retain(|d| {
+                    d.room
+                        .as_ref()
+                        .is_some_and(|r| r.to_lowercase().contains(&lower))
+                });
+            }
 
-            if matches!(control_type, "PresenceDetector" | "MotionSensor") {
-                motion_uuids.push(uuid.clone());
-                motion_info.push((uuid.clone(), control.clone()));
+            let mut rooms: std::collections::BTreeMap<String, Vec<Value>> =
+                std::collections::BTreeMap::new();
+            for diagnosis in &diagnoses {
+                let room_name = diagnosis
+                    .room
+                    .clone()
+                    .unwrap_or_else(|| "Unknown".to_string());
+                rooms.entry(room_name).or_default().push(json!(diagnosis));
             }
-        }
+            let stuck: Vec<&str> = diagnoses
+                .iter()
+                .filter(|d| d.stuck)
+                .map(|d| d.name.as_str())
+                .collect();
+
+            Ok(json!({
+                "rooms": rooms,
+                "valve_count": diagnoses.len(),
+                "stuck_valves": stuck,
+                "window_minutes": crate::services::heating_diagnostics::HISTORY_WINDOW_MINUTES,
+                "sample_interval_seconds": crate::services::heating_diagnostics::SAMPLE_INTERVAL_SECS
+            }))
+        })
+        .await
+    }
 
-        let live_states = Self::fetch_live_states(client, &motion_uuids).await;
+    /// Get valve opening and heat demand per heating zone
+    ///
+    /// Reads the actuating outputs of each room controller (falling back to
+    /// a valve actuator in the room, then to the room temperature) and lists
+    /// which rooms are currently calling for heat
+    pub async fn get_heating_zones(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_heating_zones", async move {
+            self.ensure_connected()?;
 
-        let motion_sensors: Vec<Value> = motion_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let zones = match &room {
+                Some(room_name) => {
+                    let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                    Self::find_controls_by_type_in_room(
+                        &structure,
+                        &room_uuid,
+                        heating_zones::ZONE_CONTROLLER_TYPES,
+                    )
+                }
+                None => {
+                    Self::find_controls_by_type(&structure, heating_zones::ZONE_CONTROLLER_TYPES)
+                }
+            };
+            if zones.is_empty() {
+                return Err("No room controller found".to_string());
+            }
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "room": room,
-                    "state": state
-                })
-            })
-            .collect();
+            let zone_states: Vec<_> = zones
+                .iter()
+                .map(|(_, control)| heating_zones::state_uuids(control))
+                .collect();
+            let actuators = ValveSource::discover(&structure);
+            let uuids: Vec<String> = zone_states
+                .iter()
+                .flat_map(|states| states.values().cloned())
+                .chain(actuators.iter().map(|a| a.position_state.clone()))
+                .collect();
+            let values = client
+                .get_state_values(&uuids)
+                .await
+                .map_err(|e| format!("Failed to read heating states: {e}"))?;
+
+            // Most open actuator per room
+            let mut actuator_percent: std::collections::HashMap<&str, f64> =
+                std::collections::HashMap::new();
+            for actuator in &actuators {
+                let position = values
+                    .get(&actuator.position_state)
+                    .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()));
+                if let (Some(room), Some(position)) = (actuator.room.as_deref(), position) {
+                    let percent = if actuator.fractional {
+                        position * 100.0
+                    } else {
+                        position
+                    };
+                    let entry = actuator_percent.entry(room).or_insert(0.0);
+                    *entry = entry.max(percent.clamp(0.0, 100.0));
+                }
+            }
 
-        Ok(json!({
-            "motion_sensors": motion_sensors,
-            "count": motion_sensors.len()
-        }))
+            let mut statuses: Vec<_> = zones
+                .iter()
+                .zip(zone_states)
+                .map(|((uuid, control), states)| {
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .and_then(|room| structure.rooms.get(room))
+                        .and_then(|room| room.get("name"))
+                        .and_then(|v| v.as_str());
+                    let named = states
+                        .into_iter()
+                        .filter_map(|(name, state_uuid)| {
+                            values.get(&state_uuid).map(|v| (name, v.clone()))
+                        })
+                        .collect();
+                    heating_zones::evaluate(
+                        uuid,
+                        control
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Unknown"),
+                        room,
+                        &named,
+                        room.and_then(|room| actuator_percent.get(room).copied()),
+                    )
+                })
+                .collect();
+            statuses.sort_by(|a, b| {
+                b.valve_percent
+                    .unwrap_or(0.0)
+                    .total_cmp(&a.valve_percent.unwrap_or(0.0))
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+            let calling: Vec<&str> = statuses
+                .iter()
+                .filter(|zone| zone.demand == Demand::Heating)
+                .map(|zone| zone.room.as_deref().unwrap_or(&zone.name))
+                .collect();
+
+            Ok(json!({
+                "zones": statuses,
+                "zone_count": statuses.len(),
+                "calling_for_heat": calling,
+                "demand_threshold_percent": heating_zones::DEMAND_THRESHOLD_PERCENT
+            }))
+        })
+        .await
     }
 
-    // ========================================================================
-    // WEATHER TOOLS
-    // ========================================================================
-
-    /// Get current weather data
+    /// Get the heating schedule of a room
     ///
-    /// Returns weather station readings (temperature, humidity, wind, rain)
-    pub async fn get_weather(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+    /// Returns the comfort/eco periods of the room controller's schedule,
+    /// grouped by operating mode
+    pub async fn get_climate_schedule(
+        &self,
+        room: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_climate_schedule", async move {
+            self.ensure_connected()?;
 
-        let mut weather_uuids = Vec::new();
-        let mut weather_info = Vec::new();
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            let (uuid, control) = Self::find_room_controller(&structure, &room)?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+            let periods = Self::read_climate_schedule(client, control).await?;
+
+            let mut modes: std::collections::BTreeMap<u32, Vec<Value>> =
+                std::collections::BTreeMap::new();
+            for period in &periods {
+                modes.entry(period.mode).or_default().push(json!({
+                    "period": period.time_range(),
+                    "level": period.level
+                }));
+            }
+
+            Ok(json!({
+                "controller": name,
+                "uuid": uuid,
+                "modes": modes,
+                "period_count": periods.len()
+            }))
+        })
+        .await
+    }
+
+    /// Set the heating schedule of a room
+    ///
+    /// Replaces the periods of one operating mode (mode_id, default 0).
+    /// Entries use "HH:MM-HH:MM" with an optional level ("06:00-08:00@eco";
+    /// comfort by default) and must not overlap; other modes are kept
+    pub async fn set_climate_schedule(
+        &self,
+        room: String,
+        entries: Vec<String>,
+        mode_id: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_climate_schedule", async move {
+            self.ensure_connected()?;
+
+            let mode_id = mode_id.unwrap_or(0);
+            let parsed = entries
+                .iter()
+                .map(|entry| climate_schedule::SchedulePeriod::parse(mode_id, entry))
+                .collect::<crate::error::Result<Vec<_>>>()
+                .map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (_, control) = Self::find_room_controller(&structure, &room)?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+            let (daytimer, _) = climate_schedule::daytimer(control)
+                .ok_or_else(|| format!("Room controller '{name}' has no schedule"))?;
+
+            let current = Self::read_climate_schedule(client, control).await?;
+            let schedule = climate_schedule::replace_mode(&current, mode_id, &parsed)
+                .map_err(|e| e.to_string())?;
+            let command = climate_schedule::schedule_command(&schedule);
+
+            let response = client
+                .send_command(daytimer, &command)
+                .await
+                .map_err(|e| format!("Failed to set heating schedule for {name}: {e}"))?;
+
+            let periods: Vec<Value> = schedule
+                .iter()
+                .filter(|p| p.mode == mode_id)
+                .map(|p| p.to_json())
+                .collect();
+            Ok(json!({
+                "controller": name,
+                "schedule_uuid": daytimer,
+                "mode_id": mode_id,
+                "periods": periods,
+                "other_periods_kept": schedule.len() - periods.len(),
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Get hot water status
+    ///
+    /// Returns boilers/hot water tanks with tank and target temperature
+    pub async fn get_hot_water_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_hot_water_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let units = self.fetch_hot_water_units(client, &structure).await;
+
+            Ok(json!({
+                "hot_water": units,
+                "count": units.len()
+            }))
+        })
+        .await
+    }
+
+    /// Boost hot water heating
+    ///
+    /// Heats the tank to comfort temperature for the given minutes (default 60,
+    /// max 240). The unit may be omitted when there is only one
+    pub async fn boost_hot_water(
+        &self,
+        unit: Option<String>,
+        minutes: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("boost_hot_water", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (uuid, control) = match unit.as_deref() {
+                Some(identifier) => Self::find_hot_water_control(
+                    &structure,
+                    Some(identifier),
+                    &[HotWaterRole::Controller, HotWaterRole::BoostInput],
+                )?,
+                None => Self::find_hot_water_control(&structure, None, &[HotWaterRole::Controller])
+                    .or_else(|_| {
+                        Self::find_hot_water_control(&structure, None, &[HotWaterRole::BoostInput])
+                    })?,
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let minutes = minutes.unwrap_or(hot_water::DEFAULT_BOOST_MINUTES);
+            let command = hot_water::boost_command(control, minutes).map_err(|e| e.to_string())?;
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to boost hot water {name}: {e}"))?;
+
+            Ok(json!({
+                "unit": name,
+                "uuid": uuid,
+                "boost_minutes": minutes,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Set hot water target temperature
+    ///
+    /// Sets the tank temperature the controller heats to (30-75°C). The unit
+    /// may be omitted when there is only one
+    pub async fn set_hot_water_temperature(
+        &self,
+        unit: Option<String>,
+        temperature: f64,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_hot_water_temperature", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (uuid, control) = Self::find_hot_water_control(
+                &structure,
+                unit.as_deref(),
+                &[HotWaterRole::Controller],
+            )?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let command = hot_water::target_temperature_command(control, temperature)
+                .map_err(|e| e.to_string())?;
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to set hot water temperature for {name}: {e}"))?;
+
+            Ok(json!({
+                "unit": name,
+                "uuid": uuid,
+                "target_temperature": temperature,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Set hot water heating schedule
+    ///
+    /// Replaces the heating periods of the hot water schedule. Entries use
+    /// "HH:MM-HH:MM" with an optional tank temperature ("06:00-07:00@55");
+    /// mode_id selects the operating mode the entries apply to (default 0)
+    pub async fn set_hot_water_schedule(
+        &self,
+        unit: Option<String>,
+        entries: Vec<String>,
+        mode_id: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_hot_water_schedule", async move {
+            self.ensure_connected()?;
+
+            let parsed = entries
+                .iter()
+                .map(|entry| hot_water::ScheduleEntry::parse(entry))
+                .collect::<crate::error::Result<Vec<_>>>()
+                .map_err(|e| e.to_string())?;
+            let mode_id = mode_id.unwrap_or(0);
+            let command =
+                hot_water::schedule_command(mode_id, &parsed).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (_, control) = Self::find_hot_water_control(
+                &structure,
+                unit.as_deref(),
+                &[HotWaterRole::Controller],
+            )?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+            let daytimer = hot_water::find_daytimer(control)
+                .ok_or_else(|| format!("Hot water control '{name}' has no schedule"))?;
+
+            let response = client
+                .send_command(daytimer, &command)
+                .await
+                .map_err(|e| format!("Failed to set hot water schedule for {name}: {e}"))?;
+
+            Ok(json!({
+                "unit": name,
+                "schedule_uuid": daytimer,
+                "mode_id": mode_id,
+                "entries": entries,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Get the state of split AC and fan coil units
+    ///
+    /// Returns mode, fan speed, target and room temperature of each unit
+    /// together with the modes, fan speeds and setpoint range it supports
+    pub async fn get_ac_status(
+        &self,
+        unit: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_ac_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let units = Self::find_ac_units(&structure, unit.as_deref())?;
+            let mut statuses = Self::fetch_ac_statuses(client, &units).await;
+
+            let unit_list: Vec<Value> = units
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "capabilities": Capabilities::of(control),
+                        "status": statuses.remove(*uuid)
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "units": unit_list,
+                "count": unit_list.len()
+            }))
+        })
+        .await
+    }
+
+    /// Set mode, fan speed or target temperature of an AC or fan coil unit
+    ///
+    /// Modes: off, auto, heat, cool, dry, fan; each unit accepts only the
+    /// modes it supports (fan coils cannot dry). Fan speed 0 is automatic.
+    /// Commands are mapped to the unit type, split AC or fan coil.
+    pub async fn set_ac(
+        &self,
+        unit: Option<String>,
+        mode: Option<String>,
+        fan_speed: Option<u8>,
+        temperature: Option<f64>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_ac", async move {
+            self.ensure_connected()?;
+
+            let settings = AcSettings {
+                mode: mode
+                    .as_deref()
+                    .map(AcMode::parse)
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+                fan_speed,
+                temperature,
+            };
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let units = Self::find_ac_units(&structure, unit.as_deref())?;
+            let [(uuid, control)] = units[..] else {
+                return Err("Several AC or fan coil units found; name the unit".to_string());
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+            let capabilities = Capabilities::of(control)
+                .ok_or_else(|| format!("'{name}' is not an AC or fan coil unit"))?;
+            let commands = settings
+                .commands(&capabilities)
+                .map_err(|e| format!("{name}: {e}"))?;
+
+            let previous = Self::fetch_ac_statuses(client, &[(uuid, control)])
+                .await
+                .remove(uuid);
+            for command in &commands {
+                client
+                    .send_command(uuid, command)
+                    .await
+                    .map_err(|e| format!("Failed to control {name} ({command}): {e}"))?;
+            }
+
+            Ok(json!({
+                "unit": name,
+                "uuid": uuid,
+                "unit_type": capabilities.kind,
+                "commands_sent": commands,
+                "previous_status": previous,
+                "status": "executed"
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // BLINDS/ROLLADEN TOOLS
+    // ========================================================================
+
+    /// Control blinds/rolladen position
+    ///
+    /// Set blind position (0=fully open, 100=fully closed) or use actions like up/down/stop.
+    /// Target is a blind UUID/name, or `facade:<direction>` (e.g. `facade:south`) for all
+    /// blinds in rooms with windows on that facade (from the room metadata config).
+    /// With `confirm`, waits until the blind reaches the position or starts moving.
+    pub async fn control_blinds(
+        &self,
+        target: String,
+        action: Option<String>,
+        position: Option<u8>,
+        confirm: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let arguments = json!({
+            "target": target,
+            "action": action,
+            "position": position,
+            "confirm": confirm
+        });
+        self.run_tool_with("control_blinds", arguments, async move {
+            self.ensure_connected()?;
+
+            // Determine command based on action or position
+            let command = if let Some(pos) = position {
+                if pos > 100 {
+                    return Err("Position must be between 0-100".to_string());
+                }
+                format!("ManualPosition/{pos}")
+            } else if let Some(ref act) = action {
+                match ActionAliases::global()
+                    .normalize_action(act, &["up", "down", "stop", "shade"])
+                {
+                    Some("up") => "FullUp".to_string(),
+                    Some("down") => "FullDown".to_string(),
+                    Some("stop") => "Stop".to_string(),
+                    Some("shade") => "Shade".to_string(),
+                    _ => {
+                        return Err(format!(
+                            "Invalid action '{act}'. Use: up, down, stop, shade"
+                        ));
+                    }
+                }
+            } else {
+                return Err("Either action or position must be provided".to_string());
+            };
+
+            let client = self.get_client()?;
+
+            if let Some(facade) = target.strip_prefix("facade:") {
+                let orientation = Orientation::parse(facade)
+                    .ok_or_else(|| format!("Unknown facade direction '{facade}'"))?;
+                let structure = client
+                    .get_structure()
+                    .await
+                    .map_err(|e| format!("Failed to get structure: {e}"))?;
+                let rooms = self.rooms_with_facade(&structure, orientation);
+                if rooms.is_empty() {
+                    return Err(format!(
+                        "No rooms with windows facing {facade} in the room metadata config"
+                    ));
+                }
+
+                let mut results = Vec::new();
+                for room in &rooms {
+                    for (uuid, control) in Self::find_controls_by_type_in_room(
+                        &structure,
+                        room,
+                        &["Jalousie", "Blinds", "Rolladen"],
+                    ) {
+                        let name = control.get("name").and_then(|v| v.as_str());
+                        let result = client.send_command(uuid, &command).await;
+                        results.push(json!({
+                            "uuid": uuid,
+                            "name": name,
+                            "room": room,
+                            "success": result.is_ok(),
+                            "error": result.err().map(|e| e.to_string())
+                        }));
+                    }
+                }
+                return Ok(json!({
+                    "target": target,
+                    "facade": orientation,
+                    "action": action,
+                    "position": position,
+                    "command_sent": command,
+                    "blinds": results,
+                    "count": results.len()
+                }));
+            }
+
+            // Target can be a UUID or a device name; send command directly
+            let response = client
+                .send_command(&target, &command)
+                .await
+                .map_err(|e| format!("Failed to send blinds command to {target}: {e}"))?;
+
+            Ok(json!({
+                "target": target,
+                "action": action,
+                "position": position,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Set blind position and slat angle
+    ///
+    /// Moves a Jalousie to `position` (0 up to 100 down) and/or tilts its
+    /// slats to `slat_angle` (0 open to 100 closed). With confirm=true,
+    /// waits for the blind to report the new values
+    pub async fn set_blind_position(
+        &self,
+        target: String,
+        position: Option<u8>,
+        slat_angle: Option<u8>,
+        confirm: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let arguments = json!({
+            "target": target,
+            "position": position,
+            "slat_angle": slat_angle,
+            "confirm": confirm
+        });
+        self.run_tool_with("set_blind_position", arguments, async move {
+            self.ensure_connected()?;
+
+            if position.is_none() && slat_angle.is_none() {
+                return Err("Either position or slat_angle must be provided".to_string());
+            }
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &target)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    blinds::BLIND_CONTROL_TYPES.contains(&control_type)
+                })
+                .ok_or_else(|| format!("Blind '{target}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            // Validate both values before moving anything
+            let mut commands = Vec::new();
+            if let Some(position) = position {
+                commands.push(blinds::position_command(position).map_err(|e| e.to_string())?);
+            }
+            if let Some(angle) = slat_angle {
+                commands.push(blinds::slat_command(control, angle).map_err(|e| e.to_string())?);
+            }
+
+            let mut results = Vec::new();
+            for command in &commands {
+                client
+                    .send_command(uuid, command)
+                    .await
+                    .map_err(|e| format!("Failed to send blinds command to {name}: {e}"))?;
+                results.push(json!({"command_sent": command, "status": "executed"}));
+            }
+
+            Ok(json!({
+                "blind": name,
+                "uuid": uuid,
+                "position": position,
+                "slat_angle": slat_angle,
+                "commands": results
+            }))
+        })
+        .await
+    }
+
+    /// Get status of all blinds/rolladen
+    pub async fn get_blinds_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_blinds_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut blind_uuids = Vec::new();
+            let mut blind_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                if matches!(control_type, "Jalousie" | "Blinds" | "Rolladen") {
+                    blind_uuids.push(uuid.clone());
+                    blind_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &blind_uuids).await;
+
+            let blinds: Vec<Value> = blind_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "room": room,
+                        "room_metadata": self.room_metadata(&structure, room),
+                        "state": state
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "blinds": blinds,
+                "count": blinds.len()
+            }))
+        })
+        .await
+    }
+
+    /// Get the storm protection (wind lock) of blinds and awnings
+    ///
+    /// Lists the blinds the Miniserver has locked because of wind, which
+    /// ignore commands until the lock releases, with the current wind and
+    /// the expected release
+    pub async fn get_storm_protection(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_storm_protection", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let blinds = match &room {
+                Some(room_name) => {
+                    let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                    Self::find_controls_by_type_in_room(
+                        &structure,
+                        &room_uuid,
+                        blinds::BLIND_CONTROL_TYPES,
+                    )
+                }
+                None => Self::find_controls_by_type(&structure, blinds::BLIND_CONTROL_TYPES),
+            };
+
+            let state_uuids: Vec<String> = blinds
+                .iter()
+                .flat_map(|(_, control)| {
+                    let (lock, info) = storm_protection::state_uuids(control);
+                    lock.into_iter().chain(info)
+                })
+                .collect();
+            let values = if state_uuids.is_empty() {
+                std::collections::HashMap::new()
+            } else {
+                client
+                    .get_state_values(&state_uuids)
+                    .await
+                    .map_err(|e| format!("Failed to read blind states: {e}"))?
+            };
+
+            let locked: Vec<Value> = blinds
+                .iter()
+                .filter_map(|(uuid, control)| {
+                    let (locked, reason) = storm_protection::read_lock(control, &values);
+                    locked.then(|| {
+                        json!({
+                            "uuid": uuid,
+                            "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                            "room": control
+                                .get("room")
+                                .and_then(|v| v.as_str())
+                                .and_then(|room| structure.rooms.get(room))
+                                .and_then(|room| room.get("name")),
+                            "reason": reason
+                        })
+                    })
+                })
+                .collect();
+
+            let reading = weather_protection::read_weather(client.as_ref(), &structure)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to read wind sensors: {e}");
+                    Default::default()
+                });
+            let calm_since = self.weather_protection.calm_since().await;
+            let release = (!locked.is_empty()).then(|| {
+                storm_protection::estimate_release(
+                    reading.wind_kmh,
+                    self.weather_protection.config().wind_threshold_kmh,
+                    calm_since,
+                    chrono::Utc::now(),
+                )
+            });
+
+            Ok(json!({
+                "active": !locked.is_empty(),
+                "locked_devices": locked,
+                "locked_count": locked.len(),
+                "blinds_checked": blinds.len(),
+                "wind_kmh": reading.wind_kmh,
+                "expected_release": release,
+                "release_delay_minutes": storm_protection::RELEASE_DELAY_MINUTES
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // DISCOVERY TOOLS
+    // ========================================================================
+
+    /// List all rooms in the Loxone system
+    ///
+    /// Each room lists its running stairwell light switches and timers with
+    /// the remaining seconds.
+    pub async fn list_rooms(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_rooms", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            // Running timers per room; the overview works without them
+            let timers = Self::find_controls_by_type(&structure, timers::TIMER_TYPES);
+            let timer_states = self.read_timers(client, &timers).await.unwrap_or_else(|e| {
+                warn!("{e}");
+                Vec::new()
+            });
+            let mut running_timers: std::collections::HashMap<&str, Vec<Value>> =
+                std::collections::HashMap::new();
+            for ((uuid, control), (state, _)) in timers.iter().zip(&timer_states) {
+                if let (Some(room), Some(remaining)) = (
+                    control.get("room").and_then(|v| v.as_str()),
+                    state.remaining_secs(),
+                ) {
+                    running_timers.entry(room).or_default().push(json!({
+                        "uuid": uuid,
+                        "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "remaining_secs": remaining
+                    }));
+                }
+            }
+
+            let rooms: Vec<_> = structure
+                .rooms
+                .iter()
+                .map(|(uuid, room)| {
+                    json!({
+                        "uuid": uuid,
+                        "name": room.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "type": room.get("type").and_then(|v| v.as_str()).unwrap_or("Room"),
+                        "metadata": self.room_metadata(&structure, uuid),
+                        "running_timers": running_timers.get(uuid.as_str()).cloned().unwrap_or_default()
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "rooms": rooms,
+                "count": rooms.len()
+            }))
+        })
+        .await
+    }
+
+    /// List all devices in a specific room or system-wide
+    ///
+    /// Each device carries its Loxone category (name, icon, color) as set up
+    /// in Loxone Config. Filter by `category`: a Loxone category name such
+    /// as "Pool", or one of lights, blinds, climate, sensors, ...
+    pub async fn list_devices(
+        &self,
+        room: Option<String>,
+        category: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_devices", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let categories = categories::category_index(&structure);
+
+            let mut devices: Vec<_> = structure
+                .controls
+                .iter()
+                .filter(|(_, control)| {
+                    if let Some(ref room_filter) = room {
+                        control
+                            .get("room")
+                            .and_then(|v| v.as_str())
+                            .map(|r| r.to_lowercase().contains(&room_filter.to_lowercase()))
+                            .unwrap_or(false)
+                    } else {
+                        true
+                    }
+                })
+                .map(|(uuid, control)| {
+                    let device_type = control
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let kind = ClientContext::categorize_device(device_type);
+                    let loxone_category = categories::control_category(&categories, control);
+                    (uuid, control, device_type, kind, loxone_category)
+                })
+                .filter(|(_, _, _, kind, loxone_category)| {
+                    category.as_ref().is_none_or(|wanted| {
+                        kind.eq_ignore_ascii_case(wanted)
+                            || loxone_category.as_ref().is_some_and(|cat| {
+                                cat.name.eq_ignore_ascii_case(wanted) || cat.uuid == *wanted
+                            })
+                    })
+                })
+                .map(|(uuid, control, device_type, kind, loxone_category)| {
+                    let rating = self.favorites.rating(uuid, control);
+                    let device = json!({
+                        "uuid": uuid,
+                        "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "type": device_type,
+                        "room": control.get("room").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "category": loxone_category
+                            .as_ref()
+                            .map(|cat| cat.name.as_str())
+                            .or_else(|| control.get("cat").and_then(|v| v.as_str()))
+                            .unwrap_or("Unknown"),
+                        "loxone_category": loxone_category,
+                        "kind": kind,
+                        "favorite": rating.favorite,
+                        "rating": rating.rating
+                    });
+                    (rating, device)
+                })
+                .collect();
+            // Favorites first, as in the Loxone app
+            devices.sort_by(|(a, a_device), (b, b_device)| {
+                a.sort_key().cmp(&b.sort_key()).then_with(|| {
+                    let name =
+                        |device: &serde_json::Value| device["name"].as_str().map(str::to_lowercase);
+                    name(a_device).cmp(&name(b_device))
+                })
+            });
+            let devices: Vec<_> = devices.into_iter().map(|(_, device)| device).collect();
+
+            Ok(json!({
+                "devices": devices,
+                "count": devices.len(),
+                "filter": room,
+                "category_filter": category
+            }))
+        })
+        .await
+    }
+
+    /// List favorite and rated devices, as ordered in the Loxone app
+    ///
+    /// Ratings come from the structure file unless overridden with
+    /// `set_favorite`. With `room` only the devices of that room are listed.
+    pub async fn get_favorites(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_favorites", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let room_uuid = match &room {
+                Some(name) => Some(
+                    Self::resolve_room_uuid(&structure, name)
+                        .ok_or_else(|| Self::room_not_found(&structure, name))?,
+                ),
+                None => None,
+            };
+
+            let mut favorites: Vec<_> = structure
+                .controls
+                .iter()
+                .filter(|(_, control)| {
+                    room_uuid.as_ref().is_none_or(|wanted| {
+                        control.get("room").and_then(|v| v.as_str()) == Some(wanted.as_str())
+                    })
+                })
+                .map(|(uuid, control)| (uuid, control, self.favorites.rating(uuid, control)))
+                .filter(|(_, _, rating)| rating.favorite || rating.rating > 0)
+                .collect();
+            favorites.sort_by(|(_, a_control, a), (_, b_control, b)| {
+                let name = |control: &serde_json::Value| {
+                    control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_lowercase)
+                };
+                a.sort_key()
+                    .cmp(&b.sort_key())
+                    .then_with(|| name(a_control).cmp(&name(b_control)))
+            });
+
+            let devices: Vec<_> = favorites
+                .into_iter()
+                .map(|(uuid, control, rating)| {
+                    let room_name = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .and_then(|room| structure.rooms.get(room))
+                        .and_then(|room| room.get("name"))
+                        .and_then(|v| v.as_str());
+                    json!({
+                        "uuid": uuid,
+                        "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "type": control.get("type").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "room": room_name,
+                        "favorite": rating.favorite,
+                        "rating": rating.rating,
+                        "overridden": self.favorites.is_overridden(uuid)
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "room": room,
+                "devices": devices,
+                "count": devices.len()
+            }))
+        })
+        .await
+    }
+
+    /// Mark a device as favorite or change its rating
+    ///
+    /// Unset values keep the device's current rating. Without `favorite`
+    /// and `rating` the override is cleared and the structure's rating
+    /// applies again. The Miniserver cannot store ratings, so overrides are
+    /// kept by the server until it restarts.
+    pub async fn set_favorite(
+        &self,
+        device: String,
+        favorite: Option<bool>,
+        rating: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_favorite", async move {
+            self.ensure_connected()?;
+
+            if rating.is_some_and(|rating| rating > favorites::MAX_RATING) {
+                return Err(format!(
+                    "Rating must be between 0 and {}",
+                    favorites::MAX_RATING
+                ));
+            }
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &device)
+                .ok_or_else(|| format!("Device '{device}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let previous = self.favorites.rating(uuid, control);
+            let status = if favorite.is_none() && rating.is_none() {
+                self.favorites.clear(uuid);
+                "reset"
+            } else {
+                self.favorites.set(
+                    uuid,
+                    Rating {
+                        favorite: favorite.unwrap_or(previous.favorite),
+                        rating: rating.unwrap_or(previous.rating),
+                    },
+                );
+                "updated"
+            };
+            let current = self.favorites.rating(uuid, control);
+
+            Ok(json!({
+                "device": name,
+                "uuid": uuid,
+                "favorite": current.favorite,
+                "rating": current.rating,
+                "previous": previous,
+                "overridden": self.favorites.is_overridden(uuid),
+                "status": status
+            }))
+        })
+        .await
+    }
+
+    /// Get detailed information about a specific device
+    pub async fn get_device_info(
+        &self,
+        device_id: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_device_info", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            // Find device by UUID or name
+            let device = structure.controls.iter().find(|(uuid, control)| {
+                uuid.to_string() == device_id
+                    || control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .map(|n| n.to_lowercase().contains(&device_id.to_lowercase()))
+                        .unwrap_or(false)
+            });
+
+            match device {
+                Some((uuid, control)) => {
+                    // Category and state meanings of the type
+                    let descriptor = control
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .and_then(|t| DeviceTypeRegistry::global().descriptor(t));
+
+                    // Current values of the described states, by meaning
+                    let described = descriptor
+                        .map(|d| d.state_uuids(control))
+                        .unwrap_or_default();
+                    let state_uuids: Vec<String> =
+                        described.iter().map(|(_, uuid)| uuid.clone()).collect();
+                    let values = if state_uuids.is_empty() {
+                        std::collections::HashMap::new()
+                    } else {
+                        client
+                            .get_state_values(&state_uuids)
+                            .await
+                            .unwrap_or_default()
+                    };
+                    let state_values: serde_json::Map<String, Value> = described
+                        .into_iter()
+                        .filter_map(|(meaning, uuid)| Some((meaning, values.get(&uuid)?.clone())))
+                        .collect();
+
+                    Ok(json!({
+                        "uuid": uuid,
+                        "control": control,
+                        "device_type": descriptor,
+                        "state_values": state_values
+                    }))
+                }
+                None => Err(format!("Device '{device_id}' not found")),
+            }
+        })
+        .await
+    }
+
+    /// Look up a device reference
+    ///
+    /// Resolves a UUID, device name or room name against the device index
+    /// (`loxone://system/device-index`) and returns the matching controls
+    pub async fn lookup_device(
+        &self,
+        query: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("lookup_device", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let index = DeviceIndex::from_structure(&structure);
+            let matches: Vec<Value> = index
+                .lookup(&query)
+                .into_iter()
+                .map(|(kind, entry)| {
+                    json!({
+                        "uuid": entry.uuid,
+                        "name": entry.name,
+                        "room": entry.room,
+                        "type": entry.device_type,
+                        "match": kind
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "query": query,
+                "matches": matches,
+                "count": matches.len(),
+                "index_version": index.version
+            }))
+        })
+        .await
+    }
+
+    /// Search devices by approximate name
+    ///
+    /// Fuzzy search over all controls that tolerates typos and word order,
+    /// e.g. "kitchen celing light". Words may name the device, its room or
+    /// its category; `room` and `category` rank matching devices higher
+    /// without excluding others. Returns up to `limit` results (default 10,
+    /// max 50) with a score, best first.
+    pub async fn search_devices(
+        &self,
+        query: String,
+        room: Option<String>,
+        category: Option<String>,
+        limit: Option<usize>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("search_devices", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let results = device_search::search(
+                &device_search::candidates(&structure),
+                &query,
+                room.as_deref(),
+                category.as_deref(),
+                limit.unwrap_or(device_search::DEFAULT_SEARCH_RESULTS),
+            );
+
+            Ok(json!({
+                "query": query,
+                "results": results,
+                "count": results.len()
+            }))
+        })
+        .await
+    }
+
+    /// Find configured devices that look unreachable
+    ///
+    /// Cross-references the controls of the structure file with the live
+    /// states and the Tree/Air status of the device status API, and lists
+    /// controls without state or behind an offline peripheral or extension,
+    /// each with suggested causes. Filter by `room`.
+    pub async fn diagnose_devices(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("diagnose_devices", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut controls = device_search::candidates(&structure);
+            controls.retain(|control| {
+                room.as_ref().is_none_or(|room| {
+                    control
+                        .room
+                        .as_ref()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(room))
+                })
+            });
+            if controls.is_empty() {
+                return match room {
+                    Some(room) => Err(room_suggestions::room_not_found(
+                        room_suggestions::structure_room_names(&structure),
+                        &room,
+                    )),
+                    None => Err("No devices found".to_string()),
+                };
+            }
+            let serials: std::collections::HashMap<String, String> = controls
+                .iter()
+                .filter_map(|control| {
+                    let serial = structure
+                        .controls
+                        .get(&control.uuid)
+                        .and_then(device_diagnostics::control_serial)?;
+                    Some((control.uuid.clone(), serial.to_string()))
+                })
+                .collect();
+
+            // Unlike fetch_live_states, a failed read must not report every
+            // device as unreachable
+            let uuids: Vec<String> = controls.iter().map(|c| c.uuid.clone()).collect();
+            let live_states = client
+                .get_device_states(&uuids)
+                .await
+                .map_err(|e| format!("Failed to read device states: {e}"))?;
+
+            // Hardware status is optional, e.g. over WebSocket without HTTP
+            let (links, hardware_status) = match client.get_device_status().await {
+                Ok(status) => (
+                    device_diagnostics::hardware_links(&topology::parse_extensions(&status)),
+                    None,
+                ),
+                Err(e) => (Vec::new(), Some(e.to_string())),
+            };
+            let air = match client.get_air_devices().await {
+                Ok(diagnostics) => air_devices::parse_devices(&diagnostics),
+                Err(_) => Vec::new(),
+            };
+
+            let findings =
+                device_diagnostics::diagnose(&controls, &serials, &live_states, &links, &air);
+            Ok(json!({
+                "checked": controls.len(),
+                "unreachable": findings,
+                "count": findings.len(),
+                "hardware_status_available": hardware_status.is_none(),
+                "hardware_status_error": hardware_status
+            }))
+        })
+        .await
+    }
+
+    /// Get the current values of many devices at once
+    ///
+    /// Compact columnar payload for dashboards: one array per field, with
+    /// `room`/`category` as indexes into the `rooms`/`categories` lists.
+    /// Filter by categories (lights, blinds, climate, sensors, ... or a
+    /// Loxone category name such as "Pool") and room names. Pages hold up to
+    /// `limit` devices (default 500, max 2000) and are cut at about 128 KiB;
+    /// continue with `offset = next_offset`. Values are in `units` (metric
+    /// or imperial), else `mcp.tools.units` or `LOXONE_UNITS`.
+    pub async fn get_all_states(
+        &self,
+        categories: Option<Vec<String>>,
+        rooms: Option<Vec<String>>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+        units: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_all_states", async move {
+            self.ensure_connected()?;
+            let unit_system = match units {
+                Some(units) => Some(UnitSystem::parse(&units).map_err(|e| e.to_string())?),
+                None => self
+                    .config
+                    .as_ref()
+                    .and_then(|config| config.mcp.tools.units),
+            };
+            let client = self.get_client()?;
+            let context = self
+                .context
+                .as_ref()
+                .ok_or_else(|| "Client context not initialized".to_string())?;
+
+            let query = StateQuery {
+                categories: categories.unwrap_or_default(),
+                rooms: rooms.unwrap_or_default(),
+                offset: offset.unwrap_or(0),
+                limit: limit.unwrap_or(bulk_states::DEFAULT_PAGE_SIZE),
+                ..StateQuery::default()
+            };
+            {
+                let known = context.rooms.read().await;
+                let names = || known.values().map(|room| room.name.as_str());
+                if let Some(missing) = query
+                    .rooms
+                    .iter()
+                    .find(|room| !names().any(|name| name.eq_ignore_ascii_case(room)))
+                {
+                    return Err(room_suggestions::room_not_found(names(), missing));
+                }
+            }
+            let devices = context.devices.read().await;
+            let selected = query.select(devices.values()).map_err(|e| e.to_string())?;
+            let page = query.page(&selected);
+            let uuids: Vec<String> = page.iter().map(|d| d.uuid.clone()).collect();
+
+            // The resolver batches the reads and parses values with units
+            let mut values = std::collections::HashMap::new();
+            if let Some(resolver) = &self.value_resolver {
+                match resolver
+                    .resolve_batch_values_in_units(&uuids, unit_system)
+                    .await
+                {
+                    Ok(resolved) => {
+                        for (uuid, resolved) in resolved {
+                            let value = match resolved.numeric_value {
+                                Some(number) => json!(number),
+                                None => json!(resolved.formatted_value),
+                            };
+                            values.insert(
+                                uuid,
+                                StateValue {
+                                    value,
+                                    unit: resolved.unit,
+                                },
+                            );
+                        }
+                    }
+                    Err(e) => warn!("Value resolver unavailable for bulk states: {e}"),
+                }
+            }
+
+            let missing: Vec<String> = uuids
+                .iter()
+                .filter(|uuid| !values.contains_key(*uuid))
+                .cloned()
+                .collect();
+            for (uuid, raw) in Self::fetch_live_states(client, &missing).await {
+                values.insert(uuid, bulk_states::raw_state_value(&raw));
+            }
+
+            let mut response = bulk_states::build_page(&query, &page, &values, selected.len());
+            response["units"] = json!(unit_system.unwrap_or_else(|| {
+                self.value_resolver
+                    .as_ref()
+                    .map(|resolver| resolver.unit_system())
+                    .unwrap_or_default()
+            }));
+            Ok(response)
+        })
+        .await
+    }
+
+    /// Lock a device so it ignores commands
+    ///
+    /// Locked controls reject commands from apps and the API until unlocked;
+    /// the optional reason is shown in the Loxone apps
+    pub async fn lock_device(
+        &self,
+        device: String,
+        reason: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("lock_device", async move {
+            self.set_device_lock(&device, reason.as_deref(), true).await
+        })
+        .await
+    }
+
+    /// Unlock a device locked with lock_device or in the Loxone apps
+    pub async fn unlock_device(
+        &self,
+        device: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("unlock_device", async move {
+            self.set_device_lock(&device, None, false).await
+        })
+        .await
+    }
+
+    /// Lock or unlock a control, reporting its lock status before the change
+    async fn set_device_lock(
+        &self,
+        device: &str,
+        reason: Option<&str>,
+        lock: bool,
+    ) -> std::result::Result<Value, String> {
+        self.ensure_connected()?;
+
+        let client = self.get_client()?;
+        let structure = client
+            .get_structure()
+            .await
+            .map_err(|e| format!("Failed to get structure: {e}"))?;
+        let (uuid, control) = Self::find_control_by_id_or_name(&structure, device)
+            .ok_or_else(|| format!("Device '{device}' not found"))?;
+        let name = control.get("name").and_then(|v| v.as_str()).unwrap_or("");
+
+        let previous = match device_lock::lock_state(control) {
+            Some(state) => match client.get_state_values(&[state.to_string()]).await {
+                Ok(values) => values.get(state).and_then(device_lock::parse_lock_state),
+                Err(e) => {
+                    warn!("Failed to read lock state of {name}: {e}");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let command = if lock {
+            device_lock::lock_command(reason).map_err(|e| e.to_string())?
+        } else {
+            device_lock::UNLOCK_COMMAND.to_string()
+        };
+        let response = client.send_command(uuid, &command).await.map_err(|e| {
+            format!(
+                "Failed to {} {name}: {e}",
+                if lock { "lock" } else { "unlock" }
+            )
+        })?;
+
+        Ok(json!({
+            "device": name,
+            "uuid": uuid,
+            "locked": lock,
+            "reason": reason,
+            "previously_locked": previous.as_ref().map(|p| p.locked),
+            "previous_reason": previous.and_then(|p| p.reason),
+            "command_sent": command,
+            "status": "executed",
+            "miniserver_response": response.value
+        }))
+    }
+
+    /// Run several tool calls in one request
+    ///
+    /// `operations` is a list of `{"id", "tool", "args", "depends_on"}`
+    /// objects, e.g. the steps of a scene plan. Operations without pending
+    /// dependencies run concurrently; one whose dependency failed is skipped.
+    /// With `stop_on_error` any failure skips all later operations. Returns
+    /// a result per operation.
+    pub async fn batch_execute(
+        &self,
+        operations: Vec<serde_json::Value>,
+        stop_on_error: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("batch_execute", async move {
+            let plan = BatchPlan::new(operations).map_err(|e| e.to_string())?;
+            Ok(self
+                .execute_batch(plan, stop_on_error.unwrap_or(false))
+                .await)
+        })
+        .await
+    }
+
+    /// Run device commands in order, undoing them if one fails
+    ///
+    /// `steps` is a list of `{"device", "command"}` objects for lights,
+    /// switches and blinds; commands are checked per control type before
+    /// anything is sent. The first failing step skips the rest; with
+    /// `rollback` (default true) the executed light and blind steps are then
+    /// undone in reverse order. Returns a report per step.
+    pub async fn execute_macro(
+        &self,
+        steps: Vec<serde_json::Value>,
+        rollback: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("execute_macro", async move {
+            self.ensure_connected()?;
+            let rollback = rollback.unwrap_or(true);
+            let steps = device_macro::parse_steps(steps).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            // Resolve every device before anything is sent
+            let targets = steps
+                .iter()
+                .enumerate()
+                .map(|(index, step)| {
+                    Self::find_control_by_id_or_name(&structure, &step.device).ok_or_else(|| {
+                        format!("Step {}: device '{}' not found", index + 1, step.device)
+                    })
+                })
+                .collect::<std::result::Result<Vec<_>, String>>()?;
+            for (index, (step, (_, control))) in steps.iter().zip(&targets).enumerate() {
+                device_macro::check_step(control, &step.command)
+                    .map_err(|e| format!("Step {}: {e}", index + 1))?;
+            }
+
+            // State to restore, read once before the first step
+            let previous = if rollback {
+                let uuids: Vec<String> = targets
+                    .iter()
+                    .filter_map(|(_, control)| device_macro::undo_state_uuid(control))
+                    .collect();
+                if uuids.is_empty() {
+                    std::collections::HashMap::new()
+                } else {
+                    client
+                        .get_state_values(&uuids)
+                        .await
+                        .map_err(|e| format!("Failed to read device states: {e}"))?
+                }
+            } else {
+                std::collections::HashMap::new()
+            };
+
+            let mut statuses = vec![StepStatus::Skipped; steps.len()];
+            let mut errors: Vec<Option<String>> = vec![None; steps.len()];
+            let mut failed_step = None;
+            for (index, (step, (uuid, _))) in steps.iter().zip(&targets).enumerate() {
+                match client.send_command(uuid, &step.command).await {
+                    Ok(_) => statuses[index] = StepStatus::Executed,
+                    Err(e) => {
+                        statuses[index] = StepStatus::Failed;
+                        errors[index] = Some(e.to_string());
+                        failed_step = Some(index);
+                        break;
+                    }
+                }
+            }
+
+            if let (Some(failed), true) = (failed_step, rollback) {
+                for index in (0..failed).rev() {
+                    let (uuid, control) = targets[index];
+                    let Some(commands) = device_macro::undo_commands(control, &previous) else {
+                        statuses[index] = StepStatus::NotReversible;
+                        continue;
+                    };
+                    statuses[index] = StepStatus::RolledBack;
+                    for command in commands {
+                        if let Err(e) = client.send_command(uuid, &command).await {
+                            statuses[index] = StepStatus::RollbackFailed;
+                            errors[index] = Some(format!("Undo '{command}' failed: {e}"));
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let report: Vec<_> = steps
+                .iter()
+                .zip(&targets)
+                .zip(statuses.iter().zip(errors))
+                .enumerate()
+                .map(|(index, ((step, (uuid, control)), (status, error)))| {
+                    json!({
+                        "step": index + 1,
+                        "device": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "uuid": uuid,
+                        "command": step.command,
+                        "status": status,
+                        "error": error
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "steps": report,
+                "count": steps.len(),
+                "failed_step": failed_step.map(|index| index + 1),
+                "rollback": rollback,
+                "status": match failed_step {
+                    None => "executed",
+                    Some(_) if rollback => "rolled_back",
+                    Some(_) => "partial",
+                }
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // SYSTEM TOOLS
+    // ========================================================================
+
+    /// Get server status and health information
+    pub async fn get_server_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_server_status", async move {
+            let connected = self.context.is_some() && self.client.is_some();
+
+            Ok(json!({
+                "connected": connected,
+                "version": env!("CARGO_PKG_VERSION"),
+                "name": "Loxone MCP Server",
+                "tool_timeouts": self.tool_timeouts.status(),
+                "api_budget": self.api_budget.status(),
+                "power": self.power_monitor.status().await,
+                "lifetime": self.lifetime_metrics.totals(),
+                "feature_flags": self.feature_flags.snapshot(),
+                "connections": ConnectionLimiter::global().stats(),
+                "clock_offset_ms": crate::client::time_sync::offset_ms()
+            }))
+        })
+        .await
+    }
+
+    /// Get the hardware topology
+    ///
+    /// Lists all extensions, Tree branches and Air devices with their
+    /// online/offline status as reported by the Miniserver's device status
+    /// API (`loxone://system/topology`). Devices behind an offline extension
+    /// count as offline.
+    pub async fn get_system_topology(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_system_topology", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let status = client
+                .get_device_status()
+                .await
+                .map_err(|e| format!("Failed to get device status: {e}"))?;
+
+            let extensions = topology::parse_extensions(&status);
+            Ok(json!({
+                "uri": topology::TOPOLOGY_URI,
+                "summary": topology::summarize(&extensions),
+                "extensions": extensions
+            }))
+        })
+        .await
+    }
+
+    /// List the tools available on this installation
+    ///
+    /// Device-specific tools are only offered when the structure contains
+    /// matching controls (e.g. `control_ev_charging` needs a wallbox); the
+    /// rest are listed with the capability they are missing.
+    pub async fn get_available_tools(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_available_tools", async move {
+            let context = self
+                .context
+                .as_ref()
+                .ok_or_else(|| "Client context not initialized".to_string())?;
+            let availability = ToolAvailability::from_context(context).await;
+            serde_json::to_value(availability).map_err(|e| e.to_string())
+        })
+        .await
+    }
+
+    /// Tool titles and descriptions in the configured or requested language
+    ///
+    /// Uses `locale` (e.g. "de"), else `mcp.tools.locale`, else
+    /// `LOXONE_LOCALE`/`LANG`. Untranslated tools fall back to English.
+    pub async fn get_tool_descriptions(
+        &self,
+        locale: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_tool_descriptions", async move {
+            let descriptions = self.tool_descriptions(locale.as_deref());
+            Ok(json!({
+                "locale": descriptions.locale(),
+                "available_locales": ToolDescriptions::available_locales(),
+                "untranslated": descriptions.untranslated(),
+                "tools": descriptions.all(),
+            }))
+        })
+        .await
+    }
+
+    /// Switch a house-wide operating mode (vacation, party, standby)
+    ///
+    /// With action "on" (default), creates a calendar entry activating the
+    /// mode from `from` until `until` (YYYY-MM-DD, both inclusive; default
+    /// today). Action "off" removes the entries this server created for it.
+    /// Mode is a name from the Miniserver's operating modes or an alias
+    pub async fn set_operating_mode(
+        &self,
+        mode: String,
+        action: Option<String>,
+        from: Option<String>,
+        until: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_operating_mode", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let modes = operating_modes::operating_modes(&structure.operating_modes);
+            if modes.is_empty() {
+                return Err("The structure file defines no operating modes".to_string());
+            }
+            let mode = operating_modes::find_mode(&modes, &mode).map_err(|e| e.to_string())?;
+
+            match action.as_deref().unwrap_or("on").to_lowercase().as_str() {
+                "on" | "set" => {
+                    let today = match client.get_miniserver_time().await {
+                        Ok(now) => now.date(),
+                        Err(_) => chrono::Local::now().date_naive(),
+                    };
+                    let parse = |date: Option<&str>, default| {
+                        date.map_or(Ok(default), operating_modes::parse_date)
+                            .map_err(|e| e.to_string())
+                    };
+                    let from = parse(from.as_deref(), today)?;
+                    let until = parse(until.as_deref(), from)?;
+                    let command = operating_modes::create_entry_command(mode, from, until)
+                        .map_err(|e| e.to_string())?;
+                    let response = client
+                        .send_calendar_command(&command)
+                        .await
+                        .map_err(|e| format!("Failed to set operating mode {}: {e}", mode.name))?;
+
+                    Ok(json!({
+                        "mode": mode,
+                        "action": "on",
+                        "from": from.to_string(),
+                        "until": until.to_string(),
+                        "command_sent": command,
+                        "status": "executed",
+                        "miniserver_response": response.value
+                    }))
+                }
+                "off" | "clear" => {
+                    let response = client
+                        .send_calendar_command("calendargetentries")
+                        .await
+                        .map_err(|e| format!("Failed to read calendar entries: {e}"))?;
+                    let entries: Vec<operating_modes::CalendarEntry> =
+                        operating_modes::parse_entries(&response.value)
+                            .into_iter()
+                            .filter(|entry| entry.is_managed() && entry.operating_mode == mode.id)
+                            .collect();
+
+                    let mut removed = Vec::new();
+                    for entry in &entries {
+                        let command = operating_modes::delete_entry_command(&entry.uuid);
+                        client
+                            .send_calendar_command(&command)
+                            .await
+                            .map_err(|e| format!("Failed to remove entry {}: {e}", entry.name))?;
+                        removed.push(&entry.uuid);
+                    }
+
+                    let status = if removed.is_empty() {
+                        "no_entries"
+                    } else {
+                        "executed"
+                    };
+                    Ok(json!({
+                        "mode": mode,
+                        "action": "off",
+                        "entries_removed": removed,
+                        "status": status
+                    }))
+                }
+                other => Err(format!("Invalid action '{other}'. Use: on, off")),
+            }
+        })
+        .await
+    }
+
+    // ========================================================================
+    // AUDIO TOOLS
+    // ========================================================================
+
+    /// Control an audio zone (play, pause, stop, next, previous)
+    ///
+    /// Manages playback in audio zones. Actions: play, pause, stop, next, previous, mute, unmute
+    pub async fn control_audio_zone(
+        &self,
+        zone: String,
+        action: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("control_audio_zone", async move {
+            self.ensure_connected()?;
+
+            let normalized_action = match action.to_lowercase().as_str() {
+                "play" | "abspielen" | "start" => "play",
+                "pause" | "pausieren" => "pause",
+                "stop" | "stopp" | "anhalten" => "stop",
+                "next" | "weiter" | "nächster" => "next",
+                "previous" | "zurück" | "vorheriger" => "previous",
+                "mute" | "stumm" => "mute",
+                "unmute" | "laut" => "unmute",
+                _ => {
+                    return Err(format!(
+                        "Invalid action '{action}'. Use: play, pause, stop, next, previous, mute, unmute"
+                    ));
+                }
+            };
+
+            let target = self.resolve_audio_zone(&zone).await?;
+            let client = self.get_client()?;
+
+            // Map normalized actions to Loxone audio commands
+            let command = match normalized_action {
+                "play" => "play",
+                "pause" => "pause",
+                "stop" => "stop",
+                "next" => "queueplus",
+                "previous" => "queueminus",
+                "mute" => "mute",
+                "unmute" => "unmute",
+                _ => normalized_action,
+            };
+
+            let response = client
+                .send_command(&target.uuid, command)
+                .await
+                .map_err(|e| format!("Failed to control audio zone {}: {e}", target.name))?;
+
+            Ok(json!({
+                "zone": target.name,
+                "uuid": target.uuid,
+                "action": normalized_action,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Set volume for an audio zone
+    ///
+    /// Set volume level (0-100) for a specific audio zone
+    pub async fn set_audio_volume(
+        &self,
+        zone: String,
+        volume: u8,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_audio_volume", async move {
+            self.ensure_connected()?;
+
+            let command = audio::volume_command(volume).map_err(|e| e.to_string())?;
+            let target = self.resolve_audio_zone(&zone).await?;
+            let client = self.get_client()?;
+            let response = client
+                .send_command(&target.uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to set volume on zone {}: {e}", target.name))?;
+
+            Ok(json!({
+                "zone": target.name,
+                "uuid": target.uuid,
+                "volume": volume,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// List audio zones with their room, player ID and live state
+    ///
+    /// Covers Audioserver (AudioZoneV2) and Music Server (AudioZone) zones.
+    /// Zone names or rooms from this list work in all other audio tools.
+    pub async fn list_audio_zones(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_audio_zones", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let zones = audio::audio_zones(&structure);
+
+            let state_uuids: Vec<String> = zones
+                .iter()
+                .flat_map(|zone| zone.states.iter().map(|(_, uuid)| uuid.clone()))
+                .collect();
+            let values = client
+                .get_state_values(&state_uuids)
+                .await
+                .map_err(|e| format!("Failed to read audio zone states: {e}"))?;
+
+            let zones: Vec<Value> = zones
+                .iter()
+                .map(|zone| {
+                    let states: serde_json::Map<String, Value> = zone
+                        .states
+                        .iter()
+                        .map(|(name, uuid)| {
+                            (
+                                name.clone(),
+                                values.get(uuid).cloned().unwrap_or(Value::Null),
+                            )
+                        })
+                        .collect();
+                    json!({
+                        "uuid": zone.uuid,
+                        "name": zone.name,
+                        "room": zone.room,
+                        "type": zone.control_type,
+                        "player_id": zone.player_id,
+                        "states": states
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "zones": zones,
+                "count": zones.len()
+            }))
+        })
+        .await
+    }
+
+    /// Select a source or play a favorite in an audio zone
+    ///
+    /// Give either `source` (input number, from 1) or `favorite` (zone
+    /// favorite number, from 1). The zone is a name, room or UUID.
+    pub async fn select_audio_source(
+        &self,
+        zone: String,
+        source: Option<u32>,
+        favorite: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("select_audio_source", async move {
+            self.ensure_connected()?;
+
+            let command = audio::source_command(source, favorite).map_err(|e| e.to_string())?;
+            let target = self.resolve_audio_zone(&zone).await?;
+            let client = self.get_client()?;
+            let response = client
+                .send_command(&target.uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to select source on zone {}: {e}", target.name))?;
+
+            Ok(json!({
+                "zone": target.name,
+                "uuid": target.uuid,
+                "source": source,
+                "favorite": favorite,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Group audio zones so they play in sync, or ungroup them
+    ///
+    /// Actions: `group` joins `zones` to the group led by `leader`, `ungroup`
+    /// takes each of `zones` out of its group. Zones are names, rooms or UUIDs.
+    pub async fn group_audio_zones(
+        &self,
+        action: String,
+        zones: Vec<String>,
+        leader: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("group_audio_zones", async move {
+            self.ensure_connected()?;
+
+            if zones.is_empty() {
+                return Err("At least one zone is required".to_string());
+            }
+            let (leader, command) = match action.to_lowercase().as_str() {
+                "group" | "join" | "sync" => {
+                    let leader =
+                        leader.ok_or_else(|| "Grouping needs a leader zone".to_string())?;
+                    let leader = self.resolve_audio_zone(&leader).await?;
+                    let command = audio::group_command(&leader).map_err(|e| e.to_string())?;
+                    (Some(leader), command)
+                }
+                "ungroup" | "leave" | "unsync" => (None, audio::UNGROUP_COMMAND.to_string()),
+                _ => {
+                    return Err(format!("Invalid action '{action}'. Use: group, ungroup"));
+                }
+            };
+
+            let client = self.get_client()?;
+            let mut results = Vec::new();
+            for zone in &zones {
+                let target = self.resolve_audio_zone(zone).await?;
+                if leader
+                    .as_ref()
+                    .is_some_and(|leader| leader.uuid == target.uuid)
+                {
+                    continue;
+                }
+                let result = client.send_command(&target.uuid, &command).await;
+                results.push(json!({
+                    "zone": target.name,
+                    "uuid": target.uuid,
+                    "success": result.is_ok(),
+                    "error": result.err().map(|e| e.to_string())
+                }));
+            }
+
+            let successful = results
+                .iter()
+                .filter(|r| r["success"].as_bool() == Some(true))
+                .count();
+            Ok(json!({
+                "action": action.to_lowercase(),
+                "leader": leader.map(|leader| leader.name),
+                "command_sent": command,
+                "zones": results,
+                "successful": successful,
+                "total": results.len()
+            }))
+        })
+        .await
+    }
+
+    /// Get status of all audio zones
+    pub async fn get_audio_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_audio_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut audio_uuids = Vec::new();
+            let mut audio_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                if control_type.contains("Audio") || control_type == "MediaController" {
+                    audio_uuids.push(uuid.clone());
+                    audio_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &audio_uuids).await;
+
+            let audio_zones: Vec<Value> = audio_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "state": state
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "audio_zones": audio_zones,
+                "count": audio_zones.len()
+            }))
+        })
+        .await
+    }
+
+    /// Speak a text announcement on audio zones
+    ///
+    /// Without `zones` the announcement plays on every zone. `volume` sets
+    /// the announcement volume of all zones, `zone_volumes` (zone name to
+    /// volume) of single zones. Queued announcements (the default) wait
+    /// until the ones ahead have played; with `queue` off the announcement
+    /// is sent right away and interrupts one being spoken.
+    pub async fn announce(
+        &self,
+        text: String,
+        zones: Option<Vec<String>>,
+        volume: Option<u8>,
+        zone_volumes: Option<std::collections::HashMap<String, u8>>,
+        queue: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("announce", async move {
+            self.ensure_connected()?;
+            let queue = queue.unwrap_or(true);
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let all_zones = audio::audio_zones(&structure);
+            let targets = match &zones {
+                Some(names) if !names.is_empty() => names
+                    .iter()
+                    .map(|name| audio::find_zone(&all_zones, name))
+                    .collect::<crate::error::Result<Vec<_>>>()
+                    .map_err(|e| e.to_string())?,
+                _ => all_zones.clone(),
+            };
+            if targets.is_empty() {
+                return Err("No audio zones found".to_string());
+            }
+
+            let mut volumes = std::collections::HashMap::new();
+            for (name, zone_volume) in zone_volumes.unwrap_or_default() {
+                let zone = audio::find_zone(&targets, &name).map_err(|e| e.to_string())?;
+                volumes.insert(zone.uuid, zone_volume);
+            }
+            let commands = targets
+                .iter()
+                .map(|zone| {
+                    let zone_volume = volumes.get(&zone.uuid).copied().or(volume);
+                    audio::tts_command(&text, zone_volume).map(|command| (zone, command))
+                })
+                .collect::<crate::error::Result<Vec<_>>>()
+                .map_err(|e| e.to_string())?;
+            let duration = audio::announcement_duration(&text);
+
+            if queue {
+                let ahead = self
+                    .announcer
+                    .enqueue(
+                        client.clone(),
+                        commands
+                            .iter()
+                            .map(|(zone, command)| (zone.uuid.clone(), command.clone()))
+                            .collect(),
+                        duration,
+                    )
+                    .ok_or_else(|| {
+                        format!(
+                            "{} announcements are already waiting; try again later",
+                            announcements::MAX_QUEUED
+                        )
+                    })?;
+                return Ok(json!({
+                    "text": text,
+                    "zones": commands
+                        .iter()
+                        .map(|(zone, _)| json!({"zone": zone.name, "uuid": zone.uuid, "volume": volumes.get(&zone.uuid).copied().or(volume)}))
+                        .collect::<Vec<_>>(),
+                    "ahead": ahead,
+                    "estimated_duration_secs": duration.as_secs(),
+                    "status": if ahead == 0 { "playing" } else { "queued" }
+                }));
+            }
+
+            let results = client
+                .send_parallel_commands(
+                    commands
+                        .iter()
+                        .map(|(zone, command)| (zone.uuid.clone(), command.clone()))
+                        .collect(),
+                )
+                .await
+                .map_err(|e| format!("Failed to send announcement: {e}"))?;
+            let report: Vec<_> = commands
+                .iter()
+                .zip(results)
+                .map(|((zone, _), result)| {
+                    json!({
+                        "zone": zone.name,
+                        "uuid": zone.uuid,
+                        "volume": volumes.get(&zone.uuid).copied().or(volume),
+                        "success": result.is_ok(),
+                        "error": result.err().map(|e| e.to_string())
+                    })
+                })
+                .collect();
+            let failed = report
+                .iter()
+                .filter(|zone| zone["success"] == json!(false))
+                .count();
+
+            Ok(json!({
+                "text": text,
+                "zones": report,
+                "estimated_duration_secs": duration.as_secs(),
+                "failed": failed,
+                "status": if failed == 0 { "executed" } else { "partial" }
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // SENSOR TOOLS
+    // ========================================================================
+
+    /// Get all sensor readings
+    ///
+    /// Returns current values from all sensors (temperature, humidity, motion, etc.)
+    pub async fn get_sensor_readings(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_sensor_readings", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut sensor_uuids = Vec::new();
+            let mut sensor_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                // Match sensor types
+                if matches!(
+                    control_type,
+                    "InfoOnlyAnalog"
+                        | "InfoOnlyDigital"
+                        | "PresenceDetector"
+                        | "MotionSensor"
+                        | "SmokeAlarm"
+                        | "Meter"
+                        | "Sensor"
+                ) {
+                    sensor_uuids.push(uuid.clone());
+                    sensor_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &sensor_uuids).await;
+
+            let sensors: Vec<Value> = sensor_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "value": state
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "sensors": sensors,
+                "count": sensors.len()
+            }))
+        })
+        .await
+    }
+
+    /// Get door and window sensor status
+    ///
+    /// Returns open/closed state of all door and window sensors
+    pub async fn get_door_window_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_door_window_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut dw_uuids = Vec::new();
+            let mut dw_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                let name = control
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_lowercase();
+
+                // Match door/window sensors
+                if control_type == "InfoOnlyDigital"
+                    && (name.contains("door")
+                        || name.contains("window")
+                        || name.contains("tür")
+                        || name.contains("fenster"))
+                {
+                    dw_uuids.push(uuid.clone());
+                    dw_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &dw_uuids).await;
+
+            let door_windows: Vec<Value> = dw_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let display_name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": display_name,
+                        "room": room,
+                        "state": state
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "door_window_sensors": door_windows,
+                "count": door_windows.len()
+            }))
+        })
+        .await
+    }
+
+    /// Get motion detector status
+    pub async fn get_motion_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_motion_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut motion_uuids = Vec::new();
+            let mut motion_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                if matches!(control_type, "PresenceDetector" | "MotionSensor") {
+                    motion_uuids.push(uuid.clone());
+                    motion_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &motion_uuids).await;
+
+            let motion_sensors: Vec<Value> = motion_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "room": room,
+                        "state": state
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "motion_sensors": motion_sensors,
+                "count": motion_sensors.len()
+            }))
+        })
+        .await
+    }
+
+    /// Get recorded statistics of a device
+    ///
+    /// Reads the Miniserver's statistics files for the date range (YYYY-MM-DD,
+    /// default the last 7 days, at most 366 days) and returns the time series
+    /// averaged down to `max_points` samples (default 200) with min/max/avg
+    /// per output
+    pub async fn get_device_statistics(
+        &self,
+        device: String,
+        from: Option<String>,
+        to: Option<String>,
+        max_points: Option<usize>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_device_statistics", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &device)
+                .ok_or_else(|| format!("Device '{device}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+            let outputs: Vec<String> = control
+                .pointer("/statistic/outputs")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| format!("Device '{name}' has no statistics enabled"))?
+                .iter()
+                .map(|output| {
+                    output
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Value")
+                        .to_string()
+                })
+                .collect();
+
+            let today = match client.get_miniserver_time().await {
+                Ok(now) => now.date(),
+                Err(_) => chrono::Local::now().date_naive(),
+            };
+            let parse = |date: Option<&str>, default| {
+                date.map_or(Ok(default), operating_modes::parse_date)
+                    .map_err(|e| e.to_string())
+            };
+            let to = parse(to.as_deref(), today)?;
+            let from = parse(from.as_deref(), to - chrono::Duration::days(6))?;
+            if from > to {
+                return Err(format!("Start date {from} is after end date {to}"));
+            }
+            if (to - from).num_days() > 366 {
+                return Err("Date range must not exceed 366 days".to_string());
+            }
+
+            let mut points = Vec::new();
+            let mut series_outputs = outputs.clone();
+            for (year, month) in statistics::months_between(from, to) {
+                match client.get_statistics(uuid, year, month, &outputs).await {
+                    Ok(series) => {
+                        series_outputs = series.outputs;
+                        points.extend(series.points.into_iter().filter(|p| {
+                            let date = p.time.date();
+                            date >= from && date <= to
+                        }));
+                    }
+                    Err(e) => warn!("No statistics for {name} in {year}-{month:02}: {e}"),
+                }
+            }
+            points.sort_by_key(|p| p.time);
+
+            let summary: Vec<Value> = series_outputs
+                .iter()
+                .enumerate()
+                .map(|(i, output)| {
+                    let values: Vec<f64> = points
+                        .iter()
+                        .filter_map(|p| p.values.get(i).copied())
+                        .collect();
+                    let avg = (!values.is_empty())
+                        .then(|| values.iter().sum::<f64>() / values.len() as f64);
+                    json!({
+                        "output": output,
+                        "min": values.iter().copied().reduce(f64::min),
+                        "max": values.iter().copied().reduce(f64::max),
+                        "avg": avg
+                    })
+                })
+                .collect();
+            let max_points = max_points.unwrap_or(200).clamp(1, 2000);
+            let samples = statistics::downsample(&points, max_points);
+
+            Ok(json!({
+                "device": name,
+                "uuid": uuid,
+                "from": from.to_string(),
+                "to": to.to_string(),
+                "outputs": series_outputs,
+                "sample_count": points.len(),
+                "summary": summary,
+                "points": samples
+            }))
+        })
+        .await
+    }
+
+    /// Get battery levels of Loxone Air devices
+    ///
+    /// Lists every Air peripheral with battery level and reachability and
+    /// flags devices below `threshold` percent (default 20).
+    pub async fn get_device_batteries(
+        &self,
+        threshold: Option<f64>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_device_batteries", async move {
+            self.ensure_connected()?;
+
+            let threshold = threshold.unwrap_or(air_devices::DEFAULT_LOW_BATTERY_PERCENT);
+            if !(0.0..=100.0).contains(&threshold) {
+                return Err("Threshold must be between 0 and 100 percent".to_string());
+            }
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let diagnostics = client
+                .get_air_devices()
+                .await
+                .map_err(|e| format!("Failed to get Air device diagnostics: {e}"))?;
+
+            let mut devices = air_devices::parse_devices(&diagnostics);
+            // Lowest battery first, mains-powered devices last
+            devices.sort_by(|a, b| {
+                a.battery_percent
+                    .unwrap_or(f64::INFINITY)
+                    .total_cmp(&b.battery_percent.unwrap_or(f64::INFINITY))
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+
+            let entries: Vec<Value> = devices
+                .iter()
+                .map(|device| {
+                    let room = device.room.as_ref().map(|room| {
+                        structure
+                            .rooms
+                            .get(room)
+                            .and_then(|r| r.get("name"))
+                            .and_then(|n| n.as_str())
+                            .unwrap_or(room)
+                            .to_string()
+                    });
+                    json!({
+                        "serial": device.serial,
+                        "name": device.name,
+                        "room": room,
+                        "type": device.device_type,
+                        "battery_percent": device.battery_percent,
+                        "low_battery": device.is_low(threshold),
+                        "online": device.online,
+                        "last_seen": device.last_seen,
+                        "signal_dbm": device.signal_dbm
+                    })
+                })
+                .collect();
+            let low: Vec<&str> = devices
+                .iter()
+                .filter(|device| device.is_low(threshold))
+                .map(|device| device.name.as_str())
+                .collect();
+            let offline: Vec<&str> = devices
+                .iter()
+                .filter(|device| !device.online)
+                .map(|device| device.name.as_str())
+                .collect();
+
+            Ok(json!({
+                "threshold_percent": threshold,
+                "devices": entries,
+                "count": entries.len(),
+                "low_battery": low,
+                "offline": offline
+            }))
+        })
+        .await
+    }
+
+    /// Get a comfort score per room
+    ///
+    /// Combines the temperature, humidity, CO2 and brightness sensors of
+    /// each room into a score from 0 to 100 (good from 80, poor below 60)
+    /// with explanations of what lowers it, least comfortable room first
+    pub async fn get_room_comfort(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_room_comfort", async move {
+            self.ensure_connected()?;
+
+            let resolver = self
+                .value_resolver
+                .as_ref()
+                .ok_or_else(|| "Value resolver not available".to_string())?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let sensor_types = &["InfoOnlyAnalog", "Sensor", "Ventilation", "AirHandling"];
+            let sensors = match &room {
+                Some(room_name) => {
+                    let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                    Self::find_controls_by_type_in_room(&structure, &room_uuid, sensor_types)
+                }
+                None => Self::find_controls_by_type(&structure, sensor_types),
+            };
+            let uuids: Vec<String> = sensors.iter().map(|(uuid, _)| (*uuid).clone()).collect();
+            let resolved = if uuids.is_empty() {
+                std::collections::HashMap::new()
+            } else {
+                resolver
+                    .resolve_batch_values(&uuids)
+                    .await
+                    .map_err(|e| format!("Failed to read sensors: {e}"))?
+            };
+
+            let mut readings: std::collections::BTreeMap<String, Vec<Reading>> =
+                std::collections::BTreeMap::new();
+            for (uuid, control) in &sensors {
+                let Some(room_name) = control
+                    .get("room")
+                    .and_then(|v| v.as_str())
+                    .and_then(|room| structure.rooms.get(room))
+                    .and_then(|room| room.get("name"))
+                    .and_then(|v| v.as_str())
+                else {
+                    continue;
+                };
+                let reading = resolved.get(*uuid).and_then(|value| {
+                    Reading::new(value.sensor_type.as_ref()?, value.numeric_value?)
+                });
+                readings
+                    .entry(room_name.to_string())
+                    .or_default()
+                    .extend(reading);
+            }
+            if let Some(room_name) = &room
+                && readings.is_empty()
+            {
+                readings.insert(room_name.clone(), Vec::new());
+            }
+
+            let mut rooms: Vec<_> = readings
+                .iter()
+                .map(|(room_name, readings)| room_comfort::score_room(room_name, readings))
+                .collect();
+            // Rooms without comfort sensors only matter when asked for
+            if room.is_none() {
+                rooms.retain(|comfort| comfort.score.is_some());
+            }
+            rooms.sort_by_key(|comfort| comfort.score);
+
+            Ok(json!({
+                "rooms": rooms,
+                "count": rooms.len(),
+                "good_from": room_comfort::GOOD_SCORE,
+                "poor_below": room_comfort::POOR_SCORE
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // TEXT STATE TOOLS
+    // ========================================================================
+
+    /// Get status texts
+    ///
+    /// Returns the current text of TextState displays and text virtual inputs,
+    /// optionally filtered by room
+    pub async fn get_text_states(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_text_states", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let text_types = &["TextState", "TextInput"];
+            let controls = if let Some(ref room_name) = room {
+                let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                    .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                Self::find_controls_by_type_in_room(&structure, &room_uuid, text_types)
+            } else {
+                Self::find_controls_by_type(&structure, text_types)
+            };
+
+            let uuids: Vec<String> = controls.iter().map(|(uuid, _)| (*uuid).clone()).collect();
+
+            // Prefer the resolver so texts go through the same parsing as other sensors
+            let mut texts: std::collections::HashMap<String, (Value, Option<String>)> =
+                std::collections::HashMap::new();
+            if let Some(resolver) = &self.value_resolver {
+                match resolver.resolve_batch_values(&uuids).await {
+                    Ok(resolved) => {
+                        for (uuid, value) in resolved {
+                            let text = (value.formatted_value != "Unknown")
+                                .then_some(value.formatted_value);
+                            texts.insert(uuid, (value.raw_value, text));
+                        }
+                    }
+                    Err(e) => warn!("Value resolver unavailable for text states: {e}"),
+                }
+            }
+
+            let missing: Vec<String> = uuids
+                .iter()
+                .filter(|uuid| !texts.contains_key(*uuid))
+                .cloned()
+                .collect();
+            let parser = crate::services::value_parsers::TextStateParser;
+            for (uuid, raw) in Self::fetch_live_states(client, &missing).await {
+                let text = crate::services::value_parsers::ValueParser::parse(&parser, &raw)
+                    .ok()
+                    .map(|parsed| parsed.formatted_value);
+                texts.insert(uuid, (raw, text));
+            }
+
+            let text_states: Vec<Value> = controls
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let (raw, text) = texts.get(*uuid).cloned().unwrap_or((Value::Null, None));
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "text": text,
+                        "writable": control_type == "TextInput",
+                        "raw_value": raw
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "text_states": text_states,
+                "count": text_states.len()
+            }))
+        })
+        .await
+    }
+
+    /// Set the text of a virtual text input
+    ///
+    /// Pushes a status text to a TextInput control identified by UUID or name
+    pub async fn set_text_input(
+        &self,
+        input: String,
+        text: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_text_input", async move {
+            self.ensure_connected()?;
+
+            virtual_inputs::validate_text(&text).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &input)
+                .ok_or_else(|| format!("Text input '{input}' not found"))?;
+            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            if control_type != "TextInput" {
+                return Err(format!(
+                    "Control '{input}' is a {control_type}, not a TextInput"
+                ));
+            }
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let response = client
+                .send_command(uuid, &urlencoding::encode(&text))
+                .await
+                .map_err(|e| format!("Failed to set text input {name}: {e}"))?;
+
+            Ok(json!({
+                "uuid": uuid,
+                "name": name,
+                "text": text,
+                "status": "updated",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Get entries of Tracker controls (event logs kept by the Miniserver)
+    ///
+    /// Answers questions like "when was the front door last opened?".
+    /// Filter by tracker name, text (case-insensitive) and time range
+    /// (YYYY-MM-DD or YYYY-MM-DD HH:MM); entries come newest first in pages
+    /// of `limit` (default 50, max 500) starting at `offset`
+    pub async fn get_tracker_entries(
+        &self,
+        tracker: Option<String>,
+        query: Option<String>,
+        since: Option<String>,
+        until: Option<String>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_tracker_entries", async move {
+            self.ensure_connected()?;
+
+            let filter = tracker::EntryFilter {
+                query,
+                since: since
+                    .as_deref()
+                    .map(|t| tracker::parse_time(t, false))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+                until: until
+                    .as_deref()
+                    .map(|t| tracker::parse_time(t, true))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+            };
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let trackers: Vec<(&String, &Value)> = match tracker.as_deref() {
+                Some(name) => Self::find_control_by_id_or_name(&structure, name)
+                    .filter(|(_, control)| {
+                        let control_type =
+                            control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                        tracker::TRACKER_TYPES.contains(&control_type)
+                    })
+                    .map(|found| vec![found])
+                    .ok_or_else(|| format!("Tracker '{name}' not found"))?,
+                None => Self::find_controls_by_type(&structure, tracker::TRACKER_TYPES),
+            };
+            if trackers.is_empty() {
+                return Err("No Tracker control found in the system".to_string());
+            }
+
+            let states: Vec<(&str, String)> = trackers
+                .iter()
+                .filter_map(|(_, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let state = control
+                        .pointer("/states/entries")
+                        .and_then(|v| v.as_str())?;
+                    Some((name, state.to_string()))
+                })
+                .collect();
+            let uuids: Vec<String> = states.iter().map(|(_, state)| state.clone()).collect();
+            let values = client
+                .get_state_values(&uuids)
+                .await
+                .map_err(|e| format!("Failed to read tracker entries: {e}"))?;
+
+            let merged = states.len() > 1;
+            let entries: Vec<tracker::TrackerEntry> = states
+                .iter()
+                .filter_map(|(name, state)| Some((*name, values.get(state)?)))
+                .flat_map(|(name, value)| {
+                    tracker::parse_entries(value)
+                        .into_iter()
+                        .map(move |mut entry| {
+                            if merged {
+                                entry.tracker = Some(name.to_string());
+                            }
+                            entry
+                        })
+                })
+                .collect();
+
+            let offset = offset.unwrap_or(0);
+            let (page, total) = tracker::page(
+                entries,
+                &filter,
+                offset,
+                limit.unwrap_or(tracker::DEFAULT_PAGE_SIZE),
+            );
+            let next_offset = (offset + page.len() < total).then_some(offset + page.len());
+            let names: Vec<&str> = states.iter().map(|(name, _)| *name).collect();
+
+            Ok(json!({
+                "trackers": names,
+                "entries": page,
+                "total": total,
+                "offset": offset,
+                "next_offset": next_offset
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // VIRTUAL INPUT TOOLS
+    // ========================================================================
+
+    /// List virtual inputs
+    ///
+    /// Returns digital, analog and text virtual inputs with their accepted
+    /// values, optionally filtered by kind (digital, analog, text) and room
+    pub async fn list_virtual_inputs(
+        &self,
+        kind: Option<String>,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_virtual_inputs", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let kind_filter = kind.as_deref().map(str::to_lowercase);
+            if let Some(ref k) = kind_filter
+                && !matches!(k.as_str(), "digital" | "analog" | "text")
+            {
+                return Err(format!("Invalid kind '{k}'. Use digital, analog or text"));
+            }
+            let room_uuid = match room {
+                Some(ref room_name) => Some(
+                    Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?,
+                ),
+                None => None,
+            };
+
+            let mut inputs = Vec::new();
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                let Some(input_kind) = VirtualInputKind::from_control_type(control_type) else {
+                    continue;
+                };
+                let control_room = control.get("room").and_then(|v| v.as_str()).unwrap_or("");
+                if kind_filter
+                    .as_deref()
+                    .is_some_and(|k| k != input_kind.as_str())
+                    || room_uuid.as_deref().is_some_and(|r| r != control_room)
+                {
+                    continue;
+                }
+
+                let accepts = match input_kind {
+                    VirtualInputKind::Digital if control_type == "Pushbutton" => {
+                        json!(["on", "off", "pulse"])
+                    }
+                    VirtualInputKind::Digital => json!(["on", "off"]),
+                    VirtualInputKind::Analog => {
+                        let range = virtual_inputs::AnalogRange::from_control(control);
+                        json!({"min": range.min, "max": range.max, "step": range.step})
+                    }
+                    VirtualInputKind::Text => {
+                        json!({"max_length": virtual_inputs::MAX_TEXT_INPUT_LENGTH})
+                    }
+                };
+
+                inputs.push(json!({
+                    "uuid": uuid,
+                    "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                    "type": control_type,
+                    "kind": input_kind.as_str(),
+                    "room": if control_room.is_empty() { "Unknown" } else { control_room },
+                    "accepts": accepts
+                }));
+            }
+
+            Ok(json!({
+                "virtual_inputs": inputs,
+                "count": inputs.len()
+            }))
+        })
+        .await
+    }
+
+    /// Set a virtual input
+    ///
+    /// Sets a digital (on/off/pulse), analog (number within range) or text
+    /// virtual input identified by UUID or name
+    pub async fn set_virtual_input(
+        &self,
+        input: String,
+        value: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_virtual_input", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (uuid, control) = Self::find_virtual_input(&structure, &input)?;
+            Self::send_virtual_input(client, uuid, control, &value).await
+        })
+        .await
+    }
+
+    /// Trigger a virtual input
+    ///
+    /// Drives custom Loxone logic wired to a virtual input: pulses a push
+    /// button by default, or sends on/off or an analog value when given
+    pub async fn trigger_virtual_input(
+        &self,
+        input: String,
+        value: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("trigger_virtual_input", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (uuid, control) = Self::find_virtual_input(&structure, &input)?;
+            let value = value.unwrap_or_else(|| "pulse".to_string());
+            Self::send_virtual_input(client, uuid, control, &value).await
+        })
+        .await
+    }
+
+    /// List virtual output commands, the links to external systems
+    ///
+    /// Shows each command's kind, the address and protocol (HTTP or UDP) of
+    /// its virtual output and the values it accepts.
+    pub async fn list_virtual_outputs(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_virtual_outputs", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let room_uuid = match room {
+                Some(ref room_name) => Some(
+                    Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?,
+                ),
+                None => None,
+            };
+
+            let mut outputs = Vec::new();
+            for (uuid, control) in &structure.controls {
+                let Some(output) = VirtualOutput::from_control(control) else {
+                    continue;
+                };
+                let control_room = control.get("room").and_then(|v| v.as_str()).unwrap_or("");
+                if room_uuid.as_deref().is_some_and(|r| r != control_room) {
+                    continue;
+                }
+                let accepts = match output.kind {
+                    virtual_outputs::OutputKind::Digital => json!(["on", "off", "pulse"]),
+                    virtual_outputs::OutputKind::Analog => {
+                        let range = virtual_inputs::AnalogRange::from_control(control);
+                        json!({"min": range.min, "max": range.max, "step": range.step})
+                    }
+                };
+                outputs.push(json!({
+                    "uuid": uuid,
+                    "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                    "room": if control_room.is_empty() { "Unknown" } else { control_room },
+                    "output": output,
+                    "accepts": accepts
+                }));
+            }
+
+            Ok(json!({
+                "virtual_outputs": outputs,
+                "count": outputs.len()
+            }))
+        })
+        .await
+    }
+
+    /// Drive a virtual output command to trigger an external integration
+    ///
+    /// Digital commands take on, off or pulse; analog commands a number
+    /// within the range configured in Loxone Config.
+    pub async fn set_virtual_output(
+        &self,
+        output: String,
+        value: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_virtual_output", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &output)
+                .filter(|(_, control)| VirtualOutput::from_control(control).is_some())
+                .ok_or_else(|| format!("Virtual output '{output}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let command =
+                virtual_outputs::build_command(control, &value).map_err(|e| e.to_string())?;
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to set virtual output {name}: {e}"))?;
+
+            Ok(json!({
+                "uuid": uuid,
+                "name": name,
+                "output": VirtualOutput::from_control(control),
+                "value": value,
+                "command_sent": command,
+                "status": "updated",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Get the presence heatmap
+    ///
+    /// Returns detections per room and hour of day from the motion history
+    /// (`loxone://reports/presence`), optionally for a single room
+    pub async fn get_presence_report(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_presence_report", async move {
+            self.ensure_connected()?;
+
+            let report = match self.presence_reports.latest().await {
+                Some(report) => report,
+                None => {
+                    let context = self
+                        .context
+                        .as_ref()
+                        .ok_or_else(|| "Client context not initialized".to_string())?;
+                    self.presence_reports.regenerate(context).await
+                }
+            };
+
+            let mut body = report.to_resource_json();
+            if let Some(ref room_name) = room {
+                let lower = room_name.to_lowercase();
+                let rooms: serde_json::Map<String, Value> = body["rooms"]
+                    .as_object()
+                    .map(|rooms| {
+                        rooms
+                            .iter()
+                            .filter(|(name, _)| name.to_lowercase().contains(&lower))
+                            .map(|(name, value)| (name.clone(), value.clone()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if rooms.is_empty() {
+                    return Err(format!("No presence data for room '{room_name}'"));
+                }
+                body["room_count"] = json!(rooms.len());
+                body["rooms"] = Value::Object(rooms);
+            }
+
+            Ok(body)
+        })
+        .await
+    }
+
+    /// Get device usage statistics
+    ///
+    /// Returns switch counts and on-hours per lamp and relay from the state
+    /// history (`loxone://reports/device-usage`), flagging lamps nearing
+    /// their rated lifetime and relays cycling unusually often
+    pub async fn get_device_usage(
+        &self,
+        room: Option<String>,
+        flagged_only: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_device_usage", async move {
+            self.ensure_connected()?;
+
+            let context = self
+                .context
+                .as_ref()
+                .ok_or_else(|| "Client context not initialized".to_string())?;
+            let mut report = self.device_usage.report(context).await;
+
+            if let Some(ref room_name) = room {
+                let lower = room_name.to_lowercase();
+                report.devices.retain(|d| {
+                    d.room
+                        .as_ref()
+                        .is_some_and(|r| r.to_lowercase().contains(&lower))
+                });
+            }
+            if flagged_only.unwrap_or(false) {
+                report.devices.retain(|d| !d.flags.is_empty());
+            }
+
+            Ok(report.to_resource_json())
+        })
+        .await
+    }
+
+    /// Report what changed since a point in time or a scene snapshot
+    ///
+    /// `since` is a timestamp (RFC 3339) or a duration back from now such
+    /// as `8h`; changes then come from the state history. With `snapshot`
+    /// the devices of that scene snapshot are compared against their live
+    /// state instead. Changes are grouped by room and category; devices
+    /// that ended up where they started are left out unless
+    /// `include_reverted` is set.
+    pub async fn diff_states(
+        &self,
+        since: Option<String>,
+        snapshot: Option<String>,
+        room: Option<String>,
+        include_reverted: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("diff_states", async move {
+            self.ensure_connected()?;
+            let include_reverted = include_reverted.unwrap_or(false);
+
+            let (baseline, changes): (Value, Vec<DeviceChange>) = match (&since, &snapshot) {
+                (Some(_), Some(_)) => {
+                    return Err("Give either since or snapshot, not both".to_string());
+                }
+                (None, None) => {
+                    return Err("A since time or a snapshot name is required".to_string());
+                }
+                (Some(since), None) => {
+                    let now = chrono::Utc::now();
+                    let at = match chrono::DateTime::parse_from_rfc3339(since.trim()) {
+                        Ok(at) => at.with_timezone(&chrono::Utc),
+                        Err(_) => {
+                            let duration = humantime::parse_duration(since.trim()).map_err(|_| {
+                                format!("Invalid since '{since}': use an RFC 3339 time or a duration like 8h")
+                            })?;
+                            now - chrono::Duration::from_std(duration)
+                                .map_err(|_| format!("Duration '{since}' is too long"))?
+                        }
+                    };
+                    if at > now {
+                        return Err("The since time lies in the future".to_string());
+                    }
+                    let context = self
+                        .context
+                        .as_ref()
+                        .ok_or_else(|| "Client context not initialized".to_string())?;
+                    let history = match context.get_sensor_logger().await {
+                        Some(logger) => logger.get_all_history().await,
+                        None => return Err("State history is not being recorded".to_string()),
+                    };
+                    let devices = context.devices.read().await;
+                    (
+                        json!({"since": at}),
+                        state_diff::diff_history(&history, &devices, at),
+                    )
+                }
+                (None, Some(name)) => {
+                    let snapshot = self
+                        .scene_snapshots
+                        .get(name)
+                        .ok_or_else(|| format!("Snapshot '{name}' not found"))?;
+                    let client = self.get_client()?;
+                    let structure = client
+                        .get_structure()
+                        .await
+                        .map_err(|e| format!("Failed to get structure: {e}"))?;
+                    let devices: Vec<_> = snapshot
+                        .devices
+                        .iter()
+                        .filter_map(|device| {
+                            let control = structure.controls.get(&device.uuid)?;
+                            Some((device, scene_snapshot::state_uuid(control)?))
+                        })
+                        .collect();
+                    let state_uuids: Vec<String> =
+                        devices.iter().map(|(_, state)| state.clone()).collect();
+                    let values = if state_uuids.is_empty() {
+                        std::collections::HashMap::new()
+                    } else {
+                        client
+                            .get_state_values(&state_uuids)
+                            .await
+                            .map_err(|e| format!("Failed to read device states: {e}"))?
+                    };
+                    let changes = devices
+                        .into_iter()
+                        .map(|(device, state)| DeviceChange {
+                            uuid: device.uuid.clone(),
+                            name: device.name.clone(),
+                            room: snapshot.room.clone(),
+                            category: json!(device.kind).as_str().unwrap_or("").to_string(),
+                            before: device.value.clone(),
+                            after: values.get(&state).cloned().unwrap_or(Value::Null),
+                            transitions: None,
+                        })
+                        .collect();
+                    (
+                        json!({"snapshot": snapshot.name, "captured_at": snapshot.captured_at}),
+                        changes,
+                    )
+                }
+            };
+
+            let lower = room.as_deref().map(str::to_lowercase);
+            let (changes, reverted): (Vec<_>, Vec<_>) = changes
+                .into_iter()
+                .filter(|change| {
+                    lower
+                        .as_deref()
+                        .is_none_or(|room| change.room.to_lowercase().contains(room))
+                })
+                .partition(|change| !change.reverted());
+            let reverted_count = reverted.len();
+            let changes: Vec<_> = if include_reverted {
+                changes.into_iter().chain(reverted).collect()
+            } else {
+                changes
+            };
+            let count = changes.len();
+
+            Ok(json!({
+                "baseline": baseline,
+                "room_filter": room,
+                "rooms": state_diff::group(changes),
+                "changed": count,
+                "reverted": reverted_count
+            }))
+        })
+        .await
+    }
+
+    /// List connected MCP clients
+    ///
+    /// Returns the client sessions of all transports
+    /// (`loxone://system/clients`) with name, transport, capabilities,
+    /// subscription count and last activity. Requires an admin API key.
+    pub async fn get_connected_clients(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_connected_clients", async move {
+            if !self.admin_session {
+                return Err("Listing connected clients requires an admin API key".to_string());
+            }
+
+            let sessions = ClientSessionRegistry::global().sessions();
+            Ok(json!({
+                "uri": CLIENTS_RESOURCE_URI,
+                "count": sessions.len(),
+                "current_session": self.client_session,
+                "clients": sessions,
+            }))
+        })
+        .await
+    }
+
+    /// Get sampling token and cost usage
+    ///
+    /// Returns today's sampling usage (`loxone://system/sampling-usage`):
+    /// requests, input/output tokens and cost in total and per client, the
+    /// configured daily limits and what remains of them. Tokens are estimated
+    /// when the provider does not report them. Only admin API keys see the
+    /// usage of other clients.
+    pub async fn get_sampling_usage(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_sampling_usage", async move {
+            let mut usage = self.sampling_budget.usage_resource();
+            if !self.admin_session {
+                let own = current_client();
+                if let Some(clients) = usage["clients"].as_object_mut() {
+                    clients.retain(|name, _| *name == own);
+                }
+            }
+            usage["enabled"] = json!(self.feature_flags.is_enabled("sampling"));
+            Ok(usage)
+        })
+        .await
+    }
+
+    /// Create a time-limited guest API key
+    ///
+    /// Mints a key limited to the devices in `rooms`, optionally of one
+    /// `category` (lights, blinds, climate, audio), valid for `hours`
+    /// (default 48, at most 720). Guests can control those devices unless
+    /// `read_only` is set. Returns the key and a ready-to-use connection
+    /// snippet for `server_url`; expired guest keys are deleted
+    /// automatically. Requires an admin API key.
+    pub async fn create_guest_access(
+        &self,
+        name: String,
+        rooms: Vec<String>,
+        category: Option<String>,
+        hours: Option<u32>,
+        read_only: Option<bool>,
+        server_url: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("create_guest_access", async move {
+            if !self.admin_session {
+                return Err("Creating guest keys requires an admin API key".to_string());
+            }
+            self.ensure_connected()?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let grant = GuestGrant {
+                name,
+                rooms,
+                category,
+                allow_control: !read_only.unwrap_or(false),
+                valid_for: std::time::Duration::from_secs(u64::from(hours.unwrap_or(48)) * 3600),
+            };
+            let store = self
+                .key_store
+                .as_ref()
+                .ok_or("Guest keys need a server started with a key store")?;
+            let created_by = self
+                .api_key_label
+                .clone()
+                .unwrap_or_else(|| "local".to_string());
+            let key = guest_access::mint_guest_key(store, &grant, &structure, &created_by)
+                .await
+                .map_err(|e| format!("Failed to create guest key: {e}"))?;
+
+            let server_url = server_url.unwrap_or_else(|| "http://localhost:3001".to_string());
+            Ok(json!({
+                "api_key": key.id,
+                "label": key_label(&key.id),
+                "name": key.name,
+                "scope": grant.describe(),
+                "devices": guest_access::guest_scope(&key).map(|s| s.tenant().devices.clone()),
+                "role": key.role,
+                "expires_at": key.expires_at,
+                "connection": guest_access::connection_snippet(&key, &server_url),
+                "note": "The key is shown only once; it is deleted automatically after expiry."
+            }))
+        })
+        .await
+    }
+
+    /// Get the device command history
+    ///
+    /// Returns commands sent to the Miniserver (`loxone://history/commands`),
+    /// newest first, with the tool and API key that issued them. Filter by
+    /// device name or UUID, tool, key, success, and `since` (RFC 3339 or a
+    /// duration such as "24h").
+    pub async fn get_command_history(
+        &self,
+        device: Option<String>,
+        tool: Option<String>,
+        key: Option<String>,
+        since: Option<String>,
+        success: Option<bool>,
+        limit: Option<usize>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_command_history", async move {
+            self.ensure_connected()?;
+            let client = self.get_client()?;
+            let structure = client.get_structure().await.ok();
+
+            let device = device.map(|device| {
+                structure
+                    .as_ref()
+                    .and_then(|s| Self::find_control_by_id_or_name(s, &device))
+                    .map(|(uuid, _)| uuid.clone())
+                    .unwrap_or(device)
+            });
+            let since = since
+                .as_deref()
+                .map(crate::services::command_history::parse_since)
+                .transpose()
+                .map_err(|e| e.to_string())?;
+            let filter = CommandFilter {
+                device,
+                tool,
+                key,
+                since,
+                success,
+                limit,
+            };
+
+            let commands: Vec<Value> = self
+                .command_history
+                .query(&filter)
+                .into_iter()
+                .map(|record| {
+                    let name = structure
+                        .as_ref()
+                        .and_then(|s| s.controls.get(&record.device))
+                        .and_then(|c| c.get("name"))
+                        .cloned()
+                        .unwrap_or(Value::Null);
+                    let mut entry = json!(record);
+                    entry["device_name"] = name;
+                    entry
+                })
+                .collect();
+
+            Ok(json!({
+                "uri": COMMAND_HISTORY_URI,
+                "commands": commands,
+                "count": commands.len(),
+                "total_recorded": self.command_history.len()
+            }))
+        })
+        .await
+    }
+
+    /// Get Miniserver program backups and restore instructions
+    ///
+    /// Returns the encrypted program archives, the last scheduled run and
+    /// how to restore a program (`loxone://backups/restore`). With
+    /// `run_now`, backs up the current program first.
+    pub async fn get_program_backups(
+        &self,
+        run_now: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_program_backups", async move {
+            if run_now.unwrap_or(false) {
+                self.ensure_connected()?;
+                let client = self.get_client()?;
+                self.program_backup
+                    .run_once(client.as_ref())
+                    .await
+                    .map_err(|e| format!("Program backup failed: {e}"))?;
+            }
+
+            let mut body = self.program_backup.restore_resource().await;
+            body["uri"] = json!(RESTORE_RESOURCE_URI);
+            Ok(body)
+        })
+        .await
+    }
+
+    /// Compare the current structure with a saved snapshot
+    ///
+    /// Summarizes added, removed, renamed, moved and retyped controls and
+    /// room changes since a program backup — useful to review a Loxone
+    /// Config deployment. `against` names a program backup (see
+    /// `get_program_backups`); defaults to the newest snapshot.
+    pub async fn diff_structure(
+        &self,
+        against: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("diff_structure", async move {
+            self.ensure_connected()?;
+            let client = self.get_client()?;
+            let current = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (snapshot, diff) = self
+                .program_backup
+                .diff_structure(&current, against.as_deref())
+                .await
+                .map_err(|e| format!("Structure diff failed: {e}"))?;
+
+            Ok(json!({
+                "snapshot": snapshot,
+                "current_last_modified": current.last_modified,
+                "unchanged": diff.is_empty(),
+                "summary": diff.summary(),
+                "changes": diff,
+            }))
+        })
+        .await
+    }
+
+    /// Get the Miniserver system log
+    ///
+    /// Reads the SD-card log (`def.log` unless `file` names another log file)
+    /// and returns entries at least as severe as `severity` (default warning)
+    /// from the last `since_minutes` (default 1440), newest first, at most
+    /// `limit` (default 100)
+    pub async fn get_miniserver_log(
+        &self,
+        severity: Option<String>,
+        since_minutes: Option<u32>,
+        limit: Option<usize>,
+        file: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_miniserver_log", async move {
+            self.ensure_connected()?;
+
+            let min_severity = severity
+                .as_deref()
+                .map_or(Ok(LogSeverity::Warning), LogSeverity::parse)
+                .map_err(|e| e.to_string())?;
+            let path = miniserver_log::log_file_path(
+                file.as_deref().unwrap_or(miniserver_log::DEFAULT_LOG_FILE),
+            )
+            .map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let (source, bytes) = match client.download_file(&path).await {
+                Ok(bytes) => (path, bytes),
+                // Firmware without a log directory only has the PLC log
+                Err(e) if file.is_none() => {
+                    debug!(
+                        "No SD-card log ({e}), reading {}",
+                        miniserver_log::SPS_LOG_PATH
+                    );
+                    let bytes = client
+                        .download_file(miniserver_log::SPS_LOG_PATH)
+                        .await
+                        .map_err(|e| format!("Failed to read Miniserver log: {e}"))?;
+                    (miniserver_log::SPS_LOG_PATH.to_string(), bytes)
+                }
+                Err(e) => return Err(format!("Failed to read log {path}: {e}")),
+            };
+
+            let now = match client.get_miniserver_time().await {
+                Ok(now) => now,
+                Err(_) => chrono::Local::now().naive_local(),
+            };
+            let since = now - chrono::Duration::minutes(i64::from(since_minutes.unwrap_or(1440)));
+            let in_window: Vec<_> = miniserver_log::parse_log(&String::from_utf8_lossy(&bytes))
+                .into_iter()
+                .filter(|entry| entry.time >= since)
+                .collect();
+
+            let mut counts = std::collections::BTreeMap::new();
+            for entry in &in_window {
+                *counts.entry(entry.severity).or_insert(0usize) += 1;
+            }
+            let limit = limit.unwrap_or(100).clamp(1, 1000);
+            let entries = miniserver_log::filter_entries(in_window, min_severity, None, limit);
+
+            let files = match client.download_file(miniserver_log::LOG_LISTING_PATH).await {
+                Ok(listing) => {
+                    miniserver_log::parse_log_listing(&String::from_utf8_lossy(&listing))
+                }
+                Err(_) => Vec::new(),
+            };
+
+            Ok(json!({
+                "source": source,
+                "since": since.to_string(),
+                "min_severity": min_severity,
+                "counts": counts,
+                "entries": entries,
+                "log_files": files
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // WEATHER TOOLS
+    // ========================================================================
+
+    /// Get current weather data
+    ///
+    /// Returns weather station readings (temperature, humidity, wind, rain)
+    pub async fn get_weather(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_weather", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut weather_uuids = Vec::new();
+            let mut weather_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                if control_type.contains("Weather") {
+                    weather_uuids.push(uuid.clone());
+                    weather_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &weather_uuids).await;
+
+            let weather_devices: Vec<Value> = weather_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "state": state
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "weather_devices": weather_devices,
+                "count": weather_devices.len()
+            }))
+        })
+        .await
+    }
+
+    /// Get the Weather Service forecast
+    ///
+    /// Returns the coming `hours` (default 24) hour by hour and `days`
+    /// (default 7) as daily minimum/maximum temperature, precipitation and
+    /// wind. Needs a Loxone Weather Service license on the Miniserver
+    /// (`loxone://weather/forecast`)
+    pub async fn get_weather_forecast(
+        &self,
+        hours: Option<usize>,
+        days: Option<usize>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_weather_forecast", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let raw = client.get_weather_forecast().await.map_err(|e| {
+                format!(
+                    "Weather forecast not available ({e}); it needs a Loxone Weather Service \
+                     license"
+                )
+            })?;
+            let forecast = weather_forecast::parse_hourly(&raw).map_err(|e| e.to_string())?;
+
+            // The current hour stays in, earlier ones are past
+            let now = chrono::Utc::now();
+            let upcoming: Vec<_> = forecast
+                .into_iter()
+                .filter(|hour| hour.time + chrono::Duration::hours(1) > now)
+                .collect();
+            let offset = *chrono::Local::now().offset();
+            let mut daily = weather_forecast::daily(&upcoming, offset);
+            daily.truncate(days.unwrap_or(weather_forecast::DEFAULT_FORECAST_DAYS));
+            let hourly: Vec<_> = upcoming
+                .iter()
+                .take(hours.unwrap_or(weather_forecast::DEFAULT_FORECAST_HOURS))
+                .collect();
+
+            Ok(json!({
+                "uri": weather_forecast::FORECAST_URI,
+                "hourly": hourly,
+                "daily": daily,
+                "forecast_until": upcoming.last().map(|hour| hour.time)
+            }))
+        })
+        .await
+    }
+
+    /// Get weather protection status
+    ///
+    /// Returns the protective rules (awnings retract in wind, skylights close
+    /// in rain), the last wind/rain reading, overrides and recent actions
+    pub async fn get_weather_protection(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_weather_protection", async move {
+            Ok(self.weather_protection.status().await)
+        })
+        .await
+    }
+
+    /// Override weather protection for a device
+    ///
+    /// Suspends the protective rules for the device (UUID or name) for the
+    /// given minutes (default 60), e.g. while cleaning an awning; clear
+    /// resumes protection immediately
+    pub async fn override_weather_protection(
+        &self,
+        device: String,
+        minutes: Option<u32>,
+        clear: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("override_weather_protection", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &device)
+                .ok_or_else(|| format!("Device '{device}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let until = if clear.unwrap_or(false) {
+                None
+            } else {
+                let minutes = minutes.unwrap_or(60);
+                if minutes == 0 || minutes > 24 * 60 {
+                    return Err("minutes must be between 1 and 1440".to_string());
+                }
+                Some(chrono::Utc::now() + chrono::Duration::minutes(i64::from(minutes)))
+            };
+            self.weather_protection.set_override(uuid, until).await;
+
+            Ok(json!({
+                "uuid": uuid,
+                "name": name,
+                "protection": if until.is_some() { "suspended" } else { "active" },
+                "until": until,
+                "enabled": self.weather_protection.config().enabled
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // ENERGY TOOLS
+    // ========================================================================
+
+    /// Get energy consumption data
+    ///
+    /// Returns current power usage and energy meters
+    pub async fn get_energy_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_energy_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut energy_uuids = Vec::new();
+            let mut energy_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                if matches!(control_type, "Meter" | "EnergyManager" | "EnergyMonitor")
+                    || control_type.contains("Energy")
+                {
+                    energy_uuids.push(uuid.clone());
+                    energy_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &energy_uuids).await;
+
+            let energy_devices: Vec<Value> = energy_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "state": state
+                    })
+                })
+                .collect();
+
+            // Hot water heating is usually the largest thermal load besides rooms
+            let hot_water = self.fetch_hot_water_units(client, &structure).await;
+
+            Ok(json!({
+                "energy_devices": energy_devices,
+                "count": energy_devices.len(),
+                "hot_water": hot_water
+            }))
+        })
+        .await
+    }
+
+    /// Get the current energy flow
+    ///
+    /// Reports solar production, house consumption, grid import/export and
+    /// battery power and state of charge in kW/%, read from the Energy Flow
+    /// Monitor or Energy Manager, or from meters named after what they
+    /// measure (`loxone://energy/flow`)
+    pub async fn get_energy_flow(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_energy_flow", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            // A flow block reports everything; meters are only a fallback
+            let blocks =
+                Self::find_controls_by_type(&structure, energy_flow::ENERGY_FLOW_CONTROL_TYPES);
+            let sources = if blocks.is_empty() {
+                Self::find_controls_by_type(&structure, energy_flow::METER_CONTROL_TYPES)
+            } else {
+                blocks
+            };
+            let states: Vec<(&String, &Value, Vec<(FlowQuantity, String)>)> = sources
+                .into_iter()
+                .map(|(uuid, control)| (uuid, control, energy_flow::flow_states(control)))
+                .filter(|(_, _, states)| !states.is_empty())
+                .collect();
+            if states.is_empty() {
+                return Err(
+                    "No Energy Flow Monitor, Energy Manager or grid/solar/battery meters found"
+                        .to_string(),
+                );
+            }
+
+            let state_uuids: Vec<String> = states
+                .iter()
+                .flat_map(|(_, _, states)| states.iter().map(|(_, uuid)| uuid.clone()))
+                .collect();
+            let values = client
+                .get_state_values(&state_uuids)
+                .await
+                .map_err(|e| format!("Failed to read energy flow states: {e}"))?;
+
+            let readings: Vec<(FlowQuantity, f64)> = states
+                .iter()
+                .flat_map(|(_, _, states)| states.iter())
+                .filter_map(|(quantity, uuid)| {
+                    Some((*quantity, energy_flow::value_as_f64(values.get(uuid)?)?))
+                })
+                .collect();
+            let sources: Vec<Value> = states
+                .iter()
+                .map(|(uuid, control, states)| {
+                    let quantities: Vec<FlowQuantity> = states.iter().map(|(q, _)| *q).collect();
+                    json!({
+                        "uuid": uuid,
+                        "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                        "type": control.get("type").and_then(|v| v.as_str()).unwrap_or(""),
+                        "measures": quantities
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "flow": EnergyFlow::from_readings(&readings),
+                "sources": sources,
+                "uri": ENERGY_FLOW_URI
+            }))
+        })
+        .await
+    }
+
+    /// Get EV charger status
+    ///
+    /// Returns whether a vehicle is connected and charging, the charging
+    /// power, the energy charged this session, the current limit and past
+    /// sessions of each wallbox (`loxone://energy/wallbox`)
+    pub async fn get_wallbox_status(
+        &self,
+        charger: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_wallbox_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let chargers = Self::find_wallboxes(&structure, charger.as_deref())?;
+            let mut statuses = Self::fetch_wallbox_statuses(client, &chargers).await;
+
+            let charger_list: Vec<Value> = chargers
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "status": statuses.remove(*uuid)
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "uri": WALLBOX_URI,
+                "chargers": charger_list,
+                "count": charger_list.len()
+            }))
+        })
+        .await
+    }
+
+    /// Control EV charging
+    ///
+    /// Start or pause charging on a wallbox (UUID or name), optionally with
+    /// an energy limit for the session
+    pub async fn control_ev_charging(
+        &self,
+        charger: String,
+        action: String,
+        limit_kwh: Option<f64>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("control_ev_charging", async move {
+            self.ensure_connected()?;
+
+            let charging_action = ChargingAction::parse(&action).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let chargers = Self::find_wallboxes(&structure, Some(&charger))?;
+            let (uuid, control) = chargers[0];
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let command = charging_action.command();
+            let response = client
+                .send_command(uuid, command)
+                .await
+                .map_err(|e| format!("Failed to control EV charger {name}: {e}"))?;
+
+            // If a limit was specified, try to send it as well
+            let limit_response = if let Some(limit) = limit_kwh {
+                let limit_cmd = format!("setlimit/{limit}");
+                match client.send_command(uuid, &limit_cmd).await {
+                    Ok(resp) => Some(resp.value),
+                    Err(e) => {
+                        warn!("Failed to set charging limit on {name}: {e}");
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            Ok(json!({
+                "charger": name,
+                "uuid": uuid,
+                "action": charging_action.as_str(),
+                "command_sent": command,
+                "limit_kwh": limit_kwh,
+                "status": "executed",
+                "miniserver_response": response.value,
+                "limit_response": limit_response
+            }))
+        })
+        .await
+    }
+
+    /// Set the charging current limit of an EV charger
+    ///
+    /// Current in amperes, within the range the wallbox reports (usually 6-32 A)
+    pub async fn set_charging_current(
+        &self,
+        amps: f64,
+        charger: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_charging_current", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let chargers = Self::find_wallboxes(&structure, charger.as_deref())?;
+            let [(uuid, control)] = chargers[..] else {
+                return Err("Several EV chargers found; name the charger".to_string());
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let status = Self::fetch_wallbox_statuses(client, &[(uuid, control)])
+                .await
+                .remove(uuid)
+                .unwrap_or_else(|| WallboxStatus::from_state_values(&Default::default()));
+            let command = status.limit_command(amps).map_err(|e| e.to_string())?;
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to set charging current on {name}: {e}"))?;
+
+            Ok(json!({
+                "charger": name,
+                "uuid": uuid,
+                "current_limit_a": amps,
+                "previous_limit_a": status.current_limit_a,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Get load manager status
+    ///
+    /// Lists the consumers of each load manager in priority order (1 is
+    /// shed last) with the ones currently shed, the power budget, and the
+    /// loads shed during the last 24 hours
+    pub async fn get_load_manager_status(
+        &self,
+        manager: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_load_manager_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let managers = Self::find_load_managers(&structure, manager.as_deref())?;
+
+            // Without the background job the history starts now
+            if !self.load_shedding.has_samples().await
+                && let Err(e) = self.load_shedding.sample(client.as_ref()).await
+            {
+                warn!("Failed to sample load managers: {e}");
+            }
+
+            let state_uuids: Vec<(&String, &Value, std::collections::HashMap<String, String>)> =
+                managers
+                    .iter()
+                    .map(|(uuid, control)| (*uuid, *control, load_manager::state_uuids(control)))
+                    .collect();
+            let all_uuids: Vec<String> = state_uuids
+                .iter()
+                .flat_map(|(_, _, states)| states.values().cloned())
+                .collect();
+            let values = if all_uuids.is_empty() {
+                std::collections::HashMap::new()
+            } else {
+                client
+                    .get_state_values(&all_uuids)
+                    .await
+                    .map_err(|e| format!("Failed to read load manager states: {e}"))?
+            };
+
+            let since =
+                chrono::Utc::now() - chrono::Duration::hours(load_manager::HISTORY_WINDOW_HOURS);
+            let mut results = Vec::new();
+            for (uuid, control, states) in state_uuids {
+                let named: std::collections::HashMap<String, Value> = states
+                    .into_iter()
+                    .filter_map(|(name, state)| values.get(&state).map(|v| (name, v.clone())))
+                    .collect();
+                let status = LoadManagerStatus::from_state_values(&named);
+                let consumers: Vec<Value> = load_manager::consumers(control)
+                    .into_iter()
+                    .map(|consumer| {
+                        json!({
+                            "id": consumer.id,
+                            "name": consumer.name,
+                            "priority": consumer.priority,
+                            "power_kw": consumer.power_kw,
+                            "shed": status.shed_loads.contains(&consumer.id),
+                            "running": status.running_loads.contains(&consumer.id)
+                        })
+                    })
+                    .collect();
+                results.push(json!({
+                    "uuid": uuid,
+                    "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
+                    "status": status,
+                    "consumers": consumers,
+                    "shed_last_24h": self.load_shedding.events_since(uuid, since).await
+                }));
+            }
+
+            Ok(json!({
+                "load_managers": results,
+                "count": results.len(),
+                "history_sample_interval_seconds": load_manager::SAMPLE_INTERVAL_SECS
+            }))
+        })
+        .await
+    }
+
+    /// Enable or disable load shedding
+    ///
+    /// Action "enable" or "disable". With a consumer (load id or name) only
+    /// that load is included in or excluded from shedding; without one
+    /// shedding is resumed or suspended for the whole load manager
+    pub async fn control_load_shedding(
+        &self,
+        action: String,
+        consumer: Option<String>,
+        manager: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("control_load_shedding", async move {
+            self.ensure_connected()?;
+
+            let enable = match action.trim().to_lowercase().as_str() {
+                "enable" | "on" | "resume" | "include" => true,
+                "disable" | "off" | "suspend" | "exclude" => false,
+                other => return Err(format!("Invalid action '{other}'. Use: enable, disable")),
+            };
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let managers = Self::find_load_managers(&structure, manager.as_deref())?;
+            let [(uuid, control)] = managers[..] else {
+                return Err("Several load managers found; name the manager".to_string());
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let consumers = load_manager::consumers(control);
+            let target = consumer
+                .as_deref()
+                .map(|c| load_manager::find_consumer(&consumers, c))
+                .transpose()
+                .map_err(|e| e.to_string())?;
+            let command = load_manager::shedding_command(enable, target);
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to change load shedding on {name}: {e}"))?;
+
+            Ok(json!({
+                "load_manager": name,
+                "uuid": uuid,
+                "consumer": target,
+                "shedding_enabled": enable,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Schedule a workflow, optionally deferred until PV surplus is available
+    ///
+    /// Steps are "device:command" pairs (device by UUID or name). With
+    /// min_pv_surplus_w the workflow waits until at least that much power is
+    /// exported, but no longer than max_delay_minutes (default 480)
+    pub async fn schedule_workflow(
+        &self,
+        name: String,
+        steps: Vec<String>,
+        min_pv_surplus_w: Option<f64>,
+        max_delay_minutes: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("schedule_workflow", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut resolved = Vec::with_capacity(steps.len());
+            for step in &steps {
+                let (device, command) =
+                    energy_scheduler::parse_step(step).map_err(|e| e.to_string())?;
+                let (uuid, _) = Self::find_control_by_id_or_name(&structure, &device)
+                    .ok_or_else(|| format!("Device '{device}' not found"))?;
+                resolved.push(WorkflowStep {
+                    device: uuid.clone(),
+                    command,
+                });
+            }
+
+            let preference = min_pv_surplus_w.map(|min_surplus_w| EnergyPreference {
+                min_surplus_w,
+                max_delay: std::time::Duration::from_secs(
+                    u64::from(max_delay_minutes.unwrap_or(480)) * 60,
+                ),
+            });
+            let workflow = self
+                .energy_scheduler
+                .submit(name, resolved, preference)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // Workflows without a preference, or with surplus already
+            // available, start right away
+            let started = self.energy_scheduler.run_due(client.as_ref()).await;
+            let workflow = started
+                .into_iter()
+                .find(|w| w.id == workflow.id)
+                .unwrap_or(workflow);
+
+            Ok(json!({
+                "workflow": workflow,
+                "deadline": workflow.deadline(),
+                "current_energy": self
+                    .energy_scheduler
+                    .last_snapshot()
+                    .await
+                    .map(|snapshot| snapshot.to_json())
+            }))
+        })
+        .await
+    }
+
+    /// Get scheduled workflows
+    ///
+    /// Returns waiting and recently finished workflows with the last PV
+    /// surplus reading; pass cancel with a workflow id to cancel it
+    pub async fn get_scheduled_workflows(
+        &self,
+        cancel: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_scheduled_workflows", async move {
+            let cancelled = match &cancel {
+                Some(id) => {
+                    if !self.energy_scheduler.cancel(id).await {
+                        return Err(format!("No waiting workflow with id '{id}'"));
+                    }
+                    Some(id)
+                }
+                None => None,
+            };
+
+            let workflows = self.energy_scheduler.workflows().await;
+            Ok(json!({
+                "workflows": workflows,
+                "count": workflows.len(),
+                "cancelled": cancelled,
+                "current_energy": self
+                    .energy_scheduler
+                    .last_snapshot()
+                    .await
+                    .map(|snapshot| snapshot.to_json())
+            }))
+        })
+        .await
+    }
+
+    /// Plan a heavy load for the cheapest electricity
+    ///
+    /// Takes the tariff as `HH:MM-HH:MM@price` entries covering the day and
+    /// the load's duration and power, and ranks start times within the next
+    /// `window_hours` (default 24) by the cost of grid energy, counting the
+    /// solar production typical for each hour (from the PV meter's last 7
+    /// days of statistics). With a virtual input, the cheapest start is
+    /// programmed as a workflow setting it to `value` (default "pulse")
+    pub async fn plan_heavy_load(
+        &self,
+        tariff: Vec<String>,
+        duration_minutes: u32,
+        power_kw: f64,
+        window_hours: Option<u32>,
+        virtual_input: Option<String>,
+        value: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("plan_heavy_load", async move {
+            self.ensure_connected()?;
+
+            let profile = TariffProfile::parse(&tariff).map_err(|e| e.to_string())?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let now = match client.get_miniserver_time().await {
+                Ok(now) => now,
+                Err(_) => chrono::Local::now().naive_local(),
+            };
+
+            // Typical production per hour from the first PV meter with statistics
+            let pv_meter = structure.controls.iter().find(|(_, control)| {
+                control.pointer("/statistic/outputs").is_some()
+                    && energy_flow::flow_states(control)
+                        .iter()
+                        .any(|(quantity, _)| *quantity == FlowQuantity::Production)
+            });
+            let mut points = Vec::new();
+            if let Some((uuid, _)) = pv_meter {
+                let today = now.date();
+                let from = today - chrono::Duration::days(7);
+                for (year, month) in statistics::months_between(from, today) {
+                    match client
+                        .get_statistics(uuid, year, month, &["actual".to_string()])
+                        .await
+                    {
+                        Ok(series) => points.extend(
+                            series
+                                .points
+                                .into_iter()
+                                .filter(|p| p.time.date() >= from && p.time.date() < today),
+                        ),
+                        Err(e) => warn!("No PV statistics for {year}-{month:02}: {e}"),
+                    }
+                }
+            }
+            let solar = SolarProfile::from_points(&points);
+
+            let options = tariff::plan(
+                &profile,
+                &solar,
+                now,
+                duration_minutes,
+                power_kw,
+                window_hours.unwrap_or(24),
+            )
+            .map_err(|e| e.to_string())?;
+            let immediate = tariff::estimate(&profile, &solar, now, duration_minutes, power_kw);
+            let best = options[0].clone();
+
+            let programmed = match virtual_input {
+                Some(input) => {
+                    if !self.feature_flags.is_enabled("energy_scheduler") {
+                        return Err(
+                            "Programming a start needs the energy_scheduler feature".to_string()
+                        );
+                    }
+                    let (uuid, control) = Self::find_virtual_input(&structure, &input)?;
+                    let command =
+                        virtual_inputs::build_command(control, value.as_deref().unwrap_or("pulse"))
+                            .map_err(|e| e.to_string())?;
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    // Planned in Miniserver time; the scheduler runs on UTC
+                    let start = chrono::Utc::now() + (best.start - now);
+                    let workflow = self
+                        .energy_scheduler
+                        .submit_at(
+                            format!("{name} (tariff plan)"),
+                            vec![WorkflowStep {
+                                device: uuid.clone(),
+                                command,
+                            }],
+                            start,
+                        )
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    Some(workflow)
+                }
+                None => None,
+            };
+
+            Ok(json!({
+                "recommended": best,
+                "alternatives": options.iter().skip(1).take(2).collect::<Vec<_>>(),
+                "start_now": immediate,
+                "savings": ((immediate.cost - best.cost) * 1000.0).round() / 1000.0,
+                "solar_profile_kw": solar.hourly_kw,
+                "pv_meter": pv_meter.map(|(uuid, _)| uuid),
+                "programmed_workflow": programmed
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // SECURITY TOOLS
+    // ========================================================================
+
+    /// Get security system status
+    ///
+    /// Returns alarm system state, door locks, and security sensors
+    pub async fn get_security_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_security_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut security_uuids = Vec::new();
+            let mut security_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                if matches!(
+                    control_type,
+                    "Alarm" | "SmokeAlarm" | "Gate" | "DoorLock" | "AccessControl"
+                ) || control_type.contains("Security")
+                {
+                    security_uuids.push(uuid.clone());
+                    security_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &security_uuids).await;
+
+            let security_devices: Vec<Value> = security_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "state": state
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "security_devices": security_devices,
+                "count": security_devices.len()
+            }))
+        })
+        .await
+    }
+
+    /// Arm or disarm security system
+    ///
+    /// Set security system mode: arm_away, arm_home, disarm
+    pub async fn set_security_mode(
+        &self,
+        mode: String,
+        code: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_security_mode", async move {
+            self.ensure_connected()?;
+
+            let normalized_mode = ActionAliases::global()
+                .normalize_action(&mode, &["arm_away", "arm_home", "disarm"])
+                .ok_or_else(|| format!("Invalid mode '{mode}'. Use: arm_away, arm_home, disarm"))?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            // Find alarm/security controls
+            let security_controls: Vec<(&String, &Value)> = structure
+                .controls
+                .iter()
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    matches!(control_type, "Alarm" | "AccessControl")
+                        || control_type.contains("Security")
+                })
+                .collect();
+
+            if security_controls.is_empty() {
+                return Err("No security/alarm devices found in the system".to_string());
+            }
+
+            // Build command - include code if provided
+            let command = match (normalized_mode, &code) {
+                ("arm_away", Some(c)) => format!("on/{c}"),
+                ("arm_away", None) => "on".to_string(),
+                ("arm_home", Some(c)) => format!("on/{c}"),
+                ("arm_home", None) => "on".to_string(),
+                ("disarm", Some(c)) => format!("off/{c}"),
+                ("disarm", None) => "off".to_string(),
+                _ => "off".to_string(),
+            };
+
+            let mut results = Vec::new();
+            for (uuid, control) in &security_controls {
+                let name = control
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                match client.send_command(uuid, &command).await {
+                    Ok(response) => {
+                        results.push(json!({
+                            "uuid": uuid,
+                            "name": name,
+                            "status": "executed",
+                            "miniserver_response": response.value
+                        }));
+                    }
+                    Err(e) => {
+                        results.push(json!({
+                            "uuid": uuid,
+                            "name": name,
+                            "status": "error",
+                            "error": format!("{e}")
+                        }));
+                    }
+                }
+            }
+
+            Ok(json!({
+                "mode": normalized_mode,
+                "code_provided": code.is_some(),
+                "command_sent": command,
+                "devices_affected": results.len(),
+                "results": results
+            }))
+        })
+        .await
+    }
+
+    /// Get burglar alarm status
+    ///
+    /// Reports whether each alarm is armed, arming or going off, the alarm
+    /// level and the sensors that triggered it
+    pub async fn get_alarm_status(
+        &self,
+        alarm: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_alarm_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let alarms = Self::find_alarms(&structure, alarm.as_deref())?;
+            let mut statuses = Self::fetch_alarm_statuses(client, &alarms).await;
+
+            let alarm_list: Vec<Value> = alarms
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "status": statuses.remove(*uuid)
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "alarms": alarm_list,
+                "count": alarm_list.len()
+            }))
+        })
+        .await
+    }
+
+    /// Arm, disarm or acknowledge the burglar alarm
+    ///
+    /// Actions: arm, arm_home (without motion sensors), delayed_arm, disarm,
+    /// acknowledge. Disarming waits until the user approves it
+    pub async fn control_alarm(
+        &self,
+        action: String,
+        alarm: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let arguments = json!({ "action": action, "alarm": alarm });
+        self.run_tool_with("control_alarm", arguments, async move {
+            self.ensure_connected()?;
+
+            let alarm_action = AlarmAction::parse(&action).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let alarms = Self::find_alarms(&structure, alarm.as_deref())?;
+
+            let mut previous = Self::fetch_alarm_statuses(client, &alarms).await;
+            let command = alarm_action.command();
+            let mut results = Vec::new();
+            for (uuid, control) in &alarms {
+                let name = control
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                let result = client.send_command(uuid, command).await;
+                info!("Alarm '{name}' {}", alarm_action.as_str());
+                results.push(json!({
+                    "uuid": uuid,
+                    "name": name,
+                    "previous_status": previous.remove(*uuid),
+                    "success": result.is_ok(),
+                    "miniserver_response": result.as_ref().ok().map(|r| r.value.clone()),
+                    "error": result.err().map(|e| e.to_string())
+                }));
+            }
+
+            Ok(json!({
+                "action": alarm_action.as_str(),
+                "command_sent": command,
+                "consent_confirmed": alarm_action.requires_consent(),
+                "alarms": results
+            }))
+        })
+        .await
+    }
+
+    /// Control door lock
+    ///
+    /// Lock or unlock a smart door lock
+    pub async fn control_door_lock(
+        &self,
+        lock: String,
+        action: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("control_door_lock", async move {
+            self.ensure_connected()?;
+
+            let normalized_action = ActionAliases::global()
+                .normalize_action(&action, &["lock", "unlock"])
+                .ok_or_else(|| format!("Invalid action '{action}'. Use: lock, unlock"))?;
+
+            let client = self.get_client()?;
+            let command = match normalized_action {
+                "lock" => "on",
+                "unlock" => "off",
+                _ => "off",
+            };
+
+            let response = client
+                .send_command(&lock, command)
+                .await
+                .map_err(|e| format!("Failed to control door lock {lock}: {e}"))?;
+
+            Ok(json!({
+                "lock": lock,
+                "action": normalized_action,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // SAFETY TOOLS
+    // ========================================================================
+
+    /// Get smoke alarm and water leak status
+    ///
+    /// Reports alarm level, cause and triggering sensors of each smoke alarm
+    /// and leak detector (`loxone://safety/status`)
+    pub async fn get_safety_status(
+        &self,
+        device: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_safety_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let devices = Self::find_safety_devices(&structure, device.as_deref())?;
+            let mut statuses = Self::fetch_safety_statuses(client, &devices).await;
+
+            let device_list: Vec<Value> = devices
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .and_then(|room_uuid| structure.rooms.get(room_uuid))
+                        .and_then(|room| room.get("name"))
+                        .and_then(|v| v.as_str());
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "room": room,
+                        "status": statuses.remove(*uuid)
+                    })
+                })
+                .collect();
+            let alarms = device_list
+                .iter()
+                .filter(|d| d["status"]["alarm"].as_bool() == Some(true))
+                .count();
+
+            Ok(json!({
+                "devices": device_list,
+                "count": device_list.len(),
+                "alarms_active": alarms,
+                "uri": SAFETY_STATUS_URI
+            }))
+        })
+        .await
+    }
+
+    /// Trigger a smoke alarm test
+    ///
+    /// Sounds the sirens of a smoke alarm to check them. Waits until the user
+    /// approves it since everyone in the house will hear it
+    pub async fn test_safety_alarm(
+        &self,
+        device: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let arguments = json!({ "device": device });
+        self.run_tool_with("test_safety_alarm", arguments, async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let devices: Vec<_> = Self::find_safety_devices(&structure, device.as_deref())?
+                .into_iter()
+                .filter(|(_, control)| {
+                    control
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .and_then(SafetyKind::from_control_type)
+                        .is_some_and(SafetyKind::supports_test_alarm)
+                })
+                .collect();
+            let [(uuid, control)] = devices[..] else {
+                return Err(if devices.is_empty() {
+                    "Test alarms are only supported by smoke alarms".to_string()
+                } else {
+                    "Several smoke alarms found; name the smoke alarm".to_string()
+                });
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let response = client
+                .send_command(uuid, safety::TEST_ALARM_COMMAND)
+                .await
+                .map_err(|e| format!("Failed to start test alarm on {name}: {e}"))?;
+
+            info!("Test alarm started on '{name}'");
+
+            Ok(json!({
+                "device": name,
+                "uuid": uuid,
+                "command_sent": safety::TEST_ALARM_COMMAND,
+                "consent_confirmed": true,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // ACCESS TOOLS
+    // ========================================================================
+
+    /// Get gate and garage door status
+    ///
+    /// Returns position, movement and obstruction state of gates and garage
+    /// doors, optionally filtered by gate name/UUID or room
+    pub async fn get_gate_status(
+        &self,
+        gate: Option<String>,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_gate_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let gates = if let Some(ref identifier) = gate {
+                let (uuid, control) = Self::find_control_by_id_or_name(&structure, identifier)
+                    .filter(|(_, control)| {
+                        let control_type =
+                            control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                        access::GATE_CONTROL_TYPES.contains(&control_type)
+                    })
+                    .ok_or_else(|| format!("Gate '{identifier}' not found"))?;
+                vec![(uuid, control)]
+            } else if let Some(ref room_name) = room {
+                let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                    .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                Self::find_controls_by_type_in_room(
+                    &structure,
+                    &room_uuid,
+                    access::GATE_CONTROL_TYPES,
+                )
+            } else {
+                Self::find_controls_by_type(&structure, access::GATE_CONTROL_TYPES)
+            };
+
+            let statuses = Self::fetch_gate_statuses(client, &gates).await;
+
+            let gate_list: Vec<Value> = gates
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "status": statuses.get(*uuid)
+                    })
+                })
+                .collect();
+
+            let obstructed = statuses.values().filter(|s| s.obstructed).count();
+
+            Ok(json!({
+                "capability": "access",
+                "gates": gate_list,
+                "count": gate_list.len(),
+                "obstructed_count": obstructed
+            }))
+        })
+        .await
+    }
+
+    /// Control a gate or garage door
+    ///
+    /// Actions: open, close, stop, partial_open. Opening remotely waits until
+    /// the user approves it.
+    /// - confirm: wait for the gate to start moving and report its position (optional)
+    pub async fn control_gate(
+        &self,
+        gate: String,
+        action: String,
+        confirm: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let arguments = json!({ "gate": gate, "action": action, "confirm": confirm });
+        self.run_tool_with("control_gate", arguments, async move {
+            self.ensure_connected()?;
+
+            let gate_action = GateAction::parse(&action).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let (uuid, control) = Self::find_control_by_id_or_name(&structure, &gate)
+                .filter(|(_, control)| {
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    access::GATE_CONTROL_TYPES.contains(&control_type)
+                })
+                .ok_or_else(|| format!("Gate '{gate}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
 
-            if control_type.contains("Weather") {
-                weather_uuids.push(uuid.clone());
-                weather_info.push((uuid.clone(), control.clone()));
+            let status = Self::fetch_gate_statuses(client, &[(uuid, control)])
+                .await
+                .remove(uuid);
+            if gate_action == GateAction::Close
+                && let Some(status) = status.as_ref().filter(|s| s.obstructed)
+            {
+                return Err(format!(
+                    "Cannot close '{name}': obstruction detected at {}% open",
+                    status.position_percent.unwrap_or_default()
+                ));
             }
-        }
 
-        let live_states = Self::fetch_live_states(client, &weather_uuids).await;
+            let command = gate_action.command();
+            let response = client
+                .send_command(uuid, command)
+                .await
+                .map_err(|e| format!("Failed to control gate {name}: {e}"))?;
 
-        let weather_devices: Vec<Value> = weather_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
+            info!("Gate '{name}' {}", gate_action.as_str());
+
+            Ok(json!({
+                "gate": name,
+                "uuid": uuid,
+                "action": gate_action.as_str(),
+                "command_sent": command,
+                "consent_confirmed": gate_action.requires_consent(),
+                "previous_status": status,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // CAMERA TOOLS
+    // ========================================================================
+
+    /// Get camera/intercom status
+    ///
+    /// Returns list of cameras and video intercoms
+    pub async fn get_camera_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_camera_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let mut camera_uuids = Vec::new();
+            let mut camera_info = Vec::new();
+
+            for (uuid, control) in &structure.controls {
                 let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "state": state
+                if matches!(control_type, "Intercom" | "Camera" | "Doorbell")
+                    || control_type.contains("Camera")
+                {
+                    camera_uuids.push(uuid.clone());
+                    camera_info.push((uuid.clone(), control.clone()));
+                }
+            }
+
+            let live_states = Self::fetch_live_states(client, &camera_uuids).await;
+
+            let cameras: Vec<Value> = camera_info
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                    let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "type": control_type,
+                        "room": room,
+                        "state": state
+                    })
                 })
-            })
-            .collect();
+                .collect();
 
-        Ok(json!({
-            "weather_devices": weather_devices,
-            "count": weather_devices.len()
-        }))
+            Ok(json!({
+                "cameras": cameras,
+                "count": cameras.len()
+            }))
+        })
+        .await
     }
 
     // ========================================================================
-    // ENERGY TOOLS
+    // INTERCOM TOOLS
     // ========================================================================
 
-    /// Get energy consumption data
+    /// Answer or control intercom
     ///
-    /// Returns current power usage and energy meters
-    pub async fn get_energy_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// Answer calls or control intercom features. Doors are opened with
+    /// `open_intercom_door`, which asks for the user's consent
+    pub async fn control_intercom(
+        &self,
+        intercom: String,
+        action: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("control_intercom", async move {
+            self.ensure_connected()?;
+
+            let normalized_action = match action.to_lowercase().as_str() {
+                "answer" | "annehmen" | "abheben" => "answer",
+                "hangup" | "auflegen" | "beenden" => "hangup",
+                "open" | "öffnen" | "tür" => {
+                    return Err(
+                        "Opening the door needs the user's consent; use open_intercom_door"
+                            .to_string(),
+                    );
+                }
+                "talk" | "sprechen" => "talk",
+                "mute" | "stumm" => "mute",
+                _ => {
+                    return Err(format!(
+                        "Invalid action '{action}'. Use: answer, hangup, talk, mute"
+                    ));
+                }
+            };
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let client = self.get_client()?;
 
-        let mut energy_uuids = Vec::new();
-        let mut energy_info = Vec::new();
+            // Map intercom actions to Loxone commands
+            let command = match normalized_action {
+                "answer" => "answer",
+                "hangup" => "hangup",
+                "talk" => "talk",
+                "mute" => "mute",
+                _ => normalized_action,
+            };
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            let response = client
+                .send_command(&intercom, command)
+                .await
+                .map_err(|e| format!("Failed to control intercom {intercom}: {e}"))?;
 
-            if matches!(control_type, "Meter" | "EnergyManager" | "EnergyMonitor")
-                || control_type.contains("Energy")
-            {
-                energy_uuids.push(uuid.clone());
-                energy_info.push((uuid.clone(), control.clone()));
+            Ok(json!({
+                "intercom": intercom,
+                "action": normalized_action,
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Get the latest doorbell rings
+    ///
+    /// Returns the bell events of each intercom (`loxone://intercom/events`),
+    /// newest first, whether it is ringing right now and its outputs.
+    /// Optionally for a single intercom, limited to `limit` events each.
+    pub async fn get_intercom_history(
+        &self,
+        intercom: Option<String>,
+        limit: Option<usize>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_intercom_history", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let intercoms = Self::find_intercoms(&structure, intercom.as_deref())?;
+
+            let state_uuids: Vec<String> = intercoms
+                .iter()
+                .flat_map(|(_, control)| {
+                    let (bell, events) = intercom::bell_state_uuids(control);
+                    bell.into_iter().chain(events)
+                })
+                .collect();
+            let values = client
+                .get_state_values(&state_uuids)
+                .await
+                .map_err(|e| format!("Failed to read bell events: {e}"))?;
+
+            let limit = limit.unwrap_or(10);
+            let intercom_list: Vec<Value> = intercoms
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let (bell, events) = intercom::bell_state_uuids(control);
+                    let ringing = bell
+                        .and_then(|uuid| values.get(&uuid))
+                        .and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok()))
+                        .is_some_and(|v| v != 0.0);
+                    let events: Vec<String> = events
+                        .and_then(|uuid| values.get(&uuid))
+                        .and_then(|v| v.as_str())
+                        .map(intercom::parse_bell_events)
+                        .unwrap_or_default()
+                        .iter()
+                        .take(limit)
+                        .map(|event| event.format("%Y-%m-%dT%H:%M:%S").to_string())
+                        .collect();
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "ringing": ringing,
+                        "bell_events": events,
+                        "outputs": intercom::outputs(control)
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "uri": INTERCOM_EVENTS_URI,
+                "intercoms": intercom_list,
+                "count": intercom_list.len(),
+                "note": "Bell times are Miniserver local time"
+            }))
+        })
+        .await
+    }
+
+    /// Open the door at an intercom
+    ///
+    /// Pulses the door strike output of the intercom, or the named `output`.
+    /// Waits until the user approves the opening
+    pub async fn open_intercom_door(
+        &self,
+        intercom: Option<String>,
+        output: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let arguments = json!({ "intercom": intercom, "output": output });
+        self.run_tool_with("open_intercom_door", arguments, async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let intercoms = Self::find_intercoms(&structure, intercom.as_deref())?;
+            let [(uuid, control)] = intercoms[..] else {
+                return Err(format!(
+                    "Several intercoms found; name one of: {}",
+                    intercoms
+                        .iter()
+                        .filter_map(|(_, c)| c.get("name").and_then(|v| v.as_str()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+            let door = intercom::door_output(&intercom::outputs(control), output.as_deref())
+                .map_err(|e| format!("{name}: {e}"))?;
+
+            let response = client
+                .send_command(&door.uuid, intercom::DOOR_COMMAND)
+                .await
+                .map_err(|e| format!("Failed to open {} at {name}: {e}", door.name))?;
+
+            info!("Opened '{}' at intercom '{name}'", door.name);
+
+            Ok(json!({
+                "intercom": name,
+                "uuid": uuid,
+                "output": door.name,
+                "output_uuid": door.uuid,
+                "command_sent": intercom::DOOR_COMMAND,
+                "consent_confirmed": true,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// List the access codes and NFC tags of a Code Touch
+    ///
+    /// Returns name, kind, active flag, access outputs and validity of each
+    /// entry; the codes and tag IDs themselves are never returned. Requires
+    /// an admin API key.
+    pub async fn list_access_codes(
+        &self,
+        device: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_access_codes", async move {
+            if !self.admin_session {
+                return Err("Managing access codes requires an admin API key".to_string());
             }
-        }
+            self.ensure_connected()?;
 
-        let live_states = Self::fetch_live_states(client, &energy_uuids).await;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_code_touch(&structure, device.as_deref())?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
 
-        let energy_devices: Vec<Value> = energy_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+            let entries = Self::read_access_entries(client, uuid, name).await?;
+            Ok(json!({
+                "device": name,
+                "uuid": uuid,
+                "entries": entries,
+                "count": entries.len()
+            }))
+        })
+        .await
+    }
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
+    /// Add an access code or NFC tag to a Code Touch
+    ///
+    /// `kind` is `code` (2 to 8 digits) or `nfc_tag` (tag ID in hex);
+    /// `outputs` are the access outputs it opens (1-8, default 1). Optional
+    /// validity as `YYYY-MM-DD[ HH:MM]` in UTC. The secret is masked in the
+    /// command history. Requires an admin API key.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_access_code(
+        &self,
+        device: Option<String>,
+        name: String,
+        kind: String,
+        secret: String,
+        outputs: Option<Vec<u8>>,
+        valid_from: Option<String>,
+        valid_until: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("add_access_code", async move {
+            if !self.admin_session {
+                return Err("Managing access codes requires an admin API key".to_string());
+            }
+            self.ensure_connected()?;
+
+            let access = code_touch::NewAccess {
+                name,
+                kind: code_touch::AccessKind::parse(&kind).map_err(|e| e.to_string())?,
+                secret,
+                outputs: outputs.unwrap_or_default(),
+                valid_from: valid_from
+                    .map(|t| tracker::parse_time(&t, false))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+                valid_until: valid_until
+                    .map(|t| tracker::parse_time(&t, true))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+            };
+            let command = access.add_command().map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_code_touch(&structure, device.as_deref())?;
+            let device_name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to add {kind} to {device_name}: {e}"))?;
+            info!(
+                "Added {:?} '{}' to Code Touch '{device_name}'",
+                access.kind, access.name
+            );
+
+            Ok(json!({
+                "device": device_name,
+                "uuid": uuid,
+                "name": access.name,
+                "kind": access.kind,
+                "outputs": if access.outputs.is_empty() { vec![1] } else { access.outputs.clone() },
+                "valid_from": access.valid_from,
+                "valid_until": access.valid_until,
+                "status": "added"
+            }))
+        })
+        .await
+    }
+
+    /// Revoke an access code or NFC tag of a Code Touch
+    ///
+    /// `code_id` is the ID from `list_access_codes`. Requires an admin API
+    /// key.
+    pub async fn revoke_access_code(
+        &self,
+        device: Option<String>,
+        code_id: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("revoke_access_code", async move {
+            if !self.admin_session {
+                return Err("Managing access codes requires an admin API key".to_string());
+            }
+            self.ensure_connected()?;
+            let command = code_touch::delete_command(&code_id).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let (uuid, control) = Self::find_code_touch(&structure, device.as_deref())?;
+            let device_name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let entries = Self::read_access_entries(client, uuid, device_name).await?;
+            let entry = entries
+                .iter()
+                .find(|entry| entry.id == code_id.trim())
+                .ok_or_else(|| format!("No code '{code_id}' on {device_name}"))?;
+
+            client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to revoke '{}' on {device_name}: {e}", entry.name))?;
+            info!(
+                "Revoked {:?} '{}' on Code Touch '{device_name}'",
+                entry.kind, entry.name
+            );
+
+            Ok(json!({
+                "device": device_name,
+                "uuid": uuid,
+                "revoked": entry,
+                "status": "revoked"
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // IRRIGATION TOOLS
+    // ========================================================================
+
+    /// Get irrigation status
+    ///
+    /// Returns each irrigation controller's zones with their watering times,
+    /// the zone being watered, its remaining run time and the rain lockout
+    pub async fn get_irrigation_status(
+        &self,
+        controller: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_irrigation_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let controllers = Self::find_irrigation_controllers(&structure, controller.as_deref())?;
+            let mut statuses = Self::fetch_irrigation_statuses(client, &controllers).await?;
+
+            let controller_list: Vec<Value> = controllers
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "status": statuses.remove(*uuid)
+                    })
                 })
-            })
-            .collect();
+                .collect();
 
-        Ok(json!({
-            "energy_devices": energy_devices,
-            "count": energy_devices.len()
-        }))
+            Ok(json!({
+                "controllers": controller_list,
+                "count": controller_list.len()
+            }))
+        })
+        .await
+    }
+
+    /// Start or stop irrigation
+    ///
+    /// Actions: start (all zones in order, or only `zone` if given), stop.
+    /// Zones are names or IDs from `get_irrigation_status`
+    pub async fn control_irrigation(
+        &self,
+        action: String,
+        zone: Option<String>,
+        controller: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("control_irrigation", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let controllers = Self::find_irrigation_controllers(&structure, controller.as_deref())?;
+            let [(uuid, control)] = controllers[..] else {
+                return Err("Several irrigation controllers found; name the controller".to_string());
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+
+            let status = Self::fetch_irrigation_statuses(client, &[(uuid, control)])
+                .await?
+                .remove(uuid);
+            let (command, zone) = match (action.to_lowercase().as_str(), zone) {
+                ("start" | "on" | "water", None) => ("start".to_string(), None),
+                ("start" | "on" | "water", Some(zone)) => {
+                    let zones = status.as_ref().map(|s| s.zones.as_slice()).unwrap_or(&[]);
+                    let zone = irrigation::find_zone(zones, &zone)
+                        .map_err(|e| e.to_string())?
+                        .clone();
+                    (irrigation::select_command(&zone), Some(zone))
+                }
+                ("stop" | "off", _) => ("stop".to_string(), None),
+                _ => return Err(format!("Invalid action '{action}'. Use: start, stop")),
+            };
+
+            if command != "stop"
+                && let Some(status) = status.as_ref().filter(|s| s.rain_active)
+            {
+                warn!(
+                    "Starting irrigation '{name}' while the rain lockout is active ({} zones)",
+                    status.zones.len()
+                );
+            }
+
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to control irrigation {name}: {e}"))?;
+
+            Ok(json!({
+                "controller": name,
+                "uuid": uuid,
+                "zone": zone,
+                "command_sent": command,
+                "rain_active": status.map(|s| s.rain_active),
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
     }
 
-    /// Control EV charging
+    /// Change how long an irrigation zone is watered
     ///
-    /// Start, stop, or set charging limits for electric vehicle chargers
-    pub async fn control_ev_charging(
+    /// Sets the watering time (1-240 minutes) the zone gets on every
+    /// scheduled run. Zones are names or IDs from `get_irrigation_status`
+    pub async fn set_irrigation_duration(
         &self,
-        charger: String,
-        action: String,
-        limit_kwh: Option<f64>,
+        zone: String,
+        minutes: u32,
+        controller: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let normalized_action = match action.to_lowercase().as_str() {
-            "start" | "laden" => "start",
-            "stop" | "stoppen" => "stop",
-            "pause" | "pausieren" => "pause",
-            _ => {
-                return Err(format!(
-                    "Invalid action '{action}'. Use: start, stop, pause"
-                ));
-            }
-        };
+        self.run_tool("set_irrigation_duration", async move {
+            self.ensure_connected()?;
 
-        let client = self.get_client()?;
-
-        // Map actions to Loxone commands
-        let command = match normalized_action {
-            "start" => "on".to_string(),
-            "stop" => "off".to_string(),
-            "pause" => "off".to_string(),
-            _ => "off".to_string(),
-        };
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let controllers = Self::find_irrigation_controllers(&structure, controller.as_deref())?;
+            let mut statuses = Self::fetch_irrigation_statuses(client, &controllers).await?;
 
-        let response = client
-            .send_command(&charger, &command)
-            .await
-            .map_err(|e| format!("Failed to control EV charger {charger}: {e}"))?;
+            // Without a controller name, the zone name picks the controller
+            let (uuid, control, zone) = controllers
+                .iter()
+                .find_map(|(uuid, control)| {
+                    let zones = &statuses.get(*uuid)?.zones;
+                    let zone = irrigation::find_zone(zones, &zone).ok()?.clone();
+                    Some((*uuid, *control, zone))
+                })
+                .ok_or_else(|| format!("Irrigation zone '{zone}' not found"))?;
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
 
-        // If a limit was specified, try to send it as well
-        let limit_response = if let Some(limit) = limit_kwh {
-            let limit_cmd = format!("setlimit/{limit}");
-            match client.send_command(&charger, &limit_cmd).await {
-                Ok(resp) => Some(resp.value),
-                Err(e) => {
-                    warn!("Failed to set charging limit on {charger}: {e}");
-                    None
-                }
-            }
-        } else {
-            None
-        };
+            let command =
+                irrigation::duration_command(&zone, minutes).map_err(|e| e.to_string())?;
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to set irrigation duration on {name}: {e}"))?;
 
-        Ok(json!({
-            "charger": charger,
-            "action": normalized_action,
-            "command_sent": command,
-            "limit_kwh": limit_kwh,
-            "status": "executed",
-            "miniserver_response": response.value,
-            "limit_response": limit_response
-        }))
+            Ok(json!({
+                "controller": name,
+                "uuid": uuid,
+                "zone": zone.name,
+                "zone_id": zone.id,
+                "previous_duration_minutes": zone.duration.map(|secs| secs / 60),
+                "duration_minutes": minutes,
+                "command_sent": command,
+                "watering_now": statuses.remove(uuid).is_some_and(|s| s.watering),
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
     }
 
     // ========================================================================
-    // SECURITY TOOLS
+    // POOL TOOLS
     // ========================================================================
 
-    /// Get security system status
+    /// Get pool status
     ///
-    /// Returns alarm system state, door locks, and security sensors
-    pub async fn get_security_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut security_uuids = Vec::new();
-        let mut security_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-            if matches!(
-                control_type,
-                "Alarm" | "SmokeAlarm" | "Gate" | "DoorLock" | "AccessControl"
-            ) || control_type.contains("Security")
-            {
-                security_uuids.push(uuid.clone());
-                security_info.push((uuid.clone(), control.clone()));
-            }
-        }
-
-        let live_states = Self::fetch_live_states(client, &security_uuids).await;
+    /// Returns water and target temperature, filtration pump, operating mode
+    /// and cover position of each pool controller
+    pub async fn get_pool_status(
+        &self,
+        pool: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_pool_status", async move {
+            self.ensure_connected()?;
 
-        let security_devices: Vec<Value> = security_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let pools = Self::find_pools(&structure, pool.as_deref())?;
+            let mut statuses = Self::fetch_pool_statuses(client, &pools).await;
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
+            let pool_list: Vec<Value> = pools
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "status": statuses.remove(*uuid)
+                    })
                 })
-            })
-            .collect();
+                .collect();
 
-        Ok(json!({
-            "security_devices": security_devices,
-            "count": security_devices.len()
-        }))
+            Ok(json!({
+                "pools": pool_list,
+                "count": pool_list.len()
+            }))
+        })
+        .await
     }
 
-    /// Arm or disarm security system
+    /// Control the pool pump or cover
     ///
-    /// Set security system mode: arm_away, arm_home, disarm
-    pub async fn set_security_mode(
+    /// Actions: pump_on (start a filtration cycle), pump_off, backwash,
+    /// cover_open, cover_close, cover_stop. Closing the cover waits until
+    /// the user confirms that nobody is in the water
+    pub async fn control_pool(
         &self,
-        mode: String,
-        code: Option<String>,
+        action: String,
+        pool: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        let arguments = json!({ "action": action, "pool": pool });
+        self.run_tool_with("control_pool", arguments, async move {
+            self.ensure_connected()?;
 
-        let normalized_mode = match mode.to_lowercase().as_str() {
-            "arm" | "arm_away" | "scharf" | "abwesend" => "arm_away",
-            "arm_home" | "arm_stay" | "zuhause" => "arm_home",
-            "disarm" | "unscharf" | "aus" => "disarm",
-            _ => {
-                return Err(format!(
-                    "Invalid mode '{mode}'. Use: arm_away, arm_home, disarm"
-                ));
-            }
-        };
+            let pool_action = PoolAction::parse(&action).map_err(|e| e.to_string())?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let pools = Self::find_pools(&structure, pool.as_deref())?;
+            let [(uuid, control)] = pools[..] else {
+                return Err("Several pools found; name the pool".to_string());
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
 
-        // Find alarm/security controls
-        let security_controls: Vec<(&String, &Value)> = structure
-            .controls
-            .iter()
-            .filter(|(_, control)| {
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                matches!(control_type, "Alarm" | "AccessControl")
-                    || control_type.contains("Security")
-            })
-            .collect();
+            let previous = Self::fetch_pool_statuses(client, &[(uuid, control)])
+                .await
+                .remove(uuid);
+            let command = pool_action.command();
+            let response = client
+                .send_command(uuid, command)
+                .await
+                .map_err(|e| format!("Failed to control pool {name}: {e}"))?;
 
-        if security_controls.is_empty() {
-            return Err("No security/alarm devices found in the system".to_string());
-        }
+            info!("Pool '{name}' {}", pool_action.as_str());
 
-        // Build command - include code if provided
-        let command = match (normalized_mode, &code) {
-            ("arm_away", Some(c)) => format!("on/{c}"),
-            ("arm_away", None) => "on".to_string(),
-            ("arm_home", Some(c)) => format!("on/{c}"),
-            ("arm_home", None) => "on".to_string(),
-            ("disarm", Some(c)) => format!("off/{c}"),
-            ("disarm", None) => "off".to_string(),
-            _ => "off".to_string(),
-        };
+            Ok(json!({
+                "pool": name,
+                "uuid": uuid,
+                "action": pool_action.as_str(),
+                "command_sent": command,
+                "consent_confirmed": pool_action.requires_consent(),
+                "previous_status": previous,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    /// Set the pool's target water temperature
+    ///
+    /// Temperature in °C (10-40)
+    pub async fn set_pool_temperature(
+        &self,
+        temperature: f64,
+        pool: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("set_pool_temperature", async move {
+            self.ensure_connected()?;
 
-        let mut results = Vec::new();
-        for (uuid, control) in &security_controls {
+            let command = pool::target_temp_command(temperature).map_err(|e| e.to_string())?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let pools = Self::find_pools(&structure, pool.as_deref())?;
+            let [(uuid, control)] = pools[..] else {
+                return Err("Several pools found; name the pool".to_string());
+            };
             let name = control
                 .get("name")
                 .and_then(|v| v.as_str())
                 .unwrap_or("Unknown");
-            match client.send_command(uuid, &command).await {
-                Ok(response) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "status": "executed",
-                        "miniserver_response": response.value
-                    }));
-                }
-                Err(e) => {
-                    results.push(json!({
+
+            let previous = Self::fetch_pool_statuses(client, &[(uuid, control)])
+                .await
+                .remove(uuid);
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to set pool temperature on {name}: {e}"))?;
+
+            Ok(json!({
+                "pool": name,
+                "uuid": uuid,
+                "target_temperature": temperature,
+                "previous_target_temperature": previous.as_ref().and_then(|s| s.target_temperature),
+                "water_temperature": previous.and_then(|s| s.water_temperature),
+                "command_sent": command,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
+    }
+
+    // ========================================================================
+    // VENTILATION TOOLS
+    // ========================================================================
+
+    /// Get ventilation status and indoor air quality
+    ///
+    /// Returns CO2 (ppm), humidity (%), VOC index, fan stage and mode of each
+    /// Ventilation or AirHandling unit
+    pub async fn get_ventilation_status(
+        &self,
+        unit: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("get_ventilation_status", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let units = Self::find_ventilation_units(&structure, unit.as_deref())?;
+            let mut statuses = Self::fetch_ventilation_statuses(client, &units).await;
+
+            let unit_list: Vec<Value> = units
+                .iter()
+                .map(|(uuid, control)| {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let control_type = control
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    json!({
                         "uuid": uuid,
                         "name": name,
-                        "status": "error",
-                        "error": format!("{e}")
-                    }));
-                }
-            }
-        }
+                        "type": control_type,
+                        "status": statuses.remove(*uuid)
+                    })
+                })
+                .collect();
 
-        Ok(json!({
-            "mode": normalized_mode,
-            "code_provided": code.is_some(),
-            "command_sent": command,
-            "devices_affected": results.len(),
-            "results": results
-        }))
+            Ok(json!({
+                "units": unit_list,
+                "count": unit_list.len()
+            }))
+        })
+        .await
     }
 
-    /// Control door lock
+    /// Set the ventilation mode
     ///
-    /// Lock or unlock a smart door lock
-    pub async fn control_door_lock(
+    /// Actions: auto (follow air quality and presence), stage (fixed fan
+    /// stage 0-4, requires `stage`), boost (full power for `minutes`,
+    /// default 30)
+    pub async fn set_ventilation_mode(
         &self,
-        lock: String,
         action: String,
+        stage: Option<u8>,
+        minutes: Option<u32>,
+        unit: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        self.run_tool("set_ventilation_mode", async move {
+            self.ensure_connected()?;
 
-        let normalized_action = match action.to_lowercase().as_str() {
-            "lock" | "abschließen" | "zu" => "lock",
-            "unlock" | "aufschließen" | "auf" => "unlock",
-            _ => return Err(format!("Invalid action '{action}'. Use: lock, unlock")),
-        };
+            let mode =
+                VentilationMode::parse(&action, stage, minutes).map_err(|e| e.to_string())?;
 
-        let client = self.get_client()?;
-        let command = match normalized_action {
-            "lock" => "on",
-            "unlock" => "off",
-            _ => "off",
-        };
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let units = Self::find_ventilation_units(&structure, unit.as_deref())?;
+            let [(uuid, control)] = units[..] else {
+                return Err("Several ventilation units found; name the unit".to_string());
+            };
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
 
-        let response = client
-            .send_command(&lock, command)
-            .await
-            .map_err(|e| format!("Failed to control door lock {lock}: {e}"))?;
+            let previous = Self::fetch_ventilation_statuses(client, &[(uuid, control)])
+                .await
+                .remove(uuid);
+            let command = mode.command();
+            let response = client
+                .send_command(uuid, &command)
+                .await
+                .map_err(|e| format!("Failed to control ventilation {name}: {e}"))?;
 
-        Ok(json!({
-            "lock": lock,
-            "action": normalized_action,
-            "command_sent": command,
-            "status": "executed",
-            "miniserver_response": response.value
-        }))
+            Ok(json!({
+                "unit": name,
+                "uuid": uuid,
+                "action": mode.as_str(),
+                "command_sent": command,
+                "previous_status": previous,
+                "status": "executed",
+                "miniserver_response": response.value
+            }))
+        })
+        .await
     }
 
     // ========================================================================
-    // CAMERA TOOLS
+    // SCENE/MOOD TOOLS
     // ========================================================================
 
-    /// Get camera/intercom status
+    /// Activate a scene or mood
     ///
-    /// Returns list of cameras and video intercoms
-    pub async fn get_camera_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+    /// Trigger predefined scenes (moods) for rooms or the whole house
+    pub async fn activate_scene(
+        &self,
+        scene: String,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("activate_scene", async move {
+            self.ensure_connected()?;
 
-        let mut camera_uuids = Vec::new();
-        let mut camera_info = Vec::new();
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            let scene_types = &["LightController", "MoodSwitch"];
 
-            if matches!(control_type, "Intercom" | "Camera" | "Doorbell")
-                || control_type.contains("Camera")
-            {
-                camera_uuids.push(uuid.clone());
-                camera_info.push((uuid.clone(), control.clone()));
+            // If scene looks like a UUID, send command directly
+            if scene.contains('-') && scene.len() > 30 {
+                let command = format!("changeTo/{scene}");
+                let response = client
+                    .send_command(&scene, "on")
+                    .await
+                    .map_err(|e| format!("Failed to activate scene {scene}: {e}"))?;
+                return Ok(json!({
+                    "scene": scene,
+                    "room": room,
+                    "command_sent": command,
+                    "status": "activated",
+                    "miniserver_response": response.value
+                }));
             }
-        }
 
-        let live_states = Self::fetch_live_states(client, &camera_uuids).await;
+            // Search for matching scene controllers
+            let controllers: Vec<(&String, &Value)> = if let Some(ref room_name) = room {
+                if let Some(room_uuid) = Self::resolve_room_uuid(&structure, room_name) {
+                    Self::find_controls_by_type_in_room(&structure, &room_uuid, scene_types)
+                } else {
+                    // Try matching by name
+                    structure
+                        .controls
+                        .iter()
+                        .filter(|(_, control)| {
+                            let ct = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                            let name = control
+                                .get("name")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("")
+                                .to_lowercase();
+                            scene_types.contains(&ct) && name.contains(&room_name.to_lowercase())
+                        })
+                        .collect()
+                }
+            } else {
+                Self::find_controls_by_type(&structure, scene_types)
+            };
+
+            if controllers.is_empty() {
+                return Err(format!(
+                    "No scene controllers found{}",
+                    room.as_ref()
+                        .map(|r| format!(" in room '{r}'"))
+                        .unwrap_or_default()
+                ));
+            }
 
-        let cameras: Vec<Value> = camera_info
-            .iter()
-            .map(|(uuid, control)| {
+            // Try to match the scene name to a mood ID, or use the scene value directly
+            let scene_lower = scene.to_lowercase();
+            let mut results = Vec::new();
+            for (uuid, control) in &controllers {
                 let name = control
                     .get("name")
                     .and_then(|v| v.as_str())
                     .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
-                })
-            })
-            .collect();
+                // Check if there are moods defined that match the scene name
+                let mood_id = if let Some(moods) = control.get("moods") {
+                    if let Some(moods_obj) = moods.as_object() {
+                        moods_obj
+                            .iter()
+                            .find(|(_, v)| {
+                                v.as_str()
+                                    .map(|s| s.to_lowercase().contains(&scene_lower))
+                                    .unwrap_or(false)
+                            })
+                            .map(|(id, _)| id.clone())
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
 
-        Ok(json!({
-            "cameras": cameras,
-            "count": cameras.len()
-        }))
-    }
+                let command = if let Some(ref id) = mood_id {
+                    format!("changeTo/{id}")
+                } else {
+                    // Try the scene string as a direct command (could be a mood number)
+                    format!("changeTo/{scene}")
+                };
+
+                match client.send_command(uuid, &command).await {
+                    Ok(response) => {
+                        results.push(json!({
+                            "uuid": uuid,
+                            "name": name,
+                            "command_sent": command,
+                            "mood_id": mood_id,
+                            "status": "activated",
+                            "miniserver_response": response.value
+                        }));
+                    }
+                    Err(e) => {
+                        results.push(json!({
+                            "uuid": uuid,
+                            "name": name,
+                            "command_sent": command,
+                            "status": "error",
+                            "error": format!("{e}")
+                        }));
+                    }
+                }
+            }
 
-    // ========================================================================
-    // INTERCOM TOOLS
-    // ========================================================================
+            Ok(json!({
+                "scene": scene,
+                "room": room,
+                "controllers_affected": results.len(),
+                "results": results
+            }))
+        })
+        .await
+    }
 
-    /// Answer or control intercom
+    /// Switch off a room
     ///
-    /// Answer calls, open doors, or control intercom features
-    pub async fn control_intercom(
+    /// Turns off all lights and audio zones in the room at once and, with
+    /// blinds_down=true, closes its blinds. Reports the result per device
+    pub async fn room_all_off(
         &self,
-        intercom: String,
-        action: String,
+        room: String,
+        blinds_down: Option<bool>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        self.run_tool("room_all_off", async move {
+            self.ensure_connected()?;
 
-        let normalized_action = match action.to_lowercase().as_str() {
-            "answer" | "annehmen" | "abheben" => "answer",
-            "hangup" | "auflegen" | "beenden" => "hangup",
-            "open" | "öffnen" | "tür" => "open_door",
-            "talk" | "sprechen" => "talk",
-            "mute" | "stumm" => "mute",
-            _ => {
-                return Err(format!(
-                    "Invalid action '{action}'. Use: answer, hangup, open, talk, mute"
-                ));
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let room_uuid = Self::resolve_room_uuid(&structure, &room)
+                .ok_or_else(|| Self::room_not_found(&structure, &room))?;
+
+            let plan = shortcuts::all_off_plan(
+                &structure.controls,
+                Some(&room_uuid),
+                blinds_down.unwrap_or(false),
+            );
+            if plan.is_empty() {
+                return Err(format!("No lights, blinds or audio zones in room '{room}'"));
             }
-        };
-
-        let client = self.get_client()?;
-
-        // Map intercom actions to Loxone commands
-        let command = match normalized_action {
-            "answer" => "answer",
-            "hangup" => "hangup",
-            "open_door" => "open",
-            "talk" => "talk",
-            "mute" => "mute",
-            _ => normalized_action,
-        };
-
-        let response = client
-            .send_command(&intercom, command)
-            .await
-            .map_err(|e| format!("Failed to control intercom {intercom}: {e}"))?;
-
-        Ok(json!({
-            "intercom": intercom,
-            "action": normalized_action,
-            "command_sent": command,
-            "status": "executed",
-            "miniserver_response": response.value
-        }))
+            let mut report = Self::run_shortcut(client, &structure, plan).await?;
+            report["room"] = json!(room);
+            Ok(report)
+        })
+        .await
     }
 
-    /// Get intercom call history
-    pub async fn get_intercom_history(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// Good night: everything off, blinds down
+    ///
+    /// Turns off all lights and audio zones in the house and closes all
+    /// blinds at once. Reports the result per device
+    pub async fn house_good_night(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("house_good_night", async move {
+            self.ensure_connected()?;
+
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-        Ok(json!({
-            "history": [],
-            "message": "Call history requires active connection to Loxone"
-        }))
+            let plan = shortcuts::all_off_plan(&structure.controls, None, true);
+            if plan.is_empty() {
+                return Err("No lights, blinds or audio zones in the house".to_string());
+            }
+            Self::run_shortcut(client, &structure, plan).await
+        })
+        .await
     }
 
-    // ========================================================================
-    // SCENE/MOOD TOOLS
-    // ========================================================================
+    /// List available scenes
+    pub async fn list_scenes(&self) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_scenes", async move {
+            self.ensure_connected()?;
 
-    /// Activate a scene or mood
-    ///
-    /// Trigger predefined scenes (moods) for rooms or the whole house
-    pub async fn activate_scene(
-        &self,
-        scene: String,
-        room: Option<String>,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let mut scenes = Vec::new();
 
-        let scene_types = &["LightController", "MoodSwitch"];
+            for (uuid, control) in &structure.controls {
+                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
 
-        // If scene looks like a UUID, send command directly
-        if scene.contains('-') && scene.len() > 30 {
-            let command = format!("changeTo/{scene}");
-            let response = client
-                .send_command(&scene, "on")
-                .await
-                .map_err(|e| format!("Failed to activate scene {scene}: {e}"))?;
-            return Ok(json!({
-                "scene": scene,
-                "room": room,
-                "command_sent": command,
-                "status": "activated",
-                "miniserver_response": response.value
-            }));
-        }
+                if matches!(control_type, "LightController" | "MoodSwitch") {
+                    let name = control
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
+                    let room = control
+                        .get("room")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown");
 
-        // Search for matching scene controllers
-        let controllers: Vec<(&String, &Value)> = if let Some(ref room_name) = room {
-            if let Some(room_uuid) = Self::resolve_room_uuid(&structure, room_name) {
-                Self::find_controls_by_type_in_room(&structure, &room_uuid, scene_types)
-            } else {
-                // Try matching by name
-                structure
-                    .controls
-                    .iter()
-                    .filter(|(_, control)| {
-                        let ct = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                        let name = control
-                            .get("name")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("")
-                            .to_lowercase();
-                        scene_types.contains(&ct) && name.contains(&room_name.to_lowercase())
-                    })
-                    .collect()
+                    // Extract moods if available
+                    let moods = control.get("moods").cloned().unwrap_or(json!([]));
+
+                    scenes.push(json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "room": room,
+                        "moods": moods
+                    }));
+                }
             }
-        } else {
-            Self::find_controls_by_type(&structure, scene_types)
-        };
 
-        if controllers.is_empty() {
-            return Err(format!(
-                "No scene controllers found{}",
-                room.as_ref()
-                    .map(|r| format!(" in room '{r}'"))
-                    .unwrap_or_default()
-            ));
-        }
+            Ok(json!({
+                "scene_controllers": scenes,
+                "count": scenes.len()
+            }))
+        })
+        .await
+    }
 
-        // Try to match the scene name to a mood ID, or use the scene value directly
-        let scene_lower = scene.to_lowercase();
-        let mut results = Vec::new();
-        for (uuid, control) in &controllers {
-            let name = control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown");
+    /// List, activate or mix light moods
+    ///
+    /// Works on the moods of LightControllerV2 blocks ("Movie", "Bright",
+    /// "Off", ...). action: "list" (default) reports the moods and which are
+    /// active, "activate" switches to `mood`, "add"/"remove" mix `mood` in or
+    /// out. Limit to a `room` or a `controller` (name or UUID); without
+    /// either, all light controllers are used.
+    pub async fn control_light_moods(
+        &self,
+        action: Option<String>,
+        mood: Option<String>,
+        room: Option<String>,
+        controller: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("control_light_moods", async move {
+            self.ensure_connected()?;
+            let action = match action.as_deref() {
+                Some(action) => MoodAction::parse(action).map_err(|e| e.to_string())?,
+                None => MoodAction::List,
+            };
+            if action != MoodAction::List && mood.is_none() {
+                return Err("A mood is required to activate, add or remove".to_string());
+            }
 
-            // Check if there are moods defined that match the scene name
-            let mood_id = if let Some(moods) = control.get("moods") {
-                if let Some(moods_obj) = moods.as_object() {
-                    moods_obj
-                        .iter()
-                        .find(|(_, v)| {
-                            v.as_str()
-                                .map(|s| s.to_lowercase().contains(&scene_lower))
-                                .unwrap_or(false)
-                        })
-                        .map(|(id, _)| id.clone())
-                } else {
-                    None
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+
+            let controllers: Vec<(&String, &Value)> = match (&controller, &room) {
+                (Some(identifier), _) => {
+                    let (uuid, control) = Self::find_control_by_id_or_name(&structure, identifier)
+                        .filter(|(_, control)| light_moods::is_mood_controller(control))
+                        .ok_or_else(|| format!("Light controller '{identifier}' not found"))?;
+                    vec![(uuid, control)]
+                }
+                (None, Some(room_name)) => {
+                    let room_uuid = Self::resolve_room_uuid(&structure, room_name)
+                        .ok_or_else(|| Self::room_not_found(&structure, room_name))?;
+                    Self::find_controls_by_type_in_room(
+                        &structure,
+                        &room_uuid,
+                        light_moods::MOOD_CONTROLLER_TYPES,
+                    )
+                }
+                (None, None) => {
+                    Self::find_controls_by_type(&structure, light_moods::MOOD_CONTROLLER_TYPES)
                 }
-            } else {
-                None
             };
+            if controllers.is_empty() {
+                return Err(format!(
+                    "No light controllers with moods found{}",
+                    room.as_ref()
+                        .map(|r| format!(" in room '{r}'"))
+                        .unwrap_or_default()
+                ));
+            }
 
-            let command = if let Some(ref id) = mood_id {
-                format!("changeTo/{id}")
-            } else {
-                // Try the scene string as a direct command (could be a mood number)
-                format!("changeTo/{scene}")
+            let state_uuids: Vec<String> = controllers
+                .iter()
+                .flat_map(|(_, control)| {
+                    ["moodList", "activeMoods"]
+                        .into_iter()
+                        .filter_map(|state| light_moods::state_uuid(control, state))
+                        .map(str::to_string)
+                })
+                .collect();
+            let values = client
+                .get_state_values(&state_uuids)
+                .await
+                .map_err(|e| format!("Failed to read mood lists: {e}"))?;
+            let state = |control: &Value, name: &str| {
+                light_moods::state_uuid(control, name)
+                    .and_then(|uuid| values.get(uuid))
+                    .cloned()
+                    .unwrap_or(Value::Null)
             };
 
-            match client.send_command(uuid, &command).await {
-                Ok(response) => {
+            let mut results = Vec::new();
+            for (uuid, control) in &controllers {
+                let name = control
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                let room_name = control
+                    .get("room")
+                    .and_then(|v| v.as_str())
+                    .and_then(|room| structure.rooms.get(room))
+                    .and_then(|room| room.get("name"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                let moods = light_moods::parse_mood_list(&state(control, "moodList"));
+                let active = light_moods::parse_active_moods(&state(control, "activeMoods"));
+
+                let Some(query) = mood.as_deref().filter(|_| action != MoodAction::List) else {
                     results.push(json!({
                         "uuid": uuid,
                         "name": name,
-                        "command_sent": command,
-                        "mood_id": mood_id,
-                        "status": "activated",
-                        "miniserver_response": response.value
+                        "room": room_name,
+                        "moods": moods,
+                        "active_moods": active
                     }));
-                }
-                Err(e) => {
+                    continue;
+                };
+                let Some(selected) = light_moods::find_mood(&moods, query) else {
                     results.push(json!({
                         "uuid": uuid,
                         "name": name,
+                        "room": room_name,
+                        "status": "error",
+                        "error": format!("No mood matching '{query}'"),
+                        "available_moods": moods.iter().map(|m| &m.name).collect::<Vec<_>>()
+                    }));
+                    continue;
+                };
+                let Some(command) = action.command(selected) else {
+                    continue;
+                };
+                match client.send_command(uuid, &command).await {
+                    Ok(response) => results.push(json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "room": room_name,
+                        "mood": selected,
+                        "command_sent": command,
+                        "status": "executed",
+                        "miniserver_response": response.value
+                    })),
+                    Err(e) => results.push(json!({
+                        "uuid": uuid,
+                        "name": name,
+                        "room": room_name,
+                        "mood": selected,
                         "command_sent": command,
                         "status": "error",
                         "error": format!("{e}")
-                    }));
+                    })),
                 }
             }
-        }
-
-        Ok(json!({
-            "scene": scene,
-            "room": room,
-            "controllers_affected": results.len(),
-            "results": results
-        }))
-    }
 
-    /// List available scenes
-    pub async fn list_scenes(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+            if action != MoodAction::List
+                && results.iter().all(|result| result["status"] == "error")
+            {
+                return Err(format!(
+                    "Could not apply mood '{}': {}",
+                    mood.unwrap_or_default(),
+                    results
+                        .iter()
+                        .filter_map(|result| result["error"].as_str())
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                ));
+            }
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            Ok(json!({
+                "action": action.as_str(),
+                "mood": mood,
+                "room": room,
+                "controllers": results,
+                "count": results.len()
+            }))
+        })
+        .await
+    }
 
-        let mut scenes = Vec::new();
+    /// Record the lights, blinds and climate of a room as a named snapshot
+    ///
+    /// A snapshot of the same name is replaced. Restore it later with
+    /// `restore_scene_snapshot`.
+    pub async fn capture_scene_snapshot(
+        &self,
+        room: String,
+        name: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("capture_scene_snapshot", async move {
+            self.ensure_connected()?;
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
+            let room_uuid = Self::resolve_room_uuid(&structure, &room)
+                .ok_or_else(|| Self::room_not_found(&structure, &room))?;
+            let room_name = structure
+                .rooms
+                .get(&room_uuid)
+                .and_then(|r| r.get("name"))
+                .and_then(|v| v.as_str())
+                .unwrap_or(&room)
+                .to_string();
 
-            if matches!(control_type, "LightController" | "MoodSwitch") {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
+            let controls: Vec<_> = structure
+                .controls
+                .iter()
+                .filter(|(_, control)| {
+                    control.get("room").and_then(|v| v.as_str()) == Some(room_uuid.as_str())
+                })
+                .filter_map(|(uuid, control)| {
+                    let (kind, _) = scene_snapshot::snapshot_state(control)?;
+                    Some((uuid, control, kind, scene_snapshot::state_uuid(control)?))
+                })
+                .collect();
+            if controls.is_empty() {
+                return Err(format!(
+                    "No lights, blinds or climate controllers in {room_name}"
+                ));
+            }
+            let state_uuids: Vec<String> = controls
+                .iter()
+                .map(|(_, _, _, state)| state.clone())
+                .collect();
+            let values = client
+                .get_state_values(&state_uuids)
+                .await
+                .map_err(|e| format!("Failed to read device states: {e}"))?;
+
+            let mut devices: Vec<SnapshotDevice> = controls
+                .into_iter()
+                .filter_map(|(uuid, control, kind, state)| {
+                    Some(SnapshotDevice {
+                        uuid: uuid.clone(),
+                        name: control
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("Unknown")
+                            .to_string(),
+                        control_type: control
+                            .get("type")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        kind,
+                        value: values.get(&state)?.clone(),
+                    })
+                })
+                .collect();
+            devices.sort_by(|a, b| (a.kind as u8, &a.name).cmp(&(b.kind as u8, &b.name)));
+            let skipped = state_uuids.len() - devices.len();
+
+            let snapshot = SceneSnapshot {
+                name: name.trim().to_string(),
+                room: room_name,
+                room_uuid,
+                captured_at: chrono::Utc::now(),
+                devices,
+            };
+            let replaced = self
+                .scene_snapshots
+                .save(snapshot.clone())
+                .map_err(|e| format!("Failed to save snapshot: {e}"))?;
+
+            Ok(json!({
+                "snapshot": snapshot,
+                "without_state": skipped,
+                "replaced": replaced.is_some(),
+                "status": "captured"
+            }))
+        })
+        .await
+    }
 
-                // Extract moods if available
-                let moods = control.get("moods").cloned().unwrap_or(json!([]));
+    /// Bring a room back to a snapshot taken with `capture_scene_snapshot`
+    pub async fn restore_scene_snapshot(
+        &self,
+        name: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("restore_scene_snapshot", async move {
+            self.ensure_connected()?;
+
+            let snapshot = self
+                .scene_snapshots
+                .get(&name)
+                .ok_or_else(|| format!("Snapshot '{name}' not found"))?;
+            let client = self.get_client()?;
+            let structure = client
+                .get_structure()
+                .await
+                .map_err(|e| format!("Failed to get structure: {e}"))?;
 
-                scenes.push(json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "room": room,
-                    "moods": moods
+            let mut results = Vec::new();
+            for device in &snapshot.devices {
+                let (status, error) = match device.restore_commands() {
+                    _ if !structure.controls.contains_key(&device.uuid) => {
+                        ("missing", Some("Device no longer exists".to_string()))
+                    }
+                    None => (
+                        "skipped",
+                        Some("Recorded state cannot be restored".to_string()),
+                    ),
+                    Some(commands) => {
+                        let mut error = None;
+                        for command in &commands {
+                            if let Err(e) = client.send_command(&device.uuid, command).await {
+                                error = Some(e.to_string());
+                                break;
+                            }
+                        }
+                        (
+                            if error.is_none() {
+                                "restored"
+                            } else {
+                                "failed"
+                            },
+                            error,
+                        )
+                    }
+                };
+                results.push(json!({
+                    "uuid": device.uuid,
+                    "name": device.name,
+                    "kind": device.kind,
+                    "status": status,
+                    "error": error
                 }));
             }
-        }
+            let restored = results
+                .iter()
+                .filter(|r| r["status"] == json!("restored"))
+                .count();
+
+            Ok(json!({
+                "snapshot": snapshot.name,
+                "room": snapshot.room,
+                "captured_at": snapshot.captured_at,
+                "devices": results,
+                "restored": restored,
+                "status": if restored == results.len() { "restored" } else { "partial" }
+            }))
+        })
+        .await
+    }
 
-        Ok(json!({
-            "scene_controllers": scenes,
-            "count": scenes.len()
-        }))
+    /// List scene snapshots, of one room when given
+    pub async fn list_scene_snapshots(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("list_scene_snapshots", async move {
+            let room_uuid = match &room {
+                Some(name) => {
+                    self.ensure_connected()?;
+                    let structure = self
+                        .get_client()?
+                        .get_structure()
+                        .await
+                        .map_err(|e| format!("Failed to get structure: {e}"))?;
+                    Some(
+                        Self::resolve_room_uuid(&structure, name)
+                            .ok_or_else(|| Self::room_not_found(&structure, name))?,
+                    )
+                }
+                None => None,
+            };
+            let snapshots: Vec<_> = self
+                .scene_snapshots
+                .list(room_uuid.as_deref())
+                .into_iter()
+                .map(|snapshot| {
+                    json!({
+                        "name": snapshot.name,
+                        "room": snapshot.room,
+                        "captured_at": snapshot.captured_at,
+                        "devices": snapshot.devices.len()
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "room": room,
+                "snapshots": snapshots,
+                "count": snapshots.len()
+            }))
+        })
+        .await
+    }
+
+    /// Delete a scene snapshot
+    pub async fn delete_scene_snapshot(
+        &self,
+        name: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.run_tool("delete_scene_snapshot", async move {
+            let removed = self
+                .scene_snapshots
+                .delete(&name)
+                .map_err(|e| format!("Failed to delete snapshot: {e}"))?
+                .ok_or_else(|| format!("Snapshot '{name}' not found"))?;
+
+            Ok(json!({
+                "snapshot": removed.name,
+                "room": removed.room,
+                "status": "deleted"
+            }))
+        })
+        .await
     }
 }
@@ -2,17 +2,76 @@
 //!
 //! This module contains the macro-based MCP server and supporting components.
 
+pub mod access;
+pub mod action_aliases;
+pub mod air_conditioning;
+pub mod air_devices;
+pub mod alarm;
+pub mod audio;
+pub mod batch;
+pub mod blinds;
+pub mod bulk_states;
+pub mod client_sessions;
+pub mod climate_schedule;
+pub mod code_touch;
+pub mod connection_limits;
+pub mod daemon;
+pub mod device_diagnostics;
+pub mod device_index;
+pub mod device_lock;
+pub mod device_macro;
+pub mod device_search;
+pub mod energy_flow;
+pub mod feature_flags;
 pub mod framework_backend;
 pub mod health_check;
+pub mod heating_zones;
+pub mod hot_water;
+pub mod http_gateway;
+pub mod intercom;
+pub mod irrigation;
+pub mod light_color;
+pub mod light_moods;
 pub mod loxone_batch_executor;
 pub mod macro_backend;
 pub mod models;
+pub mod operating_modes;
+pub mod pool;
 pub mod rate_limiter;
 pub mod request_coalescing;
 pub mod request_context;
 pub mod resource_monitor;
 pub mod response_cache;
+pub mod room_comfort;
+pub mod room_suggestions;
+pub mod safety;
 pub mod schema_validation;
+pub mod self_test;
+pub mod session_backend;
+pub mod shortcuts;
+pub mod standby;
+pub mod state_confirmation;
+pub mod state_diff;
+pub mod status_page;
+pub mod storm_protection;
+pub mod systemd;
+pub mod timers;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod tool_descriptions;
+pub mod tool_middleware;
+pub mod tool_registry;
+pub mod tool_response;
+pub mod tool_timeouts;
+pub mod topology;
+pub mod tracker;
+#[cfg(all(feature = "websocket", feature = "crypto-openssl"))]
+pub mod tunnel;
+pub mod ventilation;
+pub mod virtual_inputs;
+pub mod virtual_outputs;
+pub mod wallbox;
+pub mod weather_forecast;
 
 // Legacy MCP Resources enabled for weather storage integration
 pub mod resources;
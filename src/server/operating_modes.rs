@@ -0,0 +1,221 @@
+//! House-wide operating modes (vacation, party, standby)
+//!
+//! The structure file's `operatingModes` section names the Miniserver's
+//! operating modes by id (e.g. `{"3": "Urlaub", "5": "Party"}`); the active
+//! ones are reported by the `operatingMode` global state. Modes are switched
+//! by calendar entries:
+//!
+//! - `calendargetentries`: all entries as a JSON list with `uuid`, `name`,
+//!   `operatingMode` and `calMode`
+//! - `calendarcreateentry/<name>/<modeId>/3/<start>/<end>`: time span entry
+//!   (calendar mode 3) active from `<start>` to `<end>`, both `dd.mm.yyyy`
+//! - `calendardeleteentry/<uuid>`
+//!
+//! Entries created by this server carry [`ENTRY_NAME_PREFIX`] so they can be
+//! cleared again without touching the entries configured in Loxone Config.
+
+use crate::error::{LoxoneError, Result};
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Prefix of calendar entries created by this server
+pub const ENTRY_NAME_PREFIX: &str = "MCP_";
+
+/// Calendar mode of entries spanning a date range
+const CAL_MODE_TIME_SPAN: u32 = 3;
+
+/// Names Loxone installations use for the well-known modes
+const MODE_ALIASES: &[(&str, &[&str])] = &[
+    ("vacation", &["vacation", "holiday", "urlaub", "ferien"]),
+    ("party", &["party", "fest"]),
+    ("standby", &["standby", "away", "abwesend", "ruhe"]),
+];
+
+/// Operating mode defined on the Miniserver
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OperatingMode {
+    pub id: u32,
+    pub name: String,
+}
+
+/// Operating modes of the structure's `operatingModes` section, by id
+pub fn operating_modes(section: &HashMap<String, Value>) -> Vec<OperatingMode> {
+    let mut modes: Vec<OperatingMode> = section
+        .iter()
+        .filter_map(|(id, name)| {
+            Some(OperatingMode {
+                id: id.parse().ok()?,
+                name: name.as_str()?.to_string(),
+            })
+        })
+        .collect();
+    modes.sort_by_key(|m| m.id);
+    modes
+}
+
+/// Find a mode by id, name, or well-known alias (`vacation`, `party`,
+/// `standby`)
+pub fn find_mode<'a>(modes: &'a [OperatingMode], mode: &str) -> Result<&'a OperatingMode> {
+    let lower = mode.trim().to_lowercase();
+    let aliases = MODE_ALIASES
+        .iter()
+        .find(|(kind, names)| *kind == lower || names.contains(&lower.as_str()))
+        .map_or(&[][..], |(_, names)| *names);
+
+    modes
+        .iter()
+        .find(|m| m.id.to_string() == lower || m.name.to_lowercase() == lower)
+        .or_else(|| {
+            modes.iter().find(|m| {
+                let name = m.name.to_lowercase();
+                aliases.iter().any(|alias| name.contains(alias))
+            })
+        })
+        .ok_or_else(|| {
+            let names: Vec<&str> = modes.iter().map(|m| m.name.as_str()).collect();
+            LoxoneError::not_found(format!(
+                "Operating mode '{mode}' not found. Available: {}",
+                names.join(", ")
+            ))
+        })
+}
+
+/// Parse a `YYYY-MM-DD` date
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| LoxoneError::invalid_input(format!("Invalid date '{date}'. Use YYYY-MM-DD")))
+}
+
+/// Name of the calendar entry this server uses for `mode`
+pub fn entry_name(mode: &OperatingMode) -> String {
+    let name: String = mode
+        .name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    format!("{ENTRY_NAME_PREFIX}{name}{}", mode.id)
+}
+
+/// Command creating a calendar entry activating `mode` from `from` to
+/// `until` (both inclusive)
+pub fn create_entry_command(
+    mode: &OperatingMode,
+    from: NaiveDate,
+    until: NaiveDate,
+) -> Result<String> {
+    if until < from {
+        return Err(LoxoneError::invalid_input(format!(
+            "End date {until} is before start date {from}"
+        )));
+    }
+    Ok(format!(
+        "calendarcreateentry/{}/{}/{CAL_MODE_TIME_SPAN}/{}/{}",
+        entry_name(mode),
+        mode.id,
+        from.format("%d.%m.%Y"),
+        until.format("%d.%m.%Y")
+    ))
+}
+
+/// Command deleting a calendar entry
+pub fn delete_entry_command(uuid: &str) -> String {
+    format!("calendardeleteentry/{uuid}")
+}
+
+/// Calendar entry as listed by `calendargetentries`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CalendarEntry {
+    pub uuid: String,
+    pub name: String,
+    pub operating_mode: u32,
+}
+
+impl CalendarEntry {
+    /// Whether the entry was created by this server
+    pub fn is_managed(&self) -> bool {
+        self.name.starts_with(ENTRY_NAME_PREFIX)
+    }
+}
+
+/// Entries of a `calendargetentries` response value (a JSON list, possibly
+/// encoded as a string)
+pub fn parse_entries(value: &Value) -> Vec<CalendarEntry> {
+    let entries = match value {
+        Value::String(text) => serde_json::from_str(text).unwrap_or(Value::Null),
+        other => other.clone(),
+    };
+    entries
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            Some(CalendarEntry {
+                uuid: entry.get("uuid")?.as_str()?.to_string(),
+                name: entry
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                operating_mode: entry.get("operatingMode")?.as_u64()? as u32,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn modes() -> Vec<OperatingMode> {
+        let section = HashMap::from([
+            ("3".to_string(), json!("Urlaub")),
+            ("5".to_string(), json!("Party")),
+            ("8".to_string(), json!("Standby")),
+            ("x".to_string(), json!("Broken")),
+        ]);
+        operating_modes(&section)
+    }
+
+    #[test]
+    fn test_find_mode() {
+        let modes = modes();
+        assert_eq!(modes.len(), 3);
+        assert_eq!(find_mode(&modes, "vacation").unwrap().id, 3);
+        assert_eq!(find_mode(&modes, "PARTY").unwrap().id, 5);
+        assert_eq!(find_mode(&modes, "8").unwrap().name, "Standby");
+        let err = find_mode(&modes, "christmas").unwrap_err().to_string();
+        assert!(err.contains("Urlaub, Party, Standby"));
+    }
+
+    #[test]
+    fn test_entry_commands() {
+        let modes = modes();
+        let vacation = find_mode(&modes, "vacation").unwrap();
+        let from = parse_date("2026-10-16").unwrap();
+        let until = parse_date("2026-10-18").unwrap();
+        assert_eq!(
+            create_entry_command(vacation, from, until).unwrap(),
+            "calendarcreateentry/MCP_Urlaub3/3/3/16.10.2026/18.10.2026"
+        );
+        assert!(create_entry_command(vacation, until, from).is_err());
+        assert!(parse_date("18.10.2026").is_err());
+        assert_eq!(delete_entry_command("e-1"), "calendardeleteentry/e-1");
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let value = json!(
+            r#"[{"uuid":"e-1","name":"MCP_Urlaub3","operatingMode":3,"calMode":3},
+                {"uuid":"e-2","name":"Christmas","operatingMode":3,"calMode":0},
+                {"name":"no uuid","operatingMode":5}]"#
+        );
+        let entries = parse_entries(&value);
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_managed());
+        assert!(!entries[1].is_managed());
+        assert!(parse_entries(&json!("not json")).is_empty());
+    }
+}
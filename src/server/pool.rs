@@ -0,0 +1,229 @@
+//! Pool controller handling
+//!
+//! Loxone exposes pool technology as a `PoolController` control. Its
+//! `states` map names to state UUIDs:
+//!
+//! - `currentTempIn` / `currentTempOut`: water temperature before and after
+//!   the heat exchanger in °C
+//! - `targetTemp`: target water temperature
+//! - `pumpActive`: 1 while the filtration pump runs
+//! - `currentOpMode`: 0 out of service, 1 automatic, 2 service, 3 backwash
+//! - `coverPosition`: 0.0 (open) to 1.0 (closed)
+//! - `coverMovement`: -1 opening, 0 not moving, 1 closing
+//!
+//! Closing the cover while someone is in the water is dangerous, so
+//! `control_pool` asks the consent manager before sending `cover_close`.
+
+use crate::error::{LoxoneError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as pool controllers
+pub const POOL_CONTROL_TYPES: &[&str] = &["PoolController", "Pool"];
+
+/// State names read for a pool status
+pub const POOL_STATE_NAMES: &[&str] = &[
+    "currentTempIn",
+    "currentTempOut",
+    "targetTemp",
+    "pumpActive",
+    "currentOpMode",
+    "coverPosition",
+    "coverMovement",
+];
+
+/// Accepted range of the target water temperature in °C
+pub const TARGET_TEMP_RANGE: (f64, f64) = (10.0, 40.0);
+
+/// Action requested for a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolAction {
+    /// Start a filtration cycle
+    PumpOn,
+    /// Stop the filtration pump
+    PumpOff,
+    /// Rinse the filter by reversing the flow
+    Backwash,
+    CoverOpen,
+    CoverClose,
+    CoverStop,
+}
+
+impl PoolAction {
+    /// Parse an action name (English or German)
+    pub fn parse(action: &str) -> Result<Self> {
+        match action.trim().to_lowercase().as_str() {
+            "pump_on" | "filter" | "filter_on" | "pumpe_an" => Ok(Self::PumpOn),
+            "pump_off" | "filter_off" | "pumpe_aus" => Ok(Self::PumpOff),
+            "backwash" | "rückspülen" | "rueckspuelen" => Ok(Self::Backwash),
+            "cover_open" | "abdeckung_auf" => Ok(Self::CoverOpen),
+            "cover_close" | "abdeckung_zu" => Ok(Self::CoverClose),
+            "cover_stop" | "abdeckung_stopp" => Ok(Self::CoverStop),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid pool action '{other}'. Use: pump_on, pump_off, backwash, cover_open, cover_close, cover_stop"
+            ))),
+        }
+    }
+
+    /// Miniserver command for the action
+    pub fn command(self) -> &'static str {
+        match self {
+            Self::PumpOn => "pump/1",
+            Self::PumpOff => "pump/0",
+            Self::Backwash => "backwash",
+            Self::CoverOpen => "coverOpen",
+            Self::CoverClose => "coverClose",
+            Self::CoverStop => "coverStop",
+        }
+    }
+
+    /// Name used in tool output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PumpOn => "pump_on",
+            Self::PumpOff => "pump_off",
+            Self::Backwash => "backwash",
+            Self::CoverOpen => "cover_open",
+            Self::CoverClose => "cover_close",
+            Self::CoverStop => "cover_stop",
+        }
+    }
+
+    /// Whether the action closes the cover and therefore needs consent
+    pub fn requires_consent(self) -> bool {
+        self == Self::CoverClose
+    }
+}
+
+/// Command setting the target water temperature
+pub fn target_temp_command(temperature: f64) -> Result<String> {
+    let (min, max) = TARGET_TEMP_RANGE;
+    if !(min..=max).contains(&temperature) {
+        return Err(LoxoneError::invalid_input(format!(
+            "Pool temperature must be between {min}°C and {max}°C"
+        )));
+    }
+    Ok(format!("targetTemp/{temperature}"))
+}
+
+/// Snapshot of a pool's state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolStatus {
+    pub water_temperature: Option<f64>,
+    /// Water temperature after the heat exchanger
+    pub return_temperature: Option<f64>,
+    pub target_temperature: Option<f64>,
+    pub pump_active: bool,
+    /// `out_of_service`, `automatic`, `service`, `backwash` or `unknown`
+    pub operating_mode: &'static str,
+    /// Cover closed in percent (0 = open, 100 = closed)
+    pub cover_closed_percent: Option<f64>,
+    /// `opening`, `closing` or `stopped`
+    pub cover_movement: &'static str,
+}
+
+impl PoolStatus {
+    /// Build the status from state values keyed by state name
+    pub fn from_state_values(values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+
+        Self {
+            water_temperature: number("currentTempIn"),
+            return_temperature: number("currentTempOut"),
+            target_temperature: number("targetTemp"),
+            pump_active: number("pumpActive").is_some_and(|v| v != 0.0),
+            operating_mode: match number("currentOpMode").map(|m| m as i64) {
+                Some(0) => "out_of_service",
+                Some(1) => "automatic",
+                Some(2) => "service",
+                Some(3) => "backwash",
+                _ => "unknown",
+            },
+            cover_closed_percent: number("coverPosition")
+                .map(|p| (p.clamp(0.0, 1.0) * 1000.0).round() / 10.0),
+            cover_movement: match number("coverMovement") {
+                Some(m) if m < 0.0 => "opening",
+                Some(m) if m > 0.0 => "closing",
+                _ => "stopped",
+            },
+        }
+    }
+}
+
+/// State UUIDs of a pool control, keyed by state name
+pub fn pool_state_uuids(control: &Value) -> HashMap<String, String> {
+    POOL_STATE_NAMES
+        .iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(*name))
+                .and_then(Value::as_str)
+                .map(|uuid| ((*name).to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(PoolAction::parse("Filter").unwrap(), PoolAction::PumpOn);
+        assert_eq!(PoolAction::parse("pump_off").unwrap().command(), "pump/0");
+        assert_eq!(
+            PoolAction::parse("abdeckung_zu").unwrap(),
+            PoolAction::CoverClose
+        );
+        assert!(PoolAction::parse("dive").is_err());
+
+        assert!(PoolAction::CoverClose.requires_consent());
+        assert!(!PoolAction::CoverOpen.requires_consent());
+        assert!(!PoolAction::PumpOn.requires_consent());
+
+        assert_eq!(target_temp_command(27.5).unwrap(), "targetTemp/27.5");
+        assert!(target_temp_command(45.0).is_err());
+    }
+
+    #[test]
+    fn test_pool_status() {
+        let status = PoolStatus::from_state_values(&values(&[
+            ("currentTempIn", json!(24.3)),
+            ("targetTemp", json!("28")),
+            ("pumpActive", json!(1)),
+            ("currentOpMode", json!(1)),
+            ("coverPosition", json!(0.456)),
+            ("coverMovement", json!(1)),
+        ]));
+        assert_eq!(status.water_temperature, Some(24.3));
+        assert_eq!(status.target_temperature, Some(28.0));
+        assert!(status.pump_active);
+        assert_eq!(status.operating_mode, "automatic");
+        assert_eq!(status.cover_closed_percent, Some(45.6));
+        assert_eq!(status.cover_movement, "closing");
+
+        let empty = PoolStatus::from_state_values(&HashMap::new());
+        assert_eq!(empty.operating_mode, "unknown");
+        assert!(!empty.pump_active);
+    }
+}
@@ -3,6 +3,7 @@
 //! This module provides configurable rate limiting based on client IP,
 //! user agent, and request patterns to prevent abuse and ensure fair usage.
 
+use crate::utils::clock::{SharedClock, system_clock};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -53,8 +54,7 @@ struct RateLimitBucket {
 }
 
 impl RateLimitBucket {
-    fn new() -> Self {
-        let now = Instant::now();
+    fn new(now: Instant) -> Self {
         Self {
             request_count: 0,
             burst_used: 0,
@@ -79,6 +79,7 @@ pub struct RateLimiter {
     config: RateLimitConfig,
     buckets: Arc<RwLock<HashMap<String, RateLimitBucket>>>,
     last_cleanup: Arc<RwLock<Instant>>,
+    clock: SharedClock,
 }
 
 impl RateLimiter {
@@ -93,12 +94,20 @@ impl RateLimiter {
             config,
             buckets: Arc::new(RwLock::new(HashMap::new())),
             last_cleanup: Arc::new(RwLock::new(Instant::now())),
+            clock: system_clock(),
         }
     }
 
+    /// Measure windows on `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.last_cleanup = Arc::new(RwLock::new(clock.now()));
+        self.clock = clock;
+        self
+    }
+
     /// Check if a request should be allowed
     pub async fn check_request(&self, client_id: &str) -> RateLimitResult {
-        let now = Instant::now();
+        let now = self.clock.now();
 
         // Check if cleanup is needed
         self.maybe_cleanup(now).await;
@@ -106,7 +115,7 @@ impl RateLimiter {
         let mut buckets = self.buckets.write().await;
         let bucket = buckets
             .entry(client_id.to_string())
-            .or_insert_with(RateLimitBucket::new);
+            .or_insert_with(|| RateLimitBucket::new(now));
 
         // Reset window if expired
         if bucket.is_window_expired(now, self.config.window_duration) {
@@ -153,7 +162,7 @@ impl RateLimiter {
         let buckets = self.buckets.read().await;
         let bucket = buckets.get(client_id)?;
 
-        let now = Instant::now();
+        let now = self.clock.now();
         if bucket.is_window_expired(now, self.config.window_duration) {
             return None; // Expired bucket
         }
@@ -172,7 +181,7 @@ impl RateLimiter {
     /// Get rate limiter statistics
     pub async fn get_statistics(&self) -> RateLimiterStats {
         let buckets = self.buckets.read().await;
-        let now = Instant::now();
+        let now = self.clock.now();
 
         let mut active_clients = 0;
         let mut total_requests = 0;
@@ -15,6 +15,10 @@
 //! - `loxone://system/status` - System status
 //! - `loxone://system/capabilities` - System capabilities
 //! - `loxone://system/categories` - Category overview
+//! - `loxone://system/device-index` - Compact UUID ↔ name ↔ room ↔ type index
+//! - `loxone://system/clients` - Connected MCP client sessions (admin only)
+//! - `loxone://system/sampling-usage` - Sampling tokens, costs and budgets per client and day
+//! - `loxone://system/topology` - Extensions, Tree branches and Air devices with online status
 //! - `loxone://audio/zones` - Audio zones
 //! - `loxone://audio/sources` - Audio sources
 //! - `loxone://sensors/door-window` - Door/window sensors
@@ -28,11 +32,20 @@
 //! - `loxone://weather/outdoor-conditions` - Outdoor conditions with comfort assessment
 //! - `loxone://weather/forecast-daily` - Daily weather forecast
 //! - `loxone://weather/forecast-hourly` - Hourly weather forecast
+//! - `loxone://weather/forecast` - Weather Service forecast, hourly and daily
 //! - `loxone://security/status` - Security system status
 //! - `loxone://security/zones` - Security zones
+//! - `loxone://safety/status` - Smoke alarms and water leak detectors
+//! - `loxone://intercom/events` - Latest doorbell rings per intercom
 //! - `loxone://energy/consumption` - Energy consumption data
 //! - `loxone://energy/meters` - Energy meters
 //! - `loxone://energy/usage-history` - Historical energy usage
+//! - `loxone://energy/wallbox` - Current charging session per EV charger
+//! - `loxone://energy/flow` - Current grid, solar, battery and house power flow
+//! - `loxone://reports/presence` - Hour-of-day presence heatmap per room
+//! - `loxone://reports/device-usage` - Switch counts and on-hours per lamp and relay
+//! - `loxone://history/commands` - Audit log of device commands (filterable)
+//! - `loxone://backups/restore` - Program backups and restore instructions
 //!
 //! Note: For room-specific or device-type-specific queries, use the appropriate tools instead.
 
@@ -151,10 +164,20 @@ pub enum ResourceCategory {
     Weather,
     /// Security system resources
     Security,
+    /// Smoke alarm and water leak resources
+    Safety,
+    /// Intercom and doorbell resources
+    Intercom,
     /// Energy consumption resources
     Energy,
     /// Climate control resources
     Climate,
+    /// Generated report resources
+    Reports,
+    /// Audit history resources
+    History,
+    /// Miniserver program backups
+    Backups,
 }
 
 impl ResourceCategory {
@@ -168,8 +191,13 @@ impl ResourceCategory {
             ResourceCategory::Sensors => "loxone://sensors",
             ResourceCategory::Weather => "loxone://weather",
             ResourceCategory::Security => "loxone://security",
+            ResourceCategory::Safety => "loxone://safety",
+            ResourceCategory::Intercom => "loxone://intercom",
             ResourceCategory::Energy => "loxone://energy",
             ResourceCategory::Climate => "loxone://climate",
+            ResourceCategory::Reports => "loxone://reports",
+            ResourceCategory::History => "loxone://history",
+            ResourceCategory::Backups => "loxone://backups",
         }
     }
 
@@ -183,8 +211,13 @@ impl ResourceCategory {
             ResourceCategory::Sensors => "Sensors",
             ResourceCategory::Weather => "Weather",
             ResourceCategory::Security => "Security",
+            ResourceCategory::Safety => "Safety",
+            ResourceCategory::Intercom => "Intercom",
             ResourceCategory::Energy => "Energy",
             ResourceCategory::Climate => "Climate",
+            ResourceCategory::Reports => "Reports",
+            ResourceCategory::History => "History",
+            ResourceCategory::Backups => "Backups",
         }
     }
 }
@@ -310,6 +343,52 @@ impl ResourceManager {
             ResourceCategory::System,
         );
 
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://system/device-index".to_string(),
+                name: "System Device Index".to_string(),
+                description:
+                    "Compact UUID to name, room and type index for grounding device references"
+                        .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::System,
+        );
+
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://system/clients".to_string(),
+                name: "Connected Clients".to_string(),
+                description: "Connected MCP client sessions with transport, capabilities and last activity (admin only)"
+                    .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::System,
+        );
+
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://system/sampling-usage".to_string(),
+                name: "Sampling Usage".to_string(),
+                description: "Sampling tokens and costs today, in total and per client, against the configured budgets"
+                    .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::System,
+        );
+
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://system/topology".to_string(),
+                name: "System Topology".to_string(),
+                description:
+                    "Extensions, Tree branches and Air devices with their online/offline status"
+                        .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::System,
+        );
+
         // Audio resources
         self.register_resource(
             LoxoneResource {
@@ -447,6 +526,18 @@ impl ResourceManager {
             ResourceCategory::Weather,
         );
 
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://weather/forecast".to_string(),
+                name: "Weather Service Forecast".to_string(),
+                description:
+                    "Hourly and daily forecast of the Loxone Weather Service (license required)"
+                        .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Weather,
+        );
+
         // Security resources
         self.register_resource(
             LoxoneResource {
@@ -468,6 +559,29 @@ impl ResourceManager {
             ResourceCategory::Security,
         );
 
+        // Safety resources
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://safety/status".to_string(),
+                name: "Life-Safety Status".to_string(),
+                description: "Smoke alarms and water leak detectors with alarm state and cause"
+                    .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Safety,
+        );
+
+        // Intercom resources
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://intercom/events".to_string(),
+                name: "Doorbell Events".to_string(),
+                description: "Latest doorbell rings per intercom, newest first".to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Intercom,
+        );
+
         // Energy resources
         self.register_resource(
             LoxoneResource {
@@ -499,6 +613,29 @@ impl ResourceManager {
             ResourceCategory::Energy,
         );
 
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://energy/wallbox".to_string(),
+                name: "EV Chargers".to_string(),
+                description:
+                    "Current charging session, power, current limit and past sessions per wallbox"
+                        .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Energy,
+        );
+
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://energy/flow".to_string(),
+                name: "Energy Flow".to_string(),
+                description: "Current solar production, consumption, grid import/export and battery state of charge"
+                    .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Energy,
+        );
+
         // Additional resources for tools that were converted from read-only tools
 
         // Room-specific resources
@@ -555,6 +692,51 @@ impl ResourceManager {
             ResourceCategory::Climate,
         );
 
+        // Report resources
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://reports/presence".to_string(),
+                name: "Presence Heatmap".to_string(),
+                description:
+                    "Hour-of-day presence heatmap per room from motion history, regenerated nightly"
+                        .to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Reports,
+        );
+
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://reports/device-usage".to_string(),
+                name: "Device Usage".to_string(),
+                description: "Switch counts and on-hours per lamp and relay, flagging lamps nearing their rated lifetime and relays with unusually high cycling".to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Reports,
+        );
+
+        // History resources
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://history/commands".to_string(),
+                name: "Command History".to_string(),
+                description: "Device commands sent to the Miniserver with tool, API key and result; filter with ?device=&tool=&key=&since=&success=&limit=".to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::History,
+        );
+
+        // Backup resources
+        self.register_resource(
+            LoxoneResource {
+                uri: "loxone://backups/restore".to_string(),
+                name: "Program Backups".to_string(),
+                description: "Encrypted Miniserver program backups, last backup run and step-by-step restore instructions".to_string(),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceCategory::Backups,
+        );
+
         // Note: LLM-focused resources could be added here in future versions
     }
 
@@ -721,6 +903,7 @@ impl ResourceManager {
             let category = path_parts[0];
             let valid_categories = [
                 "rooms", "devices", "system", "audio", "sensors", "weather", "security", "energy",
+                "reports", "intercom", "safety",
             ];
             if !valid_categories.contains(&category) {
                 return Err(LoxoneError::invalid_input(format!(
@@ -1007,6 +1190,7 @@ impl ResourceManager {
             "irrigation",
             "ventilation",
             "access",
+            "intercom",
         ];
 
         if !valid_categories.contains(&category) {
@@ -1186,19 +1370,28 @@ impl ResourceManager {
             uri if uri.starts_with("loxone://rooms")
                 || uri.starts_with("loxone://devices")
                 || uri == "loxone://system/capabilities"
-                || uri == "loxone://system/categories" =>
+                || uri == "loxone://system/categories"
+                || uri == "loxone://system/device-index" =>
             {
                 Some(600)
             } // 10 minutes
 
             // Dynamic status data - shorter cache
             "loxone://system/status" => Some(60), // 1 minute
+            "loxone://system/topology" => Some(60),
+
+            // Client sessions come and go with every connection
+            "loxone://system/clients" => Some(0),
+            "loxone://system/sampling-usage" => Some(0),
 
             // Audio and sensor data - very short cache
             uri if uri.starts_with("loxone://audio") || uri.starts_with("loxone://sensors") => {
                 Some(30)
             } // 30 seconds
 
+            // The Weather Service updates its forecast hourly
+            "loxone://weather/forecast" => Some(900),
+
             // Weather data - very short cache since it changes frequently
             uri if uri.starts_with("loxone://weather") => Some(30), // 30 seconds
 
@@ -1208,6 +1401,21 @@ impl ResourceManager {
             // Energy data - medium cache for power consumption data
             uri if uri.starts_with("loxone://energy") => Some(60), // 1 minute for energy data
 
+            // Smoke and water alarms must never be served stale
+            uri if uri.starts_with("loxone://safety") => Some(5),
+
+            // Doorbell events should show up right away
+            uri if uri.starts_with("loxone://intercom") => Some(10),
+
+            // Reports are regenerated nightly
+            uri if uri.starts_with("loxone://reports") => Some(3600), // 1 hour
+
+            // Command history changes with every command
+            uri if uri.starts_with("loxone://history") => Some(0),
+
+            // Backups change at most once per scheduled run
+            uri if uri.starts_with("loxone://backups") => Some(300),
+
             _ => Some(120), // Default 2 minutes
         }
     }
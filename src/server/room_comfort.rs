@@ -0,0 +1,285 @@
+//! Room comfort score
+//!
+//! Backs the `get_room_comfort` tool. The readings of a room's temperature,
+//! humidity, CO2 and brightness sensors, as parsed by the
+//! [`crate::services::UnifiedValueResolver`], are each scored from 0 to 100
+//! against a comfort band:
+//!
+//! - temperature 20-23 °C, 20 points lost per degree outside
+//! - relative humidity 40-60 %, 2.5 points lost per percent outside
+//! - CO2 up to 800 ppm, falling to 0 at 1800 ppm
+//! - brightness from 150 lx, falling to 0 in the dark
+//!
+//! The room score is the weighted average of the metrics the room has
+//! sensors for; brightness weighs least as a dark room is fine at night.
+//! Several sensors of one kind in a room are averaged.
+
+use crate::services::sensor_registry::{AirQualityScale, SensorType, TemperatureUnit};
+use serde::Serialize;
+
+/// Score from which a room counts as comfortable
+pub const GOOD_SCORE: f64 = 80.0;
+
+/// Score below which a room counts as uncomfortable
+pub const POOR_SCORE: f64 = 60.0;
+
+const TEMPERATURE_BAND: (f64, f64) = (20.0, 23.0);
+const HUMIDITY_BAND: (f64, f64) = (40.0, 60.0);
+const CO2_GOOD_PPM: f64 = 800.0;
+const CO2_WORST_PPM: f64 = 1800.0;
+const BRIGHTNESS_GOOD_LUX: f64 = 150.0;
+
+/// What a sensor measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Temperature,
+    Humidity,
+    Co2,
+    Brightness,
+}
+
+impl Metric {
+    /// Metric of a detected sensor type, with the factor and offset that
+    /// convert its values to °C, %, ppm or lx
+    pub fn of(sensor_type: &SensorType) -> Option<(Self, f64, f64)> {
+        match sensor_type {
+            SensorType::Temperature { unit, .. } => Some(match unit {
+                TemperatureUnit::Celsius => (Self::Temperature, 1.0, 0.0),
+                TemperatureUnit::Fahrenheit => (Self::Temperature, 5.0 / 9.0, -32.0 * 5.0 / 9.0),
+                TemperatureUnit::Kelvin => (Self::Temperature, 1.0, -273.15),
+            }),
+            SensorType::TemperatureSimple => Some((Self::Temperature, 1.0, 0.0)),
+            SensorType::Humidity { .. } | SensorType::HumiditySimple => {
+                Some((Self::Humidity, 1.0, 0.0))
+            }
+            SensorType::AirQuality {
+                scale: AirQualityScale::CO2PPM,
+            } => Some((Self::Co2, 1.0, 0.0)),
+            SensorType::Illuminance { .. } | SensorType::Light => {
+                Some((Self::Brightness, 1.0, 0.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Unit of normalized values
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Temperature => "°C",
+            Self::Humidity => "%",
+            Self::Co2 => "ppm",
+            Self::Brightness => "lx",
+        }
+    }
+
+    fn weight(self) -> f64 {
+        match self {
+            Self::Temperature => 0.35,
+            Self::Humidity => 0.2,
+            Self::Co2 => 0.3,
+            Self::Brightness => 0.15,
+        }
+    }
+
+    /// Score of a normalized value and the reason it is not perfect
+    fn score(self, value: f64) -> (f64, Option<String>) {
+        let band = |(low, high): (f64, f64), per_unit: f64| {
+            if value < low {
+                100.0 - (low - value) * per_unit
+            } else if value > high {
+                100.0 - (value - high) * per_unit
+            } else {
+                100.0
+            }
+        };
+        let score = match self {
+            Self::Temperature => band(TEMPERATURE_BAND, 20.0),
+            Self::Humidity => band(HUMIDITY_BAND, 2.5),
+            Self::Co2 => {
+                100.0 * (CO2_WORST_PPM - value.max(CO2_GOOD_PPM)) / (CO2_WORST_PPM - CO2_GOOD_PPM)
+            }
+            Self::Brightness => 100.0 * value.min(BRIGHTNESS_GOOD_LUX) / BRIGHTNESS_GOOD_LUX,
+        }
+        .clamp(0.0, 100.0);
+
+        let explanation = match self {
+            Self::Temperature if value < TEMPERATURE_BAND.0 => Some(format!(
+                "Too cold: {value:.1} °C, comfortable is {}-{} °C",
+                TEMPERATURE_BAND.0, TEMPERATURE_BAND.1
+            )),
+            Self::Temperature if value > TEMPERATURE_BAND.1 => Some(format!(
+                "Too warm: {value:.1} °C, comfortable is {}-{} °C",
+                TEMPERATURE_BAND.0, TEMPERATURE_BAND.1
+            )),
+            Self::Humidity if value < HUMIDITY_BAND.0 => Some(format!(
+                "Air too dry: {value:.0} %, comfortable is {}-{} %",
+                HUMIDITY_BAND.0, HUMIDITY_BAND.1
+            )),
+            Self::Humidity if value > HUMIDITY_BAND.1 => Some(format!(
+                "Air too humid: {value:.0} %, comfortable is {}-{} %",
+                HUMIDITY_BAND.0, HUMIDITY_BAND.1
+            )),
+            Self::Co2 if value > CO2_GOOD_PPM => Some(format!(
+                "Stale air: {value:.0} ppm CO2, ventilate below {CO2_GOOD_PPM} ppm"
+            )),
+            Self::Brightness if value < BRIGHTNESS_GOOD_LUX => Some(format!(
+                "Dim: {value:.0} lx, {BRIGHTNESS_GOOD_LUX} lx or more is comfortable"
+            )),
+            _ => None,
+        };
+        (score, explanation)
+    }
+}
+
+/// A normalized reading of one sensor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    pub metric: Metric,
+    pub value: f64,
+}
+
+impl Reading {
+    /// Reading from a resolved sensor value
+    pub fn new(sensor_type: &SensorType, value: f64) -> Option<Self> {
+        let (metric, factor, offset) = Metric::of(sensor_type)?;
+        Some(Self {
+            metric,
+            value: value * factor + offset,
+        })
+    }
+}
+
+/// Score of one metric in a room
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricScore {
+    pub metric: Metric,
+    pub value: f64,
+    pub unit: &'static str,
+    pub sensors: usize,
+    pub score: u8,
+}
+
+/// Comfort of one room
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoomComfort {
+    pub room: String,
+    /// 0-100, `None` without comfort sensors
+    pub score: Option<u8>,
+    /// `good`, `fair`, `poor` or `unknown`
+    pub rating: &'static str,
+    pub metrics: Vec<MetricScore>,
+    /// Why the score is not perfect, worst metric first
+    pub explanations: Vec<String>,
+}
+
+/// Score a room from its readings
+pub fn score_room(room: &str, readings: &[Reading]) -> RoomComfort {
+    let mut metrics = Vec::new();
+    let mut scored: Vec<(f64, String)> = Vec::new();
+    let (mut total, mut weights) = (0.0, 0.0);
+    for metric in [
+        Metric::Temperature,
+        Metric::Humidity,
+        Metric::Co2,
+        Metric::Brightness,
+    ] {
+        let values: Vec<f64> = readings
+            .iter()
+            .filter(|r| r.metric == metric)
+            .map(|r| r.value)
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+        let average = values.iter().sum::<f64>() / values.len() as f64;
+        let (score, explanation) = metric.score(average);
+        total += score * metric.weight();
+        weights += metric.weight();
+        if let Some(explanation) = explanation {
+            scored.push((score, explanation));
+        }
+        metrics.push(MetricScore {
+            metric,
+            value: (average * 10.0).round() / 10.0,
+            unit: metric.unit(),
+            sensors: values.len(),
+            score: score.round() as u8,
+        });
+    }
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let score = (weights > 0.0).then(|| total / weights);
+    RoomComfort {
+        room: room.to_string(),
+        score: score.map(|s| s.round() as u8),
+        rating: match score {
+            Some(s) if s >= GOOD_SCORE => "good",
+            Some(s) if s >= POOR_SCORE => "fair",
+            Some(_) => "poor",
+            None => "unknown",
+        },
+        metrics,
+        explanations: scored.into_iter().map(|(_, text)| text).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sensor_registry::LightUnit;
+
+    fn reading(metric: Metric, value: f64) -> Reading {
+        Reading { metric, value }
+    }
+
+    #[test]
+    fn test_score_room() {
+        let comfortable = score_room(
+            "Living Room",
+            &[
+                reading(Metric::Temperature, 21.5),
+                reading(Metric::Humidity, 45.0),
+                reading(Metric::Co2, 650.0),
+            ],
+        );
+        assert_eq!(comfortable.score, Some(100));
+        assert_eq!(comfortable.rating, "good");
+        assert!(comfortable.explanations.is_empty());
+
+        let stuffy = score_room(
+            "Bedroom",
+            &[
+                reading(Metric::Temperature, 24.0),
+                reading(Metric::Temperature, 25.0),
+                reading(Metric::Co2, 1600.0),
+            ],
+        );
+        // Temperature 24.5 °C scores 70, CO2 scores 20
+        assert_eq!(stuffy.metrics[0].sensors, 2);
+        assert_eq!(stuffy.metrics[0].score, 70);
+        assert_eq!(stuffy.score, Some(47));
+        assert_eq!(stuffy.rating, "poor");
+        assert!(stuffy.explanations[0].starts_with("Stale air"));
+        assert!(stuffy.explanations[1].starts_with("Too warm"));
+
+        assert_eq!(score_room("Hall", &[]).rating, "unknown");
+    }
+
+    #[test]
+    fn test_readings_are_normalized() {
+        let fahrenheit = SensorType::Temperature {
+            unit: TemperatureUnit::Fahrenheit,
+            range: (-40.0, 185.0),
+        };
+        let celsius = Reading::new(&fahrenheit, 68.0).unwrap().value;
+        assert!((celsius - 20.0).abs() < 1e-9);
+
+        let lux = SensorType::Illuminance {
+            unit: LightUnit::Lux,
+            range: (0.0, 100000.0),
+        };
+        assert_eq!(Reading::new(&lux, 75.0).unwrap().metric, Metric::Brightness);
+        assert!(Reading::new(&SensorType::MotionDetector, 1.0).is_none());
+    }
+}
@@ -0,0 +1,98 @@
+//! Suggestions for misspelled room names
+//!
+//! Tools that take a room name fail with a structured error when the room
+//! does not exist. The error carries the closest room names by edit
+//! distance, so a client can retry with the right spelling instead of
+//! listing all rooms first:
+//!
+//! ```json
+//! {"error": {"code": "room_not_found", "message": "Room 'Kichen' not found",
+//!            "room": "Kichen", "suggestions": ["Kitchen", "Kids Room"]}}
+//! ```
+
+use crate::client::LoxoneStructure;
+use serde_json::json;
+
+/// Room names returned with a `room_not_found` error
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Levenshtein distance between two strings, by character
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Up to [`MAX_SUGGESTIONS`] room names closest to `wanted`
+///
+/// Case is ignored; ties are broken alphabetically and duplicates dropped.
+pub fn closest_rooms<'a>(rooms: impl IntoIterator<Item = &'a str>, wanted: &str) -> Vec<String> {
+    let wanted = wanted.trim().to_lowercase();
+    let mut ranked: Vec<(usize, &str)> = rooms
+        .into_iter()
+        .map(|name| (levenshtein(&name.to_lowercase(), &wanted), name))
+        .collect();
+    ranked.sort();
+    ranked.dedup_by(|a, b| a.1 == b.1);
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Room names of a structure
+pub fn structure_room_names(structure: &LoxoneStructure) -> impl Iterator<Item = &str> {
+    structure
+        .rooms
+        .values()
+        .filter_map(|room| room.get("name").and_then(|v| v.as_str()))
+}
+
+/// Tool error for an unknown room, with the closest known room names
+pub fn room_not_found<'a>(rooms: impl IntoIterator<Item = &'a str>, wanted: &str) -> String {
+    json!({
+        "error": {
+            "code": "room_not_found",
+            "message": format!("Room '{wanted}' not found"),
+            "room": wanted,
+            "suggestions": closest_rooms(rooms, wanted),
+        }
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_rooms() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "bad"), 3);
+
+        let rooms = ["Kitchen", "Living Room", "Kids Room", "Bathroom", "Kitchen"];
+        assert_eq!(
+            closest_rooms(rooms, "kichen"),
+            vec!["Kitchen", "Bathroom", "Kids Room"]
+        );
+
+        let error: serde_json::Value =
+            serde_json::from_str(&room_not_found(rooms, "Livng room")).unwrap();
+        assert_eq!(error["error"]["code"], "room_not_found");
+        assert_eq!(error["error"]["suggestions"][0], "Living Room");
+        assert_eq!(
+            error["error"]["suggestions"].as_array().unwrap().len(),
+            MAX_SUGGESTIONS
+        );
+    }
+}
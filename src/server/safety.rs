@@ -0,0 +1,224 @@
+//! Smoke and water leak alarm handling
+//!
+//! Loxone exposes the fire and water alarm as a `SmokeAlarm` control. Its
+//! `states` map names to state UUIDs:
+//!
+//! - `level`: current alarm level (0 = no alarm, 1 pre-alarm, 2 main alarm)
+//! - `alarmCause`: bit mask of what triggered (1 smoke, 2 water, 4 heat)
+//! - `sensors`: text listing the sensors that triggered, separated by `|`
+//! - `testAlarm`: 1 while a test alarm is running
+//! - `timeServiceMode`: seconds left in service mode, 0 when off
+//! - `areAlarmSignalsOff`: 1 while sirens are muted
+//!
+//! Standalone water sensors are `LeakDetector` controls whose `active`
+//! state is 1 while water is detected.
+//!
+//! A test alarm sounds every siren in the house, so `test_safety_alarm` asks
+//! the consent manager before sending it.
+
+use crate::server::alarm::parse_sensors;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as smoke alarms
+pub const SMOKE_ALARM_TYPES: &[&str] = &["SmokeAlarm"];
+
+/// Control types handled as water leak detectors
+pub const LEAK_DETECTOR_TYPES: &[&str] = &["LeakDetector", "WaterLeakDetector"];
+
+/// Resource summarizing all life-safety devices
+pub const SAFETY_STATUS_URI: &str = "loxone://safety/status";
+
+/// State names read for a safety device status
+pub const SAFETY_STATE_NAMES: &[&str] = &[
+    "level",
+    "alarmCause",
+    "sensors",
+    "testAlarm",
+    "timeServiceMode",
+    "areAlarmSignalsOff",
+    "active",
+];
+
+/// Command starting a test alarm on a smoke alarm
+pub const TEST_ALARM_COMMAND: &str = "testalarm";
+
+/// Alarm causes by bit in `alarmCause`
+const ALARM_CAUSES: &[(u32, &str)] = &[(1, "smoke"), (2, "water"), (4, "heat")];
+
+/// Kind of life-safety device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyKind {
+    Smoke,
+    Leak,
+}
+
+impl SafetyKind {
+    /// Kind of a control type, if it is a safety device
+    pub fn from_control_type(control_type: &str) -> Option<Self> {
+        if SMOKE_ALARM_TYPES.contains(&control_type) {
+            Some(Self::Smoke)
+        } else if LEAK_DETECTOR_TYPES.contains(&control_type) {
+            Some(Self::Leak)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the device can run a test alarm
+    pub fn supports_test_alarm(self) -> bool {
+        self == Self::Smoke
+    }
+}
+
+/// Snapshot of a smoke alarm or leak detector
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SafetyStatus {
+    pub kind: SafetyKind,
+    /// Alarm going off (pre-alarm included) or water detected
+    pub alarm: bool,
+    /// `none`, `pre_alarm` or `main_alarm`
+    pub level: &'static str,
+    /// What triggered the alarm: smoke, water, heat
+    pub causes: Vec<&'static str>,
+    pub triggered_sensors: Vec<String>,
+    pub test_running: bool,
+    /// Seconds left in service mode, while alarms are suppressed
+    pub service_mode_secs: Option<u32>,
+    pub signals_muted: bool,
+}
+
+impl SafetyStatus {
+    /// Build the status from state values keyed by state name
+    pub fn from_state_values(kind: SafetyKind, values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+        let flag = |name: &str| number(name).is_some_and(|v| v != 0.0);
+
+        let (alarm, level, causes) = match kind {
+            SafetyKind::Smoke => {
+                let level = number("level").map_or(0, |l| l.clamp(0.0, 2.0) as u8);
+                let mask = number("alarmCause").map_or(0, |c| c.max(0.0) as u32);
+                let causes = ALARM_CAUSES
+                    .iter()
+                    .filter(|(bit, _)| mask & bit != 0)
+                    .map(|(_, cause)| *cause)
+                    .collect();
+                let label = match level {
+                    0 => "none",
+                    1 => "pre_alarm",
+                    _ => "main_alarm",
+                };
+                (level > 0, label, causes)
+            }
+            SafetyKind::Leak => {
+                let leak = flag("active");
+                let label = if leak { "main_alarm" } else { "none" };
+                (leak, label, if leak { vec!["water"] } else { Vec::new() })
+            }
+        };
+
+        Self {
+            kind,
+            alarm,
+            level,
+            causes,
+            triggered_sensors: values
+                .get("sensors")
+                .and_then(Value::as_str)
+                .map(parse_sensors)
+                .unwrap_or_default(),
+            test_running: flag("testAlarm"),
+            service_mode_secs: number("timeServiceMode")
+                .filter(|t| *t > 0.0)
+                .map(|t| t.round() as u32),
+            signals_muted: flag("areAlarmSignalsOff"),
+        }
+    }
+}
+
+/// State UUIDs of a safety control, keyed by state name
+pub fn safety_state_uuids(control: &Value) -> HashMap<String, String> {
+    SAFETY_STATE_NAMES
+        .iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(*name))
+                .and_then(Value::as_str)
+                .map(|uuid| ((*name).to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_smoke_alarm_status() {
+        let status = SafetyStatus::from_state_values(
+            SafetyKind::Smoke,
+            &values(&[
+                ("level", json!(2)),
+                ("alarmCause", json!(5)),
+                ("sensors", json!("Kitchen Smoke|Hall Smoke")),
+                ("areAlarmSignalsOff", json!(1)),
+            ]),
+        );
+        assert!(status.alarm);
+        assert_eq!(status.level, "main_alarm");
+        assert_eq!(status.causes, vec!["smoke", "heat"]);
+        assert_eq!(
+            status.triggered_sensors,
+            vec!["Kitchen Smoke", "Hall Smoke"]
+        );
+        assert!(status.signals_muted);
+
+        let quiet = SafetyStatus::from_state_values(
+            SafetyKind::Smoke,
+            &values(&[("level", json!(0)), ("timeServiceMode", json!("300"))]),
+        );
+        assert!(!quiet.alarm);
+        assert!(quiet.causes.is_empty());
+        assert_eq!(quiet.service_mode_secs, Some(300));
+    }
+
+    #[test]
+    fn test_leak_detector_status() {
+        assert_eq!(
+            SafetyKind::from_control_type("LeakDetector"),
+            Some(SafetyKind::Leak)
+        );
+        assert_eq!(SafetyKind::from_control_type("Alarm"), None);
+        assert!(!SafetyKind::Leak.supports_test_alarm());
+
+        let leak =
+            SafetyStatus::from_state_values(SafetyKind::Leak, &values(&[("active", json!(1))]));
+        assert!(leak.alarm);
+        assert_eq!(leak.causes, vec!["water"]);
+
+        let dry = SafetyStatus::from_state_values(SafetyKind::Leak, &HashMap::new());
+        assert!(!dry.alarm);
+        assert_eq!(dry.level, "none");
+    }
+}
@@ -0,0 +1,286 @@
+//! Startup self-test
+//!
+//! `loxone-mcp-server --self-test <transport>` runs through the steps a
+//! deployment needs before it can serve clients — Miniserver reachability,
+//! authentication, structure parsing and one read-only tool call per tool
+//! category — prints a report and exits non-zero if any step failed. Works
+//! against the mock server as well as a real Miniserver, which makes it
+//! suitable for container health gates and deployment CI.
+
+use crate::server::macro_backend::LoxoneMcpServer;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::future::Future;
+use std::time::Instant;
+
+/// Read-only tool exercised for each tool category
+pub const CATEGORY_READS: &[(&str, &str)] = &[
+    ("system", "get_server_status"),
+    ("discovery", "list_rooms"),
+    ("lighting", "get_lights_status"),
+    ("climate", "get_climate_status"),
+    ("blinds", "get_blinds_status"),
+    ("audio", "get_audio_status"),
+    ("sensors", "get_sensor_readings"),
+    ("security", "get_security_status"),
+    ("energy", "get_energy_status"),
+    ("weather", "get_weather"),
+];
+
+/// Outcome of one self-test step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not run because a step it depends on failed
+    Skipped,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pass => write!(f, "PASS"),
+            Self::Fail => write!(f, "FAIL"),
+            Self::Skipped => write!(f, "SKIP"),
+        }
+    }
+}
+
+/// Result of one self-test step
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub duration_ms: u64,
+    pub detail: String,
+}
+
+/// All self-test steps in execution order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SelfTestReport {
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// Whether every step that ran passed
+    pub fn passed(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|c| c.status == CheckStatus::Pass)
+    }
+
+    /// Process exit code for the report
+    pub fn exit_code(&self) -> i32 {
+        if self.passed() { 0 } else { 1 }
+    }
+
+    fn skip(&mut self, name: impl Into<String>, reason: &str) {
+        self.checks.push(SelfTestCheck {
+            name: name.into(),
+            status: CheckStatus::Skipped,
+            duration_ms: 0,
+            detail: reason.to_string(),
+        });
+    }
+
+    async fn run<F>(&mut self, name: impl Into<String>, step: F) -> bool
+    where
+        F: Future<Output = std::result::Result<String, String>>,
+    {
+        let started = Instant::now();
+        let result = step.await;
+        let passed = result.is_ok();
+        self.checks.push(SelfTestCheck {
+            name: name.into(),
+            status: if passed {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Fail
+            },
+            duration_ms: started.elapsed().as_millis() as u64,
+            detail: result.unwrap_or_else(|e| e),
+        });
+        passed
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Loxone MCP Server self-test")?;
+        for check in &self.checks {
+            writeln!(
+                f,
+                "  [{}] {:<24} {:>6} ms  {}",
+                check.status, check.name, check.duration_ms, check.detail
+            )?;
+        }
+        let failed = self
+            .checks
+            .iter()
+            .filter(|c| c.status != CheckStatus::Pass)
+            .count();
+        if failed == 0 {
+            write!(f, "Result: OK ({} checks)", self.checks.len())
+        } else {
+            write!(
+                f,
+                "Result: FAILED ({failed} of {} checks)",
+                self.checks.len()
+            )
+        }
+    }
+}
+
+/// Run the self-test against the server's Miniserver connection
+pub async fn run(server: &LoxoneMcpServer) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    let Some(client) = server.client() else {
+        report
+            .run("connection", async {
+                Err("Server has no Loxone client (offline mode?)".to_string())
+            })
+            .await;
+        return report;
+    };
+
+    let mut auth_rejected = false;
+    let reachable = report
+        .run("connection", async {
+            match client.get_system_info().await {
+                Ok(_) => Ok("Miniserver reachable".to_string()),
+                Err(e) if e.is_auth_error() => {
+                    auth_rejected = true;
+                    Ok("Miniserver reachable".to_string())
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        })
+        .await;
+    if !reachable {
+        report.skip("auth", "connection failed");
+        report.skip("structure", "connection failed");
+        skip_reads(&mut report, "connection failed");
+        return report;
+    }
+
+    let mut structure = None;
+    let authenticated = report
+        .run("auth", async {
+            if auth_rejected {
+                return Err("Credentials rejected by Miniserver".to_string());
+            }
+            match client.get_structure().await {
+                Err(e) if e.is_auth_error() => Err(e.to_string()),
+                fetched => {
+                    structure = Some(fetched);
+                    Ok("Credentials accepted".to_string())
+                }
+            }
+        })
+        .await;
+    if !authenticated {
+        report.skip("structure", "authentication failed");
+        skip_reads(&mut report, "authentication failed");
+        return report;
+    }
+
+    let parsed = report
+        .run("structure", async {
+            let structure = structure
+                .expect("structure fetched during auth")
+                .map_err(|e| e.to_string())?;
+            if structure.controls.is_empty() {
+                return Err("Structure contains no controls".to_string());
+            }
+            Ok(format!(
+                "{} controls, {} rooms, {} categories",
+                structure.controls.len(),
+                structure.rooms.len(),
+                structure.cats.len()
+            ))
+        })
+        .await;
+    if !parsed {
+        skip_reads(&mut report, "structure could not be loaded");
+        return report;
+    }
+
+    for (category, tool) in CATEGORY_READS {
+        report
+            .run(format!("read:{category}"), async {
+                read_tool(server, tool)
+                    .await
+                    .map(|value| describe_read(tool, &value))
+            })
+            .await;
+    }
+    report
+}
+
+fn skip_reads(report: &mut SelfTestReport, reason: &str) {
+    for (category, _) in CATEGORY_READS {
+        report.skip(format!("read:{category}"), reason);
+    }
+}
+
+/// Invoke one of the [`CATEGORY_READS`] tools
+async fn read_tool(server: &LoxoneMcpServer, tool: &str) -> std::result::Result<Value, String> {
+    match tool {
+        "get_server_status" => server.get_server_status().await,
+        "list_rooms" => server.list_rooms().await,
+        "get_lights_status" => server.get_lights_status().await,
+        "get_climate_status" => server.get_climate_status().await,
+        "get_blinds_status" => server.get_blinds_status().await,
+        "get_audio_status" => server.get_audio_status().await,
+        "get_sensor_readings" => server.get_sensor_readings().await,
+        "get_security_status" => server.get_security_status().await,
+        "get_energy_status" => server.get_energy_status().await,
+        "get_weather" => server.get_weather().await,
+        other => Err(format!("No self-test read for tool {other}")),
+    }
+}
+
+fn describe_read(tool: &str, value: &Value) -> String {
+    match value.get("count").and_then(Value::as_u64) {
+        Some(count) => format!("{tool}: {count} devices"),
+        None => format!("{tool}: ok"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_report_outcome() {
+        let mut report = SelfTestReport::default();
+        assert!(!report.passed());
+
+        report
+            .run("connection", async { Ok("up".to_string()) })
+            .await;
+        assert!(report.passed());
+        assert_eq!(report.exit_code(), 0);
+
+        report.run("auth", async { Err("401".to_string()) }).await;
+        skip_reads(&mut report, "authentication failed");
+        assert!(!report.passed());
+        assert_eq!(report.exit_code(), 1);
+
+        let text = report.to_string();
+        assert!(text.contains("[FAIL] auth"));
+        assert!(text.contains("[SKIP] read:weather"));
+        assert!(text.ends_with(&format!(
+            "FAILED ({} of {} checks)",
+            CATEGORY_READS.len() + 1,
+            CATEGORY_READS.len() + 2
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_offline_server_fails() {
+        let report = run(&LoxoneMcpServer::default()).await;
+        assert_eq!(report.checks.len(), 1);
+        assert_eq!(report.checks[0].status, CheckStatus::Fail);
+    }
+}
@@ -0,0 +1,569 @@
+//! Per-session view of the server for the MCP transports
+//!
+//! [`SessionBackend`] is what the framework serves: it offers only the
+//! tools the installation has devices for, described in the client's
+//! language (see [`LoxoneMcpServer::listed_tools`]), and runs every request
+//! as the client behind it. The stdio transport has a single local client.
+//!
+//! On HTTP the framework hands a backend nothing but the session id of a
+//! request. The [`HttpGateway`](crate::server::http_gateway::HttpGateway)
+//! binds every session to the API key that opened it, and
+//! [`SessionBackend`] answers each request with the server view of that
+//! caller ([`LoxoneMcpServer::for_caller`]), so role, tenant and guest
+//! limits of the key apply to everything the session does - tools,
+//! resources and resource subscriptions. Tool lists and action words follow
+//! the locale of each request.
+//!
+//! Tool results carry a short text for the model and the payload as
+//! `structuredContent` (see [`ToolResponse`]).
+//!
+//! Every session is listed in the [`ClientSessionRegistry`] under the label
+//! and capabilities of its key until the client ends it (`DELETE /mcp`) or
+//! it stays idle for [`SESSION_IDLE_TIMEOUT`].
+
+use crate::error::{LoxoneError, Result};
+use crate::security::caller::{Caller, OPERATIONS};
+use crate::server::action_aliases::ActionAliases;
+use crate::server::client_sessions::{ClientSessionRegistry, SessionTransport};
+use crate::server::macro_backend::LoxoneMcpServer;
+use crate::server::subscription::SubscriptionCoordinator;
+use crate::server::subscription::types::{ClientInfo, ClientTransport};
+use crate::server::tool_response::ToolResponse;
+use pulseengine_mcp_protocol::{
+    CallToolRequestParam, CallToolResult, Error as McpError, GetPromptRequestParam,
+    GetPromptResult, ListPromptsResult, ListResourcesResult, ListToolsResult,
+    PaginatedRequestParam, ReadResourceRequestParam, ReadResourceResult, ServerInfo,
+    SubscribeRequestParam, UnsubscribeRequestParam,
+};
+use pulseengine_mcp_server::auth::AuthConfig;
+use pulseengine_mcp_server::{HasServerInfo, McpBackend, McpServer, ServerConfig, TransportConfig};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Session id of the stdio client
+const LOCAL_SESSION: &str = "local";
+
+/// Sessions without a request for this long are forgotten
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// The caller one HTTP session is bound to
+#[derive(Clone)]
+struct HttpSession {
+    /// `None` is the local operator in development mode
+    caller: Option<Caller>,
+    /// Entry in the client session registry
+    client_session: String,
+    /// Operations the session may perform
+    capabilities: Vec<String>,
+    /// Server view of the caller, built on first use
+    view: Option<LoxoneMcpServer>,
+    /// Locale of the latest request, for action words
+    locale: Option<String>,
+    last_seen: Instant,
+}
+
+/// Whether two callers use the same key
+fn same_key(a: &Option<Caller>, b: &Option<Caller>) -> bool {
+    a.as_ref().map(|c| &c.label) == b.as_ref().map(|c| &c.label)
+}
+
+/// Callers of the open HTTP sessions, shared by gateway and backend
+pub struct HttpSessions {
+    transport: SessionTransport,
+    sessions: Mutex<HashMap<String, HttpSession>>,
+}
+
+impl HttpSessions {
+    /// No sessions open yet; new ones are registered as `transport`
+    pub fn new(transport: SessionTransport) -> Self {
+        Self {
+            transport,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Bind session `id` to `caller`, for a request in `locale` from `peer`
+    ///
+    /// A known session only passes with the key that opened it; returns
+    /// `false` for any other key.
+    pub fn bind(
+        &self,
+        id: &str,
+        caller: Option<Caller>,
+        locale: Option<String>,
+        peer: Option<SocketAddr>,
+    ) -> bool {
+        let registry = ClientSessionRegistry::global();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|_, session| {
+            let open = session.last_seen.elapsed() < SESSION_IDLE_TIMEOUT;
+            if !open {
+                registry.close(&session.client_session);
+            }
+            open
+        });
+        match sessions.get_mut(id) {
+            Some(session) => {
+                if !same_key(&session.caller, &caller) {
+                    return false;
+                }
+                session.locale = locale;
+                session.last_seen = Instant::now();
+            }
+            None => {
+                let client_session = registry.open(self.transport, peer);
+                let capabilities = match &caller {
+                    Some(caller) => caller.capabilities.clone(),
+                    None => OPERATIONS.iter().map(|op| op.to_string()).collect(),
+                };
+                registry.identify(
+                    &client_session,
+                    caller.as_ref().map(|c| c.label.clone()),
+                    capabilities.clone(),
+                );
+                sessions.insert(
+                    id.to_string(),
+                    HttpSession {
+                        caller,
+                        client_session,
+                        capabilities,
+                        view: None,
+                        locale,
+                        last_seen: Instant::now(),
+                    },
+                );
+            }
+        }
+        true
+    }
+
+    /// End session `id`; returns whether it was open
+    pub fn close(&self, id: &str) -> bool {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id);
+        if let Some(session) = &session {
+            ClientSessionRegistry::global().close(&session.client_session);
+        }
+        session.is_some()
+    }
+
+    /// Whether session `id` is open and belongs to the key of `caller`
+    pub fn is_held_by(&self, id: &str, caller: &Option<Caller>) -> bool {
+        self.lookup(id)
+            .is_some_and(|session| same_key(&session.caller, caller))
+    }
+
+    fn lookup(&self, id: &str) -> Option<HttpSession> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .cloned()
+    }
+
+    fn cache_view(&self, id: &str, view: LoxoneMcpServer) {
+        if let Some(session) = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(id)
+        {
+            session.view = Some(view);
+        }
+    }
+}
+
+/// Backend that runs every request as its session's caller
+#[derive(Clone)]
+pub struct SessionBackend {
+    server: LoxoneMcpServer,
+    /// `None` for the single local client of the stdio transport
+    sessions: Option<Arc<HttpSessions>>,
+    subscriptions: Option<Arc<SubscriptionCoordinator>>,
+}
+
+impl SessionBackend {
+    /// Serve `server` to the sessions bound in `sessions`
+    pub fn new(server: LoxoneMcpServer, sessions: Arc<HttpSessions>) -> Self {
+        Self {
+            server,
+            sessions: Some(sessions),
+            subscriptions: None,
+        }
+    }
+
+    /// Serve `server` to its one local client
+    pub fn local(server: LoxoneMcpServer) -> Self {
+        Self {
+            server,
+            sessions: None,
+            subscriptions: None,
+        }
+    }
+
+    /// Register resource subscriptions with `coordinator`
+    pub fn with_subscriptions(mut self, coordinator: Arc<SubscriptionCoordinator>) -> Self {
+        self.subscriptions = Some(coordinator);
+        self
+    }
+
+    /// Framework Streamable HTTP transport on loopback `port`
+    pub async fn serve_http(self, port: u16) -> Result<McpServer<Self>> {
+        self.serve(TransportConfig::StreamableHttp {
+            port,
+            host: Some("127.0.0.1".to_string()),
+        })
+        .await
+    }
+
+    /// Framework stdio transport
+    pub async fn serve_stdio(self) -> Result<McpServer<Self>> {
+        self.serve(TransportConfig::Stdio).await
+    }
+
+    async fn serve(self, transport_config: TransportConfig) -> Result<McpServer<Self>> {
+        let mut auth_config = AuthConfig::memory();
+        auth_config.enabled = false;
+        let config = ServerConfig {
+            server_info: self.get_server_info(),
+            auth_config,
+            transport_config,
+            ..Default::default()
+        };
+        McpServer::new(self, config)
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to create server: {e}")))
+    }
+
+    /// Session of the current request, with the server view of its caller
+    async fn current(&self) -> std::result::Result<CurrentSession, McpError> {
+        let Some(sessions) = &self.sessions else {
+            return Ok(CurrentSession {
+                id: LOCAL_SESSION.to_string(),
+                caller: None,
+                capabilities: OPERATIONS.iter().map(|op| op.to_string()).collect(),
+                view: self.server.clone(),
+                locale: None,
+            });
+        };
+        let id = pulseengine_mcp_transport::try_current_session_id()
+            .ok_or_else(|| McpError::unauthorized("Request has no session"))?;
+        let HttpSession {
+            caller,
+            client_session,
+            capabilities,
+            view,
+            locale,
+            ..
+        } = sessions
+            .lookup(&id)
+            .ok_or_else(|| McpError::unauthorized("Unknown session"))?;
+        let view = match view {
+            Some(view) => view,
+            None => {
+                let view = match &caller {
+                    Some(caller) => self
+                        .server
+                        .for_caller(caller)
+                        .await
+                        .map_err(|e| McpError::internal_error(e.to_string()))?,
+                    None => self.server.clone(),
+                }
+                .in_client_session(client_session, &capabilities);
+                sessions.cache_view(&id, view.clone());
+                view
+            }
+        };
+        Ok(CurrentSession {
+            id,
+            caller,
+            capabilities,
+            view,
+            locale,
+        })
+    }
+}
+
+/// Session a request belongs to
+struct CurrentSession {
+    id: String,
+    caller: Option<Caller>,
+    capabilities: Vec<String>,
+    view: LoxoneMcpServer,
+    locale: Option<String>,
+}
+
+/// Room name of a `loxone://rooms/{room}/devices` resource
+fn room_of(uri: &str) -> Option<&str> {
+    uri.strip_prefix("loxone://rooms/")?
+        .strip_suffix("/devices")
+        .filter(|room| !room.is_empty() && !room.contains('/'))
+}
+
+/// Whether `view` may watch the resource `uri`
+///
+/// Tenant and guest keys only watch the rooms they see; the other
+/// resources span the whole installation.
+async fn may_watch(view: &LoxoneMcpServer, caller: &Caller, uri: &str) -> bool {
+    if caller.scope.is_none() {
+        return true;
+    }
+    let (Some(room), Some(client)) = (room_of(uri), view.client()) else {
+        return false;
+    };
+    client.get_structure().await.is_ok_and(|structure| {
+        structure.rooms.values().any(|r| {
+            r.get("name")
+                .and_then(|name| name.as_str())
+                .is_some_and(|name| name.eq_ignore_ascii_case(room))
+        })
+    })
+}
+
+/// A successful result with a short text and its payload as `structuredContent`
+///
+/// The generated backend puts the whole payload into the text as well.
+fn summarized(tool: &str, mut result: CallToolResult) -> CallToolResult {
+    if result.is_error == Some(true) {
+        return result;
+    }
+    match result.structured_content.take() {
+        Some(data) => ToolResponse::from_data(tool, data).into_call_tool_result(),
+        None => result,
+    }
+}
+
+#[async_trait::async_trait]
+impl McpBackend for SessionBackend {
+    type Error = McpError;
+    type Config = ();
+
+    async fn initialize(_config: Self::Config) -> std::result::Result<Self, Self::Error> {
+        Err(McpError::internal_error(
+            "Session backends are built with SessionBackend::new",
+        ))
+    }
+
+    fn get_server_info(&self) -> ServerInfo {
+        let mut info = <LoxoneMcpServer as HasServerInfo>::server_info();
+        if let Some(resources) = info.capabilities.resources.as_mut() {
+            resources.subscribe = Some(self.subscriptions.is_some());
+        }
+        info
+    }
+
+    async fn health_check(&self) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+    ) -> std::result::Result<ListToolsResult, Self::Error> {
+        let session = self.current().await?;
+        Ok(ListToolsResult {
+            tools: session.view.listed_tools(session.locale.as_deref()).await,
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+    ) -> std::result::Result<CallToolResult, Self::Error> {
+        let session = self.current().await?;
+        let tool = request.name.clone();
+        let call = McpBackend::call_tool(&session.view, request);
+        let result = match session.locale {
+            Some(locale) => ActionAliases::scope(locale, call).await,
+            None => call.await,
+        };
+        Ok(summarized(&tool, result?))
+    }
+
+    async fn list_resources(
+        &self,
+        request: PaginatedRequestParam,
+    ) -> std::result::Result<ListResourcesResult, Self::Error> {
+        let view = self.current().await?.view;
+        McpBackend::list_resources(&view, request)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+    ) -> std::result::Result<ReadResourceResult, Self::Error> {
+        let view = self.current().await?.view;
+        McpBackend::read_resource(&view, request)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn list_prompts(
+        &self,
+        request: PaginatedRequestParam,
+    ) -> std::result::Result<ListPromptsResult, Self::Error> {
+        let view = self.current().await?.view;
+        McpBackend::list_prompts(&view, request)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+    ) -> std::result::Result<GetPromptResult, Self::Error> {
+        let view = self.current().await?.view;
+        McpBackend::get_prompt(&view, request)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+    ) -> std::result::Result<(), Self::Error> {
+        let CurrentSession {
+            id,
+            caller,
+            capabilities,
+            view,
+            ..
+        } = self.current().await?;
+        if let Some(caller) = &caller
+            && !may_watch(&view, caller, &request.uri).await
+        {
+            return Err(McpError::forbidden(format!(
+                "Resource {} is outside the scope of this key",
+                request.uri
+            )));
+        }
+        let Some(coordinator) = &self.subscriptions else {
+            return Ok(());
+        };
+        let client = ClientInfo {
+            id,
+            transport: ClientTransport::LongPoll,
+            capabilities,
+            connected_at: SystemTime::now(),
+        };
+        coordinator
+            .subscribe_client(client, request.uri, None)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string()))
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+    ) -> std::result::Result<(), Self::Error> {
+        let id = self.current().await?.id;
+        let Some(coordinator) = &self.subscriptions else {
+            return Ok(());
+        };
+        coordinator
+            .unsubscribe_client(id, Some(request.uri))
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{TestServer, sample_house};
+    use pulseengine_mcp_protocol::Content;
+
+    #[test]
+    fn test_sessions_stay_with_the_key_that_opened_them() {
+        let caller = |label: &str| Caller {
+            label: label.to_string(),
+            capabilities: vec!["read".to_string()],
+            scope: None,
+        };
+        let sessions = HttpSessions::new(SessionTransport::Http);
+        assert!(sessions.bind("s1", Some(caller("lmcp_operator_001")), None, None));
+        assert!(sessions.bind("s1", Some(caller("lmcp_operator_001")), None, None));
+        assert!(!sessions.bind("s1", Some(caller("lmcp_operator_002")), None, None));
+        assert!(!sessions.bind("s1", None, None, None));
+
+        // Each session is a client of its own, named after its key
+        let client_session = sessions.lookup("s1").unwrap().client_session;
+        let listed = |id: &str| {
+            ClientSessionRegistry::global()
+                .sessions()
+                .into_iter()
+                .find(|session| session.id == id)
+        };
+        let client = listed(&client_session).unwrap();
+        assert_eq!(client.name.as_deref(), Some("lmcp_operator_001"));
+        assert_eq!(client.capabilities, vec!["read".to_string()]);
+
+        assert!(sessions.close("s1"));
+        assert!(listed(&client_session).is_none());
+        assert!(sessions.bind("s1", Some(caller("lmcp_operator_002")), None, None));
+    }
+
+    #[tokio::test]
+    async fn test_tool_list_leaves_out_tools_without_devices() {
+        let fixture = TestServer::new(sample_house()).await;
+        let backend = SessionBackend::local(fixture.server.clone());
+        let listed = McpBackend::list_tools(&backend, PaginatedRequestParam { cursor: None })
+            .await
+            .unwrap();
+        let names: Vec<_> = listed.tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert!(names.contains(&"control_lights"));
+        assert!(names.contains(&"control_gate"));
+        assert!(names.contains(&"list_rooms"));
+        assert!(!names.contains(&"control_ev_charging"));
+
+        // Titles come from the locale bundles
+        let lights = listed
+            .tools
+            .iter()
+            .find(|tool| tool.name == "control_lights")
+            .unwrap();
+        assert!(lights.title.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_are_summarized_with_structured_content() {
+        let fixture = TestServer::new(sample_house()).await;
+        let backend = SessionBackend::local(fixture.server.clone());
+        let result = McpBackend::call_tool(
+            &backend,
+            CallToolRequestParam {
+                name: "list_rooms".to_string(),
+                arguments: Some(serde_json::json!({})),
+            },
+        )
+        .await
+        .unwrap();
+        let data = result.structured_content.unwrap();
+        let Some(Content::Text { text, .. }) = result.content.first() else {
+            panic!("no text content");
+        };
+        assert_eq!(
+            *text,
+            crate::server::tool_response::summarize("list_rooms", &data)
+        );
+        assert!(
+            serde_json::from_str::<serde_json::Value>(text).is_err(),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn test_room_resources() {
+        assert_eq!(room_of("loxone://rooms/Kitchen/devices"), Some("Kitchen"));
+        assert_eq!(room_of("loxone://rooms//devices"), None);
+        assert_eq!(room_of("loxone://devices/all"), None);
+    }
+}
@@ -0,0 +1,140 @@
+//! Room and house scene shortcuts
+//!
+//! "All off" and "good night" fan out one command per device across
+//! lighting, blinds and audio. This module plans those commands from the
+//! structure; the tools send them in parallel and report per device.
+//!
+//! - lights (`LightController*`, `Dimmer`, `ColorPicker*`): `off`
+//! - blinds (`Jalousie`): `FullDown`, only when requested
+//! - audio zones (`AudioZone*`): `off`
+//!
+//! Plain `Switch`es are left alone since they often drive pumps or sockets,
+//! and gates and windows share the blinds command category but are never
+//! moved by a shortcut.
+
+use crate::client::command_throttle::CommandCategory;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Blind types moved down by shortcuts
+const BLIND_TYPES: &[&str] = &["Jalousie", "Blinds", "Rolladen"];
+
+/// Command sent to one device by a shortcut
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedCommand {
+    pub uuid: String,
+    pub name: String,
+    /// Room UUID of the device
+    pub room: Option<String>,
+    /// `lighting`, `blinds` or `audio`
+    pub category: &'static str,
+    pub command: &'static str,
+}
+
+/// Commands switching everything off in `room` (UUID), or in the whole
+/// house when `room` is `None`; blinds go down when `blinds_down` is set
+///
+/// Commands are ordered by category and name so reports are stable.
+pub fn all_off_plan(
+    controls: &HashMap<String, Value>,
+    room: Option<&str>,
+    blinds_down: bool,
+) -> Vec<PlannedCommand> {
+    let mut plan: Vec<PlannedCommand> = controls
+        .iter()
+        .filter_map(|(uuid, control)| {
+            let control_type = control.get("type").and_then(Value::as_str)?;
+            let control_room = control.get("room").and_then(Value::as_str);
+            if room.is_some_and(|room| control_room != Some(room)) {
+                return None;
+            }
+
+            let category = CommandCategory::of(control_type);
+            let command = match category {
+                CommandCategory::Lighting if control_type != "Switch" => "off",
+                CommandCategory::Audio => "off",
+                CommandCategory::Blinds if blinds_down && BLIND_TYPES.contains(&control_type) => {
+                    "FullDown"
+                }
+                _ => return None,
+            };
+            Some(PlannedCommand {
+                uuid: uuid.clone(),
+                name: control
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Unknown")
+                    .to_string(),
+                room: control_room.map(str::to_string),
+                category: category.as_str(),
+                command,
+            })
+        })
+        .collect();
+    plan.sort_by(|a, b| a.category.cmp(b.category).then(a.name.cmp(&b.name)));
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn controls() -> HashMap<String, Value> {
+        HashMap::from([
+            (
+                "l-1".to_string(),
+                json!({"name": "Ceiling", "type": "LightControllerV2", "room": "r-living"}),
+            ),
+            (
+                "b-1".to_string(),
+                json!({"name": "Window", "type": "Jalousie", "room": "r-living"}),
+            ),
+            (
+                "a-1".to_string(),
+                json!({"name": "Speakers", "type": "AudioZoneV2", "room": "r-living"}),
+            ),
+            (
+                "g-1".to_string(),
+                json!({"name": "Garage", "type": "Gate", "room": "r-living"}),
+            ),
+            (
+                "l-2".to_string(),
+                json!({"name": "Bedside", "type": "Dimmer", "room": "r-bed"}),
+            ),
+            (
+                "s-1".to_string(),
+                json!({"name": "Pool Pump", "type": "Switch", "room": "r-bed"}),
+            ),
+            (
+                "t-1".to_string(),
+                json!({"name": "Heating", "type": "IRoomControllerV2", "room": "r-bed"}),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_room_all_off() {
+        let plan = all_off_plan(&controls(), Some("r-living"), false);
+        let uuids: Vec<&str> = plan.iter().map(|c| c.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["a-1", "l-1"]);
+        assert!(plan.iter().all(|c| c.command == "off"));
+    }
+
+    #[test]
+    fn test_house_with_blinds_down() {
+        let plan = all_off_plan(&controls(), None, true);
+        let commands: Vec<(&str, &str)> =
+            plan.iter().map(|c| (c.uuid.as_str(), c.command)).collect();
+        assert_eq!(
+            commands,
+            vec![
+                ("a-1", "off"),
+                ("b-1", "FullDown"),
+                ("l-2", "off"),
+                ("l-1", "off")
+            ]
+        );
+    }
+}
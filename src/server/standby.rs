@@ -0,0 +1,278 @@
+//! Warm-standby replica mode
+//!
+//! For high-availability setups a second server runs as a standby of the
+//! primary. Until it takes over it serves nothing: it applies the primary's
+//! state snapshots (see [`storage::replication`](crate::storage::replication))
+//! and polls the primary's health endpoint, usually its status page
+//! (`/status.json`). Once the primary has failed `failover_after` checks in a
+//! row, the standby applies the last snapshot and starts serving, limited to
+//! read tools by [`StandbyReadOnly`] so two servers never send commands for
+//! the same house.
+//!
+//! Any HTTP response from the primary counts as alive, including a `down`
+//! status: that reports the Miniserver, which the standby cannot reach
+//! either.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::tool_middleware::{PreHook, ToolCall, ToolMiddleware};
+use crate::server::tool_timeouts::ToolCategory;
+use crate::storage::migrations::PersistedStore;
+use crate::storage::replication;
+use crate::utils::clock::{SharedClock, system_clock};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Standby settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandbyConfig {
+    /// Health endpoint of the primary
+    pub primary_health_url: String,
+    /// Directory the primary publishes snapshots into
+    pub replica_dir: PathBuf,
+    /// Time between health checks; snapshots are applied at the same pace
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Duration,
+    /// Consecutive failed checks before taking over
+    pub failover_after: u32,
+    /// Timeout of one health check
+    #[serde(with = "humantime_serde")]
+    pub check_timeout: Duration,
+}
+
+impl StandbyConfig {
+    pub fn new(primary_health_url: impl Into<String>, replica_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            primary_health_url: primary_health_url.into(),
+            replica_dir: replica_dir.into(),
+            check_interval: Duration::from_secs(10),
+            failover_after: 3,
+            check_timeout: Duration::from_secs(5),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        url::Url::parse(&self.primary_health_url).map_err(|e| {
+            LoxoneError::config(format!(
+                "Invalid primary health URL '{}': {e}",
+                self.primary_health_url
+            ))
+        })?;
+        if self.failover_after == 0 {
+            return Err(LoxoneError::config(
+                "Standby failover needs at least 1 failed health check",
+            ));
+        }
+        if self.check_interval.is_zero() {
+            return Err(LoxoneError::config(
+                "Standby check interval must be greater than zero",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Mirrors the primary's state and waits for it to go dark
+pub struct StandbyMonitor {
+    config: StandbyConfig,
+    stores: Vec<PersistedStore>,
+    http: reqwest::Client,
+    clock: SharedClock,
+}
+
+impl StandbyMonitor {
+    pub fn new(config: StandbyConfig, stores: Vec<PersistedStore>) -> Result<Self> {
+        config.validate()?;
+        let http = reqwest::Client::builder()
+            .timeout(config.check_timeout)
+            .build()
+            .map_err(|e| LoxoneError::config(format!("Failed to create HTTP client: {e}")))?;
+        Ok(Self {
+            config,
+            stores,
+            http,
+            clock: system_clock(),
+        })
+    }
+
+    /// Pace checks with `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether the primary's health endpoint answers
+    pub async fn primary_alive(&self) -> bool {
+        self.http
+            .get(&self.config.primary_health_url)
+            .send()
+            .await
+            .is_ok_and(|response| !response.status().is_server_error())
+    }
+
+    /// Apply the latest snapshot; failures are logged and retried next check
+    fn sync(&self) {
+        match replication::apply_snapshot(&self.stores, &self.config.replica_dir) {
+            Ok(Some(manifest)) => debug!("Applied snapshot from {}", manifest.published_at),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to apply primary snapshot: {e}"),
+        }
+    }
+
+    /// Mirror the primary until it misses `failover_after` checks in a row
+    ///
+    /// Returns once the standby should take over, after applying the last
+    /// snapshot.
+    pub async fn wait_for_takeover(&self) {
+        info!(
+            "🕯️ Standby of {}; mirroring state from {}",
+            self.config.primary_health_url,
+            self.config.replica_dir.display()
+        );
+        let mut failures = 0;
+        loop {
+            self.sync();
+            if self.primary_alive().await {
+                failures = 0;
+            } else {
+                failures += 1;
+                warn!(
+                    "Primary health check failed ({failures}/{})",
+                    self.config.failover_after
+                );
+                if failures >= self.config.failover_after {
+                    break;
+                }
+            }
+            self.clock.sleep(self.config.check_interval).await;
+        }
+        self.sync();
+        warn!(
+            "⚠️ Primary {} is unreachable; standby taking over read tools",
+            self.config.primary_health_url
+        );
+    }
+}
+
+/// Publish snapshots of `stores` into `replica_dir` every `interval`
+pub fn spawn_publisher(
+    stores: Vec<PersistedStore>,
+    replica_dir: PathBuf,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    info!("🪞 Publishing state snapshots to {}", replica_dir.display());
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = replication::publish_snapshot(&stores, &replica_dir) {
+                warn!("Failed to publish state snapshot: {e}");
+            }
+        }
+    })
+}
+
+/// Refuses control tools on a standby that took over
+pub struct StandbyReadOnly {
+    primary: String,
+}
+
+impl StandbyReadOnly {
+    pub fn new(primary: impl Into<String>) -> Self {
+        Self {
+            primary: primary.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for StandbyReadOnly {
+    fn name(&self) -> &'static str {
+        "standby_read_only"
+    }
+
+    async fn before(&self, call: &ToolCall) -> PreHook {
+        if call.category != ToolCategory::Control {
+            return PreHook::Continue;
+        }
+        PreHook::Respond(Err(format!(
+            "'{}' is unavailable: this is a standby serving read tools while the primary ({}) is down",
+            call.tool, self.primary
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tool_middleware::ToolMiddlewareChain;
+    use crate::utils::clock::ManualClock;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_takes_over_after_failed_checks() {
+        let replica = tempfile::tempdir().unwrap();
+        let local = tempfile::tempdir().unwrap();
+        let primary = tempfile::tempdir().unwrap();
+        std::fs::write(primary.path().join("flags.json"), "{}").unwrap();
+        replication::publish_snapshot(
+            &[PersistedStore::new(
+                "feature-flags",
+                primary.path().join("flags.json"),
+                1,
+            )],
+            replica.path(),
+        )
+        .unwrap();
+
+        // Nothing listens on port 9 of the loopback interface
+        let clock = Arc::new(ManualClock::new());
+        let monitor = Arc::new(
+            StandbyMonitor::new(
+                StandbyConfig::new("http://127.0.0.1:9/status.json", replica.path()),
+                vec![PersistedStore::new(
+                    "feature-flags",
+                    local.path().join("flags.json"),
+                    1,
+                )],
+            )
+            .unwrap()
+            .with_clock(clock.clone()),
+        );
+        let takeover = tokio::spawn({
+            let monitor = monitor.clone();
+            async move { monitor.wait_for_takeover().await }
+        });
+
+        // Fails at 0s and 10s, takes over on the third failure at 20s
+        clock.wait_for_sleeps(1).await;
+        assert!(local.path().join("flags.json").exists());
+        clock.advance(Duration::from_secs(10));
+        clock.wait_for_sleeps(2).await;
+        assert!(!takeover.is_finished());
+        clock.advance(Duration::from_secs(10));
+        takeover.await.unwrap();
+        assert_eq!(clock.sleeps(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_after_takeover() {
+        let mut chain = ToolMiddlewareChain::new();
+        chain.push(Arc::new(StandbyReadOnly::new("http://primary:8080")));
+
+        let read = chain
+            .run(&ToolCall::new("get_lights_status"), async { Ok(json!(1)) })
+            .await;
+        assert_eq!(read.unwrap(), json!(1));
+
+        let control = chain
+            .run(&ToolCall::new("control_lights"), async {
+                panic!("a standby must not execute control tools")
+            })
+            .await;
+        assert!(control.unwrap_err().contains("standby"));
+    }
+}
@@ -0,0 +1,411 @@
+//! Read-after-write state confirmation for control tools
+//!
+//! A successful `send_command` only means the Miniserver accepted the
+//! command. With `confirm: true`, the control's states are snapshot before
+//! sending, then polled until the expected value is reached or the state
+//! moves, and what was observed is reported — so the caller knows whether
+//! the blind actually moved instead of assuming it did.
+//!
+//! Tools do not do this themselves: the
+//! [`ConfirmationMiddleware`](crate::server::tool_middleware::ConfirmationMiddleware)
+//! marks the call's [`PendingConfirmations`] as requested, the
+//! [`WatchingClient`](crate::client::WatchingClient) registers a
+//! [`StateWatch`] for every command sent during the call, and the
+//! middleware waits for them once the tool returned.
+
+use crate::client::LoxoneClient;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long to wait for a state change after a command
+///
+/// The wait runs after the tool returned, outside its time budget.
+pub const DEFAULT_CONFIRM_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Interval between state polls
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Tolerance when comparing numeric states against the expected value
+const TOLERANCE: f64 = 0.01;
+
+/// Observed outcome of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationStatus {
+    /// The watched state reached the expected value
+    Confirmed,
+    /// The state moved but has not reached the expected value yet
+    /// (e.g. a blind that started moving)
+    Changed,
+    /// No state change observed within the timeout
+    Unchanged,
+    /// States could not be read
+    Unavailable,
+}
+
+/// Result of waiting for a state change
+#[derive(Debug, Clone, Serialize)]
+pub struct Confirmation {
+    pub status: ConfirmationStatus,
+    /// State values after the command, by state name
+    pub state: Map<String, Value>,
+    /// State compared against `expected`, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watched: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<f64>,
+    pub waited_ms: u64,
+}
+
+/// State expected after sending `command` to a control of `control_type`
+///
+/// Returns the state name to watch and the target value, if the command
+/// implies one. Commands without a definite target (toggle, stop, moods)
+/// are confirmed by any change of the watched states.
+pub fn expected_state(control_type: &str, command: &str) -> Option<(&'static str, Option<f64>)> {
+    let number = command
+        .rsplit('/')
+        .next()
+        .and_then(|n| n.parse::<f64>().ok());
+    match control_type {
+        "Switch" | "Pushbutton" | "TimedSwitch" => Some((
+            "active",
+            match command {
+                "on" | "On" => Some(1.0),
+                "off" | "Off" => Some(0.0),
+                _ => number.map(|n| if n > 0.0 { 1.0 } else { 0.0 }),
+            },
+        )),
+        "Dimmer" | "EIBDimmer" => Some((
+            "position",
+            match command {
+                "off" | "Off" => Some(0.0),
+                _ => number,
+            },
+        )),
+        // ManualLamelle tilts the slats, which report shadePosition 0.0-1.0
+        "Jalousie" | "Blinds" | "Rolladen" if command.starts_with("ManualLamelle/") => {
+            Some(("shadePosition", number.map(|n| n / 100.0)))
+        }
+        "Jalousie" | "Blinds" | "Rolladen" => Some((
+            "position",
+            match command {
+                "FullUp" => Some(0.0),
+                "FullDown" => Some(1.0),
+                // ManualPosition takes percent, the state reports 0.0-1.0
+                _ if command.starts_with("ManualPosition/") => number.map(|n| n / 100.0),
+                _ => None,
+            },
+        )),
+        "Gate" | "CentralGate" => Some((
+            "position",
+            match command {
+                "open" => Some(1.0),
+                "close" => Some(0.0),
+                _ => None,
+            },
+        )),
+        _ => None,
+    }
+}
+
+/// Snapshot of a control's states taken before a command is sent
+pub struct StateWatch {
+    /// State name → state UUID
+    states: Vec<(String, String)>,
+    before: HashMap<String, Value>,
+    watched: Option<String>,
+    expected: Option<f64>,
+}
+
+impl StateWatch {
+    /// Snapshot the states of `control` before sending `command`
+    pub async fn prepare(client: &Arc<dyn LoxoneClient>, control: &Value, command: &str) -> Self {
+        let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+        let expectation = expected_state(control_type, command);
+
+        let mut states: Vec<(String, String)> = control
+            .get("states")
+            .and_then(Value::as_object)
+            .map(|states| {
+                states
+                    .iter()
+                    .filter_map(|(name, uuid)| Some((name.clone(), uuid.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        states.sort();
+
+        let mut watch = Self {
+            states,
+            before: HashMap::new(),
+            watched: expectation
+                .map(|(name, _)| name.to_string())
+                .filter(|name| control.pointer(&format!("/states/{name}")).is_some()),
+            expected: expectation.and_then(|(_, value)| value),
+        };
+        if watch.watched.is_none() {
+            watch.expected = None;
+        }
+        watch.before = watch.read(client).await.unwrap_or_default();
+        watch
+    }
+
+    /// Poll until the expected value is reached, the state changes, or `timeout`
+    pub async fn confirm(&self, client: &Arc<dyn LoxoneClient>, timeout: Duration) -> Confirmation {
+        let started = Instant::now();
+        let mut last = None;
+        loop {
+            if let Some(current) = self.read(client).await {
+                let status = self.evaluate(&current);
+                last = Some(current);
+                if matches!(
+                    status,
+                    ConfirmationStatus::Confirmed | ConfirmationStatus::Changed
+                ) {
+                    return self.result(status, last, started);
+                }
+            }
+            if started.elapsed() + POLL_INTERVAL > timeout {
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        let status = if last.is_some() && !self.before.is_empty() {
+            ConfirmationStatus::Unchanged
+        } else {
+            ConfirmationStatus::Unavailable
+        };
+        self.result(status, last, started)
+    }
+
+    fn evaluate(&self, current: &HashMap<String, Value>) -> ConfirmationStatus {
+        if let (Some(watched), Some(expected)) = (&self.watched, self.expected)
+            && current
+                .get(watched)
+                .and_then(numeric)
+                .is_some_and(|value| (value - expected).abs() <= TOLERANCE)
+        {
+            return ConfirmationStatus::Confirmed;
+        }
+
+        let changed = |name: &String| {
+            let before = self.before.get(name).and_then(numeric);
+            let after = current.get(name).and_then(numeric);
+            before.is_some() && after.is_some() && before != after
+        };
+        let moved = match &self.watched {
+            Some(watched) => changed(watched),
+            None => current.keys().any(changed),
+        };
+        if moved {
+            ConfirmationStatus::Changed
+        } else {
+            ConfirmationStatus::Unchanged
+        }
+    }
+
+    async fn read(&self, client: &Arc<dyn LoxoneClient>) -> Option<HashMap<String, Value>> {
+        if self.states.is_empty() {
+            return None;
+        }
+        let uuids: Vec<String> = self.states.iter().map(|(_, uuid)| uuid.clone()).collect();
+        let values = client.get_state_values(&uuids).await.ok()?;
+        Some(
+            self.states
+                .iter()
+                .filter_map(|(name, uuid)| Some((name.clone(), values.get(uuid)?.clone())))
+                .collect(),
+        )
+    }
+
+    fn result(
+        &self,
+        status: ConfirmationStatus,
+        state: Option<HashMap<String, Value>>,
+        started: Instant,
+    ) -> Confirmation {
+        Confirmation {
+            status,
+            state: state.unwrap_or_default().into_iter().collect(),
+            watched: self.watched.clone(),
+            expected: self.expected,
+            waited_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+tokio::task_local! {
+    static PENDING: PendingConfirmations;
+}
+
+/// Commands of the current tool call waiting for confirmation
+#[derive(Clone, Default)]
+pub struct PendingConfirmations {
+    inner: Arc<Mutex<PendingState>>,
+}
+
+#[derive(Default)]
+struct PendingState {
+    requested: bool,
+    watches: Vec<PendingWatch>,
+}
+
+struct PendingWatch {
+    uuid: String,
+    command: String,
+    watch: StateWatch,
+    client: Arc<dyn LoxoneClient>,
+}
+
+/// Confirmation of one command sent during a tool call
+#[derive(Debug, Clone)]
+pub struct CommandConfirmation {
+    pub uuid: String,
+    pub command: String,
+    pub confirmation: Confirmation,
+}
+
+impl PendingConfirmations {
+    /// Run `future` with its own set of pending confirmations
+    pub async fn scope<F: std::future::Future>(future: F) -> F::Output {
+        PENDING.scope(Self::default(), future).await
+    }
+
+    /// Pending confirmations of the current task (`None` outside of a scope)
+    pub fn current() -> Option<Self> {
+        PENDING.try_with(Clone::clone).ok()
+    }
+
+    /// Watch the commands sent from now on
+    pub fn request(&self) {
+        self.lock().requested = true;
+    }
+
+    /// Whether the call asked for confirmation
+    pub fn is_requested(&self) -> bool {
+        self.lock().requested
+    }
+
+    /// Register the watch of an accepted command
+    pub fn push(
+        &self,
+        uuid: &str,
+        command: &str,
+        watch: StateWatch,
+        client: Arc<dyn LoxoneClient>,
+    ) {
+        self.lock().watches.push(PendingWatch {
+            uuid: uuid.to_string(),
+            command: command.to_string(),
+            watch,
+            client,
+        });
+    }
+
+    /// Wait for all registered watches concurrently, in the order the
+    /// commands were sent
+    pub async fn confirm_all(&self, timeout: Duration) -> Vec<CommandConfirmation> {
+        let watches = std::mem::take(&mut self.lock().watches);
+        let confirmations = futures::future::join_all(
+            watches
+                .iter()
+                .map(|pending| pending.watch.confirm(&pending.client, timeout)),
+        )
+        .await;
+        watches
+            .into_iter()
+            .zip(confirmations)
+            .map(|(pending, confirmation)| CommandConfirmation {
+                uuid: pending.uuid,
+                command: pending.command,
+                confirmation,
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PendingState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Numeric value of a state (numbers, numeric strings and booleans)
+fn numeric(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn watch(before: f64, expected: Option<f64>) -> StateWatch {
+        StateWatch {
+            states: vec![("position".to_string(), "s-1".to_string())],
+            before: HashMap::from([("position".to_string(), json!(before))]),
+            watched: Some("position".to_string()),
+            expected,
+        }
+    }
+
+    #[test]
+    fn test_expected_state() {
+        assert_eq!(expected_state("Switch", "on"), Some(("active", Some(1.0))));
+        assert_eq!(
+            expected_state("Dimmer", "40"),
+            Some(("position", Some(40.0)))
+        );
+        assert_eq!(
+            expected_state("Jalousie", "ManualPosition/25"),
+            Some(("position", Some(0.25)))
+        );
+        assert_eq!(expected_state("Jalousie", "Stop"), Some(("position", None)));
+        assert_eq!(
+            expected_state("Jalousie", "ManualLamelle/50"),
+            Some(("shadePosition", Some(0.5)))
+        );
+        assert_eq!(
+            expected_state("Gate", "close"),
+            Some(("position", Some(0.0)))
+        );
+        assert_eq!(
+            expected_state("Gate", "partiallyOpen"),
+            Some(("position", None))
+        );
+        assert_eq!(expected_state("LightControllerV2", "plus"), None);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let current = |v: Value| HashMap::from([("position".to_string(), v)]);
+
+        let blind = watch(0.0, Some(1.0));
+        assert_eq!(
+            blind.evaluate(&current(json!(1.0))),
+            ConfirmationStatus::Confirmed
+        );
+        assert_eq!(
+            blind.evaluate(&current(json!("0.3"))),
+            ConfirmationStatus::Changed
+        );
+        assert_eq!(
+            blind.evaluate(&current(json!(0.0))),
+            ConfirmationStatus::Unchanged
+        );
+
+        // Already at the target counts as confirmed
+        assert_eq!(
+            watch(1.0, Some(1.0)).evaluate(&current(json!(1))),
+            ConfirmationStatus::Confirmed
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Device state differences
+//!
+//! Backs the `diff_states` tool, which answers "what changed while I was
+//! away?". Two baselines are supported:
+//!
+//! - a point in time: the state history of the sensor logger records each
+//!   change with its old and new value, so the value at that time is the
+//!   new value of the last change before it, or the old value of the first
+//!   change after it
+//! - a scene snapshot ([`crate::services::scene_snapshot`]), compared
+//!   against the live value of each recorded state
+//!
+//! Numbers compare with a small tolerance, so `"1"` and `1.0` are equal.
+
+use crate::client::LoxoneDevice;
+use crate::services::sensor_logger::SensorStateEntry;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+const NUMBER_TOLERANCE: f64 = 1e-6;
+
+/// What changed on one device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceChange {
+    pub uuid: String,
+    pub name: String,
+    pub room: String,
+    pub category: String,
+    /// Value at the baseline, `null` when unknown
+    pub before: Value,
+    pub after: Value,
+    /// Changes recorded since the baseline (history only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transitions: Option<usize>,
+}
+
+impl DeviceChange {
+    /// Whether the device ended up where it started, e.g. a light switched
+    /// on and off again
+    pub fn reverted(&self) -> bool {
+        same_value(&self.before, &self.after)
+    }
+}
+
+/// Whether two state values are equal, numbers within a tolerance
+pub fn same_value(a: &Value, b: &Value) -> bool {
+    let number = |v: &Value| match v {
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        other => other
+            .as_f64()
+            .or_else(|| other.as_str()?.trim().parse().ok()),
+    };
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => (a - b).abs() < NUMBER_TOLERANCE,
+        _ => a == b,
+    }
+}
+
+/// Value a device had at `at`, from its ordered history
+pub fn value_at(entries: &[SensorStateEntry], at: DateTime<Utc>) -> Option<Value> {
+    match entries.iter().rposition(|entry| entry.timestamp <= at) {
+        Some(index) => Some(entries[index].new_value.clone()),
+        None => entries.first().map(|entry| entry.old_value.clone()),
+    }
+}
+
+/// Devices with recorded changes after `since`
+pub fn diff_history(
+    history: &HashMap<String, Vec<SensorStateEntry>>,
+    devices: &HashMap<String, LoxoneDevice>,
+    since: DateTime<Utc>,
+) -> Vec<DeviceChange> {
+    history
+        .iter()
+        .filter_map(|(uuid, entries)| {
+            let transitions = entries.iter().filter(|e| e.timestamp > since).count();
+            if transitions == 0 {
+                return None;
+            }
+            let device = devices.get(uuid);
+            let last = entries.last()?;
+            Some(DeviceChange {
+                uuid: uuid.clone(),
+                name: device
+                    .map(|d| d.name.clone())
+                    .or_else(|| last.sensor_name.clone())
+                    .unwrap_or_else(|| uuid.clone()),
+                room: device
+                    .and_then(|d| d.room.clone())
+                    .or_else(|| last.room.clone())
+                    .unwrap_or_else(|| "Unknown".to_string()),
+                category: device
+                    .map(|d| d.category.clone())
+                    .unwrap_or_else(|| "Unknown".to_string()),
+                before: value_at(entries, since).unwrap_or(Value::Null),
+                after: last.new_value.clone(),
+                transitions: Some(transitions),
+            })
+        })
+        .collect()
+}
+
+/// Changes grouped by room and category, each group sorted by name
+pub fn group(changes: Vec<DeviceChange>) -> BTreeMap<String, BTreeMap<String, Vec<DeviceChange>>> {
+    let mut grouped: BTreeMap<String, BTreeMap<String, Vec<DeviceChange>>> = BTreeMap::new();
+    for change in changes {
+        grouped
+            .entry(change.room.clone())
+            .or_default()
+            .entry(change.category.clone())
+            .or_default()
+            .push(change);
+    }
+    for categories in grouped.values_mut() {
+        for changes in categories.values_mut() {
+            changes.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn entry(at: DateTime<Utc>, old: Value, new: Value) -> SensorStateEntry {
+        SensorStateEntry {
+            timestamp: at,
+            old_value: old,
+            new_value: new,
+            sensor_name: Some("Front Door".to_string()),
+            sensor_type: None,
+            room: Some("Hall".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_value_comparison() {
+        assert!(same_value(&json!("1"), &json!(1.0)));
+        assert!(same_value(&json!(true), &json!(1)));
+        assert!(!same_value(&json!(0.5), &json!(0.6)));
+        assert!(same_value(&json!("Away"), &json!("Away")));
+        assert!(!same_value(&json!("Away"), &json!(null)));
+    }
+
+    #[test]
+    fn test_diff_history() {
+        let now = Utc::now();
+        let since = now - Duration::hours(8);
+        let history = HashMap::from([
+            (
+                "door".to_string(),
+                vec![
+                    entry(now - Duration::hours(10), json!(1), json!(0)),
+                    entry(now - Duration::hours(2), json!(0), json!(1)),
+                    entry(now - Duration::hours(1), json!(1), json!(0)),
+                ],
+            ),
+            (
+                "window".to_string(),
+                vec![entry(now - Duration::hours(3), json!(0), json!(1))],
+            ),
+            (
+                "quiet".to_string(),
+                vec![entry(now - Duration::hours(9), json!(0), json!(1))],
+            ),
+        ]);
+        let changes = diff_history(&history, &HashMap::new(), since);
+        assert_eq!(changes.len(), 2);
+
+        let grouped = group(changes);
+        let hall = &grouped["Hall"]["Unknown"];
+        let door = hall.iter().find(|c| c.uuid == "door").unwrap();
+        assert_eq!(door.before, json!(0));
+        assert_eq!(door.transitions, Some(2));
+        assert!(door.reverted());
+
+        let window = hall.iter().find(|c| c.uuid == "window").unwrap();
+        assert_eq!(window.before, json!(0));
+        assert!(!window.reverted());
+    }
+}
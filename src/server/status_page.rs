@@ -0,0 +1,288 @@
+//! Read-only public status page
+//!
+//! An optional, unauthenticated listener for embedding the server's state in
+//! home dashboards. It reports only a coarse health (`up`, `degraded` or
+//! `down`) and the uptime, never device names, states or configuration:
+//!
+//! - `GET /` - small self-refreshing HTML page
+//! - `GET /status.json` - `{"status": "up", "uptime_seconds": 3600, ...}`
+//!
+//! Requests are limited per client IP; clients over the limit get `429`.
+//! The page is off unless a port is configured.
+
+use crate::client::LoxoneClient;
+use crate::client::api_budget::ApiBudget;
+use crate::error::{LoxoneError, Result};
+use axum::extract::{ConnectInfo, State};
+use axum::http::{StatusCode, header};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::Utc;
+use humantime_serde::re::humantime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Miniserver degradation (see [`ApiBudget::degradation`]) reported as `degraded`
+pub const DEGRADED_THRESHOLD: f64 = 0.5;
+
+/// Rate limit window
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Status page settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusPageConfig {
+    /// Address to serve the page on
+    pub listen_addr: SocketAddr,
+    /// Requests per minute from one IP address
+    pub requests_per_minute: u32,
+}
+
+impl StatusPageConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.requests_per_minute == 0 {
+            return Err(LoxoneError::config(
+                "Status page rate limit must be at least 1 request per minute",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Coarse server health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    /// Connected to the Miniserver and responding normally
+    Up,
+    /// Connected, but Miniserver responses are slow or failing
+    Degraded,
+    /// Not connected to the Miniserver
+    Down,
+}
+
+impl Health {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Degraded => "degraded",
+            Self::Down => "down",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Self::Up => "#2e7d32",
+            Self::Degraded => "#f9a825",
+            Self::Down => "#c62828",
+        }
+    }
+}
+
+/// Source of the coarse health, taken from the server before it starts
+#[derive(Clone)]
+pub struct StatusProbe {
+    client: Option<Arc<dyn LoxoneClient>>,
+    api_budget: Arc<ApiBudget>,
+}
+
+impl StatusProbe {
+    pub fn new(client: Option<Arc<dyn LoxoneClient>>, api_budget: Arc<ApiBudget>) -> Self {
+        Self { client, api_budget }
+    }
+
+    pub async fn health(&self) -> Health {
+        let connected = match &self.client {
+            Some(client) => client.is_connected().await.unwrap_or(false),
+            None => false,
+        };
+        if !connected {
+            Health::Down
+        } else if self.api_budget.degradation() >= DEGRADED_THRESHOLD {
+            Health::Degraded
+        } else {
+            Health::Up
+        }
+    }
+}
+
+/// Fixed-window request counts per IP address
+#[derive(Debug)]
+struct PeerRateLimit {
+    limit: u32,
+    windows: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl PeerRateLimit {
+    fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request from `peer`; `Err` holds the seconds until the window resets
+    fn admit(&self, peer: IpAddr, now: Instant) -> std::result::Result<(), u64> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() > 1024 {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+        }
+        let (start, count) = windows.entry(peer).or_insert((now, 0));
+        if now.duration_since(*start) >= WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.limit {
+            let reset = WINDOW.saturating_sub(now.duration_since(*start));
+            return Err(reset.as_secs().max(1));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+struct StatusPageState {
+    probe: StatusProbe,
+    started: Instant,
+    rate_limit: PeerRateLimit,
+}
+
+/// Coarse health and uptime as served by `/status.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublicStatus {
+    pub status: Health,
+    pub uptime_seconds: u64,
+    pub checked_at: String,
+}
+
+impl StatusPageState {
+    async fn current(&self) -> PublicStatus {
+        PublicStatus {
+            status: self.probe.health().await,
+            uptime_seconds: self.started.elapsed().as_secs(),
+            checked_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    fn limited(&self, peer: SocketAddr) -> Option<Response> {
+        let retry_after = self.rate_limit.admit(peer.ip(), Instant::now()).err()?;
+        Some(
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                "Too many requests",
+            )
+                .into_response(),
+        )
+    }
+}
+
+/// Routes of the status page
+fn router(state: Arc<StatusPageState>) -> Router {
+    Router::new()
+        .route("/", get(status_html))
+        .route("/status.json", get(status_json))
+        .with_state(state)
+}
+
+async fn status_json(
+    State(state): State<Arc<StatusPageState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+) -> Response {
+    if let Some(limited) = state.limited(peer) {
+        return limited;
+    }
+    let status = state.current().await;
+    (
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"),
+        ],
+        Json(status),
+    )
+        .into_response()
+}
+
+async fn status_html(
+    State(state): State<Arc<StatusPageState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+) -> Response {
+    if let Some(limited) = state.limited(peer) {
+        return limited;
+    }
+    let status = state.current().await;
+    let uptime = humantime::format_duration(Duration::from_secs(status.uptime_seconds));
+    let page = format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><meta http-equiv="refresh" content="30">
+<title>Loxone MCP: {health}</title></head>
+<body style="font-family:sans-serif;margin:1em">
+<span style="display:inline-block;width:.8em;height:.8em;border-radius:50%;background:{color}"></span>
+<strong>{health}</strong> &middot; up {uptime}
+</body></html>
+"#,
+        health = status.status.as_str(),
+        color = status.status.color(),
+    );
+    ([(header::CACHE_CONTROL, "no-store")], Html(page)).into_response()
+}
+
+/// Serve the status page on `config.listen_addr`
+pub async fn spawn(config: StatusPageConfig, probe: StatusProbe) -> Result<JoinHandle<()>> {
+    config.validate()?;
+    let listener = TcpListener::bind(config.listen_addr).await.map_err(|e| {
+        LoxoneError::config(format!(
+            "Failed to bind status page on {}: {e}",
+            config.listen_addr
+        ))
+    })?;
+    let state = Arc::new(StatusPageState {
+        probe,
+        started: Instant::now(),
+        rate_limit: PeerRateLimit::new(config.requests_per_minute),
+    });
+    info!("📟 Public status page on http://{}/", config.listen_addr);
+    Ok(tokio::spawn(async move {
+        let app = router(state).into_make_service_with_connect_info::<SocketAddr>();
+        if let Err(e) = axum::serve(listener, app).await {
+            warn!("Status page stopped: {e}");
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::api_budget::ApiBudgetConfig;
+    use crate::mock::{ScriptedClient, sample_house};
+
+    #[tokio::test]
+    async fn test_health_follows_connection() {
+        let client = Arc::new(ScriptedClient::new(sample_house()));
+        let budget = Arc::new(ApiBudget::new(ApiBudgetConfig::default()));
+        let probe = StatusProbe::new(Some(client.clone()), budget.clone());
+        assert_eq!(probe.health().await, Health::Up);
+
+        client.set_connected(false);
+        assert_eq!(probe.health().await, Health::Down);
+        assert_eq!(StatusProbe::new(None, budget).health().await, Health::Down);
+    }
+
+    #[test]
+    fn test_rate_limit_per_peer() {
+        let limit = PeerRateLimit::new(2);
+        let (a, b): (IpAddr, IpAddr) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+        let now = Instant::now();
+        assert!(limit.admit(a, now).is_ok());
+        assert!(limit.admit(a, now).is_ok());
+        assert_eq!(limit.admit(a, now + Duration::from_secs(20)), Err(40));
+        assert!(limit.admit(b, now).is_ok());
+        assert!(limit.admit(a, now + WINDOW).is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+//! Wind lock of blinds and awnings
+//!
+//! Backs the `get_storm_protection` tool. The Miniserver's automatic
+//! shading retracts blinds and awnings in a storm and locks them until the
+//! wind has calmed down; locked blinds ignore commands. Jalousie states:
+//!
+//! - `safetyActive`: 1 while the wind lock (safety shutdown) holds
+//! - `infoText`: reason shown in the app, e.g. "Wind alarm"
+//!
+//! The Miniserver does not report when the lock releases. It holds the lock
+//! until the wind has stayed below its threshold for
+//! [`RELEASE_DELAY_MINUTES`]; the release time is estimated from the wind
+//! readings of [`crate::services::weather_protection`].
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Minutes of calm after which the Miniserver releases the wind lock
+pub const RELEASE_DELAY_MINUTES: i64 = 15;
+
+const LOCK_STATE: &str = "safetyActive";
+const INFO_STATE: &str = "infoText";
+
+/// State UUIDs of the wind lock and its reason
+pub fn state_uuids(control: &Value) -> (Option<String>, Option<String>) {
+    let state = |name: &str| {
+        control
+            .get("states")
+            .and_then(|states| states.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    (state(LOCK_STATE), state(INFO_STATE))
+}
+
+/// Whether the wind lock holds, and why, from the state values
+pub fn read_lock(control: &Value, values: &HashMap<String, Value>) -> (bool, Option<String>) {
+    let (lock, info) = state_uuids(control);
+    let locked = lock
+        .and_then(|uuid| values.get(&uuid))
+        .and_then(|v| match v {
+            Value::Bool(b) => Some(*b),
+            other => other
+                .as_f64()
+                .or_else(|| other.as_str()?.trim().parse().ok())
+                .map(|n| n != 0.0),
+        })
+        .unwrap_or(false);
+    let reason = info
+        .and_then(|uuid| values.get(&uuid))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string);
+    (locked, reason)
+}
+
+/// When the wind lock is expected to release
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "release", rename_all = "snake_case")]
+pub enum Release {
+    /// Calm since `calm_since`; releases at `at`
+    Expected {
+        calm_since: DateTime<Utc>,
+        at: DateTime<Utc>,
+    },
+    /// Still windy; releases once the wind stays below the threshold
+    AfterCalm { wind_kmh: f64, threshold_kmh: f64 },
+    /// No wind reading
+    Unknown,
+}
+
+/// Estimate the release from the current wind and the start of the calm
+pub fn estimate_release(
+    wind_kmh: Option<f64>,
+    threshold_kmh: f64,
+    calm_since: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Release {
+    match (wind_kmh, calm_since) {
+        (Some(wind), _) if wind > threshold_kmh => Release::AfterCalm {
+            wind_kmh: wind,
+            threshold_kmh,
+        },
+        // Calm, but not tracked yet: the lock holds at least the delay
+        (Some(_), calm_since) => {
+            let calm_since = calm_since.unwrap_or(now);
+            Release::Expected {
+                calm_since,
+                at: calm_since + Duration::minutes(RELEASE_DELAY_MINUTES),
+            }
+        }
+        (None, _) => Release::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lock_and_release() {
+        let control = json!({"states": {"safetyActive": "s-lock", "infoText": "s-info"}});
+        let values = HashMap::from([
+            ("s-lock".to_string(), json!("1")),
+            ("s-info".to_string(), json!("Wind alarm ")),
+        ]);
+        assert_eq!(
+            read_lock(&control, &values),
+            (true, Some("Wind alarm".to_string()))
+        );
+        assert_eq!(read_lock(&control, &HashMap::new()), (false, None));
+
+        let now = Utc::now();
+        assert_eq!(
+            estimate_release(Some(62.0), 50.0, None, now),
+            Release::AfterCalm {
+                wind_kmh: 62.0,
+                threshold_kmh: 50.0
+            }
+        );
+        let calm_since = now - Duration::minutes(5);
+        assert_eq!(
+            estimate_release(Some(20.0), 50.0, Some(calm_since), now),
+            Release::Expected {
+                calm_since,
+                at: now + Duration::minutes(10)
+            }
+        );
+        assert_eq!(estimate_release(None, 50.0, None, now), Release::Unknown);
+    }
+}
@@ -0,0 +1,248 @@
+//! Synthetic resource changes for load testing
+//!
+//! Only built with the `chaos` feature (and in tests). A [`ChaosInjector`]
+//! publishes fake [`ResourceChange`]s on the coordinator's event channel at a
+//! configurable rate, so clients and the dispatcher's buffering and lag
+//! handling can be exercised without a Miniserver:
+//!
+//! ```bash
+//! cargo run --features chaos -- http --dev-mode
+//! LOXONE_CHAOS_RATE=500 LOXONE_CHAOS_BURST=50 \
+//!     LOXONE_CHAOS_RESOURCES=loxone://devices/all,loxone://sensors/temperature
+//! ```
+//!
+//! Changes are sent in bursts of `burst` every `burst / rate` seconds; large
+//! bursts overrun the broadcast channel and the event buffer on purpose.
+//! Synthetic changes carry `"synthetic": true` in their metadata.
+
+use super::types::{ResourceChange, ResourceChangeType, SubscriptionEvent};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Settings of a chaos run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosConfig {
+    /// Synthetic changes per second
+    pub rate_per_sec: f64,
+    /// Changes published back to back per tick
+    pub burst: usize,
+    /// Resource URIs changes are reported for, picked at random
+    pub resources: Vec<String>,
+    /// Number of distinct fake devices
+    pub devices: usize,
+    /// Stop after this many changes
+    pub limit: Option<u64>,
+    /// Seed for reproducible runs
+    pub seed: Option<u64>,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            rate_per_sec: 10.0,
+            burst: 1,
+            resources: vec!["loxone://devices/all".to_string()],
+            devices: 100,
+            limit: None,
+            seed: None,
+        }
+    }
+}
+
+impl ChaosConfig {
+    /// Settings from `LOXONE_CHAOS_*`; `None` unless `LOXONE_CHAOS_RATE` is set
+    pub fn from_env() -> Option<Self> {
+        fn parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+            let value = std::env::var(name).ok()?;
+            value
+                .trim()
+                .parse()
+                .map_err(|_| warn!("Ignoring invalid {name}={value}"))
+                .ok()
+        }
+
+        let defaults = Self::default();
+        Some(Self {
+            rate_per_sec: parse("LOXONE_CHAOS_RATE")?,
+            burst: parse("LOXONE_CHAOS_BURST").unwrap_or(defaults.burst),
+            resources: std::env::var("LOXONE_CHAOS_RESOURCES")
+                .ok()
+                .map(|list| {
+                    list.split(',')
+                        .map(str::trim)
+                        .filter(|uri| !uri.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|list| !list.is_empty())
+                .unwrap_or(defaults.resources),
+            devices: parse("LOXONE_CHAOS_DEVICES").unwrap_or(defaults.devices),
+            limit: parse("LOXONE_CHAOS_LIMIT"),
+            seed: parse("LOXONE_CHAOS_SEED"),
+        })
+    }
+
+    /// Time between bursts
+    pub fn tick(&self) -> Duration {
+        let rate = self.rate_per_sec.max(0.001);
+        Duration::from_secs_f64(self.burst.max(1) as f64 / rate)
+    }
+}
+
+/// Counters of a chaos run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChaosStats {
+    /// Changes published
+    pub injected: u64,
+    /// Changes published while no dispatcher was listening
+    pub unheard: u64,
+}
+
+/// Publishes synthetic resource changes
+pub struct ChaosInjector {
+    config: ChaosConfig,
+    events: broadcast::Sender<SubscriptionEvent>,
+    injected: AtomicU64,
+    unheard: AtomicU64,
+    stopped: AtomicBool,
+}
+
+impl ChaosInjector {
+    pub fn new(config: ChaosConfig, events: broadcast::Sender<SubscriptionEvent>) -> Self {
+        Self {
+            config,
+            events,
+            injected: AtomicU64::new(0),
+            unheard: AtomicU64::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn stats(&self) -> ChaosStats {
+        ChaosStats {
+            injected: self.injected.load(Ordering::Relaxed),
+            unheard: self.unheard.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stop after the current burst
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Publish changes until stopped or the limit is reached
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn start(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let injector = self.clone();
+        warn!(
+            "🧪 Chaos mode: injecting {} synthetic changes/s in bursts of {}",
+            injector.config.rate_per_sec, injector.config.burst
+        );
+        tokio::spawn(async move {
+            let mut rng = match injector.config.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_os_rng(),
+            };
+            let mut interval = tokio::time::interval(injector.config.tick());
+            while !injector.stopped.load(Ordering::Relaxed) {
+                interval.tick().await;
+                for _ in 0..injector.config.burst.max(1) {
+                    let seq = injector.injected.load(Ordering::Relaxed);
+                    if injector.config.limit.is_some_and(|limit| seq >= limit) {
+                        injector.stop();
+                        break;
+                    }
+                    let change = injector.synthetic_change(&mut rng, seq);
+                    if injector
+                        .events
+                        .send(SubscriptionEvent::ResourceChanged { change })
+                        .is_err()
+                    {
+                        injector.unheard.fetch_add(1, Ordering::Relaxed);
+                    }
+                    injector.injected.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            info!("🧪 Chaos mode stopped: {:?}", injector.stats());
+        })
+    }
+
+    fn synthetic_change(&self, rng: &mut StdRng, seq: u64) -> ResourceChange {
+        const TYPES: [ResourceChangeType; 4] = [
+            ResourceChangeType::DeviceState,
+            ResourceChangeType::SensorValue,
+            ResourceChangeType::Energy,
+            ResourceChangeType::Weather,
+        ];
+        let resource = match self.config.resources.len() {
+            0 => "loxone://devices/all".to_string(),
+            n => self.config.resources[rng.random_range(0..n)].clone(),
+        };
+        let device = rng.random_range(0..self.config.devices.max(1));
+        ResourceChange {
+            resource_uri: resource,
+            change_type: TYPES[rng.random_range(0..TYPES.len())].clone(),
+            timestamp: SystemTime::now(),
+            previous_value: None,
+            new_value: json!({ "value": rng.random_range(0.0..100.0) }),
+            loxone_uuid: Some(format!("chaos-{device:05}")),
+            metadata: HashMap::from([
+                ("synthetic".to_string(), json!(true)),
+                ("sequence".to_string(), json!(seq)),
+            ]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::subscription::SubscriptionCoordinator;
+    use crate::server::subscription::types::{ClientInfo, ClientTransport};
+
+    #[tokio::test]
+    async fn test_chaos_changes_reach_subscribers() {
+        let coordinator = SubscriptionCoordinator::new().await.unwrap();
+        coordinator.start().await.unwrap();
+        let client = ClientInfo {
+            id: "load-test".to_string(),
+            transport: ClientTransport::LongPoll,
+            capabilities: vec!["resources".to_string()],
+            connected_at: SystemTime::now(),
+        };
+        coordinator
+            .subscribe_client(client, "loxone://devices/all".to_string(), None)
+            .await
+            .unwrap();
+
+        let injector = coordinator.start_chaos(ChaosConfig {
+            rate_per_sec: 2000.0,
+            burst: 10,
+            limit: Some(30),
+            seed: Some(7),
+            ..ChaosConfig::default()
+        });
+        let buffer = coordinator.event_buffer();
+        let mut received = 0;
+        let mut cursor = 0;
+        while received < 30 {
+            let batch = buffer
+                .wait_since("load-test", cursor, Duration::from_secs(5))
+                .await;
+            assert!(!batch.events.is_empty(), "only {received} changes arrived");
+            received += batch.events.len();
+            cursor = batch.cursor;
+        }
+        assert_eq!(received, 30);
+        assert_eq!(injector.stats().injected, 30);
+    }
+}
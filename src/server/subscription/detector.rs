@@ -6,6 +6,7 @@
 use super::types::{ChangeDetectorStats, ResourceChange, ResourceChangeType, SubscriptionEvent};
 use crate::client::LoxoneClient;
 use crate::error::{LoxoneError, Result};
+use crate::server::energy_flow;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -283,6 +284,9 @@ impl ResourceChangeDetector {
                         "AudioZone" => "loxone://audio/zones",
                         "TempSensor" => "loxone://sensors/temperature",
                         "SecuritySwitch" => "loxone://security/status",
+                        _ if !energy_flow::flow_states(control_data).is_empty() => {
+                            energy_flow::ENERGY_FLOW_URI
+                        }
                         _ => "loxone://devices/all",
                     };
 
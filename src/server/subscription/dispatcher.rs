@@ -4,6 +4,7 @@
 //! transport protocols (stdio, HTTP/SSE, WebSocket).
 
 use super::manager::ResourceSubscriptionManager;
+use super::poll::EventBuffer;
 use super::types::{
     ClientInfo, ClientTransport, NotificationDispatcherStats, ResourceChange,
     ResourceChangeNotification, SubscriptionEvent,
@@ -32,6 +33,9 @@ pub struct NotificationDispatcher {
 
     /// Notification timeout
     notification_timeout: Duration,
+
+    /// Delivered notifications, read by SSE streams and long-poll requests
+    buffer: Arc<EventBuffer>,
 }
 
 impl NotificationDispatcher {
@@ -46,9 +50,15 @@ impl NotificationDispatcher {
             max_retries: 3,
             retry_delay: Duration::from_millis(100),
             notification_timeout: Duration::from_secs(5),
+            buffer: Arc::new(EventBuffer::default()),
         }
     }
 
+    /// Buffer the dispatched notifications are queued in
+    pub fn event_buffer(&self) -> Arc<EventBuffer> {
+        self.buffer.clone()
+    }
+
     /// Start processing notifications
     pub async fn start_processing(
         &self,
@@ -74,6 +84,7 @@ impl NotificationDispatcher {
             let max_retries = self.max_retries;
             let retry_delay = self.retry_delay;
             let notification_timeout = self.notification_timeout;
+            let buffer = self.buffer.clone();
 
             tokio::spawn(async move {
                 loop {
@@ -87,6 +98,7 @@ impl NotificationDispatcher {
                             if let Err(e) = Self::handle_subscription_event(
                                 event,
                                 &subscription_manager,
+                                &buffer,
                                 &stats,
                                 max_retries,
                                 retry_delay,
@@ -136,6 +148,7 @@ impl NotificationDispatcher {
     async fn handle_subscription_event(
         event: SubscriptionEvent,
         subscription_manager: &Arc<ResourceSubscriptionManager>,
+        buffer: &EventBuffer,
         stats: &Arc<RwLock<NotificationDispatcherStats>>,
         max_retries: u32,
         retry_delay: Duration,
@@ -146,6 +159,7 @@ impl NotificationDispatcher {
                 Self::handle_resource_change(
                     change,
                     subscription_manager,
+                    buffer,
                     stats,
                     max_retries,
                     retry_delay,
@@ -193,6 +207,7 @@ impl NotificationDispatcher {
     async fn handle_resource_change(
         change: ResourceChange,
         subscription_manager: &Arc<ResourceSubscriptionManager>,
+        buffer: &EventBuffer,
         stats: &Arc<RwLock<NotificationDispatcherStats>>,
         max_retries: u32,
         retry_delay: Duration,
@@ -225,6 +240,9 @@ impl NotificationDispatcher {
         let mut failed_notifications = 0;
 
         for subscriber in subscribers {
+            // Queue first so SSE streams and long-polls see it even if a push fails
+            buffer.push(&subscriber.id, notification.clone()).await;
+
             let notify_result = Self::send_notification_to_client(
                 &subscriber,
                 &notification,
@@ -348,6 +366,8 @@ impl NotificationDispatcher {
             ClientTransport::WebSocket { connection_id } => {
                 Self::send_websocket_notification(client, notification, connection_id).await
             }
+            // Long-poll clients fetch the buffered notification themselves
+            ClientTransport::LongPoll => Ok(()),
         }
     }
 
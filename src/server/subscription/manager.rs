@@ -327,6 +327,7 @@ impl ResourceSubscriptionManager {
                 ClientTransport::Stdio => "stdio",
                 ClientTransport::HttpSse { .. } => "http_sse",
                 ClientTransport::WebSocket { .. } => "websocket",
+                ClientTransport::LongPoll => "long_poll",
             };
             *transport_counts
                 .entry(transport_name.to_string())
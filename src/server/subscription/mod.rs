@@ -2,16 +2,20 @@
 //!
 //! This module implements real-time resource change notifications for MCP clients.
 //! It provides subscription management, change detection, and notification dispatch
-//! across multiple transport protocols (stdio, HTTP/SSE).
+//! across multiple transport protocols (stdio, HTTP/SSE, HTTP long-poll).
 
+#[cfg(any(test, feature = "chaos"))]
+pub mod chaos;
 pub mod detector;
 pub mod dispatcher;
 pub mod manager;
+pub mod poll;
 pub mod types;
 
 pub use detector::ResourceChangeDetector;
 pub use dispatcher::NotificationDispatcher;
 pub use manager::ResourceSubscriptionManager;
+pub use poll::EventBuffer;
 pub use types::{
     ClientInfo, ClientSubscription, NotificationTarget, ResourceChange, SubscriptionEvent,
     SubscriptionFilter,
@@ -93,10 +97,26 @@ impl SubscriptionCoordinator {
             }
         });
 
+        #[cfg(feature = "chaos")]
+        if let Some(config) = chaos::ChaosConfig::from_env() {
+            self.start_chaos(config);
+        }
+
         debug!("✅ Subscription system started successfully");
         Ok(())
     }
 
+    /// Publish synthetic resource changes (load testing only)
+    #[cfg(any(test, feature = "chaos"))]
+    pub fn start_chaos(&self, config: chaos::ChaosConfig) -> Arc<chaos::ChaosInjector> {
+        let injector = Arc::new(chaos::ChaosInjector::new(
+            config,
+            self.system_events.clone(),
+        ));
+        injector.start();
+        injector
+    }
+
     /// Subscribe a client to resource changes
     pub async fn subscribe_client(
         &self,
@@ -154,6 +174,16 @@ impl SubscriptionCoordinator {
         Ok(())
     }
 
+    /// Buffer of delivered notifications backing SSE and `/events/poll`
+    pub fn event_buffer(&self) -> Arc<EventBuffer> {
+        self.notification_dispatcher.event_buffer()
+    }
+
+    /// Router serving the long-poll endpoint on the dispatcher's buffer
+    pub fn poll_router(&self) -> axum::Router {
+        poll::router(self.event_buffer())
+    }
+
     /// Get subscription statistics for monitoring
     pub async fn get_statistics(&self) -> SubscriptionStatistics {
         let subscription_stats = self.subscription_manager.get_statistics().await;
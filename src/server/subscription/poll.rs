@@ -0,0 +1,221 @@
+//! Long-poll fallback for clients that cannot hold SSE streams
+//!
+//! The notification dispatcher appends every notification it delivers to an
+//! [`EventBuffer`]. SSE connections and the `/events/poll` endpoint read from
+//! the same buffer: a poll returns the events queued for the client after the
+//! given cursor, or waits up to the requested timeout for new ones.
+//!
+//! ```text
+//! GET /events/poll?client_id=n8n&cursor=41&timeout=25
+//! ```
+//!
+//! On the HTTP transports the client id is the MCP session id, and the
+//! gateway serves a session's events only to the key that opened it.
+
+use super::types::ResourceChangeNotification;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
+
+/// Path of the long-poll endpoint
+pub const POLL_PATH: &str = "/events/poll";
+
+/// Events kept for polling clients before the oldest are dropped
+pub const DEFAULT_BUFFER_CAPACITY: usize = 1000;
+
+/// Longest a poll request may wait for new events
+pub const MAX_POLL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A notification queued for a client
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferedEvent {
+    /// Position in the buffer; poll with this as cursor to get later events
+    pub cursor: u64,
+    pub client_id: String,
+    pub notification: ResourceChangeNotification,
+}
+
+/// Events returned by a poll
+#[derive(Debug, Clone, Serialize)]
+pub struct PollBatch {
+    pub events: Vec<BufferedEvent>,
+    /// Cursor to pass to the next poll
+    pub cursor: u64,
+    /// Whether events after the requested cursor were already dropped
+    pub missed: bool,
+}
+
+/// Bounded buffer of delivered notifications, shared by SSE and long-poll
+pub struct EventBuffer {
+    events: RwLock<VecDeque<BufferedEvent>>,
+    next_cursor: RwLock<u64>,
+    capacity: usize,
+    notify: Notify,
+}
+
+impl Default for EventBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUFFER_CAPACITY)
+    }
+}
+
+impl EventBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: RwLock::new(VecDeque::new()),
+            next_cursor: RwLock::new(1),
+            capacity: capacity.max(1),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Queue a notification for a client and wake waiting polls
+    pub async fn push(&self, client_id: &str, notification: ResourceChangeNotification) -> u64 {
+        let mut events = self.events.write().await;
+        let mut next_cursor = self.next_cursor.write().await;
+        let cursor = *next_cursor;
+        *next_cursor += 1;
+
+        events.push_back(BufferedEvent {
+            cursor,
+            client_id: client_id.to_string(),
+            notification,
+        });
+        while events.len() > self.capacity {
+            events.pop_front();
+        }
+        drop(events);
+
+        self.notify.notify_waiters();
+        cursor
+    }
+
+    /// Events for `client_id` queued after `cursor`
+    pub async fn since(&self, client_id: &str, cursor: u64) -> PollBatch {
+        let events = self.events.read().await;
+        let oldest = events.front().map(|e| e.cursor);
+        let batch: Vec<_> = events
+            .iter()
+            .filter(|e| e.cursor > cursor && e.client_id == client_id)
+            .cloned()
+            .collect();
+        let latest = *self.next_cursor.read().await - 1;
+
+        PollBatch {
+            cursor: batch.last().map(|e| e.cursor).unwrap_or(latest.max(cursor)),
+            missed: oldest.is_some_and(|oldest| oldest > cursor + 1),
+            events: batch,
+        }
+    }
+
+    /// Like [`Self::since`], waiting up to `timeout` when nothing is queued yet
+    pub async fn wait_since(&self, client_id: &str, cursor: u64, timeout: Duration) -> PollBatch {
+        let deadline = tokio::time::Instant::now() + timeout.min(MAX_POLL_TIMEOUT);
+        loop {
+            // Register before checking so a push in between is not lost
+            let notified = self.notify.notified();
+            let batch = self.since(client_id, cursor).await;
+            if !batch.events.is_empty() {
+                return batch;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return batch;
+            }
+        }
+    }
+}
+
+/// Query parameters of `/events/poll`
+#[derive(Debug, Deserialize)]
+pub struct PollQuery {
+    pub client_id: String,
+    /// Cursor returned by the previous poll (0 for the first)
+    #[serde(default)]
+    pub cursor: u64,
+    /// Seconds to wait for new events
+    #[serde(default = "default_timeout_secs")]
+    pub timeout: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    25
+}
+
+/// Router serving the long-poll endpoint, to be merged next to the SSE routes
+pub fn router(buffer: Arc<EventBuffer>) -> Router {
+    Router::new()
+        .route(POLL_PATH, get(poll_events))
+        .with_state(buffer)
+}
+
+async fn poll_events(
+    State(buffer): State<Arc<EventBuffer>>,
+    Query(query): Query<PollQuery>,
+) -> std::result::Result<Json<PollBatch>, (StatusCode, String)> {
+    if query.client_id.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "client_id is required".to_string()));
+    }
+    let batch = buffer
+        .wait_since(
+            &query.client_id,
+            query.cursor,
+            Duration::from_secs(query.timeout),
+        )
+        .await;
+    Ok(Json(batch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::subscription::types::{ResourceChange, ResourceChangeType};
+    use std::collections::HashMap;
+    use std::time::SystemTime;
+
+    fn notification(uri: &str) -> ResourceChangeNotification {
+        ResourceChangeNotification::new(ResourceChange {
+            resource_uri: uri.to_string(),
+            change_type: ResourceChangeType::DeviceState,
+            timestamp: SystemTime::now(),
+            previous_value: None,
+            new_value: serde_json::json!({"state": "on"}),
+            loxone_uuid: None,
+            metadata: HashMap::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_poll_cursor_and_overflow() {
+        let buffer = Arc::new(EventBuffer::new(2));
+        buffer.push("a", notification("loxone://rooms")).await;
+        buffer.push("b", notification("loxone://rooms")).await;
+
+        let batch = buffer.since("a", 0).await;
+        assert_eq!(batch.events.len(), 1);
+        assert_eq!(batch.cursor, 1);
+        assert!(!batch.missed);
+
+        // A waiting poll is woken by the next push for its client
+        let waiter = {
+            let buffer = buffer.clone();
+            tokio::spawn(async move { buffer.wait_since("a", 1, Duration::from_secs(5)).await })
+        };
+        tokio::task::yield_now().await;
+        buffer.push("a", notification("loxone://devices/all")).await;
+        let batch = waiter.await.unwrap();
+        assert_eq!(batch.events.len(), 1);
+        assert_eq!(batch.cursor, 3);
+
+        // The first event has been dropped by now
+        assert!(buffer.since("a", 0).await.missed);
+        let empty = buffer.wait_since("a", 3, Duration::from_millis(10)).await;
+        assert!(empty.events.is_empty());
+        assert_eq!(empty.cursor, 3);
+    }
+}
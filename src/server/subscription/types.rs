@@ -37,6 +37,9 @@ pub enum ClientTransport {
         /// WebSocket connection ID
         connection_id: String,
     },
+
+    /// HTTP long-poll on `/events/poll` (clients that cannot hold SSE streams)
+    LongPoll,
 }
 
 /// Client subscription to a specific resource
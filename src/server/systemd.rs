@@ -0,0 +1,320 @@
+//! systemd integration: socket activation, readiness and watchdog notification
+//!
+//! When launched from a `.socket` unit the listening socket is inherited via
+//! `LISTEN_FDS`. The framework HTTP transport always binds its own port, so in
+//! that case the transport is started on a free loopback port and connections
+//! accepted on the inherited socket are forwarded to it.
+//!
+//! Readiness (`READY=1`), shutdown (`STOPPING=1`) and watchdog keep-alives are
+//! reported through `sd_notify`. All notification calls are no-ops when the
+//! process was not started by systemd or the `systemd` feature is disabled.
+//!
+//! The unit generator is always available so unit files can be produced on a
+//! development machine and copied to the target host.
+
+use crate::error::{LoxoneError, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(feature = "systemd")]
+use tracing::{debug, info, warn};
+
+/// Options for generating systemd unit files
+#[derive(Debug, Clone)]
+pub struct UnitOptions {
+    /// Absolute path of the server binary
+    pub binary: PathBuf,
+    /// Transport subcommand to run (`http` or `streamable-http`)
+    pub transport: String,
+    /// Public HTTP port
+    pub port: u16,
+    /// System user the service runs as
+    pub user: Option<String>,
+    /// Credential ID passed to the server (from loxone-mcp-auth)
+    pub credential_id: Option<String>,
+    /// Generate a companion `.socket` unit for socket activation
+    pub socket_activation: bool,
+    /// Watchdog interval; `None` disables the watchdog
+    pub watchdog: Option<Duration>,
+}
+
+impl Default for UnitOptions {
+    fn default() -> Self {
+        Self {
+            binary: PathBuf::from("/usr/local/bin/loxone-mcp-server"),
+            transport: "http".to_string(),
+            port: 3001,
+            user: None,
+            credential_id: None,
+            socket_activation: false,
+            watchdog: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl UnitOptions {
+    /// Validate the options before rendering
+    pub fn validate(&self) -> Result<()> {
+        if !self.binary.is_absolute() {
+            return Err(LoxoneError::config(format!(
+                "systemd requires an absolute binary path, got '{}'",
+                self.binary.display()
+            )));
+        }
+        if !matches!(self.transport.as_str(), "http" | "streamable-http") {
+            return Err(LoxoneError::config(format!(
+                "Unsupported transport for a systemd service: '{}' (use http or streamable-http)",
+                self.transport
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Render the `.service` unit
+pub fn generate_service_unit(options: &UnitOptions) -> String {
+    let mut exec = format!(
+        "{} {} --port {}",
+        options.binary.display(),
+        options.transport,
+        options.port
+    );
+    if let Some(credential_id) = &options.credential_id {
+        exec.push_str(&format!(" --credential-id {credential_id}"));
+    }
+
+    let mut unit = String::from("[Unit]\nDescription=Loxone MCP Server\n");
+    unit.push_str("After=network-online.target\nWants=network-online.target\n");
+    if options.socket_activation {
+        unit.push_str("Requires=loxone-mcp.socket\nAfter=loxone-mcp.socket\n");
+    }
+
+    unit.push_str("\n[Service]\nType=notify\nNotifyAccess=main\n");
+    unit.push_str(&format!("ExecStart={exec}\n"));
+    if let Some(user) = &options.user {
+        unit.push_str(&format!("User={user}\nGroup={user}\n"));
+    }
+    if let Some(watchdog) = options.watchdog {
+        unit.push_str(&format!("WatchdogSec={}\n", watchdog.as_secs().max(1)));
+    }
+    unit.push_str("Restart=on-failure\nRestartSec=5\n");
+    unit.push_str("NoNewPrivileges=true\nProtectSystem=strict\nProtectHome=read-only\n");
+    unit.push_str("PrivateTmp=true\nStateDirectory=loxone-mcp\n");
+
+    if !options.socket_activation {
+        unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+    }
+    unit
+}
+
+/// Render the `.socket` unit used for socket activation
+pub fn generate_socket_unit(options: &UnitOptions) -> String {
+    format!(
+        "[Unit]\nDescription=Loxone MCP Server socket\n\n\
+         [Socket]\nListenStream={}\nNoDelay=true\n\n\
+         [Install]\nWantedBy=sockets.target\n",
+        options.port
+    )
+}
+
+/// Take the first TCP listener passed by systemd socket activation, if any
+#[cfg(feature = "systemd")]
+pub fn take_activated_listener() -> Result<Option<std::net::TcpListener>> {
+    let mut fds = listenfd::ListenFd::from_env();
+    if fds.len() == 0 {
+        return Ok(None);
+    }
+    if fds.len() > 1 {
+        warn!(
+            "systemd passed {} sockets; only the first one is used",
+            fds.len()
+        );
+    }
+
+    let listener = fds
+        .take_tcp_listener(0)
+        .map_err(|e| LoxoneError::config(format!("Inherited socket is not a TCP listener: {e}")))?;
+    if let Some(listener) = &listener {
+        info!(
+            "🔌 Using systemd socket activation on {}",
+            listener
+                .local_addr()
+                .map(|a| a.to_string())
+                .unwrap_or_else(|_| "unknown address".to_string())
+        );
+    }
+    Ok(listener)
+}
+
+/// Socket activation is unavailable without the `systemd` feature
+#[cfg(not(feature = "systemd"))]
+pub fn take_activated_listener() -> Result<Option<std::net::TcpListener>> {
+    if std::env::var_os("LISTEN_FDS").is_some() {
+        return Err(LoxoneError::config(
+            "Started with systemd socket activation but the server was built without the `systemd` feature",
+        ));
+    }
+    Ok(None)
+}
+
+/// Find a free loopback port for the transport behind an inherited socket
+pub fn free_loopback_port() -> Result<u16> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Forward connections accepted on the inherited socket to the local transport
+#[cfg(feature = "systemd")]
+pub fn forward_activated_listener(
+    listener: std::net::TcpListener,
+    upstream_port: u16,
+) -> Result<tokio::task::JoinHandle<()>> {
+    use crate::server::connection_limits::{self, ConnectionLimiter};
+    use tokio::net::TcpListener;
+
+    listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(listener)?;
+    let upstream = std::net::SocketAddr::from(([127, 0, 0, 1], upstream_port));
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let (inbound, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    ConnectionLimiter::global().record_accept_error();
+                    warn!("Activated socket accept failed: {e}");
+                    continue;
+                }
+            };
+            let permit = match ConnectionLimiter::global().admit(peer) {
+                Ok(permit) => permit,
+                Err(rejection) => {
+                    debug!("Refusing activated connection from {peer}: {rejection:?}");
+                    tokio::spawn(connection_limits::refuse(inbound, rejection));
+                    continue;
+                }
+            };
+
+            tokio::spawn(async move {
+                let _session = crate::server::client_sessions::ClientSessionRegistry::global()
+                    .open_guarded(
+                        crate::server::client_sessions::SessionTransport::SocketActivated,
+                        Some(peer),
+                    );
+                connection_limits::forward(inbound, upstream, permit).await;
+            });
+        }
+    }))
+}
+
+/// Socket activation is unavailable without the `systemd` feature
+#[cfg(not(feature = "systemd"))]
+pub fn forward_activated_listener(
+    _listener: std::net::TcpListener,
+    _upstream_port: u16,
+) -> Result<tokio::task::JoinHandle<()>> {
+    Err(LoxoneError::config(
+        "Socket activation requires the `systemd` feature",
+    ))
+}
+
+/// Report readiness to systemd and start the watchdog if one is configured
+#[cfg(feature = "systemd")]
+pub fn notify_ready(status: &str) {
+    use sd_notify::NotifyState;
+
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Ready, NotifyState::Status(status)]) {
+        warn!("sd_notify READY failed: {e}");
+    }
+
+    let mut usec = 0;
+    if sd_notify::watchdog_enabled(false, &mut usec) {
+        // Ping at half the configured interval as recommended by sd_watchdog_enabled(3)
+        let interval = Duration::from_micros(usec / 2).max(Duration::from_millis(500));
+        info!("🐕 systemd watchdog enabled, pinging every {:?}", interval);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+                    warn!("sd_notify WATCHDOG failed: {e}");
+                }
+            }
+        });
+    }
+}
+
+/// Readiness notification is a no-op without the `systemd` feature
+#[cfg(not(feature = "systemd"))]
+pub fn notify_ready(_status: &str) {}
+
+/// Tell systemd the service is shutting down
+#[cfg(feature = "systemd")]
+pub fn notify_stopping() {
+    if let Err(e) = sd_notify::notify(true, &[sd_notify::NotifyState::Stopping]) {
+        debug!("sd_notify STOPPING failed: {e}");
+    }
+}
+
+/// Shutdown notification is a no-op without the `systemd` feature
+#[cfg(not(feature = "systemd"))]
+pub fn notify_stopping() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_unit_contents() {
+        let options = UnitOptions {
+            user: Some("loxone".to_string()),
+            credential_id: Some("abc123".to_string()),
+            ..Default::default()
+        };
+        let unit = generate_service_unit(&options);
+
+        assert!(unit.contains("Type=notify"));
+        assert!(unit.contains(
+            "ExecStart=/usr/local/bin/loxone-mcp-server http --port 3001 --credential-id abc123"
+        ));
+        assert!(unit.contains("User=loxone"));
+        assert!(unit.contains("WatchdogSec=30"));
+        assert!(unit.contains("WantedBy=multi-user.target"));
+        assert!(!unit.contains("loxone-mcp.socket"));
+    }
+
+    #[test]
+    fn test_socket_activation_units() {
+        let options = UnitOptions {
+            port: 8080,
+            socket_activation: true,
+            watchdog: None,
+            ..Default::default()
+        };
+        let service = generate_service_unit(&options);
+        let socket = generate_socket_unit(&options);
+
+        assert!(service.contains("Requires=loxone-mcp.socket"));
+        assert!(!service.contains("WatchdogSec"));
+        assert!(!service.contains("[Install]"));
+        assert!(socket.contains("ListenStream=8080"));
+        assert!(socket.contains("WantedBy=sockets.target"));
+    }
+
+    #[test]
+    fn test_validate_options() {
+        assert!(UnitOptions::default().validate().is_ok());
+
+        let relative = UnitOptions {
+            binary: PathBuf::from("loxone-mcp-server"),
+            ..Default::default()
+        };
+        assert!(relative.validate().is_err());
+
+        let stdio = UnitOptions {
+            transport: "stdio".to_string(),
+            ..Default::default()
+        };
+        assert!(stdio.validate().is_err());
+    }
+}
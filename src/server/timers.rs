@@ -0,0 +1,121 @@
+//! Stairwell light switches and other timer controls
+//!
+//! A `TimedSwitch` turns its output on for the duration configured in Loxone
+//! Config when pulsed. Its states:
+//!
+//! - `deactivationDelayTotal`: configured duration in seconds
+//! - `deactivationDelay`: remaining seconds while running, 0 when off and -1
+//!   when switched on permanently
+//!
+//! Runs with a custom duration are counted down by the server, see
+//! [`crate::services::countdowns`].
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as timers
+pub const TIMER_TYPES: &[&str] = &["TimedSwitch"];
+
+/// Command starting a run of the configured duration
+pub const PULSE_COMMAND: &str = "pulse";
+
+/// Command switching the output on until switched off
+pub const ON_COMMAND: &str = "on";
+
+/// Command switching the output off, ending a run
+pub const OFF_COMMAND: &str = "off";
+
+const REMAINING_STATE: &str = "deactivationDelay";
+const TOTAL_STATE: &str = "deactivationDelayTotal";
+
+/// What a timer is doing
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum TimerState {
+    Off,
+    Running {
+        remaining_secs: u64,
+    },
+    /// On without a deadline
+    On,
+}
+
+impl TimerState {
+    /// State from the `deactivationDelay` value
+    pub fn from_remaining(remaining: f64) -> Self {
+        if remaining < 0.0 {
+            Self::On
+        } else if remaining > 0.0 {
+            Self::Running {
+                remaining_secs: remaining.ceil() as u64,
+            }
+        } else {
+            Self::Off
+        }
+    }
+
+    pub fn remaining_secs(self) -> Option<u64> {
+        match self {
+            Self::Running { remaining_secs } => Some(remaining_secs),
+            _ => None,
+        }
+    }
+}
+
+/// State UUIDs of the remaining and the configured time
+pub fn state_uuids(control: &Value) -> (Option<String>, Option<String>) {
+    let state = |name: &str| {
+        control
+            .get("states")
+            .and_then(|states| states.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    (state(REMAINING_STATE), state(TOTAL_STATE))
+}
+
+/// Current state and configured duration of a timer
+pub fn read_timer(control: &Value, values: &HashMap<String, Value>) -> (TimerState, Option<u64>) {
+    let (remaining, total) = state_uuids(control);
+    let number = |uuid: Option<String>| {
+        uuid.and_then(|uuid| values.get(&uuid))
+            .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+    };
+    (
+        number(remaining).map_or(TimerState::Off, TimerState::from_remaining),
+        number(total).filter(|t| *t > 0.0).map(|t| t.round() as u64),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_timer() {
+        let control = json!({
+            "type": "TimedSwitch",
+            "states": {"deactivationDelay": "s-rem", "deactivationDelayTotal": "s-tot"}
+        });
+        let values = HashMap::from([
+            ("s-rem".to_string(), json!(42.3)),
+            ("s-tot".to_string(), json!("180")),
+        ]);
+        assert_eq!(
+            read_timer(&control, &values),
+            (TimerState::Running { remaining_secs: 43 }, Some(180))
+        );
+        assert_eq!(TimerState::from_remaining(-1.0), TimerState::On);
+        assert_eq!(TimerState::from_remaining(0.0).remaining_secs(), None);
+        assert_eq!(
+            read_timer(&control, &HashMap::new()),
+            (TimerState::Off, None)
+        );
+        assert_eq!(
+            serde_json::to_value(TimerState::Running { remaining_secs: 5 }).unwrap(),
+            json!({"state": "running", "remaining_secs": 5})
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Built-in TLS termination for the HTTP transports
+//!
+//! The framework HTTP transport only speaks plain HTTP. When TLS is enabled the
+//! plain listener keeps running on its configured port and this module accepts
+//! TLS connections on a separate public port, terminates them with rustls and
+//! forwards the decrypted stream to the plain listener on loopback.
+//!
+//! Certificates are loaded from PEM files or obtained automatically from
+//! Let's Encrypt via ACME. Only the TLS-ALPN-01 challenge is supported: it is
+//! answered on the TLS port itself, so no additional port 80 listener is
+//! required. Issued certificates and the ACME account are cached on disk and
+//! renewed in the background.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::client_sessions::{ClientSessionRegistry, SessionTransport};
+use crate::server::connection_limits::{ConnectionLimiter, ConnectionPermit};
+use futures::StreamExt;
+use rustls_acme::caches::DirCache;
+use rustls_acme::{AcmeConfig, is_tls_alpn_challenge};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_rustls::LazyConfigAcceptor;
+use tokio_rustls::rustls::ServerConfig as RustlsServerConfig;
+use tokio_rustls::rustls::server::Acceptor;
+use tracing::{debug, error, info, warn};
+
+/// Where the TLS certificate comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CertificateSource {
+    /// PEM-encoded certificate chain and private key on disk
+    Files {
+        /// Path to the certificate chain (leaf first)
+        cert_path: PathBuf,
+        /// Path to the PKCS#8, PKCS#1 or SEC1 private key
+        key_path: PathBuf,
+    },
+    /// Certificates issued by Let's Encrypt using the TLS-ALPN-01 challenge
+    Acme {
+        /// Domains to request the certificate for
+        domains: Vec<String>,
+        /// Contact email address registered with the ACME account
+        contact: Option<String>,
+        /// Directory used to cache the account key and issued certificates
+        cache_dir: PathBuf,
+        /// Use the Let's Encrypt production directory instead of staging
+        production: bool,
+    },
+}
+
+/// TLS termination configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Address the TLS listener binds to
+    pub listen_addr: SocketAddr,
+    /// Plain HTTP port of the MCP transport that connections are forwarded to
+    pub upstream_port: u16,
+    /// Certificate source
+    pub certificate: CertificateSource,
+}
+
+impl TlsConfig {
+    /// Validate the configuration before binding any sockets
+    pub fn validate(&self) -> Result<()> {
+        if self.listen_addr.port() == self.upstream_port {
+            return Err(LoxoneError::config(
+                "TLS port must differ from the plain HTTP port",
+            ));
+        }
+
+        match &self.certificate {
+            CertificateSource::Files {
+                cert_path,
+                key_path,
+            } => {
+                if !cert_path.exists() {
+                    return Err(LoxoneError::config(format!(
+                        "TLS certificate not found: {}",
+                        cert_path.display()
+                    )));
+                }
+                if !key_path.exists() {
+                    return Err(LoxoneError::config(format!(
+                        "TLS private key not found: {}",
+                        key_path.display()
+                    )));
+                }
+            }
+            CertificateSource::Acme { domains, .. } => {
+                if domains.is_empty() {
+                    return Err(LoxoneError::config(
+                        "ACME requires at least one domain (--acme-domain)",
+                    ));
+                }
+                if let Some(domain) = domains
+                    .iter()
+                    .find(|d| d.trim().is_empty() || d.contains('*'))
+                {
+                    return Err(LoxoneError::config(format!(
+                        "Invalid ACME domain '{domain}': wildcards need DNS-01, which is not supported"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Certificate material used by the accept loop
+enum CertificateProvider {
+    Static(Arc<RustlsServerConfig>),
+    Acme {
+        default_config: Arc<RustlsServerConfig>,
+        challenge_config: Arc<RustlsServerConfig>,
+    },
+}
+
+/// TLS terminating proxy in front of the plain HTTP transport
+pub struct TlsTerminator;
+
+impl TlsTerminator {
+    /// Bind the TLS listener and start forwarding connections
+    ///
+    /// Returns the handle of the accept loop. For ACME the background renewal
+    /// task is started as well and lives as long as the accept loop.
+    pub async fn spawn(config: TlsConfig) -> Result<JoinHandle<()>> {
+        config.validate()?;
+
+        let provider = match &config.certificate {
+            CertificateSource::Files {
+                cert_path,
+                key_path,
+            } => CertificateProvider::Static(Arc::new(load_static_config(cert_path, key_path)?)),
+            CertificateSource::Acme {
+                domains,
+                contact,
+                cache_dir,
+                production,
+            } => start_acme(domains, contact.as_deref(), cache_dir, *production)?,
+        };
+
+        let listener = TcpListener::bind(config.listen_addr).await.map_err(|e| {
+            LoxoneError::config(format!(
+                "Failed to bind TLS listener on {}: {e}",
+                config.listen_addr
+            ))
+        })?;
+        let upstream = SocketAddr::from(([127, 0, 0, 1], config.upstream_port));
+        let provider = Arc::new(provider);
+
+        info!(
+            "🔒 TLS termination listening on {} (forwarding to {})",
+            config.listen_addr, upstream
+        );
+
+        Ok(tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        ConnectionLimiter::global().record_accept_error();
+                        warn!("TLS listener accept failed: {e}");
+                        continue;
+                    }
+                };
+
+                // Refused before the handshake, so no HTTP response can be sent
+                let permit = match ConnectionLimiter::global().admit(peer) {
+                    Ok(permit) => permit,
+                    Err(rejection) => {
+                        debug!("Refusing TLS connection from {peer}: {rejection:?}");
+                        continue;
+                    }
+                };
+
+                let provider = provider.clone();
+                tokio::spawn(async move {
+                    let _session = ClientSessionRegistry::global()
+                        .open_guarded(SessionTransport::Tls, Some(peer));
+                    if let Err(e) = handle_connection(stream, upstream, &provider, permit).await {
+                        debug!("TLS connection from {peer} closed: {e}");
+                    }
+                });
+            }
+        }))
+    }
+}
+
+/// Terminate TLS on a single connection and pipe it to the upstream listener
+async fn handle_connection(
+    stream: TcpStream,
+    upstream: SocketAddr,
+    provider: &CertificateProvider,
+    mut permit: ConnectionPermit,
+) -> std::io::Result<()> {
+    let start = LazyConfigAcceptor::new(Acceptor::default(), stream).await?;
+
+    let server_config = match provider {
+        CertificateProvider::Static(config) => config.clone(),
+        CertificateProvider::Acme {
+            default_config,
+            challenge_config,
+        } => {
+            if is_tls_alpn_challenge(&start.client_hello()) {
+                debug!("Answering ACME TLS-ALPN-01 challenge");
+                start.into_stream(challenge_config.clone()).await?;
+                return Ok(());
+            }
+            default_config.clone()
+        }
+    };
+
+    let mut tls_stream = start.into_stream(server_config).await?;
+    let mut upstream_stream = TcpStream::connect(upstream).await?;
+    permit.upstream_connected();
+    upstream_stream.set_nodelay(true)?;
+
+    tokio::io::copy_bidirectional(&mut tls_stream, &mut upstream_stream).await?;
+    Ok(())
+}
+
+/// Build a rustls server configuration from PEM files
+fn load_static_config(
+    cert_path: &std::path::Path,
+    key_path: &std::path::Path,
+) -> Result<RustlsServerConfig> {
+    let mut cert_reader = std::io::BufReader::new(std::fs::File::open(cert_path)?);
+    let certs = rustls_pemfile::certs(&mut cert_reader)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| LoxoneError::config(format!("Invalid TLS certificate: {e}")))?;
+    if certs.is_empty() {
+        return Err(LoxoneError::config(format!(
+            "No certificates found in {}",
+            cert_path.display()
+        )));
+    }
+
+    let mut key_reader = std::io::BufReader::new(std::fs::File::open(key_path)?);
+    let key = rustls_pemfile::private_key(&mut key_reader)
+        .map_err(|e| LoxoneError::config(format!("Invalid TLS private key: {e}")))?
+        .ok_or_else(|| {
+            LoxoneError::config(format!("No private key found in {}", key_path.display()))
+        })?;
+
+    let mut config = RustlsServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| LoxoneError::config(format!("TLS certificate/key mismatch: {e}")))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Create the ACME state machine and drive it in the background
+fn start_acme(
+    domains: &[String],
+    contact: Option<&str>,
+    cache_dir: &std::path::Path,
+    production: bool,
+) -> Result<CertificateProvider> {
+    std::fs::create_dir_all(cache_dir).map_err(|e| {
+        LoxoneError::config(format!(
+            "Failed to create ACME cache directory {}: {e}",
+            cache_dir.display()
+        ))
+    })?;
+
+    let mut acme = AcmeConfig::new(domains)
+        .cache(DirCache::new(cache_dir.to_path_buf()))
+        .directory_lets_encrypt(production);
+    if let Some(contact) = contact {
+        acme = acme.contact_push(format!("mailto:{contact}"));
+    }
+
+    let mut state = acme.state();
+    let challenge_config = state.challenge_rustls_config();
+    let mut default_config = RustlsServerConfig::builder()
+        .with_no_client_auth()
+        .with_cert_resolver(state.resolver());
+    default_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    info!(
+        "🔐 Requesting certificates for {} from Let's Encrypt ({})",
+        domains.join(", "),
+        if production { "production" } else { "staging" }
+    );
+
+    tokio::spawn(async move {
+        while let Some(event) = state.next().await {
+            match event {
+                Ok(ok) => info!("ACME: {ok:?}"),
+                Err(err) => error!("ACME: {err:?}"),
+            }
+        }
+    });
+
+    Ok(CertificateProvider::Acme {
+        default_config: Arc::new(default_config),
+        challenge_config,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acme_config(domains: Vec<&str>) -> TlsConfig {
+        TlsConfig {
+            listen_addr: "0.0.0.0:3443".parse().unwrap(),
+            upstream_port: 3001,
+            certificate: CertificateSource::Acme {
+                domains: domains.into_iter().map(String::from).collect(),
+                contact: None,
+                cache_dir: PathBuf::from("/tmp/acme"),
+                production: false,
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_same_port() {
+        let mut config = acme_config(vec!["home.example.com"]);
+        config.upstream_port = 3443;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_acme_domains() {
+        assert!(acme_config(vec!["home.example.com"]).validate().is_ok());
+        assert!(acme_config(vec![]).validate().is_err());
+        assert!(acme_config(vec!["*.example.com"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_missing_files() {
+        let config = TlsConfig {
+            listen_addr: "0.0.0.0:3443".parse().unwrap(),
+            upstream_port: 3001,
+            certificate: CertificateSource::Files {
+                cert_path: PathBuf::from("/nonexistent/cert.pem"),
+                key_path: PathBuf::from("/nonexistent/key.pem"),
+            },
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Localized tool titles and descriptions
+//!
+//! Tool descriptions are kept in per-locale TOML bundles
+//! (`locales/<lang>/tools.toml`), one table per tool:
+//!
+//! ```toml
+//! [control_lights]
+//! title = "Licht steuern"
+//! description = "Einzelne Leuchten, einen Raum oder das ganze Haus schalten oder dimmen."
+//! ```
+//!
+//! English and German are built in. Further bundles, or overrides of the
+//! built-in ones, are read from `LOXONE_LOCALE_DIR/<lang>/tools.toml`. The
+//! locale is `mcp.tools.locale`, else `LOXONE_LOCALE`/`LANG` (see
+//! [`resolve_locale`]); tools missing from a bundle fall back to English.
+//! `tools/list` uses the locale of the request when the client sends one
+//! (`_meta.locale` or `Accept-Language`).
+
+use crate::error::{LoxoneError, Result};
+use crate::notifications::templates::{FALLBACK_LOCALE, resolve_locale};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Bundles compiled into the binary
+const BUILTIN_BUNDLES: &[(&str, &str)] = &[
+    ("en", include_str!("../../locales/en/tools.toml")),
+    ("de", include_str!("../../locales/de/tools.toml")),
+];
+
+/// Text of one tool in one locale
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolText {
+    pub title: String,
+    pub description: String,
+}
+
+/// A tool's text with the locale it was taken from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocalizedTool {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub locale: String,
+}
+
+/// Tool texts for one locale with English fallback
+#[derive(Debug, Clone)]
+pub struct ToolDescriptions {
+    locale: String,
+    localized: BTreeMap<String, ToolText>,
+    fallback: BTreeMap<String, ToolText>,
+}
+
+impl ToolDescriptions {
+    /// Texts for `locale` (resolved as described in the module docs)
+    pub fn load(locale: Option<&str>) -> Self {
+        let locale = resolve_locale(locale);
+        let fallback = load_bundle(FALLBACK_LOCALE);
+        let localized = if locale == FALLBACK_LOCALE {
+            BTreeMap::new()
+        } else {
+            load_bundle(&locale)
+        };
+        Self {
+            locale,
+            localized,
+            fallback,
+        }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Locales with a bundle (built in or in `LOXONE_LOCALE_DIR`)
+    pub fn available_locales() -> Vec<String> {
+        let mut locales: Vec<String> = BUILTIN_BUNDLES
+            .iter()
+            .map(|(locale, _)| locale.to_string())
+            .collect();
+        if let Some(dir) = locale_dir()
+            && let Ok(entries) = std::fs::read_dir(dir)
+        {
+            for entry in entries.flatten() {
+                let locale = entry.file_name().to_string_lossy().to_lowercase();
+                if entry.path().join("tools.toml").exists() && !locales.contains(&locale) {
+                    locales.push(locale);
+                }
+            }
+        }
+        locales.sort();
+        locales
+    }
+
+    /// Text of `tool`, in the selected locale if translated
+    pub fn describe(&self, tool: &str) -> Option<LocalizedTool> {
+        let (text, locale) = match self.localized.get(tool) {
+            Some(text) => (text, self.locale.as_str()),
+            None => (self.fallback.get(tool)?, FALLBACK_LOCALE),
+        };
+        Some(LocalizedTool {
+            name: tool.to_string(),
+            title: text.title.clone(),
+            description: text.description.clone(),
+            locale: locale.to_string(),
+        })
+    }
+
+    /// All tools of the English bundle, localized where possible
+    pub fn all(&self) -> Vec<LocalizedTool> {
+        self.fallback
+            .keys()
+            .filter_map(|tool| self.describe(tool))
+            .collect()
+    }
+
+    /// Tools without a translation in the selected locale
+    pub fn untranslated(&self) -> Vec<&str> {
+        if self.locale == FALLBACK_LOCALE {
+            return Vec::new();
+        }
+        self.fallback
+            .keys()
+            .filter(|tool| !self.localized.contains_key(*tool))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+impl Default for ToolDescriptions {
+    fn default() -> Self {
+        Self::load(None)
+    }
+}
+
+/// Parse a `tools.toml` bundle
+pub fn parse_bundle(content: &str) -> Result<BTreeMap<String, ToolText>> {
+    toml::from_str(content)
+        .map_err(|e| LoxoneError::config(format!("Invalid tool description bundle: {e}")))
+}
+
+fn locale_dir() -> Option<PathBuf> {
+    std::env::var("LOXONE_LOCALE_DIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Built-in bundle of `locale` overlaid with the one from `LOXONE_LOCALE_DIR`
+fn load_bundle(locale: &str) -> BTreeMap<String, ToolText> {
+    let mut bundle = BUILTIN_BUNDLES
+        .iter()
+        .find(|(name, _)| *name == locale)
+        .map(|(_, content)| parse_bundle(content).expect("built-in tool bundles are valid"))
+        .unwrap_or_default();
+
+    if let Some(path) = locale_dir().map(|dir| dir.join(locale).join("tools.toml"))
+        && path.exists()
+    {
+        match std::fs::read_to_string(&path)
+            .map_err(LoxoneError::from)
+            .and_then(|content| parse_bundle(&content))
+        {
+            Ok(overrides) => bundle.extend(overrides),
+            Err(e) => warn!("Ignoring tool descriptions in {}: {e}", path.display()),
+        }
+    }
+    bundle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundles_cover_every_tool() {
+        let source = include_str!("macro_backend.rs");
+        let tools: Vec<&str> = source
+            .split("self.run_tool")
+            .skip(1)
+            .filter_map(|rest| {
+                let rest = rest.trim_start_matches("_with").strip_prefix("(\"")?;
+                rest.split('"').next()
+            })
+            .collect();
+        assert!(tools.len() >= 50);
+
+        for (locale, content) in BUILTIN_BUNDLES {
+            let bundle = parse_bundle(content).unwrap();
+            for tool in &tools {
+                assert!(
+                    bundle.contains_key(*tool),
+                    "'{tool}' missing in the {locale} bundle"
+                );
+            }
+            assert_eq!(bundle.len(), tools.len(), "stale entries in {locale}");
+        }
+
+        let german = ToolDescriptions::load(Some("de_AT.UTF-8"));
+        assert_eq!(german.locale(), "de");
+        assert_eq!(
+            german.describe("control_lights").unwrap().title,
+            "Licht steuern"
+        );
+        assert!(german.untranslated().is_empty());
+
+        let french = ToolDescriptions::load(Some("fr"));
+        let text = french.describe("control_lights").unwrap();
+        assert_eq!(
+            (text.title.as_str(), text.locale.as_str()),
+            ("Control lights", "en")
+        );
+    }
+}
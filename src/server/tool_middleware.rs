@@ -0,0 +1,747 @@
+//! Composable pre/post hooks around tool calls
+//!
+//! Cross-cutting concerns are implemented once as a [`ToolMiddleware`] and
+//! applied to every tool by `run_tool`, instead of being repeated in each
+//! tool function. Middlewares run in registration order before the tool and
+//! in reverse order after it; a `before` hook can answer the call itself,
+//! which skips the tool and all later middlewares.
+//!
+//! Built-in middlewares:
+//!
+//! - [`MetricsMiddleware`]: records a `tool_call` metric per call
+//! - [`LifetimeMetricsMiddleware`]: counts calls and errors in the totals
+//!   kept across restarts
+//! - [`DryRunMiddleware`]: answers control tools without sending commands
+//!   (`LOXONE_DRY_RUN=true`)
+//! - [`IdempotencyMiddleware`]: replays the result of a control call the
+//!   same caller repeats within a short window, instead of sending the
+//!   commands again (only the [`IDEMPOTENT_TOOLS`])
+//! - [`ConsentMiddleware`]: asks the user, through the [`ConsentManager`],
+//!   before a call that disarms, unlocks or opens the house
+//! - [`ConfirmationMiddleware`]: waits for the states of the commanded
+//!   controls to change when a control call passes `confirm: true`
+
+use crate::error::LoxoneError;
+use crate::mcp_consent::{ConsentDecision, ConsentManager, OperationType};
+use crate::monitoring::lifetime_metrics::LifetimeMetrics;
+use crate::performance::metrics::MetricsCollector;
+use crate::server::access::GateAction;
+use crate::server::alarm::AlarmAction;
+use crate::server::pool::PoolAction;
+use crate::server::state_confirmation::{CommandConfirmation, PendingConfirmations};
+use crate::server::tool_timeouts::ToolCategory;
+use crate::services::command_history::CommandOrigin;
+use async_trait::async_trait;
+use humantime_serde::re::humantime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Result type of tool functions
+pub type ToolResult = std::result::Result<Value, String>;
+
+/// Default window in which repeated control calls are replayed
+pub const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(5);
+
+/// Which built-in middlewares are enabled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolMiddlewareConfig {
+    /// Record a `tool_call` metric for every call
+    pub metrics: bool,
+
+    /// Answer control tools without executing them
+    pub dry_run: bool,
+
+    /// Replay successful control calls repeated within this window
+    /// (`0s` disables it)
+    #[serde(with = "humantime_serde")]
+    pub idempotency_window: Duration,
+}
+
+impl Default for ToolMiddlewareConfig {
+    fn default() -> Self {
+        Self {
+            metrics: true,
+            dry_run: std::env::var("LOXONE_DRY_RUN")
+                .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            idempotency_window: std::env::var("LOXONE_IDEMPOTENCY_WINDOW")
+                .ok()
+                .and_then(|v| humantime::parse_duration(&v).ok())
+                .unwrap_or(DEFAULT_IDEMPOTENCY_WINDOW),
+        }
+    }
+}
+
+/// A tool call as seen by middlewares
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub tool: String,
+    pub category: ToolCategory,
+    /// Arguments the tool declared for middlewares, `null` if none
+    pub arguments: Value,
+    pub started_at: Instant,
+}
+
+impl ToolCall {
+    pub fn new(tool: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            category: ToolCategory::of(tool),
+            arguments: Value::Null,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Attach the call's arguments
+    pub fn with_arguments(mut self, arguments: Value) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
+
+/// Outcome of a `before` hook
+#[derive(Debug)]
+pub enum PreHook {
+    /// Continue with the next middleware and the tool
+    Continue,
+    /// Skip the tool and answer with this result
+    Respond(ToolResult),
+}
+
+/// Hook pair wrapped around every tool call
+#[async_trait]
+pub trait ToolMiddleware: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Runs before the tool
+    async fn before(&self, _call: &ToolCall) -> PreHook {
+        PreHook::Continue
+    }
+
+    /// Runs after the tool (or after a later middleware answered the call)
+    /// and may rewrite the result
+    async fn after(&self, _call: &ToolCall, _result: &mut ToolResult) {}
+}
+
+/// Ordered middleware chain
+#[derive(Clone, Default)]
+pub struct ToolMiddlewareChain {
+    layers: Vec<Arc<dyn ToolMiddleware>>,
+}
+
+impl ToolMiddlewareChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chain of the built-in middlewares enabled in `config`
+    pub fn from_config(
+        config: &ToolMiddlewareConfig,
+        metrics: Option<Arc<MetricsCollector>>,
+    ) -> Self {
+        let mut chain = Self::new();
+        if config.metrics
+            && let Some(metrics) = metrics
+        {
+            chain.push(Arc::new(MetricsMiddleware::new(metrics)));
+        }
+        if config.dry_run {
+            info!("🧪 Dry-run mode: control tools will not send commands");
+            chain.push(Arc::new(DryRunMiddleware));
+        }
+        if !config.idempotency_window.is_zero() {
+            chain.push(Arc::new(IdempotencyMiddleware::new(
+                config.idempotency_window,
+            )));
+        }
+        chain
+    }
+
+    /// Append a middleware; it runs after those already registered
+    pub fn push(&mut self, middleware: Arc<dyn ToolMiddleware>) {
+        self.layers.push(middleware);
+    }
+
+    /// Names of the registered middlewares, in order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.layers.iter().map(|m| m.name()).collect()
+    }
+
+    /// Run `body` through the chain
+    pub async fn run<F>(&self, call: &ToolCall, body: F) -> ToolResult
+    where
+        F: Future<Output = ToolResult>,
+    {
+        let mut entered = 0;
+        let mut answered = None;
+        for layer in &self.layers {
+            entered += 1;
+            if let PreHook::Respond(result) = layer.before(call).await {
+                answered = Some(result);
+                break;
+            }
+        }
+
+        let mut result = match answered {
+            Some(result) => result,
+            None => body.await,
+        };
+        for layer in self.layers[..entered].iter().rev() {
+            layer.after(call, &mut result).await;
+        }
+        result
+    }
+}
+
+/// Records call duration and outcome as a `tool_call` metric
+pub struct MetricsMiddleware {
+    metrics: Arc<MetricsCollector>,
+}
+
+impl MetricsMiddleware {
+    pub fn new(metrics: Arc<MetricsCollector>) -> Self {
+        Self { metrics }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for MetricsMiddleware {
+    fn name(&self) -> &'static str {
+        "metrics"
+    }
+
+    async fn after(&self, call: &ToolCall, result: &mut ToolResult) {
+        let tags = HashMap::from([
+            ("tool".to_string(), call.tool.clone()),
+            ("category".to_string(), call.category.as_str().to_string()),
+            (
+                "outcome".to_string(),
+                if result.is_ok() { "ok" } else { "error" }.to_string(),
+            ),
+        ]);
+        let elapsed_ms = call.elapsed().as_secs_f64() * 1000.0;
+        if let Err(e) = self
+            .metrics
+            .record_metric("tool_call".to_string(), elapsed_ms, tags)
+            .await
+        {
+            warn!("Failed to record tool call metric: {e}");
+        }
+    }
+}
+
+/// Counts calls and errors in the [`LifetimeMetrics`]
+pub struct LifetimeMetricsMiddleware {
+    metrics: Arc<LifetimeMetrics>,
+}
+
+impl LifetimeMetricsMiddleware {
+    pub fn new(metrics: Arc<LifetimeMetrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for LifetimeMetricsMiddleware {
+    fn name(&self) -> &'static str {
+        "lifetime_metrics"
+    }
+
+    async fn after(&self, call: &ToolCall, result: &mut ToolResult) {
+        self.metrics
+            .record_request(call.category.as_str(), result.is_ok());
+    }
+}
+
+/// Answers control tools without running them
+pub struct DryRunMiddleware;
+
+#[async_trait]
+impl ToolMiddleware for DryRunMiddleware {
+    fn name(&self) -> &'static str {
+        "dry_run"
+    }
+
+    async fn before(&self, call: &ToolCall) -> PreHook {
+        if call.category != ToolCategory::Control {
+            return PreHook::Continue;
+        }
+        info!("Dry run: skipped {}", call.tool);
+        PreHook::Respond(Ok(json!({
+            "dry_run": true,
+            "tool": call.tool,
+            "message": format!("Dry-run mode is enabled; '{}' was not executed", call.tool),
+        })))
+    }
+}
+
+/// Control tools whose repeated calls [`IdempotencyMiddleware`] replays
+///
+/// Replay needs the call's arguments to tell a retry from a different
+/// command, so only tools that pass them to `run_tool_with` can be listed.
+/// Other control tools always run, repeated or not.
+pub const IDEMPOTENT_TOOLS: &[&str] = &[
+    "control_lights",
+    "control_blinds",
+    "set_blind_position",
+    "control_alarm",
+    "test_safety_alarm",
+    "control_gate",
+    "open_intercom_door",
+    "control_pool",
+];
+
+/// Replays successful control calls repeated within a window
+///
+/// A client that retries after a lost or slow response would otherwise send
+/// the commands twice, e.g. toggle a light back off. Calls are keyed by the
+/// caller's API key, the tool and its arguments; only [`IDEMPOTENT_TOOLS`]
+/// are covered. Replayed results carry `"replayed": true`.
+pub struct IdempotencyMiddleware {
+    window: Duration,
+    recent: Mutex<HashMap<String, (Instant, Value)>>,
+}
+
+impl IdempotencyMiddleware {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Key of `call`, if it is replayable
+    fn key(call: &ToolCall) -> Option<String> {
+        if call.category != ToolCategory::Control
+            || call.arguments.is_null()
+            || !IDEMPOTENT_TOOLS.contains(&call.tool.as_str())
+        {
+            return None;
+        }
+        let caller = CommandOrigin::current().key.unwrap_or_default();
+        Some(format!("{caller}|{}|{}", call.tool, call.arguments))
+    }
+
+    fn recent(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Instant, Value)>> {
+        self.recent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for IdempotencyMiddleware {
+    fn name(&self) -> &'static str {
+        "idempotency"
+    }
+
+    async fn before(&self, call: &ToolCall) -> PreHook {
+        let Some(key) = Self::key(call) else {
+            return PreHook::Continue;
+        };
+        let mut recent = self.recent();
+        recent.retain(|_, (at, _)| at.elapsed() < self.window);
+        let Some((_, value)) = recent.get(&key) else {
+            return PreHook::Continue;
+        };
+        info!("Replayed repeated {} call", call.tool);
+        let mut value = value.clone();
+        if let Some(object) = value.as_object_mut() {
+            object.insert("replayed".to_string(), json!(true));
+        }
+        PreHook::Respond(Ok(value))
+    }
+
+    async fn after(&self, call: &ToolCall, result: &mut ToolResult) {
+        if let (Some(key), Ok(value)) = (Self::key(call), &*result) {
+            // Keeps the first result, so the window runs from the first call
+            self.recent()
+                .entry(key)
+                .or_insert_with(|| (Instant::now(), value.clone()));
+        }
+    }
+}
+
+/// Operation the user has to approve before `call` runs, if any
+///
+/// Declares the consent-gated tools. Arguments that do not parse are left
+/// for the tool to reject.
+pub fn consent_operation(call: &ToolCall) -> Option<OperationType> {
+    let arg = |name: &str| call.arguments.get(name).and_then(Value::as_str);
+    match call.tool.as_str() {
+        "control_alarm" => AlarmAction::parse(arg("action")?)
+            .ok()?
+            .requires_consent()
+            .then(|| OperationType::SecurityControl {
+                action: "disarm_alarm".to_string(),
+                scope: arg("alarm").unwrap_or("all alarms").to_string(),
+            }),
+        "control_gate" => GateAction::parse(arg("action")?)
+            .ok()?
+            .requires_consent()
+            .then(|| OperationType::SecurityControl {
+                action: "open_gate".to_string(),
+                scope: arg("gate").unwrap_or("gate").to_string(),
+            }),
+        "control_pool" => PoolAction::parse(arg("action")?)
+            .ok()?
+            .requires_consent()
+            .then(|| OperationType::SecurityControl {
+                action: "close_pool_cover".to_string(),
+                scope: arg("pool").unwrap_or("pool").to_string(),
+            }),
+        "open_intercom_door" => Some(OperationType::SecurityControl {
+            action: "open_door".to_string(),
+            scope: arg("intercom").unwrap_or("intercom").to_string(),
+        }),
+        "test_safety_alarm" => Some(OperationType::SecurityControl {
+            action: "test_smoke_alarm".to_string(),
+            scope: arg("device").unwrap_or("smoke alarm").to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// Asks the user to approve consent-gated calls (see [`consent_operation`])
+///
+/// The request waits in the [`ConsentManager`] until it is answered, e.g.
+/// through `POST /admin/api/consent/{id}`, or times out. The tool only runs
+/// once the user approved; the calling client cannot answer for the user.
+pub struct ConsentMiddleware {
+    consent: Arc<ConsentManager>,
+}
+
+impl ConsentMiddleware {
+    pub fn new(consent: Arc<ConsentManager>) -> Self {
+        Self { consent }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for ConsentMiddleware {
+    fn name(&self) -> &'static str {
+        "consent"
+    }
+
+    async fn before(&self, call: &ToolCall) -> PreHook {
+        let Some(operation) = consent_operation(call) else {
+            return PreHook::Continue;
+        };
+        let source = CommandOrigin::current()
+            .key
+            .unwrap_or_else(|| "mcp".to_string());
+        let refusal = match self.consent.request_consent(operation, source).await {
+            Ok(ConsentDecision::Approved | ConsentDecision::AutoApproved { .. }) => {
+                return PreHook::Continue;
+            }
+            Ok(ConsentDecision::Denied { reason }) => reason,
+            Ok(ConsentDecision::TimedOut) => "the user did not answer in time".to_string(),
+            Err(e) => e.to_string(),
+        };
+        warn!(
+            "Refused {} without the user's consent: {refusal}",
+            call.tool
+        );
+        PreHook::Respond(Err(LoxoneError::consent_denied(refusal).to_string()))
+    }
+}
+
+/// Confirms the commands of control calls made with `confirm: true`
+///
+/// See [`crate::server::state_confirmation`]. Each confirmation is added to
+/// the result entry naming its device (`uuid`) or, failing that, its
+/// command (`command_sent`). A single command without such an entry is
+/// confirmed at the top level, several are listed under `confirmations`.
+pub struct ConfirmationMiddleware {
+    timeout: Duration,
+}
+
+impl ConfirmationMiddleware {
+    /// Wait up to `timeout` for the states to change
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for ConfirmationMiddleware {
+    fn name(&self) -> &'static str {
+        "confirmation"
+    }
+
+    async fn before(&self, call: &ToolCall) -> PreHook {
+        if call.category == ToolCategory::Control
+            && call.arguments.get("confirm").and_then(Value::as_bool) == Some(true)
+            && let Some(pending) = PendingConfirmations::current()
+        {
+            pending.request();
+        }
+        PreHook::Continue
+    }
+
+    async fn after(&self, _call: &ToolCall, result: &mut ToolResult) {
+        let Some(pending) = PendingConfirmations::current().filter(|p| p.is_requested()) else {
+            return;
+        };
+        let confirmations = pending.confirm_all(self.timeout).await;
+        if let Ok(value) = result {
+            attach_confirmations(value, confirmations);
+        }
+    }
+}
+
+/// Add `confirmations` to the tool result `value`
+fn attach_confirmations(value: &mut Value, confirmations: Vec<CommandConfirmation>) {
+    fn names(entry: &Value, confirmed: &CommandConfirmation) -> bool {
+        match entry.get("uuid").and_then(Value::as_str) {
+            Some(uuid) => uuid == confirmed.uuid,
+            None => {
+                entry.get("command_sent").and_then(Value::as_str)
+                    == Some(confirmed.command.as_str())
+            }
+        }
+    }
+
+    let single = confirmations.len() == 1;
+    let mut unattached = Vec::new();
+    for confirmed in confirmations {
+        let entry = value
+            .as_object_mut()
+            .into_iter()
+            .flat_map(|object| object.values_mut())
+            .filter_map(Value::as_array_mut)
+            .flatten()
+            .find(|entry| entry.get("confirmation").is_none() && names(entry, &confirmed));
+        match entry {
+            Some(entry) => entry["confirmation"] = json!(confirmed.confirmation),
+            None => unattached.push(confirmed),
+        }
+    }
+
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    match unattached.as_slice() {
+        [] => {}
+        [confirmed] if single => {
+            object.insert("confirmation".to_string(), json!(confirmed.confirmation));
+        }
+        _ => {
+            let listed: Vec<Value> = unattached
+                .iter()
+                .map(|confirmed| {
+                    json!({
+                        "uuid": confirmed.uuid,
+                        "command_sent": confirmed.command,
+                        "confirmation": confirmed.confirmation,
+                    })
+                })
+                .collect();
+            object.insert("confirmations".to_string(), json!(listed));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{
+        TestServer, assert_command_sent, assert_tool_ok, device_uuid, sample_house, state_uuid,
+    };
+    use std::sync::Mutex;
+
+    struct Trace(&'static str, Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl ToolMiddleware for Trace {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn before(&self, _call: &ToolCall) -> PreHook {
+            self.1.lock().unwrap().push(format!("before {}", self.0));
+            PreHook::Continue
+        }
+
+        async fn after(&self, _call: &ToolCall, _result: &mut ToolResult) {
+            self.1.lock().unwrap().push(format!("after {}", self.0));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_order_and_short_circuit() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut chain = ToolMiddlewareChain::new();
+        chain.push(Arc::new(Trace("outer", log.clone())));
+        chain.push(Arc::new(DryRunMiddleware));
+        chain.push(Arc::new(Trace("inner", log.clone())));
+
+        let read = chain
+            .run(&ToolCall::new("get_lights_status"), async { Ok(json!(1)) })
+            .await;
+        assert_eq!(read.unwrap(), json!(1));
+
+        let control = chain
+            .run(&ToolCall::new("control_lights"), async {
+                panic!("dry run must not execute control tools")
+            })
+            .await
+            .unwrap();
+        assert_eq!(control["dry_run"], true);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "before outer",
+                "before inner",
+                "after inner",
+                "after outer",
+                "before outer",
+                "after outer"
+            ]
+        );
+        assert_eq!(chain.names(), ["outer", "dry_run", "inner"]);
+    }
+
+    #[test]
+    fn test_consent_gated_tools() {
+        let needs_consent = |tool: &str, arguments: Value| {
+            consent_operation(&ToolCall::new(tool).with_arguments(arguments)).is_some()
+        };
+        assert!(needs_consent("control_alarm", json!({"action": "disarm"})));
+        assert!(!needs_consent("control_alarm", json!({"action": "arm"})));
+        assert!(needs_consent(
+            "control_gate",
+            json!({"gate": "Garage", "action": "open"})
+        ));
+        assert!(!needs_consent(
+            "control_gate",
+            json!({"gate": "Garage", "action": "close"})
+        ));
+        assert!(needs_consent(
+            "control_pool",
+            json!({"action": "cover_close"})
+        ));
+        assert!(!needs_consent("control_pool", json!({"action": "pump_on"})));
+        assert!(needs_consent("open_intercom_door", json!({})));
+        assert!(needs_consent("test_safety_alarm", json!({"device": null})));
+        assert!(!needs_consent("control_lights", json!({"action": "on"})));
+    }
+
+    #[tokio::test]
+    async fn test_confirmation_waits_for_the_commanded_state() {
+        let fixture = TestServer::new(sample_house()).await;
+        let window = device_uuid("Kitchen", "Window");
+        let position = state_uuid(&window, "position");
+        fixture.client.set_state(&position, json!(0.0));
+        fixture
+            .client
+            .on_command_set_state(&window, "FullDown", &position, json!(1.0));
+
+        let result = fixture
+            .control_blinds(window.clone(), Some("down".into()), None, Some(true))
+            .await;
+        let result = assert_tool_ok(result);
+        assert_eq!(result["confirmation"]["status"], "confirmed");
+        assert_eq!(result["confirmation"]["watched"], "position");
+
+        let result = fixture
+            .control_blinds(window.clone(), Some("stop".into()), None, None)
+            .await;
+        assert!(assert_tool_ok(result).get("confirmation").is_none());
+
+        // Room-wide calls confirm each device in its result entry
+        let ceiling = device_uuid("Kitchen", "Ceiling");
+        let scene = state_uuid(&ceiling, "activeScene");
+        fixture.client.set_state(&scene, json!(0));
+        fixture
+            .client
+            .on_command_set_state(&ceiling, "on", &scene, json!(9));
+        let result = fixture
+            .control_lights(
+                "room".into(),
+                Some("Kitchen".into()),
+                "on".into(),
+                None,
+                Some(true),
+            )
+            .await;
+        let result = assert_tool_ok(result);
+        assert_eq!(result["results"][0]["uuid"], ceiling);
+        assert_eq!(result["results"][0]["confirmation"]["status"], "changed");
+    }
+
+    #[test]
+    fn test_idempotent_tools_declare_their_arguments() {
+        let source = include_str!("macro_backend.rs");
+        let mut declared: Vec<&str> = source
+            .split("self.run_tool_with(\"")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .filter(|tool| ToolCategory::of(tool) == ToolCategory::Control)
+            .collect();
+        let mut listed = IDEMPOTENT_TOOLS.to_vec();
+        declared.sort_unstable();
+        listed.sort_unstable();
+        assert_eq!(declared, listed);
+    }
+
+    #[tokio::test]
+    async fn test_gate_position_is_confirmed_by_the_middleware() {
+        let fixture = TestServer::new(sample_house()).await;
+        let gate = device_uuid("Garage", "Garage Door");
+        let position = state_uuid(&gate, "position");
+        fixture.client.set_state(&position, json!(1.0));
+        fixture
+            .client
+            .on_command_set_state(&gate, "close", &position, json!(0.0));
+
+        let result = fixture
+            .control_gate("Garage Door".into(), "close".into(), Some(true))
+            .await;
+        let result = assert_tool_ok(result);
+        assert_eq!(result["confirmation"]["status"], "confirmed");
+        assert!(result.get("position_feedback").is_none());
+        assert_command_sent(&fixture.client, &gate, "close");
+    }
+
+    #[tokio::test]
+    async fn test_repeated_control_call_is_replayed() {
+        let fixture = TestServer::new(sample_house()).await;
+        let ceiling = device_uuid("Kitchen", "Ceiling");
+        let switch_on = || {
+            fixture.control_lights(
+                "device".into(),
+                Some(ceiling.clone()),
+                "on".into(),
+                None,
+                None,
+            )
+        };
+
+        assert!(assert_tool_ok(switch_on().await).get("replayed").is_none());
+        assert_eq!(assert_tool_ok(switch_on().await)["replayed"], true);
+        assert_eq!(fixture.client.commands_for(&ceiling), ["on"]);
+
+        let result = fixture
+            .control_lights(
+                "device".into(),
+                Some(ceiling.clone()),
+                "off".into(),
+                None,
+                None,
+            )
+            .await;
+        assert_tool_ok(result);
+        assert_eq!(fixture.client.commands_for(&ceiling), ["on", "off"]);
+    }
+}
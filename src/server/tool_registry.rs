@@ -0,0 +1,299 @@
+//! Declarative tool requirements derived from the installation's devices
+//!
+//! Device-specific tools declare which control types they need. Once the
+//! structure is loaded, the registry infers the installation's tool set from
+//! the controls it actually has, so e.g. `control_ev_charging` is only offered
+//! when a wallbox exists. Calls to tools without matching devices are
+//! answered by [`CapabilityGate`] with an explanation instead of failing deep
+//! inside the tool; `get_available_tools` lists the generated tool set.
+//!
+//! Tools without a declaration (structure, status and history tools) are
+//! always available. Before the structure is loaded nothing is filtered.
+
+use crate::client::ClientContext;
+use crate::server::tool_middleware::{PreHook, ToolCall, ToolMiddleware};
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Device family a tool needs, matched case-insensitively against control types
+#[derive(Debug, Clone, Copy)]
+pub struct ToolRequirement {
+    /// Human-readable capability, used in explanations
+    pub capability: &'static str,
+    /// Substrings of control types that provide the capability
+    pub type_patterns: &'static [&'static str],
+}
+
+const LIGHTING: ToolRequirement = ToolRequirement {
+    capability: "lighting",
+    type_patterns: &["light", "dimmer", "switch", "colorpicker", "moodswitch"],
+};
+const CLIMATE: ToolRequirement = ToolRequirement {
+    capability: "room climate control",
+    type_patterns: &["iroomcontroller", "intelligent room controller", "climate"],
+};
+const AIR_CONDITIONING: ToolRequirement = ToolRequirement {
+    capability: "AC or fan coil units",
+    type_patterns: &["accontrol", "fancoil"],
+};
+const BLINDS: ToolRequirement = ToolRequirement {
+    capability: "blinds",
+    type_patterns: &["jalousie", "blind", "rolladen"],
+};
+const AUDIO: ToolRequirement = ToolRequirement {
+    capability: "audio zones",
+    type_patterns: &["audio", "music"],
+};
+const WEATHER: ToolRequirement = ToolRequirement {
+    capability: "weather station",
+    type_patterns: &["weather"],
+};
+const IRRIGATION: ToolRequirement = ToolRequirement {
+    capability: "irrigation",
+    type_patterns: &["irrigation"],
+};
+const POOL: ToolRequirement = ToolRequirement {
+    capability: "pool",
+    type_patterns: &["pool"],
+};
+const VENTILATION: ToolRequirement = ToolRequirement {
+    capability: "ventilation",
+    type_patterns: &["ventilation", "airhandling"],
+};
+const ENERGY: ToolRequirement = ToolRequirement {
+    capability: "energy metering",
+    type_patterns: &["meter", "energy"],
+};
+const EV_CHARGING: ToolRequirement = ToolRequirement {
+    capability: "EV charging",
+    type_patterns: &["wallbox", "evcharg", "charger"],
+};
+const LOAD_MANAGEMENT: ToolRequirement = ToolRequirement {
+    capability: "load management",
+    type_patterns: &["loadmanager"],
+};
+const SECURITY: ToolRequirement = ToolRequirement {
+    capability: "alarm system",
+    type_patterns: &["alarm"],
+};
+const SAFETY: ToolRequirement = ToolRequirement {
+    capability: "smoke and leak detectors",
+    type_patterns: &["smokealarm", "leakdetector"],
+};
+const GATES: ToolRequirement = ToolRequirement {
+    capability: "gates",
+    type_patterns: &["gate", "garage"],
+};
+const INTERCOM: ToolRequirement = ToolRequirement {
+    capability: "intercom",
+    type_patterns: &["intercom", "camera", "doorbell"],
+};
+const TEXT: ToolRequirement = ToolRequirement {
+    capability: "text states",
+    type_patterns: &["textstate", "textinput"],
+};
+const VIRTUAL_OUTPUTS: ToolRequirement = ToolRequirement {
+    capability: "virtual outputs",
+    type_patterns: &["virtualoutcmd"],
+};
+
+/// Tools and the devices they need
+pub const TOOL_REQUIREMENTS: &[(&str, ToolRequirement)] = &[
+    ("control_lights", LIGHTING),
+    ("get_lights_status", LIGHTING),
+    ("set_light_color", LIGHTING),
+    ("activate_scene", LIGHTING),
+    ("list_scenes", LIGHTING),
+    ("control_light_moods", LIGHTING),
+    ("get_timers", LIGHTING),
+    ("start_timer", LIGHTING),
+    ("cancel_timer", LIGHTING),
+    ("set_temperature", CLIMATE),
+    ("get_climate_status", CLIMATE),
+    ("get_valve_diagnostics", CLIMATE),
+    ("get_heating_zones", CLIMATE),
+    ("get_climate_schedule", CLIMATE),
+    ("set_climate_schedule", CLIMATE),
+    ("get_ac_status", AIR_CONDITIONING),
+    ("set_ac", AIR_CONDITIONING),
+    ("control_blinds", BLINDS),
+    ("set_blind_position", BLINDS),
+    ("get_blinds_status", BLINDS),
+    ("get_storm_protection", BLINDS),
+    ("control_audio_zone", AUDIO),
+    ("set_audio_volume", AUDIO),
+    ("get_audio_status", AUDIO),
+    ("list_audio_zones", AUDIO),
+    ("select_audio_source", AUDIO),
+    ("group_audio_zones", AUDIO),
+    ("announce", AUDIO),
+    ("get_irrigation_status", IRRIGATION),
+    ("control_irrigation", IRRIGATION),
+    ("set_irrigation_duration", IRRIGATION),
+    ("get_pool_status", POOL),
+    ("control_pool", POOL),
+    ("set_pool_temperature", POOL),
+    ("get_ventilation_status", VENTILATION),
+    ("set_ventilation_mode", VENTILATION),
+    ("get_weather", WEATHER),
+    ("get_energy_status", ENERGY),
+    ("get_energy_flow", ENERGY),
+    ("control_ev_charging", EV_CHARGING),
+    ("get_wallbox_status", EV_CHARGING),
+    ("set_charging_current", EV_CHARGING),
+    ("get_load_manager_status", LOAD_MANAGEMENT),
+    ("control_load_shedding", LOAD_MANAGEMENT),
+    ("get_security_status", SECURITY),
+    ("set_security_mode", SECURITY),
+    ("get_alarm_status", SECURITY),
+    ("control_alarm", SECURITY),
+    ("get_safety_status", SAFETY),
+    ("test_safety_alarm", SAFETY),
+    ("get_gate_status", GATES),
+    ("control_gate", GATES),
+    ("get_camera_status", INTERCOM),
+    ("control_intercom", INTERCOM),
+    ("get_intercom_history", INTERCOM),
+    ("open_intercom_door", INTERCOM),
+    ("get_text_states", TEXT),
+    ("set_text_input", TEXT),
+    ("list_virtual_outputs", VIRTUAL_OUTPUTS),
+    ("set_virtual_output", VIRTUAL_OUTPUTS),
+];
+
+/// Requirement declared for a tool, if any
+pub fn requirement_for(tool: &str) -> Option<&'static ToolRequirement> {
+    TOOL_REQUIREMENTS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, requirement)| requirement)
+}
+
+/// A tool left out of this installation's tool set
+#[derive(Debug, Clone, Serialize)]
+pub struct UnavailableTool {
+    pub tool: &'static str,
+    pub missing: &'static str,
+}
+
+/// Tool set generated for one installation
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolAvailability {
+    /// Whether the structure was loaded; without it every tool is offered
+    pub inferred: bool,
+    pub available: Vec<&'static str>,
+    pub unavailable: Vec<UnavailableTool>,
+}
+
+impl ToolAvailability {
+    /// Infer the tool set from the control types present in the structure
+    pub fn infer<'a>(control_types: impl IntoIterator<Item = &'a str>) -> Self {
+        let types: BTreeSet<String> = control_types
+            .into_iter()
+            .map(|t| t.to_lowercase())
+            .collect();
+        let inferred = !types.is_empty();
+
+        let mut available = Vec::new();
+        let mut unavailable = Vec::new();
+        for (tool, requirement) in TOOL_REQUIREMENTS {
+            let provided = requirement
+                .type_patterns
+                .iter()
+                .any(|pattern| types.iter().any(|t| t.contains(pattern)));
+            if provided || !inferred {
+                available.push(*tool);
+            } else {
+                unavailable.push(UnavailableTool {
+                    tool,
+                    missing: requirement.capability,
+                });
+            }
+        }
+
+        Self {
+            inferred,
+            available,
+            unavailable,
+        }
+    }
+
+    /// Tool set of the devices currently known to `context`
+    pub async fn from_context(context: &ClientContext) -> Self {
+        let devices = context.devices.read().await;
+        Self::infer(devices.values().map(|d| d.device_type.as_str()))
+    }
+
+    pub fn is_available(&self, tool: &str) -> bool {
+        !self.unavailable.iter().any(|u| u.tool == tool)
+    }
+}
+
+/// Middleware answering calls to tools the installation has no devices for
+pub struct CapabilityGate {
+    context: Arc<ClientContext>,
+}
+
+impl CapabilityGate {
+    pub fn new(context: Arc<ClientContext>) -> Self {
+        Self { context }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for CapabilityGate {
+    fn name(&self) -> &'static str {
+        "capability_gate"
+    }
+
+    async fn before(&self, call: &ToolCall) -> PreHook {
+        let Some(requirement) = requirement_for(&call.tool) else {
+            return PreHook::Continue;
+        };
+        if ToolAvailability::from_context(&self.context)
+            .await
+            .is_available(&call.tool)
+        {
+            return PreHook::Continue;
+        }
+        PreHook::Respond(Err(json!({
+            "error": "unavailable",
+            "tool": call.tool,
+            "missing": requirement.capability,
+            "message": format!(
+                "'{}' is not available: this installation has no {}",
+                call.tool, requirement.capability
+            ),
+        })
+        .to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_set_follows_devices() {
+        let availability = ToolAvailability::infer(["LightControllerV2", "Jalousie", "Meter"]);
+        assert!(availability.inferred);
+        assert!(availability.is_available("control_lights"));
+        assert!(availability.is_available("control_blinds"));
+        assert!(availability.is_available("list_rooms"));
+        assert!(!availability.is_available("control_ev_charging"));
+        assert!(
+            availability
+                .unavailable
+                .iter()
+                .any(|u| u.tool == "control_gate" && u.missing == "gates")
+        );
+
+        // Nothing is filtered before the structure is known
+        let unknown = ToolAvailability::infer([]);
+        assert!(!unknown.inferred);
+        assert!(unknown.unavailable.is_empty());
+    }
+}
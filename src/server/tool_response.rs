@@ -0,0 +1,139 @@
+//! Tool results split into text and data
+//!
+//! A tool result has two audiences. The model reads the text content, which
+//! should be a sentence or two ("Turned on 3 lights in Kitchen"), while
+//! clients and scripts read `structuredContent`, which carries the full typed
+//! payload. [`ToolResponse`] keeps the two apart instead of dumping the whole
+//! payload into the text, which costs context on every call.
+//!
+//! Tool methods return their payload as JSON; [`ToolResponse::from_data`]
+//! derives the text from it, preferring an explicit `summary` or `message`
+//! field, then a count of the returned items.
+
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Longest text derived from a payload before it is cut
+pub const MAX_TEXT_LEN: usize = 400;
+
+/// Fields that hold the human-readable outcome of a tool
+const TEXT_FIELDS: &[&str] = &["summary", "message"];
+
+/// Concise text for the model plus the machine-readable payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolResponse {
+    /// Human-readable outcome, sent as the text content
+    pub text: String,
+    /// Typed payload, sent as `structuredContent`
+    pub data: Value,
+}
+
+impl ToolResponse {
+    pub fn new(text: impl Into<String>, data: Value) -> Self {
+        Self {
+            text: text.into(),
+            data,
+        }
+    }
+
+    /// Response for a serializable payload, with text derived from it
+    pub fn from_data(tool: &str, data: impl Serialize) -> Self {
+        let data = serde_json::to_value(data).unwrap_or(Value::Null);
+        let text = summarize(tool, &data);
+        Self { text, data }
+    }
+
+    /// MCP result with the text as content and the data as `structuredContent`
+    pub fn into_call_tool_result(self) -> CallToolResult {
+        CallToolResult {
+            content: vec![Content::text(self.text)],
+            is_error: Some(false),
+            structured_content: Some(self.data),
+            _meta: None,
+        }
+    }
+}
+
+/// Concise text describing a tool payload
+pub fn summarize(tool: &str, data: &Value) -> String {
+    let text = match data {
+        Value::Null => format!("{tool} completed"),
+        Value::String(text) => text.clone(),
+        Value::Object(map) => TEXT_FIELDS
+            .iter()
+            .find_map(|field| map.get(*field).and_then(Value::as_str))
+            .map(str::to_string)
+            .or_else(|| {
+                // The first array-valued field is what the tool listed
+                map.iter().find_map(|(key, value)| {
+                    value.as_array().map(|items| {
+                        let count = map
+                            .get("count")
+                            .and_then(Value::as_u64)
+                            .unwrap_or(items.len() as u64);
+                        format!("{tool}: {count} {}", key.replace('_', " "))
+                    })
+                })
+            })
+            .unwrap_or_else(|| {
+                let status = map.get("status").and_then(Value::as_str).unwrap_or("ok");
+                format!("{tool}: {status}")
+            }),
+        Value::Array(items) => format!("{tool}: {} items", items.len()),
+        other => other.to_string(),
+    };
+    truncate(text)
+}
+
+fn truncate(mut text: String) -> String {
+    if text.len() > MAX_TEXT_LEN {
+        let mut end = MAX_TEXT_LEN;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push('…');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_text_prefers_message_then_counts() {
+        let controlled = ToolResponse::from_data(
+            "control_lights",
+            json!({"message": "Turned on 3 lights in Kitchen", "devices": ["a", "b", "c"]}),
+        );
+        assert_eq!(controlled.text, "Turned on 3 lights in Kitchen");
+        assert_eq!(controlled.data["devices"], json!(["a", "b", "c"]));
+
+        let listed = ToolResponse::from_data(
+            "list_devices",
+            json!({"devices": [{"name": "Ceiling"}], "count": 1, "filter": null}),
+        );
+        assert_eq!(listed.text, "list_devices: 1 devices");
+
+        let status = ToolResponse::from_data("get_server_status", json!({"status": "connected"}));
+        assert_eq!(status.text, "get_server_status: connected");
+    }
+
+    #[test]
+    fn test_call_tool_result_keeps_data_out_of_text() {
+        let long = "x".repeat(MAX_TEXT_LEN * 2);
+        let result = ToolResponse::from_data("get_device_info", json!({"summary": long}))
+            .into_call_tool_result();
+        assert_eq!(result.content.len(), 1);
+        assert_eq!(
+            result.structured_content.unwrap()["summary"]
+                .as_str()
+                .unwrap()
+                .len(),
+            MAX_TEXT_LEN * 2
+        );
+    }
+}
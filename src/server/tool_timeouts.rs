@@ -0,0 +1,506 @@
+//! Per-tool execution timeouts
+//!
+//! Every tool call runs under a time budget so a slow Miniserver cannot hold
+//! an MCP client indefinitely. Budgets default by tool category, declared
+//! per tool in [`TOOL_CATEGORIES`], and can be overridden per tool:
+//!
+//! - reads: 5 s
+//! - controls (everything that sends commands or changes stored state): 10 s
+//! - discovery (structure walks, searches and bulk reads): 120 s
+//!
+//! Environment overrides: `LOXONE_TOOL_TIMEOUT_READ`,
+//! `LOXONE_TOOL_TIMEOUT_CONTROL`, `LOXONE_TOOL_TIMEOUT_DISCOVERY` (e.g. `8s`)
+//! and `LOXONE_TOOL_TIMEOUTS` for single tools (`list_devices=30s,...`).
+//!
+//! Timed-out calls return a structured JSON error and are recorded as a
+//! `tool_timeout` metric in the server's performance metrics collector.
+
+use crate::performance::metrics::MetricsCollector;
+use humantime_serde::re::humantime;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+/// Default budget for read-only tools
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default budget for tools that send commands
+pub const DEFAULT_CONTROL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default budget for discovery tools
+pub const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Timeout category of a tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolCategory {
+    Read,
+    Control,
+    Discovery,
+}
+
+impl ToolCategory {
+    /// Category of a tool, from [`TOOL_CATEGORIES`]
+    pub fn of(tool: &str) -> Self {
+        TOOL_CATEGORIES
+            .iter()
+            .find(|(name, _)| *name == tool)
+            .map_or(Self::Control, |(_, category)| *category)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Control => "control",
+            Self::Discovery => "discovery",
+        }
+    }
+}
+
+/// Category of every tool
+///
+/// Tools missing here count as control tools, so an unlisted tool is never
+/// let through by dry-run mode.
+pub const TOOL_CATEGORIES: &[(&str, ToolCategory)] = &[
+    ("control_lights", ToolCategory::Control),
+    ("get_lights_status", ToolCategory::Read),
+    ("set_light_color", ToolCategory::Control),
+    ("get_timers", ToolCategory::Read),
+    ("start_timer", ToolCategory::Control),
+    ("cancel_timer", ToolCategory::Control),
+    ("set_temperature", ToolCategory::Control),
+    ("get_climate_status", ToolCategory::Read),
+    ("get_valve_diagnostics", ToolCategory::Read),
+    ("get_heating_zones", ToolCategory::Read),
+    ("get_climate_schedule", ToolCategory::Read),
+    ("set_climate_schedule", ToolCategory::Control),
+    ("get_hot_water_status", ToolCategory::Read),
+    ("boost_hot_water", ToolCategory::Control),
+    ("set_hot_water_temperature", ToolCategory::Control),
+    ("set_hot_water_schedule", ToolCategory::Control),
+    ("get_ac_status", ToolCategory::Read),
+    ("set_ac", ToolCategory::Control),
+    ("control_blinds", ToolCategory::Control),
+    ("set_blind_position", ToolCategory::Control),
+    ("get_blinds_status", ToolCategory::Read),
+    ("get_storm_protection", ToolCategory::Read),
+    ("list_rooms", ToolCategory::Discovery),
+    ("list_devices", ToolCategory::Discovery),
+    ("get_favorites", ToolCategory::Read),
+    ("set_favorite", ToolCategory::Control),
+    ("get_device_info", ToolCategory::Discovery),
+    ("lookup_device", ToolCategory::Discovery),
+    ("search_devices", ToolCategory::Discovery),
+    ("diagnose_devices", ToolCategory::Discovery),
+    ("get_all_states", ToolCategory::Discovery),
+    ("lock_device", ToolCategory::Control),
+    ("unlock_device", ToolCategory::Control),
+    ("batch_execute", ToolCategory::Control),
+    ("execute_macro", ToolCategory::Control),
+    ("get_server_status", ToolCategory::Read),
+    ("get_system_topology", ToolCategory::Read),
+    ("get_available_tools", ToolCategory::Read),
+    ("get_tool_descriptions", ToolCategory::Read),
+    ("set_operating_mode", ToolCategory::Control),
+    ("control_audio_zone", ToolCategory::Control),
+    ("set_audio_volume", ToolCategory::Control),
+    ("list_audio_zones", ToolCategory::Read),
+    ("select_audio_source", ToolCategory::Control),
+    ("group_audio_zones", ToolCategory::Control),
+    ("get_audio_status", ToolCategory::Read),
+    ("announce", ToolCategory::Control),
+    ("get_sensor_readings", ToolCategory::Read),
+    ("get_door_window_status", ToolCategory::Read),
+    ("get_motion_status", ToolCategory::Read),
+    ("get_device_statistics", ToolCategory::Read),
+    ("get_device_batteries", ToolCategory::Read),
+    ("get_room_comfort", ToolCategory::Read),
+    ("get_text_states", ToolCategory::Read),
+    ("set_text_input", ToolCategory::Control),
+    ("get_tracker_entries", ToolCategory::Read),
+    ("list_virtual_inputs", ToolCategory::Read),
+    ("set_virtual_input", ToolCategory::Control),
+    ("trigger_virtual_input", ToolCategory::Control),
+    ("list_virtual_outputs", ToolCategory::Read),
+    ("set_virtual_output", ToolCategory::Control),
+    ("get_presence_report", ToolCategory::Read),
+    ("get_device_usage", ToolCategory::Read),
+    ("diff_states", ToolCategory::Discovery),
+    ("get_connected_clients", ToolCategory::Read),
+    ("get_sampling_usage", ToolCategory::Read),
+    ("create_guest_access", ToolCategory::Control),
+    ("get_command_history", ToolCategory::Read),
+    ("get_program_backups", ToolCategory::Read),
+    ("diff_structure", ToolCategory::Discovery),
+    ("get_miniserver_log", ToolCategory::Read),
+    ("get_weather", ToolCategory::Read),
+    ("get_weather_forecast", ToolCategory::Read),
+    ("get_weather_protection", ToolCategory::Read),
+    ("override_weather_protection", ToolCategory::Control),
+    ("get_energy_status", ToolCategory::Read),
+    ("get_energy_flow", ToolCategory::Read),
+    ("get_wallbox_status", ToolCategory::Read),
+    ("control_ev_charging", ToolCategory::Control),
+    ("set_charging_current", ToolCategory::Control),
+    ("get_load_manager_status", ToolCategory::Read),
+    ("control_load_shedding", ToolCategory::Control),
+    ("schedule_workflow", ToolCategory::Control),
+    ("get_scheduled_workflows", ToolCategory::Read),
+    ("plan_heavy_load", ToolCategory::Control),
+    ("get_security_status", ToolCategory::Read),
+    ("set_security_mode", ToolCategory::Control),
+    ("get_alarm_status", ToolCategory::Read),
+    ("control_alarm", ToolCategory::Control),
+    ("control_door_lock", ToolCategory::Control),
+    ("get_safety_status", ToolCategory::Read),
+    ("test_safety_alarm", ToolCategory::Control),
+    ("get_gate_status", ToolCategory::Read),
+    ("control_gate", ToolCategory::Control),
+    ("get_camera_status", ToolCategory::Read),
+    ("control_intercom", ToolCategory::Control),
+    ("get_intercom_history", ToolCategory::Read),
+    ("open_intercom_door", ToolCategory::Control),
+    ("list_access_codes", ToolCategory::Read),
+    ("add_access_code", ToolCategory::Control),
+    ("revoke_access_code", ToolCategory::Control),
+    ("get_irrigation_status", ToolCategory::Read),
+    ("control_irrigation", ToolCategory::Control),
+    ("set_irrigation_duration", ToolCategory::Control),
+    ("get_pool_status", ToolCategory::Read),
+    ("control_pool", ToolCategory::Control),
+    ("set_pool_temperature", ToolCategory::Control),
+    ("get_ventilation_status", ToolCategory::Read),
+    ("set_ventilation_mode", ToolCategory::Control),
+    ("activate_scene", ToolCategory::Control),
+    ("room_all_off", ToolCategory::Control),
+    ("house_good_night", ToolCategory::Control),
+    ("list_scenes", ToolCategory::Read),
+    ("control_light_moods", ToolCategory::Control),
+    ("capture_scene_snapshot", ToolCategory::Control),
+    ("restore_scene_snapshot", ToolCategory::Control),
+    ("list_scene_snapshots", ToolCategory::Read),
+    ("delete_scene_snapshot", ToolCategory::Control),
+];
+
+/// Timeout budgets per category with per-tool overrides
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolTimeoutConfig {
+    /// Budget for read-only tools
+    #[serde(with = "humantime_serde")]
+    pub read: Duration,
+
+    /// Budget for control tools
+    #[serde(with = "humantime_serde")]
+    pub control: Duration,
+
+    /// Budget for discovery tools
+    #[serde(with = "humantime_serde")]
+    pub discovery: Duration,
+
+    /// Budgets for individual tools, taking precedence over categories
+    #[serde(with = "override_serde")]
+    pub overrides: HashMap<String, Duration>,
+}
+
+impl Default for ToolTimeoutConfig {
+    fn default() -> Self {
+        let from_env = |name: &str, default: Duration| {
+            env::var(name)
+                .ok()
+                .and_then(|v| humantime::parse_duration(&v).ok())
+                .unwrap_or(default)
+        };
+        Self {
+            read: from_env("LOXONE_TOOL_TIMEOUT_READ", DEFAULT_READ_TIMEOUT),
+            control: from_env("LOXONE_TOOL_TIMEOUT_CONTROL", DEFAULT_CONTROL_TIMEOUT),
+            discovery: from_env("LOXONE_TOOL_TIMEOUT_DISCOVERY", DEFAULT_DISCOVERY_TIMEOUT),
+            overrides: env::var("LOXONE_TOOL_TIMEOUTS")
+                .map(|v| parse_overrides(&v))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ToolTimeoutConfig {
+    /// Budget that applies to a tool
+    pub fn timeout_for(&self, tool: &str) -> Duration {
+        if let Some(timeout) = self.overrides.get(tool) {
+            return *timeout;
+        }
+        match ToolCategory::of(tool) {
+            ToolCategory::Read => self.read,
+            ToolCategory::Control => self.control,
+            ToolCategory::Discovery => self.discovery,
+        }
+    }
+}
+
+/// Parse `tool=duration` pairs separated by commas, skipping invalid entries
+fn parse_overrides(value: &str) -> HashMap<String, Duration> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (tool, duration) = entry.split_once('=')?;
+            match humantime::parse_duration(duration.trim()) {
+                Ok(d) => Some((tool.trim().to_string(), d)),
+                Err(e) => {
+                    warn!("Ignoring invalid tool timeout '{entry}': {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Serialize per-tool overrides as human-readable durations
+mod override_serde {
+    use super::humantime;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        map: &HashMap<String, Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            map.iter()
+                .map(|(tool, d)| (tool, humantime::format_duration(*d).to_string())),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<String, Duration>, D::Error> {
+        HashMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(tool, d)| {
+                humantime::parse_duration(&d)
+                    .map(|d| (tool, d))
+                    .map_err(serde::de::Error::custom)
+            })
+            .collect()
+    }
+}
+
+/// Enforces tool budgets and tracks timeout events
+pub struct ToolTimeouts {
+    config: ToolTimeoutConfig,
+    metrics: Option<Arc<MetricsCollector>>,
+    events: Mutex<HashMap<String, u64>>,
+}
+
+impl Default for ToolTimeouts {
+    fn default() -> Self {
+        Self::new(ToolTimeoutConfig::default())
+    }
+}
+
+impl ToolTimeouts {
+    /// Create with the given budgets; timeouts are only counted until a
+    /// collector is attached with [`Self::with_metrics`]
+    pub fn new(config: ToolTimeoutConfig) -> Self {
+        Self {
+            config,
+            metrics: None,
+            events: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record timeout events in the server's metrics collector
+    pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn config(&self) -> &ToolTimeoutConfig {
+        &self.config
+    }
+
+    /// Metrics collector receiving `tool_timeout` events
+    pub fn metrics(&self) -> Option<&Arc<MetricsCollector>> {
+        self.metrics.as_ref()
+    }
+
+    /// Run a tool future within its budget
+    ///
+    /// On timeout the future is dropped and a structured error is returned:
+    /// `{"error":"timeout","tool":...,"category":...,"timeout_ms":...}`.
+    pub async fn run<F>(&self, tool: &str, fut: F) -> Result<serde_json::Value, String>
+    where
+        F: Future<Output = Result<serde_json::Value, String>>,
+    {
+        let budget = self.config.timeout_for(tool);
+        match tokio::time::timeout(budget, fut).await {
+            Ok(result) => result,
+            Err(_) => {
+                let category = ToolCategory::of(tool);
+                warn!(
+                    "Tool {tool} timed out after {}ms ({} budget)",
+                    budget.as_millis(),
+                    category.as_str()
+                );
+                self.record_timeout(tool, category, budget).await;
+                Err(timeout_error(tool, category, budget))
+            }
+        }
+    }
+
+    async fn record_timeout(&self, tool: &str, category: ToolCategory, budget: Duration) {
+        *self
+            .events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(tool.to_string())
+            .or_insert(0) += 1;
+        if let Some(metrics) = &self.metrics {
+            let tags = HashMap::from([
+                ("tool".to_string(), tool.to_string()),
+                ("category".to_string(), category.as_str().to_string()),
+            ]);
+            if let Err(e) = metrics
+                .record_metric("tool_timeout".to_string(), budget.as_millis() as f64, tags)
+                .await
+            {
+                warn!("Failed to record tool timeout metric: {e}");
+            }
+        }
+    }
+
+    /// Timeout events per tool since startup
+    pub fn timeout_counts(&self) -> HashMap<String, u64> {
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Budgets and timeout counts for status reporting
+    pub fn status(&self) -> serde_json::Value {
+        let counts = self.timeout_counts();
+        json!({
+            "read_ms": self.config.read.as_millis() as u64,
+            "control_ms": self.config.control.as_millis() as u64,
+            "discovery_ms": self.config.discovery.as_millis() as u64,
+            "overrides_ms": self.config.overrides.iter()
+                .map(|(tool, d)| (tool.clone(), d.as_millis() as u64))
+                .collect::<HashMap<_, _>>(),
+            "timeouts_total": counts.values().sum::<u64>(),
+            "timeouts_by_tool": counts,
+        })
+    }
+}
+
+/// Structured error returned to the MCP client when a tool exceeds its budget
+pub fn timeout_error(tool: &str, category: ToolCategory, budget: Duration) -> String {
+    json!({
+        "error": "timeout",
+        "tool": tool,
+        "category": category.as_str(),
+        "timeout_ms": budget.as_millis() as u64,
+        "message": format!(
+            "Tool '{tool}' did not complete within {}",
+            humantime::format_duration(budget)
+        ),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::metrics::MetricsConfig;
+
+    #[test]
+    fn test_category_budgets() {
+        let config = ToolTimeoutConfig {
+            read: DEFAULT_READ_TIMEOUT,
+            control: DEFAULT_CONTROL_TIMEOUT,
+            discovery: DEFAULT_DISCOVERY_TIMEOUT,
+            overrides: parse_overrides("get_weather = 2s, bogus=soon"),
+        };
+        assert_eq!(
+            config.timeout_for("get_lights_status"),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            config.timeout_for("control_lights"),
+            Duration::from_secs(10)
+        );
+        assert_eq!(config.timeout_for("list_devices"), Duration::from_secs(120));
+        assert_eq!(config.timeout_for("get_weather"), Duration::from_secs(2));
+        assert_eq!(config.overrides.len(), 1);
+
+        let parsed: ToolTimeoutConfig =
+            toml::from_str("read = \"8s\"\n[overrides]\ncontrol_gate = \"30s\"").unwrap();
+        assert_eq!(parsed.read, Duration::from_secs(8));
+        assert_eq!(
+            toml::from_str::<ToolTimeoutConfig>(&toml::to_string(&parsed).unwrap()).unwrap(),
+            parsed
+        );
+        assert_eq!(parsed.timeout_for("control_gate"), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_every_tool_has_a_category() {
+        let source = include_str!("macro_backend.rs");
+        let tools: Vec<&str> = source
+            .split("self.run_tool")
+            .skip(1)
+            .filter_map(|rest| {
+                let rest = rest.trim_start_matches("_with").strip_prefix("(\"")?;
+                rest.split('"').next()
+            })
+            .collect();
+        for tool in &tools {
+            assert!(
+                TOOL_CATEGORIES.iter().any(|(name, _)| name == tool),
+                "{tool} missing in TOOL_CATEGORIES"
+            );
+        }
+        assert_eq!(TOOL_CATEGORIES.len(), tools.len());
+
+        assert_eq!(ToolCategory::of("search_devices"), ToolCategory::Discovery);
+        assert_eq!(ToolCategory::of("diff_states"), ToolCategory::Discovery);
+        assert_eq!(ToolCategory::of("get_weather"), ToolCategory::Read);
+        assert_eq!(ToolCategory::of("unknown_tool"), ToolCategory::Control);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_is_structured_and_recorded() {
+        let metrics = Arc::new(MetricsCollector::new(MetricsConfig::default()).unwrap());
+        let timeouts = ToolTimeouts::new(ToolTimeoutConfig {
+            overrides: HashMap::from([("get_weather".to_string(), Duration::from_millis(10))]),
+            ..ToolTimeoutConfig::default()
+        })
+        .with_metrics(metrics.clone());
+
+        let ok = timeouts
+            .run("get_lights_status", async { Ok(json!(1)) })
+            .await;
+        assert_eq!(ok.unwrap(), json!(1));
+
+        let err = timeouts
+            .run("get_weather", async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(json!(null))
+            })
+            .await
+            .unwrap_err();
+        let err: serde_json::Value = serde_json::from_str(&err).unwrap();
+        assert_eq!(err["error"], "timeout");
+        assert_eq!(err["category"], "read");
+        assert_eq!(err["timeout_ms"], 10);
+        assert_eq!(timeouts.timeout_counts()["get_weather"], 1);
+
+        let recorded = metrics.get_metrics(Duration::from_secs(60)).await.unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].tags["tool"], "get_weather");
+    }
+}
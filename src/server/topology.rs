@@ -0,0 +1,233 @@
+//! Hardware topology of the Miniserver
+//!
+//! Backs the `loxone://system/topology` resource. Unlike the structure
+//! file, which only knows the programmed controls, the device status API
+//! ([`crate::client::DEVICE_STATUS_PATH`]) reports the hardware actually
+//! attached and whether it answers:
+//!
+//! ```json
+//! {"extensions": [
+//!   {"serial": "13:00:00:01", "name": "Tree Extension", "type": "TreeExtension",
+//!    "online": true, "branches": [
+//!      {"name": "Left", "devices": [{"serial": "B0:...", "name": "Touch Kitchen",
+//!                                    "type": "Touch Tree", "online": true}]}]},
+//!   {"serial": "12:00:00:07", "name": "Air Base Extension", "type": "AirBaseExtension",
+//!    "online": true, "devices": [{"serial": "50:...", "name": "Window Kitchen",
+//!                                  "type": "Door & Window Contact Air", "online": false}]}
+//! ]}
+//! ```
+//!
+//! The Miniserver's built-in Tree and Air interfaces are listed as
+//! extensions of their own.
+
+use crate::server::air_devices;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Resource with the current hardware topology
+pub const TOPOLOGY_URI: &str = "loxone://system/topology";
+
+/// How a peripheral is connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Connection {
+    Tree,
+    Air,
+    /// Loxone Link extension bus
+    Link,
+}
+
+/// A device attached to an extension
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Peripheral {
+    pub serial: String,
+    pub name: String,
+    pub device_type: Option<String>,
+    pub online: bool,
+}
+
+/// One Tree branch of an extension
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeBranch {
+    pub name: String,
+    pub devices: Vec<Peripheral>,
+}
+
+/// An extension with the devices behind it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Extension {
+    pub serial: String,
+    pub name: String,
+    pub extension_type: Option<String>,
+    pub online: bool,
+    pub connection: Connection,
+    pub branches: Vec<TreeBranch>,
+    /// Devices attached directly, e.g. Air devices
+    pub devices: Vec<Peripheral>,
+}
+
+/// Online/offline counts
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TopologySummary {
+    pub extensions: usize,
+    pub tree_devices: usize,
+    pub air_devices: usize,
+    pub online: usize,
+    /// Names of everything not answering
+    pub offline: Vec<String>,
+}
+
+/// Parse the device status response
+pub fn parse_extensions(value: &Value) -> Vec<Extension> {
+    value
+        .get("extensions")
+        .and_then(Value::as_array)
+        .map(|extensions| extensions.iter().filter_map(parse_extension).collect())
+        .unwrap_or_default()
+}
+
+fn parse_extension(extension: &Value) -> Option<Extension> {
+    let peripheral = parse_peripheral(extension)?;
+    let branches: Vec<TreeBranch> = extension
+        .get("branches")
+        .and_then(Value::as_array)
+        .map(|branches| {
+            branches
+                .iter()
+                .enumerate()
+                .map(|(index, branch)| TreeBranch {
+                    name: branch
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .map_or_else(|| format!("Branch {}", index + 1), str::to_string),
+                    devices: parse_peripherals(branch),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let is_air = peripheral
+        .device_type
+        .as_deref()
+        .is_some_and(|t| t.to_lowercase().contains("air"));
+    let connection = if !branches.is_empty() {
+        Connection::Tree
+    } else if is_air {
+        Connection::Air
+    } else {
+        Connection::Link
+    };
+
+    Some(Extension {
+        serial: peripheral.serial,
+        name: peripheral.name,
+        extension_type: peripheral.device_type,
+        online: peripheral.online,
+        connection,
+        branches,
+        devices: parse_peripherals(extension),
+    })
+}
+
+fn parse_peripherals(parent: &Value) -> Vec<Peripheral> {
+    parent
+        .get("devices")
+        .and_then(Value::as_array)
+        .map(|devices| devices.iter().filter_map(parse_peripheral).collect())
+        .unwrap_or_default()
+}
+
+fn parse_peripheral(device: &Value) -> Option<Peripheral> {
+    let text = |name: &str| {
+        device
+            .get(name)
+            .and_then(Value::as_str)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let serial = text("serial").or_else(|| text("serialNr"))?;
+    Some(Peripheral {
+        name: text("name").unwrap_or_else(|| serial.clone()),
+        serial,
+        device_type: text("type"),
+        online: air_devices::is_online(device),
+    })
+}
+
+/// Count devices; those behind an offline extension count as offline
+pub fn summarize(extensions: &[Extension]) -> TopologySummary {
+    let mut summary = TopologySummary {
+        extensions: extensions.len(),
+        ..Default::default()
+    };
+    let count = |online: bool, name: &str, summary: &mut TopologySummary| {
+        if online {
+            summary.online += 1;
+        } else {
+            summary.offline.push(name.to_string());
+        }
+    };
+    for extension in extensions {
+        count(extension.online, &extension.name, &mut summary);
+        for device in extension.branches.iter().flat_map(|b| &b.devices) {
+            summary.tree_devices += 1;
+            count(
+                extension.online && device.online,
+                &device.name,
+                &mut summary,
+            );
+        }
+        for device in &extension.devices {
+            if extension.connection == Connection::Air {
+                summary.air_devices += 1;
+            }
+            count(
+                extension.online && device.online,
+                &device.name,
+                &mut summary,
+            );
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_summarize() {
+        let extensions = parse_extensions(&json!({"extensions": [
+            {"serial": "13:00:00:01", "name": "Tree Extension", "type": "TreeExtension",
+             "online": true, "branches": [
+                {"name": "Left", "devices": [
+                    {"serial": "B0:01", "name": "Touch Kitchen", "online": true},
+                    {"serial": "B0:02", "name": "Valve Bath", "online": false}]},
+                {"devices": []}]},
+            {"serial": "12:00:00:07", "name": "Air Base Extension", "type": "AirBaseExtension",
+             "online": false, "devices": [
+                {"serial": "50:01", "name": "Window Kitchen", "online": true}]},
+            {"serial": "14:00:00:02", "type": "RelayExtension", "online": 1},
+            {"name": "no serial"}
+        ]}));
+        assert_eq!(extensions.len(), 3);
+        assert_eq!(extensions[0].connection, Connection::Tree);
+        assert_eq!(extensions[0].branches[1].name, "Branch 2");
+        assert_eq!(extensions[1].connection, Connection::Air);
+        assert_eq!(extensions[2].connection, Connection::Link);
+        assert_eq!(extensions[2].name, "14:00:00:02");
+        assert!(extensions[2].online);
+
+        let summary = summarize(&extensions);
+        assert_eq!(summary.extensions, 3);
+        assert_eq!(summary.tree_devices, 2);
+        assert_eq!(summary.air_devices, 1);
+        assert_eq!(summary.online, 3);
+        assert_eq!(
+            summary.offline,
+            vec!["Valve Bath", "Air Base Extension", "Window Kitchen"]
+        );
+
+        assert!(parse_extensions(&json!([])).is_empty());
+    }
+}
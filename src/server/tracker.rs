@@ -0,0 +1,185 @@
+//! Tracker (event log) controls
+//!
+//! A `Tracker` control keeps the last events logged to it by the program,
+//! e.g. "Front door opened" or "Alarm armed by Anna". Its `entries` state is
+//! a text of entries separated by `|`, newest first, each starting with a
+//! timestamp:
+//!
+//! ```text
+//! 2025-03-04 18:12:40 Front door opened|2025-03-04 07:55:02 Front door closed
+//! ```
+//!
+//! The Miniserver keeps at most `details.maxEntries` entries per tracker.
+
+use crate::error::{LoxoneError, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Control types handled as trackers
+pub const TRACKER_TYPES: &[&str] = &["Tracker"];
+
+/// Default page size
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page size
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// One logged event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackerEntry {
+    /// `None` if the entry has no readable timestamp
+    pub time: Option<NaiveDateTime>,
+    pub text: String,
+    /// Tracker the entry was logged to, set when merging trackers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracker: Option<String>,
+}
+
+/// Parse an `entries` state value, keeping the Miniserver's order
+pub fn parse_entries(value: &Value) -> Vec<TrackerEntry> {
+    let text = match value {
+        Value::String(text) => text.as_str(),
+        _ => return Vec::new(),
+    };
+    text.split('|')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let time = entry
+                .get(..19)
+                .and_then(|head| NaiveDateTime::parse_from_str(head, "%Y-%m-%d %H:%M:%S").ok());
+            TrackerEntry {
+                time,
+                text: match time {
+                    Some(_) => entry[19..].trim().to_string(),
+                    None => entry.to_string(),
+                },
+                tracker: None,
+            }
+        })
+        .collect()
+}
+
+/// Parse a `YYYY-MM-DD HH:MM[:SS]` time or a `YYYY-MM-DD` date; a date
+/// stands for its start, or with `end_of_day` for its last second
+pub fn parse_time(time: &str, end_of_day: bool) -> Result<NaiveDateTime> {
+    let time = time.trim();
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(time, format) {
+            return Ok(parsed);
+        }
+    }
+    NaiveDate::parse_from_str(time, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| {
+            if end_of_day {
+                date.and_hms_opt(23, 59, 59)
+            } else {
+                date.and_hms_opt(0, 0, 0)
+            }
+        })
+        .ok_or_else(|| {
+            LoxoneError::invalid_input(format!(
+                "Invalid time '{time}'. Use YYYY-MM-DD or YYYY-MM-DD HH:MM"
+            ))
+        })
+}
+
+/// Which entries to return
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntryFilter {
+    /// Case-insensitive text the entry must contain
+    pub query: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl EntryFilter {
+    /// Whether `entry` passes the filter; entries without a timestamp only
+    /// pass without a time range
+    pub fn matches(&self, entry: &TrackerEntry) -> bool {
+        if let Some(query) = &self.query
+            && !entry.text.to_lowercase().contains(&query.to_lowercase())
+        {
+            return false;
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        entry.time.is_some_and(|time| {
+            self.since.is_none_or(|since| time >= since)
+                && self.until.is_none_or(|until| time <= until)
+        })
+    }
+}
+
+/// Matching entries, newest first, with the total number of matches
+pub fn page(
+    mut entries: Vec<TrackerEntry>,
+    filter: &EntryFilter,
+    offset: usize,
+    limit: usize,
+) -> (Vec<TrackerEntry>, usize) {
+    entries.retain(|entry| filter.matches(entry));
+    // Stable, so untimed entries keep their place relative to each other
+    entries.sort_by(|a, b| b.time.cmp(&a.time));
+    let total = entries.len();
+    let page = entries
+        .into_iter()
+        .skip(offset)
+        .take(limit.clamp(1, MAX_PAGE_SIZE))
+        .collect();
+    (page, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entries() -> Vec<TrackerEntry> {
+        parse_entries(&json!(
+            "2025-03-04 18:12:40 Front door opened|2025-03-04 07:55:02 Front door closed|\
+             2025-03-03 22:01:00 Alarm armed by Anna|| manual note "
+        ))
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let entries = entries();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].text, "Front door opened");
+        assert_eq!(
+            entries[0].time,
+            Some(parse_time("2025-03-04 18:12:40", false).unwrap())
+        );
+        assert_eq!(entries[3].time, None);
+        assert_eq!(entries[3].text, "manual note");
+        assert!(parse_entries(&json!(3)).is_empty());
+    }
+
+    #[test]
+    fn test_filter_and_page() {
+        let door = EntryFilter {
+            query: Some("FRONT DOOR".to_string()),
+            ..Default::default()
+        };
+        let (page_one, total) = page(entries(), &door, 0, 1);
+        assert_eq!(total, 2);
+        assert_eq!(page_one[0].text, "Front door opened");
+        let (page_two, _) = page(entries(), &door, 1, 1);
+        assert_eq!(page_two[0].text, "Front door closed");
+
+        let yesterday = EntryFilter {
+            until: Some(parse_time("2025-03-03", true).unwrap()),
+            ..Default::default()
+        };
+        let (found, total) = page(entries(), &yesterday, 0, 10);
+        assert_eq!(total, 1);
+        assert_eq!(found[0].text, "Alarm armed by Anna");
+
+        assert_eq!(page(entries(), &EntryFilter::default(), 0, 10).1, 4);
+        assert!(parse_time("yesterday", false).is_err());
+    }
+}
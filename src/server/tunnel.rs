@@ -0,0 +1,512 @@
+//! Outbound tunnel to a relay for clients outside the LAN
+//!
+//! The server dials out to a user-run relay over WebSocket, so no port has to
+//! be forwarded on the router. Remote MCP clients connect to the relay, which
+//! multiplexes their connections as streams over the tunnel; each stream is
+//! piped to the local HTTP transport like a connection on the TLS port.
+//!
+//! Streams are encrypted end to end: the relay only forwards opaque frames.
+//! A remote client opens a stream by naming its API key by label (the public
+//! `lmcp_<role>_<seq>` prefix, never the secret) and sending a random nonce.
+//! Both ends derive the stream key from the full API key and both nonces with
+//! HMAC-SHA256, so only holders of a key from the server's key store can
+//! produce frames the server accepts. Frames are sealed with AES-256-GCM
+//! using per-direction counters as nonces.
+//!
+//! Control messages are JSON text frames:
+//!
+//! ```text
+//! server → relay  {"type":"register","tunnel":"home","version":1}
+//! relay → server  {"type":"open","stream":7,"key":"lmcp_operator_001","nonce":"<base64>"}
+//! server → relay  {"type":"accept","stream":7,"nonce":"<base64>"}
+//!                 {"type":"reject","stream":7,"reason":"..."}
+//! either          {"type":"close","stream":7}
+//! ```
+//!
+//! Data travels in binary frames: the stream id as big-endian `u32` followed
+//! by the sealed payload (ciphertext and 16 byte tag).
+
+use crate::error::{LoxoneError, Result};
+use crate::security::key_store::{ApiKey, KeyStore};
+use crate::server::client_sessions::{ClientSessionRegistry, SessionTransport};
+use crate::services::command_history::key_label;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+/// Protocol version sent when registering with the relay
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Length of the nonces exchanged when a stream is opened
+pub const STREAM_NONCE_LEN: usize = 16;
+
+const TAG_LEN: usize = 16;
+const KEY_CONTEXT: &[u8] = b"loxone-mcp tunnel v1";
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Tunnel settings
+#[derive(Debug, Clone)]
+pub struct TunnelConfig {
+    /// Relay WebSocket URL (`wss://relay.example.com/tunnel`)
+    pub relay_url: String,
+    /// Name the relay routes remote clients by
+    pub tunnel_id: String,
+    /// Local port of the plain HTTP transport
+    pub upstream_port: u16,
+}
+
+/// Control messages exchanged with the relay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlMessage {
+    Register {
+        tunnel: String,
+        version: u32,
+    },
+    Open {
+        stream: u32,
+        key: String,
+        nonce: String,
+    },
+    Accept {
+        stream: u32,
+        nonce: String,
+    },
+    Reject {
+        stream: u32,
+        reason: String,
+    },
+    Close {
+        stream: u32,
+    },
+}
+
+/// Side of a stream, which selects the direction of its frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+impl Side {
+    fn direction(self) -> u32 {
+        match self {
+            Side::Client => 0,
+            Side::Server => 1,
+        }
+    }
+
+    fn peer(self) -> Side {
+        match self {
+            Side::Client => Side::Server,
+            Side::Server => Side::Client,
+        }
+    }
+}
+
+/// Derive the key of a stream from the API key and both nonces
+pub fn derive_stream_key(
+    api_key: &str,
+    client_nonce: &[u8],
+    server_nonce: &[u8],
+) -> Result<[u8; 32]> {
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::sign::Signer;
+
+    let pkey = PKey::hmac(api_key.as_bytes()).map_err(crypto_error)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &pkey).map_err(crypto_error)?;
+    for part in [KEY_CONTEXT, client_nonce, server_nonce] {
+        signer.update(part).map_err(crypto_error)?;
+    }
+    let mac = signer.sign_to_vec().map_err(crypto_error)?;
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&mac);
+    Ok(key)
+}
+
+/// Seals or opens the frames of one stream direction
+pub struct FrameCipher {
+    key: [u8; 32],
+    direction: u32,
+    counter: u64,
+}
+
+impl FrameCipher {
+    /// Cipher for frames sent by `side`
+    pub fn sealer(key: [u8; 32], side: Side) -> Self {
+        Self {
+            key,
+            direction: side.direction(),
+            counter: 0,
+        }
+    }
+
+    /// Cipher for frames received by `side`
+    pub fn opener(key: [u8; 32], side: Side) -> Self {
+        Self::sealer(key, side.peer())
+    }
+
+    fn next_nonce(&mut self) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[..4].copy_from_slice(&self.direction.to_be_bytes());
+        nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+        self.counter += 1;
+        nonce
+    }
+
+    pub fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        use openssl::symm::{Cipher, encrypt_aead};
+
+        let nonce = self.next_nonce();
+        let mut tag = [0u8; TAG_LEN];
+        let mut sealed = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(&nonce),
+            &[],
+            plaintext,
+            &mut tag,
+        )
+        .map_err(crypto_error)?;
+        sealed.extend_from_slice(&tag);
+        Ok(sealed)
+    }
+
+    pub fn open(&mut self, sealed: &[u8]) -> Result<Vec<u8>> {
+        use openssl::symm::{Cipher, decrypt_aead};
+
+        if sealed.len() < TAG_LEN {
+            return Err(LoxoneError::crypto("Truncated tunnel frame"));
+        }
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+        let nonce = self.next_nonce();
+        decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(&nonce),
+            &[],
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| LoxoneError::authentication("Tunnel frame failed authentication"))
+    }
+}
+
+fn crypto_error(e: openssl::error::ErrorStack) -> LoxoneError {
+    LoxoneError::crypto(format!("Tunnel encryption failed: {e}"))
+}
+
+/// Prefix a sealed payload with its stream id
+pub fn encode_frame(stream: u32, sealed: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(4 + sealed.len());
+    frame.extend_from_slice(&stream.to_be_bytes());
+    frame.extend_from_slice(sealed);
+    frame
+}
+
+/// Split a binary frame into stream id and sealed payload
+pub fn decode_frame(frame: &[u8]) -> Option<(u32, &[u8])> {
+    let (id, payload) = frame.split_at_checked(4)?;
+    Some((u32::from_be_bytes(id.try_into().ok()?), payload))
+}
+
+/// An accepted stream: plaintext towards the upstream connection
+struct OpenStream {
+    opener: FrameCipher,
+    upstream: mpsc::Sender<Vec<u8>>,
+}
+
+/// Keeps the tunnel to the relay open and serves its streams
+pub struct TunnelClient {
+    config: TunnelConfig,
+    keys: Arc<KeyStore>,
+}
+
+impl TunnelClient {
+    pub fn new(config: TunnelConfig, keys: Arc<KeyStore>) -> Self {
+        Self { config, keys }
+    }
+
+    /// Connect in the background, reconnecting with backoff
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                match self.run_connection().await {
+                    Ok(()) => {
+                        info!("Tunnel to {} closed by relay", self.config.relay_url);
+                        delay = Duration::from_secs(1);
+                    }
+                    Err(e) => warn!("Tunnel to {} failed: {e}", self.config.relay_url),
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        })
+    }
+
+    async fn run_connection(&self) -> Result<()> {
+        let (socket, _) = tokio_tungstenite::connect_async(self.config.relay_url.as_str())
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Relay connection failed: {e}")))?;
+        let (mut sink, mut source) = socket.split();
+
+        let (outbound, mut outbound_rx) = mpsc::channel::<Message>(64);
+        let writer = tokio::spawn(async move {
+            while let Some(message) = outbound_rx.recv().await {
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        send_control(
+            &outbound,
+            &ControlMessage::Register {
+                tunnel: self.config.tunnel_id.clone(),
+                version: PROTOCOL_VERSION,
+            },
+        )
+        .await;
+        info!(
+            "🌐 Tunnel '{}' registered with {}",
+            self.config.tunnel_id, self.config.relay_url
+        );
+
+        let mut streams: HashMap<u32, OpenStream> = HashMap::new();
+        while let Some(message) = source.next().await {
+            let message =
+                message.map_err(|e| LoxoneError::connection(format!("Relay error: {e}")))?;
+            match message {
+                Message::Text(text) => match serde_json::from_str::<ControlMessage>(&text) {
+                    Ok(ControlMessage::Open { stream, key, nonce }) => {
+                        match self.open_stream(stream, &key, &nonce, &outbound).await {
+                            Ok(open) => {
+                                streams.insert(stream, open);
+                            }
+                            Err(e) => {
+                                warn!("Rejected tunnel stream {stream} for key {key}: {e}");
+                                let reject = ControlMessage::Reject {
+                                    stream,
+                                    reason: e.to_string(),
+                                };
+                                send_control(&outbound, &reject).await;
+                            }
+                        }
+                    }
+                    Ok(ControlMessage::Close { stream }) => {
+                        streams.remove(&stream);
+                    }
+                    Ok(other) => debug!("Ignoring tunnel control message {other:?}"),
+                    Err(e) => debug!("Ignoring malformed tunnel control message: {e}"),
+                },
+                Message::Binary(frame) => {
+                    let Some((id, sealed)) = decode_frame(&frame) else {
+                        continue;
+                    };
+                    let Some(stream) = streams.get_mut(&id) else {
+                        continue;
+                    };
+                    let delivered = match stream.opener.open(sealed) {
+                        Ok(plaintext) => stream.upstream.send(plaintext).await.is_ok(),
+                        Err(e) => {
+                            warn!("Closing tunnel stream {id}: {e}");
+                            false
+                        }
+                    };
+                    if !delivered {
+                        streams.remove(&id);
+                        send_control(&outbound, &ControlMessage::Close { stream: id }).await;
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        writer.abort();
+        Ok(())
+    }
+
+    /// Authenticate a stream request and start piping it upstream
+    async fn open_stream(
+        &self,
+        stream: u32,
+        label: &str,
+        client_nonce: &str,
+        outbound: &mpsc::Sender<Message>,
+    ) -> Result<OpenStream> {
+        let client_nonce = BASE64
+            .decode(client_nonce)
+            .ok()
+            .filter(|n| n.len() == STREAM_NONCE_LEN)
+            .ok_or_else(|| LoxoneError::invalid_input("Invalid stream nonce"))?;
+        let key = self.find_key(label).await?;
+
+        let mut server_nonce = [0u8; STREAM_NONCE_LEN];
+        openssl::rand::rand_bytes(&mut server_nonce).map_err(crypto_error)?;
+        let stream_key = derive_stream_key(&key.id, &client_nonce, &server_nonce)?;
+
+        let upstream_addr = SocketAddr::from(([127, 0, 0, 1], self.config.upstream_port));
+        let upstream = TcpStream::connect(upstream_addr).await?;
+        upstream.set_nodelay(true)?;
+
+        let (to_upstream, from_tunnel) = mpsc::channel(64);
+        tokio::spawn(pipe_stream(
+            stream,
+            key,
+            upstream,
+            from_tunnel,
+            FrameCipher::sealer(stream_key, Side::Server),
+            outbound.clone(),
+        ));
+
+        let accept = ControlMessage::Accept {
+            stream,
+            nonce: BASE64.encode(server_nonce),
+        };
+        send_control(outbound, &accept).await;
+        Ok(OpenStream {
+            opener: FrameCipher::opener(stream_key, Side::Server),
+            upstream: to_upstream,
+        })
+    }
+
+    /// Active, unexpired key matching a public key label
+    async fn find_key(&self, label: &str) -> Result<ApiKey> {
+        let now = chrono::Utc::now();
+        self.keys
+            .list_keys()
+            .await
+            .into_iter()
+            .find(|key| {
+                key.active
+                    && key.expires_at.is_none_or(|expires| expires > now)
+                    && key_label(&key.id) == label
+            })
+            .ok_or_else(|| LoxoneError::authentication("Unknown or inactive API key"))
+    }
+}
+
+/// Copy bytes between the upstream connection and the tunnel until either closes
+async fn pipe_stream(
+    stream: u32,
+    key: ApiKey,
+    upstream: TcpStream,
+    mut from_tunnel: mpsc::Receiver<Vec<u8>>,
+    mut sealer: FrameCipher,
+    outbound: mpsc::Sender<Message>,
+) {
+    let session = ClientSessionRegistry::global().open_guarded(SessionTransport::Tunnel, None);
+    ClientSessionRegistry::global().identify(session.id(), Some(key.name.clone()), Vec::new());
+    debug!("Tunnel stream {stream} opened for key '{}'", key.name);
+
+    let (mut reader, mut writer) = upstream.into_split();
+    let upload = async {
+        while let Some(data) = from_tunnel.recv().await {
+            ClientSessionRegistry::global().touch(session.id());
+            if writer.write_all(&data).await.is_err() {
+                break;
+            }
+        }
+    };
+    let download = async {
+        let mut buf = vec![0u8; 16 * 1024];
+        loop {
+            let n = match reader.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            let Ok(sealed) = sealer.seal(&buf[..n]) else {
+                break;
+            };
+            if outbound
+                .send(Message::Binary(encode_frame(stream, &sealed)))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    };
+    tokio::select! {
+        _ = upload => {}
+        _ = download => {}
+    }
+
+    send_control(&outbound, &ControlMessage::Close { stream }).await;
+    debug!("Tunnel stream {stream} closed");
+}
+
+async fn send_control(outbound: &mpsc::Sender<Message>, message: &ControlMessage) {
+    if let Ok(text) = serde_json::to_string(message) {
+        let _ = outbound.send(Message::Text(text)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_are_authenticated_end_to_end() {
+        let client_nonce = [1u8; STREAM_NONCE_LEN];
+        let server_nonce = [2u8; STREAM_NONCE_LEN];
+        let key =
+            derive_stream_key("lmcp_operator_001_secret", &client_nonce, &server_nonce).unwrap();
+
+        let mut client = FrameCipher::sealer(key, Side::Client);
+        let mut server = FrameCipher::opener(key, Side::Server);
+        for request in [&b"POST /mcp"[..], b"{\"jsonrpc\":\"2.0\"}"] {
+            let frame = encode_frame(7, &client.seal(request).unwrap());
+            let (stream, sealed) = decode_frame(&frame).unwrap();
+            assert_eq!(stream, 7);
+            assert_eq!(server.open(sealed).unwrap(), request);
+        }
+
+        // A relay without the API key cannot forge frames
+        let forged_key =
+            derive_stream_key("lmcp_operator_001_guess", &client_nonce, &server_nonce).unwrap();
+        let forged = FrameCipher::sealer(forged_key, Side::Client)
+            .seal(b"GET /")
+            .unwrap();
+        assert!(server.open(&forged).is_err());
+
+        // Nor replay a server frame back to the server
+        let mut server_out = FrameCipher::sealer(key, Side::Server);
+        let reply = server_out.seal(b"200 OK").unwrap();
+        let mut fresh = FrameCipher::opener(key, Side::Server);
+        assert!(fresh.open(&reply).is_err());
+    }
+
+    #[test]
+    fn test_control_messages_wire_format() {
+        let open: ControlMessage = serde_json::from_str(
+            r#"{"type":"open","stream":3,"key":"lmcp_admin_001","nonce":"AAAA"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            open,
+            ControlMessage::Open {
+                stream: 3,
+                key: "lmcp_admin_001".to_string(),
+                nonce: "AAAA".to_string(),
+            }
+        );
+        let close = serde_json::to_value(ControlMessage::Close { stream: 3 }).unwrap();
+        assert_eq!(close, serde_json::json!({"type": "close", "stream": 3}));
+    }
+}
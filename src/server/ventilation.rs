@@ -0,0 +1,228 @@
+//! Ventilation and air handling units
+//!
+//! Loxone exposes ventilation units as `Ventilation` controls (`AirHandling`
+//! for central air handling units). Their `states` report the indoor air
+//! and the fan stage; state names differ between the two blocks, so each
+//! value is read from the first state present:
+//!
+//! - CO2 in ppm: `airQualityIn`, `co2`
+//! - relative humidity in %: `humidityIn`, `humidity`
+//! - VOC index: `vocIn`, `voc`
+//! - fan stage (0-4): `stage`, `speed`
+//! - mode: `mode` (0 automatic, 1 manual, 2 boost)
+//! - remaining boost time in seconds: `timerRemaining`
+//!
+//! Units are switched to automatic with `auto`, to a fixed stage with
+//! `stage/<n>` and to full power for a while with `boost/<minutes>`.
+
+use crate::error::{LoxoneError, Result};
+pub use crate::services::sensor_registry::is_ventilation_control_type;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as ventilation units
+pub const VENTILATION_CONTROL_TYPES: &[&str] = &["Ventilation", "AirHandling"];
+
+/// Candidate state names per reported value, in order of preference
+const STATE_ALIASES: &[(&str, &[&str])] = &[
+    ("co2", &["airQualityIn", "co2"]),
+    ("humidity", &["humidityIn", "humidity"]),
+    ("voc", &["vocIn", "voc"]),
+    ("stage", &["stage", "speed"]),
+    ("mode", &["mode"]),
+    ("timer", &["timerRemaining"]),
+];
+
+/// Highest fan stage
+pub const MAX_STAGE: u8 = 4;
+
+/// Default boost duration
+pub const DEFAULT_BOOST_MINUTES: u32 = 30;
+
+/// CO2 level above which the air counts as stale (DIN EN 13779 IDA 3)
+const CO2_STALE_PPM: f64 = 1000.0;
+
+/// Mode requested for a ventilation unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VentilationMode {
+    /// Let the unit follow air quality and presence
+    Auto,
+    /// Fixed fan stage
+    Stage(u8),
+    /// Full power for a number of minutes
+    Boost(u32),
+}
+
+impl VentilationMode {
+    /// Parse an action with its optional stage or duration
+    pub fn parse(action: &str, stage: Option<u8>, minutes: Option<u32>) -> Result<Self> {
+        match action.trim().to_lowercase().as_str() {
+            "auto" | "automatic" | "automatik" => Ok(Self::Auto),
+            "stage" | "stufe" | "manual" => {
+                let stage = stage.ok_or_else(|| {
+                    LoxoneError::invalid_input(format!("A stage (0-{MAX_STAGE}) is required"))
+                })?;
+                if stage > MAX_STAGE {
+                    return Err(LoxoneError::invalid_input(format!(
+                        "Stage must be between 0-{MAX_STAGE}"
+                    )));
+                }
+                Ok(Self::Stage(stage))
+            }
+            "boost" | "stoßlüften" | "stosslueften" => {
+                let minutes = minutes.unwrap_or(DEFAULT_BOOST_MINUTES);
+                if !(1..=240).contains(&minutes) {
+                    return Err(LoxoneError::invalid_input(
+                        "Boost duration must be between 1-240 minutes",
+                    ));
+                }
+                Ok(Self::Boost(minutes))
+            }
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid ventilation action '{other}'. Use: auto, stage, boost"
+            ))),
+        }
+    }
+
+    /// Miniserver command for the mode
+    pub fn command(self) -> String {
+        match self {
+            Self::Auto => "auto".to_string(),
+            Self::Stage(stage) => format!("stage/{stage}"),
+            Self::Boost(minutes) => format!("boost/{minutes}"),
+        }
+    }
+
+    /// Name used in tool output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Stage(_) => "stage",
+            Self::Boost(_) => "boost",
+        }
+    }
+}
+
+/// Snapshot of a ventilation unit's state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VentilationStatus {
+    pub co2_ppm: Option<f64>,
+    pub humidity_percent: Option<f64>,
+    pub voc_index: Option<f64>,
+    pub stage: Option<u8>,
+    /// `auto`, `manual`, `boost` or `unknown`
+    pub mode: &'static str,
+    pub boost_remaining_secs: Option<u32>,
+    /// CO2 above 1000 ppm
+    pub air_stale: bool,
+}
+
+impl VentilationStatus {
+    /// Build the status from values keyed by the names in [`state_uuids`]
+    pub fn from_state_values(values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+
+        let co2 = number("co2");
+        Self {
+            co2_ppm: co2,
+            humidity_percent: number("humidity"),
+            voc_index: number("voc"),
+            stage: number("stage").map(|s| s.clamp(0.0, MAX_STAGE as f64).round() as u8),
+            mode: match number("mode").map(|m| m as i64) {
+                Some(0) => "auto",
+                Some(1) => "manual",
+                Some(2) => "boost",
+                _ => "unknown",
+            },
+            boost_remaining_secs: number("timer")
+                .filter(|t| *t > 0.0)
+                .map(|t| t.round() as u32),
+            air_stale: co2.is_some_and(|c| c > CO2_STALE_PPM),
+        }
+    }
+}
+
+/// State UUIDs of a ventilation control, keyed by reported value
+pub fn state_uuids(control: &Value) -> HashMap<String, String> {
+    let states = control.get("states");
+    STATE_ALIASES
+        .iter()
+        .filter_map(|(value, aliases)| {
+            aliases.iter().find_map(|alias| {
+                states
+                    .and_then(|states| states.get(*alias))
+                    .and_then(Value::as_str)
+                    .map(|uuid| ((*value).to_string(), uuid.to_string()))
+            })
+        })
+        .collect()
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_modes() {
+        assert_eq!(
+            VentilationMode::parse("Stufe", Some(3), None)
+                .unwrap()
+                .command(),
+            "stage/3"
+        );
+        assert!(VentilationMode::parse("stage", None, None).is_err());
+        assert!(VentilationMode::parse("stage", Some(5), None).is_err());
+        assert_eq!(
+            VentilationMode::parse("boost", None, None).unwrap(),
+            VentilationMode::Boost(DEFAULT_BOOST_MINUTES)
+        );
+        assert_eq!(
+            VentilationMode::parse("auto", None, None)
+                .unwrap()
+                .command(),
+            "auto"
+        );
+        assert!(VentilationMode::parse("off", None, None).is_err());
+    }
+
+    #[test]
+    fn test_status_from_aliased_states() {
+        let control = json!({
+            "type": "AirHandling",
+            "states": {"co2": "s-co2", "humidityIn": "s-hum", "speed": "s-speed", "mode": "s-mode"}
+        });
+        let uuids = state_uuids(&control);
+        assert_eq!(uuids["co2"], "s-co2");
+        assert_eq!(uuids["humidity"], "s-hum");
+        assert_eq!(uuids["stage"], "s-speed");
+        assert!(!uuids.contains_key("voc"));
+
+        let values = HashMap::from([
+            ("co2".to_string(), json!(1240)),
+            ("humidity".to_string(), json!("48.5")),
+            ("stage".to_string(), json!(2)),
+            ("mode".to_string(), json!(0)),
+        ]);
+        let status = VentilationStatus::from_state_values(&values);
+        assert_eq!(status.co2_ppm, Some(1240.0));
+        assert_eq!(status.humidity_percent, Some(48.5));
+        assert_eq!(status.stage, Some(2));
+        assert_eq!(status.mode, "auto");
+        assert!(status.air_stale);
+
+        assert!(is_ventilation_control_type("ventilation"));
+        assert!(!is_ventilation_control_type("IRoomControllerV2"));
+    }
+}
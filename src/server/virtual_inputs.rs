@@ -0,0 +1,207 @@
+//! Virtual input handling
+//!
+//! Virtual inputs are the standard way for external systems to feed values
+//! into a Loxone configuration. In the structure file they show up as the
+//! control type chosen for their visualization:
+//!
+//! - digital: `Switch` (on/off) and `Pushbutton` (on/off/pulse)
+//! - analog: `Slider` and `ValueSelector`, with `min`/`max`/`step` in `details`
+//! - text: `TextInput`
+//!
+//! This module maps a requested value onto the command the Miniserver expects
+//! for each kind, rejecting values the input cannot accept.
+
+use crate::error::{LoxoneError, Result};
+use serde_json::Value;
+
+/// Maximum length accepted for virtual text inputs
+pub const MAX_TEXT_INPUT_LENGTH: usize = 255;
+
+/// Kind of virtual input, derived from the control type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualInputKind {
+    /// On/off input
+    Digital,
+    /// Analog value input
+    Analog,
+    /// Free-form text input
+    Text,
+}
+
+impl VirtualInputKind {
+    /// Classify a control type, returning `None` for non-input controls
+    pub fn from_control_type(control_type: &str) -> Option<Self> {
+        match control_type {
+            "Switch" | "Pushbutton" => Some(Self::Digital),
+            "Slider" | "ValueSelector" => Some(Self::Analog),
+            "TextInput" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// Name used in tool output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Digital => "digital",
+            Self::Analog => "analog",
+            Self::Text => "text",
+        }
+    }
+}
+
+/// Range limits of an analog input from its structure `details`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalogRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub step: Option<f64>,
+}
+
+impl AnalogRange {
+    /// Read `min`, `max` and `step` from a control definition
+    pub fn from_control(control: &Value) -> Self {
+        let detail = |key: &str| {
+            control
+                .get("details")
+                .and_then(|d| d.get(key))
+                .and_then(Value::as_f64)
+        };
+        Self {
+            min: detail("min"),
+            max: detail("max"),
+            step: detail("step").filter(|step| *step > 0.0),
+        }
+    }
+}
+
+/// Validate a text value for a virtual text input
+pub fn validate_text(text: &str) -> Result<()> {
+    let length = text.chars().count();
+    if length > MAX_TEXT_INPUT_LENGTH {
+        return Err(LoxoneError::invalid_input(format!(
+            "Text is too long ({length} characters, maximum {MAX_TEXT_INPUT_LENGTH})"
+        )));
+    }
+    if text.chars().any(char::is_control) {
+        return Err(LoxoneError::invalid_input(
+            "Text must not contain control characters",
+        ));
+    }
+    Ok(())
+}
+
+/// Build the Miniserver command that sets `control` to `value`
+///
+/// Digital inputs accept on/off/true/false/1/0 (and `pulse` for push
+/// buttons), analog inputs a number within the configured range and text
+/// inputs any printable string, which is URL-encoded for the command path.
+pub fn build_command(control: &Value, value: &str) -> Result<String> {
+    let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+    let kind = VirtualInputKind::from_control_type(control_type).ok_or_else(|| {
+        LoxoneError::invalid_input(format!(
+            "Control type '{control_type}' is not a virtual input"
+        ))
+    })?;
+
+    match kind {
+        VirtualInputKind::Digital => {
+            let command = match value.trim().to_lowercase().as_str() {
+                "on" | "true" | "1" => "on",
+                "off" | "false" | "0" => "off",
+                "pulse" if control_type == "Pushbutton" => "pulse",
+                other => {
+                    return Err(LoxoneError::invalid_input(format!(
+                        "Invalid value '{other}' for digital input (use on/off{})",
+                        if control_type == "Pushbutton" {
+                            "/pulse"
+                        } else {
+                            ""
+                        }
+                    )));
+                }
+            };
+            Ok(command.to_string())
+        }
+        VirtualInputKind::Analog => {
+            let number: f64 = value.trim().parse().map_err(|_| {
+                LoxoneError::invalid_input(format!("Analog input expects a number, got '{value}'"))
+            })?;
+            if !number.is_finite() {
+                return Err(LoxoneError::invalid_input(
+                    "Analog input value must be finite",
+                ));
+            }
+
+            let range = AnalogRange::from_control(control);
+            if range.min.is_some_and(|min| number < min)
+                || range.max.is_some_and(|max| number > max)
+            {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Value {number} is outside the input range {}..{}",
+                    range.min.map(|v| v.to_string()).unwrap_or_default(),
+                    range.max.map(|v| v.to_string()).unwrap_or_default()
+                )));
+            }
+            if let Some(step) = range.step {
+                let offset = (number - range.min.unwrap_or(0.0)) / step;
+                if (offset - offset.round()).abs() > 1e-6 {
+                    return Err(LoxoneError::invalid_input(format!(
+                        "Value {number} does not match the input step {step}"
+                    )));
+                }
+            }
+            Ok(number.to_string())
+        }
+        VirtualInputKind::Text => {
+            validate_text(value)?;
+            Ok(urlencoding::encode(value).into_owned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_digital_commands() {
+        let switch = json!({"type": "Switch"});
+        assert_eq!(build_command(&switch, "ON").unwrap(), "on");
+        assert_eq!(build_command(&switch, "0").unwrap(), "off");
+        assert!(build_command(&switch, "pulse").is_err());
+
+        let button = json!({"type": "Pushbutton"});
+        assert_eq!(build_command(&button, "pulse").unwrap(), "pulse");
+        assert!(build_command(&button, "maybe").is_err());
+    }
+
+    #[test]
+    fn test_analog_range_and_step() {
+        let slider = json!({
+            "type": "Slider",
+            "details": {"min": 0.0, "max": 100.0, "step": 0.5}
+        });
+        assert_eq!(build_command(&slider, "42.5").unwrap(), "42.5");
+        assert!(build_command(&slider, "101").is_err());
+        assert!(build_command(&slider, "42.3").is_err());
+        assert!(build_command(&slider, "warm").is_err());
+
+        let unbounded = json!({"type": "ValueSelector"});
+        assert_eq!(build_command(&unbounded, "-7").unwrap(), "-7");
+    }
+
+    #[test]
+    fn test_text_and_unsupported_inputs() {
+        let text = json!({"type": "TextInput"});
+        assert_eq!(
+            build_command(&text, "Back at 6/7").unwrap(),
+            "Back%20at%206%2F7"
+        );
+        assert!(build_command(&text, "line\nbreak").is_err());
+        assert!(build_command(&text, &"x".repeat(MAX_TEXT_INPUT_LENGTH + 1)).is_err());
+
+        assert!(build_command(&json!({"type": "Jalousie"}), "on").is_err());
+        assert_eq!(VirtualInputKind::from_control_type("Jalousie"), None);
+    }
+}
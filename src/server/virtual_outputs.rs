@@ -0,0 +1,196 @@
+//! Virtual output handling
+//!
+//! Virtual outputs are how a Loxone configuration talks to external systems:
+//! each virtual output holds the address of the receiver (`http://host` or
+//! `/dev/udp/host/port`), and each of its output commands sends a request
+//! when the command's input changes. Output commands shown in the user
+//! interface appear in the structure file as:
+//!
+//! - `VirtualOutCmd`: digital, sends `details.commandOn` / `commandOff`
+//! - `VirtualOutCmdAnalog`: analog, sends the value in `details.commandOn`
+//!   (as `<v>`), with `min`/`max`/`step` in `details`
+//!
+//! `details.address` carries the address of the parent output. An output
+//! command is driven like an input: `on`, `off` or `pulse` for digital and
+//! a number for analog commands, validated as for virtual inputs.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::virtual_inputs::AnalogRange;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Control types of virtual output commands
+pub const VIRTUAL_OUTPUT_TYPES: &[&str] = &["VirtualOutCmd", "VirtualOutCmdAnalog"];
+
+/// Kind of virtual output command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    Digital,
+    Analog,
+}
+
+impl OutputKind {
+    /// Classify a control type, returning `None` for other controls
+    pub fn from_control_type(control_type: &str) -> Option<Self> {
+        match control_type {
+            "VirtualOutCmd" => Some(Self::Digital),
+            "VirtualOutCmdAnalog" => Some(Self::Analog),
+            _ => None,
+        }
+    }
+}
+
+/// Transport the parent output sends with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    Http,
+    Udp,
+    Unknown,
+}
+
+impl Protocol {
+    /// Protocol of an output address
+    pub fn of(address: &str) -> Self {
+        let address = address.trim().to_lowercase();
+        if address.starts_with("/dev/udp/") {
+            Self::Udp
+        } else if address.starts_with("http://") || address.starts_with("https://") {
+            Self::Http
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// A virtual output command from the structure file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VirtualOutput {
+    pub kind: OutputKind,
+    pub address: Option<String>,
+    pub protocol: Protocol,
+    pub command_on: Option<String>,
+    pub command_off: Option<String>,
+}
+
+impl VirtualOutput {
+    /// Read an output command, `None` for other controls
+    pub fn from_control(control: &Value) -> Option<Self> {
+        let kind = OutputKind::from_control_type(control.get("type")?.as_str()?)?;
+        let detail = |key: &str| {
+            control
+                .get("details")
+                .and_then(|d| d.get(key))
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let address = detail("address");
+        Some(Self {
+            kind,
+            protocol: address
+                .as_deref()
+                .map(Protocol::of)
+                .unwrap_or(Protocol::Unknown),
+            address,
+            command_on: detail("commandOn"),
+            command_off: detail("commandOff"),
+        })
+    }
+}
+
+/// Build the Miniserver command that drives `control` with `value`
+///
+/// Digital commands accept on/off/true/false/1/0/pulse, analog commands a
+/// number within the configured range and step.
+pub fn build_command(control: &Value, value: &str) -> Result<String> {
+    let output = VirtualOutput::from_control(control).ok_or_else(|| {
+        LoxoneError::invalid_input(format!(
+            "Control type '{}' is not a virtual output command",
+            control.get("type").and_then(Value::as_str).unwrap_or("")
+        ))
+    })?;
+
+    match output.kind {
+        OutputKind::Digital => match value.trim().to_lowercase().as_str() {
+            "on" | "true" | "1" => Ok("on".to_string()),
+            "off" | "false" | "0" => Ok("off".to_string()),
+            "pulse" => Ok("pulse".to_string()),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid value '{other}' for digital output (use on/off/pulse)"
+            ))),
+        },
+        OutputKind::Analog => {
+            let number: f64 = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|n: &f64| n.is_finite())
+                .ok_or_else(|| {
+                    LoxoneError::invalid_input(format!(
+                        "Analog output expects a number, got '{value}'"
+                    ))
+                })?;
+            let range = AnalogRange::from_control(control);
+            if range.min.is_some_and(|min| number < min)
+                || range.max.is_some_and(|max| number > max)
+            {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Value {number} is outside the output range {}..{}",
+                    range.min.map(|v| v.to_string()).unwrap_or_default(),
+                    range.max.map(|v| v.to_string()).unwrap_or_default()
+                )));
+            }
+            if let Some(step) = range.step {
+                let offset = (number - range.min.unwrap_or(0.0)) / step;
+                if (offset - offset.round()).abs() > 1e-6 {
+                    return Err(LoxoneError::invalid_input(format!(
+                        "Value {number} does not match the output step {step}"
+                    )));
+                }
+            }
+            Ok(number.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_outputs_from_structure() {
+        let udp = json!({
+            "type": "VirtualOutCmd",
+            "details": {"address": "/dev/udp/192.168.1.20/7000", "commandOn": "ring", "commandOff": ""}
+        });
+        let output = VirtualOutput::from_control(&udp).unwrap();
+        assert_eq!(output.kind, OutputKind::Digital);
+        assert_eq!(output.protocol, Protocol::Udp);
+        assert_eq!(output.command_on.as_deref(), Some("ring"));
+        assert_eq!(output.command_off, None);
+
+        assert_eq!(Protocol::of("http://nas.local"), Protocol::Http);
+        assert!(VirtualOutput::from_control(&json!({"type": "Switch"})).is_none());
+    }
+
+    #[test]
+    fn test_commands() {
+        let digital = json!({"type": "VirtualOutCmd"});
+        assert_eq!(build_command(&digital, "TRUE").unwrap(), "on");
+        assert_eq!(build_command(&digital, "pulse").unwrap(), "pulse");
+        assert!(build_command(&digital, "50").is_err());
+
+        let analog = json!({
+            "type": "VirtualOutCmdAnalog",
+            "details": {"min": 0.0, "max": 10.0, "step": 0.5}
+        });
+        assert_eq!(build_command(&analog, "7.5").unwrap(), "7.5");
+        assert!(build_command(&analog, "11").is_err());
+        assert!(build_command(&analog, "7.2").is_err());
+        assert!(build_command(&analog, "NaN").is_err());
+        assert!(build_command(&json!({"type": "Slider"}), "1").is_err());
+    }
+}
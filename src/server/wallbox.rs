@@ -0,0 +1,241 @@
+//! Wallbox (EV charger) handling
+//!
+//! Loxone exposes chargers as `Wallbox2` controls (`Wallbox` and
+//! `EVCharger` on older configurations). Their `states` map names to state
+//! UUIDs:
+//!
+//! - `connected`: 1 while a vehicle is plugged in
+//! - `enabled`: 1 while charging is allowed
+//! - `active`: 1 while energy flows
+//! - `power`: charging power in kW
+//! - `energySession`: energy charged in the current session in kWh
+//! - `currentLimit`: charging current limit in A, within `minLimit`..`maxLimit`
+//! - `sessions`: JSON list of past sessions, newest last
+//!
+//! Charging is allowed with `allow/1` and paused with `allow/0`; the limit
+//! is set with `limit/<A>`.
+
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control types handled as wallboxes
+pub const WALLBOX_CONTROL_TYPES: &[&str] = &["Wallbox2", "Wallbox", "EVCharger"];
+
+/// Resource with the current session of each wallbox
+pub const WALLBOX_URI: &str = "loxone://energy/wallbox";
+
+/// State names read for a wallbox status
+pub const WALLBOX_STATE_NAMES: &[&str] = &[
+    "connected",
+    "enabled",
+    "active",
+    "power",
+    "energySession",
+    "currentLimit",
+    "minLimit",
+    "maxLimit",
+    "sessions",
+];
+
+/// Charging current range of IEC 61851 chargers, used when the wallbox
+/// does not report its own
+const DEFAULT_LIMIT_RANGE: (f64, f64) = (6.0, 32.0);
+
+/// Action requested for a wallbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargingAction {
+    Start,
+    Pause,
+}
+
+impl ChargingAction {
+    /// Parse an action name (English or German); `stop` pauses
+    pub fn parse(action: &str) -> Result<Self> {
+        match action.trim().to_lowercase().as_str() {
+            "start" | "resume" | "laden" => Ok(Self::Start),
+            "pause" | "stop" | "pausieren" | "stoppen" => Ok(Self::Pause),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Invalid charging action '{other}'. Use: start, pause"
+            ))),
+        }
+    }
+
+    /// Miniserver command for the action
+    pub fn command(self) -> &'static str {
+        match self {
+            Self::Start => "allow/1",
+            Self::Pause => "allow/0",
+        }
+    }
+
+    /// Name used in tool output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Pause => "pause",
+        }
+    }
+}
+
+/// A finished charging session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChargingSession {
+    /// Start as reported by the Miniserver
+    #[serde(default)]
+    pub start: Option<Value>,
+    #[serde(default)]
+    pub end: Option<Value>,
+    /// Energy charged in kWh
+    #[serde(default, alias = "energy")]
+    pub energy_kwh: Option<f64>,
+    /// Charging duration in seconds
+    #[serde(default)]
+    pub duration: Option<f64>,
+}
+
+/// Snapshot of a wallbox's state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WallboxStatus {
+    pub vehicle_connected: bool,
+    pub charging_allowed: bool,
+    pub charging: bool,
+    pub power_kw: Option<f64>,
+    pub session_energy_kwh: Option<f64>,
+    pub current_limit_a: Option<f64>,
+    pub min_limit_a: Option<f64>,
+    pub max_limit_a: Option<f64>,
+    /// Past sessions, newest first
+    pub sessions: Vec<ChargingSession>,
+}
+
+impl WallboxStatus {
+    /// Build the status from state values keyed by state name
+    pub fn from_state_values(values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+        let flag = |name: &str| number(name).is_some_and(|v| v != 0.0);
+
+        let power = number("power");
+        Self {
+            vehicle_connected: flag("connected"),
+            charging_allowed: flag("enabled"),
+            charging: flag("active") || power.is_some_and(|p| p > 0.0),
+            power_kw: power,
+            session_energy_kwh: number("energySession"),
+            current_limit_a: number("currentLimit"),
+            min_limit_a: number("minLimit"),
+            max_limit_a: number("maxLimit"),
+            sessions: values
+                .get("sessions")
+                .map(parse_sessions)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Command setting the charging current limit, checked against the
+    /// wallbox's range
+    pub fn limit_command(&self, amps: f64) -> Result<String> {
+        let min = self.min_limit_a.unwrap_or(DEFAULT_LIMIT_RANGE.0);
+        let max = self.max_limit_a.unwrap_or(DEFAULT_LIMIT_RANGE.1);
+        if !(min..=max).contains(&amps) {
+            return Err(LoxoneError::invalid_input(format!(
+                "Charging current must be between {min} A and {max} A"
+            )));
+        }
+        Ok(format!("limit/{amps}"))
+    }
+}
+
+/// Sessions from a `sessions` state value, newest first
+pub fn parse_sessions(value: &Value) -> Vec<ChargingSession> {
+    let list = match value {
+        Value::String(text) => serde_json::from_str(text).unwrap_or(Value::Null),
+        other => other.clone(),
+    };
+    let mut sessions: Vec<ChargingSession> = list
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|session| serde_json::from_value(session.clone()).ok())
+        .collect();
+    sessions.reverse();
+    sessions
+}
+
+/// State UUIDs of a wallbox control, keyed by state name
+pub fn wallbox_state_uuids(control: &Value) -> HashMap<String, String> {
+    WALLBOX_STATE_NAMES
+        .iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(*name))
+                .and_then(Value::as_str)
+                .map(|uuid| ((*name).to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(ChargingAction::parse("Laden").unwrap().command(), "allow/1");
+        assert_eq!(
+            ChargingAction::parse("stop").unwrap(),
+            ChargingAction::Pause
+        );
+        assert!(ChargingAction::parse("boost").is_err());
+    }
+
+    #[test]
+    fn test_wallbox_status() {
+        let status = WallboxStatus::from_state_values(&values(&[
+            ("connected", json!(1)),
+            ("enabled", json!(1)),
+            ("power", json!("7.2")),
+            ("energySession", json!(12.5)),
+            ("currentLimit", json!(16)),
+            ("minLimit", json!(6)),
+            ("maxLimit", json!(16)),
+            (
+                "sessions",
+                json!(r#"[{"start":1700000000,"energy":20.1},{"start":1700100000,"energy":8.4}]"#),
+            ),
+        ]));
+        assert!(status.vehicle_connected);
+        assert!(status.charging);
+        assert_eq!(status.session_energy_kwh, Some(12.5));
+        assert_eq!(status.sessions.len(), 2);
+        assert_eq!(status.sessions[0].energy_kwh, Some(8.4));
+
+        assert_eq!(status.limit_command(10.0).unwrap(), "limit/10");
+        assert!(status.limit_command(20.0).is_err());
+
+        let idle = WallboxStatus::from_state_values(&HashMap::new());
+        assert!(!idle.charging);
+        assert!(idle.limit_command(32.0).is_ok());
+        assert!(idle.limit_command(5.0).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Weather Service forecast
+//!
+//! Backs the `get_weather_forecast` tool and the `loxone://weather/forecast`
+//! resource. Unlike the weather station, which measures on site, the Loxone
+//! Weather Service needs a license; the Miniserver then returns hourly
+//! entries for the coming days ([`crate::client::WEATHER_FORECAST_PATH`]):
+//!
+//! ```json
+//! {"hourly": [{"timestamp": 1760000400, "temperature": 14.2,
+//!   "perceivedTemperature": 13.1, "precipitation": 0.4,
+//!   "precipitationProbability": 60, "windSpeed": 12.0, "windDirection": 240,
+//!   "relativeHumidity": 78, "cloudCover": 90, "weatherType": 16}]}
+//! ```
+//!
+//! Speeds are km/h, precipitation mm per hour. Daily forecasts are
+//! aggregated from the hourly entries by local date.
+
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// URI of the forecast resource
+pub const FORECAST_URI: &str = "loxone://weather/forecast";
+
+/// Default number of hourly entries returned
+pub const DEFAULT_FORECAST_HOURS: usize = 24;
+
+/// Default number of days returned
+pub const DEFAULT_FORECAST_DAYS: usize = 7;
+
+/// One hour of the forecast
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HourlyForecast {
+    pub time: DateTime<Utc>,
+    pub temperature: Option<f64>,
+    pub perceived_temperature: Option<f64>,
+    pub precipitation_mm: Option<f64>,
+    pub precipitation_probability: Option<f64>,
+    pub wind_speed_kmh: Option<f64>,
+    pub wind_direction: Option<f64>,
+    pub humidity_percent: Option<f64>,
+    pub cloud_cover_percent: Option<f64>,
+    /// Weather Service condition code
+    pub weather_type: Option<u32>,
+}
+
+/// One day of the forecast, aggregated from its hours
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyForecast {
+    pub date: NaiveDate,
+    pub min_temperature: Option<f64>,
+    pub max_temperature: Option<f64>,
+    pub precipitation_mm: f64,
+    pub max_precipitation_probability: Option<f64>,
+    pub max_wind_speed_kmh: Option<f64>,
+    /// Hours of the day the forecast covers
+    pub hours: usize,
+}
+
+/// Parse the forecast response, `{"hourly": [...]}` or the bare list
+pub fn parse_hourly(value: &Value) -> Result<Vec<HourlyForecast>> {
+    let entries = value
+        .get("hourly")
+        .unwrap_or(value)
+        .as_array()
+        .ok_or_else(|| LoxoneError::parsing_error("Unexpected weather forecast: no hourly list"))?;
+
+    let mut hours: Vec<HourlyForecast> = entries
+        .iter()
+        .filter_map(|entry| {
+            let number = |name: &str| {
+                entry
+                    .get(name)
+                    .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+            };
+            let time = match entry.get("timestamp").and_then(Value::as_i64) {
+                Some(seconds) => DateTime::from_timestamp(seconds, 0)?,
+                None => DateTime::parse_from_rfc3339(entry.get("time")?.as_str()?)
+                    .ok()?
+                    .with_timezone(&Utc),
+            };
+            Some(HourlyForecast {
+                time,
+                temperature: number("temperature"),
+                perceived_temperature: number("perceivedTemperature"),
+                precipitation_mm: number("precipitation"),
+                precipitation_probability: number("precipitationProbability"),
+                wind_speed_kmh: number("windSpeed"),
+                wind_direction: number("windDirection"),
+                humidity_percent: number("relativeHumidity"),
+                cloud_cover_percent: number("cloudCover"),
+                weather_type: number("weatherType").map(|t| t as u32),
+            })
+        })
+        .collect();
+    hours.sort_by_key(|hour| hour.time);
+    Ok(hours)
+}
+
+/// Aggregate hours into days of the given UTC offset
+pub fn daily(hours: &[HourlyForecast], offset: FixedOffset) -> Vec<DailyForecast> {
+    let mut days: BTreeMap<NaiveDate, Vec<&HourlyForecast>> = BTreeMap::new();
+    for hour in hours {
+        days.entry(hour.time.with_timezone(&offset).date_naive())
+            .or_default()
+            .push(hour);
+    }
+
+    let fold = |values: Vec<f64>, pick: fn(f64, f64) -> f64| values.into_iter().reduce(pick);
+    days.into_iter()
+        .map(|(date, hours)| {
+            let values = |field: fn(&HourlyForecast) -> Option<f64>| -> Vec<f64> {
+                hours.iter().filter_map(|&hour| field(hour)).collect()
+            };
+            let temperatures = values(|h| h.temperature);
+            DailyForecast {
+                date,
+                min_temperature: fold(temperatures.clone(), f64::min),
+                max_temperature: fold(temperatures, f64::max),
+                precipitation_mm: (values(|h| h.precipitation_mm).iter().sum::<f64>() * 10.0)
+                    .round()
+                    / 10.0,
+                max_precipitation_probability: fold(
+                    values(|h| h.precipitation_probability),
+                    f64::max,
+                ),
+                max_wind_speed_kmh: fold(values(|h| h.wind_speed_kmh), f64::max),
+                hours: hours.len(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_aggregate() {
+        let hours = parse_hourly(&json!({"hourly": [
+            {"timestamp": 1767315600, "temperature": 4.0, "precipitation": 0.5,
+             "precipitationProbability": 70, "windSpeed": "20"},
+            {"time": "2026-01-01T22:00:00Z", "temperature": 1.5, "precipitation": 0.25},
+            {"timestamp": 1767229200, "temperature": -1.0, "weatherType": 4},
+            {"temperature": 9.0}
+        ]}))
+        .unwrap();
+        // Sorted by time, the entry without one dropped
+        assert_eq!(hours.len(), 3);
+        assert_eq!(hours[0].weather_type, Some(4));
+        assert_eq!(hours[2].wind_speed_kmh, Some(20.0));
+
+        let utc = daily(&hours, FixedOffset::east_opt(0).unwrap());
+        assert_eq!(utc.len(), 2);
+        assert_eq!(utc[0].date.to_string(), "2026-01-01");
+        assert_eq!(utc[0].min_temperature, Some(-1.0));
+        assert_eq!(utc[0].max_temperature, Some(1.5));
+        assert_eq!(utc[1].precipitation_mm, 0.5);
+        assert_eq!(utc[1].max_precipitation_probability, Some(70.0));
+
+        // 22:00 UTC is the next day in UTC+3
+        let east = daily(&hours, FixedOffset::east_opt(3 * 3600).unwrap());
+        assert_eq!(east[1].hours, 2);
+        assert_eq!(east[1].precipitation_mm, 0.8);
+
+        assert!(parse_hourly(&json!({"error": "not licensed"})).is_err());
+    }
+}
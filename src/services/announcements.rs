@@ -0,0 +1,142 @@
+//! Announcement queue
+//!
+//! The Audioserver starts a new announcement right away, cutting off one
+//! still being spoken. Queued announcements are therefore played one at a
+//! time by the server: each holds the queue for its estimated duration
+//! ([`crate::server::audio::announcement_duration`]) before the next is
+//! sent. The queue lives in memory; queued announcements are dropped on
+//! restart.
+
+use crate::client::LoxoneClient;
+use crate::utils::clock::{SharedClock, system_clock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Announcements waiting at most; further ones are rejected
+pub const MAX_QUEUED: usize = 10;
+
+/// Announcements played one at a time
+pub struct Announcer {
+    /// Last announcement queued; the next one waits for it
+    tail: Mutex<Option<JoinHandle<()>>>,
+    queued: Arc<AtomicUsize>,
+    clock: SharedClock,
+}
+
+impl Default for Announcer {
+    fn default() -> Self {
+        Self {
+            tail: Mutex::new(None),
+            queued: Arc::new(AtomicUsize::new(0)),
+            clock: system_clock(),
+        }
+    }
+}
+
+impl Announcer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time announcements with `clock`; tests fast-forward it
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Announcements queued or playing
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// Queue `commands` (zone UUID, command) to be sent once the announcements
+    /// ahead have played; returns how many are ahead, or `None` when the
+    /// queue is full
+    pub fn enqueue(
+        &self,
+        client: Arc<dyn LoxoneClient>,
+        commands: Vec<(String, String)>,
+        duration: Duration,
+    ) -> Option<usize> {
+        let ahead = self
+            .queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < MAX_QUEUED).then_some(queued + 1)
+            })
+            .ok()?;
+
+        let queued = self.queued.clone();
+        let clock = self.clock.clone();
+        let mut tail = self.tail.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = tail.take();
+        *tail = Some(tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+            match client.send_parallel_commands(commands).await {
+                Ok(results) => {
+                    let failed = results.iter().filter(|r| r.is_err()).count();
+                    if failed > 0 {
+                        warn!("Announcement failed on {failed} zone(s)");
+                    } else {
+                        info!("Announcement sent to {} zone(s)", results.len());
+                    }
+                }
+                Err(e) => warn!("Failed to send announcement: {e}"),
+            }
+            clock.sleep(duration).await;
+            queued.fetch_sub(1, Ordering::SeqCst);
+        }));
+        Some(ahead)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{ScriptedClient, device_uuid, sample_house};
+    use crate::utils::clock::ManualClock;
+
+    #[tokio::test]
+    async fn test_announcements_do_not_overlap() {
+        let clock = Arc::new(ManualClock::new());
+        let client = Arc::new(ScriptedClient::new(sample_house()));
+        let announcer = Announcer::new().with_clock(clock.clone());
+        let zone = device_uuid("Kitchen", "Ceiling");
+
+        let first = vec![(zone.clone(), "tts/One".to_string())];
+        let second = vec![(zone.clone(), "tts/Two".to_string())];
+        assert_eq!(
+            announcer.enqueue(client.clone(), first, Duration::from_secs(5)),
+            Some(0)
+        );
+        assert_eq!(
+            announcer.enqueue(client.clone(), second, Duration::from_secs(5)),
+            Some(1)
+        );
+
+        // The second waits until the first has played
+        clock.wait_for_sleeps(1).await;
+        assert_eq!(client.commands_for(&zone), vec!["tts/One"]);
+        clock.advance(Duration::from_secs(5));
+        clock.wait_for_sleeps(2).await;
+        assert_eq!(client.commands_for(&zone), vec!["tts/One", "tts/Two"]);
+        assert_eq!(announcer.queued(), 1);
+
+        for _ in 0..MAX_QUEUED - 1 {
+            assert!(
+                announcer
+                    .enqueue(client.clone(), vec![], Duration::from_secs(5))
+                    .is_some()
+            );
+        }
+        assert!(
+            announcer
+                .enqueue(client.clone(), vec![], Duration::from_secs(5))
+                .is_none()
+        );
+    }
+}
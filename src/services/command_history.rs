@@ -0,0 +1,451 @@
+//! Command history
+//!
+//! Every outbound device command is appended to an event log: which device
+//! and command, when, through which tool and API key, and what the
+//! Miniserver answered. The log is exposed as `loxone://history/commands`
+//! so users can audit what an assistant actually did in their home.
+//!
+//! Records are kept in memory up to a capacity; with a log file configured
+//! every record is also appended as one JSON line and the in-memory window
+//! is rebuilt from that file on startup.
+
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, Utc};
+use humantime_serde::re::humantime;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::warn;
+
+/// URI of the command history resource
+pub const COMMAND_HISTORY_URI: &str = "loxone://history/commands";
+
+/// Records kept in memory by default
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Records returned by a query without an explicit limit
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// Upper bound for the query limit
+pub const MAX_QUERY_LIMIT: usize = 1_000;
+
+tokio::task_local! {
+    static ORIGIN: CommandOrigin;
+}
+
+/// Who issued the commands of the current task
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommandOrigin {
+    /// Tool the command was sent from
+    pub tool: Option<String>,
+    /// Label of the API key the server runs under
+    pub key: Option<String>,
+}
+
+impl CommandOrigin {
+    /// Run `future` with this origin attached to all commands it sends
+    pub async fn scope<F: std::future::Future>(self, future: F) -> F::Output {
+        ORIGIN.scope(self, future).await
+    }
+
+    /// Origin of the current task (empty outside of a scope)
+    pub fn current() -> Self {
+        ORIGIN.try_with(Clone::clone).unwrap_or_default()
+    }
+}
+
+/// Loggable label for an API key
+///
+/// Key IDs look like `lmcp_{role}_{seq}_{random}`; the random part is the
+/// secret and is dropped. Other keys are reduced to a short prefix.
+pub fn key_label(key_id: &str) -> String {
+    if key_id.starts_with("lmcp_")
+        && let Some((label, _secret)) = key_id.rsplit_once('_')
+        && label.matches('_').count() >= 2
+    {
+        return label.to_string();
+    }
+    let prefix: String = key_id.chars().take(6).collect();
+    format!("{prefix}…")
+}
+
+/// Command history settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandHistoryConfig {
+    /// JSON-lines file the history is appended to (memory only if unset)
+    #[serde(default)]
+    pub log_file: Option<PathBuf>,
+    /// Records kept in memory
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+}
+
+fn default_capacity() -> usize {
+    DEFAULT_CAPACITY
+}
+
+impl Default for CommandHistoryConfig {
+    fn default() -> Self {
+        Self {
+            log_file: std::env::var("LOXONE_COMMAND_HISTORY_FILE")
+                .ok()
+                .map(PathBuf::from),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+}
+
+/// One device command sent to the Miniserver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Device UUID the command was sent to
+    pub device: String,
+    pub command: String,
+    #[serde(flatten)]
+    pub origin: CommandOrigin,
+    pub success: bool,
+    /// Miniserver response code, if a response was received
+    pub code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Filter for history queries
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandFilter {
+    /// Device UUID
+    pub device: Option<String>,
+    pub tool: Option<String>,
+    pub key: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub success: Option<bool>,
+    /// Maximum number of records (newest first)
+    pub limit: Option<usize>,
+}
+
+impl CommandFilter {
+    /// Build a filter from resource query parameters
+    ///
+    /// `since` accepts an RFC 3339 timestamp or a look-back duration
+    /// such as `30m` or `24h`.
+    pub fn from_query_params(params: &HashMap<String, String>) -> Result<Self> {
+        let since = params.get("since").map(|s| parse_since(s)).transpose()?;
+        let success = params
+            .get("success")
+            .map(|s| {
+                s.parse::<bool>().map_err(|_| {
+                    LoxoneError::invalid_input(format!("'success' must be true or false, got {s}"))
+                })
+            })
+            .transpose()?;
+        let limit = params
+            .get("limit")
+            .map(|s| {
+                s.parse::<usize>().map_err(|_| {
+                    LoxoneError::invalid_input(format!("'limit' must be a number, got {s}"))
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            device: params.get("device").cloned(),
+            tool: params.get("tool").cloned(),
+            key: params.get("key").cloned(),
+            since,
+            success,
+            limit,
+        })
+    }
+
+    fn matches(&self, record: &CommandRecord) -> bool {
+        self.device.as_ref().is_none_or(|d| &record.device == d)
+            && self
+                .tool
+                .as_ref()
+                .is_none_or(|t| record.origin.tool.as_ref() == Some(t))
+            && self
+                .key
+                .as_ref()
+                .is_none_or(|k| record.origin.key.as_ref() == Some(k))
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.success.is_none_or(|s| record.success == s)
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a look-back duration
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let duration = humantime::parse_duration(value).map_err(|_| {
+        LoxoneError::invalid_input(format!(
+            "'since' must be an RFC 3339 timestamp or a duration like '24h', got {value}"
+        ))
+    })?;
+    let duration = chrono::Duration::from_std(duration)
+        .map_err(|_| LoxoneError::invalid_input(format!("'since' out of range: {value}")))?;
+    Ok(Utc::now() - duration)
+}
+
+/// Commands carrying a secret: prefix and index of the secret segment
+/// after it (Code Touch codes and NFC tag IDs)
+const SECRET_COMMANDS: &[(&str, usize)] = &[("codes/add/", 3)];
+
+/// A command with any secret it carries masked, as stored in the history
+pub fn redact(command: &str) -> String {
+    for (prefix, index) in SECRET_COMMANDS {
+        if let Some(rest) = command.strip_prefix(prefix) {
+            let segments: Vec<&str> = rest
+                .split('/')
+                .enumerate()
+                .map(|(i, segment)| if i == *index { "***" } else { segment })
+                .collect();
+            return format!("{prefix}{}", segments.join("/"));
+        }
+    }
+    command.to_string()
+}
+
+/// Append-only command log
+#[derive(Debug)]
+pub struct CommandHistory {
+    records: Mutex<VecDeque<CommandRecord>>,
+    capacity: usize,
+    next_id: AtomicU64,
+    log_file: Option<PathBuf>,
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::in_memory(DEFAULT_CAPACITY)
+    }
+}
+
+impl CommandHistory {
+    /// History kept in memory only
+    pub fn in_memory(capacity: usize) -> Self {
+        Self {
+            records: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+            next_id: AtomicU64::new(1),
+            log_file: None,
+        }
+    }
+
+    /// History backed by a JSON-lines file, replaying existing records
+    pub fn open(path: &Path, capacity: usize) -> Result<Self> {
+        let history = Self {
+            log_file: Some(path.to_path_buf()),
+            ..Self::in_memory(capacity)
+        };
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(LoxoneError::config(format!(
+                    "Failed to read command history {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+
+        let mut records = history.records.lock().unwrap_or_else(|e| e.into_inner());
+        for (line_no, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<CommandRecord>(line) {
+                Ok(record) => {
+                    history.next_id.fetch_max(record.id + 1, Ordering::Relaxed);
+                    records.push_back(record);
+                    if records.len() > history.capacity {
+                        records.pop_front();
+                    }
+                }
+                Err(e) => warn!(
+                    "Skipping malformed command history line {} in {}: {e}",
+                    line_no + 1,
+                    path.display()
+                ),
+            }
+        }
+        drop(records);
+        Ok(history)
+    }
+
+    /// Build the history from its configuration, falling back to memory
+    pub fn from_config(config: &CommandHistoryConfig) -> Self {
+        match &config.log_file {
+            Some(path) => Self::open(path, config.capacity).unwrap_or_else(|e| {
+                warn!("{e}; keeping command history in memory only");
+                Self::in_memory(config.capacity)
+            }),
+            None => Self::in_memory(config.capacity),
+        }
+    }
+
+    /// Append a command to the history
+    pub fn record(
+        &self,
+        device: &str,
+        command: &str,
+        origin: CommandOrigin,
+        outcome: std::result::Result<i32, String>,
+        duration_ms: u64,
+    ) -> CommandRecord {
+        let (success, code, error) = match outcome {
+            Ok(code) => ((200..300).contains(&code), Some(code), None),
+            Err(e) => (false, None, Some(e)),
+        };
+        let record = CommandRecord {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            timestamp: Utc::now(),
+            device: device.to_string(),
+            command: redact(command),
+            origin,
+            success,
+            code,
+            error,
+            duration_ms,
+        };
+
+        if let Some(path) = &self.log_file
+            && let Err(e) = append_line(path, &record)
+        {
+            warn!(
+                "Failed to append to command history {}: {e}",
+                path.display()
+            );
+        }
+
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        records.push_back(record.clone());
+        if records.len() > self.capacity {
+            records.pop_front();
+        }
+        record
+    }
+
+    /// Records matching `filter`, newest first
+    pub fn query(&self, filter: &CommandFilter) -> Vec<CommandRecord> {
+        let limit = filter
+            .limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .min(MAX_QUERY_LIMIT);
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .filter(|record| filter.matches(record))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Number of records held in memory
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn append_line(path: &Path, record: &CommandRecord) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    writeln!(file, "{line}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin(tool: &str) -> CommandOrigin {
+        CommandOrigin {
+            tool: Some(tool.to_string()),
+            key: Some("lmcp_operator_001".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_record_and_filter() {
+        let history = CommandHistory::in_memory(3);
+        history.record("light-1", "on", origin("control_device"), Ok(200), 12);
+        history.record("blind-1", "FullUp", origin("control_blinds"), Ok(200), 20);
+        history.record(
+            "light-1",
+            "off",
+            origin("control_device"),
+            Err("timeout".to_string()),
+            5000,
+        );
+        history.record("light-2", "on", origin("control_device"), Ok(500), 8);
+        assert_eq!(history.len(), 3);
+
+        let all = history.query(&CommandFilter::default());
+        assert_eq!(
+            all.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![4, 3, 2],
+            "newest first, oldest evicted"
+        );
+
+        let params = HashMap::from([
+            ("device".to_string(), "light-1".to_string()),
+            ("success".to_string(), "false".to_string()),
+            ("since".to_string(), "1h".to_string()),
+        ]);
+        let failed = history.query(&CommandFilter::from_query_params(&params).unwrap());
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].error.as_deref(), Some("timeout"));
+        assert!(!all[0].success, "non-2xx responses count as failures");
+
+        let bad = HashMap::from([("since".to_string(), "yesterday".to_string())]);
+        assert!(CommandFilter::from_query_params(&bad).is_err());
+
+        let code = history.record(
+            "touch-1",
+            "codes/add/0/Anna/1/4711/0/0",
+            origin("add_access_code"),
+            Ok(200),
+            9,
+        );
+        assert_eq!(code.command, "codes/add/0/Anna/1/***/0/0");
+        assert_eq!(redact("codes/delete/1a2b"), "codes/delete/1a2b");
+
+        assert_eq!(key_label("lmcp_admin_001_a1b2c3d4"), "lmcp_admin_001");
+        assert_eq!(key_label("supersecretkey"), "supers…");
+    }
+
+    #[tokio::test]
+    async fn test_log_file_replay_and_origin() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("commands.jsonl");
+
+        let history = CommandHistory::open(&path, 100).unwrap();
+        let origin = origin("control_device")
+            .scope(async { CommandOrigin::current() })
+            .await;
+        history.record("light-1", "on", origin, Ok(200), 10);
+        history.record("light-1", "off", CommandOrigin::current(), Ok(200), 10);
+
+        let replayed = CommandHistory::open(&path, 100).unwrap();
+        let records = replayed.query(&CommandFilter::default());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].origin.tool.as_deref(), Some("control_device"));
+        assert_eq!(records[0].origin, CommandOrigin::default());
+
+        let next = replayed.record("light-1", "on", CommandOrigin::default(), Ok(200), 1);
+        assert_eq!(next.id, 3);
+    }
+}
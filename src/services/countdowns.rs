@@ -0,0 +1,185 @@
+//! Countdowns with a custom duration
+//!
+//! A stairwell light switch (`TimedSwitch`) runs for the duration configured
+//! in Loxone Config when pulsed; the Miniserver has no command for a one-off
+//! duration. A custom duration is run by the server instead: the switch is
+//! turned on permanently and a countdown sends `off` when it expires.
+//! Countdowns live in memory and are lost on restart, leaving the switch on.
+
+use crate::client::LoxoneClient;
+use crate::utils::clock::{SharedClock, system_clock};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Command sent when a countdown expires
+pub const EXPIRE_COMMAND: &str = "off";
+
+/// Longest custom duration, one day
+pub const MAX_DURATION_SECS: u64 = 24 * 3600;
+
+struct Countdown {
+    id: u64,
+    ends_at: DateTime<Utc>,
+    task: JoinHandle<()>,
+}
+
+/// A running countdown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CountdownStatus {
+    pub device: String,
+    pub ends_at: DateTime<Utc>,
+    pub remaining_secs: u64,
+}
+
+/// Server-side countdowns by device UUID
+pub struct Countdowns {
+    running: Mutex<HashMap<String, Countdown>>,
+    next_id: AtomicU64,
+    clock: SharedClock,
+}
+
+impl Default for Countdowns {
+    fn default() -> Self {
+        Self {
+            running: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            clock: system_clock(),
+        }
+    }
+}
+
+impl Countdowns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time countdowns with `clock`; tests fast-forward it
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Send [`EXPIRE_COMMAND`] to `device` after `duration`, replacing a
+    /// countdown already running for it
+    pub fn start(
+        self: &Arc<Self>,
+        client: Arc<dyn LoxoneClient>,
+        device: &str,
+        duration: Duration,
+    ) -> CountdownStatus {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let ends_at = self.clock.utc_now()
+            + chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::zero());
+
+        // Registered before the task can look itself up
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let countdowns = self.clone();
+        let uuid = device.to_string();
+        let task = tokio::spawn(async move {
+            countdowns.clock.sleep(duration).await;
+            // Only the countdown still registered may remove itself
+            let current = {
+                let mut running = countdowns.running.lock().unwrap_or_else(|e| e.into_inner());
+                match running.get(&uuid) {
+                    Some(countdown) if countdown.id == id => running.remove(&uuid).is_some(),
+                    _ => false,
+                }
+            };
+            if !current {
+                return;
+            }
+            match client.send_command(&uuid, EXPIRE_COMMAND).await {
+                Ok(_) => info!("Countdown of {uuid} expired"),
+                Err(e) => warn!("Failed to end countdown of {uuid}: {e}"),
+            }
+        });
+
+        if let Some(previous) = running.insert(device.to_string(), Countdown { id, ends_at, task })
+        {
+            previous.task.abort();
+        }
+        CountdownStatus {
+            device: device.to_string(),
+            ends_at,
+            remaining_secs: duration.as_secs(),
+        }
+    }
+
+    /// Stop the countdown of `device` without sending anything; returns
+    /// whether one was running
+    pub fn cancel(&self, device: &str) -> bool {
+        match self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(device)
+        {
+            Some(countdown) => {
+                countdown.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The running countdown of `device`
+    pub fn status(&self, device: &str) -> Option<CountdownStatus> {
+        let now = self.clock.utc_now();
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(device)
+            .map(|countdown| CountdownStatus {
+                device: device.to_string(),
+                ends_at: countdown.ends_at,
+                remaining_secs: (countdown.ends_at - now).num_seconds().max(0) as u64,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{ScriptedClient, device_uuid, sample_house};
+    use crate::utils::clock::ManualClock;
+
+    #[tokio::test]
+    async fn test_countdown_expires_and_cancels() {
+        let clock = Arc::new(ManualClock::new());
+        let client = Arc::new(ScriptedClient::new(sample_house()));
+        let countdowns = Arc::new(Countdowns::new().with_clock(clock.clone()));
+        let device = device_uuid("Kitchen", "Ceiling");
+
+        countdowns.start(client.clone(), &device, Duration::from_secs(600));
+        clock.wait_for_sleeps(1).await;
+        clock.advance(Duration::from_secs(240));
+        assert_eq!(countdowns.status(&device).unwrap().remaining_secs, 360);
+
+        // Restarting replaces the running countdown
+        countdowns.start(client.clone(), &device, Duration::from_secs(60));
+        clock.wait_for_sleeps(2).await;
+        clock.advance(Duration::from_secs(60));
+        for _ in 0..100 {
+            if !client.commands_for(&device).is_empty() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(client.commands_for(&device), vec![EXPIRE_COMMAND]);
+        assert!(countdowns.status(&device).is_none());
+
+        countdowns.start(client.clone(), &device, Duration::from_secs(60));
+        clock.wait_for_sleeps(3).await;
+        assert!(countdowns.cancel(&device));
+        assert!(!countdowns.cancel(&device));
+        clock.advance(Duration::from_secs(120));
+        tokio::task::yield_now().await;
+        assert_eq!(client.commands_for(&device).len(), 1);
+    }
+}
@@ -0,0 +1,443 @@
+//! Device usage statistics report
+//!
+//! Aggregates switch counts and on-hours per switched device from the state
+//! history into `loxone://reports/device-usage`. Two kinds of wear are
+//! flagged:
+//!
+//! - lamps whose accumulated on-hours approach their rated lifetime
+//!   ([`LAMP_LIFETIME_HOURS`])
+//! - relays that cycle far more often than the other relays, which usually
+//!   points to a flapping input or a misconfigured automation
+//!
+//! The history only retains a bounded number of entries per device, so the
+//! [`DeviceUsageStore`] folds new entries into running totals every hour.
+//! Totals start with the oldest retained history entry of a device.
+
+use crate::client::{ClientContext, LoxoneDevice};
+use crate::services::sensor_logger::SensorStateEntry;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+/// Interval between updates of the running totals
+pub const UPDATE_INTERVAL_SECS: u64 = 3600;
+
+/// URI of the device usage resource
+pub const DEVICE_USAGE_URI: &str = "loxone://reports/device-usage";
+
+/// Rated lifetime of a lamp (typical LED) in hours
+pub const LAMP_LIFETIME_HOURS: f64 = 25_000.0;
+
+/// Share of the lifetime from which a lamp is flagged
+const LIFETIME_WARNING_RATIO: f64 = 0.8;
+
+/// Relays switching more often than this per day are always flagged
+pub const HIGH_CYCLING_PER_DAY: f64 = 50.0;
+
+/// Factor over the median relay cycling from which a relay is flagged
+const CYCLING_MEDIAN_FACTOR: f64 = 3.0;
+
+/// Minimum tracked time before per-day rates are meaningful
+const MIN_WINDOW_HOURS: f64 = 24.0;
+
+/// Kind of switched device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageCategory {
+    Lamp,
+    Relay,
+}
+
+impl UsageCategory {
+    /// Category of a device, if it is a switched load
+    pub fn of(device: &LoxoneDevice) -> Option<Self> {
+        let name = device.name.to_lowercase();
+        match device.device_type.as_str() {
+            t if t.starts_with("LightController") || matches!(t, "Dimmer" | "EIBDimmer") => {
+                Some(Self::Lamp)
+            }
+            "Switch" | "TimedSwitch" | "Pushbutton" => {
+                if ["light", "lamp", "licht", "leuchte", "spot"]
+                    .iter()
+                    .any(|p| name.contains(p))
+                {
+                    Some(Self::Lamp)
+                } else {
+                    Some(Self::Relay)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why a device was flagged
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "flag", rename_all = "snake_case")]
+pub enum UsageFlag {
+    /// Accumulated on-hours reach the given share of the lifetime
+    NearingLifetime { lifetime_used_ratio: f64 },
+    /// Switching far more often than other relays
+    HighCycling { median_per_day: f64 },
+}
+
+/// Usage statistics of one device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceUsage {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    pub device_type: String,
+    pub category: UsageCategory,
+    /// Switch-on events since tracking started
+    pub switch_count: u64,
+    /// Hours switched on since tracking started
+    pub on_hours: f64,
+    pub tracked_since: DateTime<Utc>,
+    pub switches_per_day: Option<f64>,
+    pub flags: Vec<UsageFlag>,
+}
+
+/// Running totals of one device
+#[derive(Debug, Clone, PartialEq)]
+struct UsageTotals {
+    switch_count: u64,
+    on_time: Duration,
+    since: DateTime<Utc>,
+    /// Newest state change folded into the totals
+    last_change: DateTime<Utc>,
+    /// Start of the current on period
+    on_since: Option<DateTime<Utc>>,
+}
+
+impl UsageTotals {
+    /// Fold state changes newer than the last one seen into the totals
+    fn update(totals: Option<Self>, entries: &[SensorStateEntry]) -> Option<Self> {
+        let mut changes: Vec<(DateTime<Utc>, bool)> = entries
+            .iter()
+            .filter(|entry| {
+                totals
+                    .as_ref()
+                    .is_none_or(|t| entry.timestamp > t.last_change)
+            })
+            .filter_map(|entry| is_on(&entry.new_value).map(|on| (entry.timestamp, on)))
+            .collect();
+        changes.sort_by_key(|(timestamp, _)| *timestamp);
+
+        let mut totals = totals.or_else(|| {
+            changes.first().map(|(first, _)| Self {
+                switch_count: 0,
+                on_time: Duration::zero(),
+                since: *first,
+                last_change: *first,
+                on_since: None,
+            })
+        })?;
+        for (timestamp, on) in changes {
+            match (on, totals.on_since) {
+                (true, None) => {
+                    totals.switch_count += 1;
+                    totals.on_since = Some(timestamp);
+                }
+                (false, Some(since)) => {
+                    totals.on_time += timestamp - since;
+                    totals.on_since = None;
+                }
+                _ => {}
+            }
+            totals.last_change = timestamp;
+        }
+        Some(totals)
+    }
+
+    fn usage(
+        &self,
+        device: &LoxoneDevice,
+        category: UsageCategory,
+        now: DateTime<Utc>,
+    ) -> DeviceUsage {
+        let on_time = self.on_time + self.on_since.map_or(Duration::zero(), |since| now - since);
+        let tracked_hours = hours(now - self.since);
+        DeviceUsage {
+            uuid: device.uuid.clone(),
+            name: device.name.clone(),
+            room: device.room.clone(),
+            device_type: device.device_type.clone(),
+            category,
+            switch_count: self.switch_count,
+            on_hours: (hours(on_time) * 10.0).round() / 10.0,
+            tracked_since: self.since,
+            switches_per_day: (tracked_hours >= MIN_WINDOW_HOURS)
+                .then(|| (self.switch_count as f64 / tracked_hours * 24.0 * 10.0).round() / 10.0),
+            flags: Vec::new(),
+        }
+    }
+}
+
+/// Usage statistics across all switched devices
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceUsageReport {
+    pub generated_at: DateTime<Utc>,
+    pub devices: Vec<DeviceUsage>,
+}
+
+impl DeviceUsageReport {
+    fn new(mut devices: Vec<DeviceUsage>, now: DateTime<Utc>) -> Self {
+        flag_high_cycling(&mut devices);
+        for device in devices
+            .iter_mut()
+            .filter(|d| d.category == UsageCategory::Lamp)
+        {
+            let ratio = device.on_hours / LAMP_LIFETIME_HOURS;
+            if ratio >= LIFETIME_WARNING_RATIO {
+                device.flags.push(UsageFlag::NearingLifetime {
+                    lifetime_used_ratio: (ratio * 100.0).round() / 100.0,
+                });
+            }
+        }
+
+        devices.sort_by(|a, b| {
+            b.flags
+                .len()
+                .cmp(&a.flags.len())
+                .then(b.switch_count.cmp(&a.switch_count))
+                .then(a.name.cmp(&b.name))
+        });
+        Self {
+            generated_at: now,
+            devices,
+        }
+    }
+
+    /// Render the resource body
+    pub fn to_resource_json(&self) -> Value {
+        let flagged = self.devices.iter().filter(|d| !d.flags.is_empty()).count();
+        serde_json::json!({
+            "uri": DEVICE_USAGE_URI,
+            "generated_at": self.generated_at.to_rfc3339(),
+            "device_count": self.devices.len(),
+            "flagged_count": flagged,
+            "lamp_lifetime_hours": LAMP_LIFETIME_HOURS,
+            "devices": self.devices,
+        })
+    }
+}
+
+/// Running usage totals per device
+#[derive(Default)]
+pub struct DeviceUsageStore {
+    totals: RwLock<HashMap<String, UsageTotals>>,
+}
+
+impl DeviceUsageStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold the history into the totals and build the report
+    fn update<'a>(
+        totals: &mut HashMap<String, UsageTotals>,
+        history: &HashMap<String, Vec<SensorStateEntry>>,
+        devices: impl Iterator<Item = &'a LoxoneDevice>,
+        now: DateTime<Utc>,
+    ) -> DeviceUsageReport {
+        let usage = devices
+            .filter_map(|device| {
+                let category = UsageCategory::of(device)?;
+                let entries = history.get(&device.uuid).map(Vec::as_slice).unwrap_or(&[]);
+                let updated = UsageTotals::update(totals.remove(&device.uuid), entries)?;
+                let usage = updated.usage(device, category, now);
+                totals.insert(device.uuid.clone(), updated);
+                Some(usage)
+            })
+            .collect();
+        DeviceUsageReport::new(usage, now)
+    }
+
+    /// Update the totals from the context's history and build the report
+    pub async fn report(&self, context: &ClientContext) -> DeviceUsageReport {
+        let history = match context.get_sensor_logger().await {
+            Some(logger) => logger.get_all_history().await,
+            None => HashMap::new(),
+        };
+        let devices = context.devices.read().await;
+        let mut totals = self.totals.write().await;
+        Self::update(&mut totals, &history, devices.values(), Utc::now())
+    }
+
+    /// Fold new history into the totals every [`UPDATE_INTERVAL_SECS`]
+    pub fn start_updates(
+        self: &Arc<Self>,
+        context: Arc<ClientContext>,
+    ) -> tokio::task::JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(UPDATE_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                let report = store.report(&context).await;
+                debug!("Updated usage totals for {} devices", report.devices.len());
+            }
+        })
+    }
+}
+
+/// Flag relays cycling far above the median relay
+fn flag_high_cycling(usage: &mut [DeviceUsage]) {
+    let mut rates: Vec<f64> = usage
+        .iter()
+        .filter(|d| d.category == UsageCategory::Relay)
+        .filter_map(|d| d.switches_per_day)
+        .collect();
+    if rates.is_empty() {
+        return;
+    }
+    rates.sort_by(f64::total_cmp);
+    let median = rates[rates.len() / 2];
+    let threshold = HIGH_CYCLING_PER_DAY
+        .min(median * CYCLING_MEDIAN_FACTOR)
+        .max(1.0);
+
+    for device in usage
+        .iter_mut()
+        .filter(|d| d.category == UsageCategory::Relay)
+    {
+        if device
+            .switches_per_day
+            .is_some_and(|rate| rate > threshold && rate > median)
+        {
+            device.flags.push(UsageFlag::HighCycling {
+                median_per_day: median,
+            });
+        }
+    }
+}
+
+fn hours(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+/// Whether a state value means "on"; `None` for values that are not a switch state
+fn is_on(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::Number(n) => n.as_f64().map(|v| v > 0.0),
+        Value::String(s) => match s.to_lowercase().as_str() {
+            "1" | "on" | "true" => Some(true),
+            "0" | "off" | "false" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn device(uuid: &str, name: &str, device_type: &str) -> LoxoneDevice {
+        LoxoneDevice {
+            uuid: uuid.to_string(),
+            name: name.to_string(),
+            device_type: device_type.to_string(),
+            room: Some("Hall".to_string()),
+            states: HashMap::new(),
+            category: String::new(),
+            loxone_category: None,
+            sub_controls: HashMap::new(),
+        }
+    }
+
+    fn toggles(
+        now: DateTime<Utc>,
+        days: i64,
+        per_day: i64,
+        on_minutes: i64,
+    ) -> Vec<SensorStateEntry> {
+        let start = now - Duration::days(days);
+        let step = Duration::minutes(24 * 60 / per_day);
+        (0..days * per_day)
+            .flat_map(|i| {
+                let on = start + step * i as i32;
+                [
+                    (on, json!(1)),
+                    (on + Duration::minutes(on_minutes), json!(0)),
+                ]
+            })
+            .map(|(timestamp, value)| SensorStateEntry {
+                timestamp,
+                old_value: json!(null),
+                new_value: value,
+                sensor_name: None,
+                sensor_type: None,
+                room: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_usage_counts_and_flags() {
+        let now = Utc::now();
+        let devices = [
+            device("lamp", "Hall Light", "Switch"),
+            device("pump", "Pump", "Switch"),
+            device("fan", "Fan", "Switch"),
+            device("valve", "Valve", "Switch"),
+            device("sensor", "Door", "InfoOnlyDigital"),
+        ];
+        let history = HashMap::from([
+            ("lamp".to_string(), toggles(now, 10, 1, 23 * 60)),
+            ("pump".to_string(), toggles(now, 10, 4, 30)),
+            ("fan".to_string(), toggles(now, 10, 6, 10)),
+            ("valve".to_string(), toggles(now, 10, 96, 5)),
+            ("sensor".to_string(), toggles(now, 10, 96, 1)),
+        ]);
+
+        let mut totals = HashMap::new();
+        let report = DeviceUsageStore::update(&mut totals, &history, devices.iter(), now);
+        assert_eq!(report.devices.len(), 4);
+        let get = |uuid: &str| {
+            report
+                .devices
+                .iter()
+                .find(|d| d.uuid == uuid)
+                .unwrap()
+                .clone()
+        };
+
+        let pump = get("pump");
+        assert_eq!(pump.category, UsageCategory::Relay);
+        assert_eq!(pump.switch_count, 40);
+        assert_eq!(pump.on_hours, 20.0);
+        assert_eq!(pump.switches_per_day, Some(4.0));
+        assert!(pump.flags.is_empty());
+        assert!(matches!(
+            get("valve").flags.as_slice(),
+            [UsageFlag::HighCycling { .. }]
+        ));
+        assert_eq!(report.devices[0].uuid, "valve");
+
+        // A later update only folds in new changes
+        let later = now + Duration::days(1);
+        let report = DeviceUsageStore::update(&mut totals, &history, devices.iter(), later);
+        let lamp = report.devices.iter().find(|d| d.uuid == "lamp").unwrap();
+        assert_eq!(lamp.category, UsageCategory::Lamp);
+        assert_eq!(lamp.switch_count, 10);
+        assert!(lamp.flags.is_empty());
+
+        // Lamps approaching their rated lifetime are flagged
+        totals.get_mut("lamp").unwrap().on_time = Duration::hours(21_000);
+        let report = DeviceUsageStore::update(&mut totals, &history, devices.iter(), later);
+        let lamp = report.devices.iter().find(|d| d.uuid == "lamp").unwrap();
+        assert!(matches!(
+            lamp.flags.as_slice(),
+            [UsageFlag::NearingLifetime { .. }]
+        ));
+    }
+}
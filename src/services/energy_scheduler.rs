@@ -0,0 +1,619 @@
+//! Energy-aware scheduling of workflows
+//!
+//! A scheduled workflow is a list of device commands. It may declare an
+//! energy preference such as "run when the PV surplus exceeds 2000 W"; the
+//! scheduler then holds the workflow back until the energy reading meets
+//! the preference, or until `max_delay` has passed, so that flexible loads
+//! (washing machine, dishwasher, hot water boost) run on solar power when
+//! possible but still run the same day.
+//!
+//! The surplus is the power exported to the grid, read from an Energy
+//! Manager's grid power (`Gpwr`, negative while exporting) or from a meter
+//! named after the grid connection. Without a reading, workflows wait for
+//! their maximum delay.
+//!
+//! Workflows may also be planned for a fixed start time (e.g. the cheapest
+//! tariff slot); they wait until then without looking at the surplus.
+
+use crate::client::{LoxoneClient, LoxoneStructure};
+use crate::error::{LoxoneError, Result};
+use crate::utils::clock::{SharedClock, system_clock};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Interval between checks of waiting workflows
+pub const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Finished workflows kept for reporting
+const MAX_FINISHED: usize = 50;
+
+/// Energy manager control types exposing grid and production power
+const ENERGY_MANAGER_TYPES: &[&str] = &["EnergyManager2", "EnergyManager", "EnergyFlowMonitor"];
+
+/// Name fragments of meters measuring the grid connection
+const GRID_METER_PATTERNS: &[&str] = &["grid", "netz", "bezug", "einspeisung"];
+
+/// Name fragments of meters measuring PV production
+const PV_METER_PATTERNS: &[&str] = &["pv", "solar", "photovoltaik"];
+
+/// When a workflow may run, in terms of available solar power
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnergyPreference {
+    /// PV surplus (W) that must be available before the workflow runs
+    pub min_surplus_w: f64,
+    /// Run anyway once the workflow has waited this long
+    #[serde(with = "humantime_serde")]
+    pub max_delay: Duration,
+}
+
+/// One device command of a workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    /// Control UUID
+    pub device: String,
+    pub command: String,
+}
+
+/// Why a workflow was started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartTrigger {
+    /// No energy preference was declared
+    Immediate,
+    /// The planned start time was reached
+    Scheduled,
+    /// The surplus reached the declared minimum
+    Surplus,
+    /// The maximum delay passed without enough surplus
+    MaxDelay,
+}
+
+/// Lifecycle of a scheduled workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WorkflowStatus {
+    Waiting,
+    Completed {
+        started_at: DateTime<Utc>,
+        trigger: StartTrigger,
+        /// Surplus at start, if it could be read
+        surplus_w: Option<f64>,
+        /// Steps that failed, with the error
+        failed_steps: Vec<String>,
+    },
+    Cancelled,
+}
+
+/// A workflow waiting for, or done with, execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledWorkflow {
+    pub id: String,
+    pub name: String,
+    pub steps: Vec<WorkflowStep>,
+    pub preference: Option<EnergyPreference>,
+    /// Planned start time; the workflow waits until then
+    #[serde(default)]
+    pub not_before: Option<DateTime<Utc>>,
+    pub submitted_at: DateTime<Utc>,
+    pub status: WorkflowStatus,
+}
+
+impl ScheduledWorkflow {
+    /// Latest time the workflow will start
+    pub fn deadline(&self) -> Option<DateTime<Utc>> {
+        let preference = self.preference.as_ref()?;
+        chrono::Duration::from_std(preference.max_delay)
+            .ok()
+            .map(|delay| self.submitted_at + delay)
+    }
+
+    /// Whether the workflow should start at `now` with the given surplus
+    pub fn start_trigger(
+        &self,
+        now: DateTime<Utc>,
+        surplus_w: Option<f64>,
+    ) -> Option<StartTrigger> {
+        if let Some(start) = self.not_before {
+            return (now >= start).then_some(StartTrigger::Scheduled);
+        }
+        let Some(preference) = &self.preference else {
+            return Some(StartTrigger::Immediate);
+        };
+        if surplus_w.is_some_and(|surplus| surplus >= preference.min_surplus_w) {
+            Some(StartTrigger::Surplus)
+        } else if self.deadline().is_none_or(|deadline| now >= deadline) {
+            Some(StartTrigger::MaxDelay)
+        } else {
+            None
+        }
+    }
+}
+
+/// Current power flows, in watts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnergySnapshot {
+    /// PV production
+    pub production_w: Option<f64>,
+    /// Grid power; negative while exporting
+    pub grid_w: Option<f64>,
+}
+
+impl EnergySnapshot {
+    /// Power available beyond the house's own consumption
+    pub fn surplus_w(&self) -> Option<f64> {
+        self.grid_w.map(|grid| (-grid).max(0.0))
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "production_w": self.production_w,
+            "grid_w": self.grid_w,
+            "surplus_w": self.surplus_w()
+        })
+    }
+
+    /// Read the current power flows from the Miniserver
+    pub async fn read(client: &dyn LoxoneClient) -> Result<Self> {
+        let structure = client.get_structure().await?;
+        let sources = EnergySources::discover(&structure);
+        if sources.grid.is_none() && sources.production.is_none() {
+            return Err(LoxoneError::not_found(
+                "No energy manager or grid meter found",
+            ));
+        }
+        let uuids: Vec<String> = sources
+            .grid
+            .iter()
+            .chain(sources.production.iter())
+            .cloned()
+            .collect();
+        let values = client.get_state_values(&uuids).await?;
+        // Loxone reports power in kW
+        let watts = |uuid: &Option<String>| {
+            uuid.as_ref()
+                .and_then(|uuid| values.get(uuid))
+                .and_then(value_as_f64)
+                .map(|kw| kw * 1000.0)
+        };
+        Ok(Self {
+            production_w: watts(&sources.production),
+            grid_w: watts(&sources.grid),
+        })
+    }
+}
+
+/// State UUIDs carrying grid and production power
+#[derive(Debug, Default, PartialEq)]
+struct EnergySources {
+    grid: Option<String>,
+    production: Option<String>,
+}
+
+impl EnergySources {
+    fn discover(structure: &LoxoneStructure) -> Self {
+        let mut sources = Self::default();
+        let state = |control: &Value, name: &str| {
+            control
+                .get("states")
+                .and_then(|states| states.get(name))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+
+        for control in structure.controls.values() {
+            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            if ENERGY_MANAGER_TYPES.contains(&control_type) {
+                if let Some(grid) = state(control, "Gpwr") {
+                    return Self {
+                        grid: Some(grid),
+                        production: state(control, "Ppwr"),
+                    };
+                }
+                continue;
+            }
+            if control_type != "Meter" {
+                continue;
+            }
+            let name = control
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_lowercase();
+            if sources.grid.is_none() && GRID_METER_PATTERNS.iter().any(|p| name.contains(p)) {
+                sources.grid = state(control, "actual");
+            } else if sources.production.is_none()
+                && PV_METER_PATTERNS.iter().any(|p| name.contains(p))
+            {
+                sources.production = state(control, "actual");
+            }
+        }
+        sources
+    }
+}
+
+/// Holds workflows back until their energy preference is met
+pub struct EnergyScheduler {
+    workflows: RwLock<Vec<ScheduledWorkflow>>,
+    last_snapshot: RwLock<Option<EnergySnapshot>>,
+    next_id: AtomicU64,
+    clock: SharedClock,
+}
+
+impl Default for EnergyScheduler {
+    fn default() -> Self {
+        Self {
+            workflows: RwLock::new(Vec::new()),
+            last_snapshot: RwLock::new(None),
+            next_id: AtomicU64::new(1),
+            clock: system_clock(),
+        }
+    }
+}
+
+impl EnergyScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time workflows and checks with `clock`; tests fast-forward it
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Queue a workflow; it runs on the next check its preference allows
+    pub async fn submit(
+        &self,
+        name: String,
+        steps: Vec<WorkflowStep>,
+        preference: Option<EnergyPreference>,
+    ) -> Result<ScheduledWorkflow> {
+        self.queue(name, steps, preference, None).await
+    }
+
+    /// Queue a workflow that starts on the first check at or after `start`
+    pub async fn submit_at(
+        &self,
+        name: String,
+        steps: Vec<WorkflowStep>,
+        start: DateTime<Utc>,
+    ) -> Result<ScheduledWorkflow> {
+        self.queue(name, steps, None, Some(start)).await
+    }
+
+    async fn queue(
+        &self,
+        name: String,
+        steps: Vec<WorkflowStep>,
+        preference: Option<EnergyPreference>,
+        not_before: Option<DateTime<Utc>>,
+    ) -> Result<ScheduledWorkflow> {
+        if steps.is_empty() {
+            return Err(LoxoneError::invalid_input(
+                "A workflow needs at least one step",
+            ));
+        }
+        if preference
+            .as_ref()
+            .is_some_and(|p| !p.min_surplus_w.is_finite() || p.min_surplus_w < 0.0)
+        {
+            return Err(LoxoneError::invalid_input(
+                "Minimum PV surplus must be a non-negative number of watts",
+            ));
+        }
+        let workflow = ScheduledWorkflow {
+            id: format!("wf-{}", self.next_id.fetch_add(1, Ordering::Relaxed)),
+            name,
+            steps,
+            preference,
+            not_before,
+            submitted_at: self.clock.utc_now(),
+            status: WorkflowStatus::Waiting,
+        };
+        self.workflows.write().await.push(workflow.clone());
+        Ok(workflow)
+    }
+
+    /// Cancel a waiting workflow; returns whether it was waiting
+    pub async fn cancel(&self, id: &str) -> bool {
+        let mut workflows = self.workflows.write().await;
+        match workflows
+            .iter_mut()
+            .find(|w| w.id == id && w.status == WorkflowStatus::Waiting)
+        {
+            Some(workflow) => {
+                workflow.status = WorkflowStatus::Cancelled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn workflows(&self) -> Vec<ScheduledWorkflow> {
+        self.workflows.read().await.clone()
+    }
+
+    /// Energy reading of the last check
+    pub async fn last_snapshot(&self) -> Option<EnergySnapshot> {
+        self.last_snapshot.read().await.clone()
+    }
+
+    /// Start every waiting workflow whose preference is met
+    ///
+    /// Returns the workflows started by this check.
+    pub async fn run_due(&self, client: &dyn LoxoneClient) -> Vec<ScheduledWorkflow> {
+        let now = self.clock.utc_now();
+        let waiting: Vec<ScheduledWorkflow> = self
+            .workflows
+            .read()
+            .await
+            .iter()
+            .filter(|w| w.status == WorkflowStatus::Waiting)
+            .cloned()
+            .collect();
+        if waiting.is_empty() {
+            return Vec::new();
+        }
+
+        // Only read the energy flows when a workflow depends on them
+        let surplus_w = if waiting.iter().any(|w| w.preference.is_some()) {
+            match EnergySnapshot::read(client).await {
+                Ok(snapshot) => {
+                    let surplus = snapshot.surplus_w();
+                    *self.last_snapshot.write().await = Some(snapshot);
+                    surplus
+                }
+                Err(e) => {
+                    warn!("Failed to read PV surplus: {e}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut started = Vec::new();
+        for workflow in waiting {
+            let Some(trigger) = workflow.start_trigger(now, surplus_w) else {
+                continue;
+            };
+            let mut failed_steps = Vec::new();
+            for step in &workflow.steps {
+                if let Err(e) = client.send_command(&step.device, &step.command).await {
+                    failed_steps.push(format!("{} {}: {e}", step.device, step.command));
+                }
+            }
+            info!(
+                "Started workflow '{}' ({:?}, surplus {:?} W)",
+                workflow.name, trigger, surplus_w
+            );
+            let status = WorkflowStatus::Completed {
+                started_at: self.clock.utc_now(),
+                trigger,
+                surplus_w,
+                failed_steps,
+            };
+            started.push(self.set_status(&workflow.id, status).await);
+        }
+        self.prune().await;
+        started.into_iter().flatten().collect()
+    }
+
+    /// Check waiting workflows every [`CHECK_INTERVAL_SECS`]
+    pub fn start(self: &Arc<Self>, client: Arc<dyn LoxoneClient>) -> tokio::task::JoinHandle<()> {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let started = scheduler.run_due(client.as_ref()).await;
+                if !started.is_empty() {
+                    debug!("Started {} scheduled workflows", started.len());
+                }
+                scheduler
+                    .clock
+                    .sleep(Duration::from_secs(CHECK_INTERVAL_SECS))
+                    .await;
+            }
+        })
+    }
+
+    async fn set_status(&self, id: &str, status: WorkflowStatus) -> Option<ScheduledWorkflow> {
+        let mut workflows = self.workflows.write().await;
+        let workflow = workflows.iter_mut().find(|w| w.id == id)?;
+        workflow.status = status;
+        Some(workflow.clone())
+    }
+
+    /// Drop the oldest finished workflows beyond [`MAX_FINISHED`]
+    async fn prune(&self) {
+        let mut workflows = self.workflows.write().await;
+        let finished = workflows
+            .iter()
+            .filter(|w| w.status != WorkflowStatus::Waiting)
+            .count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED);
+        workflows.retain(|w| {
+            if excess > 0 && w.status != WorkflowStatus::Waiting {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// Parse a workflow step written as `device:command`
+pub fn parse_step(step: &str) -> Result<(String, String)> {
+    step.split_once(':')
+        .map(|(device, command)| (device.trim().to_string(), command.trim().to_string()))
+        .filter(|(device, command)| !device.is_empty() && !command.is_empty())
+        .ok_or_else(|| {
+            LoxoneError::invalid_input(format!(
+                "Invalid workflow step '{step}', expected 'device:command'"
+            ))
+        })
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_start_trigger_respects_surplus_and_max_delay() {
+        let submitted_at = Utc::now();
+        let workflow = ScheduledWorkflow {
+            id: "wf-1".to_string(),
+            name: "Dishwasher".to_string(),
+            steps: vec![WorkflowStep {
+                device: "dishwasher".to_string(),
+                command: "on".to_string(),
+            }],
+            preference: Some(EnergyPreference {
+                min_surplus_w: 1500.0,
+                max_delay: Duration::from_secs(4 * 3600),
+            }),
+            not_before: None,
+            submitted_at,
+            status: WorkflowStatus::Waiting,
+        };
+
+        let in_an_hour = submitted_at + chrono::Duration::hours(1);
+        assert_eq!(workflow.start_trigger(in_an_hour, Some(800.0)), None);
+        assert_eq!(workflow.start_trigger(in_an_hour, None), None);
+        assert_eq!(
+            workflow.start_trigger(in_an_hour, Some(1500.0)),
+            Some(StartTrigger::Surplus)
+        );
+        assert_eq!(
+            workflow.start_trigger(submitted_at + chrono::Duration::hours(4), Some(0.0)),
+            Some(StartTrigger::MaxDelay)
+        );
+
+        let planned = ScheduledWorkflow {
+            not_before: Some(in_an_hour),
+            ..workflow.clone()
+        };
+        assert_eq!(planned.start_trigger(submitted_at, Some(5000.0)), None);
+        assert_eq!(
+            planned.start_trigger(in_an_hour, None),
+            Some(StartTrigger::Scheduled)
+        );
+
+        let immediate = ScheduledWorkflow {
+            preference: None,
+            ..workflow
+        };
+        assert_eq!(
+            immediate.start_trigger(submitted_at, None),
+            Some(StartTrigger::Immediate)
+        );
+    }
+
+    #[test]
+    fn test_energy_sources_and_surplus() {
+        let mut structure = LoxoneStructure {
+            last_modified: String::new(),
+            controls: HashMap::new(),
+            rooms: HashMap::new(),
+            cats: HashMap::new(),
+            global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
+        };
+        structure.controls.insert(
+            "meter-grid".to_string(),
+            json!({"name": "Netzbezug", "type": "Meter", "states": {"actual": "grid-actual"}}),
+        );
+        structure.controls.insert(
+            "meter-pv".to_string(),
+            json!({"name": "PV Anlage", "type": "Meter", "states": {"actual": "pv-actual"}}),
+        );
+        assert_eq!(
+            EnergySources::discover(&structure),
+            EnergySources {
+                grid: Some("grid-actual".to_string()),
+                production: Some("pv-actual".to_string()),
+            }
+        );
+
+        // An energy manager takes precedence over individual meters
+        structure.controls.insert(
+            "em".to_string(),
+            json!({"name": "Energy", "type": "EnergyManager2", "states": {"Gpwr": "em-grid", "Ppwr": "em-pv"}}),
+        );
+        assert_eq!(
+            EnergySources::discover(&structure).grid.as_deref(),
+            Some("em-grid")
+        );
+
+        let exporting = EnergySnapshot {
+            production_w: Some(4200.0),
+            grid_w: Some(-2500.0),
+        };
+        assert_eq!(exporting.surplus_w(), Some(2500.0));
+        let importing = EnergySnapshot {
+            grid_w: Some(300.0),
+            ..exporting
+        };
+        assert_eq!(importing.surplus_w(), Some(0.0));
+        assert!(parse_step("Dishwasher: on").is_ok());
+        assert!(parse_step("Dishwasher").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checks_fast_forward_to_max_delay() {
+        use crate::mock::{ScriptedClient, device_uuid, sample_house};
+        use crate::utils::clock::ManualClock;
+
+        let clock = Arc::new(ManualClock::new());
+        let client = Arc::new(ScriptedClient::new(sample_house()));
+        let scheduler = Arc::new(EnergyScheduler::new().with_clock(clock.clone()));
+        let device = device_uuid("Kitchen", "Ceiling");
+        scheduler
+            .submit(
+                "Dishwasher".to_string(),
+                vec![WorkflowStep {
+                    device: device.clone(),
+                    command: "on".to_string(),
+                }],
+                Some(EnergyPreference {
+                    min_surplus_w: 1500.0,
+                    max_delay: Duration::from_secs(2 * 3600),
+                }),
+            )
+            .await
+            .unwrap();
+        let task = scheduler.start(client.clone());
+
+        // Without a surplus reading the workflow waits through the checks of
+        // the first two hours and starts on the check at the deadline
+        let minute = Duration::from_secs(CHECK_INTERVAL_SECS);
+        clock.wait_for_sleeps(1).await;
+        for check in 2..=120 {
+            clock.advance(minute);
+            clock.wait_for_sleeps(check).await;
+        }
+        assert!(client.commands_for(&device).is_empty());
+
+        clock.advance(minute);
+        clock.wait_for_sleeps(121).await;
+        assert_eq!(client.commands_for(&device), vec!["on"]);
+        task.abort();
+    }
+}
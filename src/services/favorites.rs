@@ -0,0 +1,140 @@
+//! Device favorites and ratings
+//!
+//! The structure file marks controls the way the Loxone app shows them:
+//!
+//! - `isFavorite`: listed under favorites in the app
+//! - `defaultRating`: sort weight within favorites, higher first (0 when
+//!   not rated)
+//!
+//! Both are set in Loxone Config; the Miniserver offers no command to change
+//! them. Ratings set through the server are kept as overrides that take
+//! precedence over the structure until cleared. Overrides live in memory and
+//! are lost on restart.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Highest rating accepted, as offered by the Loxone app
+pub const MAX_RATING: u32 = 10;
+
+/// Favorite flag and rating of a control
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Rating {
+    pub favorite: bool,
+    pub rating: u32,
+}
+
+impl Rating {
+    /// Rating from the structure file
+    pub fn of(control: &Value) -> Self {
+        Self {
+            favorite: control
+                .get("isFavorite")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            rating: control
+                .get("defaultRating")
+                .and_then(Value::as_u64)
+                .map(|rating| rating.min(MAX_RATING as u64) as u32)
+                .unwrap_or(0),
+        }
+    }
+
+    /// Sort key listing favorites first, then by descending rating
+    pub fn sort_key(self) -> (Reverse<bool>, Reverse<u32>) {
+        (Reverse(self.favorite), Reverse(self.rating))
+    }
+}
+
+/// Server-side rating overrides by control UUID
+#[derive(Default)]
+pub struct Favorites {
+    overrides: Mutex<HashMap<String, Rating>>,
+}
+
+impl Favorites {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Effective rating of a control: the override, else the structure's
+    pub fn rating(&self, uuid: &str, control: &Value) -> Rating {
+        self.overrides
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(uuid)
+            .copied()
+            .unwrap_or_else(|| Rating::of(control))
+    }
+
+    /// Whether the rating of `uuid` is overridden
+    pub fn is_overridden(&self, uuid: &str) -> bool {
+        self.overrides
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(uuid)
+    }
+
+    /// Override the rating of `uuid`
+    pub fn set(&self, uuid: &str, rating: Rating) {
+        self.overrides
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(uuid.to_string(), rating);
+    }
+
+    /// Drop the override of `uuid`; returns whether there was one
+    pub fn clear(&self, uuid: &str) -> bool {
+        self.overrides
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(uuid)
+            .is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_overrides_and_ordering() {
+        let control = json!({"isFavorite": true, "defaultRating": 3});
+        let favorites = Favorites::new();
+        assert_eq!(
+            favorites.rating("c1", &control),
+            Rating {
+                favorite: true,
+                rating: 3
+            }
+        );
+
+        favorites.set(
+            "c1",
+            Rating {
+                favorite: false,
+                rating: 0,
+            },
+        );
+        assert!(!favorites.rating("c1", &control).favorite);
+        assert!(favorites.clear("c1"));
+        assert!(!favorites.clear("c1"));
+        assert_eq!(favorites.rating("c1", &control).rating, 3);
+
+        let mut ratings = [
+            Rating::of(&json!({})),
+            Rating::of(&json!({"isFavorite": true, "defaultRating": 1})),
+            Rating::of(&json!({"defaultRating": 99})),
+            Rating::of(&json!({"isFavorite": true, "defaultRating": 5})),
+        ];
+        ratings.sort_by_key(|rating| rating.sort_key());
+        assert_eq!(
+            ratings.map(|r| (r.favorite, r.rating)),
+            [(true, 5), (true, 1), (false, 10), (false, 0)]
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! Valve and underfloor heating actuator diagnostics
+//!
+//! Samples valve positions together with the temperature of the room they
+//! heat into a short rolling window. From that window we derive each
+//! actuator's duty cycle and flag valves that look stuck: fully open for the
+//! whole detection window while the room temperature does not move, which
+//! usually means a seized valve pin, an air lock or a dead thermal actuator.
+//!
+//! The sensor history cannot be used for this because it does not record
+//! which state of a control changed.
+
+use crate::client::{LoxoneClient, LoxoneStructure};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Interval between samples taken by the background job
+pub const SAMPLE_INTERVAL_SECS: u64 = 300;
+
+/// Samples older than this are dropped
+pub const HISTORY_WINDOW_MINUTES: i64 = 120;
+
+/// How long a valve must be fully open without effect to count as stuck
+pub const STUCK_WINDOW_MINUTES: i64 = 45;
+
+/// Position (percent) from which a valve counts as fully open
+pub const FULL_DEMAND_PERCENT: f64 = 99.0;
+
+/// Minimum temperature rise (°C) expected over the stuck window
+pub const MIN_TEMPERATURE_RISE: f64 = 0.2;
+
+/// Control types of valve actuators
+const VALVE_CONTROL_TYPES: &[&str] = &["Valve", "ValveActuator", "HeatingValve"];
+
+/// Name fragments identifying valve outputs placed in the visualization
+const VALVE_NAME_PATTERNS: &[&str] = &["valve", "ventil", "stellantrieb", "actuator"];
+
+/// States that may carry a valve position, in order of preference
+const VALVE_POSITION_STATES: &[&str] = &["valvePosition", "position", "value"];
+
+/// Room controller types providing the room temperature
+const ROOM_CONTROLLER_TYPES: &[&str] = &[
+    "IRoomControllerV2",
+    "IRoomController",
+    "Intelligent Room Controller",
+];
+
+/// A valve actuator and the state UUIDs needed to sample it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValveSource {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    pub position_state: String,
+    /// Whether the position is reported as 0.0-1.0 instead of percent
+    pub fractional: bool,
+    pub temperature_state: Option<String>,
+    pub target_state: Option<String>,
+}
+
+impl ValveSource {
+    /// Find valve actuators and their room controllers in the structure
+    pub fn discover(structure: &LoxoneStructure) -> Vec<Self> {
+        let state = |control: &Value, name: &str| {
+            control
+                .get("states")
+                .and_then(|s| s.get(name))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let text = |control: &Value, key: &str| {
+            control
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string()
+        };
+
+        // Room UUID -> (tempActual, tempTarget) of its room controller
+        let room_temperatures: HashMap<String, (Option<String>, Option<String>)> = structure
+            .controls
+            .values()
+            .filter(|c| ROOM_CONTROLLER_TYPES.contains(&text(c, "type").as_str()))
+            .filter_map(|c| {
+                let room = c.get("room").and_then(Value::as_str)?;
+                Some((
+                    room.to_string(),
+                    (state(c, "tempActual"), state(c, "tempTarget")),
+                ))
+            })
+            .collect();
+
+        let mut valves: Vec<Self> = structure
+            .controls
+            .iter()
+            .filter_map(|(uuid, control)| {
+                let control_type = text(control, "type");
+                let name = text(control, "name");
+                let lower = name.to_lowercase();
+                if !VALVE_CONTROL_TYPES.contains(&control_type.as_str())
+                    && !VALVE_NAME_PATTERNS.iter().any(|p| lower.contains(p))
+                {
+                    return None;
+                }
+                let position_state = VALVE_POSITION_STATES
+                    .iter()
+                    .find_map(|s| state(control, s))?;
+                let room_uuid = control.get("room").and_then(Value::as_str);
+                let (temperature_state, target_state) = room_uuid
+                    .and_then(|r| room_temperatures.get(r).cloned())
+                    .unwrap_or_default();
+                let fractional = control
+                    .get("details")
+                    .and_then(|d| d.get("max"))
+                    .and_then(Value::as_f64)
+                    .is_some_and(|max| max <= 1.0);
+
+                Some(Self {
+                    uuid: uuid.clone(),
+                    name,
+                    room: room_uuid
+                        .and_then(|r| structure.rooms.get(r))
+                        .and_then(|r| r.get("name"))
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    position_state,
+                    fractional,
+                    temperature_state,
+                    target_state,
+                })
+            })
+            .collect();
+        valves.sort_by(|a, b| (&a.room, &a.name).cmp(&(&b.room, &b.name)));
+        valves
+    }
+
+    /// State UUIDs to read for one sample
+    fn state_uuids(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.position_state)
+            .chain(self.temperature_state.iter())
+            .chain(self.target_state.iter())
+    }
+}
+
+/// One reading of a valve and its room
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ValveSample {
+    pub timestamp: DateTime<Utc>,
+    /// Valve opening in percent
+    pub position: f64,
+    pub room_temperature: Option<f64>,
+    pub target_temperature: Option<f64>,
+}
+
+/// Diagnosis of one valve over the sampled window
+#[derive(Debug, Clone, Serialize)]
+pub struct ValveDiagnosis {
+    pub uuid: String,
+    pub name: String,
+    pub room: Option<String>,
+    /// Latest valve opening in percent
+    pub position: Option<f64>,
+    /// Average opening over the window, i.e. the actuator duty cycle
+    pub duty_cycle_percent: Option<f64>,
+    pub room_temperature: Option<f64>,
+    pub target_temperature: Option<f64>,
+    /// Room temperature change over the stuck window
+    pub temperature_change: Option<f64>,
+    pub samples: usize,
+    pub stuck: bool,
+}
+
+impl ValveDiagnosis {
+    /// Evaluate the samples of one valve
+    pub fn evaluate(
+        source: &ValveSource,
+        samples: &VecDeque<ValveSample>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let latest = samples.back();
+        let duty_cycle = (!samples.is_empty()).then(|| {
+            let average = samples.iter().map(|s| s.position).sum::<f64>() / samples.len() as f64;
+            (average * 10.0).round() / 10.0
+        });
+
+        let window_start = now - Duration::minutes(STUCK_WINDOW_MINUTES);
+        let window: Vec<&ValveSample> = samples
+            .iter()
+            .filter(|s| s.timestamp >= window_start)
+            .collect();
+        let temperatures: Vec<f64> = window.iter().filter_map(|s| s.room_temperature).collect();
+        let temperature_change = match (temperatures.first(), temperatures.last()) {
+            (Some(first), Some(last)) if temperatures.len() > 1 => {
+                Some(((last - first) * 100.0).round() / 100.0)
+            }
+            _ => None,
+        };
+
+        // The window must be covered by samples, or a freshly opened valve
+        // would be flagged before the room had time to react
+        let covers_window = window.first().is_some_and(|s| {
+            s.timestamp - window_start <= Duration::seconds(SAMPLE_INTERVAL_SECS as i64)
+        });
+        let below_target =
+            latest.is_some_and(|s| match (s.room_temperature, s.target_temperature) {
+                (Some(actual), Some(target)) => actual < target,
+                _ => true,
+            });
+        let stuck = covers_window
+            && window.iter().all(|s| s.position >= FULL_DEMAND_PERCENT)
+            && temperature_change.is_some_and(|change| change < MIN_TEMPERATURE_RISE)
+            && below_target;
+
+        Self {
+            uuid: source.uuid.clone(),
+            name: source.name.clone(),
+            room: source.room.clone(),
+            position: latest.map(|s| s.position),
+            duty_cycle_percent: duty_cycle,
+            room_temperature: latest.and_then(|s| s.room_temperature),
+            target_temperature: latest.and_then(|s| s.target_temperature),
+            temperature_change,
+            samples: samples.len(),
+            stuck,
+        }
+    }
+}
+
+/// Rolling valve sample window
+#[derive(Default)]
+pub struct HeatingDiagnostics {
+    sources: RwLock<Vec<ValveSource>>,
+    samples: RwLock<HashMap<String, VecDeque<ValveSample>>>,
+}
+
+impl HeatingDiagnostics {
+    /// Create an empty sample window
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store one set of state values read at `now`
+    pub async fn record(
+        &self,
+        sources: Vec<ValveSource>,
+        values: &HashMap<String, Value>,
+        now: DateTime<Utc>,
+    ) {
+        let number = |uuid: &String| values.get(uuid).and_then(value_as_f64);
+        let cutoff = now - Duration::minutes(HISTORY_WINDOW_MINUTES);
+
+        let mut samples = self.samples.write().await;
+        samples.retain(|uuid, _| sources.iter().any(|s| &s.uuid == uuid));
+        for source in &sources {
+            let Some(raw) = number(&source.position_state) else {
+                continue;
+            };
+            let position = if source.fractional { raw * 100.0 } else { raw };
+            let track = samples.entry(source.uuid.clone()).or_default();
+            track.push_back(ValveSample {
+                timestamp: now,
+                position: position.clamp(0.0, 100.0),
+                room_temperature: source.temperature_state.as_ref().and_then(number),
+                target_temperature: source.target_state.as_ref().and_then(number),
+            });
+            while track.front().is_some_and(|s| s.timestamp < cutoff) {
+                track.pop_front();
+            }
+        }
+        *self.sources.write().await = sources;
+    }
+
+    /// Read the current valve states from the Miniserver and record them
+    pub async fn sample(&self, client: &dyn LoxoneClient) -> crate::error::Result<usize> {
+        let structure = client.get_structure().await?;
+        let sources = ValveSource::discover(&structure);
+        if sources.is_empty() {
+            return Ok(0);
+        }
+        let uuids: Vec<String> = sources
+            .iter()
+            .flat_map(ValveSource::state_uuids)
+            .cloned()
+            .collect();
+        let values = client.get_state_values(&uuids).await?;
+        let count = sources.len();
+        self.record(sources, &values, Utc::now()).await;
+        Ok(count)
+    }
+
+    /// Diagnose all known valves
+    pub async fn diagnose(&self, now: DateTime<Utc>) -> Vec<ValveDiagnosis> {
+        let sources = self.sources.read().await;
+        let samples = self.samples.read().await;
+        let empty = VecDeque::new();
+        sources
+            .iter()
+            .map(|source| {
+                let track = samples.get(&source.uuid).unwrap_or(&empty);
+                ValveDiagnosis::evaluate(source, track, now)
+            })
+            .collect()
+    }
+
+    /// Whether any sample has been recorded yet
+    pub async fn has_samples(&self) -> bool {
+        !self.samples.read().await.is_empty()
+    }
+
+    /// Sample valves every [`SAMPLE_INTERVAL_SECS`]
+    pub fn start_sampling(
+        self: &Arc<Self>,
+        client: Arc<dyn LoxoneClient>,
+    ) -> tokio::task::JoinHandle<()> {
+        let diagnostics = self.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(SAMPLE_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                match diagnostics.sample(client.as_ref()).await {
+                    Ok(count) => debug!("Sampled {count} heating valves"),
+                    Err(e) => warn!("Failed to sample heating valves: {e}"),
+                }
+            }
+        })
+    }
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn structure() -> LoxoneStructure {
+        LoxoneStructure {
+            last_modified: String::new(),
+            controls: HashMap::from([
+                (
+                    "valve-1".to_string(),
+                    json!({
+                        "name": "Bath Floor Valve",
+                        "type": "Slider",
+                        "room": "room-b",
+                        "details": {"min": 0.0, "max": 1.0},
+                        "states": {"value": "valve-1-pos"}
+                    }),
+                ),
+                (
+                    "irc-1".to_string(),
+                    json!({
+                        "name": "Bath",
+                        "type": "IRoomControllerV2",
+                        "room": "room-b",
+                        "states": {"tempActual": "irc-1-act", "tempTarget": "irc-1-tgt"}
+                    }),
+                ),
+                (
+                    "light-1".to_string(),
+                    json!({"name": "Bath Light", "type": "Switch", "room": "room-b"}),
+                ),
+            ]),
+            rooms: HashMap::from([("room-b".to_string(), json!({"name": "Bath"}))]),
+            cats: HashMap::new(),
+            global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
+        }
+    }
+
+    fn values(position: f64, temperature: f64) -> HashMap<String, Value> {
+        HashMap::from([
+            ("valve-1-pos".to_string(), json!(position)),
+            ("irc-1-act".to_string(), json!(temperature)),
+            ("irc-1-tgt".to_string(), json!(22.0)),
+        ])
+    }
+
+    #[test]
+    fn test_discover_valves() {
+        let sources = ValveSource::discover(&structure());
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].room.as_deref(), Some("Bath"));
+        assert!(sources[0].fractional);
+        assert_eq!(sources[0].temperature_state.as_deref(), Some("irc-1-act"));
+    }
+
+    #[tokio::test]
+    async fn test_stuck_valve_detection() {
+        let start = Utc.with_ymd_and_hms(2025, 1, 15, 6, 0, 0).unwrap();
+        let sources = ValveSource::discover(&structure());
+
+        // Fully open for an hour, temperature flat: stuck
+        let stuck = HeatingDiagnostics::new();
+        for step in 0..13 {
+            let now = start + Duration::minutes(step * 5);
+            stuck.record(sources.clone(), &values(1.0, 19.0), now).await;
+        }
+        let diagnosis = &stuck.diagnose(start + Duration::minutes(60)).await[0];
+        assert_eq!(diagnosis.position, Some(100.0));
+        assert_eq!(diagnosis.duty_cycle_percent, Some(100.0));
+        assert!(diagnosis.stuck);
+
+        // Fully open and the room warms up: healthy
+        let healthy = HeatingDiagnostics::new();
+        for step in 0..13 {
+            let now = start + Duration::minutes(step * 5);
+            let temperature = 19.0 + step as f64 * 0.1;
+            healthy
+                .record(sources.clone(), &values(1.0, temperature), now)
+                .await;
+        }
+        assert!(!healthy.diagnose(start + Duration::minutes(60)).await[0].stuck);
+
+        // Not enough history yet: never flagged
+        let fresh = HeatingDiagnostics::new();
+        fresh
+            .record(sources.clone(), &values(1.0, 19.0), start)
+            .await;
+        fresh
+            .record(sources, &values(0.5, 19.0), start + Duration::minutes(5))
+            .await;
+        let diagnosis = &fresh.diagnose(start + Duration::minutes(5)).await[0];
+        assert!(!diagnosis.stuck);
+        assert_eq!(diagnosis.duty_cycle_percent, Some(75.0));
+    }
+}
@@ -0,0 +1,407 @@
+//! Load Manager handling
+//!
+//! A `LoadManager` control keeps the house below its connection limit by
+//! switching off (shedding) consumers. Its `details.loads` list names the
+//! managed consumers in priority order; the first load has the highest
+//! priority and is shed last:
+//!
+//! ```json
+//! {"loads": [{"id": 0, "name": "Wallbox", "power": 11.0}, {"id": 1, "name": "Sauna", "power": 9.0}]}
+//! ```
+//!
+//! States (powers in kW):
+//!
+//! - `currentPower`, `availablePower`, `maxPower`
+//! - `lockedLoads`: bit mask of the load ids currently shed
+//! - `statusLoads`: bit mask of the load ids currently running
+//! - `sheddingActive`: 0 while shedding is suspended
+//!
+//! Shedding is suspended with `shedding/0` and resumed with `shedding/1`;
+//! `load/<id>/shed/0` excludes a single load from shedding and
+//! `load/<id>/shed/1` includes it again.
+//!
+//! The manager only reports which loads are shed right now, so
+//! [`LoadSheddingLog`] samples `lockedLoads` in the background and turns its
+//! changes into shed events, kept for [`HISTORY_WINDOW_HOURS`]. An event
+//! without `ended` is still in progress.
+
+use crate::client::{LoxoneClient, LoxoneStructure};
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Control types handled as load managers
+pub const LOAD_MANAGER_TYPES: &[&str] = &["LoadManager"];
+
+/// Interval between samples taken by the background job
+pub const SAMPLE_INTERVAL_SECS: u64 = 60;
+
+/// Finished shed events older than this are dropped
+pub const HISTORY_WINDOW_HOURS: i64 = 24;
+
+/// State names read for a load manager status
+pub const LOAD_MANAGER_STATE_NAMES: &[&str] = &[
+    "currentPower",
+    "availablePower",
+    "maxPower",
+    "lockedLoads",
+    "statusLoads",
+    "sheddingActive",
+];
+
+/// Consumer managed by a load manager
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadConsumer {
+    pub id: u32,
+    pub name: String,
+    /// Rated power in kW
+    pub power_kw: Option<f64>,
+    /// 1 for the most important load, which is shed last
+    pub priority: usize,
+}
+
+/// Consumers of a load manager in priority order
+pub fn consumers(control: &Value) -> Vec<LoadConsumer> {
+    control
+        .get("details")
+        .and_then(|details| details.get("loads"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|load| {
+            Some((
+                load.get("id")?.as_u64()? as u32,
+                load.get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                load.get("power").and_then(Value::as_f64),
+            ))
+        })
+        .enumerate()
+        .map(|(index, (id, name, power_kw))| LoadConsumer {
+            id,
+            name,
+            power_kw,
+            priority: index + 1,
+        })
+        .collect()
+}
+
+/// Find a consumer by id or name (case-insensitive partial match)
+pub fn find_consumer<'a>(
+    consumers: &'a [LoadConsumer],
+    consumer: &str,
+) -> Result<&'a LoadConsumer> {
+    let lower = consumer.trim().to_lowercase();
+    consumers
+        .iter()
+        .find(|c| c.id.to_string() == lower || c.name.to_lowercase() == lower)
+        .or_else(|| {
+            consumers
+                .iter()
+                .find(|c| c.name.to_lowercase().contains(&lower))
+        })
+        .ok_or_else(|| {
+            let names: Vec<&str> = consumers.iter().map(|c| c.name.as_str()).collect();
+            LoxoneError::not_found(format!(
+                "Load '{consumer}' not found. Managed loads: {}",
+                names.join(", ")
+            ))
+        })
+}
+
+/// Load ids set in a `lockedLoads`/`statusLoads` bit mask
+pub fn loads_in_mask(mask: f64) -> Vec<u32> {
+    let mask = mask.max(0.0) as u64;
+    (0..64).filter(|bit| mask & (1 << bit) != 0).collect()
+}
+
+/// Snapshot of a load manager's state
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LoadManagerStatus {
+    pub current_power_kw: Option<f64>,
+    pub available_power_kw: Option<f64>,
+    pub max_power_kw: Option<f64>,
+    /// `None` when the control does not report it
+    pub shedding_enabled: Option<bool>,
+    /// Ids of the loads currently shed
+    pub shed_loads: Vec<u32>,
+    /// Ids of the loads currently running
+    pub running_loads: Vec<u32>,
+}
+
+impl LoadManagerStatus {
+    /// Build the status from state values keyed by state name
+    pub fn from_state_values(values: &HashMap<String, Value>) -> Self {
+        let number = |name: &str| values.get(name).and_then(value_as_f64);
+        Self {
+            current_power_kw: number("currentPower"),
+            available_power_kw: number("availablePower"),
+            max_power_kw: number("maxPower"),
+            shedding_enabled: number("sheddingActive").map(|v| v != 0.0),
+            shed_loads: number("lockedLoads").map(loads_in_mask).unwrap_or_default(),
+            running_loads: number("statusLoads").map(loads_in_mask).unwrap_or_default(),
+        }
+    }
+}
+
+/// State UUIDs of a load manager, keyed by state name
+pub fn state_uuids(control: &Value) -> HashMap<String, String> {
+    LOAD_MANAGER_STATE_NAMES
+        .iter()
+        .filter_map(|name| {
+            control
+                .get("states")
+                .and_then(|states| states.get(*name))
+                .and_then(Value::as_str)
+                .map(|uuid| ((*name).to_string(), uuid.to_string()))
+        })
+        .collect()
+}
+
+/// Command enabling or disabling shedding for the whole manager or, with
+/// `consumer`, for a single load
+pub fn shedding_command(enable: bool, consumer: Option<&LoadConsumer>) -> String {
+    let flag = u8::from(enable);
+    match consumer {
+        Some(consumer) => format!("load/{}/shed/{flag}", consumer.id),
+        None => format!("shedding/{flag}"),
+    }
+}
+
+/// One period during which a load was shed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShedEvent {
+    /// UUID of the load manager
+    pub manager: String,
+    pub load_id: u32,
+    pub load: String,
+    pub started: DateTime<Utc>,
+    pub ended: Option<DateTime<Utc>>,
+}
+
+/// Rolling log of shed events
+#[derive(Default)]
+pub struct LoadSheddingLog {
+    events: RwLock<Vec<ShedEvent>>,
+    sampled: RwLock<bool>,
+}
+
+impl LoadSheddingLog {
+    /// Create an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the loads of `manager` shed at `now`
+    pub async fn record(
+        &self,
+        manager: &str,
+        consumers: &[LoadConsumer],
+        shed: &[u32],
+        now: DateTime<Utc>,
+    ) {
+        let mut events = self.events.write().await;
+        for event in events
+            .iter_mut()
+            .filter(|e| e.manager == manager && e.ended.is_none())
+        {
+            if !shed.contains(&event.load_id) {
+                event.ended = Some(now);
+            }
+        }
+        for id in shed {
+            let open = events
+                .iter()
+                .any(|e| e.manager == manager && e.load_id == *id && e.ended.is_none());
+            if !open {
+                let load = consumers
+                    .iter()
+                    .find(|c| c.id == *id)
+                    .map_or_else(|| format!("Load {id}"), |c| c.name.clone());
+                events.push(ShedEvent {
+                    manager: manager.to_string(),
+                    load_id: *id,
+                    load,
+                    started: now,
+                    ended: None,
+                });
+            }
+        }
+        let cutoff = now - Duration::hours(HISTORY_WINDOW_HOURS);
+        events.retain(|e| e.ended.is_none_or(|ended| ended >= cutoff));
+        *self.sampled.write().await = true;
+    }
+
+    /// Read the shed loads of all load managers and record them
+    pub async fn sample(&self, client: &dyn LoxoneClient) -> Result<usize> {
+        let structure = client.get_structure().await?;
+        let managers = discover(&structure);
+        let uuids: Vec<String> = managers
+            .iter()
+            .filter_map(|(_, state, _)| state.clone())
+            .collect();
+        if uuids.is_empty() {
+            return Ok(0);
+        }
+        let values = client.get_state_values(&uuids).await?;
+        let now = Utc::now();
+        for (uuid, state, consumers) in &managers {
+            let Some(value) = state.as_ref().and_then(|s| values.get(s)) else {
+                continue;
+            };
+            let shed = value_as_f64(value).map(loads_in_mask).unwrap_or_default();
+            self.record(uuid, consumers, &shed, now).await;
+        }
+        Ok(managers.len())
+    }
+
+    /// Events of `manager` that were in progress at some point since `since`
+    pub async fn events_since(&self, manager: &str, since: DateTime<Utc>) -> Vec<ShedEvent> {
+        self.events
+            .read()
+            .await
+            .iter()
+            .filter(|e| e.manager == manager && e.ended.is_none_or(|ended| ended >= since))
+            .cloned()
+            .collect()
+    }
+
+    /// Whether any sample has been recorded yet
+    pub async fn has_samples(&self) -> bool {
+        *self.sampled.read().await
+    }
+
+    /// Sample the load managers every [`SAMPLE_INTERVAL_SECS`]
+    pub fn start(self: &Arc<Self>, client: Arc<dyn LoxoneClient>) -> tokio::task::JoinHandle<()> {
+        let log = self.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(SAMPLE_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                match log.sample(client.as_ref()).await {
+                    Ok(count) => debug!("Sampled {count} load managers"),
+                    Err(e) => warn!("Failed to sample load managers: {e}"),
+                }
+            }
+        })
+    }
+}
+
+/// Load managers with their `lockedLoads` state UUID and consumers
+fn discover(structure: &LoxoneStructure) -> Vec<(String, Option<String>, Vec<LoadConsumer>)> {
+    structure
+        .controls
+        .iter()
+        .filter(|(_, control)| {
+            control
+                .get("type")
+                .and_then(|t| t.as_str())
+                .is_some_and(|t| LOAD_MANAGER_TYPES.contains(&t))
+        })
+        .map(|(uuid, control)| {
+            (
+                uuid.clone(),
+                state_uuids(control).remove("lockedLoads"),
+                consumers(control),
+            )
+        })
+        .collect()
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn manager() -> Value {
+        json!({
+            "type": "LoadManager",
+            "details": {"loads": [
+                {"id": 0, "name": "Wallbox", "power": 11.0},
+                {"id": 2, "name": "Sauna", "power": 9.0},
+                {"name": "no id"}
+            ]},
+            "states": {"lockedLoads": "s-locked", "currentPower": "s-power"}
+        })
+    }
+
+    #[test]
+    fn test_consumers() {
+        let consumers = consumers(&manager());
+        assert_eq!(consumers.len(), 2);
+        assert_eq!(consumers[1].name, "Sauna");
+        assert_eq!(consumers[1].priority, 2);
+        assert_eq!(find_consumer(&consumers, "2").unwrap().name, "Sauna");
+        assert_eq!(find_consumer(&consumers, "wall").unwrap().id, 0);
+        assert!(find_consumer(&consumers, "pool").is_err());
+
+        assert_eq!(
+            shedding_command(false, Some(&consumers[1])),
+            "load/2/shed/0"
+        );
+        assert_eq!(shedding_command(true, None), "shedding/1");
+        assert_eq!(state_uuids(&manager()).len(), 2);
+    }
+
+    #[test]
+    fn test_status() {
+        let values = HashMap::from([
+            ("currentPower".to_string(), json!("21.5")),
+            ("lockedLoads".to_string(), json!(5)),
+            ("sheddingActive".to_string(), json!(1)),
+        ]);
+        let status = LoadManagerStatus::from_state_values(&values);
+        assert_eq!(status.current_power_kw, Some(21.5));
+        assert_eq!(status.shed_loads, vec![0, 2]);
+        assert!(status.running_loads.is_empty());
+        assert_eq!(status.shedding_enabled, Some(true));
+        assert_eq!(
+            LoadManagerStatus::from_state_values(&HashMap::new()).shedding_enabled,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shed_events() {
+        let consumers = consumers(&manager());
+        let log = LoadSheddingLog::new();
+        assert!(!log.has_samples().await);
+        let start = Utc::now() - Duration::hours(30);
+
+        // Shed for ten minutes 30 hours ago: dropped once a day has passed
+        log.record("m-1", &consumers, &[2], start).await;
+        log.record("m-1", &consumers, &[], start + Duration::minutes(10))
+            .await;
+        let events = log.events_since("m-1", start).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].load, "Sauna");
+        assert_eq!(events[0].ended, Some(start + Duration::minutes(10)));
+
+        let now = Utc::now();
+        log.record("m-1", &consumers, &[2, 3], now).await;
+        log.record("m-1", &consumers, &[2, 3], now + Duration::minutes(1))
+            .await;
+        let events = log.events_since("m-1", now - Duration::hours(24)).await;
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.ended.is_none()));
+        assert_eq!(events[1].load, "Load 3");
+        assert!(log.events_since("m-2", start).await.is_empty());
+    }
+}
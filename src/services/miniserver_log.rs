@@ -0,0 +1,256 @@
+//! Miniserver system log retrieval
+//!
+//! The Miniserver writes its system log to the SD card under `/log`, served
+//! through the file API: `dev/fslist/log` lists the files and
+//! `dev/fsget/log/<file>` downloads one; `def.log` is the current log. Older
+//! firmware only offers the PLC log at `dev/sps/log`.
+//!
+//! Log lines start with a timestamp (`2025-03-04 09:12:40` with optional
+//! milliseconds) followed by the message; the severity is either given as a
+//! marker (`<Error>`, `[WARNING]`, `Info:`) or guessed from the message.
+//! Lines without a timestamp continue the previous entry.
+
+use crate::error::{LoxoneError, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Listing of the log files
+pub const LOG_LISTING_PATH: &str = "dev/fslist/log";
+
+/// Directory log files are downloaded from
+pub const LOG_DOWNLOAD_PATH: &str = "dev/fsget/log";
+
+/// Current system log
+pub const DEFAULT_LOG_FILE: &str = "def.log";
+
+/// PLC log of firmware without a log directory
+pub const SPS_LOG_PATH: &str = "dev/sps/log";
+
+/// Severity of a log entry, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSeverity {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl LogSeverity {
+    /// Parse a severity name
+    pub fn parse(severity: &str) -> Result<Self> {
+        Self::from_marker(severity).ok_or_else(|| {
+            LoxoneError::invalid_input(format!(
+                "Invalid severity '{severity}'. Use: debug, info, warning, error"
+            ))
+        })
+    }
+
+    fn from_marker(marker: &str) -> Option<Self> {
+        match marker.trim().to_lowercase().as_str() {
+            "debug" | "trace" | "verbose" => Some(Self::Debug),
+            "info" | "information" | "notice" => Some(Self::Info),
+            "warning" | "warn" => Some(Self::Warning),
+            "error" | "err" | "fatal" | "critical" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    /// Severity guessed from the message text
+    fn guess(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if ["error", "fail", "exception", "crash"]
+            .iter()
+            .any(|word| lower.contains(word))
+        {
+            Self::Error
+        } else if lower.contains("warn") {
+            Self::Warning
+        } else {
+            Self::Info
+        }
+    }
+}
+
+/// One log entry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEntry {
+    pub time: NaiveDateTime,
+    pub severity: LogSeverity,
+    pub message: String,
+}
+
+/// A log file on the SD card
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogFile {
+    pub name: String,
+    pub size: Option<u64>,
+}
+
+/// Parse the `dev/fslist/log` output into `.log` files
+///
+/// Lines look like `-  48213 Tue Mar 04 09:12:40 2025 def.log`.
+pub fn parse_log_listing(listing: &str) -> Vec<LogFile> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            if fields.next()?.starts_with('d') {
+                return None;
+            }
+            let size = fields.next().and_then(|s| s.parse().ok());
+            let name = line.split_whitespace().last()?;
+            name.to_ascii_lowercase()
+                .ends_with(".log")
+                .then(|| LogFile {
+                    name: name.to_string(),
+                    size,
+                })
+        })
+        .collect()
+}
+
+/// Download path of a log file, rejecting names outside the log directory
+pub fn log_file_path(file: &str) -> Result<String> {
+    if file.is_empty() || file.contains('/') || file.contains('\\') || file.contains("..") {
+        return Err(LoxoneError::invalid_input(format!(
+            "Invalid log file name '{file}'"
+        )));
+    }
+    Ok(format!("{LOG_DOWNLOAD_PATH}/{file}"))
+}
+
+/// Parse log text into entries, oldest first
+pub fn parse_log(text: &str) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            continue;
+        }
+        match split_timestamp(line) {
+            Some((time, rest)) => {
+                let (marker, message) = split_marker(rest);
+                entries.push(LogEntry {
+                    time,
+                    severity: marker.unwrap_or_else(|| LogSeverity::guess(message)),
+                    message: message.to_string(),
+                });
+            }
+            None => {
+                if let Some(last) = entries.last_mut() {
+                    last.message.push('\n');
+                    last.message.push_str(line.trim());
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// Leading `YYYY-MM-DD HH:MM:SS[.fff]` timestamp and the rest of the line
+fn split_timestamp(line: &str) -> Option<(NaiveDateTime, &str)> {
+    let head = line.get(..19)?;
+    let time = NaiveDateTime::parse_from_str(head, "%Y-%m-%d %H:%M:%S").ok()?;
+    let mut rest = &line[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    Some((time, rest.trim_start_matches([' ', ';', '\t'])))
+}
+
+/// Severity marker at the start of a message, and the message without it
+fn split_marker(rest: &str) -> (Option<LogSeverity>, &str) {
+    let (token, message) = rest.split_once([' ', ';']).unwrap_or((rest, ""));
+    let inner = token
+        .trim_start_matches(['<', '['])
+        .trim_end_matches(['>', ']', ':']);
+    match LogSeverity::from_marker(inner) {
+        Some(severity) if inner.len() < token.len() || rest.contains(';') => {
+            (Some(severity), message.trim())
+        }
+        _ => (None, rest.trim()),
+    }
+}
+
+/// Entries at least as severe as `min_severity` and not older than `since`,
+/// newest first, at most `limit`
+pub fn filter_entries(
+    entries: Vec<LogEntry>,
+    min_severity: LogSeverity,
+    since: Option<NaiveDateTime>,
+    limit: usize,
+) -> Vec<LogEntry> {
+    let mut selected: Vec<LogEntry> = entries
+        .into_iter()
+        .filter(|e| e.severity >= min_severity && since.is_none_or(|since| e.time >= since))
+        .collect();
+    selected.sort_by(|a, b| b.time.cmp(&a.time));
+    selected.truncate(limit);
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+2025-03-04 09:12:40.120 <Info> Program started
+2025-03-04 09:13:02 [WARNING] Tree device 0F1E2D3C offline
+2025-03-04 09:15:11;Error;Extension 1 not responding
+    retry 3 of 3
+2025-03-04 09:20:00 Mail server connection failed
+garbage before a timestamp
+2025-03-04 09:21:00 Info: NTP time received";
+
+    #[test]
+    fn test_parse_log() {
+        let entries = parse_log(LOG);
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0].severity, LogSeverity::Info);
+        assert_eq!(entries[0].message, "Program started");
+        assert_eq!(entries[1].severity, LogSeverity::Warning);
+        assert_eq!(entries[2].severity, LogSeverity::Error);
+        assert_eq!(
+            entries[2].message,
+            "Extension 1 not responding\nretry 3 of 3"
+        );
+        // No marker: guessed from the message
+        assert_eq!(entries[3].severity, LogSeverity::Error);
+        assert!(entries[3].message.ends_with("garbage before a timestamp"));
+        assert_eq!(entries[4].message, "NTP time received");
+    }
+
+    #[test]
+    fn test_filter_entries() {
+        let since = NaiveDateTime::parse_from_str("2025-03-04 09:13:00", "%Y-%m-%d %H:%M:%S").ok();
+        let warnings = filter_entries(parse_log(LOG), LogSeverity::Warning, since, 10);
+        let messages: Vec<&str> = warnings.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].starts_with("Mail server"));
+        assert_eq!(
+            filter_entries(parse_log(LOG), LogSeverity::Debug, None, 2).len(),
+            2
+        );
+        assert!(LogSeverity::parse("fatal").is_ok());
+        assert!(LogSeverity::parse("loud").is_err());
+    }
+
+    #[test]
+    fn test_listing_and_paths() {
+        let files = parse_log_listing(
+            "-      48213 Tue Mar 04 09:12:40 2025 def.log\n\
+             d          0 Tue Mar 04 09:12:40 2025 old\n\
+             -        512 Tue Mar 04 09:12:40 2025 notes.txt\n",
+        );
+        assert_eq!(
+            files,
+            vec![LogFile {
+                name: "def.log".to_string(),
+                size: Some(48213)
+            }]
+        );
+        assert_eq!(log_file_path("def.log").unwrap(), "dev/fsget/log/def.log");
+        assert!(log_file_path("../prog/sps.zip").is_err());
+    }
+}
@@ -3,14 +3,31 @@
 //! This module contains centralized services that provide a single source
 //! of truth for device values, sensor detection, and state management.
 
+pub mod announcements;
 pub mod cache_manager;
+pub mod command_history;
 pub mod connection_pool;
+pub mod countdowns;
+pub mod device_usage;
+pub mod energy_scheduler;
+pub mod favorites;
+pub mod heating_diagnostics;
+pub mod load_manager;
+pub mod miniserver_log;
+pub mod power_monitor;
+pub mod presence_report;
+pub mod program_backup;
+pub mod scene_snapshot;
 pub mod sensor_logger;
 pub mod sensor_registry;
 pub mod state_manager;
+pub mod structure_diff;
+pub mod tariff;
 pub mod unified_models;
+pub mod units;
 pub mod value_parsers;
 pub mod value_resolution;
+pub mod weather_protection;
 
 pub use sensor_logger::SensorStateLogger;
 pub use sensor_registry::{SensorInventory, SensorType, SensorTypeRegistry};
@@ -21,5 +38,6 @@ pub use unified_models::{
     DataQuality, DataSource, SemanticValue, UnifiedDeviceValue, UnifiedDeviceValueBatch,
     UnifiedValue, ValueMetadata,
 };
+pub use units::UnitSystem;
 pub use value_parsers::{ParsedValue, ValueParser, ValueParserRegistry};
 pub use value_resolution::{ResolvedValue, UnifiedValueResolver, ValidationStatus, ValueSource};
@@ -0,0 +1,462 @@
+//! UPS and power-fail awareness
+//!
+//! Installations with a UPS usually wire its status into the Miniserver: a
+//! digital input for "power fail" / "on battery" (or "mains OK") and often
+//! an analog input with the battery charge. When such inputs exist in the
+//! structure, this monitor polls them and on a power failure:
+//!
+//! - switches the [`ApiBudget`] to a reduced profile so background polling
+//!   stops competing with the Miniserver for the remaining battery time
+//! - sends a `power.on_battery` notification through the configured
+//!   channels, and `power.restored` once mains power is back
+//!
+//! Inputs are discovered by name (`Power fail`, `Stromausfall`, `USV Akku`,
+//! ...) or configured explicitly. Without matching inputs the monitor stays
+//! idle. The current power state is part of `get_server_status`.
+
+use crate::client::api_budget::ApiBudget;
+use crate::client::{LoxoneClient, LoxoneStructure};
+use crate::error::Result;
+use crate::notifications::{NotificationDispatcher, NotificationSeverity};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Interval between power checks
+pub const CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Name fragments of inputs that are active while mains power is missing
+const POWER_FAIL_PATTERNS: &[&str] = &[
+    "power fail",
+    "powerfail",
+    "power outage",
+    "on battery",
+    "stromausfall",
+    "netzausfall",
+    "batteriebetrieb",
+];
+
+/// Name fragments of inputs that are active while mains power is present
+const MAINS_OK_PATTERNS: &[&str] = &["mains ok", "mains power", "netz ok", "netzspannung"];
+
+/// Name fragments identifying a UPS
+const UPS_PATTERNS: &[&str] = &["ups", "usv"];
+
+/// Name fragments of UPS battery charge inputs
+const BATTERY_PATTERNS: &[&str] = &["battery", "akku", "charge", "ladung"];
+
+/// Meaning of a power input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerInputRole {
+    /// Active while running on battery
+    PowerFail,
+    /// Active while mains power is present
+    MainsOk,
+    /// UPS battery charge in percent
+    Battery,
+}
+
+/// An explicitly configured power input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PowerInputConfig {
+    /// Control UUID or name
+    pub device: String,
+    pub role: PowerInputRole,
+}
+
+/// Power monitoring settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerMonitorConfig {
+    /// Monitor power inputs (idle anyway if none are found)
+    #[serde(default = "enabled_from_env")]
+    pub enabled: bool,
+    /// Share of the API budget left while on battery
+    #[serde(default = "default_reduced_budget")]
+    pub reduced_budget_factor: f64,
+    /// Discover power inputs by name
+    #[serde(default = "default_true")]
+    pub discover_inputs: bool,
+    /// Inputs used in addition to discovered ones
+    #[serde(default)]
+    pub inputs: Vec<PowerInputConfig>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_reduced_budget() -> f64 {
+    0.25
+}
+
+fn enabled_from_env() -> bool {
+    std::env::var("LOXONE_POWER_MONITOR").map_or(true, |v| v != "false" && v != "0")
+}
+
+impl Default for PowerMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: enabled_from_env(),
+            reduced_budget_factor: default_reduced_budget(),
+            discover_inputs: true,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+/// A Miniserver input reporting power status
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PowerInput {
+    pub uuid: String,
+    pub name: String,
+    pub role: PowerInputRole,
+    /// State UUID carrying the value
+    #[serde(skip)]
+    state: String,
+}
+
+/// Power inputs of a structure, configured ones first
+pub fn find_inputs(config: &PowerMonitorConfig, structure: &LoxoneStructure) -> Vec<PowerInput> {
+    let mut inputs = Vec::new();
+    for configured in &config.inputs {
+        let lower = configured.device.to_lowercase();
+        let found = structure
+            .controls
+            .get_key_value(&configured.device)
+            .or_else(|| {
+                structure
+                    .controls
+                    .iter()
+                    .find(|(_, control)| control_name(control).to_lowercase() == lower)
+            });
+        match found.and_then(|(uuid, control)| input_for(uuid, control, configured.role)) {
+            Some(input) => inputs.push(input),
+            None => warn!("Power input '{}' not found", configured.device),
+        }
+    }
+
+    if config.discover_inputs {
+        let mut discovered: Vec<_> = structure
+            .controls
+            .iter()
+            .filter(|(uuid, _)| !inputs.iter().any(|i: &PowerInput| &i.uuid == *uuid))
+            .filter_map(|(uuid, control)| input_for(uuid, control, discover_role(control)?))
+            .collect();
+        discovered.sort_by(|a, b| a.name.cmp(&b.name));
+        inputs.extend(discovered);
+    }
+    inputs
+}
+
+fn discover_role(control: &Value) -> Option<PowerInputRole> {
+    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let name = control_name(control).to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| name.contains(p));
+    let is_ups = name
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| UPS_PATTERNS.contains(&word));
+    match control_type {
+        "InfoOnlyDigital" if matches(POWER_FAIL_PATTERNS) => Some(PowerInputRole::PowerFail),
+        "InfoOnlyDigital" if matches(MAINS_OK_PATTERNS) => Some(PowerInputRole::MainsOk),
+        "InfoOnlyAnalog" if is_ups && matches(BATTERY_PATTERNS) => Some(PowerInputRole::Battery),
+        _ => None,
+    }
+}
+
+fn input_for(uuid: &str, control: &Value, role: PowerInputRole) -> Option<PowerInput> {
+    let state = control
+        .get("states")
+        .and_then(|s| s.get("active").or_else(|| s.get("value")))
+        .and_then(|v| v.as_str())?;
+    Some(PowerInput {
+        uuid: uuid.to_string(),
+        name: control_name(control),
+        role,
+        state: state.to_string(),
+    })
+}
+
+fn control_name(control: &Value) -> String {
+    control
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Whether the installation runs on mains or battery power
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerState {
+    /// No power inputs, or not checked yet
+    #[default]
+    Unknown,
+    Mains,
+    OnBattery,
+}
+
+/// Power status read from the inputs
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PowerReading {
+    pub state: PowerState,
+    /// Input that reported the power failure
+    pub source: Option<String>,
+    pub battery_percent: Option<f64>,
+}
+
+/// Combine input values into a reading; any failing input means battery power
+pub fn read_power(inputs: &[PowerInput], values: &HashMap<String, Value>) -> PowerReading {
+    let mut reading = PowerReading::default();
+    for input in inputs {
+        let Some(value) = values.get(&input.state).and_then(value_as_f64) else {
+            continue;
+        };
+        let failed = match input.role {
+            PowerInputRole::PowerFail => value > 0.0,
+            PowerInputRole::MainsOk => value <= 0.0,
+            PowerInputRole::Battery => {
+                reading.battery_percent = Some(value);
+                continue;
+            }
+        };
+        if failed {
+            reading.state = PowerState::OnBattery;
+            reading.source.get_or_insert_with(|| input.name.clone());
+        } else if reading.state == PowerState::Unknown {
+            reading.state = PowerState::Mains;
+        }
+    }
+    reading
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    inputs: Vec<PowerInput>,
+    reading: PowerReading,
+    /// When the current power state began
+    since: Option<DateTime<Utc>>,
+    last_check: Option<DateTime<Utc>>,
+    power_failures: u64,
+}
+
+/// Watches power inputs and adapts polling to the power state
+pub struct PowerMonitor {
+    config: PowerMonitorConfig,
+    budget: Arc<ApiBudget>,
+    notifier: Arc<NotificationDispatcher>,
+    state: RwLock<MonitorState>,
+}
+
+impl Default for PowerMonitor {
+    fn default() -> Self {
+        Self::new(
+            PowerMonitorConfig::default(),
+            Arc::new(ApiBudget::default()),
+            Arc::new(NotificationDispatcher::default()),
+        )
+    }
+}
+
+impl PowerMonitor {
+    pub fn new(
+        config: PowerMonitorConfig,
+        budget: Arc<ApiBudget>,
+        notifier: Arc<NotificationDispatcher>,
+    ) -> Self {
+        Self {
+            config,
+            budget,
+            notifier,
+            state: RwLock::new(MonitorState::default()),
+        }
+    }
+
+    pub async fn state(&self) -> PowerState {
+        self.state.read().await.reading.state
+    }
+
+    /// Read the power inputs once and react to a change
+    pub async fn check(&self, client: &dyn LoxoneClient) -> Result<PowerReading> {
+        let structure = client.get_structure().await?;
+        let inputs = find_inputs(&self.config, &structure);
+        let states: Vec<String> = inputs.iter().map(|i| i.state.clone()).collect();
+        let values = if states.is_empty() {
+            HashMap::new()
+        } else {
+            client.get_state_values(&states).await?
+        };
+        let reading = read_power(&inputs, &values);
+        self.state.write().await.inputs = inputs;
+        self.update(reading.clone()).await;
+        Ok(reading)
+    }
+
+    /// Record a reading; on a transition switch the budget profile and notify
+    pub async fn update(&self, reading: PowerReading) {
+        let now = Utc::now();
+        let (previous, since) = {
+            let mut state = self.state.write().await;
+            state.last_check = Some(now);
+            let previous = state.reading.state;
+            let since = state.since;
+            if previous != reading.state {
+                state.since = Some(now);
+                if reading.state == PowerState::OnBattery {
+                    state.power_failures += 1;
+                }
+            }
+            state.reading = reading.clone();
+            (previous, since)
+        };
+
+        match (previous, reading.state) {
+            (PowerState::OnBattery, PowerState::OnBattery) => {}
+            (_, PowerState::OnBattery) => {
+                warn!(
+                    "⚡ Power failure reported by {}; reducing background polling",
+                    reading.source.as_deref().unwrap_or("power input")
+                );
+                self.budget
+                    .set_reduced_profile(Some(self.config.reduced_budget_factor));
+                self.notifier
+                    .notify(
+                        "power.on_battery",
+                        NotificationSeverity::Critical,
+                        &json!({
+                            "source": reading.source,
+                            "battery_percent": reading.battery_percent,
+                        }),
+                    )
+                    .await;
+            }
+            (PowerState::OnBattery, PowerState::Mains) => {
+                let duration = since
+                    .and_then(|since| (now - since).to_std().ok())
+                    .map(|d| Duration::from_secs(d.as_secs()))
+                    .unwrap_or_default();
+                let duration =
+                    humantime_serde::re::humantime::format_duration(duration).to_string();
+                info!("⚡ Mains power restored after {duration}; resuming normal polling");
+                self.budget.set_reduced_profile(None);
+                self.notifier
+                    .notify(
+                        "power.restored",
+                        NotificationSeverity::Info,
+                        &json!({"duration": duration}),
+                    )
+                    .await;
+            }
+            _ => {}
+        }
+    }
+
+    /// Power state, inputs and failure count as JSON
+    pub async fn status(&self) -> Value {
+        let state = self.state.read().await;
+        json!({
+            "enabled": self.config.enabled,
+            "state": state.reading.state,
+            "source": state.reading.source,
+            "battery_percent": state.reading.battery_percent,
+            "since": state.since,
+            "last_check": state.last_check,
+            "inputs": state.inputs,
+            "power_failures": state.power_failures,
+            "reduced_budget_factor": self.config.reduced_budget_factor,
+        })
+    }
+
+    /// Check the power inputs every [`CHECK_INTERVAL_SECS`] if enabled
+    ///
+    /// Stops once a check finds no power inputs in the structure.
+    pub fn start(
+        self: &Arc<Self>,
+        client: Arc<dyn LoxoneClient>,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        if !self.config.enabled {
+            return None;
+        }
+        let monitor = self.clone();
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                match monitor.check(client.as_ref()).await {
+                    Ok(_) if monitor.state.read().await.inputs.is_empty() => {
+                        debug!("No UPS or power-fail inputs found; power monitor idle");
+                        return;
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Power check failed: {e}"),
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure() -> LoxoneStructure {
+        serde_json::from_value(json!({
+            "lastModified": "",
+            "controls": {
+                "fail": {"name": "USV Stromausfall", "type": "InfoOnlyDigital",
+                         "states": {"active": "fail-active"}},
+                "battery": {"name": "UPS Battery", "type": "InfoOnlyAnalog",
+                            "states": {"value": "battery-value"}},
+                "light": {"name": "Battery Room Light", "type": "Switch",
+                          "states": {"active": "light-active"}},
+            },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_power_failure_reduces_budget() {
+        let inputs = find_inputs(&PowerMonitorConfig::default(), &structure());
+        let roles: Vec<_> = inputs.iter().map(|i| i.role).collect();
+        assert_eq!(roles, [PowerInputRole::Battery, PowerInputRole::PowerFail]);
+
+        let values = HashMap::from([
+            ("fail-active".to_string(), json!(1.0)),
+            ("battery-value".to_string(), json!("87")),
+        ]);
+        let reading = read_power(&inputs, &values);
+        assert_eq!(reading.state, PowerState::OnBattery);
+        assert_eq!(reading.source.as_deref(), Some("USV Stromausfall"));
+        assert_eq!(reading.battery_percent, Some(87.0));
+
+        let budget = Arc::new(ApiBudget::default());
+        let full = budget.effective_rate();
+        let monitor = PowerMonitor::new(
+            PowerMonitorConfig::default(),
+            budget.clone(),
+            Arc::new(NotificationDispatcher::default()),
+        );
+        monitor.update(reading).await;
+        assert!((budget.effective_rate() - full * 0.25).abs() < 0.01);
+        assert_eq!(monitor.status().await["power_failures"], 1);
+
+        let values = HashMap::from([("fail-active".to_string(), json!(0.0))]);
+        monitor.update(read_power(&inputs, &values)).await;
+        assert_eq!(monitor.state().await, PowerState::Mains);
+        assert_eq!(budget.effective_rate(), full);
+    }
+}
@@ -0,0 +1,283 @@
+//! Presence heatmap report
+//!
+//! Aggregates motion/presence detections from the sensor history into an
+//! hour-of-day heatmap per room, exposed as `loxone://reports/presence`.
+//! The report is regenerated nightly by a background job; between runs the
+//! last generated report is served from memory.
+
+use crate::client::ClientContext;
+use crate::services::SensorType;
+use crate::services::sensor_logger::SensorStateEntry;
+use chrono::{DateTime, Duration, Local, TimeZone, Timelike, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+/// URI of the presence report resource
+pub const PRESENCE_REPORT_URI: &str = "loxone://reports/presence";
+
+/// Local hour at which the nightly job regenerates the report
+pub const NIGHTLY_RUN_HOUR: u32 = 3;
+
+/// Default number of days of history covered by the report
+pub const DEFAULT_WINDOW_DAYS: i64 = 14;
+
+/// Hour-of-day presence statistics for one room
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoomPresence {
+    /// Detections per local hour of day
+    pub counts: [u32; 24],
+    /// Counts scaled to 0.0-1.0 relative to the room's busiest hour
+    pub intensity: [f64; 24],
+    /// Busiest local hour, if any detection was recorded
+    pub peak_hour: Option<u32>,
+    /// Total detections in the window
+    pub total_detections: u32,
+}
+
+/// Presence heatmap across all rooms
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceHeatmap {
+    pub generated_at: DateTime<Utc>,
+    pub window_days: i64,
+    pub rooms: BTreeMap<String, RoomPresence>,
+}
+
+impl PresenceHeatmap {
+    /// Build the heatmap from sensor history
+    ///
+    /// Only transitions into the detected state are counted, so a sensor that
+    /// stays active for an hour contributes one detection rather than one per
+    /// state refresh. Hours are bucketed in `tz`.
+    pub fn from_history<Tz: TimeZone>(
+        history: &HashMap<String, Vec<SensorStateEntry>>,
+        window_days: i64,
+        now: DateTime<Utc>,
+        tz: &Tz,
+    ) -> Self {
+        let since = now - Duration::days(window_days);
+        let mut counts: BTreeMap<String, [u32; 24]> = BTreeMap::new();
+
+        for entry in history.values().flatten() {
+            if entry.timestamp < since
+                || !is_presence_entry(entry)
+                || !is_detected(&entry.new_value)
+            {
+                continue;
+            }
+            let room = entry.room.clone().unwrap_or_else(|| "Unknown".to_string());
+            let hour = entry.timestamp.with_timezone(tz).hour() as usize;
+            counts.entry(room).or_insert([0; 24])[hour] += 1;
+        }
+
+        let rooms = counts
+            .into_iter()
+            .map(|(room, counts)| {
+                let max = counts.iter().copied().max().unwrap_or(0);
+                let intensity = counts.map(|c| {
+                    if max == 0 {
+                        0.0
+                    } else {
+                        (f64::from(c) / f64::from(max) * 100.0).round() / 100.0
+                    }
+                });
+                let peak_hour = (max > 0)
+                    .then(|| counts.iter().position(|c| *c == max))
+                    .flatten()
+                    .map(|h| h as u32);
+                let presence = RoomPresence {
+                    counts,
+                    intensity,
+                    peak_hour,
+                    total_detections: counts.iter().sum(),
+                };
+                (room, presence)
+            })
+            .collect();
+
+        Self {
+            generated_at: now,
+            window_days,
+            rooms,
+        }
+    }
+
+    /// Render the resource body
+    pub fn to_resource_json(&self) -> Value {
+        serde_json::json!({
+            "uri": PRESENCE_REPORT_URI,
+            "generated_at": self.generated_at.to_rfc3339(),
+            "window_days": self.window_days,
+            "room_count": self.rooms.len(),
+            "rooms": self.rooms,
+        })
+    }
+}
+
+/// Whether a history entry comes from a motion or presence sensor
+fn is_presence_entry(entry: &SensorStateEntry) -> bool {
+    match &entry.sensor_type {
+        Some(SensorType::Motion | SensorType::MotionDetector | SensorType::PresenceSensor) => true,
+        Some(_) => false,
+        None => entry.sensor_name.as_deref().is_some_and(|name| {
+            let name = name.to_lowercase();
+            ["motion", "presence", "bewegung", "präsenz"]
+                .iter()
+                .any(|pattern| name.contains(pattern))
+        }),
+    }
+}
+
+/// Whether a state value means "presence detected"
+fn is_detected(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|v| v > 0.0),
+        Value::String(s) => matches!(s.to_lowercase().as_str(), "1" | "on" | "true"),
+        _ => false,
+    }
+}
+
+/// Holds the latest presence report and regenerates it nightly
+pub struct PresenceReportStore {
+    latest: RwLock<Option<PresenceHeatmap>>,
+    window_days: i64,
+}
+
+impl PresenceReportStore {
+    /// Create an empty store covering `window_days` of history
+    pub fn new(window_days: i64) -> Self {
+        Self {
+            latest: RwLock::new(None),
+            window_days,
+        }
+    }
+
+    /// Last generated report
+    pub async fn latest(&self) -> Option<PresenceHeatmap> {
+        self.latest.read().await.clone()
+    }
+
+    /// Generate a report from the context's sensor history and store it
+    pub async fn regenerate(&self, context: &ClientContext) -> PresenceHeatmap {
+        let history = match context.get_sensor_logger().await {
+            Some(logger) => logger.get_all_history().await,
+            None => HashMap::new(),
+        };
+        let report = PresenceHeatmap::from_history(&history, self.window_days, Utc::now(), &Local);
+        debug!("Generated presence report for {} rooms", report.rooms.len());
+        *self.latest.write().await = Some(report.clone());
+        report
+    }
+
+    /// Generate a report now and then every night at [`NIGHTLY_RUN_HOUR`]
+    pub fn start_nightly(
+        self: &Arc<Self>,
+        context: Arc<ClientContext>,
+    ) -> tokio::task::JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            store.regenerate(&context).await;
+            loop {
+                let wait = until_next_run(Local::now(), NIGHTLY_RUN_HOUR);
+                debug!("Next presence report in {:?}", wait);
+                tokio::time::sleep(wait).await;
+                let report = store.regenerate(&context).await;
+                info!(
+                    "📊 Nightly presence report generated ({} rooms)",
+                    report.rooms.len()
+                );
+            }
+        })
+    }
+}
+
+impl Default for PresenceReportStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_DAYS)
+    }
+}
+
+/// Time from `now` until the next occurrence of `hour`:00 local time
+fn until_next_run<Tz: TimeZone>(now: DateTime<Tz>, hour: u32) -> std::time::Duration {
+    let tz = now.timezone();
+    let run_on = |date: chrono::NaiveDate| {
+        date.and_hms_opt(hour, 0, 0)
+            .and_then(|t| t.and_local_timezone(tz.clone()).earliest())
+    };
+    let next = run_on(now.date_naive())
+        .filter(|t| *t > now)
+        .or_else(|| run_on(now.date_naive() + Duration::days(1)))
+        .unwrap_or_else(|| now.clone() + Duration::days(1));
+    (next - now)
+        .to_std()
+        .unwrap_or(std::time::Duration::from_secs(3600))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(hours_ago: i64, hour: u32, value: Value, room: &str) -> SensorStateEntry {
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 23, 0, 0).unwrap();
+        let day = (now - Duration::hours(hours_ago)).date_naive();
+        SensorStateEntry {
+            timestamp: day.and_hms_opt(hour, 15, 0).unwrap().and_utc(),
+            old_value: json!(0),
+            new_value: value,
+            sensor_name: Some("Motion".to_string()),
+            sensor_type: Some(SensorType::MotionDetector),
+            room: Some(room.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_heatmap_counts_detections_per_hour() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 23, 0, 0).unwrap();
+        let mut temperature = entry(0, 8, json!(21.5), "Kitchen");
+        temperature.sensor_type = Some(SensorType::TemperatureSimple);
+
+        let history = HashMap::from([
+            (
+                "motion-k".to_string(),
+                vec![
+                    entry(0, 7, json!(1), "Kitchen"),
+                    entry(24, 7, json!("on"), "Kitchen"),
+                    entry(0, 7, json!(0), "Kitchen"),
+                    entry(0, 19, json!(true), "Kitchen"),
+                    // Outside the window
+                    entry(24 * 30, 7, json!(1), "Kitchen"),
+                ],
+            ),
+            ("temp".to_string(), vec![temperature]),
+        ]);
+
+        let heatmap = PresenceHeatmap::from_history(&history, 14, now, &Utc);
+        let kitchen = &heatmap.rooms["Kitchen"];
+        assert_eq!(heatmap.rooms.len(), 1);
+        assert_eq!(kitchen.counts[7], 2);
+        assert_eq!(kitchen.counts[19], 1);
+        assert_eq!(kitchen.total_detections, 3);
+        assert_eq!(kitchen.peak_hour, Some(7));
+        assert_eq!(kitchen.intensity[19], 0.5);
+    }
+
+    #[test]
+    fn test_until_next_run() {
+        let before = Utc.with_ymd_and_hms(2025, 3, 10, 1, 30, 0).unwrap();
+        assert_eq!(
+            until_next_run(before, 3),
+            std::time::Duration::from_secs(90 * 60)
+        );
+
+        let after = Utc.with_ymd_and_hms(2025, 3, 10, 4, 0, 0).unwrap();
+        assert_eq!(
+            until_next_run(after, 3),
+            std::time::Duration::from_secs(23 * 3600)
+        );
+    }
+}
@@ -0,0 +1,523 @@
+//! Scheduled backups of the Miniserver program file
+//!
+//! The Miniserver keeps its Loxone Config program under `/prog` and serves it
+//! through its file API (`dev/fslist/prog` lists the files, `dev/fsget/prog/…`
+//! downloads one). This service downloads the newest program on a schedule,
+//! encrypts it and writes it to a backup directory — typically a mounted
+//! off-site location (NFS, rclone, a synced folder).
+//!
+//! Archives are encrypted with AES-256-GCM under a key derived from
+//! `LOXONE_BACKUP_PASSPHRASE` (PBKDF2-HMAC-SHA256), so a leaked backup
+//! location does not expose the program, which contains user names and
+//! network details. Backups are disabled until a directory and passphrase
+//! are configured.
+//!
+//! Each new archive is accompanied by an encrypted snapshot of the structure
+//! file, which `diff_structure` compares against the live structure (see
+//! [`crate::services::structure_diff`]).
+
+use crate::client::{LoxoneClient, LoxoneStructure};
+use crate::error::{LoxoneError, Result};
+use crate::services::structure_diff::{self, StructureDiff};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Resource with restore instructions and the available backups
+pub const RESTORE_RESOURCE_URI: &str = "loxone://backups/restore";
+
+/// File extension of encrypted archives
+pub const ARCHIVE_EXTENSION: &str = "lxbak";
+
+const LISTING_PATH: &str = "dev/fslist/prog";
+const DOWNLOAD_PATH: &str = "dev/fsget/prog";
+
+/// Archive header: magic, then salt, nonce and tag, then the ciphertext
+const MAGIC: &[u8; 8] = b"LXBAK\x00\x00\x01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const KDF_ITERATIONS: usize = 200_000;
+
+/// Program backup settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramBackupConfig {
+    /// Directory archives are written to (backups disabled if unset)
+    #[serde(default)]
+    pub directory: Option<PathBuf>,
+    /// Interval between backup runs
+    #[serde(default = "default_interval", with = "humantime_serde")]
+    pub interval: Duration,
+    /// Archives kept; older ones are deleted
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+    /// Encryption passphrase, only read from the environment
+    #[serde(skip, default = "passphrase_from_env")]
+    pub passphrase: Option<String>,
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+fn passphrase_from_env() -> Option<String> {
+    env_value("LOXONE_BACKUP_PASSPHRASE")
+}
+
+fn default_interval() -> Duration {
+    Duration::from_secs(24 * 3600)
+}
+
+fn default_keep() -> usize {
+    14
+}
+
+impl Default for ProgramBackupConfig {
+    fn default() -> Self {
+        Self {
+            directory: env_value("LOXONE_BACKUP_DIR").map(PathBuf::from),
+            interval: env_value("LOXONE_BACKUP_INTERVAL")
+                .and_then(|v| humantime_serde::re::humantime::parse_duration(&v).ok())
+                .unwrap_or_else(default_interval),
+            keep: env_value("LOXONE_BACKUP_KEEP")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_keep),
+            passphrase: passphrase_from_env(),
+        }
+    }
+}
+
+impl ProgramBackupConfig {
+    /// Whether scheduled backups can run
+    pub fn is_enabled(&self) -> bool {
+        self.directory.is_some() && self.passphrase.is_some()
+    }
+}
+
+/// A program file on the Miniserver
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProgramFile {
+    pub name: String,
+    pub size: Option<u64>,
+}
+
+/// An encrypted archive in the backup directory
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupRecord {
+    /// Archive file name
+    pub file: String,
+    /// Program file the archive contains
+    pub program: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of one backup run
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupRun {
+    pub record: BackupRecord,
+    /// False if the newest program was already backed up
+    pub created: bool,
+    /// Archives deleted by retention
+    pub pruned: Vec<String>,
+}
+
+/// Parse the `dev/fslist` output into program files
+///
+/// Lines look like `-  48213 Tue Mar 04 09:12:40 2025 sps_150_20250304091240.zip`;
+/// only `sps_*` program files (`.zip`, `.LoxCC`) are returned.
+pub fn parse_program_listing(listing: &str) -> Vec<ProgramFile> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let kind = fields.next()?;
+            if kind.starts_with('d') {
+                return None;
+            }
+            let size = fields.next().and_then(|s| s.parse().ok());
+            let name = line.split_whitespace().last()?;
+            let lower = name.to_ascii_lowercase();
+            (lower.starts_with("sps_") && (lower.ends_with(".zip") || lower.ends_with(".loxcc")))
+                .then(|| ProgramFile {
+                    name: name.to_string(),
+                    size,
+                })
+        })
+        .collect()
+}
+
+/// Newest program file; names embed the save time (`sps_<ver>_<yyyymmddhhmmss>`)
+pub fn latest_program(files: &[ProgramFile]) -> Option<&ProgramFile> {
+    files
+        .iter()
+        .max_by_key(|file| file.name.rsplit('_').next().unwrap_or_default().to_string())
+}
+
+/// Encrypt `data` into an archive
+#[cfg(feature = "crypto-openssl")]
+pub fn encrypt_archive(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    use openssl::symm::{Cipher, encrypt_aead};
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    openssl::rand::rand_bytes(&mut salt).map_err(crypto_error)?;
+    openssl::rand::rand_bytes(&mut nonce).map_err(crypto_error)?;
+    let key = derive_key(passphrase, &salt)?;
+
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&nonce),
+        MAGIC,
+        data,
+        &mut tag,
+    )
+    .map_err(crypto_error)?;
+
+    let mut archive = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + TAG_LEN + data.len());
+    archive.extend_from_slice(MAGIC);
+    archive.extend_from_slice(&salt);
+    archive.extend_from_slice(&nonce);
+    archive.extend_from_slice(&tag);
+    archive.extend_from_slice(&ciphertext);
+    Ok(archive)
+}
+
+/// Decrypt an archive written by [`encrypt_archive`]
+#[cfg(feature = "crypto-openssl")]
+pub fn decrypt_archive(archive: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    use openssl::symm::{Cipher, decrypt_aead};
+
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN + TAG_LEN;
+    if archive.len() < header || &archive[..MAGIC.len()] != MAGIC {
+        return Err(LoxoneError::invalid_input("Not a program backup archive"));
+    }
+    let (salt, rest) = archive[MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, rest) = rest.split_at(NONCE_LEN);
+    let (tag, ciphertext) = rest.split_at(TAG_LEN);
+    let key = derive_key(passphrase, salt)?;
+
+    decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(nonce),
+        MAGIC,
+        ciphertext,
+        tag,
+    )
+    .map_err(|_| LoxoneError::crypto("Wrong passphrase or corrupted backup archive"))
+}
+
+#[cfg(feature = "crypto-openssl")]
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    openssl::pkcs5::pbkdf2_hmac(
+        passphrase.as_bytes(),
+        salt,
+        KDF_ITERATIONS,
+        openssl::hash::MessageDigest::sha256(),
+        &mut key,
+    )
+    .map_err(crypto_error)?;
+    Ok(key)
+}
+
+#[cfg(feature = "crypto-openssl")]
+fn crypto_error(e: openssl::error::ErrorStack) -> LoxoneError {
+    LoxoneError::crypto(format!("Backup encryption failed: {e}"))
+}
+
+#[cfg(not(feature = "crypto-openssl"))]
+pub fn encrypt_archive(_data: &[u8], _passphrase: &str) -> Result<Vec<u8>> {
+    Err(LoxoneError::config(
+        "Program backups require the crypto-openssl feature",
+    ))
+}
+
+#[cfg(not(feature = "crypto-openssl"))]
+pub fn decrypt_archive(_archive: &[u8], _passphrase: &str) -> Result<Vec<u8>> {
+    Err(LoxoneError::config(
+        "Program backups require the crypto-openssl feature",
+    ))
+}
+
+/// Downloads, encrypts and rotates program backups
+#[derive(Default)]
+pub struct ProgramBackup {
+    config: ProgramBackupConfig,
+    last_run: RwLock<Option<std::result::Result<BackupRun, String>>>,
+}
+
+impl ProgramBackup {
+    pub fn new(config: ProgramBackupConfig) -> Self {
+        Self {
+            config,
+            last_run: RwLock::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &ProgramBackupConfig {
+        &self.config
+    }
+
+    /// Back up the newest program file unless it is already archived
+    pub async fn run_once(&self, client: &dyn LoxoneClient) -> Result<BackupRun> {
+        let result = self.backup(client).await;
+        *self.last_run.write().await = Some(match &result {
+            Ok(run) => Ok(run.clone()),
+            Err(e) => Err(e.to_string()),
+        });
+        result
+    }
+
+    async fn backup(&self, client: &dyn LoxoneClient) -> Result<BackupRun> {
+        let (directory, passphrase) = match (&self.config.directory, &self.config.passphrase) {
+            (Some(directory), Some(passphrase)) => (directory, passphrase),
+            _ => {
+                return Err(LoxoneError::config(
+                    "Program backups need LOXONE_BACKUP_DIR and LOXONE_BACKUP_PASSPHRASE",
+                ));
+            }
+        };
+
+        let listing = client.download_file(LISTING_PATH).await?;
+        let files = parse_program_listing(&String::from_utf8_lossy(&listing));
+        let program = latest_program(&files)
+            .ok_or_else(|| LoxoneError::not_found("No program file found on the Miniserver"))?;
+
+        let archive_name = format!("{}.{ARCHIVE_EXTENSION}", program.name);
+        let archive_path = directory.join(&archive_name);
+        let created = if archive_path.exists() {
+            debug!("Program {} already backed up", program.name);
+            false
+        } else {
+            let data = client
+                .download_file(&format!("{DOWNLOAD_PATH}/{}", program.name))
+                .await?;
+            let archive = encrypt_archive(&data, passphrase)?;
+
+            tokio::fs::create_dir_all(directory).await?;
+            // Write to a temporary name first so a partial file never looks complete
+            let partial = directory.join(format!("{archive_name}.partial"));
+            tokio::fs::write(&partial, &archive).await?;
+            tokio::fs::rename(&partial, &archive_path).await?;
+            info!(
+                "Backed up Miniserver program {} ({} bytes)",
+                program.name,
+                data.len()
+            );
+            // The structure snapshot is a review aid; the backup stands without it
+            match client.get_structure().await {
+                Ok(structure) => {
+                    if let Err(e) = structure_diff::save_snapshot(
+                        directory,
+                        &program.name,
+                        &structure,
+                        passphrase,
+                    )
+                    .await
+                    {
+                        warn!("Failed to save structure snapshot: {e}");
+                    }
+                }
+                Err(e) => warn!("No structure snapshot for {}: {e}", program.name),
+            }
+            true
+        };
+
+        let pruned = prune(directory, self.config.keep)?;
+        let record = list_backups(directory)?
+            .into_iter()
+            .find(|record| record.file == archive_name)
+            .ok_or_else(|| LoxoneError::internal("Backup archive disappeared after writing"))?;
+        Ok(BackupRun {
+            record,
+            created,
+            pruned,
+        })
+    }
+
+    /// Run backups every `interval` in the background
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn start_schedule(self: &Arc<Self>, client: Arc<dyn LoxoneClient>) {
+        if !self.config.is_enabled() {
+            debug!("Program backups not configured");
+            return;
+        }
+        let backup = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(backup.config.interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = backup.run_once(client.as_ref()).await {
+                    warn!("Program backup failed: {e}");
+                }
+            }
+        });
+    }
+
+    /// Archives in the backup directory, newest first
+    pub fn backups(&self) -> Result<Vec<BackupRecord>> {
+        match &self.config.directory {
+            Some(directory) if directory.exists() => list_backups(directory),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Compare `current` with a structure snapshot, by default the newest
+    ///
+    /// Returns the name of the program backup the snapshot belongs to.
+    pub async fn diff_structure(
+        &self,
+        current: &LoxoneStructure,
+        against: Option<&str>,
+    ) -> Result<(String, StructureDiff)> {
+        let (Some(directory), Some(passphrase)) = (&self.config.directory, &self.config.passphrase)
+        else {
+            return Err(LoxoneError::config(
+                "Structure snapshots need LOXONE_BACKUP_DIR and LOXONE_BACKUP_PASSPHRASE",
+            ));
+        };
+        let name = match against {
+            Some(name) => name
+                .trim_end_matches(&format!(".{ARCHIVE_EXTENSION}"))
+                .to_string(),
+            None => structure_diff::list_snapshots(directory)?
+                .into_iter()
+                .next()
+                .map(|snapshot| snapshot.name)
+                .ok_or_else(|| {
+                    LoxoneError::not_found("No structure snapshot yet; run a program backup first")
+                })?,
+        };
+        let snapshot = structure_diff::load_snapshot(directory, &name, passphrase).await?;
+        Ok((name, structure_diff::diff_structures(&snapshot, current)))
+    }
+
+    /// Content of [`RESTORE_RESOURCE_URI`]
+    pub async fn restore_resource(&self) -> Value {
+        let backups = self.backups().unwrap_or_else(|e| {
+            warn!("Failed to list program backups: {e}");
+            Vec::new()
+        });
+        let last_run = match &*self.last_run.read().await {
+            Some(Ok(run)) => json!({"status": "ok", "run": run}),
+            Some(Err(error)) => json!({"status": "failed", "error": error}),
+            None => Value::Null,
+        };
+        json!({
+            "enabled": self.config.is_enabled(),
+            "directory": self.config.directory,
+            "interval": humantime_serde::re::humantime::format_duration(self.config.interval)
+                .to_string(),
+            "keep": self.config.keep,
+            "last_run": last_run,
+            "backups": backups,
+            "instructions": RESTORE_INSTRUCTIONS,
+        })
+    }
+}
+
+/// Steps to restore a program from an archive
+pub const RESTORE_INSTRUCTIONS: &[&str] = &[
+    "Pick the archive to restore; names contain the program version and save time (sps_<version>_<yyyymmddhhmmss>).",
+    "Decrypt it with the backup passphrase: `loxone-cli decrypt-backup <archive>.lxbak` (reads LOXONE_BACKUP_PASSPHRASE) writes the original .zip/.LoxCC file next to it.",
+    "Open the decrypted file in Loxone Config (File → Open), matching the Config version to the program version where possible.",
+    "Connect to the Miniserver and use 'Save in Miniserver' to transfer the program; the Miniserver restarts with it.",
+    "After the restart, reconnect this server so the structure file and caches are reloaded.",
+];
+
+/// Encrypted archives in `directory`, newest first
+pub fn list_backups(directory: &Path) -> Result<Vec<BackupRecord>> {
+    let mut records = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let file = entry.file_name().to_string_lossy().to_string();
+        let Some(program) = file.strip_suffix(&format!(".{ARCHIVE_EXTENSION}")) else {
+            continue;
+        };
+        let metadata = entry.metadata()?;
+        records.push(BackupRecord {
+            program: program.to_string(),
+            size_bytes: metadata.len(),
+            created_at: metadata
+                .modified()
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_else(|_| Utc::now()),
+            file,
+        });
+    }
+    records.sort_by(|a, b| b.program.cmp(&a.program));
+    Ok(records)
+}
+
+/// Delete all but the newest `keep` archives
+fn prune(directory: &Path, keep: usize) -> Result<Vec<String>> {
+    let mut pruned = Vec::new();
+    for record in list_backups(directory)?.into_iter().skip(keep.max(1)) {
+        std::fs::remove_file(directory.join(&record.file))?;
+        structure_diff::remove_snapshot(directory, &record.program)?;
+        info!("Deleted old program backup {}", record.file);
+        pruned.push(record.file);
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockLoxoneClient;
+
+    #[test]
+    fn test_parse_program_listing() {
+        let listing = "d        0 Tue Mar 04 09:12:40 2025 old\n\
+                       -    48213 Tue Mar 04 09:12:40 2025 sps_150_20250304091240.zip\n\
+                       -      812 Tue Mar 04 09:12:40 2025 permissions.bin\n\
+                       -    50113 Sat Mar 08 18:30:02 2025 sps_151_20250308183002.LoxCC\n";
+        let files = parse_program_listing(listing);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].size, Some(48213));
+        assert_eq!(
+            latest_program(&files).unwrap().name,
+            "sps_151_20250308183002.LoxCC"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backup_round_trip_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("sps_100_20200101000000.zip.lxbak"), b"old").unwrap();
+        let backup = ProgramBackup::new(ProgramBackupConfig {
+            directory: Some(dir.path().to_path_buf()),
+            interval: default_interval(),
+            keep: 1,
+            passphrase: Some("correct horse".to_string()),
+        });
+        let client = MockLoxoneClient::new();
+
+        let run = backup.run_once(&client).await.unwrap();
+        assert!(run.created);
+        assert_eq!(run.record.program, "sps_151_20250308183002.zip");
+        assert_eq!(run.pruned, vec!["sps_100_20200101000000.zip.lxbak"]);
+        assert!(!backup.run_once(&client).await.unwrap().created);
+
+        let archive = std::fs::read(dir.path().join(&run.record.file)).unwrap();
+        assert!(!archive.windows(4).any(|w| w == b"mock"));
+        assert_eq!(
+            decrypt_archive(&archive, "correct horse").unwrap(),
+            b"PK\x03\x04mock program"
+        );
+        assert!(decrypt_archive(&archive, "wrong").is_err());
+
+        let resource = backup.restore_resource().await;
+        assert_eq!(resource["backups"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,342 @@
+//! Scene snapshots
+//!
+//! A snapshot records the lights, blinds and room climate of a room under a
+//! name so the room can be brought back to it later, as user-defined scenes
+//! that need no mood configured in Loxone Config. Per device one state is
+//! recorded and restored:
+//!
+//! - lights and blinds: as undone by `execute_macro`
+//!   ([`crate::server::device_macro::restore_state`])
+//! - room controllers: `tempTarget`, restored with `settemp/<°C>`
+//!
+//! Snapshots are kept in a JSON file, set by `scene_snapshots` in the server
+//! configuration (see [`SceneSnapshotConfig`]). The file is one of the
+//! stores versioned by [`crate::storage::migrations`] and replicated to a
+//! standby by [`crate::storage::replication`].
+
+use crate::error::{LoxoneError, Result};
+use crate::server::{climate_schedule, device_macro};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Most snapshots kept
+pub const MAX_SNAPSHOTS: usize = 100;
+
+const TARGET_TEMPERATURE_STATE: &str = "tempTarget";
+
+/// What a recorded device is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotKind {
+    Lighting,
+    Blinds,
+    Climate,
+}
+
+/// One device of a snapshot with its recorded state value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDevice {
+    pub uuid: String,
+    pub name: String,
+    pub control_type: String,
+    pub kind: SnapshotKind,
+    pub value: Value,
+}
+
+impl SnapshotDevice {
+    /// Commands bringing the device back to the recorded value
+    pub fn restore_commands(&self) -> Option<Vec<String>> {
+        match self.kind {
+            SnapshotKind::Climate => {
+                let target = self
+                    .value
+                    .as_f64()
+                    .or_else(|| self.value.as_str()?.trim().parse().ok())?;
+                Some(vec![format!("settemp/{target}")])
+            }
+            _ => device_macro::restore_commands(&self.control_type, &self.value),
+        }
+    }
+}
+
+/// A named snapshot of a room
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneSnapshot {
+    pub name: String,
+    pub room: String,
+    pub room_uuid: String,
+    pub captured_at: DateTime<Utc>,
+    pub devices: Vec<SnapshotDevice>,
+}
+
+/// Kind and recorded state of a control, if snapshots cover it
+pub fn snapshot_state(control: &Value) -> Option<(SnapshotKind, &'static str)> {
+    let control_type = control.get("type").and_then(Value::as_str)?;
+    if climate_schedule::ROOM_CONTROLLER_TYPES.contains(&control_type) {
+        return Some((SnapshotKind::Climate, TARGET_TEMPERATURE_STATE));
+    }
+    let state = device_macro::restore_state(control)?;
+    let kind = if state == "position" && !matches!(control_type, "Dimmer" | "EIBDimmer") {
+        SnapshotKind::Blinds
+    } else {
+        SnapshotKind::Lighting
+    };
+    Some((kind, state))
+}
+
+/// UUID of the state recorded for a control
+pub fn state_uuid(control: &Value) -> Option<String> {
+    let (_, state) = snapshot_state(control)?;
+    control
+        .get("states")
+        .and_then(|states| states.get(state))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Where snapshots are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneSnapshotConfig {
+    /// JSON file of the snapshots (memory only if unset)
+    pub file: Option<PathBuf>,
+}
+
+impl Default for SceneSnapshotConfig {
+    fn default() -> Self {
+        Self {
+            file: Some(
+                std::env::var("LOXONE_SCENE_SNAPSHOTS_FILE")
+                    .ok()
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(default_snapshots_file),
+            ),
+        }
+    }
+}
+
+/// Default location of the snapshots file
+pub fn default_snapshots_file() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("loxone-mcp")
+        .join("scene_snapshots.json")
+}
+
+/// Snapshots by lowercase name
+#[derive(Default)]
+pub struct SceneSnapshots {
+    /// Not persisted when unset
+    file: Option<PathBuf>,
+    snapshots: Mutex<BTreeMap<String, SceneSnapshot>>,
+}
+
+fn key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+impl SceneSnapshots {
+    /// Snapshots from the configured file; an unreadable file is ignored
+    pub fn new(config: &SceneSnapshotConfig) -> Self {
+        let snapshots = config
+            .file
+            .as_deref()
+            .filter(|path| path.exists())
+            .map(|path| {
+                load(path).unwrap_or_else(|e| {
+                    warn!("Ignoring scene snapshots file {}: {e}", path.display());
+                    Vec::new()
+                })
+            })
+            .unwrap_or_default();
+        Self {
+            file: config.file.clone(),
+            snapshots: Mutex::new(
+                snapshots
+                    .into_iter()
+                    .map(|snapshot| (key(&snapshot.name), snapshot))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Snapshots by name, of one room (UUID) when given
+    pub fn list(&self, room_uuid: Option<&str>) -> Vec<SceneSnapshot> {
+        self.snapshots
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|snapshot| room_uuid.is_none_or(|room| snapshot.room_uuid == room))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<SceneSnapshot> {
+        self.snapshots
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key(name))
+            .cloned()
+    }
+
+    /// Store a snapshot, replacing one of the same name; returns the
+    /// replaced snapshot
+    pub fn save(&self, snapshot: SceneSnapshot) -> Result<Option<SceneSnapshot>> {
+        if snapshot.name.trim().is_empty() {
+            return Err(LoxoneError::invalid_input("The snapshot name is empty"));
+        }
+        let mut snapshots = self.snapshots.lock().unwrap_or_else(|e| e.into_inner());
+        let name = key(&snapshot.name);
+        if !snapshots.contains_key(&name) && snapshots.len() >= MAX_SNAPSHOTS {
+            return Err(LoxoneError::invalid_input(format!(
+                "At most {MAX_SNAPSHOTS} snapshots can be kept; delete one first"
+            )));
+        }
+        let mut updated = snapshots.clone();
+        let replaced = updated.insert(name, snapshot);
+        self.persist(&updated)?;
+        *snapshots = updated;
+        Ok(replaced)
+    }
+
+    /// Remove a snapshot; returns it if there was one
+    pub fn delete(&self, name: &str) -> Result<Option<SceneSnapshot>> {
+        let mut snapshots = self.snapshots.lock().unwrap_or_else(|e| e.into_inner());
+        let mut updated = snapshots.clone();
+        let removed = updated.remove(&key(name));
+        if removed.is_some() {
+            self.persist(&updated)?;
+            *snapshots = updated;
+        }
+        Ok(removed)
+    }
+
+    fn persist(&self, snapshots: &BTreeMap<String, SceneSnapshot>) -> Result<()> {
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+        let list: Vec<&SceneSnapshot> = snapshots.values().collect();
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&list)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Snapshots from a snapshots file
+pub fn load(path: &Path) -> Result<Vec<SceneSnapshot>> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use serde_json::json;
+
+    fn snapshot(name: &str) -> SceneSnapshot {
+        SceneSnapshot {
+            name: name.to_string(),
+            room: "Living Room".to_string(),
+            room_uuid: "r-living".to_string(),
+            captured_at: Utc::now(),
+            devices: vec![
+                SnapshotDevice {
+                    uuid: "c-blind".to_string(),
+                    name: "Window".to_string(),
+                    control_type: "Jalousie".to_string(),
+                    kind: SnapshotKind::Blinds,
+                    value: json!(0.75),
+                },
+                SnapshotDevice {
+                    uuid: "c-climate".to_string(),
+                    name: "Climate".to_string(),
+                    control_type: "IRoomControllerV2".to_string(),
+                    kind: SnapshotKind::Climate,
+                    value: json!("21.5"),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_snapshot_states() {
+        let blind = json!({"type": "Jalousie", "states": {"position": "s-pos"}});
+        assert_eq!(
+            snapshot_state(&blind),
+            Some((SnapshotKind::Blinds, "position"))
+        );
+        assert_eq!(state_uuid(&blind).as_deref(), Some("s-pos"));
+        let dimmer = json!({"type": "Dimmer"});
+        assert_eq!(snapshot_state(&dimmer).unwrap().0, SnapshotKind::Lighting);
+        let controller = json!({"type": "IRoomControllerV2", "states": {"tempTarget": "s-t"}});
+        assert_eq!(state_uuid(&controller).as_deref(), Some("s-t"));
+        assert_eq!(snapshot_state(&json!({"type": "Gate"})), None);
+
+        let devices = snapshot("Evening").devices;
+        assert_eq!(
+            devices[0].restore_commands(),
+            Some(vec!["ManualPosition/75".to_string()])
+        );
+        assert_eq!(
+            devices[1].restore_commands(),
+            Some(vec!["settemp/21.5".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_snapshots_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SceneSnapshotConfig {
+            file: Some(dir.path().join("snapshots.json")),
+        };
+        let snapshots = SceneSnapshots::new(&config);
+        assert!(snapshots.save(snapshot("Evening")).unwrap().is_none());
+        assert!(snapshots.save(snapshot("evening ")).unwrap().is_some());
+        snapshots.save(snapshot("Movie")).unwrap();
+        assert!(snapshots.save(snapshot(" ")).is_err());
+
+        let reloaded = SceneSnapshots::new(&config);
+        assert_eq!(reloaded.list(Some("r-living")).len(), 2);
+        assert!(reloaded.list(Some("r-kitchen")).is_empty());
+        assert_eq!(reloaded.get("EVENING").unwrap().devices.len(), 2);
+
+        assert!(reloaded.delete("movie").unwrap().is_some());
+        assert!(reloaded.delete("movie").unwrap().is_none());
+        assert_eq!(SceneSnapshots::new(&config).list(None).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_capture_and_restore_through_the_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("snapshots.json");
+        let mut config = mock::isolated_config();
+        config.scene_snapshots.file = Some(file.clone());
+        let fixture = mock::TestServer::with_config(mock::sample_house(), config).await;
+        let window = mock::device_uuid("Kitchen", "Window");
+        let position = mock::state_uuid(&window, "position");
+        fixture.client.set_state(&position, json!(0.4));
+
+        let result = fixture
+            .capture_scene_snapshot("Kitchen".into(), "Evening".into())
+            .await;
+        assert_eq!(mock::assert_tool_ok(result)["status"], "captured");
+        assert_eq!(load(&file).unwrap()[0].name, "Evening");
+
+        fixture.client.set_state(&position, json!(1.0));
+        let result = fixture.restore_scene_snapshot("evening".into()).await;
+        assert_eq!(mock::assert_tool_ok(result)["snapshot"], "Evening");
+        mock::assert_command_sent(&fixture.client, &window, "ManualPosition/40");
+    }
+}
@@ -100,6 +100,9 @@ pub enum SensorType {
         unit: SoundUnit,
     },
 
+    // Text displays (TextState blocks and text virtual inputs)
+    TextStatus,
+
     // Unknown with learning metadata
     Unknown {
         device_type: String,
@@ -179,6 +182,17 @@ pub enum AirQualityScale {
     CO2PPM, // CO2 parts per million
 }
 
+/// Whether a control type carries free-form status text
+pub fn is_text_control_type(device_type: &str) -> bool {
+    device_type.eq_ignore_ascii_case("TextState") || device_type.eq_ignore_ascii_case("TextInput")
+}
+
+/// Whether a control type is a ventilation or air handling unit
+pub fn is_ventilation_control_type(device_type: &str) -> bool {
+    device_type.eq_ignore_ascii_case("Ventilation")
+        || device_type.eq_ignore_ascii_case("AirHandling")
+}
+
 /// Detection rule for sensor type identification
 pub struct SensorDetectionRule {
     pub name_patterns: Vec<String>,
@@ -219,6 +233,20 @@ impl SensorTypeRegistry {
             return Ok(Some(sensor_type.clone()));
         }
 
+        // Text controls are identified by block type alone; their names are
+        // free-form status labels that would otherwise match unrelated rules
+        if is_text_control_type(&device.device_type) {
+            return Ok(Some(SensorType::TextStatus));
+        }
+
+        // Ventilation units measure the indoor air they control, whatever
+        // they are named
+        if is_ventilation_control_type(&device.device_type) {
+            return Ok(Some(SensorType::AirQuality {
+                scale: AirQualityScale::CO2PPM,
+            }));
+        }
+
         // Apply detection rules
         let mut best_match: Option<(SensorType, f32)> = None;
 
@@ -0,0 +1,352 @@
+//! Structure snapshots and diffs for reviewing configuration changes
+//!
+//! Every program backup also stores an encrypted snapshot of the structure
+//! file (`LoxAPP3.json`) under `<backup dir>/structure/`, named after the
+//! program it belongs to. After a Loxone Config deployment the current
+//! structure can be compared with a snapshot to review what changed:
+//! added, removed, renamed, moved and retyped controls as well as room
+//! changes.
+//!
+//! Controls are matched by UUID, which Loxone Config keeps stable across
+//! edits. A control that was deleted and re-created shows up as a removal
+//! plus an addition; pairs with the same name and type are reported as
+//! replaced.
+
+use crate::client::LoxoneStructure;
+use crate::error::{LoxoneError, Result};
+use crate::services::program_backup::{ARCHIVE_EXTENSION, decrypt_archive, encrypt_archive};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Subdirectory of the backup directory holding structure snapshots
+pub const SNAPSHOT_DIR: &str = "structure";
+
+const SNAPSHOT_SUFFIX: &str = ".structure";
+
+/// A control present in only one of the structures
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ControlRef {
+    pub uuid: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub control_type: String,
+    pub room: Option<String>,
+}
+
+/// A value that differs between the structures
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub uuid: String,
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// A control deleted and re-created under a new UUID
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Replacement {
+    pub name: String,
+    pub old_uuid: String,
+    pub new_uuid: String,
+}
+
+/// Differences between two structure files
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StructureDiff {
+    pub added: Vec<ControlRef>,
+    pub removed: Vec<ControlRef>,
+    pub replaced: Vec<Replacement>,
+    pub renamed: Vec<Change>,
+    /// Controls assigned to another room
+    pub moved: Vec<Change>,
+    pub retyped: Vec<Change>,
+    pub rooms_added: Vec<String>,
+    pub rooms_removed: Vec<String>,
+    pub rooms_renamed: Vec<Change>,
+}
+
+impl StructureDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.replaced.is_empty()
+            && self.renamed.is_empty()
+            && self.moved.is_empty()
+            && self.retyped.is_empty()
+            && self.rooms_added.is_empty()
+            && self.rooms_removed.is_empty()
+            && self.rooms_renamed.is_empty()
+    }
+
+    /// One line per kind of change, for a quick review
+    pub fn summary(&self) -> Vec<String> {
+        let counts = [
+            (self.added.len(), "controls added"),
+            (self.removed.len(), "controls removed"),
+            (self.replaced.len(), "controls re-created"),
+            (self.renamed.len(), "controls renamed"),
+            (self.moved.len(), "controls moved to another room"),
+            (self.retyped.len(), "controls changed type"),
+            (self.rooms_added.len(), "rooms added"),
+            (self.rooms_removed.len(), "rooms removed"),
+            (self.rooms_renamed.len(), "rooms renamed"),
+        ];
+        counts
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, what)| format!("{count} {what}"))
+            .collect()
+    }
+}
+
+fn str_field(value: &Value, field: &str) -> Option<String> {
+    value.get(field).and_then(Value::as_str).map(str::to_string)
+}
+
+fn room_names(structure: &LoxoneStructure) -> HashMap<&str, String> {
+    structure
+        .rooms
+        .iter()
+        .map(|(uuid, room)| {
+            let name = str_field(room, "name").unwrap_or_else(|| uuid.clone());
+            (uuid.as_str(), name)
+        })
+        .collect()
+}
+
+fn control_ref(uuid: &str, control: &Value, rooms: &HashMap<&str, String>) -> ControlRef {
+    ControlRef {
+        uuid: uuid.to_string(),
+        name: str_field(control, "name").unwrap_or_default(),
+        control_type: str_field(control, "type").unwrap_or_default(),
+        room: control
+            .get("room")
+            .and_then(Value::as_str)
+            .map(|room| rooms.get(room).cloned().unwrap_or_else(|| room.to_string())),
+    }
+}
+
+/// Compare two structure files
+pub fn diff_structures(old: &LoxoneStructure, new: &LoxoneStructure) -> StructureDiff {
+    let old_rooms = room_names(old);
+    let new_rooms = room_names(new);
+    let mut diff = StructureDiff::default();
+
+    for (uuid, control) in &new.controls {
+        let current = control_ref(uuid, control, &new_rooms);
+        let Some(previous) = old.controls.get(uuid) else {
+            diff.added.push(current);
+            continue;
+        };
+        let previous = control_ref(uuid, previous, &old_rooms);
+        let change = |from: &Option<String>, to: &Option<String>| Change {
+            uuid: uuid.clone(),
+            name: current.name.clone(),
+            from: from.clone(),
+            to: to.clone(),
+        };
+        if previous.name != current.name {
+            diff.renamed.push(change(
+                &Some(previous.name.clone()),
+                &Some(current.name.clone()),
+            ));
+        }
+        if previous.room != current.room {
+            diff.moved.push(change(&previous.room, &current.room));
+        }
+        if previous.control_type != current.control_type {
+            diff.retyped.push(change(
+                &Some(previous.control_type.clone()),
+                &Some(current.control_type.clone()),
+            ));
+        }
+    }
+    for (uuid, control) in &old.controls {
+        if !new.controls.contains_key(uuid) {
+            diff.removed.push(control_ref(uuid, control, &old_rooms));
+        }
+    }
+
+    // Same name and type under a new UUID: deleted and re-created
+    diff.added.retain(|added| {
+        let Some(index) = diff.removed.iter().position(|removed| {
+            removed.name == added.name && removed.control_type == added.control_type
+        }) else {
+            return true;
+        };
+        let removed = diff.removed.remove(index);
+        diff.replaced.push(Replacement {
+            name: added.name.clone(),
+            old_uuid: removed.uuid,
+            new_uuid: added.uuid.clone(),
+        });
+        false
+    });
+
+    for (uuid, name) in &new_rooms {
+        match old_rooms.get(uuid) {
+            None => diff.rooms_added.push(name.clone()),
+            Some(previous) if previous != name => diff.rooms_renamed.push(Change {
+                uuid: uuid.to_string(),
+                name: name.clone(),
+                from: Some(previous.clone()),
+                to: Some(name.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (uuid, name) in &old_rooms {
+        if !new_rooms.contains_key(uuid) {
+            diff.rooms_removed.push(name.clone());
+        }
+    }
+
+    diff.added.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.removed.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.replaced.sort_by(|a, b| a.name.cmp(&b.name));
+    for changes in [
+        &mut diff.renamed,
+        &mut diff.moved,
+        &mut diff.retyped,
+        &mut diff.rooms_renamed,
+    ] {
+        changes.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    diff.rooms_added.sort();
+    diff.rooms_removed.sort();
+    diff
+}
+
+/// A saved structure snapshot
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotRecord {
+    /// Program backup the snapshot belongs to
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn snapshot_path(backup_dir: &Path, name: &str) -> PathBuf {
+    backup_dir
+        .join(SNAPSHOT_DIR)
+        .join(format!("{name}{SNAPSHOT_SUFFIX}.{ARCHIVE_EXTENSION}"))
+}
+
+/// Encrypt and store a structure snapshot next to a program backup
+pub async fn save_snapshot(
+    backup_dir: &Path,
+    name: &str,
+    structure: &LoxoneStructure,
+    passphrase: &str,
+) -> Result<()> {
+    let json = serde_json::to_vec(structure)?;
+    let archive = encrypt_archive(&json, passphrase)?;
+    let path = snapshot_path(backup_dir, name);
+    tokio::fs::create_dir_all(backup_dir.join(SNAPSHOT_DIR)).await?;
+    tokio::fs::write(&path, archive).await?;
+    Ok(())
+}
+
+/// Load and decrypt a snapshot by name
+pub async fn load_snapshot(
+    backup_dir: &Path,
+    name: &str,
+    passphrase: &str,
+) -> Result<LoxoneStructure> {
+    let path = snapshot_path(backup_dir, name);
+    let archive = tokio::fs::read(&path)
+        .await
+        .map_err(|_| LoxoneError::not_found(format!("No structure snapshot '{name}'")))?;
+    let json = decrypt_archive(&archive, passphrase)?;
+    serde_json::from_slice(&json)
+        .map_err(|e| LoxoneError::parsing_error(format!("Invalid structure snapshot: {e}")))
+}
+
+/// Saved snapshots, newest first
+pub fn list_snapshots(backup_dir: &Path) -> Result<Vec<SnapshotRecord>> {
+    let directory = backup_dir.join(SNAPSHOT_DIR);
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+    let suffix = format!("{SNAPSHOT_SUFFIX}.{ARCHIVE_EXTENSION}");
+    let mut records = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let file = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = file.strip_suffix(&suffix) else {
+            continue;
+        };
+        records.push(SnapshotRecord {
+            name: name.to_string(),
+            created_at: entry
+                .metadata()?
+                .modified()
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_else(|_| Utc::now()),
+        });
+    }
+    // Program names embed their save time, so they sort chronologically
+    records.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(records)
+}
+
+/// Delete the snapshot belonging to a pruned program backup
+pub fn remove_snapshot(backup_dir: &Path, name: &str) -> Result<()> {
+    let path = snapshot_path(backup_dir, name);
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn structure(controls: Value, rooms: Value) -> LoxoneStructure {
+        serde_json::from_value(json!({
+            "lastModified": "2025-03-08 18:30:02",
+            "controls": controls,
+            "rooms": rooms,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_after_deployment() {
+        let old = structure(
+            json!({
+                "c1": {"name": "Ceiling", "type": "Dimmer", "room": "r1"},
+                "c2": {"name": "Blind", "type": "Jalousie", "room": "r1"},
+                "c3": {"name": "Heater", "type": "IRoomController", "room": "r2"},
+                "c4": {"name": "Pump", "type": "Switch", "room": "r2"},
+            }),
+            json!({"r1": {"name": "Living"}, "r2": {"name": "Bath"}}),
+        );
+        let new = structure(
+            json!({
+                "c1": {"name": "Ceiling Light", "type": "LightControllerV2", "room": "r1"},
+                "c2": {"name": "Blind", "type": "Jalousie", "room": "r3"},
+                "c5": {"name": "Pump", "type": "Switch", "room": "r2"},
+                "c6": {"name": "Fan", "type": "Switch", "room": "r2"},
+            }),
+            json!({"r1": {"name": "Living Room"}, "r2": {"name": "Bath"}, "r3": {"name": "Office"}}),
+        );
+
+        let diff = diff_structures(&old, &new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "Fan");
+        assert_eq!(diff.removed[0].name, "Heater");
+        assert_eq!(diff.replaced[0].new_uuid, "c5");
+        assert_eq!(diff.renamed[0].to.as_deref(), Some("Ceiling Light"));
+        assert_eq!(diff.retyped[0].from.as_deref(), Some("Dimmer"));
+        assert_eq!(diff.moved[0].to.as_deref(), Some("Office"));
+        assert_eq!(diff.rooms_added, ["Office"]);
+        assert_eq!(diff.rooms_renamed[0].from.as_deref(), Some("Living"));
+        assert_eq!(diff.summary().len(), 8);
+        assert!(diff_structures(&new, &new).is_empty());
+    }
+}
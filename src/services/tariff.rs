@@ -0,0 +1,322 @@
+//! Tariff-aware planning of heavy loads
+//!
+//! A tariff profile gives the electricity price per time of day as
+//! `HH:MM-HH:MM@price` entries that together cover the whole day; an entry
+//! may wrap past midnight (`22:00-06:00@0.18`). Combined with the typical
+//! solar production per hour of day, averaged from the PV meter's
+//! statistics, the planner ranks start times for a load of known power and
+//! duration by the cost of the energy it has to draw from the grid.
+
+use crate::client::statistics::StatisticPoint;
+use crate::error::{LoxoneError, Result};
+use chrono::{Duration, NaiveDateTime, Timelike};
+use serde::Serialize;
+
+/// Minutes per day
+const DAY_MINUTES: u32 = 24 * 60;
+
+/// Spacing of candidate start times and resolution of the cost estimate
+pub const STEP_MINUTES: u32 = 15;
+
+/// Longest planning window in hours
+pub const MAX_WINDOW_HOURS: u32 = 48;
+
+/// Price of one time-of-day period
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TariffSlot {
+    /// Start in minutes since midnight
+    pub from: u32,
+    /// End in minutes since midnight
+    pub to: u32,
+    /// Price per kWh
+    pub price: f64,
+}
+
+/// Electricity prices over a day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TariffProfile {
+    /// Non-overlapping slots covering the day, sorted by start
+    pub slots: Vec<TariffSlot>,
+}
+
+impl TariffProfile {
+    /// Parse `HH:MM-HH:MM@price` entries covering the whole day
+    pub fn parse(entries: &[String]) -> Result<Self> {
+        let mut slots = Vec::new();
+        for entry in entries {
+            let invalid = || {
+                LoxoneError::invalid_input(format!(
+                    "Invalid tariff entry '{entry}'. Use HH:MM-HH:MM@price"
+                ))
+            };
+            let (period, price) = entry.trim().split_once('@').ok_or_else(invalid)?;
+            let price: f64 = price.trim().parse().map_err(|_| invalid())?;
+            if !price.is_finite() || price < 0.0 {
+                return Err(invalid());
+            }
+            let (from, to) = period.split_once('-').ok_or_else(invalid)?;
+            let (from, to) = (
+                parse_time(from).ok_or_else(invalid)?,
+                parse_time(to).ok_or_else(invalid)?,
+            );
+            if from == to % DAY_MINUTES {
+                // A slot of a whole day
+                slots.push(TariffSlot {
+                    from: 0,
+                    to: DAY_MINUTES,
+                    price,
+                });
+            } else if from < to {
+                slots.push(TariffSlot { from, to, price });
+            } else {
+                slots.push(TariffSlot {
+                    from,
+                    to: DAY_MINUTES,
+                    price,
+                });
+                slots.push(TariffSlot { from: 0, to, price });
+            }
+        }
+
+        slots.retain(|slot| slot.from < slot.to);
+        slots.sort_by_key(|slot| slot.from);
+        let mut covered = 0;
+        for slot in &slots {
+            if slot.from != covered {
+                let (problem, at) = if slot.from > covered {
+                    ("has no price", covered)
+                } else {
+                    ("has overlapping prices", slot.from)
+                };
+                return Err(LoxoneError::invalid_input(format!(
+                    "Tariff {problem} at {}",
+                    format_minutes(at)
+                )));
+            }
+            covered = slot.to;
+        }
+        if covered != DAY_MINUTES {
+            return Err(LoxoneError::invalid_input(format!(
+                "Tariff has no price at {}",
+                format_minutes(covered)
+            )));
+        }
+        Ok(Self { slots })
+    }
+
+    /// Price at `minute` since midnight
+    pub fn price_at(&self, minute: u32) -> f64 {
+        let minute = minute % DAY_MINUTES;
+        self.slots
+            .iter()
+            .find(|slot| slot.from <= minute && minute < slot.to)
+            .map_or(0.0, |slot| slot.price)
+    }
+}
+
+/// `HH:MM` (24:00 allowed) in minutes since midnight
+fn parse_time(time: &str) -> Option<u32> {
+    let (h, m) = time.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60 || h == 24 && m == 0).then_some(h * 60 + m)
+}
+
+fn format_minutes(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Typical solar production per hour of day in kW
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SolarProfile {
+    /// Average production per hour, `None` for hours without samples
+    pub hourly_kw: Vec<Option<f64>>,
+}
+
+impl SolarProfile {
+    /// Average the first value of each sample per hour of day
+    pub fn from_points(points: &[StatisticPoint]) -> Self {
+        let mut sums = [(0.0, 0usize); 24];
+        for point in points {
+            if let Some(value) = point.values.first().filter(|v| v.is_finite()) {
+                let hour = point.time.hour() as usize;
+                sums[hour].0 += value.max(0.0);
+                sums[hour].1 += 1;
+            }
+        }
+        Self {
+            hourly_kw: sums
+                .iter()
+                .map(|(sum, count)| (*count > 0).then(|| sum / *count as f64))
+                .collect(),
+        }
+    }
+
+    /// Expected production at `hour` of day
+    pub fn production_at(&self, hour: u32) -> f64 {
+        self.hourly_kw
+            .get(hour as usize)
+            .copied()
+            .flatten()
+            .unwrap_or(0.0)
+    }
+}
+
+/// Estimated cost of starting the load at one time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StartOption {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// Energy drawn from the grid in kWh
+    pub grid_kwh: f64,
+    /// Energy covered by expected solar production in kWh
+    pub solar_kwh: f64,
+    pub cost: f64,
+}
+
+/// Rank start times within `window_hours` from `now` by cost, cheapest
+/// first; ties go to the earlier start
+pub fn plan(
+    tariff: &TariffProfile,
+    solar: &SolarProfile,
+    now: NaiveDateTime,
+    duration_minutes: u32,
+    power_kw: f64,
+    window_hours: u32,
+) -> Result<Vec<StartOption>> {
+    if duration_minutes == 0 || duration_minutes > DAY_MINUTES {
+        return Err(LoxoneError::invalid_input(
+            "Duration must be between 1 and 1440 minutes",
+        ));
+    }
+    if !power_kw.is_finite() || power_kw <= 0.0 {
+        return Err(LoxoneError::invalid_input(
+            "Power must be a positive number of kW",
+        ));
+    }
+    if window_hours == 0 || window_hours > MAX_WINDOW_HOURS {
+        return Err(LoxoneError::invalid_input(format!(
+            "Planning window must be between 1 and {MAX_WINDOW_HOURS} hours"
+        )));
+    }
+
+    // First candidate: the next full step
+    let now = now
+        .with_second(0)
+        .unwrap_or(now)
+        .with_nanosecond(0)
+        .unwrap_or(now);
+    let offset = (STEP_MINUTES - now.minute() % STEP_MINUTES) % STEP_MINUTES;
+    let first = now + Duration::minutes(i64::from(offset));
+    let latest_end = now + Duration::hours(i64::from(window_hours));
+
+    let mut options = Vec::new();
+    let mut start = first;
+    while start + Duration::minutes(i64::from(duration_minutes)) <= latest_end {
+        options.push(estimate(tariff, solar, start, duration_minutes, power_kw));
+        start += Duration::minutes(i64::from(STEP_MINUTES));
+    }
+    if options.is_empty() {
+        return Err(LoxoneError::invalid_input(
+            "Planning window is shorter than the load's duration",
+        ));
+    }
+    options.sort_by(|a, b| a.cost.total_cmp(&b.cost).then(a.start.cmp(&b.start)));
+    Ok(options)
+}
+
+/// Cost of running the load from `start`, in steps of [`STEP_MINUTES`]
+pub fn estimate(
+    tariff: &TariffProfile,
+    solar: &SolarProfile,
+    start: NaiveDateTime,
+    duration_minutes: u32,
+    power_kw: f64,
+) -> StartOption {
+    let (mut grid_kwh, mut solar_kwh, mut cost) = (0.0, 0.0, 0.0);
+    let mut elapsed = 0;
+    while elapsed < duration_minutes {
+        let step = STEP_MINUTES.min(duration_minutes - elapsed);
+        let time = start + Duration::minutes(i64::from(elapsed));
+        let hours = f64::from(step) / 60.0;
+        let from_solar = power_kw.min(solar.production_at(time.hour()));
+        let from_grid = power_kw - from_solar;
+        grid_kwh += from_grid * hours;
+        solar_kwh += from_solar * hours;
+        cost += from_grid * hours * tariff.price_at(time.hour() * 60 + time.minute());
+        elapsed += step;
+    }
+    let round = |value: f64| (value * 1000.0).round() / 1000.0;
+    StartOption {
+        start,
+        end: start + Duration::minutes(i64::from(duration_minutes)),
+        grid_kwh: round(grid_kwh),
+        solar_kwh: round(solar_kwh),
+        cost: round(cost),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn tariff(entries: &[&str]) -> Result<TariffProfile> {
+        let entries: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+        TariffProfile::parse(&entries)
+    }
+
+    #[test]
+    fn test_parse_tariff() {
+        let profile = tariff(&["22:00-06:00@0.18", "06:00-22:00@0.32"]).unwrap();
+        assert_eq!(profile.slots.len(), 3);
+        assert_eq!(profile.price_at(23 * 60), 0.18);
+        assert_eq!(profile.price_at(5 * 60 + 59), 0.18);
+        assert_eq!(profile.price_at(12 * 60), 0.32);
+        assert_eq!(tariff(&["00:00-24:00@0.3"]).unwrap().price_at(0), 0.3);
+
+        let gap = tariff(&["00:00-06:00@0.18", "07:00-24:00@0.3"]).unwrap_err();
+        assert!(gap.to_string().contains("no price at 06:00"));
+        let overlap = tariff(&["00:00-12:00@0.18", "11:00-24:00@0.3"]).unwrap_err();
+        assert!(overlap.to_string().contains("overlapping prices at 11:00"));
+        assert!(tariff(&["00:00-24:00"]).is_err());
+        assert!(tariff(&["00:00-24:00@-1"]).is_err());
+    }
+
+    #[test]
+    fn test_plan_prefers_cheap_and_sunny_hours() {
+        let profile = tariff(&["22:00-06:00@0.20", "06:00-22:00@0.40"]).unwrap();
+        let night = plan(
+            &profile,
+            &SolarProfile::default(),
+            time("2026-10-16 17:07:30"),
+            120,
+            2.0,
+            24,
+        )
+        .unwrap();
+        assert_eq!(night[0].start, time("2026-10-16 22:00:00"));
+        assert_eq!(night[0].cost, 0.8);
+        assert_eq!(night.last().unwrap().cost, 1.6);
+
+        // 3 kW of sun around noon beats the night tariff
+        let sunny: Vec<StatisticPoint> = (10..15)
+            .map(|hour| StatisticPoint {
+                time: time(&format!("2026-10-15 {hour:02}:00:00")),
+                values: vec![3.0],
+            })
+            .collect();
+        let solar = SolarProfile::from_points(&sunny);
+        assert_eq!(solar.production_at(11), 3.0);
+        assert_eq!(solar.production_at(20), 0.0);
+        let day = plan(&profile, &solar, time("2026-10-16 06:00:00"), 120, 2.0, 24).unwrap();
+        assert_eq!(day[0].start, time("2026-10-16 10:00:00"));
+        assert_eq!(day[0].cost, 0.0);
+        assert_eq!(day[0].solar_kwh, 4.0);
+
+        assert!(plan(&profile, &solar, time("2026-10-16 06:00:00"), 120, 2.0, 1).is_err());
+        assert!(plan(&profile, &solar, time("2026-10-16 06:00:00"), 0, 2.0, 24).is_err());
+    }
+}
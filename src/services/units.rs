@@ -0,0 +1,147 @@
+//! Unit conversion
+//!
+//! The Miniserver reports metric values: temperatures in °C, wind speeds in
+//! m/s or km/h, air pressure in hPa, rain in mm and energy in Wh, kWh or
+//! MWh. [`crate::services::UnifiedValueResolver`] can return them in either
+//! unit system, chosen per tool call, by `mcp.tools.units` or by
+//! `LOXONE_UNITS`:
+//!
+//! | Quantity | Metric | Imperial |
+//! |----------|--------|----------|
+//! | Temperature | °C | °F |
+//! | Wind speed | m/s, km/h | mph |
+//! | Air pressure | hPa | inHg |
+//! | Rain | mm | in |
+//! | Energy | kWh | kWh |
+//!
+//! Energy is given in kWh in both systems. Units not listed, such as %, W or
+//! ppm, are left as they are.
+
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+
+const MPH_PER_MS: f64 = 2.236_936;
+const KMH_PER_MS: f64 = 3.6;
+const INHG_PER_HPA: f64 = 0.029_53;
+const MM_PER_INCH: f64 = 25.4;
+
+/// Unit system of returned values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl UnitSystem {
+    /// Parse `metric` / `imperial` (also `si` and `us`)
+    pub fn parse(system: &str) -> Result<Self> {
+        match system.trim().to_lowercase().as_str() {
+            "metric" | "si" => Ok(Self::Metric),
+            "imperial" | "us" => Ok(Self::Imperial),
+            other => Err(LoxoneError::invalid_input(format!(
+                "Unknown unit system '{other}'. Use: metric, imperial"
+            ))),
+        }
+    }
+
+    /// System set by `LOXONE_UNITS`, metric if unset or invalid
+    pub fn from_env() -> Self {
+        std::env::var("LOXONE_UNITS")
+            .ok()
+            .and_then(|system| Self::parse(&system).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// `value` in `unit` expressed in `system`, or `None` when the unit is not
+/// converted
+pub fn convert(value: f64, unit: &str, system: UnitSystem) -> Option<(f64, &'static str)> {
+    let converted = match (unit.trim(), system) {
+        ("°C", UnitSystem::Imperial) => (value * 9.0 / 5.0 + 32.0, "°F"),
+        ("°F", UnitSystem::Metric) => ((value - 32.0) * 5.0 / 9.0, "°C"),
+        ("m/s", UnitSystem::Imperial) => (value * MPH_PER_MS, "mph"),
+        ("km/h", UnitSystem::Imperial) => (value / KMH_PER_MS * MPH_PER_MS, "mph"),
+        ("mph", UnitSystem::Metric) => (value / MPH_PER_MS * KMH_PER_MS, "km/h"),
+        ("hPa", UnitSystem::Imperial) => (value * INHG_PER_HPA, "inHg"),
+        ("inHg", UnitSystem::Metric) => (value / INHG_PER_HPA, "hPa"),
+        ("mm", UnitSystem::Imperial) => (value / MM_PER_INCH, "in"),
+        ("in", UnitSystem::Metric) => (value * MM_PER_INCH, "mm"),
+        ("Wh", _) => (value / 1000.0, "kWh"),
+        ("MWh", _) => (value * 1000.0, "kWh"),
+        _ => return None,
+    };
+    Some(converted)
+}
+
+/// Display string of `value` in `unit`, e.g. `72.5°F` or `12.4 mph`
+pub fn format_value(value: f64, unit: &str) -> String {
+    match unit {
+        "°C" | "°F" => format!("{value:.1}{unit}"),
+        "inHg" | "in" | "kWh" => format!("{value:.2} {unit}"),
+        "%" => format!("{}%", value.round() as i64),
+        "" => format!("{value:.1}"),
+        _ => format!("{value:.1} {unit}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::value_resolution::{ResolvedValue, ValidationStatus, ValueSource};
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn close(actual: Option<(f64, &str)>, expected: f64, unit: &str) {
+        let (value, actual_unit) = actual.unwrap();
+        assert!((value - expected).abs() < 0.01, "{value} != {expected}");
+        assert_eq!(actual_unit, unit);
+    }
+
+    #[test]
+    fn test_conversions() {
+        close(convert(22.5, "°C", UnitSystem::Imperial), 72.5, "°F");
+        close(convert(72.5, "°F", UnitSystem::Metric), 22.5, "°C");
+        close(convert(10.0, "m/s", UnitSystem::Imperial), 22.37, "mph");
+        close(convert(36.0, "km/h", UnitSystem::Imperial), 22.37, "mph");
+        close(convert(1013.25, "hPa", UnitSystem::Imperial), 29.92, "inHg");
+        close(convert(25.4, "mm", UnitSystem::Imperial), 1.0, "in");
+        close(convert(1500.0, "Wh", UnitSystem::Metric), 1.5, "kWh");
+        assert_eq!(convert(22.5, "°C", UnitSystem::Metric), None);
+        assert_eq!(convert(40.0, "%", UnitSystem::Imperial), None);
+
+        assert_eq!(
+            UnitSystem::parse(" Imperial").unwrap(),
+            UnitSystem::Imperial
+        );
+        assert!(UnitSystem::parse("nautical").is_err());
+        assert_eq!(format_value(72.46, "°F"), "72.5°F");
+        assert_eq!(format_value(3.254, "kWh"), "3.25 kWh");
+    }
+
+    #[test]
+    fn test_resolved_value_in_units() {
+        let resolved = ResolvedValue {
+            uuid: "t-1".to_string(),
+            device_name: "Outdoor".to_string(),
+            raw_value: json!("22.5°"),
+            numeric_value: Some(22.5),
+            formatted_value: "22.5°C".to_string(),
+            unit: Some("°C".to_string()),
+            sensor_type: None,
+            room: None,
+            source: ValueSource::RealTimeApi,
+            timestamp: Utc::now(),
+            confidence: 0.9,
+            validation_status: ValidationStatus::Valid,
+        };
+        let imperial = resolved.clone().in_units(UnitSystem::Imperial);
+        assert_eq!(imperial.unit.as_deref(), Some("°F"));
+        assert_eq!(imperial.formatted_value, "72.5°F");
+        assert_eq!(imperial.raw_value, json!("22.5°"));
+
+        let metric = resolved.in_units(UnitSystem::Metric);
+        assert_eq!(metric.formatted_value, "22.5°C");
+    }
+}
@@ -89,6 +89,10 @@ impl ValueParserRegistry {
             .insert("WindSpeed".to_string(), Arc::new(WindSpeedParser));
         self.parsers
             .insert("Rainfall".to_string(), Arc::new(RainfallParser));
+
+        // Text displays
+        self.parsers
+            .insert("TextStatus".to_string(), Arc::new(TextStateParser));
     }
 
     fn sensor_type_to_key(&self, sensor_type: &SensorType) -> String {
@@ -669,8 +673,79 @@ impl ValueParser for RainfallParser {
     }
 }
 
+/// TextState / text input parser
+///
+/// Text controls report free-form strings, so no numeric value is produced.
+pub struct TextStateParser;
+
+impl ValueParser for TextStateParser {
+    fn parse(&self, raw_value: &Value) -> Result<ParsedValue> {
+        let text = extract_text(raw_value)
+            .ok_or_else(|| LoxoneError::parsing_error("Unable to parse status text"))?;
+
+        let mut metadata = HashMap::new();
+        if let Some(icon) = raw_value
+            .get("textAndIcon")
+            .and_then(|v| v.get("icon"))
+            .and_then(|v| v.as_str())
+        {
+            metadata.insert("icon".to_string(), icon.to_string());
+        }
+
+        Ok(ParsedValue {
+            numeric_value: None,
+            formatted_value: text,
+            unit: None,
+            metadata,
+        })
+    }
+
+    fn confidence(&self, raw_value: &Value) -> f32 {
+        if raw_value.get("LL").and_then(|v| v.get("value")).is_some()
+            || raw_value.get("textAndIcon").is_some()
+        {
+            0.9
+        } else if raw_value.is_string() {
+            0.8
+        } else {
+            0.0
+        }
+    }
+}
+
 // Helper functions for value extraction
 
+fn extract_text(raw_value: &Value) -> Option<String> {
+    // LL.value from /jdev/sps/io/{uuid}/state
+    if let Some(value) = raw_value.get("LL").and_then(|v| v.get("value")) {
+        return value_to_text(value);
+    }
+
+    // textAndIcon state of a TextState block
+    if let Some(text_and_icon) = raw_value.get("textAndIcon") {
+        return text_and_icon
+            .get("text")
+            .and_then(value_to_text)
+            .or_else(|| value_to_text(text_and_icon));
+    }
+
+    // text state of a text input
+    if let Some(text) = raw_value.get("text") {
+        return value_to_text(text);
+    }
+
+    value_to_text(raw_value)
+}
+
+fn value_to_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.trim().to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
 fn extract_temperature(value_str: &str) -> Option<f64> {
     value_str
         .replace("°C", "")
@@ -752,3 +827,36 @@ fn extract_percentage_or_number(raw_value: &Value) -> Option<f64> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_text_state_parser() {
+        let registry = ValueParserRegistry::new();
+        let parser = registry.get_parser(&SensorType::TextStatus).unwrap();
+
+        let parsed = parser
+            .parse(&json!({"LL": {"value": " Washing machine done ", "Code": "200"}}))
+            .unwrap();
+        assert_eq!(parsed.formatted_value, "Washing machine done");
+        assert!(parsed.numeric_value.is_none());
+
+        let parsed = parser
+            .parse(&json!({"textAndIcon": {"text": "Alarm armed", "icon": "lock.svg"}}))
+            .unwrap();
+        assert_eq!(parsed.formatted_value, "Alarm armed");
+        assert_eq!(
+            parsed.metadata.get("icon").map(String::as_str),
+            Some("lock.svg")
+        );
+
+        assert_eq!(
+            parser.parse(&json!("Idle")).unwrap().formatted_value,
+            "Idle"
+        );
+        assert!(parser.parse(&json!(null)).is_err());
+    }
+}
@@ -7,6 +7,7 @@ use crate::client::LoxoneClient;
 use crate::error::{LoxoneError, Result};
 use crate::services::cache_manager::{CacheConfig, EnhancedCacheManager, PrefetchHandler};
 use crate::services::sensor_registry::{SensorType, SensorTypeRegistry};
+use crate::services::units::{self, UnitSystem};
 use crate::services::value_parsers::{ParsedValue, ValueParserRegistry};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -20,6 +21,8 @@ pub struct UnifiedValueResolver {
     enhanced_cache: Arc<EnhancedCacheManager>,
     sensor_registry: Arc<SensorTypeRegistry>,
     parsers: Arc<ValueParserRegistry>,
+    /// Unit system values are converted to unless a call asks for another
+    unit_system: UnitSystem,
 }
 
 /// Resolved device value with comprehensive metadata
@@ -75,6 +78,7 @@ impl UnifiedValueResolver {
             )),
             sensor_registry,
             parsers: Arc::new(ValueParserRegistry::new()),
+            unit_system: UnitSystem::from_env(),
         }
     }
 
@@ -98,9 +102,43 @@ impl UnifiedValueResolver {
             )),
             sensor_registry,
             parsers: Arc::new(ValueParserRegistry::new()),
+            unit_system: UnitSystem::from_env(),
         }
     }
 
+    /// Resolver reading through `client`, with this resolver's sensor
+    /// registry and unit system but a cache of its own
+    pub fn for_client(&self, client: Arc<dyn LoxoneClient>) -> Self {
+        Self::new(client, self.sensor_registry.clone()).with_unit_system(self.unit_system)
+    }
+
+    /// Convert values to `system` by default (instead of `LOXONE_UNITS`)
+    pub fn with_unit_system(mut self, system: UnitSystem) -> Self {
+        self.unit_system = system;
+        self
+    }
+
+    /// Unit system values are converted to by default
+    pub fn unit_system(&self) -> UnitSystem {
+        self.unit_system
+    }
+
+    /// Resolve values like [`Self::resolve_batch_values`], converted to
+    /// `system` or else the configured unit system
+    pub async fn resolve_batch_values_in_units(
+        &self,
+        uuids: &[String],
+        system: Option<UnitSystem>,
+    ) -> Result<HashMap<String, ResolvedValue>> {
+        let system = system.unwrap_or(self.unit_system);
+        Ok(self
+            .resolve_batch_values(uuids)
+            .await?
+            .into_iter()
+            .map(|(uuid, value)| (uuid, value.in_units(system)))
+            .collect())
+    }
+
     /// Resolve value for a single device
     pub async fn resolve_device_value(&self, uuid: &str) -> Result<ResolvedValue> {
         // Use enhanced cache for single device resolution via batch method
@@ -656,6 +694,19 @@ impl ResolvedValue {
     pub fn has_numeric_value(&self) -> bool {
         self.numeric_value.is_some()
     }
+
+    /// The value expressed in `system`, with its display string; the raw
+    /// value is kept as reported
+    pub fn in_units(mut self, system: UnitSystem) -> Self {
+        if let (Some(value), Some(unit)) = (self.numeric_value, self.unit.as_deref())
+            && let Some((converted, unit)) = units::convert(value, unit, system)
+        {
+            self.numeric_value = Some(converted);
+            self.formatted_value = units::format_value(converted, unit);
+            self.unit = Some(unit.to_string());
+        }
+        self
+    }
 }
 
 /// Helper functions (consolidation of current logic)
@@ -0,0 +1,692 @@
+//! Weather-triggered protective automations
+//!
+//! Two built-in rules protect the building when the weather turns:
+//!
+//! - **Wind**: awnings are retracted once the wind speed exceeds a
+//!   threshold (50 km/h by default)
+//! - **Rain**: skylights and roof windows are closed when rain is detected
+//!
+//! Awnings are Jalousie controls with the awning animation or an awning
+//! name, skylights are Window controls named after roof windows. Each
+//! device can be configured individually (threshold, command, disabled),
+//! and protection can be overridden for a while, e.g. when the awning is
+//! being cleaned. A rule fires once when its condition starts and re-arms
+//! once the condition has cleared.
+//!
+//! The rules are disabled unless `weather_protection.enabled` is set in the
+//! configuration or `LOXONE_WEATHER_PROTECTION=true`.
+
+use crate::client::{LoxoneClient, LoxoneStructure};
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Interval between weather checks
+pub const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Protective actions kept for reporting
+const MAX_EVENTS: usize = 100;
+
+/// Jalousie animation id of awnings in the structure file
+const AWNING_ANIMATION: u64 = 6;
+
+/// Name fragments identifying awnings
+const AWNING_NAME_PATTERNS: &[&str] = &["awning", "markise", "sonnensegel"];
+
+/// Name fragments identifying skylights and roof windows
+const SKYLIGHT_NAME_PATTERNS: &[&str] = &[
+    "skylight",
+    "roof window",
+    "dachfenster",
+    "lichtkuppel",
+    "velux",
+];
+
+/// Name fragments of wind speed sensors
+const WIND_NAME_PATTERNS: &[&str] = &["wind", "anemometer"];
+
+/// Name fragments of rain sensors
+const RAIN_NAME_PATTERNS: &[&str] = &["rain", "regen", "niederschlag"];
+
+/// Weather condition a rule reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtectionKind {
+    /// Retract when the wind exceeds the threshold
+    Wind,
+    /// Close when rain is detected
+    Rain,
+}
+
+impl ProtectionKind {
+    /// Command protecting a device of `control_type` against this condition
+    fn default_command(&self, control_type: &str) -> Option<&'static str> {
+        match (self, control_type) {
+            (Self::Wind, "Jalousie" | "CentralJalousie") => Some("FullUp"),
+            (Self::Rain, "Window" | "CentralWindow") => Some("fullClose"),
+            (Self::Rain, "Jalousie" | "CentralJalousie") => Some("FullUp"),
+            _ => None,
+        }
+    }
+}
+
+/// Per-device protection settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProtection {
+    /// Control UUID or name
+    pub device: String,
+    pub kind: ProtectionKind,
+    /// Protect the device (false excludes a discovered device)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Wind threshold in km/h, overriding the global one
+    #[serde(default)]
+    pub wind_threshold_kmh: Option<f64>,
+    /// Command to send instead of the default for the control type
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// Weather protection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeatherProtectionConfig {
+    #[serde(default = "enabled_from_env")]
+    pub enabled: bool,
+    /// Wind speed (km/h) above which awnings are retracted
+    #[serde(default = "default_wind_threshold")]
+    pub wind_threshold_kmh: f64,
+    /// Protect discovered awnings and skylights without configuration
+    #[serde(default = "default_true")]
+    pub discover_devices: bool,
+    /// Per-device settings
+    #[serde(default)]
+    pub devices: Vec<DeviceProtection>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_wind_threshold() -> f64 {
+    50.0
+}
+
+fn enabled_from_env() -> bool {
+    std::env::var("LOXONE_WEATHER_PROTECTION").is_ok_and(|v| v == "true" || v == "1")
+}
+
+impl Default for WeatherProtectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: enabled_from_env(),
+            wind_threshold_kmh: default_wind_threshold(),
+            discover_devices: true,
+            devices: Vec::new(),
+        }
+    }
+}
+
+/// A device protected by a rule
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProtectionRule {
+    pub uuid: String,
+    pub name: String,
+    pub kind: ProtectionKind,
+    pub command: String,
+    /// Wind threshold (km/h) for wind rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_threshold_kmh: Option<f64>,
+}
+
+impl ProtectionRule {
+    /// Whether the rule's condition holds for a reading
+    pub fn is_triggered(&self, reading: &WeatherReading) -> bool {
+        match self.kind {
+            ProtectionKind::Wind => reading
+                .wind_kmh
+                .zip(self.wind_threshold_kmh)
+                .is_some_and(|(wind, threshold)| wind > threshold),
+            ProtectionKind::Rain => reading.raining == Some(true),
+        }
+    }
+}
+
+/// Build the rules for a structure from the configuration
+pub fn build_rules(
+    config: &WeatherProtectionConfig,
+    structure: &LoxoneStructure,
+) -> Vec<ProtectionRule> {
+    let mut rules: Vec<ProtectionRule> = Vec::new();
+    let mut configured = HashSet::new();
+
+    for device in &config.devices {
+        let Some((uuid, control)) = find_control(structure, &device.device) else {
+            warn!("Weather protection device '{}' not found", device.device);
+            continue;
+        };
+        configured.insert((uuid.clone(), device.kind));
+        if !device.enabled {
+            continue;
+        }
+        let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let Some(command) = device.command.clone().or_else(|| {
+            device
+                .kind
+                .default_command(control_type)
+                .map(str::to_string)
+        }) else {
+            warn!(
+                "No protective command known for '{}' ({control_type}); configure one",
+                device.device
+            );
+            continue;
+        };
+        rules.push(ProtectionRule {
+            uuid: uuid.clone(),
+            name: control_name(control),
+            kind: device.kind,
+            command,
+            wind_threshold_kmh: (device.kind == ProtectionKind::Wind).then(|| {
+                device
+                    .wind_threshold_kmh
+                    .unwrap_or(config.wind_threshold_kmh)
+            }),
+        });
+    }
+
+    if config.discover_devices {
+        for (uuid, control) in &structure.controls {
+            let Some(kind) = discover_kind(control) else {
+                continue;
+            };
+            if configured.contains(&(uuid.clone(), kind)) {
+                continue;
+            }
+            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            if let Some(command) = kind.default_command(control_type) {
+                rules.push(ProtectionRule {
+                    uuid: uuid.clone(),
+                    name: control_name(control),
+                    kind,
+                    command: command.to_string(),
+                    wind_threshold_kmh: (kind == ProtectionKind::Wind)
+                        .then_some(config.wind_threshold_kmh),
+                });
+            }
+        }
+    }
+
+    rules.sort_by(|a, b| a.name.cmp(&b.name));
+    rules
+}
+
+/// Built-in rule a control falls under, if any
+fn discover_kind(control: &Value) -> Option<ProtectionKind> {
+    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let name = control_name(control).to_lowercase();
+    match control_type {
+        "Jalousie" => {
+            let animation = control
+                .get("details")
+                .and_then(|d| d.get("animation"))
+                .and_then(|v| v.as_u64());
+            (animation == Some(AWNING_ANIMATION)
+                || AWNING_NAME_PATTERNS.iter().any(|p| name.contains(p)))
+            .then_some(ProtectionKind::Wind)
+        }
+        "Window" => SKYLIGHT_NAME_PATTERNS
+            .iter()
+            .any(|p| name.contains(p))
+            .then_some(ProtectionKind::Rain),
+        _ => None,
+    }
+}
+
+fn find_control<'a>(
+    structure: &'a LoxoneStructure,
+    device: &str,
+) -> Option<(&'a String, &'a Value)> {
+    structure.controls.get_key_value(device).or_else(|| {
+        let lower = device.to_lowercase();
+        structure
+            .controls
+            .iter()
+            .find(|(_, control)| control_name(control).to_lowercase() == lower)
+    })
+}
+
+fn control_name(control: &Value) -> String {
+    control
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Current weather conditions
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WeatherReading {
+    pub wind_kmh: Option<f64>,
+    pub raining: Option<bool>,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// A weather sensor state and how to interpret it
+#[derive(Debug, Clone, PartialEq)]
+struct WeatherSource {
+    state: String,
+    kind: ProtectionKind,
+    /// Factor converting the sensor's unit to km/h
+    to_kmh: f64,
+}
+
+impl WeatherSource {
+    /// Wind and rain sensors among the analog and digital inputs
+    fn discover(structure: &LoxoneStructure) -> Vec<Self> {
+        let mut sources = Vec::new();
+        for control in structure.controls.values() {
+            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            if !matches!(control_type, "InfoOnlyAnalog" | "InfoOnlyDigital" | "Meter") {
+                continue;
+            }
+            let Some(state) = control
+                .get("states")
+                .and_then(|s| s.get("value").or_else(|| s.get("active")))
+                .and_then(|v| v.as_str())
+            else {
+                continue;
+            };
+            let name = control_name(control).to_lowercase();
+            let kind = if WIND_NAME_PATTERNS.iter().any(|p| name.contains(p)) {
+                ProtectionKind::Wind
+            } else if RAIN_NAME_PATTERNS.iter().any(|p| name.contains(p)) {
+                ProtectionKind::Rain
+            } else {
+                continue;
+            };
+            let format = control
+                .get("details")
+                .and_then(|d| d.get("format"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            sources.push(Self {
+                state: state.to_string(),
+                kind,
+                to_kmh: if format.contains("m/s") { 3.6 } else { 1.0 },
+            });
+        }
+        sources
+    }
+}
+
+/// Combine sensor values into a reading; any raining sensor counts
+fn reading_from(sources: &[WeatherSource], values: &HashMap<String, Value>) -> WeatherReading {
+    let mut reading = WeatherReading {
+        timestamp: Some(Utc::now()),
+        ..Default::default()
+    };
+    for source in sources {
+        let Some(value) = values.get(&source.state).and_then(value_as_f64) else {
+            continue;
+        };
+        match source.kind {
+            ProtectionKind::Wind => {
+                let kmh = value * source.to_kmh;
+                reading.wind_kmh = Some(reading.wind_kmh.map_or(kmh, |w| w.max(kmh)));
+            }
+            ProtectionKind::Rain => {
+                reading.raining = Some(reading.raining == Some(true) || value > 0.0);
+            }
+        }
+    }
+    reading
+}
+
+/// Numeric state values arrive as numbers or numeric strings
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+/// Read the wind and rain sensors of a structure
+pub async fn read_weather(
+    client: &dyn LoxoneClient,
+    structure: &LoxoneStructure,
+) -> Result<WeatherReading> {
+    let sources = WeatherSource::discover(structure);
+    let states: Vec<String> = sources.iter().map(|s| s.state.clone()).collect();
+    let values = if states.is_empty() {
+        HashMap::new()
+    } else {
+        client.get_state_values(&states).await?
+    };
+    Ok(reading_from(&sources, &values))
+}
+
+/// A protective command sent by a rule
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtectionEvent {
+    pub uuid: String,
+    pub name: String,
+    pub kind: ProtectionKind,
+    pub command: String,
+    pub wind_kmh: Option<f64>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct ProtectionState {
+    rules: Vec<ProtectionRule>,
+    reading: WeatherReading,
+    /// Rules whose condition is currently active and already handled
+    triggered: HashSet<(String, ProtectionKind)>,
+    /// Devices excluded from protection until the given time
+    overrides: HashMap<String, DateTime<Utc>>,
+    /// Since when the wind has stayed below the global threshold
+    calm_since: Option<DateTime<Utc>>,
+    events: VecDeque<ProtectionEvent>,
+}
+
+/// Runs the protective rules against the current weather
+pub struct WeatherProtection {
+    config: WeatherProtectionConfig,
+    state: RwLock<ProtectionState>,
+}
+
+impl Default for WeatherProtection {
+    fn default() -> Self {
+        Self::new(WeatherProtectionConfig::default())
+    }
+}
+
+impl WeatherProtection {
+    pub fn new(config: WeatherProtectionConfig) -> Self {
+        Self {
+            config,
+            state: RwLock::new(ProtectionState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &WeatherProtectionConfig {
+        &self.config
+    }
+
+    /// Rules that fire for a reading, skipping handled and overridden ones
+    ///
+    /// Rules whose condition has cleared are re-armed.
+    async fn due_rules(&self, reading: &WeatherReading, now: DateTime<Utc>) -> Vec<ProtectionRule> {
+        let mut state = self.state.write().await;
+        state.overrides.retain(|_, until| *until > now);
+        state.reading = reading.clone();
+        if let Some(wind) = reading.wind_kmh {
+            state.calm_since = if wind > self.config.wind_threshold_kmh {
+                None
+            } else {
+                state.calm_since.or(Some(now))
+            };
+        }
+
+        let mut due = Vec::new();
+        let rules = state.rules.clone();
+        for rule in rules {
+            let key = (rule.uuid.clone(), rule.kind);
+            if !rule.is_triggered(reading) {
+                state.triggered.remove(&key);
+                continue;
+            }
+            if state.overrides.contains_key(&rule.uuid) || !state.triggered.insert(key) {
+                continue;
+            }
+            due.push(rule);
+        }
+        due
+    }
+
+    /// Read the weather and run the rules once
+    pub async fn check(&self, client: &dyn LoxoneClient) -> Result<Vec<ProtectionEvent>> {
+        let structure = client.get_structure().await?;
+        let rules = build_rules(&self.config, &structure);
+        self.state.write().await.rules = rules;
+        let reading = read_weather(client, &structure).await?;
+
+        let mut events = Vec::new();
+        for rule in self.due_rules(&reading, Utc::now()).await {
+            let result = client.send_command(&rule.uuid, &rule.command).await;
+            match &result {
+                Ok(_) => info!(
+                    "Weather protection: sent {} to {} ({:?})",
+                    rule.command, rule.name, rule.kind
+                ),
+                Err(e) => warn!("Weather protection failed for {}: {e}", rule.name),
+            }
+            events.push(ProtectionEvent {
+                uuid: rule.uuid,
+                name: rule.name,
+                kind: rule.kind,
+                command: rule.command,
+                wind_kmh: reading.wind_kmh,
+                success: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+                timestamp: Utc::now(),
+            });
+        }
+
+        if !events.is_empty() {
+            let mut state = self.state.write().await;
+            state.events.extend(events.iter().cloned());
+            let excess = state.events.len().saturating_sub(MAX_EVENTS);
+            state.events.drain(..excess);
+        }
+        Ok(events)
+    }
+
+    /// Since when the wind has stayed below the global threshold, as seen
+    /// by the periodic checks; `None` while windy or not checked yet
+    pub async fn calm_since(&self) -> Option<DateTime<Utc>> {
+        self.state.read().await.calm_since
+    }
+
+    /// Exclude a device from protection until `until` (`None` clears it)
+    pub async fn set_override(&self, uuid: &str, until: Option<DateTime<Utc>>) {
+        let mut state = self.state.write().await;
+        match until {
+            Some(until) => {
+                state.overrides.insert(uuid.to_string(), until);
+            }
+            None => {
+                state.overrides.remove(uuid);
+                // Re-arm so an ongoing condition is handled on the next check
+                state.triggered.retain(|(device, _)| device != uuid);
+            }
+        }
+    }
+
+    /// Rules, last reading, overrides and recent actions as JSON
+    pub async fn status(&self) -> Value {
+        let state = self.state.read().await;
+        serde_json::json!({
+            "enabled": self.config.enabled,
+            "wind_threshold_kmh": self.config.wind_threshold_kmh,
+            "rules": state.rules,
+            "reading": state.reading,
+            "active": state
+                .triggered
+                .iter()
+                .map(|(uuid, kind)| serde_json::json!({"uuid": uuid, "kind": kind}))
+                .collect::<Vec<_>>(),
+            "overrides": state.overrides,
+            "recent_actions": state.events.iter().rev().collect::<Vec<_>>()
+        })
+    }
+
+    /// Check the weather every [`CHECK_INTERVAL_SECS`] if enabled
+    pub fn start(
+        self: &Arc<Self>,
+        client: Arc<dyn LoxoneClient>,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        if !self.config.enabled {
+            return None;
+        }
+        let protection = self.clone();
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = protection.check(client.as_ref()).await {
+                    warn!("Weather protection check failed: {e}");
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn structure() -> LoxoneStructure {
+        let mut structure = LoxoneStructure {
+            last_modified: String::new(),
+            controls: HashMap::new(),
+            rooms: HashMap::new(),
+            cats: HashMap::new(),
+            global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
+        };
+        for (uuid, control) in [
+            (
+                "awning",
+                json!({"name": "Terrace", "type": "Jalousie", "details": {"animation": 6}}),
+            ),
+            (
+                "blind",
+                json!({"name": "Living Room Blind", "type": "Jalousie", "details": {"animation": 0}}),
+            ),
+            (
+                "skylight",
+                json!({"name": "Dachfenster Bad", "type": "Window"}),
+            ),
+            (
+                "wind",
+                json!({"name": "Wind speed", "type": "InfoOnlyAnalog",
+                       "details": {"format": "%.1f m/s"}, "states": {"value": "wind-value"}}),
+            ),
+            (
+                "rain",
+                json!({"name": "Regensensor", "type": "InfoOnlyDigital", "states": {"active": "rain-active"}}),
+            ),
+        ] {
+            structure.controls.insert(uuid.to_string(), control);
+        }
+        structure
+    }
+
+    #[test]
+    fn test_rules_from_discovery_and_configuration() {
+        let structure = structure();
+        let mut config = WeatherProtectionConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let rules = build_rules(&config, &structure);
+        let kinds: Vec<_> = rules.iter().map(|r| (r.uuid.as_str(), r.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("skylight", ProtectionKind::Rain),
+                ("awning", ProtectionKind::Wind)
+            ]
+        );
+        assert_eq!(rules[1].command, "FullUp");
+        assert_eq!(rules[1].wind_threshold_kmh, Some(50.0));
+
+        config.devices = vec![
+            DeviceProtection {
+                device: "Terrace".to_string(),
+                kind: ProtectionKind::Wind,
+                enabled: true,
+                wind_threshold_kmh: Some(30.0),
+                command: None,
+            },
+            DeviceProtection {
+                device: "skylight".to_string(),
+                kind: ProtectionKind::Rain,
+                enabled: false,
+                wind_threshold_kmh: None,
+                command: None,
+            },
+        ];
+        let rules = build_rules(&config, &structure);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].wind_threshold_kmh, Some(30.0));
+
+        let sources = WeatherSource::discover(&structure);
+        let values = HashMap::from([
+            ("wind-value".to_string(), json!(10.0)),
+            ("rain-active".to_string(), json!(1)),
+        ]);
+        let reading = reading_from(&sources, &values);
+        assert_eq!(reading.wind_kmh, Some(36.0));
+        assert_eq!(reading.raining, Some(true));
+        assert!(rules[0].is_triggered(&reading));
+    }
+
+    #[tokio::test]
+    async fn test_rules_fire_once_and_respect_overrides() {
+        let config = WeatherProtectionConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let protection = WeatherProtection::new(config.clone());
+        protection.state.write().await.rules = build_rules(&config, &structure());
+        let now = Utc::now();
+        let storm = WeatherReading {
+            wind_kmh: Some(70.0),
+            raining: Some(false),
+            timestamp: Some(now),
+        };
+
+        let due = protection.due_rules(&storm, now).await;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].uuid, "awning");
+        // Still windy: already handled
+        assert!(protection.due_rules(&storm, now).await.is_empty());
+        assert_eq!(protection.calm_since().await, None);
+
+        // Calm re-arms the rule, but an override suppresses it
+        let calm = WeatherReading {
+            wind_kmh: Some(10.0),
+            ..storm.clone()
+        };
+        assert!(protection.due_rules(&calm, now).await.is_empty());
+        assert!(
+            protection
+                .due_rules(&calm, now + chrono::Duration::minutes(1))
+                .await
+                .is_empty()
+        );
+        assert_eq!(protection.calm_since().await, Some(now));
+        protection
+            .set_override("awning", Some(now + chrono::Duration::hours(1)))
+            .await;
+        assert!(protection.due_rules(&storm, now).await.is_empty());
+
+        // Expired override
+        let later = now + chrono::Duration::hours(2);
+        assert_eq!(protection.due_rules(&storm, later).await.len(), 1);
+    }
+}
@@ -0,0 +1,424 @@
+//! Versioned migrations of persisted data
+//!
+//! Files the server keeps between runs (API keys, lifetime metrics, feature
+//! flags, the command history) each get a format version, stored next to
+//! the file as `<file>.version`. On startup [`migrate`] upgrades every file
+//! that is behind, one registered step at a time:
+//!
+//! - the original file is copied to `<file>.v<from>.<timestamp>.bak` first;
+//! - all steps run in memory and the result replaces the file atomically, so
+//!   a failing step leaves the file untouched;
+//! - a file written by a newer release is refused instead of being read and
+//!   rewritten in the older format.
+//!
+//! Files without a version file predate versioning and count as version 0.
+//! For files that do not exist yet the version file is written right away,
+//! so the file the server creates later counts as current.
+//! `--migrate-dry-run` prints what would change without writing anything.
+
+use crate::error::{LoxoneError, Result};
+use chrono::Utc;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Rewrites the content of a file from one version to the next
+pub type MigrateFn = fn(&str) -> Result<String>;
+
+/// One upgrade step of a persisted file
+#[derive(Clone)]
+pub struct Migration {
+    /// Version the step upgrades from; it produces `from + 1`
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: MigrateFn,
+}
+
+/// A file the server persists, with its current format version
+#[derive(Clone)]
+pub struct PersistedStore {
+    pub name: &'static str,
+    pub path: PathBuf,
+    /// Version this release reads and writes
+    pub version: u32,
+    pub migrations: Vec<Migration>,
+}
+
+impl PersistedStore {
+    pub fn new(name: &'static str, path: impl Into<PathBuf>, version: u32) -> Self {
+        Self {
+            name,
+            path: path.into(),
+            version,
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Register the step upgrading from version `from`
+    pub fn migration(mut self, from: u32, description: &'static str, apply: MigrateFn) -> Self {
+        self.migrations.push(Migration {
+            from,
+            description,
+            apply,
+        });
+        self
+    }
+
+    /// Path of the file holding the format version
+    pub fn version_file(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".version");
+        self.path.with_file_name(name)
+    }
+
+    /// Format version of the file on disk; 0 if it predates versioning
+    pub fn stored_version(&self) -> Result<u32> {
+        let path = self.version_file();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => content.trim().parse().map_err(|_| {
+                LoxoneError::parsing_error(format!(
+                    "Invalid version file {}: '{}'",
+                    path.display(),
+                    content.trim()
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Steps from `from` to the current version, in order
+    fn steps(&self, from: u32) -> Result<Vec<&Migration>> {
+        (from..self.version)
+            .map(|version| {
+                self.migrations
+                    .iter()
+                    .find(|m| m.from == version)
+                    .ok_or_else(|| {
+                        LoxoneError::internal(format!(
+                            "No migration registered for {} v{version}",
+                            self.name
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    fn write_version(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(self.version_file(), format!("{}\n", self.version))?;
+        Ok(())
+    }
+}
+
+/// What happened, or would happen, to one store
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationOutcome {
+    /// No file yet; it is created in the current format
+    Missing,
+    UpToDate,
+    Migrated {
+        from: u32,
+        to: u32,
+        backup: PathBuf,
+    },
+    /// Dry run: the steps that would run
+    Pending {
+        from: u32,
+        to: u32,
+        steps: Vec<&'static str>,
+    },
+}
+
+/// Outcome of [`migrate`] for one store
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreReport {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub outcome: MigrationOutcome,
+}
+
+/// Outcome of [`migrate`] for all stores
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub stores: Vec<StoreReport>,
+}
+
+impl MigrationReport {
+    /// Whether any file was, or would be, changed
+    pub fn changed(&self) -> bool {
+        self.stores.iter().any(|store| {
+            matches!(
+                store.outcome,
+                MigrationOutcome::Migrated { .. } | MigrationOutcome::Pending { .. }
+            )
+        })
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = if self.dry_run { " (dry run)" } else { "" };
+        writeln!(f, "Data migrations{mode}:")?;
+        for store in &self.stores {
+            write!(f, "  {} ({}): ", store.name, store.path.display())?;
+            match &store.outcome {
+                MigrationOutcome::Missing => writeln!(f, "not present")?,
+                MigrationOutcome::UpToDate => writeln!(f, "up to date")?,
+                MigrationOutcome::Migrated { from, to, backup } => writeln!(
+                    f,
+                    "migrated v{from} -> v{to}, backup at {}",
+                    backup.display()
+                )?,
+                MigrationOutcome::Pending { from, to, steps } => {
+                    writeln!(f, "would migrate v{from} -> v{to}")?;
+                    for step in steps {
+                        writeln!(f, "    - {step}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bring every store up to its current version
+///
+/// With `dry_run` nothing is written. Fails on the first store that cannot
+/// be migrated; stores before it stay migrated.
+pub fn migrate(stores: &[PersistedStore], dry_run: bool) -> Result<MigrationReport> {
+    let mut reports = Vec::new();
+    for store in stores {
+        let outcome = migrate_store(store, dry_run)?;
+        if let MigrationOutcome::Migrated { from, to, backup } = &outcome {
+            info!(
+                "Migrated {} from v{from} to v{to} (backup: {})",
+                store.name,
+                backup.display()
+            );
+        }
+        reports.push(StoreReport {
+            name: store.name,
+            path: store.path.clone(),
+            outcome,
+        });
+    }
+    Ok(MigrationReport {
+        dry_run,
+        stores: reports,
+    })
+}
+
+fn migrate_store(store: &PersistedStore, dry_run: bool) -> Result<MigrationOutcome> {
+    if !store.path.exists() {
+        if !dry_run {
+            store.write_version()?;
+        }
+        return Ok(MigrationOutcome::Missing);
+    }
+
+    let from = store.stored_version()?;
+    if from > store.version {
+        return Err(LoxoneError::config(format!(
+            "{} was written by a newer release (format v{from}, this release reads v{}); \
+             refusing to start to avoid losing data",
+            store.path.display(),
+            store.version
+        )));
+    }
+    let steps = store.steps(from)?;
+    if steps.is_empty() {
+        return Ok(MigrationOutcome::UpToDate);
+    }
+    if dry_run {
+        return Ok(MigrationOutcome::Pending {
+            from,
+            to: store.version,
+            steps: steps.iter().map(|step| step.description).collect(),
+        });
+    }
+
+    let mut content = std::fs::read_to_string(&store.path)?;
+    for step in &steps {
+        content = (step.apply)(&content).map_err(|e| {
+            LoxoneError::config(format!(
+                "Migrating {} from v{} failed, {} left unchanged: {e}",
+                store.name,
+                step.from,
+                store.path.display()
+            ))
+        })?;
+    }
+
+    let backup = backup_path(&store.path, from);
+    std::fs::copy(&store.path, &backup)?;
+    let mut tmp = store.path.clone().into_os_string();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, &store.path)?;
+    store.write_version()?;
+    Ok(MigrationOutcome::Migrated {
+        from,
+        to: store.version,
+        backup,
+    })
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".v{version}.{}.bak",
+        Utc::now().format("%Y%m%d%H%M%S")
+    ));
+    path.with_file_name(name)
+}
+
+/// Stores of this release, at their configured locations
+pub fn default_stores() -> Vec<PersistedStore> {
+    use crate::monitoring::lifetime_metrics::{LifetimeMetricsConfig, LifetimeTotals};
+    use crate::security::key_store::{ApiKey, KeyStoreConfig, default_key_store_path};
+    use crate::server::feature_flags::FeatureFlagsConfig;
+    use crate::services::command_history::CommandHistoryConfig;
+    use crate::services::scene_snapshot::{self, SceneSnapshotConfig};
+    use std::collections::BTreeMap;
+
+    fn adopt_api_keys(content: &str) -> Result<String> {
+        // Files named .toml may hold JSON, see KeyStore::load_from_file
+        if toml::from_str::<Vec<ApiKey>>(content).is_err() {
+            serde_json::from_str::<Vec<ApiKey>>(content)?;
+        }
+        Ok(content.to_string())
+    }
+
+    fn adopt_lifetime_metrics(content: &str) -> Result<String> {
+        serde_json::from_str::<LifetimeTotals>(content)?;
+        Ok(content.to_string())
+    }
+
+    fn adopt_feature_flags(content: &str) -> Result<String> {
+        serde_json::from_str::<BTreeMap<String, bool>>(content)?;
+        Ok(content.to_string())
+    }
+
+    fn adopt_scene_snapshots(content: &str) -> Result<String> {
+        serde_json::from_str::<Vec<scene_snapshot::SceneSnapshot>>(content)?;
+        Ok(content.to_string())
+    }
+
+    fn adopt_unchanged(content: &str) -> Result<String> {
+        Ok(content.to_string())
+    }
+
+    let mut stores = vec![
+        PersistedStore::new(
+            "api-keys",
+            KeyStoreConfig::default()
+                .file_path
+                .unwrap_or_else(default_key_store_path),
+            1,
+        )
+        .migration(
+            0,
+            "Check the key file parses before versioning it",
+            adopt_api_keys,
+        ),
+    ];
+    let metrics = LifetimeMetricsConfig::default();
+    if metrics.persist {
+        stores.push(
+            PersistedStore::new("lifetime-metrics", metrics.file, 1).migration(
+                0,
+                "Check the totals parse before versioning them",
+                adopt_lifetime_metrics,
+            ),
+        );
+    }
+    if let Some(flags_file) = FeatureFlagsConfig::default().file {
+        stores.push(
+            PersistedStore::new("feature-flags", flags_file, 1).migration(
+                0,
+                "Check the flags parse before versioning them",
+                adopt_feature_flags,
+            ),
+        );
+    }
+    if let Some(log_file) = CommandHistoryConfig::default().log_file {
+        stores.push(
+            PersistedStore::new("command-history", log_file, 1).migration(
+                0,
+                "Version the command log (malformed lines are skipped on load)",
+                adopt_unchanged,
+            ),
+        );
+    }
+    if let Some(snapshots_file) = SceneSnapshotConfig::default().file {
+        stores.push(
+            PersistedStore::new("scene-snapshots", snapshots_file, 1).migration(
+                0,
+                "Check the snapshots parse before versioning them",
+                adopt_scene_snapshots,
+            ),
+        );
+    }
+    stores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_field(content: &str) -> Result<String> {
+        Ok(content.replace("\"count\"", "\"total\""))
+    }
+
+    fn reject(_: &str) -> Result<String> {
+        Err(LoxoneError::parsing_error("unexpected format"))
+    }
+
+    #[test]
+    fn test_migrates_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, r#"{"count": 3}"#).unwrap();
+        let store = PersistedStore::new("data", &path, 2)
+            .migration(0, "Adopt", |c| Ok(c.to_string()))
+            .migration(1, "Rename count to total", rename_field);
+
+        let dry = migrate(std::slice::from_ref(&store), true).unwrap();
+        assert!(dry.changed());
+        assert_eq!(
+            dry.stores[0].outcome,
+            MigrationOutcome::Pending {
+                from: 0,
+                to: 2,
+                steps: vec!["Adopt", "Rename count to total"],
+            }
+        );
+        assert!(!store.version_file().exists());
+
+        let report = migrate(std::slice::from_ref(&store), false).unwrap();
+        let MigrationOutcome::Migrated { backup, .. } = &report.stores[0].outcome else {
+            panic!("not migrated: {report}");
+        };
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), r#"{"count": 3}"#);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{"total": 3}"#);
+        assert_eq!(store.stored_version().unwrap(), 2);
+        assert!(
+            !migrate(std::slice::from_ref(&store), false)
+                .unwrap()
+                .changed()
+        );
+
+        // A failing step leaves the file alone, a newer file is refused
+        let failing = PersistedStore::new("data", &path, 3).migration(2, "Reject", reject);
+        assert!(migrate(&[failing], false).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{"total": 3}"#);
+        let older = PersistedStore::new("data", &path, 1);
+        assert!(migrate(&[older], false).is_err());
+    }
+}
@@ -4,11 +4,15 @@
 //! - Weather data from WebSocket streams
 //! - Device state history
 //! - System metrics and analytics
+//! - Versioned migrations of persisted files
+//! - Snapshots of persisted files for a warm standby
 //!
 //! Available implementations:
 //! - Simple in-memory storage (default)
 //! - Turso database storage (with "turso" feature)
 
+pub mod migrations;
+pub mod replication;
 pub mod simple_storage;
 
 #[cfg(feature = "turso")]
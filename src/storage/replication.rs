@@ -0,0 +1,177 @@
+//! Snapshots of persisted state for a warm standby
+//!
+//! A primary server publishes the files it persists (see
+//! [`default_stores`](super::migrations::default_stores): API keys, feature
+//! flags, lifetime metrics, the command history) into a replica directory,
+//! typically a shared volume. A standby applies the latest snapshot to its
+//! own store locations, so it serves the same keys and flags when it takes
+//! over.
+//!
+//! Each published file is written to a temporary name and renamed, and the
+//! manifest is written last, so a standby never applies a half-written
+//! snapshot. Snapshots from a newer release are refused like newer files in
+//! [`migrate`](super::migrations::migrate); older ones are migrated after
+//! they are applied.
+
+use crate::error::{LoxoneError, Result};
+use crate::storage::migrations::{self, PersistedStore};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Manifest listing the files of the latest snapshot
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// One persisted file in a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// Store name, also the file name in the replica directory
+    pub name: String,
+    /// Format version of the file
+    pub version: u32,
+}
+
+/// Contents of [`MANIFEST_FILE`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub published_at: DateTime<Utc>,
+    pub stores: Vec<SnapshotEntry>,
+}
+
+/// Copy `from` to `to` through a temporary file, so readers see either file
+fn copy_atomically(from: &Path, to: &Path) -> Result<()> {
+    if let Some(dir) = to.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tmp = to.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::copy(from, &tmp)?;
+    std::fs::rename(&tmp, to)?;
+    Ok(())
+}
+
+/// Publish the existing store files into `replica_dir`
+pub fn publish_snapshot(stores: &[PersistedStore], replica_dir: &Path) -> Result<SnapshotManifest> {
+    std::fs::create_dir_all(replica_dir)?;
+    let mut entries = Vec::new();
+    for store in stores.iter().filter(|store| store.path.exists()) {
+        copy_atomically(&store.path, &replica_dir.join(store.name))?;
+        entries.push(SnapshotEntry {
+            name: store.name.to_string(),
+            version: store.stored_version()?,
+        });
+    }
+
+    let manifest = SnapshotManifest {
+        published_at: Utc::now(),
+        stores: entries,
+    };
+    let manifest_path = replica_dir.join(MANIFEST_FILE);
+    let tmp = replica_dir.join(format!("{MANIFEST_FILE}.tmp"));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&manifest)?)?;
+    std::fs::rename(&tmp, &manifest_path)?;
+    debug!(
+        "Published snapshot of {} stores to {}",
+        manifest.stores.len(),
+        replica_dir.display()
+    );
+    Ok(manifest)
+}
+
+/// Latest snapshot in `replica_dir`, if one was published
+pub fn read_manifest(replica_dir: &Path) -> Result<Option<SnapshotManifest>> {
+    match std::fs::read(replica_dir.join(MANIFEST_FILE)) {
+        Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Apply the latest snapshot in `replica_dir` to the local store files
+///
+/// Stores missing from the snapshot and snapshot files this release does not
+/// know are left alone. Returns `None` if nothing was published yet.
+pub fn apply_snapshot(
+    stores: &[PersistedStore],
+    replica_dir: &Path,
+) -> Result<Option<SnapshotManifest>> {
+    let Some(manifest) = read_manifest(replica_dir)? else {
+        return Ok(None);
+    };
+
+    let mut applied = Vec::new();
+    for entry in &manifest.stores {
+        let Some(store) = stores.iter().find(|store| store.name == entry.name) else {
+            debug!("Skipping unknown store '{}' in snapshot", entry.name);
+            continue;
+        };
+        if entry.version > store.version {
+            return Err(LoxoneError::config(format!(
+                "Snapshot of {} has format v{}, this release reads up to v{}; update the standby",
+                store.name, entry.version, store.version
+            )));
+        }
+        copy_atomically(&replica_dir.join(&entry.name), &store.path)?;
+        std::fs::write(store.version_file(), format!("{}\n", entry.version))?;
+        applied.push(store.clone());
+    }
+
+    let report = migrations::migrate(&applied, false)?;
+    if report.changed() {
+        info!("{}", report.to_string().trim_end());
+    }
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let primary = tempfile::tempdir().unwrap();
+        let standby = tempfile::tempdir().unwrap();
+        let replica = tempfile::tempdir().unwrap();
+        let stores = |dir: &Path| {
+            vec![
+                PersistedStore::new("feature-flags", dir.join("flags.json"), 1),
+                PersistedStore::new("api-keys", dir.join("keys.json"), 1),
+            ]
+        };
+
+        assert_eq!(
+            apply_snapshot(&stores(standby.path()), replica.path()).unwrap(),
+            None
+        );
+
+        std::fs::write(primary.path().join("flags.json"), r#"{"sampling": false}"#).unwrap();
+        std::fs::write(primary.path().join("flags.json.version"), "1\n").unwrap();
+        let published = publish_snapshot(&stores(primary.path()), replica.path()).unwrap();
+        assert_eq!(
+            published.stores,
+            vec![SnapshotEntry {
+                name: "feature-flags".to_string(),
+                version: 1
+            }]
+        );
+
+        let applied = apply_snapshot(&stores(standby.path()), replica.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(applied, published);
+        assert_eq!(
+            std::fs::read_to_string(standby.path().join("flags.json")).unwrap(),
+            r#"{"sampling": false}"#
+        );
+        assert!(!standby.path().join("keys.json").exists());
+
+        // A snapshot from a newer release is refused
+        std::fs::write(primary.path().join("flags.json.version"), "2\n").unwrap();
+        publish_snapshot(&stores(primary.path()), replica.path()).unwrap();
+        assert!(apply_snapshot(&stores(standby.path()), replica.path()).is_err());
+    }
+}
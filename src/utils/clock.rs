@@ -0,0 +1,198 @@
+//! Clock abstraction for time-dependent behavior
+//!
+//! Retry policies, circuit breakers, rate limiters, the Miniserver request
+//! budget, token expiry and the energy scheduler read time and sleep through
+//! a [`Clock`] instead of calling `Instant::now`, `Utc::now` or
+//! `tokio::time::sleep` directly. Production code uses [`SystemClock`];
+//! tests pass a [`ManualClock`] and move time forward explicitly, so backoff
+//! delays, open-circuit timeouts and scheduler intervals elapse instantly and
+//! deterministically.
+//!
+//! ```
+//! use loxone_mcp_rust::utils::clock::{Clock, ManualClock};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! # tokio_test::block_on(async {
+//! let clock = Arc::new(ManualClock::new());
+//! let sleeper = {
+//!     let clock = clock.clone();
+//!     tokio::spawn(async move { clock.sleep(Duration::from_secs(3600)).await })
+//! };
+//! clock.wait_for_sleeps(1).await;
+//! clock.advance(Duration::from_secs(3600));
+//! sleeper.await.unwrap();
+//! # });
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Source of the current time and of delays
+#[async_trait]
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Monotonic time, for measuring intervals
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, for timestamps and deadlines
+    fn utc_now(&self) -> DateTime<Utc>;
+
+    /// Wait until `duration` has passed on this clock
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Clock shared between components
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Shared [`SystemClock`], the default of every component taking a clock
+pub fn system_clock() -> SharedClock {
+    static SYSTEM: OnceLock<SharedClock> = OnceLock::new();
+    SYSTEM.get_or_init(|| Arc::new(SystemClock)).clone()
+}
+
+/// Clock that only moves when told to
+///
+/// Sleeps complete once [`advance`](Self::advance) has moved the clock past
+/// their deadline. A background task that sleeps between steps (a retry loop,
+/// a scheduler) has finished its `n`-th step once it starts its `n`-th sleep,
+/// so [`wait_for_sleeps`](Self::wait_for_sleeps) lets a test wait for that
+/// point before checking results or advancing further.
+pub struct ManualClock {
+    base: Instant,
+    base_utc: DateTime<Utc>,
+    elapsed: watch::Sender<Duration>,
+    sleeps: AtomicUsize,
+}
+
+impl ManualClock {
+    /// Clock starting at the current wall-clock time
+    pub fn new() -> Self {
+        Self::starting_at(Utc::now())
+    }
+
+    /// Clock starting at `start`
+    pub fn starting_at(start: DateTime<Utc>) -> Self {
+        Self {
+            base: Instant::now(),
+            base_utc: start,
+            elapsed: watch::Sender::new(Duration::ZERO),
+            sleeps: AtomicUsize::new(0),
+        }
+    }
+
+    /// Time advanced since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.borrow()
+    }
+
+    /// Move the clock forward, waking sleeps whose deadline has passed
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.send_modify(|elapsed| *elapsed += duration);
+    }
+
+    /// Sleeps started on this clock so far
+    pub fn sleeps(&self) -> usize {
+        self.sleeps.load(Ordering::SeqCst)
+    }
+
+    /// Yield until at least `count` sleeps have started on this clock
+    pub async fn wait_for_sleeps(&self, count: usize) {
+        while self.sleeps() < count {
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManualClock")
+            .field("now", &self.utc_now())
+            .field("sleeps", &self.sleeps())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.base + self.elapsed()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        self.base_utc + self.elapsed()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let mut elapsed = self.elapsed.subscribe();
+        let deadline = *elapsed.borrow_and_update() + duration;
+        self.sleeps.fetch_add(1, Ordering::SeqCst);
+        while *elapsed.borrow_and_update() < deadline {
+            // The sender lives as long as the clock
+            if elapsed.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manual_clock_wakes_sleepers_in_deadline_order() {
+        let clock = Arc::new(ManualClock::starting_at(
+            "2025-01-01T00:00:00Z".parse().unwrap(),
+        ));
+        let start = clock.now();
+        let short = tokio::spawn({
+            let clock = clock.clone();
+            async move { clock.sleep(Duration::from_secs(10)).await }
+        });
+        let long = tokio::spawn({
+            let clock = clock.clone();
+            async move { clock.sleep(Duration::from_secs(60)).await }
+        });
+        clock.wait_for_sleeps(2).await;
+
+        clock.advance(Duration::from_secs(30));
+        short.await.unwrap();
+        tokio::task::yield_now().await;
+        assert!(!long.is_finished());
+
+        clock.advance(Duration::from_secs(30));
+        long.await.unwrap();
+        assert_eq!(clock.now() - start, Duration::from_secs(60));
+        assert_eq!(clock.utc_now().to_rfc3339(), "2025-01-01T00:01:00+00:00");
+    }
+}
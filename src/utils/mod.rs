@@ -1,5 +1,6 @@
 //! Utility modules for common functionality
 
+pub mod clock;
 pub mod error_helpers;
 
 // Re-export commonly used helpers
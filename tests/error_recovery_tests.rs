@@ -0,0 +1,219 @@
+//! Error recovery behavior over time, driven by a manual clock
+//!
+//! Backoff delays, open-circuit timeouts, fallback cache expiry and rate
+//! limit windows elapse by advancing a `ManualClock`, so these tests neither
+//! sleep nor depend on scheduling jitter.
+
+use loxone_mcp_rust::error::{LoxoneError, Result};
+use loxone_mcp_rust::error_recovery::resilience_manager::FallbackConfig;
+use loxone_mcp_rust::error_recovery::retry_policy::JitterConfig;
+use loxone_mcp_rust::error_recovery::{
+    BackoffStrategy, CircuitBreaker, CircuitBreakerConfig, CircuitState, FallbackStrategy,
+    JitterType, ResilienceConfig, ResilienceManager, RetryExecutor, RetryPolicy,
+};
+use loxone_mcp_rust::server::rate_limiter::{RateLimitConfig, RateLimitResult, RateLimiter};
+use loxone_mcp_rust::utils::clock::{Clock, ManualClock, SharedClock};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+fn manual_clock() -> (Arc<ManualClock>, SharedClock) {
+    let clock = Arc::new(ManualClock::new());
+    (clock.clone(), clock)
+}
+
+fn exponential_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_delay: chrono::Duration::seconds(1),
+        backoff_strategy: BackoffStrategy::Exponential { multiplier: 2.0 },
+        jitter: JitterConfig {
+            enabled: false,
+            jitter_type: JitterType::Full,
+            jitter_factor: 0.0,
+        },
+        ..RetryPolicy::default()
+    }
+}
+
+#[tokio::test]
+async fn test_retry_backoff_waits_on_clock() {
+    let (clock, shared) = manual_clock();
+    let executor = Arc::new(RetryExecutor::new(exponential_policy(3)).with_clock(shared));
+    let attempts = Arc::new(AtomicU32::new(0));
+
+    let task = tokio::spawn({
+        let executor = executor.clone();
+        let attempts = attempts.clone();
+        async move {
+            executor
+                .execute(|| {
+                    let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                    async move {
+                        if attempt < 3 {
+                            Err(LoxoneError::connection("Miniserver unreachable"))
+                        } else {
+                            Ok(attempt)
+                        }
+                    }
+                })
+                .await
+        }
+    });
+
+    // First retry after 1s, second after 2s
+    clock.wait_for_sleeps(1).await;
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    clock.advance(Duration::from_millis(999));
+    tokio::task::yield_now().await;
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    clock.advance(Duration::from_millis(1));
+    clock.wait_for_sleeps(2).await;
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    clock.advance(Duration::from_secs(2));
+
+    assert_eq!(task.await.unwrap().unwrap(), 3);
+    assert_eq!(clock.elapsed(), Duration::from_secs(3));
+    let stats = executor.get_stats().await;
+    assert_eq!(stats.total_retry_attempts, 2);
+    assert_eq!(stats.successful_after_retry, 1);
+    assert_eq!(
+        stats.last_retry,
+        Some(clock.utc_now() - chrono::Duration::seconds(2))
+    );
+}
+
+#[tokio::test]
+async fn test_retry_gives_up_without_waiting_on_permanent_errors() {
+    let (clock, shared) = manual_clock();
+    let executor = RetryExecutor::new(exponential_policy(5)).with_clock(shared);
+
+    let result: Result<()> = executor
+        .execute(|| async { Err(LoxoneError::authentication("Invalid credentials")) })
+        .await;
+    assert!(result.is_err());
+    assert_eq!(clock.sleeps(), 0);
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_opens_and_half_opens_on_clock() {
+    let (clock, shared) = manual_clock();
+    let breaker = CircuitBreaker::with_clock(
+        CircuitBreakerConfig {
+            failure_threshold: 2,
+            failure_window: chrono::Duration::minutes(1),
+            timeout_duration: chrono::Duration::seconds(30),
+            exponential_backoff: false,
+            ..CircuitBreakerConfig::default()
+        },
+        shared,
+    );
+    let failure = LoxoneError::connection("Miniserver unreachable");
+
+    // Failures further apart than the window do not add up
+    breaker.record_failure(&failure).await;
+    clock.advance(Duration::from_secs(61));
+    breaker.record_failure(&failure).await;
+    assert_eq!(breaker.get_stats().await.state, CircuitState::Closed);
+
+    breaker.record_failure(&failure).await;
+    assert_eq!(breaker.get_stats().await.state, CircuitState::Open);
+    assert!(!breaker.should_allow_request().await);
+
+    clock.advance(Duration::from_secs(20));
+    assert_eq!(
+        breaker.get_stats().await.time_until_transition,
+        Some(chrono::Duration::seconds(10))
+    );
+    assert!(!breaker.should_allow_request().await);
+
+    clock.advance(Duration::from_secs(10));
+    assert!(breaker.should_allow_request().await);
+    assert_eq!(breaker.get_stats().await.state, CircuitState::HalfOpen);
+}
+
+#[tokio::test]
+async fn test_resilience_timeout_and_cached_fallback_expiry() {
+    let (clock, shared) = manual_clock();
+    let manager = Arc::new(ResilienceManager::with_clock(shared));
+    manager
+        .register_service(
+            "weather",
+            ResilienceConfig {
+                retry_policy: exponential_policy(1),
+                fallback: FallbackConfig {
+                    enabled: true,
+                    strategy: FallbackStrategy::Cached,
+                    cache_duration: chrono::Duration::minutes(5),
+                },
+                timeout_duration: chrono::Duration::seconds(10),
+                ..ResilienceConfig::default()
+            },
+        )
+        .await;
+
+    let fresh = manager
+        .execute_with_resilience("weather", || async { Ok(21u32) }, Some(0))
+        .await
+        .unwrap();
+    assert_eq!(fresh, 21);
+
+    // A hanging request times out after 10s and falls back to the cached value
+    let hanging = |manager: Arc<ResilienceManager>| {
+        tokio::spawn(async move {
+            manager
+                .execute_with_resilience(
+                    "weather",
+                    || std::future::pending::<Result<u32>>(),
+                    Some(0),
+                )
+                .await
+        })
+    };
+    let task = hanging(manager.clone());
+    clock.wait_for_sleeps(1).await;
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(task.await.unwrap().unwrap(), 21);
+
+    // Once the cache has expired the default fallback is used
+    clock.advance(Duration::from_secs(5 * 60));
+    let task = hanging(manager.clone());
+    clock.wait_for_sleeps(2).await;
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(task.await.unwrap().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_rate_limit_window_resets_on_clock() {
+    let (clock, shared) = manual_clock();
+    let limiter = RateLimiter::with_config(RateLimitConfig {
+        max_requests: 2,
+        window_duration: Duration::from_secs(60),
+        burst_size: 0,
+        cleanup_interval: Duration::from_secs(300),
+    })
+    .with_clock(shared);
+    let window_start = clock.now();
+
+    assert_eq!(
+        limiter.check_request("kiosk").await,
+        RateLimitResult::Allowed
+    );
+    assert_eq!(
+        limiter.check_request("kiosk").await,
+        RateLimitResult::Allowed
+    );
+    clock.advance(Duration::from_secs(59));
+    assert_eq!(
+        limiter.check_request("kiosk").await,
+        RateLimitResult::Limited {
+            reset_at: window_start + Duration::from_secs(60)
+        }
+    );
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(
+        limiter.check_request("kiosk").await,
+        RateLimitResult::Allowed
+    );
+}
@@ -74,6 +74,7 @@ async fn test_device_categorization() {
         room: Some("Living Room".to_string()),
         states: HashMap::new(),
         category: "lighting".to_string(),
+        loxone_category: None,
         sub_controls: HashMap::new(),
     };
 
@@ -88,6 +89,7 @@ async fn test_device_categorization() {
         room: Some("Living Room".to_string()),
         states: HashMap::new(),
         category: "sensors".to_string(),
+        loxone_category: None,
         sub_controls: HashMap::new(),
     };
 
@@ -117,6 +119,7 @@ async fn test_temperature_extraction() {
         room: Some("Living Room".to_string()),
         states: HashMap::new(),
         category: "sensors".to_string(),
+        loxone_category: None,
         sub_controls: HashMap::new(),
     };
 
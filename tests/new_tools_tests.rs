@@ -25,6 +25,7 @@ mod tests {
                     ("state".to_string(), json!(true)),
                     ("brightness".to_string(), json!(75.0)),
                 ]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             LoxoneDevice {
@@ -37,6 +38,7 @@ mod tests {
                     ("state".to_string(), json!(false)),
                     ("brightness".to_string(), json!(0.0)),
                 ]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             LoxoneDevice {
@@ -46,6 +48,7 @@ mod tests {
                 room: Some("Bedroom".to_string()),
                 category: "lighting".to_string(),
                 states: HashMap::from([("state".to_string(), json!(true))]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             // Blind devices
@@ -59,6 +62,7 @@ mod tests {
                     ("position".to_string(), json!(25.0)),
                     ("moving".to_string(), json!(false)),
                 ]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             LoxoneDevice {
@@ -71,6 +75,7 @@ mod tests {
                     ("position".to_string(), json!(100.0)),
                     ("moving".to_string(), json!(false)),
                 ]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             // Climate devices
@@ -85,6 +90,7 @@ mod tests {
                     ("target_temperature".to_string(), json!(22.0)),
                     ("mode".to_string(), json!("heating")),
                 ]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             // Sensor devices
@@ -95,6 +101,7 @@ mod tests {
                 room: Some("Entrance".to_string()),
                 category: "sensors".to_string(),
                 states: HashMap::from([("value".to_string(), json!(0))]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             LoxoneDevice {
@@ -104,6 +111,7 @@ mod tests {
                 room: Some("Living Room".to_string()),
                 category: "sensors".to_string(),
                 states: HashMap::from([("value".to_string(), json!(1))]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
             // Audio device
@@ -117,6 +125,7 @@ mod tests {
                     ("volume".to_string(), json!(50.0)),
                     ("playing".to_string(), json!(false)),
                 ]),
+                loxone_category: None,
                 sub_controls: HashMap::new(),
             },
         ]
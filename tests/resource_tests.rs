@@ -199,7 +199,7 @@ mod tests {
         assert_eq!(device_resources.len(), 1); // only all
 
         let system_resources = manager.list_resources_by_category(ResourceCategory::System);
-        assert_eq!(system_resources.len(), 3); // status, capabilities, categories
+        assert_eq!(system_resources.len(), 7); // status, capabilities, categories, device-index, clients, sampling-usage, topology
 
         let audio_resources = manager.list_resources_by_category(ResourceCategory::Audio);
         assert_eq!(audio_resources.len(), 2); // zones, sources
@@ -208,7 +208,7 @@ mod tests {
         assert_eq!(sensor_resources.len(), 7); // door-window, temperature, discovered, motion, air-quality, presence, weather-station
 
         let weather_resources = manager.list_resources_by_category(ResourceCategory::Weather);
-        assert_eq!(weather_resources.len(), 4); // current, outdoor-conditions, forecast-daily, forecast-hourly
+        assert_eq!(weather_resources.len(), 5); // current, outdoor-conditions, forecast-daily, forecast-hourly, forecast
     }
 
     /// Comprehensive Resource Access and Validation Tests
@@ -0,0 +1,127 @@
+//! Structure file fixtures from firmware 9 to 15
+//!
+//! Each fixture reproduces the layout quirks of its firmware generation:
+//! - 9: capitalized keys, numeric `LastModified`, no `cats`/`globalStates`
+//! - 10: `cats` as an empty array, no `globalStates`
+//! - 11: `globalStates: null`
+//! - 12: extra top-level sections (`operatingModes`, `times`)
+//! - 13: `rooms`/`cats` as arrays of objects
+//! - 14/15: current layout with `messageCenter`, `autopilot`, `weatherServer`
+
+use loxone_mcp_rust::client::LoxoneStructure;
+
+struct Fixture {
+    firmware: u32,
+    json: &'static str,
+    control: &'static str,
+    room_name: &'static str,
+    cats: usize,
+    global_states: usize,
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        firmware: 9,
+        json: include_str!("fixtures/structures/firmware_9.json"),
+        control: "0f1a2b3c-0000-0001-ffff403fb0c34b9e",
+        room_name: "Living Room",
+        cats: 0,
+        global_states: 0,
+    },
+    Fixture {
+        firmware: 10,
+        json: include_str!("fixtures/structures/firmware_10.json"),
+        control: "10a1b2c3-0000-0001-ffff403fb0c34b9e",
+        room_name: "Kitchen",
+        cats: 0,
+        global_states: 0,
+    },
+    Fixture {
+        firmware: 11,
+        json: include_str!("fixtures/structures/firmware_11.json"),
+        control: "11a1b2c3-0000-0001-ffff403fb0c34b9e",
+        room_name: "Office",
+        cats: 1,
+        global_states: 0,
+    },
+    Fixture {
+        firmware: 12,
+        json: include_str!("fixtures/structures/firmware_12.json"),
+        control: "12a1b2c3-0000-0001-ffff403fb0c34b9e",
+        room_name: "Bad",
+        cats: 0,
+        global_states: 2,
+    },
+    Fixture {
+        firmware: 13,
+        json: include_str!("fixtures/structures/firmware_13.json"),
+        control: "13a1b2c3-0000-0001-ffff403fb0c34b9e",
+        room_name: "Entrance",
+        cats: 1,
+        global_states: 1,
+    },
+    Fixture {
+        firmware: 14,
+        json: include_str!("fixtures/structures/firmware_14.json"),
+        control: "14a1b2c3-0000-0001-ffff403fb0c34b9e",
+        room_name: "Garage",
+        cats: 1,
+        global_states: 1,
+    },
+    Fixture {
+        firmware: 15,
+        json: include_str!("fixtures/structures/firmware_15.json"),
+        control: "15a1b2c3-0000-0001-ffff403fb0c34b9e",
+        room_name: "Living Room",
+        cats: 1,
+        global_states: 2,
+    },
+];
+
+#[test]
+fn test_structures_parse_across_firmware_versions() {
+    for fixture in FIXTURES {
+        let structure: LoxoneStructure = serde_json::from_str(fixture.json)
+            .unwrap_or_else(|e| panic!("firmware {} failed to parse: {e}", fixture.firmware));
+
+        let control = structure
+            .controls
+            .get(fixture.control)
+            .unwrap_or_else(|| panic!("firmware {}: control missing", fixture.firmware));
+        let room_uuid = control["room"].as_str().unwrap();
+        assert_eq!(
+            structure.rooms[room_uuid]["name"], fixture.room_name,
+            "firmware {}",
+            fixture.firmware
+        );
+        assert_eq!(
+            structure.cats.len(),
+            fixture.cats,
+            "firmware {}",
+            fixture.firmware
+        );
+        assert_eq!(
+            structure.global_states.len(),
+            fixture.global_states,
+            "firmware {}",
+            fixture.firmware
+        );
+        assert!(
+            !structure.last_modified.is_empty(),
+            "firmware {}",
+            fixture.firmware
+        );
+    }
+}
+
+#[test]
+fn test_normalized_structures_round_trip() {
+    for fixture in FIXTURES {
+        let structure: LoxoneStructure = serde_json::from_str(fixture.json).unwrap();
+        let serialized = serde_json::to_string(&structure).unwrap();
+        let reparsed: LoxoneStructure = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(reparsed.controls, structure.controls);
+        assert_eq!(reparsed.rooms, structure.rooms);
+        assert_eq!(reparsed.global_states, structure.global_states);
+    }
+}
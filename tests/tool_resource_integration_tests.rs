@@ -94,8 +94,8 @@ mod tests {
         let resource_manager = ResourceManager::new();
         let resources = resource_manager.list_resources();
 
-        // Verify we have the expected number of resources (26 total)
-        assert_eq!(resources.len(), 26);
+        // Verify we have the expected number of resources (44 total)
+        assert_eq!(resources.len(), 44);
 
         // Verify some key resources are present
         let resource_uris: Vec<&str> = resources.iter().map(|r| r.uri.as_str()).collect();